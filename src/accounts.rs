@@ -0,0 +1,671 @@
+use eframe::egui;
+use nostr::nips::nip19::ToBech32;
+use nostr::{Filter, Keys, Kind, PublicKey, Timestamp};
+use nostr_sdk::{Client, RelayPoolNotification};
+use serde::Deserialize;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{error, info};
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::config_file_path;
+use crate::desktop_notify::{self, NotificationCategory};
+use crate::drafts;
+use crate::gift_wrap;
+use crate::i18n::tr;
+use crate::key_store;
+use crate::nip49;
+use crate::outbox;
+use crate::pow;
+use crate::relay_debug;
+use crate::sign_gate;
+use crate::signer::AppSigner;
+use crate::types::*;
+use crate::ui::login_view;
+
+// このアプリに保存した複数のアカウントと、その切り替え
+// 切り替える前のアカウントは接続したまま残し、DM とメンションが届いたら通知する
+// キャッシュやリレーの一覧は公開鍵ごとに保存しているので、切り替えても混ざらない
+
+// 以前の、アカウントを1つだけ保存していた設定ファイル
+#[derive(Deserialize)]
+struct LegacyConfig {
+    encrypted_secret_key: String,
+    salt: String,
+    #[serde(default)]
+    encrypted_nwc_uri: Option<String>,
+    #[serde(default)]
+    key_storage: KeyStorage,
+}
+
+// 設定ファイルを読み込む。以前の形式なら、それを1つ目のアカウントとして扱う
+pub fn read_config() -> Result<Config, Box<dyn std::error::Error + Send + Sync>> {
+    let config_str = fs::read_to_string(config_file_path())?;
+    let config: Config = serde_json::from_str(&config_str)?;
+    if !config.accounts.is_empty() {
+        return Ok(config);
+    }
+    let legacy: LegacyConfig = serde_json::from_str(&config_str)?;
+    Ok(Config {
+        accounts: vec![StoredAccount {
+            label: String::new(),
+            pubkey: String::new(),
+            encrypted_secret_key: legacy.encrypted_secret_key,
+            salt: legacy.salt,
+            encrypted_nwc_uri: legacy.encrypted_nwc_uri,
+            key_storage: legacy.key_storage,
+            kdf: Kdf::legacy(),
+        }],
+        active_account: 0,
+        kdf: Kdf::default(),
+    })
+}
+
+// ファイルを書き換える。途中で落ちても壊れたファイルが残らないよう、
+// 同じディレクトリの一時ファイルに書いてディスクに書き出してから置き換える
+pub fn write_file_atomically(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+    let result = (|| -> std::io::Result<()> {
+        let mut file = fs::File::create(&temp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        fs::rename(&temp_path, path)?;
+        // 置き換えたこと自体もディスクに残す
+        #[cfg(unix)]
+        if let Some(dir) = path.parent() {
+            fs::File::open(dir)?.sync_all()?;
+        }
+        Ok(())
+    })();
+    if result.is_err()
+        && let Err(remove_error) = fs::remove_file(&temp_path)
+        && remove_error.kind() != std::io::ErrorKind::NotFound {
+        error!("Failed to remove the temporary file {}: {remove_error}", temp_path.display());
+    }
+    result
+}
+
+pub fn write_config(config: &Config) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    write_file_atomically(&config_file_path(), serde_json::to_string_pretty(config)?.as_bytes())?;
+    Ok(())
+}
+
+// ログイン中のアカウントのパスフレーズを変える。鍵とウォレットの接続先を新しいパスフレーズで暗号化し直す
+// キーチェーンに保存している場合は、キーチェーンに書けなかったら設定ファイルも元に戻す
+pub fn change_passphrase(
+    public_key: PublicKey,
+    old_passphrase: &str,
+    new_passphrase: &str,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut config = read_config()?;
+    let index = config
+        .accounts
+        .iter()
+        .position(|account| account.pubkey == public_key.to_hex())
+        .ok_or(tr!("このアカウントの鍵は保存されていません"))?;
+    let account = &config.accounts[index];
+    let old_encrypted_secret_key = key_store::load_encrypted_secret_key(account)?;
+    let secret_key = nip49::decrypt_secret_key(&old_encrypted_secret_key, old_passphrase, &account.salt)
+        .map_err(|_| tr!("今のパスフレーズが違います"))?;
+    if Keys::new(secret_key.clone()).public_key() != public_key {
+        return Err(tr!("保存されている鍵がログイン中のアカウントと一致しません").into());
+    }
+
+    let mut updated = account.clone();
+    updated.salt = nip49::generate_salt();
+    updated.kdf = config.kdf;
+    updated.encrypted_nwc_uri = match &account.encrypted_nwc_uri {
+        Some(encrypted_nwc_uri) => {
+            let nwc_uri = nip49::decrypt(encrypted_nwc_uri, old_passphrase, &account.salt, &account.kdf)?;
+            Some(nip49::encrypt_with_salt(&nwc_uri, new_passphrase, &updated.salt, &updated.kdf)?)
+        }
+        None => None,
+    };
+    let new_encrypted_secret_key = nip49::encrypt_secret_key(&secret_key, new_passphrase)?;
+    // 書き込む前に、新しいパスフレーズで元の鍵に戻せることを確かめる
+    if nip49::decrypt_secret_key(&new_encrypted_secret_key, new_passphrase, &updated.salt)? != secret_key {
+        return Err(tr!("暗号化し直した鍵を確認できませんでした").into());
+    }
+    let uses_keyring = updated.key_storage == KeyStorage::Keyring;
+    if !uses_keyring {
+        key_store::store_encrypted_secret_key(&mut updated, new_encrypted_secret_key.clone())?;
+    }
+    let previous = std::mem::replace(&mut config.accounts[index], updated);
+
+    // 先に設定ファイルを置き換え、キーチェーンに書けなかったら設定ファイルを元に戻す
+    write_config(&config).map_err(|e| tr!("設定ファイルを書き換えられませんでした: {}", e))?;
+    if uses_keyring
+        && let Err(e) = key_store::store_encrypted_secret_key(&mut config.accounts[index], new_encrypted_secret_key)
+    {
+        config.accounts[index] = previous;
+        if let Err(restore_error) = write_config(&config) {
+            error!("Failed to restore the config file: {restore_error}");
+        }
+        return Err(tr!("キーチェーンに鍵を保存できませんでした: {}", e).into());
+    }
+    info!("Changed the passphrase of {}", short_npub(&public_key));
+    Ok(())
+}
+
+// ログイン中のアカウントの秘密鍵を、パスフレーズを確かめてから nsec にして返す
+pub fn export_secret_key(
+    public_key: PublicKey,
+    passphrase: &str,
+) -> Result<Zeroizing<String>, Box<dyn std::error::Error + Send + Sync>> {
+    let config = read_config()?;
+    let account = config
+        .accounts
+        .iter()
+        .find(|account| account.pubkey == public_key.to_hex())
+        .ok_or(tr!("このアカウントの鍵は保存されていません"))?;
+    let encrypted_secret_key = key_store::load_encrypted_secret_key(account)?;
+    let secret_key = nip49::decrypt_secret_key(&encrypted_secret_key, passphrase, &account.salt)
+        .map_err(|_| tr!("パスフレーズが違います"))?;
+    if Keys::new(secret_key.clone()).public_key() != public_key {
+        return Err(tr!("保存されている鍵がログイン中のアカウントと一致しません").into());
+    }
+    Ok(Zeroizing::new(secret_key.to_bech32()?))
+}
+
+// ラベルがまだないアカウントは番号で表示する
+fn account_label(index: usize, account: &StoredAccount) -> String {
+    if account.label.is_empty() {
+        tr!("アカウント {}", index + 1)
+    } else {
+        account.label.clone()
+    }
+}
+
+pub fn load_account_summaries() -> (Vec<AccountSummary>, usize) {
+    if !config_file_path().exists() {
+        return (Vec::new(), 0);
+    }
+    match read_config() {
+        Ok(config) => {
+            let summaries = config
+                .accounts
+                .iter()
+                .enumerate()
+                .map(|(index, account)| AccountSummary { label: account_label(index, account), pubkey: account.pubkey.clone() })
+                .collect();
+            (summaries, config.active_account)
+        }
+        Err(e) => {
+            error!("Failed to read the config file: {e}");
+            (Vec::new(), 0)
+        }
+    }
+}
+
+pub fn refresh_accounts(app_data: &mut NostrStatusAppInternal) {
+    let (accounts, active_account) = load_account_summaries();
+    app_data.selected_login_account = active_account.min(accounts.len().saturating_sub(1));
+    app_data.accounts = accounts;
+}
+
+// アカウントを追加する。同じ鍵のアカウントがあれば置き換え、最後に使ったアカウントにする
+pub fn add_account(config: &mut Config, account: StoredAccount) {
+    match config.accounts.iter().position(|a| a.pubkey == account.pubkey) {
+        Some(index) => {
+            config.accounts[index] = account;
+            config.active_account = index;
+        }
+        None => {
+            config.accounts.push(account);
+            config.active_account = config.accounts.len() - 1;
+        }
+    }
+}
+
+// 最後に使ったアカウントとして記録し、そのアカウントの鍵の保存先を返す
+fn set_active_account(pubkey_hex: &str) -> Result<Option<KeyStorage>, Box<dyn std::error::Error + Send + Sync>> {
+    let mut config = read_config()?;
+    let Some(index) = config.accounts.iter().position(|a| a.pubkey == pubkey_hex) else {
+        return Ok(None);
+    };
+    config.active_account = index;
+    write_config(&config)?;
+    Ok(Some(config.accounts[index].key_storage))
+}
+
+// ログイン中の状態をすべて消し、使っていたクライアントと署名者を返す
+// ログアウトでは閉じ、アカウントの切り替えではバックグラウンドのアカウントとして残す
+pub fn clear_session(
+    app_data: &mut NostrStatusAppInternal,
+    runtime_handle: &tokio::runtime::Handle,
+) -> Option<(Client, AppSigner)> {
+    // 入力途中の投稿は、次にこのアカウントでログインしたときに戻す
+    drafts::save_drafts(app_data);
+    app_data.saved_drafts = Drafts::default();
+    let client = app_data.nostr_client.take();
+    let nwc_client_to_shutdown = app_data.nwc_client.take();
+    app_data.nwc = None;
+    app_data.zap_history.clear();
+    // 前のアカウントの通信を次のアカウントで見せない
+    relay_debug::clear();
+
+    app_data.is_logged_in = false;
+    app_data.is_loading = false;
+    app_data.login_stage = None;
+    app_data.session_generation += 1;
+    let signer = app_data.my_signer.take();
+    app_data.remote_signer_status = None;
+    app_data.bunker_auth_url = None;
+    app_data.key_storage_error = None;
+    app_data.followed_pubkeys.clear();
+    app_data.selected_friend = None;
+    app_data.show_scan_qr = false;
+    app_data.scan_qr_input.clear();
+    app_data.scan_qr_image_input.clear();
+    app_data.camera_scan = None;
+    app_data.scanned_profile = None;
+    app_data.profiles.clear();
+    app_data.conversations.clear();
+    app_data.read_state = ReadState::starting_at(Timestamp::now());
+    app_data.dm_peer_read_at.clear();
+    app_data.dm_peer_typing.clear();
+    app_data.show_chat_emoji_picker = false;
+    app_data.emoji_search_input.clear();
+    app_data.attach_target = None;
+    app_data.attach_path_input.clear();
+    app_data.is_uploading_media = false;
+    app_data.voice_recording = None;
+    app_data.uploaded_media.clear();
+    app_data.call = None;
+    app_data.call_backend.close();
+    app_data.status_private = false;
+    app_data.follow_sets.clear();
+    app_data.selected_follow_set = None;
+    app_data.last_typing_sent = None;
+    app_data.selected_conversation = None;
+    app_data.dm_message_input.clear();
+    app_data.dm_listener_started = false;
+    app_data.notification_listener_started = false;
+    app_data.zap_listener_started = false;
+    app_data.relay_monitor_started = false;
+    app_data.relay_supervisor_started = false;
+    app_data.outbox_worker_started = false;
+    app_data.now_playing_worker_started = false;
+    app_data.timestamp_proofs.clear();
+    app_data.opentimestamps_worker_started = false;
+    app_data.event_labels.clear();
+    app_data.pubkey_labels.clear();
+    app_data.labels_checked_events.clear();
+    app_data.labels_checked_pubkeys.clear();
+    app_data.label_worker_started = false;
+    app_data.wot_follow_counts.clear();
+    app_data.wot_loaded = false;
+    app_data.wot_worker_started = false;
+    app_data.revealed_stranger_posts.clear();
+    app_data.scheduler_started = false;
+    app_data.scheduled_statuses.clear();
+    app_data.note_input.clear();
+    app_data.note_content_warning = false;
+    app_data.note_content_warning_reason.clear();
+    app_data.revealed_content_warnings.clear();
+    app_data.notes.clear();
+    app_data.notes_loaded = false;
+    app_data.bookmarked_ids.clear();
+    app_data.bookmarks_requested = false;
+    app_data.bookmark_events.clear();
+    app_data.bookmark_events_loaded = false;
+    app_data.articles.clear();
+    app_data.articles_loaded = false;
+    app_data.open_article = None;
+    app_data.pins = PinnedItems::default();
+    app_data.pins_requested = false;
+    app_data.pins_loaded = false;
+    app_data.synced_settings = None;
+    app_data.settings_sync_started = false;
+    app_data.reply_target = None;
+    app_data.reply_input.clear();
+    app_data.quote_target = None;
+    app_data.quote_input.clear();
+    app_data.report_target = None;
+    app_data.report_comment.clear();
+    app_data.badge_award_target = None;
+    app_data.my_badge_definitions = None;
+    app_data.link_view = None;
+    app_data.link_view_event = None;
+    app_data.user_profile = None;
+    app_data.hashtag_feed = None;
+    app_data.hashtag_events.clear();
+    app_data.global_events.clear();
+    app_data.search_query.clear();
+    app_data.search_results = None;
+    app_data.outbox.clear();
+    // 取り消せる間に待っていたものは送信キューに残してあり、次にこのアカウントでログインしたときに送る
+    if !app_data.held_sends.is_empty() {
+        let count = app_data.held_sends.len();
+        app_data.notify(tr!("取り消せる間だった {} 件は、次にログインしたときに送信します", count));
+    }
+    app_data.held_sends.clear();
+    pow::cancel_all(app_data);
+    app_data.status_history.clear();
+    app_data.current_statuses.clear();
+    app_data.timeline_status_filter = None;
+    app_data.last_error = None;
+    app_data.relay_status_rows.clear();
+    app_data.zap_totals.clear();
+    app_data.counted_zap_receipts.clear();
+    app_data.joined_groups.clear();
+    app_data.joined_groups_loaded = false;
+    app_data.group_directory.clear();
+    app_data.selected_group = None;
+    app_data.group_messages.clear();
+    app_data.group_members.clear();
+    app_data.group_message_input.clear();
+    app_data.group_client = None;
+    app_data.group_listener_key = None;
+    app_data.channels.clear();
+    app_data.channels_loaded = false;
+    app_data.selected_channel = None;
+    app_data.channel_messages.clear();
+    app_data.channel_message_input.clear();
+    app_data.channel_listener_key = None;
+    app_data.channel_prefs = ChannelPrefs::default();
+    app_data.communities.clear();
+    app_data.communities_loaded = false;
+    app_data.selected_community = None;
+    app_data.community_posts.clear();
+    app_data.community_posts_key = None;
+    app_data.community_post_input.clear();
+    app_data.timeline_posts.clear();
+    app_data.status_message_input.clear();
+    app_data.passphrase_input.zeroize();
+    app_data.confirm_passphrase_input.zeroize();
+    app_data.secret_key_input.zeroize();
+    app_data.mnemonic_passphrase_input.zeroize();
+    app_data.current_tab = AppTab::Home;
+    app_data.nip01_profile_display.clear();
+    app_data.editable_profile = ProfileMetadata::default();
+    app_data.my_identities.clear();
+    app_data.profile_fetch_status = tr!("ログインしてください").to_string();
+    app_data.switch_account_target = None;
+    app_data.switch_passphrase_input.zeroize();
+    app_data.show_passphrase_dialog = false;
+    app_data.old_passphrase_input.zeroize();
+    app_data.new_passphrase_input.zeroize();
+    app_data.confirm_new_passphrase_input.zeroize();
+    app_data.passphrase_change_error = None;
+    app_data.show_export_key_dialog = false;
+    app_data.export_key_passphrase_input.zeroize();
+    app_data.export_key_error = None;
+    app_data.exported_secret_key = None;
+    app_data.is_locked = false;
+    // 署名を待っているものは、このアカウントのものなので断る
+    sign_gate::reject_all();
+    app_data.sign_always_allow_kind = false;
+    app_data.should_repaint = true;
+
+    if let Some(client) = nwc_client_to_shutdown {
+        runtime_handle.spawn(async move {
+            client.shutdown().await;
+        });
+    }
+    client.zip(signer)
+}
+
+// 鍵はこの場で消し、接続はバックグラウンドで閉じる
+fn shutdown_account(runtime_handle: &tokio::runtime::Handle, client: Client, signer: AppSigner) {
+    signer.wipe();
+    runtime_handle.spawn(async move {
+        client.shutdown().await;
+        if let Some(remote) = signer.remote().cloned() {
+            remote.shutdown().await;
+        }
+    });
+}
+
+// ログアウトする。バックグラウンドのアカウントも切断する
+pub fn logout(app_data: &mut NostrStatusAppInternal, runtime_handle: &tokio::runtime::Handle) {
+    if let Some((client, signer)) = clear_session(app_data, runtime_handle) {
+        shutdown_account(runtime_handle, client, signer);
+    }
+    disconnect_secondary(app_data, runtime_handle);
+    info!("Logged out.");
+}
+
+// アプリを閉じるときの後始末。送信待ちのイベントを送ってみてから、下書きを保存してすべてのリレーとの接続を閉じる
+// ログアウトと違い、ランタイムが止まる前に接続を閉じ終えるまで待つ
+pub async fn shutdown(app_data_arc: Arc<Mutex<NostrStatusAppInternal>>, runtime_handle: tokio::runtime::Handle) {
+    let client = app_data_arc.lock().unwrap().nostr_client.clone();
+    if let Some(client) = &client {
+        outbox::flush(client, &app_data_arc).await;
+    }
+
+    let mut clients = Vec::new();
+    let mut signers = Vec::new();
+    {
+        let mut app_data = app_data_arc.lock().unwrap();
+        clients.extend(app_data.nwc_client.take());
+        clients.extend(app_data.group_client.take());
+        if let Some(secondary) = app_data.secondary_account.take() {
+            clients.push(secondary.client);
+            signers.push(secondary.signer);
+        }
+        if let Some((client, signer)) = clear_session(&mut app_data, &runtime_handle) {
+            clients.push(client);
+            signers.push(signer);
+        }
+    }
+    for signer in &signers {
+        signer.wipe();
+    }
+    futures::future::join_all(clients.iter().map(|client| client.shutdown())).await;
+    for signer in signers {
+        if let Some(remote) = signer.remote().cloned() {
+            remote.shutdown().await;
+        }
+    }
+    info!("Closed all relay connections.");
+}
+
+// 復号した鍵をメモリから消して、同じアカウントのパスフレーズの入力に戻る
+// バックグラウンドのアカウントも鍵を持っているので一緒に切断する
+pub fn lock(app_data: &mut NostrStatusAppInternal, runtime_handle: &tokio::runtime::Handle) {
+    let pubkey_hex = app_data
+        .my_signer
+        .as_ref()
+        .or(app_data.secondary_account.as_ref().map(|secondary| &secondary.signer))
+        .map(|signer| signer.public_key().to_hex());
+    logout(app_data, runtime_handle);
+    if let Some(index) = pubkey_hex.and_then(|hex| app_data.accounts.iter().position(|account| account.pubkey == hex)) {
+        app_data.selected_login_account = index;
+    }
+    app_data.adding_account = false;
+    app_data.is_locked = true;
+    info!("Locked the session.");
+}
+
+pub fn disconnect_secondary(app_data: &mut NostrStatusAppInternal, runtime_handle: &tokio::runtime::Handle) {
+    if let Some(secondary) = app_data.secondary_account.take() {
+        info!("Disconnecting the background account {}", secondary.label);
+        shutdown_account(runtime_handle, secondary.client, secondary.signer);
+    }
+}
+
+// 今のアカウントをログアウトせずにバックグラウンドへ移す。前にバックグラウンドにいたアカウントは切断する
+pub fn move_to_background(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    ctx: &egui::Context,
+) {
+    let Some((client, signer)) = clear_session(app_data, runtime_handle) else {
+        return;
+    };
+    disconnect_secondary(app_data, runtime_handle);
+    let pubkey_hex = signer.public_key().to_hex();
+    let label = app_data
+        .accounts
+        .iter()
+        .find(|account| account.pubkey == pubkey_hex)
+        .map(|account| account.label.clone())
+        .unwrap_or_else(|| short_npub(&signer.public_key()));
+    info!("Keeping {label} connected in the background");
+    runtime_handle.spawn(run_secondary_listener(client.clone(), signer.clone(), app_data_arc, ctx.clone()));
+    app_data.secondary_account = Some(SecondaryAccount { label, signer, client, unread: 0 });
+}
+
+fn finish_switch(app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>, result: Result<(), Box<dyn std::error::Error + Send + Sync>>) {
+    let mut app_data = app_data_arc.lock().unwrap();
+    if let Err(e) = result {
+        error!("Failed to switch accounts: {e}");
+        app_data.report_error(tr!("アカウントを切り替えられませんでした: {}", e));
+    }
+    app_data.is_switching_account = false;
+    app_data.is_loading = false;
+    app_data.should_repaint = true;
+}
+
+// バックグラウンドにいるアカウントに切り替える。鍵は復号済みなのでパスフレーズはいらない
+// ウォレット (NWC) の接続先はパスフレーズで暗号化しているので、つなぎ直すにはログインし直す
+pub fn spawn_switch_to_secondary(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    ctx: &egui::Context,
+) {
+    if app_data.is_switching_account {
+        return;
+    }
+    let Some(target) = app_data.secondary_account.take() else {
+        return;
+    };
+    move_to_background(app_data, app_data_arc.clone(), runtime_handle, ctx);
+    app_data.is_switching_account = true;
+    app_data.is_loading = true;
+    let cache_db = app_data.cache_db.clone();
+    let handle = runtime_handle.clone();
+    runtime_handle.spawn(async move {
+        target.client.shutdown().await;
+        match set_active_account(&target.signer.public_key().to_hex()) {
+            Ok(key_storage) => {
+                let mut app_data = app_data_arc.lock().unwrap();
+                app_data.key_storage = key_storage.unwrap_or_default();
+                app_data.selected_login_account = app_data.accounts.iter().position(|a| a.pubkey == target.signer.public_key().to_hex()).unwrap_or(app_data.selected_login_account);
+            }
+            Err(e) => error!("Failed to remember the active account: {e}"),
+        }
+        let result = login_view::start_session(target.signer, cache_db, app_data_arc.clone(), handle).await;
+        finish_switch(&app_data_arc, result);
+    });
+}
+
+// 保存したほかのアカウントに切り替える。パスフレーズが違うときは今のアカウントのまま
+pub fn spawn_switch_to_stored(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    ctx: &egui::Context,
+    account_index: usize,
+) {
+    if app_data.is_switching_account {
+        return;
+    }
+    let passphrase = Zeroizing::new(std::mem::take(&mut app_data.switch_passphrase_input));
+    let cache_db = app_data.cache_db.clone();
+    app_data.is_switching_account = true;
+    app_data.should_repaint = true;
+    let handle = runtime_handle.clone();
+    let ctx = ctx.clone();
+    runtime_handle.spawn(async move {
+        let unlocked = match login_view::unlock_account(account_index, &passphrase) {
+            Ok(unlocked) => unlocked,
+            Err(e) => {
+                finish_switch(&app_data_arc, Err(e));
+                return;
+            }
+        };
+        {
+            let mut app_data = app_data_arc.lock().unwrap();
+            move_to_background(&mut app_data, app_data_arc.clone(), &handle, &ctx);
+            app_data.is_loading = true;
+        }
+        let result = login_view::start_unlocked_session(unlocked, cache_db, app_data_arc.clone(), handle).await;
+        finish_switch(&app_data_arc, result);
+    });
+}
+
+fn is_secondary(app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>, public_key: PublicKey) -> bool {
+    let app_data = app_data_arc.lock().unwrap();
+    app_data.secondary_account.as_ref().is_some_and(|account| account.signer.public_key() == public_key)
+}
+
+// バックグラウンドのアカウント宛ての DM とメンションを数え、デスクトップ通知を出すタスク
+// バックグラウンドから外れたら終了する
+pub async fn run_secondary_listener(
+    client: Client,
+    signer: AppSigner,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    ctx: egui::Context,
+) {
+    let my_public_key = signer.public_key();
+    let since = Timestamp::now();
+    let mut notifications = client.notifications();
+
+    let filters = [
+        gift_wrap::filter_for(my_public_key, Some(since)),
+        Filter::new().kind(Kind::TextNote).pubkey(my_public_key).since(since),
+    ];
+    let mut subscription_ids = Vec::new();
+    for filter in filters {
+        match client.subscribe(filter, None).await {
+            Ok(output) => subscription_ids.push(output.val),
+            Err(e) => error!("Failed to subscribe for the background account: {e}"),
+        }
+    }
+
+    while is_secondary(&app_data_arc, my_public_key) {
+        let notification = match tokio::time::timeout(Duration::from_secs(2), notifications.recv()).await {
+            Err(_) => continue,
+            Ok(Ok(notification)) => notification,
+            Ok(Err(RecvError::Lagged(_))) => continue,
+            Ok(Err(RecvError::Closed)) => break,
+        };
+        let RelayPoolNotification::Event { subscription_id, event, .. } = notification else {
+            continue;
+        };
+        if !subscription_ids.contains(&subscription_id) {
+            continue;
+        }
+        let (category, author, content) = match event.kind {
+            Kind::GiftWrap => match gift_wrap::unwrap(&signer, &event).await {
+                // ギフトラップの時刻はずらされているので、中の rumor の時刻で新しいものか判断する
+                Ok((sender, rumor)) if rumor.kind == Kind::PrivateDirectMessage && sender != my_public_key && rumor.created_at >= since => {
+                    (NotificationCategory::DirectMessage, sender, rumor.content)
+                }
+                Ok(_) => continue,
+                Err(e) => {
+                    error!("Failed to unwrap a gift wrap for the background account: {e}");
+                    continue;
+                }
+            },
+            Kind::TextNote if event.pubkey != my_public_key && !event.is_expired() => {
+                (NotificationCategory::Mention, event.pubkey, event.content.clone())
+            }
+            _ => continue,
+        };
+
+        let mut app_data = app_data_arc.lock().unwrap();
+        let Some(secondary) = app_data.secondary_account.as_mut().filter(|account| account.signer.public_key() == my_public_key) else {
+            break;
+        };
+        secondary.unread += 1;
+        let label = secondary.label.clone();
+        desktop_notify::notify_for_account(&app_data, &label, category, &author, &content);
+        app_data.should_repaint = true;
+        ctx.request_repaint();
+    }
+
+    for id in subscription_ids {
+        client.unsubscribe(&id).await;
+    }
+}
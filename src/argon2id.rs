@@ -0,0 +1,62 @@
+// Argon2id (RFC 9106, バージョン 0x13)。計算は RustCrypto の argon2 クレートに任せる
+// パスフレーズから暗号鍵を作るためだけに使うので、秘密値 (K) と関連データ (X) は空にしている
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use zeroize::Zeroizing;
+
+use crate::i18n::tr;
+
+// 設定ファイルが壊れていても、ロック解除でメモリや時間を使い果たさないための上限
+// 既定の 64 MiB・3回・1並列より十分大きくしておく
+const MAX_MEMORY_KIB: u32 = 1024 * 1024;
+const MAX_ITERATIONS: u32 = 64;
+const MAX_PARALLELISM: u32 = 16;
+
+// Argon2id で password と salt から out_len バイトの鍵を作る
+// memory_kib は 8 * parallelism 以上、iterations は 1 以上であること
+pub fn hash(
+    password: &[u8],
+    salt: &[u8],
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+    out_len: usize,
+) -> Result<Zeroizing<Vec<u8>>, Box<dyn std::error::Error + Send + Sync>> {
+    // メモリを確保する前に、パラメータが上限を超えていないか確かめる
+    if parallelism == 0 || parallelism > MAX_PARALLELISM || iterations == 0 || iterations > MAX_ITERATIONS || memory_kib > MAX_MEMORY_KIB {
+        return Err(tr!("Argon2id のパラメータが不正です").into());
+    }
+    if memory_kib < 8 * parallelism {
+        return Err(tr!("Argon2id のメモリ量が少なすぎます").into());
+    }
+    let params = Params::new(memory_kib, iterations, parallelism, Some(out_len)).map_err(|_| tr!("Argon2id のパラメータが不正です"))?;
+    let mut out = Zeroizing::new(vec![0u8; out_len]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(password, salt, &mut out)
+        .map_err(|_| tr!("Argon2id のパラメータが不正です"))?;
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 9106 の参照実装 (phc-winner-argon2) のテストベクター
+    #[test]
+    fn matches_the_reference_vector() {
+        let hash = hash(b"password", b"somesalt", 65536, 2, 1, 32).unwrap();
+        assert_eq!(hex::encode(hash.as_slice()), "09316115d5cf24ed5a15a31a3ba326e5cf32edc24702987c02b6566f61913cf7");
+    }
+
+    #[test]
+    fn rejects_out_of_range_parameters() {
+        assert!(hash(b"password", b"somesalt", 64, 1, 0, 32).is_err());
+        assert!(hash(b"password", b"somesalt", 64, 0, 1, 32).is_err());
+        assert!(hash(b"password", b"somesalt", 64, MAX_ITERATIONS + 1, 1, 32).is_err());
+        assert!(hash(b"password", b"somesalt", 64, 1, MAX_PARALLELISM + 1, 32).is_err());
+        assert!(hash(b"password", b"somesalt", MAX_MEMORY_KIB + 1, 1, 1, 32).is_err());
+        // 並列数ごとに 8 KiB はいる
+        assert!(hash(b"password", b"somesalt", 15, 1, 2, 32).is_err());
+        assert!(hash(b"password", b"somesalt", 16, 1, 2, 32).is_ok());
+    }
+}
@@ -0,0 +1,75 @@
+use nostr::nips::nip01::Coordinate;
+use nostr::{Event, Filter, Kind, PublicKey, TagKind, Timestamp};
+use nostr_sdk::Client;
+use std::collections::{HashMap, HashSet};
+
+use crate::network;
+use crate::nostr_client::fetch_profiles_for_pubkeys;
+use crate::replaceable;
+use crate::types::ProfileMetadata;
+
+// 長文の記事 (NIP-23, kind 30023)。友だちの記事を記事タブにまとめて読めるようにする
+const ARTICLES_FETCH_LIMIT: usize = 100;
+
+// 記事の見出しに使うタグ。どれもなくてもよい
+pub struct ArticleInfo<'a> {
+    pub title: Option<&'a str>,
+    pub image: Option<&'a str>,
+    pub summary: Option<&'a str>,
+    // 最初に公開した時刻。書き直しても変わらない
+    pub published_at: Timestamp,
+}
+
+fn tag_value<'a>(event: &'a Event, kind: TagKind<'_>) -> Option<&'a str> {
+    event.tags.find(kind).and_then(|tag| tag.content()).map(str::trim).filter(|value| !value.is_empty())
+}
+
+pub fn article_info(event: &Event) -> ArticleInfo<'_> {
+    let published_at = tag_value(event, TagKind::PublishedAt)
+        .and_then(|value| value.parse::<u64>().ok())
+        .map(Timestamp::from)
+        .unwrap_or(event.created_at);
+    ArticleInfo {
+        title: tag_value(event, TagKind::Title),
+        image: tag_value(event, TagKind::Image),
+        summary: tag_value(event, TagKind::Summary),
+        published_at,
+    }
+}
+
+pub fn article_coordinate(event: &Event) -> Coordinate {
+    Coordinate::new(Kind::LongFormTextNote, event.pubkey).identifier(event.tags.identifier().unwrap_or_default())
+}
+
+// フォローしている人の記事を、公開した日の新しい順に取得する。書き直された記事は最新のものだけを残す
+pub async fn fetch_articles(
+    client: &Client,
+    authors: Vec<PublicKey>,
+    known_profiles: HashSet<PublicKey>,
+) -> Result<(Vec<Event>, HashMap<PublicKey, ProfileMetadata>), Box<dyn std::error::Error + Send + Sync>> {
+    if authors.is_empty() {
+        return Ok((Vec::new(), HashMap::new()));
+    }
+    let filter = Filter::new().authors(authors).kind(Kind::LongFormTextNote).limit(ARTICLES_FETCH_LIMIT);
+    let fetched = network::fetch_events(client, filter).await?;
+    let mut events: Vec<Event> =
+        replaceable::dedupe_events(fetched).into_iter().filter(|event| !event.content.is_empty()).collect();
+    events.sort_by_key(|event| std::cmp::Reverse(article_info(event).published_at));
+
+    let unknown: HashSet<PublicKey> = events
+        .iter()
+        .map(|event| event.pubkey)
+        .filter(|pubkey| !known_profiles.contains(pubkey))
+        .collect();
+    let profiles = fetch_profiles_for_pubkeys(client, unknown.into_iter().collect()).await?;
+    Ok((events, profiles))
+}
+
+// nostr:naddr などで指定された記事を1件取得する
+pub async fn fetch_article(
+    client: &Client,
+    coordinate: Coordinate,
+) -> Result<Option<Event>, Box<dyn std::error::Error + Send + Sync>> {
+    let events = network::fetch_events(client, Filter::from(&coordinate)).await?;
+    Ok(events.into_iter().max_by_key(|event| event.created_at))
+}
@@ -0,0 +1,174 @@
+use chrono::Local;
+use nostr::{Event, EventId, Kind, PublicKey, RelayMessage, RelayUrl, Timestamp};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{LazyLock, Mutex};
+use tracing::error;
+
+use crate::audit_log_file_path;
+use crate::i18n::tr;
+
+// このアプリが署名したイベントと、それを送ったリレーの応答 (OK) の記録
+// 知らないうちに何かが公開されていないか後から確かめられるよう、1行ずつ追記するだけで書き換えない
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum AuditRecord {
+    Signed {
+        id: EventId,
+        kind: u16,
+        pubkey: PublicKey,
+        created_at: Timestamp,
+        signed_at: Timestamp,
+    },
+    RelayResult {
+        id: EventId,
+        relay: String,
+        accepted: bool,
+        message: String,
+        received_at: Timestamp,
+    },
+}
+
+// 画面に出すときの、1つのイベントにまとめた記録
+#[derive(Debug, Clone)]
+pub struct AuditEntry {
+    pub id: EventId,
+    pub kind: Kind,
+    pub pubkey: PublicKey,
+    pub signed_at: Timestamp,
+    pub relays: Vec<RelayResult>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RelayResult {
+    pub relay: String,
+    pub accepted: bool,
+    pub message: String,
+}
+
+// 記録したイベントの ID。ほかの人のイベントへの応答は記録しないよう、これで見分ける
+// 追記もこのロックを取ってから行い、行が混ざらないようにする
+static SIGNED_IDS: LazyLock<Mutex<HashSet<EventId>>> = LazyLock::new(|| {
+    let ids = read_records()
+        .into_iter()
+        .filter_map(|record| match record {
+            AuditRecord::Signed { id, .. } => Some(id),
+            AuditRecord::RelayResult { .. } => None,
+        })
+        .collect();
+    Mutex::new(ids)
+});
+
+fn read_records() -> Vec<AuditRecord> {
+    let Ok(content) = fs::read_to_string(audit_log_file_path()) else {
+        return Vec::new();
+    };
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match serde_json::from_str(line) {
+            Ok(record) => Some(record),
+            Err(e) => {
+                error!("Skipping a broken audit log line: {e}");
+                None
+            }
+        })
+        .collect()
+}
+
+fn append(record: &AuditRecord) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let path = audit_log_file_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(record)?)?;
+    Ok(())
+}
+
+// 署名したイベントを記録する。AppSigner が署名するたびに呼ぶ
+pub fn record_signed(event: &Event) {
+    let mut ids = SIGNED_IDS.lock().unwrap();
+    let record = AuditRecord::Signed {
+        id: event.id,
+        kind: event.kind.as_u16(),
+        pubkey: event.pubkey,
+        created_at: event.created_at,
+        signed_at: Timestamp::now(),
+    };
+    match append(&record) {
+        Ok(()) => {
+            ids.insert(event.id);
+        }
+        Err(e) => error!("Failed to write the audit log: {e}"),
+    }
+}
+
+// リレーからのメッセージのうち、署名したイベントへの OK を記録する
+pub fn record_relay_message(relay_url: &RelayUrl, message: &RelayMessage) {
+    let RelayMessage::Ok { event_id, status, message } = message else {
+        return;
+    };
+    let ids = SIGNED_IDS.lock().unwrap();
+    if !ids.contains(event_id) {
+        return;
+    }
+    let record = AuditRecord::RelayResult {
+        id: *event_id,
+        relay: relay_url.to_string(),
+        accepted: *status,
+        message: message.to_string(),
+        received_at: Timestamp::now(),
+    };
+    if let Err(e) = append(&record) {
+        error!("Failed to write the audit log: {e}");
+    }
+}
+
+// 記録をイベントごとにまとめて、新しい順に返す
+pub fn load_entries() -> Vec<AuditEntry> {
+    let _guard = SIGNED_IDS.lock().unwrap();
+    let mut entries: Vec<AuditEntry> = Vec::new();
+    let mut index_by_id: HashMap<EventId, usize> = HashMap::new();
+    for record in read_records() {
+        match record {
+            AuditRecord::Signed { id, kind, pubkey, signed_at, .. } => {
+                index_by_id.insert(id, entries.len());
+                entries.push(AuditEntry { id, kind: Kind::from(kind), pubkey, signed_at, relays: Vec::new() });
+            }
+            AuditRecord::RelayResult { id, relay, accepted, message, .. } => {
+                if let Some(&index) = index_by_id.get(&id) {
+                    let relays = &mut entries[index].relays;
+                    // 同じリレーから何度か応答があれば最後のものを使う
+                    relays.retain(|result| result.relay != relay);
+                    relays.push(RelayResult { relay, accepted, message });
+                }
+            }
+        }
+    }
+    entries.reverse();
+    entries
+}
+
+// 書き出し用に、記録したままの内容 (JSON Lines) を返す
+pub fn export() -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let _guard = SIGNED_IDS.lock().unwrap();
+    match fs::read_to_string(audit_log_file_path()) {
+        Ok(content) => Ok(content),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+// 記録をダウンロードフォルダに書き出し、書き出したファイルのパスを返す
+pub fn export_to_file() -> Result<PathBuf, Box<dyn std::error::Error + Send + Sync>> {
+    let content = export()?;
+    let dir = dirs::download_dir().or_else(dirs::home_dir).ok_or(tr!("書き出し先のフォルダが見つかりません"))?;
+    let path = dir.join(format!("N-audit-log-{}.jsonl", Local::now().format("%Y%m%d-%H%M%S")));
+    fs::write(&path, content)?;
+    Ok(path)
+}
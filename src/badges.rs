@@ -0,0 +1,150 @@
+use nostr::nips::nip01::Coordinate;
+use nostr::{Event, EventBuilder, EventId, Filter, Kind, PublicKey, Tag, TagKind, TagStandard};
+use nostr_sdk::Client;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use tracing::{error, info};
+
+use crate::i18n::tr;
+use crate::network;
+use crate::outbox;
+use crate::replaceable;
+use crate::signer::AppSigner;
+use crate::types::{Badge, NostrStatusAppInternal};
+
+// バッジ (NIP-58)
+// プロフィールのバッジ (kind 30008) は、バッジの定義 (kind 30009) の a タグと、授与 (kind 8) の e タグの組を並べたもの
+// 授与が定義を作った人からの本人宛てのものだけを表示する
+
+// プロフィールのバッジの d タグ
+const PROFILE_BADGES_D_TAG: &str = "profile_badges";
+// プロフィールに表示する件数の上限
+const MAX_PROFILE_BADGES: usize = 30;
+
+fn tag_value(event: &Event, name: &str) -> String {
+    event
+        .tags
+        .iter()
+        .find(|tag| tag.kind() == TagKind::custom(name))
+        .and_then(|tag| tag.content())
+        .unwrap_or_default()
+        .to_string()
+}
+
+pub fn badge_from_definition(event: &Event) -> Badge {
+    Badge {
+        issuer: event.pubkey,
+        d_tag: event.tags.identifier().unwrap_or_default().to_string(),
+        name: tag_value(event, "name"),
+        description: tag_value(event, "description"),
+        image: tag_value(event, "image"),
+        thumb: tag_value(event, "thumb"),
+    }
+}
+
+// a タグ・e タグの順に並んだ組を取り出す。組になっていないタグは飛ばす
+fn badge_pairs(profile_badges: &Event) -> Vec<(Coordinate, EventId)> {
+    let mut pairs = Vec::new();
+    let mut coordinate: Option<Coordinate> = None;
+    for tag in profile_badges.tags.iter() {
+        match tag.as_standardized() {
+            Some(TagStandard::Coordinate { coordinate: a, .. }) if a.kind == Kind::BadgeDefinition => {
+                coordinate = Some(a.clone());
+            }
+            Some(TagStandard::Event { event_id, .. }) => {
+                if let Some(coordinate) = coordinate.take() {
+                    pairs.push((coordinate, *event_id));
+                }
+            }
+            _ => {}
+        }
+    }
+    pairs.truncate(MAX_PROFILE_BADGES);
+    pairs
+}
+
+// 授与が定義を作った人からのもので、その定義を指し、本人に宛てたものか
+fn is_valid_award(award: &Event, coordinate: &Coordinate, public_key: &PublicKey) -> bool {
+    award.kind == Kind::BadgeAward
+        && award.pubkey == coordinate.public_key
+        && award.tags.iter().any(|tag| matches!(tag.as_standardized(), Some(TagStandard::Coordinate { coordinate: a, .. }) if a == coordinate))
+        && award.tags.public_keys().any(|awarded| awarded == public_key)
+}
+
+// ユーザーがプロフィールに表示しているバッジを、表示している順に返す
+pub async fn fetch_profile_badges(client: &Client, public_key: PublicKey) -> Result<Vec<Badge>, Box<dyn std::error::Error + Send + Sync>> {
+    let filter = Filter::new()
+        .author(public_key)
+        .kind(Kind::ProfileBadges)
+        .identifier(PROFILE_BADGES_D_TAG);
+    let events = network::fetch_events(client, filter).await?;
+    let Some(profile_badges) = events.into_iter().max_by_key(|event| event.created_at) else {
+        return Ok(Vec::new());
+    };
+    let pairs = badge_pairs(&profile_badges);
+    if pairs.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let issuers: HashSet<PublicKey> = pairs.iter().map(|(coordinate, _)| coordinate.public_key).collect();
+    let d_tags: HashSet<String> = pairs.iter().map(|(coordinate, _)| coordinate.identifier.clone()).collect();
+    let definitions_filter = Filter::new().authors(issuers).kind(Kind::BadgeDefinition).identifiers(d_tags);
+    let awards_filter = Filter::new().ids(pairs.iter().map(|(_, award_id)| *award_id)).kind(Kind::BadgeAward);
+    let (definitions, awards) = tokio::join!(
+        network::fetch_events(client, definitions_filter),
+        network::fetch_events(client, awards_filter),
+    );
+    let definitions: HashMap<(PublicKey, String), Event> = replaceable::dedupe_events(definitions?)
+        .into_iter()
+        .map(|event| ((event.pubkey, event.tags.identifier().unwrap_or_default().to_string()), event))
+        .collect();
+    let awards: HashMap<EventId, Event> = awards?.into_iter().map(|event| (event.id, event)).collect();
+
+    Ok(pairs
+        .into_iter()
+        .filter(|(coordinate, award_id)| awards.get(award_id).is_some_and(|award| is_valid_award(award, coordinate, &public_key)))
+        .filter_map(|(coordinate, _)| definitions.get(&(coordinate.public_key, coordinate.identifier)))
+        .map(badge_from_definition)
+        .collect())
+}
+
+// 自分が作ったバッジの定義
+pub async fn fetch_my_badge_definitions(client: &Client, public_key: PublicKey) -> Result<Vec<Badge>, Box<dyn std::error::Error + Send + Sync>> {
+    let filter = Filter::new().author(public_key).kind(Kind::BadgeDefinition);
+    let events = network::fetch_events(client, filter).await?;
+    let mut badges: Vec<Badge> = replaceable::dedupe_events(events).iter().map(badge_from_definition).collect();
+    badges.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(badges)
+}
+
+// バッジを授与する (kind 8)。送れなかった場合も送信キューに残るので、署名できたら true を返す
+pub async fn award_badge(
+    client: Client,
+    signer: AppSigner,
+    badge: Badge,
+    recipient: PublicKey,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+) -> bool {
+    let coordinate = Coordinate::new(Kind::BadgeDefinition, badge.issuer).identifier(badge.d_tag);
+    let builder = EventBuilder::new(Kind::BadgeAward, "")
+        .tag(Tag::coordinate(coordinate, None))
+        .tag(Tag::public_key(recipient));
+    let event = match builder.sign(&signer).await {
+        Ok(event) => event,
+        Err(e) => {
+            error!("Failed to sign the badge award: {e}");
+            app_data_arc.lock().unwrap().report_error(tr!("バッジの授与に署名できませんでした: {}", e));
+            return false;
+        }
+    };
+
+    let sent = outbox::send_or_queue(&client, event.clone(), &app_data_arc).await;
+    let mut app_data = app_data_arc.lock().unwrap();
+    if sent {
+        info!("Badge award published with event id: {:?}", event.id);
+        app_data.notify(tr!("バッジを贈りました"));
+    } else {
+        app_data.report_error(tr!("バッジの授与を送信できませんでした。送信キューに残し、接続が戻ったら再送します"));
+    }
+    true
+}
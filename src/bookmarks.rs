@@ -0,0 +1,89 @@
+use nostr::{Event, EventBuilder, EventId, Filter, Kind, PublicKey, Tag, TagKind};
+use nostr_sdk::Client;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use tracing::{error, info};
+
+use crate::network;
+use crate::nostr_client::fetch_profiles_for_pubkeys;
+use crate::signer::AppSigner;
+use crate::types::{NostrStatusAppInternal, ProfileMetadata};
+
+// ブックマークリスト (NIP-51, kind 10003)。ステータスもノートも、その時の内容を残せるよう e タグで保存する
+
+async fn fetch_bookmark_list(client: &Client, public_key: PublicKey) -> Result<Option<Event>, Box<dyn std::error::Error + Send + Sync>> {
+    let filter = Filter::new().author(public_key).kind(Kind::Bookmarks).limit(1);
+    let events = network::fetch_events(client, filter).await?;
+    // 複数のリレーから返ってきた場合は最新のものを使う
+    Ok(events.into_iter().max_by_key(|event| event.created_at))
+}
+
+fn bookmarked_ids(tags: &[Tag]) -> Vec<EventId> {
+    tags.iter()
+        .filter(|tag| tag.kind() == TagKind::e())
+        .filter_map(|tag| EventId::parse(tag.content()?).ok())
+        .collect()
+}
+
+// ログインしたときに、どの投稿をブックマークしているかを読み込む
+pub async fn load_bookmarks(client: Client, keys: AppSigner, app_data_arc: Arc<Mutex<NostrStatusAppInternal>>) {
+    match fetch_bookmark_list(&client, keys.public_key()).await {
+        Ok(list) => {
+            let ids = list.map(|event| bookmarked_ids(event.tags.as_slice())).unwrap_or_default();
+            let mut app_data = app_data_arc.lock().unwrap();
+            app_data.bookmarked_ids = ids;
+            app_data.should_repaint = true;
+        }
+        Err(e) => error!("Failed to fetch the bookmark list: {e}"),
+    }
+}
+
+// ブックマークを足すか外して、リストを発行し直す
+// 最新のリストを取り直し、e タグ以外の項目 (a タグやハッシュタグ、暗号化された非公開の項目) はそのまま残す
+pub async fn update_bookmarks(
+    client: &Client,
+    keys: &AppSigner,
+    event_id: EventId,
+    add: bool,
+) -> Result<Vec<EventId>, Box<dyn std::error::Error + Send + Sync>> {
+    let latest = fetch_bookmark_list(client, keys.public_key()).await?;
+    let content = latest.as_ref().map(|event| event.content.clone()).unwrap_or_default();
+    let mut tags: Vec<Tag> = latest.map(|event| event.tags.to_vec()).unwrap_or_default();
+
+    let is_bookmarked = bookmarked_ids(&tags).contains(&event_id);
+    if add && !is_bookmarked {
+        // 新しいものは末尾に足す (NIP-51)
+        tags.push(Tag::event(event_id));
+        info!("Bookmarking {event_id}");
+    } else if !add && is_bookmarked {
+        tags.retain(|tag| !(tag.kind() == TagKind::e() && tag.content() == Some(event_id.to_hex().as_str())));
+        info!("Removing the bookmark of {event_id}");
+    }
+
+    let event = EventBuilder::new(Kind::Bookmarks, content).tags(tags.clone()).sign(keys).await?;
+    client.send_event(&event).await?;
+    Ok(bookmarked_ids(&tags))
+}
+
+// ブックマークした投稿を、新しくブックマークした順に取得する
+pub async fn fetch_bookmarked_events(
+    client: &Client,
+    ids: Vec<EventId>,
+    known_profiles: HashSet<PublicKey>,
+) -> Result<(Vec<Event>, HashMap<PublicKey, ProfileMetadata>), Box<dyn std::error::Error + Send + Sync>> {
+    if ids.is_empty() {
+        return Ok((Vec::new(), HashMap::new()));
+    }
+    let filter = Filter::new().ids(ids.clone());
+    let fetched = network::fetch_events(client, filter).await?;
+    let mut by_id: HashMap<EventId, Event> = fetched.into_iter().map(|event| (event.id, event)).collect();
+    let events: Vec<Event> = ids.iter().rev().filter_map(|id| by_id.remove(id)).collect();
+
+    let unknown: HashSet<PublicKey> = events
+        .iter()
+        .map(|event| event.pubkey)
+        .filter(|pubkey| !known_profiles.contains(pubkey))
+        .collect();
+    let profiles = fetch_profiles_for_pubkeys(client, unknown.into_iter().collect()).await?;
+    Ok((events, profiles))
+}
@@ -13,6 +13,16 @@ pub const DB_FOLLOWED: &str = "followed_pubkeys";
 pub const DB_RELAYS: &str = "nip65_relays";
 pub const DB_TIMELINE: &str = "timeline_posts";
 pub const DB_IMAGES: &str = "images";
+pub const DB_CONTACT_PROFILES: &str = "contact_profiles";
+pub const DB_CHANNEL_PREFS: &str = "channel_prefs";
+pub const DB_OUTBOX: &str = "outbox";
+pub const DB_STATUS_HISTORY: &str = "status_history";
+pub const DB_NIP05: &str = "nip05";
+pub const DB_READ_STATE: &str = "read_state";
+pub const DB_LINK_PREVIEWS: &str = "link_previews";
+pub const DB_DRAFTS: &str = "drafts";
+pub const DB_OPENTIMESTAMPS: &str = "opentimestamps";
+pub const DB_IDENTITIES: &str = "identities";
 
 #[derive(Clone)]
 pub struct LmdbCache {
@@ -20,24 +30,46 @@ pub struct LmdbCache {
 }
 
 impl LmdbCache {
-    pub fn new(path: &Path) -> Result<Self, Error> {
+    fn open(path: &Path, db_names: &[&str]) -> Result<Self, Error> {
         std::fs::create_dir_all(path)?;
         let mut options = heed::EnvOpenOptions::new();
         options.map_size(1024 * 1024 * 1024); // 1 GB
-        options.max_dbs(8);
+        options.max_dbs(16);
         let env = unsafe { options.open(path)? };
 
         let mut txn = env.write_txn()?;
-        let _: Database<Str, Bytes> = env.create_database(&mut txn, Some(DB_PROFILES))?;
-        let _: Database<Str, Bytes> = env.create_database(&mut txn, Some(DB_FOLLOWED))?;
-        let _: Database<Str, Bytes> = env.create_database(&mut txn, Some(DB_RELAYS))?;
-        let _: Database<Str, Bytes> = env.create_database(&mut txn, Some(DB_TIMELINE))?;
-        let _: Database<Str, Bytes> = env.create_database(&mut txn, Some(DB_IMAGES))?;
+        for db_name in db_names {
+            let _: Database<Str, Bytes> = env.create_database(&mut txn, Some(db_name))?;
+        }
         txn.commit()?;
 
         Ok(Self { env: Arc::new(env) })
     }
 
+    pub fn new(path: &Path) -> Result<Self, Error> {
+        Self::open(path, &[
+            DB_PROFILES,
+            DB_FOLLOWED,
+            DB_RELAYS,
+            DB_TIMELINE,
+            DB_CONTACT_PROFILES,
+            DB_CHANNEL_PREFS,
+            DB_OUTBOX,
+            DB_STATUS_HISTORY,
+            DB_NIP05,
+            DB_READ_STATE,
+            DB_LINK_PREVIEWS,
+            DB_DRAFTS,
+            DB_OPENTIMESTAMPS,
+            DB_IDENTITIES,
+        ])
+    }
+
+    // 画像だけを入れるキャッシュ。消えても取り直せるので、OS のキャッシュディレクトリに置く
+    pub fn new_image_cache(path: &Path) -> Result<Self, Error> {
+        Self::open(path, &[DB_IMAGES])
+    }
+
     pub fn read_cache<T: DeserializeOwned>(
         &self,
         db_name: &str,
@@ -79,6 +111,39 @@ impl LmdbCache {
         Ok(())
     }
 
+    // 期限切れにならない設定値などを読み書きする
+    pub fn read_value<T: DeserializeOwned>(
+        &self,
+        db_name: &str,
+        key: &str,
+    ) -> Result<Option<T>, Box<dyn std::error::Error + Send + Sync>> {
+        let rtxn = self.env.read_txn()?;
+        let db: Database<Str, Bytes> = self
+            .env
+            .open_database(&rtxn, Some(db_name))?
+            .ok_or("database not found")?;
+        match db.get(&rtxn, key)? {
+            Some(data) => Ok(Some(serde_json::from_slice(data)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn write_value<T: Serialize>(
+        &self,
+        db_name: &str,
+        key: &str,
+        data: &T,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut wtxn = self.env.write_txn()?;
+        let db: Database<Str, Bytes> = self
+            .env
+            .open_database(&wtxn, Some(db_name))?
+            .ok_or("database not found")?;
+        db.put(&mut wtxn, key, &serde_json::to_vec(data)?)?;
+        wtxn.commit()?;
+        Ok(())
+    }
+
     pub fn read_image_cache(&self, key: &str) -> Result<Option<Vec<u8>>, Error> {
         let rtxn = self.env.read_txn()?;
         let db: Database<Str, Bytes> = self
@@ -0,0 +1,310 @@
+use nostr::{EventBuilder, Kind, PublicKey, Tag, Timestamp};
+use nostr_sdk::Client;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::error;
+
+use crate::gift_wrap;
+use crate::i18n::tr;
+use crate::signer::AppSigner;
+use crate::types::NostrStatusAppInternal;
+
+#[cfg(feature = "call")]
+mod webrtc_backend;
+
+// 通話の合図をギフトラップで送るときの rumor の種類。既読 (20014) と同じく、このアプリ独自のもの
+pub const CALL_SIGNAL_KIND: u16 = 20016;
+// 合図は古くなると意味がないので、リレーに残る期間を短くする (NIP-40)
+const CALL_SIGNAL_EXPIRY_SECS: u64 = 60;
+// これより古い合図は、履歴の読み込みなどで遅れて届いたものとして無視する
+const MAX_SIGNAL_AGE_SECS: u64 = 60;
+// 相手が応答しないまま呼び出しをやめるまでの時間
+const RING_TIMEOUT: Duration = Duration::from_secs(45);
+
+// 通話の合図の中身。WebRTC の SDP と ICE 候補をそのまま運ぶ
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CallSignal {
+    Offer { call_id: String, sdp: String },
+    Answer { call_id: String, sdp: String },
+    Ice { call_id: String, candidate: String },
+    Decline { call_id: String },
+    HangUp { call_id: String },
+}
+
+impl CallSignal {
+    pub fn call_id(&self) -> &str {
+        match self {
+            CallSignal::Offer { call_id, .. }
+            | CallSignal::Answer { call_id, .. }
+            | CallSignal::Ice { call_id, .. }
+            | CallSignal::Decline { call_id }
+            | CallSignal::HangUp { call_id } => call_id,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CallState {
+    // 自分から呼び出している
+    Outgoing,
+    // 相手から呼び出されている
+    Incoming { offer_sdp: String },
+    Connected { since: Instant },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallSession {
+    pub call_id: String,
+    pub peer: PublicKey,
+    pub state: CallState,
+    pub started_at: Instant,
+}
+
+// 通話の音声を扱う部分。WebRTC のスタックをここに差し込む
+// 合図のやりとりはこのモジュールが受け持ち、SDP と ICE 候補の中身はバックエンドに任せる
+pub trait CallMediaBackend: Send + Sync {
+    fn is_available(&self) -> bool;
+    fn create_offer(&self) -> Result<String, Box<dyn std::error::Error + Send + Sync>>;
+    fn create_answer(&self, offer_sdp: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>>;
+    fn set_answer(&self, answer_sdp: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
+    fn add_ice_candidate(&self, candidate: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
+    // 集まった自分の ICE 候補を取り出す。相手に送るのは呼び出し側
+    fn take_local_ice_candidates(&self) -> Vec<String>;
+    fn close(&self);
+}
+
+// 音声のスタックがない環境のバックエンド。着信と拒否はできるが、発信と応答はできない
+pub struct UnavailableMediaBackend;
+
+impl CallMediaBackend for UnavailableMediaBackend {
+    fn is_available(&self) -> bool {
+        false
+    }
+
+    fn create_offer(&self) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        Err(tr!("この環境では音声通話に対応していません").into())
+    }
+
+    fn create_answer(&self, _offer_sdp: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        Err(tr!("この環境では音声通話に対応していません").into())
+    }
+
+    fn set_answer(&self, _answer_sdp: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Err(tr!("この環境では音声通話に対応していません").into())
+    }
+
+    fn add_ice_candidate(&self, _candidate: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Ok(())
+    }
+
+    fn take_local_ice_candidates(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn close(&self) {}
+}
+
+// call 機能を有効にしたビルドでは WebRTC で音声を送り合う。準備に失敗したら音声なしで動かす
+pub fn default_backend() -> Arc<dyn CallMediaBackend> {
+    #[cfg(feature = "call")]
+    match webrtc_backend::WebRtcMediaBackend::new() {
+        Ok(backend) => return Arc::new(backend),
+        Err(e) => error!("Failed to start the call media backend: {e}"),
+    }
+    Arc::new(UnavailableMediaBackend)
+}
+
+fn new_call_id() -> String {
+    format!("{:016x}", rand::random::<u64>())
+}
+
+// 合図を NIP-17 と同じようにギフトラップして相手に送る
+pub async fn send_call_signal(
+    client: &Client,
+    keys: &AppSigner,
+    receiver: PublicKey,
+    signal: &CallSignal,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let content = serde_json::to_string(signal)?;
+    let rumor = EventBuilder::new(Kind::Custom(CALL_SIGNAL_KIND), content)
+        .tag(Tag::public_key(receiver))
+        .build(keys.public_key());
+    let expiration = Timestamp::now() + Duration::from_secs(CALL_SIGNAL_EXPIRY_SECS);
+    gift_wrap::send(client, keys, receiver, rumor, Some(expiration)).await
+}
+
+pub fn parse_call_signal(content: &str) -> Result<CallSignal, serde_json::Error> {
+    serde_json::from_str(content)
+}
+
+fn spawn_send(app_data: &NostrStatusAppInternal, runtime_handle: &tokio::runtime::Handle, peer: PublicKey, signals: Vec<CallSignal>) {
+    let (Some(client), Some(keys)) = (app_data.nostr_client.clone(), app_data.my_signer.clone()) else {
+        return;
+    };
+    runtime_handle.spawn(async move {
+        for signal in signals {
+            if let Err(e) = send_call_signal(&client, &keys, peer, &signal).await {
+                error!("Failed to send the call signal: {e}");
+            }
+        }
+    });
+}
+
+// 自分の ICE 候補を合図にする
+fn local_ice_signals(app_data: &NostrStatusAppInternal, call_id: &str) -> Vec<CallSignal> {
+    app_data
+        .call_backend
+        .take_local_ice_candidates()
+        .into_iter()
+        .map(|candidate| CallSignal::Ice { call_id: call_id.to_string(), candidate })
+        .collect()
+}
+
+// 相手を呼び出す
+pub fn start_call(app_data: &mut NostrStatusAppInternal, runtime_handle: &tokio::runtime::Handle, peer: PublicKey) {
+    if app_data.call.is_some() {
+        return;
+    }
+    let sdp = match app_data.call_backend.create_offer() {
+        Ok(sdp) => sdp,
+        Err(e) => {
+            app_data.report_error(tr!("通話を始められませんでした: {}", e));
+            return;
+        }
+    };
+    let call_id = new_call_id();
+    let mut signals = vec![CallSignal::Offer { call_id: call_id.clone(), sdp }];
+    signals.extend(local_ice_signals(app_data, &call_id));
+    spawn_send(app_data, runtime_handle, peer, signals);
+    app_data.call = Some(CallSession {
+        call_id,
+        peer,
+        state: CallState::Outgoing,
+        started_at: Instant::now(),
+    });
+}
+
+// 着信に応答する
+pub fn accept_call(app_data: &mut NostrStatusAppInternal, runtime_handle: &tokio::runtime::Handle) {
+    let Some(session) = app_data.call.clone() else {
+        return;
+    };
+    let CallState::Incoming { offer_sdp } = &session.state else {
+        return;
+    };
+    let sdp = match app_data.call_backend.create_answer(offer_sdp) {
+        Ok(sdp) => sdp,
+        Err(e) => {
+            app_data.report_error(tr!("通話に応答できませんでした: {}", e));
+            decline_call(app_data, runtime_handle);
+            return;
+        }
+    };
+    let mut signals = vec![CallSignal::Answer { call_id: session.call_id.clone(), sdp }];
+    signals.extend(local_ice_signals(app_data, &session.call_id));
+    spawn_send(app_data, runtime_handle, session.peer, signals);
+    if let Some(call) = app_data.call.as_mut() {
+        call.state = CallState::Connected { since: Instant::now() };
+    }
+}
+
+// 着信を断る
+pub fn decline_call(app_data: &mut NostrStatusAppInternal, runtime_handle: &tokio::runtime::Handle) {
+    let Some(session) = app_data.call.take() else {
+        return;
+    };
+    spawn_send(app_data, runtime_handle, session.peer, vec![CallSignal::Decline { call_id: session.call_id }]);
+    app_data.call_backend.close();
+}
+
+// 呼び出しか通話をやめる
+pub fn hang_up(app_data: &mut NostrStatusAppInternal, runtime_handle: &tokio::runtime::Handle) {
+    let Some(session) = app_data.call.take() else {
+        return;
+    };
+    spawn_send(app_data, runtime_handle, session.peer, vec![CallSignal::HangUp { call_id: session.call_id }]);
+    app_data.call_backend.close();
+}
+
+// 相手から届いた合図を通話の状態に反映する。DM の購読から呼ばれる
+pub fn handle_call_signal(
+    app_data: &mut NostrStatusAppInternal,
+    runtime_handle: &tokio::runtime::Handle,
+    peer: PublicKey,
+    signal: CallSignal,
+    created_at: Timestamp,
+) {
+    if created_at.as_u64() + MAX_SIGNAL_AGE_SECS < Timestamp::now().as_u64() {
+        return;
+    }
+    let current = app_data.call.as_ref().filter(|call| call.peer == peer && call.call_id == signal.call_id());
+    match (signal, current.map(|call| call.state.clone())) {
+        (CallSignal::Offer { call_id, sdp }, _) => {
+            // 通話中なら新しい着信は断る
+            if app_data.call.is_some() {
+                spawn_send(app_data, runtime_handle, peer, vec![CallSignal::Decline { call_id }]);
+                return;
+            }
+            app_data.call = Some(CallSession {
+                call_id,
+                peer,
+                state: CallState::Incoming { offer_sdp: sdp },
+                started_at: Instant::now(),
+            });
+        }
+        (CallSignal::Answer { sdp, .. }, Some(CallState::Outgoing)) => {
+            if let Err(e) = app_data.call_backend.set_answer(&sdp) {
+                app_data.report_error(tr!("通話をつなげませんでした: {}", e));
+                hang_up(app_data, runtime_handle);
+                return;
+            }
+            if let Some(call) = app_data.call.as_mut() {
+                call.state = CallState::Connected { since: Instant::now() };
+            }
+        }
+        (CallSignal::Ice { candidate, .. }, Some(_)) => {
+            if let Err(e) = app_data.call_backend.add_ice_candidate(&candidate) {
+                error!("Failed to add the ICE candidate: {e}");
+            }
+        }
+        (CallSignal::Decline { .. }, Some(_)) => {
+            app_data.call = None;
+            app_data.call_backend.close();
+            app_data.notify(tr!("相手が通話を断りました"));
+        }
+        (CallSignal::HangUp { .. }, Some(_)) => {
+            app_data.call = None;
+            app_data.call_backend.close();
+            app_data.notify(tr!("通話が終了しました"));
+        }
+        _ => {}
+    }
+}
+
+// 通話中に毎フレーム呼ぶ。後から集まった ICE 候補を送り、応答のないまま時間が過ぎた呼び出しをやめる
+pub fn tick(app_data: &mut NostrStatusAppInternal, runtime_handle: &tokio::runtime::Handle) {
+    let Some(session) = &app_data.call else {
+        return;
+    };
+    let ice_signals = local_ice_signals(app_data, &session.call_id);
+    if !ice_signals.is_empty() {
+        spawn_send(app_data, runtime_handle, session.peer, ice_signals);
+    }
+    if session.started_at.elapsed() < RING_TIMEOUT {
+        return;
+    }
+    match session.state {
+        CallState::Outgoing => {
+            hang_up(app_data, runtime_handle);
+            app_data.notify(tr!("応答がありませんでした"));
+        }
+        CallState::Incoming { .. } => {
+            app_data.call = None;
+            app_data.call_backend.close();
+        }
+        CallState::Connected { .. } => {}
+    }
+}
+
@@ -0,0 +1,434 @@
+use bytes::Bytes;
+use rodio::cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use rodio::cpal::{self, FromSample, SampleFormat, SizedSample};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tracing::{error, info, warn};
+use webrtc::api::interceptor_registry::register_default_interceptors;
+use webrtc::api::media_engine::{MediaEngine, MIME_TYPE_PCMU};
+use webrtc::api::APIBuilder;
+use webrtc::ice_transport::ice_candidate::RTCIceCandidateInit;
+use webrtc::ice_transport::ice_server::RTCIceServer;
+use webrtc::interceptor::registry::Registry;
+use webrtc::media::Sample;
+use webrtc::peer_connection::configuration::RTCConfiguration;
+use webrtc::peer_connection::peer_connection_state::RTCPeerConnectionState;
+use webrtc::peer_connection::sdp::session_description::RTCSessionDescription;
+use webrtc::peer_connection::RTCPeerConnection;
+use webrtc::rtp_transceiver::rtp_codec::RTCRtpCodecCapability;
+use webrtc::track::track_local::track_local_static_sample::TrackLocalStaticSample;
+use webrtc::track::track_local::TrackLocal;
+
+use super::CallMediaBackend;
+use crate::i18n::tr;
+use crate::network;
+
+// webrtc クレートで音声だけの通話をする。音は G.711 μ-law (8 kHz, モノラル) で送り合う
+// マイクとスピーカーは cpal で開く。cpal のストリームはスレッドをまたげないので、つながっている間は専用のスレッドで持つ
+
+const STUN_SERVER: &str = "stun:stun.l.google.com:19302";
+const SAMPLE_RATE: u32 = 8000;
+// 1 回に送る音の長さ (20 ミリ秒) とそのサンプル数
+const FRAME_MILLIS: u64 = 20;
+const FRAME_SAMPLES: usize = (SAMPLE_RATE as u64 * FRAME_MILLIS / 1000) as usize;
+// 送り待ち・再生待ちの音をためておく上限 (1 秒)。遅れがたまったら古い音から捨てる
+const MAX_BUFFERED_SAMPLES: usize = SAMPLE_RATE as usize;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+// G.711 μ-law の変換
+const ULAW_BIAS: i32 = 0x84;
+const ULAW_CLIP: i32 = 32635;
+
+fn ulaw_encode(sample: i16) -> u8 {
+    let mut pcm = sample as i32;
+    let sign = if pcm < 0 {
+        pcm = -pcm;
+        0x80
+    } else {
+        0
+    };
+    pcm = pcm.min(ULAW_CLIP) + ULAW_BIAS;
+    let mut exponent = 7;
+    let mut mask = 0x4000;
+    while exponent > 0 && pcm & mask == 0 {
+        exponent -= 1;
+        mask >>= 1;
+    }
+    let mantissa = (pcm >> (exponent + 3)) & 0x0f;
+    !(sign | (exponent << 4) | mantissa) as u8
+}
+
+fn ulaw_decode(byte: u8) -> i16 {
+    let byte = !byte;
+    let exponent = (byte >> 4) & 0x07;
+    let mantissa = (byte & 0x0f) as i32;
+    let magnitude = (((mantissa << 3) + ULAW_BIAS) << exponent) - ULAW_BIAS;
+    if byte & 0x80 != 0 { -magnitude as i16 } else { magnitude as i16 }
+}
+
+fn push_limited(buffer: &mut VecDeque<i16>, samples: impl IntoIterator<Item = i16>) {
+    buffer.extend(samples);
+    let excess = buffer.len().saturating_sub(MAX_BUFFERED_SAMPLES);
+    buffer.drain(..excess);
+}
+
+// 通話ごとの音のやりとり。マイクの音 (capture) を送り、届いた音 (playback) を鳴らす
+#[derive(Default)]
+struct AudioShared {
+    capture: Mutex<VecDeque<i16>>,
+    playback: Mutex<VecDeque<i16>>,
+    closed: AtomicBool,
+    // 落とすと音声のスレッドが止まる
+    stop_audio: Mutex<Option<mpsc::Sender<()>>>,
+}
+
+impl AudioShared {
+    fn stop(&self) {
+        self.closed.store(true, Ordering::Relaxed);
+        self.stop_audio.lock().unwrap().take();
+    }
+}
+
+fn build_input_stream<T>(device: &cpal::Device, config: &cpal::StreamConfig, shared: Arc<AudioShared>) -> Result<cpal::Stream, cpal::BuildStreamError>
+where
+    T: SizedSample,
+    i16: FromSample<T>,
+{
+    // チャンネルを平均してモノラルにし、8 kHz になるように区切りごとに平均する
+    let channels = config.channels.max(1) as usize;
+    let step = config.sample_rate.0 as f64 / SAMPLE_RATE as f64;
+    let (mut sum, mut count, mut position) = (0i64, 0i64, 0.0f64);
+    device.build_input_stream(
+        config,
+        move |data: &[T], _| {
+            let mut resampled = Vec::new();
+            for frame in data.chunks(channels) {
+                let mono: i32 = frame.iter().map(|sample| sample.to_sample::<i16>() as i32).sum::<i32>() / frame.len() as i32;
+                sum += mono as i64;
+                count += 1;
+                position += 1.0;
+                if position >= step {
+                    position -= step;
+                    resampled.push((sum / count) as i16);
+                    (sum, count) = (0, 0);
+                }
+            }
+            push_limited(&mut shared.capture.lock().unwrap(), resampled);
+        },
+        |e| error!("Call microphone stream error: {e}"),
+        None,
+    )
+}
+
+fn build_output_stream<T>(device: &cpal::Device, config: &cpal::StreamConfig, shared: Arc<AudioShared>) -> Result<cpal::Stream, cpal::BuildStreamError>
+where
+    T: SizedSample + FromSample<i16>,
+{
+    // 8 kHz の音を出力の速さに合わせて引き伸ばし、すべてのチャンネルに同じ音を出す
+    let channels = config.channels.max(1) as usize;
+    let step = SAMPLE_RATE as f64 / config.sample_rate.0 as f64;
+    let (mut current, mut position) = (0i16, 1.0f64);
+    device.build_output_stream(
+        config,
+        move |data: &mut [T], _| {
+            let mut playback = shared.playback.lock().unwrap();
+            for frame in data.chunks_mut(channels) {
+                position += step;
+                while position >= 1.0 {
+                    position -= 1.0;
+                    current = playback.pop_front().unwrap_or(0);
+                }
+                frame.fill(T::from_sample(current));
+            }
+        },
+        |e| error!("Call speaker stream error: {e}"),
+        None,
+    )
+}
+
+fn open_audio(shared: &Arc<AudioShared>) -> Result<(cpal::Stream, cpal::Stream), BoxError> {
+    let host = cpal::default_host();
+    let input = host.default_input_device().ok_or(tr!("マイクが見つかりません"))?;
+    let supported = input.default_input_config()?;
+    let config = supported.config();
+    let input_stream = match supported.sample_format() {
+        SampleFormat::F32 => build_input_stream::<f32>(&input, &config, shared.clone())?,
+        SampleFormat::I16 => build_input_stream::<i16>(&input, &config, shared.clone())?,
+        SampleFormat::U16 => build_input_stream::<u16>(&input, &config, shared.clone())?,
+        SampleFormat::I32 => build_input_stream::<i32>(&input, &config, shared.clone())?,
+        _ => return Err(tr!("このマイクの形式には対応していません").into()),
+    };
+    let output = host.default_output_device().ok_or(tr!("スピーカーが見つかりません"))?;
+    let supported = output.default_output_config()?;
+    let config = supported.config();
+    let output_stream = match supported.sample_format() {
+        SampleFormat::F32 => build_output_stream::<f32>(&output, &config, shared.clone())?,
+        SampleFormat::I16 => build_output_stream::<i16>(&output, &config, shared.clone())?,
+        SampleFormat::U16 => build_output_stream::<u16>(&output, &config, shared.clone())?,
+        SampleFormat::I32 => build_output_stream::<i32>(&output, &config, shared.clone())?,
+        _ => return Err(tr!("このスピーカーの形式には対応していません").into()),
+    };
+    input_stream.play()?;
+    output_stream.play()?;
+    Ok((input_stream, output_stream))
+}
+
+// つながったらマイクとスピーカーを開き、マイクの音を 20 ミリ秒ずつ送る
+fn start_audio(shared: Arc<AudioShared>, track: Arc<TrackLocalStaticSample>, runtime_handle: &tokio::runtime::Handle) {
+    {
+        let mut stop_audio = shared.stop_audio.lock().unwrap();
+        if stop_audio.is_some() || shared.closed.load(Ordering::Relaxed) {
+            return;
+        }
+        let (stop, stop_rx) = mpsc::channel::<()>();
+        *stop_audio = Some(stop);
+        let shared = shared.clone();
+        thread::spawn(move || match open_audio(&shared) {
+            Ok(streams) => {
+                // 通話が終わって送り手が捨てられるまで待つ
+                let _ = stop_rx.recv();
+                drop(streams);
+            }
+            Err(e) => error!("Failed to open the audio devices for the call: {e}"),
+        });
+    }
+    runtime_handle.spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_millis(FRAME_MILLIS));
+        while !shared.closed.load(Ordering::Relaxed) {
+            interval.tick().await;
+            let frame: Vec<u8> = {
+                let mut capture = shared.capture.lock().unwrap();
+                let available = capture.len().min(FRAME_SAMPLES);
+                // マイクの音が足りない分は無音で埋める
+                capture
+                    .drain(..available)
+                    .chain(std::iter::repeat_n(0, FRAME_SAMPLES - available))
+                    .map(ulaw_encode)
+                    .collect()
+            };
+            let sample = Sample { data: Bytes::from(frame), duration: Duration::from_millis(FRAME_MILLIS), ..Default::default() };
+            if let Err(e) = track.write_sample(&sample).await {
+                warn!("Failed to send call audio: {e}");
+            }
+        }
+    });
+}
+
+struct Connection {
+    peer_connection: Arc<RTCPeerConnection>,
+    shared: Arc<AudioShared>,
+    local_candidates: Arc<Mutex<Vec<String>>>,
+    has_remote_description: bool,
+}
+
+#[derive(Default)]
+struct BackendState {
+    connection: Option<Connection>,
+    // 相手の SDP より先に届いた ICE 候補。SDP を設定してから加える
+    pending_remote_candidates: Vec<RTCIceCandidateInit>,
+}
+
+pub struct WebRtcMediaBackend {
+    runtime: tokio::runtime::Runtime,
+    state: Mutex<BackendState>,
+}
+
+impl WebRtcMediaBackend {
+    pub fn new() -> Result<Self, BoxError> {
+        let runtime = tokio::runtime::Builder::new_multi_thread().worker_threads(2).thread_name("call").enable_all().build()?;
+        Ok(Self { runtime, state: Mutex::new(BackendState::default()) })
+    }
+
+    // 呼び出し元は UI のスレッドのことも非同期のタスクのこともあるので、別のスレッドで通話用のランタイムの完了を待つ
+    fn block_on<F>(&self, future: F) -> F::Output
+    where
+        F: Future + Send,
+        F::Output: Send,
+    {
+        thread::scope(|scope| scope.spawn(|| self.runtime.block_on(future)).join().unwrap())
+    }
+
+    async fn new_connection(runtime_handle: tokio::runtime::Handle) -> Result<Connection, BoxError> {
+        let mut media_engine = MediaEngine::default();
+        media_engine.register_default_codecs()?;
+        let registry = register_default_interceptors(Registry::new(), &mut media_engine)?;
+        let api = APIBuilder::new().with_media_engine(media_engine).with_interceptor_registry(registry).build();
+        let config = RTCConfiguration {
+            ice_servers: vec![RTCIceServer { urls: vec![STUN_SERVER.to_string()], ..Default::default() }],
+            ..Default::default()
+        };
+        let peer_connection = Arc::new(api.new_peer_connection(config).await?);
+
+        let track = Arc::new(TrackLocalStaticSample::new(
+            RTCRtpCodecCapability { mime_type: MIME_TYPE_PCMU.to_string(), clock_rate: SAMPLE_RATE, channels: 1, ..Default::default() },
+            "audio".to_string(),
+            crate::WINDOW_TITLE.to_string(),
+        ));
+        peer_connection.add_track(track.clone() as Arc<dyn TrackLocal + Send + Sync>).await?;
+
+        let shared = Arc::new(AudioShared::default());
+        let local_candidates = Arc::new(Mutex::new(Vec::new()));
+        let candidates = local_candidates.clone();
+        peer_connection.on_ice_candidate(Box::new(move |candidate| {
+            let local_candidates = candidates.clone();
+            Box::pin(async move {
+                let Some(candidate) = candidate else {
+                    return;
+                };
+                match candidate.to_json().map_err(BoxError::from).and_then(|init| Ok(serde_json::to_string(&init)?)) {
+                    Ok(json) => local_candidates.lock().unwrap().push(json),
+                    Err(e) => warn!("Failed to serialize the local ICE candidate: {e}"),
+                }
+            })
+        }));
+        let state_shared = shared.clone();
+        peer_connection.on_peer_connection_state_change(Box::new(move |state| {
+            info!("Call connection state: {state}");
+            match state {
+                RTCPeerConnectionState::Connected => start_audio(state_shared.clone(), track.clone(), &runtime_handle),
+                RTCPeerConnectionState::Failed | RTCPeerConnectionState::Closed => state_shared.stop(),
+                _ => {}
+            }
+            Box::pin(async {})
+        }));
+        let track_shared = shared.clone();
+        peer_connection.on_track(Box::new(move |remote, _, _| {
+            let shared = track_shared.clone();
+            Box::pin(async move {
+                // 通話が閉じられると読めなくなって終わる
+                while let Ok((packet, _)) = remote.read_rtp().await {
+                    push_limited(&mut shared.playback.lock().unwrap(), packet.payload.iter().map(|byte| ulaw_decode(*byte)));
+                }
+            })
+        }));
+
+        Ok(Connection { peer_connection, shared, local_candidates, has_remote_description: false })
+    }
+
+    // 前の通話を閉じて新しくつなぐ準備をする
+    fn open(&self, state: &mut BackendState) -> Result<Arc<RTCPeerConnection>, BoxError> {
+        // ICE と STUN は UDP で直接つなぐのでプロキシを通せない。Tor のみのときは通話しない
+        if network::is_tor_only() {
+            return Err(tr!("Tor のみの設定では通話できません").into());
+        }
+        self.close_connection(state);
+        let connection = self.block_on(Self::new_connection(self.runtime.handle().clone()))?;
+        let peer_connection = connection.peer_connection.clone();
+        state.connection = Some(connection);
+        Ok(peer_connection)
+    }
+
+    fn close_connection(&self, state: &mut BackendState) {
+        if let Some(connection) = state.connection.take() {
+            connection.shared.stop();
+            if let Err(e) = self.block_on(connection.peer_connection.close()) {
+                warn!("Failed to close the call connection: {e}");
+            }
+        }
+    }
+
+    fn set_remote_description(&self, state: &mut BackendState, description: RTCSessionDescription) -> Result<(), BoxError> {
+        let connection = state.connection.as_mut().ok_or(tr!("通話の準備ができていません"))?;
+        let peer_connection = connection.peer_connection.clone();
+        self.block_on(peer_connection.set_remote_description(description))?;
+        connection.has_remote_description = true;
+        for candidate in std::mem::take(&mut state.pending_remote_candidates) {
+            if let Err(e) = self.block_on(peer_connection.add_ice_candidate(candidate)) {
+                warn!("Failed to add the ICE candidate: {e}");
+            }
+        }
+        Ok(())
+    }
+}
+
+impl CallMediaBackend for WebRtcMediaBackend {
+    fn is_available(&self) -> bool {
+        !network::is_tor_only()
+    }
+
+    fn create_offer(&self) -> Result<String, BoxError> {
+        let mut state = self.state.lock().unwrap();
+        state.pending_remote_candidates.clear();
+        let peer_connection = self.open(&mut state)?;
+        self.block_on(async {
+            let offer = peer_connection.create_offer(None).await?;
+            peer_connection.set_local_description(offer.clone()).await?;
+            Ok(offer.sdp)
+        })
+    }
+
+    fn create_answer(&self, offer_sdp: &str) -> Result<String, BoxError> {
+        let mut state = self.state.lock().unwrap();
+        let peer_connection = self.open(&mut state)?;
+        self.set_remote_description(&mut state, RTCSessionDescription::offer(offer_sdp.to_string())?)?;
+        self.block_on(async {
+            let answer = peer_connection.create_answer(None).await?;
+            peer_connection.set_local_description(answer.clone()).await?;
+            Ok(answer.sdp)
+        })
+    }
+
+    fn set_answer(&self, answer_sdp: &str) -> Result<(), BoxError> {
+        let mut state = self.state.lock().unwrap();
+        self.set_remote_description(&mut state, RTCSessionDescription::answer(answer_sdp.to_string())?)
+    }
+
+    fn add_ice_candidate(&self, candidate: &str) -> Result<(), BoxError> {
+        let candidate: RTCIceCandidateInit = serde_json::from_str(candidate)?;
+        let mut state = self.state.lock().unwrap();
+        match &state.connection {
+            Some(connection) if connection.has_remote_description => {
+                let peer_connection = connection.peer_connection.clone();
+                self.block_on(peer_connection.add_ice_candidate(candidate))?;
+            }
+            _ => state.pending_remote_candidates.push(candidate),
+        }
+        Ok(())
+    }
+
+    fn take_local_ice_candidates(&self) -> Vec<String> {
+        let state = self.state.lock().unwrap();
+        state
+            .connection
+            .as_ref()
+            .map(|connection| std::mem::take(&mut *connection.local_candidates.lock().unwrap()))
+            .unwrap_or_default()
+    }
+
+    fn close(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.pending_remote_candidates.clear();
+        self.close_connection(&mut state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ulaw_round_trips_within_the_quantization_step() {
+        assert_eq!(ulaw_encode(0), 0xff);
+        assert_eq!(ulaw_decode(0xff), 0);
+        for sample in [1i16, -1, 100, -100, 1000, -1000, 10000, -10000, i16::MAX, i16::MIN] {
+            let decoded = ulaw_decode(ulaw_encode(sample)) as i32;
+            // 大きい音ほど粗く量子化される
+            let step = (sample as i32).abs().min(ULAW_CLIP) / 16 + 8;
+            assert!((decoded - (sample as i32).clamp(-ULAW_CLIP, ULAW_CLIP)).abs() <= step, "{sample} -> {decoded}");
+        }
+    }
+
+    #[test]
+    fn push_limited_drops_the_oldest_samples() {
+        let mut buffer = VecDeque::new();
+        push_limited(&mut buffer, 0..MAX_BUFFERED_SAMPLES as i16);
+        push_limited(&mut buffer, [-1, -2]);
+        assert_eq!(buffer.len(), MAX_BUFFERED_SAMPLES);
+        assert_eq!(buffer.front(), Some(&2));
+        assert_eq!(buffer.back(), Some(&-2));
+    }
+}
@@ -0,0 +1,152 @@
+use eframe::egui;
+
+// Web カメラで QR コードを読み取る。cargo の camera 機能を有効にしたときだけ使える
+// カメラは専用のスレッドで開き、映像を画面に出しながら1コマずつ QR コードを探す
+
+// カメラで読み取れるビルドか
+pub fn is_supported() -> bool {
+    cfg!(feature = "camera")
+}
+
+#[cfg(feature = "camera")]
+mod backend {
+    use super::*;
+    use nokhwa::Camera;
+    use nokhwa::pixel_format::LumaFormat;
+    use nokhwa::utils::{CameraIndex, RequestedFormat, RequestedFormatType};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{mpsc, Arc, Mutex};
+    use std::thread;
+    use tracing::error;
+
+    use crate::i18n::tr;
+    use crate::ui::qr;
+
+    pub struct CameraScan {
+        stop: Arc<AtomicBool>,
+        // いちばん新しいコマ。描くときにテクスチャにする
+        frame: Arc<Mutex<Option<egui::ColorImage>>>,
+        result: mpsc::Receiver<Result<String, Box<dyn std::error::Error + Send + Sync>>>,
+        texture: Option<egui::TextureHandle>,
+    }
+
+    impl CameraScan {
+        // 読み取れたか、カメラが止まったときだけ返す
+        pub fn poll(&self) -> Option<Result<String, Box<dyn std::error::Error + Send + Sync>>> {
+            self.result.try_recv().ok()
+        }
+
+        // カメラの映像を幅 width で描く。まだコマが来ていなければ待っている印を出す
+        pub fn show_preview(&mut self, ui: &mut egui::Ui, width: f32) {
+            if let Some(image) = self.frame.lock().unwrap().take() {
+                match &mut self.texture {
+                    Some(texture) => texture.set(image, egui::TextureOptions::LINEAR),
+                    None => self.texture = Some(ui.ctx().load_texture("camera-preview", image, egui::TextureOptions::LINEAR)),
+                }
+            }
+            match &self.texture {
+                Some(texture) => {
+                    let size = texture.size_vec2();
+                    ui.add(egui::Image::new(texture).fit_to_exact_size(egui::vec2(width, width * size.y / size.x.max(1.0))));
+                }
+                None => {
+                    ui.spinner();
+                }
+            }
+        }
+    }
+
+    // 閉じたら次のコマで止まる
+    impl Drop for CameraScan {
+        fn drop(&mut self) {
+            self.stop.store(true, Ordering::Relaxed);
+        }
+    }
+
+    fn open_camera() -> Result<Camera, Box<dyn std::error::Error + Send + Sync>> {
+        let format = RequestedFormat::new::<LumaFormat>(RequestedFormatType::AbsoluteHighestFrameRate);
+        let mut camera = Camera::new(CameraIndex::Index(0), format)?;
+        camera.open_stream()?;
+        Ok(camera)
+    }
+
+    // 読み取れるか、止められるまでカメラの映像から QR コードを探す
+    // カメラはスレッドをまたげないことがあるので、開くところから専用のスレッドで行う
+    pub fn start_scan(ctx: &egui::Context) -> Result<CameraScan, Box<dyn std::error::Error + Send + Sync>> {
+        let stop = Arc::new(AtomicBool::new(false));
+        let frame = Arc::new(Mutex::new(None));
+        let (result_tx, result) = mpsc::channel();
+        let (ready_tx, ready_rx) = mpsc::channel();
+        let thread_stop = stop.clone();
+        let thread_frame = frame.clone();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            // macOS ではカメラを使う許可を先に求める
+            #[cfg(target_os = "macos")]
+            nokhwa::nokhwa_initialize(|_| {});
+            let mut camera = match open_camera() {
+                Ok(camera) => {
+                    let _ = ready_tx.send(Ok(()));
+                    camera
+                }
+                Err(e) => {
+                    let _ = ready_tx.send(Err(tr!("カメラを開けませんでした: {}", e).into()));
+                    return;
+                }
+            };
+            while !thread_stop.load(Ordering::Relaxed) {
+                let image = match camera.frame().and_then(|buffer| buffer.decode_image::<LumaFormat>()) {
+                    Ok(image) => image,
+                    Err(e) => {
+                        error!("Failed to read a camera frame: {e}");
+                        let _ = result_tx.send(Err(tr!("カメラの映像を読み取れませんでした: {}", e).into()));
+                        break;
+                    }
+                };
+                let size = [image.width() as usize, image.height() as usize];
+                *thread_frame.lock().unwrap() = Some(egui::ColorImage::from_gray(size, image.as_raw()));
+                ctx.request_repaint();
+                // QR コードが写っていないコマは読み飛ばす
+                if let Ok(content) = qr::decode_qr_luma(image) {
+                    let _ = result_tx.send(Ok(content));
+                    ctx.request_repaint();
+                    break;
+                }
+            }
+            if let Err(e) = camera.stop_stream() {
+                error!("Failed to stop the camera: {e}");
+            }
+        });
+        match ready_rx.recv() {
+            Ok(Ok(())) => Ok(CameraScan { stop, frame, result, texture: None }),
+            Ok(Err(e)) => Err(e),
+            Err(_) => Err(tr!("カメラを使えませんでした").into()),
+        }
+    }
+}
+
+#[cfg(not(feature = "camera"))]
+mod backend {
+    use super::*;
+
+    use crate::i18n::tr;
+
+    // このビルドではカメラを使わないので、値を持たない
+    pub enum CameraScan {}
+
+    impl CameraScan {
+        pub fn poll(&self) -> Option<Result<String, Box<dyn std::error::Error + Send + Sync>>> {
+            match *self {}
+        }
+
+        pub fn show_preview(&mut self, _ui: &mut egui::Ui, _width: f32) {
+            match *self {}
+        }
+    }
+
+    pub fn start_scan(_ctx: &egui::Context) -> Result<CameraScan, Box<dyn std::error::Error + Send + Sync>> {
+        Err(tr!("このビルドではカメラを使えません").into())
+    }
+}
+
+pub use backend::{start_scan, CameraScan};
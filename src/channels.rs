@@ -0,0 +1,220 @@
+use eframe::egui;
+use nostr::{
+    Event, EventBuilder, EventId, Filter, Kind, Metadata, PublicKey, Tag, TagStandard, Timestamp,
+    nips::nip10::Marker,
+};
+use nostr_sdk::{Client, RelayPoolNotification};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::error;
+
+use crate::network;
+use crate::signer::AppSigner;
+use crate::types::{ChannelInfo, ChannelMessage, NostrStatusAppInternal};
+
+fn parse_channel_metadata(content: &str) -> Metadata {
+    serde_json::from_str::<Metadata>(content).unwrap_or_default()
+}
+
+// kind 40 のチャンネル作成イベントを ChannelInfo に変換する
+fn to_channel_info(event: &Event) -> ChannelInfo {
+    let metadata = parse_channel_metadata(&event.content);
+    ChannelInfo {
+        id: event.id,
+        creator: event.pubkey,
+        name: metadata.name.unwrap_or_default(),
+        about: metadata.about.unwrap_or_default(),
+        picture: metadata.picture.unwrap_or_default(),
+        created_at: event.created_at,
+    }
+}
+
+fn to_channel_message(my_pubkey: PublicKey, event: &Event) -> ChannelMessage {
+    ChannelMessage {
+        id: event.id,
+        sender: event.pubkey,
+        content: event.content.clone(),
+        created_at: event.created_at,
+        is_mine: event.pubkey == my_pubkey,
+    }
+}
+
+// kind 42 / 41 のイベントが参照しているチャンネルIDを返す
+fn referenced_channel(event: &Event) -> Option<EventId> {
+    event.tags.event_ids().next().copied()
+}
+
+// チャンネルにメッセージを追加する。重複は無視し、時系列順を保つ
+pub fn push_channel_message(
+    channel_messages: &mut HashMap<EventId, Vec<ChannelMessage>>,
+    channel_id: EventId,
+    message: ChannelMessage,
+) -> bool {
+    let messages = channel_messages.entry(channel_id).or_default();
+    if messages.iter().any(|m| m.id == message.id) {
+        return false;
+    }
+    messages.push(message);
+    messages.sort_by_key(|m| m.created_at);
+    true
+}
+
+// 接続中のリレーにあるチャンネル一覧を取得し、作成者によるkind 41の更新を反映する
+pub async fn fetch_channels(
+    client: &Client,
+) -> Result<Vec<ChannelInfo>, Box<dyn std::error::Error + Send + Sync>> {
+    let creation_filter = Filter::new().kind(Kind::ChannelCreation).limit(200);
+    let creation_events = network::fetch_events(client, creation_filter).await?;
+    let mut channels: HashMap<EventId, ChannelInfo> =
+        creation_events.iter().map(|e| (e.id, to_channel_info(e))).collect();
+    if channels.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let metadata_filter = Filter::new()
+        .kind(Kind::ChannelMetadata)
+        .events(channels.keys().copied());
+    let mut metadata_events: Vec<Event> = network::fetch_events(client, metadata_filter)
+        .await?
+        .into_iter()
+        .collect();
+    metadata_events.sort_by_key(|e| e.created_at);
+    for event in metadata_events {
+        let Some(channel) = referenced_channel(&event).and_then(|id| channels.get_mut(&id)) else {
+            continue;
+        };
+        // メタデータを更新できるのはチャンネルの作成者だけ
+        if event.pubkey != channel.creator {
+            continue;
+        }
+        let metadata = parse_channel_metadata(&event.content);
+        if let Some(name) = metadata.name {
+            channel.name = name;
+        }
+        if let Some(about) = metadata.about {
+            channel.about = about;
+        }
+        if let Some(picture) = metadata.picture {
+            channel.picture = picture;
+        }
+    }
+
+    let mut channels: Vec<ChannelInfo> = channels.into_values().collect();
+    channels.sort_by_key(|c| std::cmp::Reverse(c.created_at));
+    Ok(channels)
+}
+
+// 新しいチャンネルを作成する(kind 40)
+pub async fn create_channel(
+    client: &Client,
+    keys: &AppSigner,
+    name: &str,
+    about: &str,
+) -> Result<ChannelInfo, Box<dyn std::error::Error + Send + Sync>> {
+    let mut metadata = Metadata::new().name(name);
+    if !about.is_empty() {
+        metadata = metadata.about(about);
+    }
+    let event = EventBuilder::channel(&metadata).sign(keys).await?;
+    client.send_event(&event).await?;
+    Ok(to_channel_info(&event))
+}
+
+// チャンネルにメッセージを送る(kind 42)
+pub async fn send_channel_message(
+    client: &Client,
+    keys: &AppSigner,
+    channel_id: EventId,
+    content: &str,
+) -> Result<ChannelMessage, Box<dyn std::error::Error + Send + Sync>> {
+    let event = EventBuilder::new(Kind::ChannelMessage, content)
+        .tags([Tag::from_standardized(TagStandard::Event {
+            event_id: channel_id,
+            relay_url: None,
+            marker: Some(Marker::Root),
+            public_key: None,
+            uppercase: false,
+        })])
+        .sign(keys)
+        .await?;
+    client.send_event(&event).await?;
+    Ok(to_channel_message(keys.public_key(), &event))
+}
+
+// チャンネル内のユーザーをミュートしたことを公開する(kind 44)
+pub async fn mute_channel_user(
+    client: &Client,
+    keys: &AppSigner,
+    pubkey: PublicKey,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let event = EventBuilder::mute_channel_user(pubkey, None::<String>)
+        .sign(keys)
+        .await?;
+    client.send_event(&event).await?;
+    Ok(())
+}
+
+// 選択中のチャンネルの過去ログを読み込み、新着メッセージを購読し続けるタスク
+// 選択が別のチャンネルに変わったら購読を解除して終了する
+pub async fn run_channel_listener(
+    client: Client,
+    keys: AppSigner,
+    channel_id: EventId,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    ctx: egui::Context,
+) {
+    let since = Timestamp::now();
+    let mut notifications = client.notifications();
+    let messages_filter = Filter::new().kind(Kind::ChannelMessage).event(channel_id);
+
+    match network::fetch_events(&client, messages_filter.clone().limit(200)).await {
+        Ok(events) => {
+            let mut app_data = app_data_arc.lock().unwrap();
+            for event in events.iter() {
+                push_channel_message(&mut app_data.channel_messages, channel_id, to_channel_message(keys.public_key(), event));
+            }
+            ctx.request_repaint();
+        }
+        Err(e) => error!("Failed to fetch channel messages: {e}"),
+    }
+
+    let subscription_id = match client.subscribe(messages_filter.since(since), None).await {
+        Ok(output) => Some(output.val),
+        Err(e) => {
+            error!("Failed to subscribe to channel messages: {e}");
+            None
+        }
+    };
+
+    loop {
+        let still_selected = {
+            let app_data = app_data_arc.lock().unwrap();
+            app_data.is_logged_in && app_data.selected_channel.as_ref().map(|c| c.id) == Some(channel_id)
+        };
+        if !still_selected {
+            break;
+        }
+
+        let notification = match tokio::time::timeout(Duration::from_secs(2), notifications.recv()).await {
+            Err(_) => continue,
+            Ok(Ok(notification)) => notification,
+            Ok(Err(RecvError::Lagged(_))) => continue,
+            Ok(Err(RecvError::Closed)) => break,
+        };
+        if let RelayPoolNotification::Event { event, .. } = notification {
+            if event.kind != Kind::ChannelMessage || referenced_channel(&event) != Some(channel_id) {
+                continue;
+            }
+            let mut app_data = app_data_arc.lock().unwrap();
+            if push_channel_message(&mut app_data.channel_messages, channel_id, to_channel_message(keys.public_key(), &event)) {
+                ctx.request_repaint();
+            }
+        }
+    }
+
+    if let Some(subscription_id) = subscription_id {
+        client.unsubscribe(&subscription_id).await;
+    }
+}
@@ -0,0 +1,212 @@
+use nostr::nips::nip01::Coordinate;
+use nostr::{Alphabet, Event, EventBuilder, EventId, Filter, JsonUtil, Kind, PublicKey, SingleLetterTag, Tag, TagKind};
+use nostr_sdk::Client;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use tracing::{error, info};
+
+use crate::event_check;
+use crate::i18n::tr;
+use crate::network;
+use crate::outbox;
+use crate::replaceable;
+use crate::signer::AppSigner;
+use crate::types::{CommunityInfo, CommunityPost, NostrStatusAppInternal};
+
+// モデレーター付きのコミュニティ (NIP-72)
+// 誰でも投稿でき、コミュニティの作成者かモデレーターが承認 (kind 4550) した投稿だけが表示される
+
+pub const COMMUNITY_DEFINITION_KIND: u16 = 34550;
+const COMMUNITY_APPROVAL_KIND: u16 = 4550;
+// 一覧に出すコミュニティと、1つのコミュニティで読む投稿の件数
+const COMMUNITIES_FETCH_LIMIT: usize = 200;
+const COMMUNITY_POSTS_FETCH_LIMIT: usize = 200;
+
+fn tag_value(event: &Event, name: &str) -> String {
+    event
+        .tags
+        .iter()
+        .find(|tag| tag.kind() == TagKind::custom(name))
+        .and_then(|tag| tag.content())
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn to_community_info(event: &Event) -> CommunityInfo {
+    let d_tag = event.tags.identifier().unwrap_or_default().to_string();
+    // p タグのうち、4 番目に "moderator" と書かれたものがモデレーター
+    let moderators = event
+        .tags
+        .iter()
+        .filter_map(|tag| {
+            let values = tag.as_slice();
+            (values.first().map(String::as_str) == Some("p") && values.get(3).map(String::as_str) == Some("moderator"))
+                .then(|| values.get(1).and_then(|pk| PublicKey::from_hex(pk).ok()))
+                .flatten()
+        })
+        .collect();
+    let name = tag_value(event, "name");
+    CommunityInfo {
+        coordinate: Coordinate::new(Kind::Custom(COMMUNITY_DEFINITION_KIND), event.pubkey).identifier(d_tag.clone()),
+        name: if name.is_empty() { d_tag } else { name },
+        description: tag_value(event, "description"),
+        image: tag_value(event, "image"),
+        moderators,
+        created_at: event.created_at,
+    }
+}
+
+// 接続中のリレーにあるコミュニティを新しい順に取得する
+pub async fn fetch_communities(client: &Client) -> Result<Vec<CommunityInfo>, Box<dyn std::error::Error + Send + Sync>> {
+    let filter = Filter::new().kind(Kind::Custom(COMMUNITY_DEFINITION_KIND)).limit(COMMUNITIES_FETCH_LIMIT);
+    let events = network::fetch_events(client, filter).await?;
+    let mut communities: Vec<CommunityInfo> = replaceable::dedupe_events(events).iter().map(to_community_info).collect();
+    communities.sort_by_key(|community| std::cmp::Reverse(community.created_at));
+    Ok(communities)
+}
+
+// 承認の e タグが指す投稿
+fn approved_post_id(approval: &Event) -> Option<EventId> {
+    approval.tags.event_ids().next().copied()
+}
+
+// コミュニティ宛ての投稿と、モデレーターの承認を取得する。承認されていない投稿も approved = false で返す
+pub async fn fetch_community_posts(
+    client: &Client,
+    community: &CommunityInfo,
+) -> Result<Vec<CommunityPost>, Box<dyn std::error::Error + Send + Sync>> {
+    let coordinate = community.coordinate.to_string();
+    // 今の仕様の投稿は kind 1111 の A タグ、古い仕様の投稿は kind 1 の a タグでコミュニティを指す
+    let comments_filter = Filter::new()
+        .kind(Kind::Comment)
+        .custom_tag(SingleLetterTag::uppercase(Alphabet::A), coordinate.clone())
+        .limit(COMMUNITY_POSTS_FETCH_LIMIT);
+    let notes_filter = Filter::new()
+        .kind(Kind::TextNote)
+        .coordinate(&community.coordinate)
+        .limit(COMMUNITY_POSTS_FETCH_LIMIT);
+    let approvals_filter = Filter::new()
+        .kind(Kind::Custom(COMMUNITY_APPROVAL_KIND))
+        .coordinate(&community.coordinate)
+        .authors(community.moderators.iter().copied().chain([community.coordinate.public_key]))
+        .limit(COMMUNITY_POSTS_FETCH_LIMIT);
+    let (comments, notes, approvals) = tokio::join!(
+        network::fetch_events(client, comments_filter),
+        network::fetch_events(client, notes_filter),
+        network::fetch_events(client, approvals_filter),
+    );
+    let approvals: Vec<Event> = approvals?.into_iter().filter(|approval| community.is_moderator(&approval.pubkey)).collect();
+    let approved_ids: HashSet<EventId> = approvals.iter().filter_map(approved_post_id).collect();
+
+    let mut posts: HashMap<EventId, Event> = comments?.into_iter().chain(notes?).map(|event| (event.id, event)).collect();
+    // リレーに投稿が残っていなくても、承認の本文に入っている投稿を使う
+    for approval in &approvals {
+        let Ok(post) = Event::from_json(&approval.content) else {
+            continue;
+        };
+        if approved_ids.contains(&post.id) && !posts.contains_key(&post.id) {
+            posts.insert(post.id, post);
+        }
+    }
+
+    let mut posts: Vec<CommunityPost> = event_check::keep_valid(posts.into_values())
+        .into_iter()
+        .map(|event| CommunityPost { approved: approved_ids.contains(&event.id), event })
+        .collect();
+    posts.sort_by_key(|post| std::cmp::Reverse(post.event.created_at));
+    posts.truncate(COMMUNITY_POSTS_FETCH_LIMIT);
+    Ok(posts)
+}
+
+fn coordinate_tags(community: &CommunityInfo, uppercase: bool) -> Result<Vec<Tag>, Box<dyn std::error::Error + Send + Sync>> {
+    let (a, k, p) = if uppercase { ("A", "K", "P") } else { ("a", "k", "p") };
+    let kind = COMMUNITY_DEFINITION_KIND.to_string();
+    let owner = community.coordinate.public_key.to_hex();
+    Ok(vec![
+        Tag::parse([a, &community.coordinate.to_string()])?,
+        Tag::parse([k, &kind])?,
+        Tag::parse([p, &owner])?,
+    ])
+}
+
+async fn sign_post(signer: &AppSigner, community: &CommunityInfo, content: &str) -> Result<Event, Box<dyn std::error::Error + Send + Sync>> {
+    // コミュニティへの直接の投稿は、ルートも親もコミュニティ自身を指す
+    let mut tags = coordinate_tags(community, true)?;
+    tags.extend(coordinate_tags(community, false)?);
+    Ok(EventBuilder::new(Kind::Comment, content).tags(tags).sign(signer).await?)
+}
+
+// 承認には投稿そのものを入れておく
+async fn sign_approval(signer: &AppSigner, community: &CommunityInfo, post: &Event) -> Result<Event, Box<dyn std::error::Error + Send + Sync>> {
+    let tags = vec![
+        Tag::coordinate(community.coordinate.clone(), None),
+        Tag::event(post.id),
+        Tag::public_key(post.pubkey),
+        Tag::parse(["k", &post.kind.as_u16().to_string()])?,
+    ];
+    Ok(EventBuilder::new(Kind::Custom(COMMUNITY_APPROVAL_KIND), post.as_json()).tags(tags).sign(signer).await?)
+}
+
+// コミュニティに投稿する (kind 1111)。送れなかった場合も送信キューに残るので、署名できたら true を返す
+pub async fn submit_post(
+    client: Client,
+    signer: AppSigner,
+    community: CommunityInfo,
+    content: String,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+) -> bool {
+    let event = match sign_post(&signer, &community, content.trim()).await {
+        Ok(event) => event,
+        Err(e) => {
+            error!("Failed to sign the community post: {e}");
+            app_data_arc.lock().unwrap().report_error(tr!("コミュニティに投稿できませんでした: {}", e));
+            return false;
+        }
+    };
+
+    let sent = outbox::send_or_queue(&client, event.clone(), &app_data_arc).await;
+    let mut app_data = app_data_arc.lock().unwrap();
+    if app_data.selected_community.as_ref() == Some(&community) {
+        app_data.community_posts.insert(0, CommunityPost { event: event.clone(), approved: false });
+    }
+    if sent {
+        info!("Community post published with event id: {:?}", event.id);
+        if community.is_moderator(&signer.public_key()) {
+            app_data.notify(tr!("コミュニティに投稿しました"));
+        } else {
+            app_data.notify(tr!("コミュニティに投稿しました。モデレーターが承認すると表示されます"));
+        }
+    } else {
+        app_data.report_error(tr!("投稿を送信できませんでした。送信キューに残し、接続が戻ったら再送します"));
+    }
+    true
+}
+
+// 投稿を承認する (kind 4550)
+pub async fn approve_post(
+    client: Client,
+    signer: AppSigner,
+    community: CommunityInfo,
+    post: Event,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+) {
+    let event = match sign_approval(&signer, &community, &post).await {
+        Ok(event) => event,
+        Err(e) => {
+            error!("Failed to sign the community approval: {e}");
+            app_data_arc.lock().unwrap().report_error(tr!("投稿を承認できませんでした: {}", e));
+            return;
+        }
+    };
+
+    let sent = outbox::send_or_queue(&client, event, &app_data_arc).await;
+    let mut app_data = app_data_arc.lock().unwrap();
+    if let Some(entry) = app_data.community_posts.iter_mut().find(|entry| entry.event.id == post.id) {
+        entry.approved = true;
+    }
+    if sent {
+        app_data.notify(tr!("投稿を承認しました"));
+    } else {
+        app_data.report_error(tr!("承認を送信できませんでした。送信キューに残し、接続が戻ったら再送します"));
+    }
+}
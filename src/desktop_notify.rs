@@ -0,0 +1,185 @@
+use eframe::egui;
+use nostr::{Filter, Kind, PublicKey, SubscriptionId, Timestamp};
+use nostr_sdk::{Client, RelayPoolNotification};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::error;
+
+use crate::i18n::tr;
+use crate::signer::AppSigner;
+use crate::types::{NostrStatusAppInternal, Settings};
+
+// 通知に載せる本文の最大文字数
+const MAX_BODY_CHARS: usize = 120;
+
+// 通知の種類。設定でそれぞれ有効・無効を切り替えられる
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationCategory {
+    DirectMessage,
+    Mention,
+    StarredStatus,
+}
+
+impl NotificationCategory {
+    fn is_enabled(self, settings: &Settings) -> bool {
+        match self {
+            NotificationCategory::DirectMessage => settings.notify_direct_messages,
+            NotificationCategory::Mention => settings.notify_mentions,
+            NotificationCategory::StarredStatus => settings.notify_starred_statuses,
+        }
+    }
+}
+
+// デスクトップ通知を出せる OS か。今は Linux (freedesktop の通知サービス) のみ
+pub fn is_supported() -> bool {
+    cfg!(target_os = "linux")
+}
+
+#[cfg(target_os = "linux")]
+fn show_notification(summary: &str, body: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    use dbus::arg::PropMap;
+    use dbus::blocking::Connection;
+
+    let connection = Connection::new_session()?;
+    let proxy = connection.with_proxy(
+        "org.freedesktop.Notifications",
+        "/org/freedesktop/Notifications",
+        Duration::from_secs(5),
+    );
+    let actions: Vec<&str> = Vec::new();
+    // 引数は app_name, replaces_id, app_icon, summary, body, actions, hints, expire_timeout
+    let (_id,): (u32,) = proxy.method_call(
+        "org.freedesktop.Notifications",
+        "Notify",
+        ("N", 0u32, "", summary, body, actions, PropMap::new(), -1i32),
+    )?;
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn show_notification(_summary: &str, _body: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    Ok(())
+}
+
+fn truncate_body(body: &str) -> String {
+    if body.chars().count() <= MAX_BODY_CHARS {
+        body.to_string()
+    } else {
+        format!("{}…", body.chars().take(MAX_BODY_CHARS).collect::<String>())
+    }
+}
+
+// 設定で有効な種類なら OS の通知を出す。ウィンドウを見ているときは出さない
+pub fn notify(app_data: &NostrStatusAppInternal, category: NotificationCategory, author: &PublicKey, body: &str) {
+    show(app_data, None, category, author, body);
+}
+
+// バックグラウンドのアカウント宛ての通知。どのアカウント宛てか分かるようにラベルを付ける
+pub fn notify_for_account(app_data: &NostrStatusAppInternal, account_label: &str, category: NotificationCategory, author: &PublicKey, body: &str) {
+    show(app_data, Some(account_label), category, author, body);
+}
+
+fn show(app_data: &NostrStatusAppInternal, account_label: Option<&str>, category: NotificationCategory, author: &PublicKey, body: &str) {
+    if !is_supported() || app_data.window_focused || !category.is_enabled(&app_data.settings) {
+        return;
+    }
+    let name = app_data.profiles.get(author).cloned().unwrap_or_default().display_name_or_npub(author);
+    let summary = match category {
+        NotificationCategory::DirectMessage => tr!("{} からのメッセージ", name),
+        NotificationCategory::Mention => tr!("{} がメンションしました", name),
+        NotificationCategory::StarredStatus => tr!("{} のステータス", name),
+    };
+    let summary = match account_label {
+        Some(label) => format!("[{label}] {summary}"),
+        None => summary,
+    };
+    let body = truncate_body(body);
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = show_notification(&summary, &body) {
+            error!("Failed to show a desktop notification: {e}");
+        }
+    });
+}
+
+async fn subscribe(client: &Client, filter: Filter, label: &str) -> Option<SubscriptionId> {
+    match client.subscribe(filter, None).await {
+        Ok(output) => Some(output.val),
+        Err(e) => {
+            error!("Failed to subscribe to {label} for notifications: {e}");
+            None
+        }
+    }
+}
+
+// 自分へのメンションと、星を付けた友だちの新しいステータスを購読して通知するタスク
+// 星を付けた友だちが変わったら購読し直す。ログアウトしたら終了する
+pub async fn run_notification_listener(
+    client: Client,
+    keys: AppSigner,
+    session: u64,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    ctx: egui::Context,
+) {
+    let my_public_key = keys.public_key();
+    let since = Timestamp::now();
+    let mut notifications = client.notifications();
+
+    let mentions_filter = Filter::new().kind(Kind::TextNote).pubkey(my_public_key).since(since);
+    let mentions_id = subscribe(&client, mentions_filter, "mentions").await;
+    let mut starred: HashSet<PublicKey> = HashSet::new();
+    let mut starred_id: Option<SubscriptionId> = None;
+
+    loop {
+        let current_starred = {
+            let app_data = app_data_arc.lock().unwrap();
+            if !app_data.is_current_session(session) {
+                break;
+            }
+            app_data.settings.starred_friends.clone()
+        };
+        if current_starred != starred {
+            if let Some(id) = starred_id.take() {
+                client.unsubscribe(&id).await;
+            }
+            if !current_starred.is_empty() {
+                let filter = Filter::new()
+                    .kind(Kind::from(30315))
+                    .authors(current_starred.iter().copied())
+                    .since(Timestamp::now());
+                starred_id = subscribe(&client, filter, "starred statuses").await;
+            }
+            starred = current_starred;
+        }
+
+        let notification = match tokio::time::timeout(Duration::from_secs(2), notifications.recv()).await {
+            Err(_) => continue,
+            Ok(Ok(notification)) => notification,
+            Ok(Err(RecvError::Lagged(_))) => continue,
+            Ok(Err(RecvError::Closed)) => break,
+        };
+        let RelayPoolNotification::Event { subscription_id, event, .. } = notification else {
+            continue;
+        };
+        let category = if Some(&subscription_id) == mentions_id.as_ref() {
+            NotificationCategory::Mention
+        } else if Some(&subscription_id) == starred_id.as_ref() {
+            NotificationCategory::StarredStatus
+        } else {
+            continue;
+        };
+        // 自分の投稿と、ステータスの消去 (空の内容) は通知しない
+        if event.pubkey == my_public_key || event.content.is_empty() || event.is_expired() {
+            continue;
+        }
+        let app_data = app_data_arc.lock().unwrap();
+        notify(&app_data, category, &event.pubkey, &event.content);
+        ctx.request_repaint();
+    }
+
+    for id in [mentions_id, starred_id].into_iter().flatten() {
+        client.unsubscribe(&id).await;
+    }
+    app_data_arc.lock().unwrap().notification_listener_started = false;
+}
@@ -0,0 +1,380 @@
+use eframe::egui;
+use nostr::{
+    Event, EventBuilder, EventId, Filter, NostrSigner, Kind, PublicKey, Tag, Timestamp,
+};
+use nostr_sdk::{Client, RelayPoolNotification};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::error;
+
+use crate::call::{self, CallSignal};
+use crate::desktop_notify::{self, NotificationCategory};
+use crate::gift_wrap;
+use crate::i18n::tr;
+use crate::network;
+use crate::note;
+use crate::private_status;
+use crate::signer::AppSigner;
+use crate::types::{ChatMessage, DmProtocol, NostrStatusAppInternal, TimelinePost};
+
+// 既読をギフトラップで送るときの rumor の種類。NIP-17 のメッセージ (14) に合わせた、このアプリ独自のもの
+const READ_RECEIPT_KIND: u16 = 20014;
+// 入力中を知らせるエフェメラルイベントの種類。リレーには保存されない
+const TYPING_KIND: u16 = 20015;
+// 入力中のイベントはすぐに意味がなくなるので、念のため期限も付ける (NIP-40)
+const TYPING_EXPIRY_SECS: u64 = 30;
+
+// 復号したDMの中身
+pub enum DmEvent {
+    Message(ChatMessage),
+    // 相手がこの時刻までのメッセージを読んだ
+    ReadReceipt(Timestamp),
+    // 通話の合図と、それを送った時刻
+    CallSignal(CallSignal, Timestamp),
+    // フォローセットの人だけに送られたステータス
+    PrivateStatus(Box<TimelinePost>),
+}
+
+// 受信したイベントを復号し、(会話相手, 中身)の組に変換する
+pub async fn decrypt_dm_event(
+    keys: &AppSigner,
+    event: &Event,
+) -> Result<(PublicKey, DmEvent), Box<dyn std::error::Error + Send + Sync>> {
+    let my_pubkey = keys.public_key();
+    match event.kind {
+        Kind::EncryptedDirectMessage => {
+            let peer = if event.pubkey == my_pubkey {
+                event
+                    .tags
+                    .public_keys()
+                    .next()
+                    .copied()
+                    .ok_or(tr!("NIP-04 DMにpタグがありません"))?
+            } else {
+                event.pubkey
+            };
+            let content = keys.nip04_decrypt(&peer, &event.content).await?;
+            Ok((
+                peer,
+                DmEvent::Message(ChatMessage {
+                    id: event.id,
+                    sender: event.pubkey,
+                    content,
+                    created_at: event.created_at,
+                    is_mine: event.pubkey == my_pubkey,
+                    protocol: DmProtocol::Nip04,
+                    emojis: note::emojis_from_tags(event.tags.iter()),
+                    images: note::imeta_image_urls(event.tags.iter()),
+                    audio_clips: note::imeta_audio_clips(event.tags.iter()),
+                }),
+            ))
+        }
+        Kind::GiftWrap => {
+            let (sender, mut rumor) = gift_wrap::unwrap(keys, event).await?;
+            if rumor.kind == Kind::Custom(READ_RECEIPT_KIND) && sender != my_pubkey {
+                return Ok((sender, DmEvent::ReadReceipt(rumor.created_at)));
+            }
+            if rumor.kind == Kind::Custom(call::CALL_SIGNAL_KIND) && sender != my_pubkey {
+                let signal = call::parse_call_signal(&rumor.content)?;
+                return Ok((sender, DmEvent::CallSignal(signal, rumor.created_at)));
+            }
+            if rumor.kind == Kind::from(30315) && sender != my_pubkey {
+                return Ok((sender, DmEvent::PrivateStatus(Box::new(private_status::timeline_post_from_rumor(rumor)))));
+            }
+            if rumor.kind != Kind::PrivateDirectMessage {
+                return Err(tr!("NIP-17 DM以外のギフトラップです").into());
+            }
+            let peer = if sender == my_pubkey {
+                rumor
+                    .tags
+                    .public_keys()
+                    .find(|pk| **pk != my_pubkey)
+                    .copied()
+                    .unwrap_or(my_pubkey)
+            } else {
+                sender
+            };
+            Ok((
+                peer,
+                DmEvent::Message(ChatMessage {
+                    id: rumor.id(),
+                    sender,
+                    content: rumor.content.clone(),
+                    created_at: rumor.created_at,
+                    is_mine: sender == my_pubkey,
+                    protocol: DmProtocol::Nip17,
+                    emojis: note::emojis_from_tags(rumor.tags.iter()),
+                    images: note::imeta_image_urls(rumor.tags.iter()),
+                    audio_clips: note::imeta_audio_clips(rumor.tags.iter()),
+                }),
+            ))
+        }
+        _ => Err(tr!("DMではないイベントです").into()),
+    }
+}
+
+// 会話にメッセージを追加する。重複は無視し、時系列順を保つ
+pub fn push_chat_message(
+    conversations: &mut HashMap<PublicKey, Vec<ChatMessage>>,
+    peer: PublicKey,
+    message: ChatMessage,
+) -> bool {
+    let messages = conversations.entry(peer).or_default();
+    if messages.iter().any(|m| m.id == message.id) {
+        return false;
+    }
+    messages.push(message);
+    messages.sort_by_key(|m| m.created_at);
+    true
+}
+
+// 相手から届いた既読を記録する。より新しいものだけを残す
+fn push_read_receipt(peer_read_at: &mut HashMap<PublicKey, Timestamp>, peer: PublicKey, read_at: Timestamp) -> bool {
+    let current = peer_read_at.entry(peer).or_insert(Timestamp::from(0));
+    if read_at > *current {
+        *current = read_at;
+        true
+    } else {
+        false
+    }
+}
+
+// 相手がNIP-04で送ってきている場合はNIP-04で返信し、それ以外はNIP-17を使う
+pub fn preferred_protocol(messages: &[ChatMessage]) -> DmProtocol {
+    messages
+        .iter()
+        .rev()
+        .find(|m| !m.is_mine)
+        .map_or(DmProtocol::Nip17, |m| m.protocol)
+}
+
+fn dm_filters(my_pubkey: PublicKey, since: Option<Timestamp>) -> Vec<Filter> {
+    let nip04_to_me = Filter::new()
+        .kind(Kind::EncryptedDirectMessage)
+        .pubkey(my_pubkey);
+    let nip04_from_me = Filter::new()
+        .kind(Kind::EncryptedDirectMessage)
+        .author(my_pubkey);
+    let gift_wraps = gift_wrap::filter_for(my_pubkey, since);
+
+    match since {
+        Some(since) => vec![nip04_to_me.since(since), nip04_from_me.since(since), gift_wraps],
+        None => vec![nip04_to_me, nip04_from_me, gift_wraps],
+    }
+}
+
+// 過去のDMを取得する
+pub async fn fetch_dm_history(
+    client: &Client,
+    keys: &AppSigner,
+) -> Result<Vec<(PublicKey, DmEvent)>, Box<dyn std::error::Error + Send + Sync>> {
+    let mut messages = Vec::new();
+    for filter in dm_filters(keys.public_key(), None) {
+        let events = network::fetch_events(client, filter.limit(200)).await?;
+        for event in events {
+            match decrypt_dm_event(keys, &event).await {
+                Ok(message) => messages.push(message),
+                Err(e) => error!("Failed to decrypt DM {}: {e}", event.id),
+            }
+        }
+    }
+    Ok(messages)
+}
+
+// DMに署名し、表示するメッセージと送るイベントを返す。NIP-17の場合は自分宛てのコピーもギフトラップする
+// 本文のカスタム絵文字は emoji タグにして付ける (NIP-30)
+pub async fn sign_dm(
+    keys: &AppSigner,
+    receiver: PublicKey,
+    content: &str,
+    protocol: DmProtocol,
+    my_emojis: &HashMap<String, String>,
+    imeta_tags: Vec<Tag>,
+) -> Result<(ChatMessage, Vec<Event>), Box<dyn std::error::Error + Send + Sync>> {
+    let emoji_tags = note::emoji_tags(content, my_emojis);
+    let emojis = note::emojis_from_tags(emoji_tags.iter());
+    match protocol {
+        // NIP-04 はタグが暗号化されないので、画像の URL がわかる imeta タグは付けない
+        DmProtocol::Nip04 => {
+            let encrypted = keys.nip04_encrypt(&receiver, content).await?;
+            let event = EventBuilder::new(Kind::EncryptedDirectMessage, encrypted)
+                .tag(Tag::public_key(receiver))
+                .tags(emoji_tags)
+                .sign(keys)
+                .await?;
+            let message = ChatMessage {
+                id: event.id,
+                sender: keys.public_key(),
+                content: content.to_string(),
+                created_at: event.created_at,
+                is_mine: true,
+                protocol,
+                emojis,
+                images: Vec::new(),
+                audio_clips: Vec::new(),
+            };
+            Ok((message, vec![event]))
+        }
+        DmProtocol::Nip17 => {
+            let images = note::imeta_image_urls(imeta_tags.iter());
+            let audio_clips = note::imeta_audio_clips(imeta_tags.iter());
+            let mut rumor = EventBuilder::private_msg_rumor(receiver, content)
+                .tags(emoji_tags)
+                .tags(imeta_tags)
+                .build(keys.public_key());
+            let rumor_id = rumor.id();
+            let gift_wraps = gift_wrap::wrap_with_copy(keys, receiver, rumor.clone()).await?;
+            let message = ChatMessage {
+                id: rumor_id,
+                sender: keys.public_key(),
+                content: content.to_string(),
+                created_at: rumor.created_at,
+                is_mine: true,
+                protocol,
+                emojis,
+                images,
+                audio_clips,
+            };
+            Ok((message, gift_wraps))
+        }
+    }
+}
+
+// 会話を読んだことを、最後に読んだメッセージを添えて相手にギフトラップで知らせる
+pub async fn send_read_receipt(
+    client: &Client,
+    keys: &AppSigner,
+    receiver: PublicKey,
+    last_read: EventId,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let rumor = EventBuilder::new(Kind::Custom(READ_RECEIPT_KIND), "")
+        .tags([Tag::public_key(receiver), Tag::event(last_read)])
+        .build(keys.public_key());
+    gift_wrap::send(client, keys, receiver, rumor, None).await
+}
+
+// メッセージを入力中であることを相手に知らせる
+pub async fn send_typing(
+    client: &Client,
+    keys: &AppSigner,
+    receiver: PublicKey,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let expiration = Timestamp::now() + Duration::from_secs(TYPING_EXPIRY_SECS);
+    let event = EventBuilder::new(Kind::Custom(TYPING_KIND), "")
+        .tags([Tag::public_key(receiver), Tag::expiration(expiration)])
+        .sign(keys)
+        .await?;
+    client.send_event(&event).await?;
+    Ok(())
+}
+
+// 過去のDMを読み込んだ後、新着DMを購読し続けるタスク
+pub async fn run_dm_listener(
+    client: Client,
+    keys: AppSigner,
+    session: u64,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    ctx: egui::Context,
+) {
+    let since = Timestamp::now();
+    // 購読開始前に受信口を作っておき、取りこぼしを防ぐ
+    let mut notifications = client.notifications();
+
+    match fetch_dm_history(&client, &keys).await {
+        Ok(history) => {
+            let mut app_data = app_data_arc.lock().unwrap();
+            for (peer, dm_event) in history {
+                match dm_event {
+                    DmEvent::Message(message) => {
+                        push_chat_message(&mut app_data.conversations, peer, message);
+                    }
+                    DmEvent::ReadReceipt(read_at) => {
+                        push_read_receipt(&mut app_data.dm_peer_read_at, peer, read_at);
+                    }
+                    // 過去の通話の合図は使わない
+                    DmEvent::CallSignal(..) => {}
+                    DmEvent::PrivateStatus(post) => {
+                        private_status::push_private_status(&mut app_data, *post);
+                    }
+                }
+            }
+            ctx.request_repaint();
+        }
+        Err(e) => error!("Failed to fetch DM history: {e}"),
+    }
+
+    let typing_filter = Filter::new().kind(Kind::Custom(TYPING_KIND)).pubkey(keys.public_key()).since(since);
+    for filter in dm_filters(keys.public_key(), Some(since)).into_iter().chain([typing_filter]) {
+        if let Err(e) = client.subscribe(filter, None).await {
+            error!("Failed to subscribe to DMs: {e}");
+        }
+    }
+
+    loop {
+        let notification = match notifications.recv().await {
+            Ok(notification) => notification,
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => break,
+        };
+        if let RelayPoolNotification::Event { event, .. } = notification {
+            if event.kind == Kind::Custom(TYPING_KIND) {
+                if event.pubkey != keys.public_key() && !event.is_expired() {
+                    let mut app_data = app_data_arc.lock().unwrap();
+                    // アカウントを切り替えた後はこのクライアントがバックグラウンドで残るので、ここでも確かめる
+                    if !app_data.is_current_session(session) {
+                        break;
+                    }
+                    app_data.dm_peer_typing.insert(event.pubkey, Instant::now());
+                    ctx.request_repaint();
+                }
+                continue;
+            }
+            if event.kind != Kind::EncryptedDirectMessage && event.kind != Kind::GiftWrap {
+                continue;
+            }
+            match decrypt_dm_event(&keys, &event).await {
+                Ok((peer, dm_event)) => {
+                    let mut app_data = app_data_arc.lock().unwrap();
+                    // ログアウト後に届いたイベントは捨てて終了する
+                    if !app_data.is_current_session(session) {
+                        break;
+                    }
+                    match dm_event {
+                        DmEvent::Message(message) => {
+                            let incoming = (!message.is_mine).then(|| message.content.clone());
+                            if push_chat_message(&mut app_data.conversations, peer, message) {
+                                // メッセージが届いたら入力中の表示は消す
+                                app_data.dm_peer_typing.remove(&peer);
+                                if let Some(content) = incoming {
+                                    desktop_notify::notify(&app_data, NotificationCategory::DirectMessage, &peer, &content);
+                                }
+                                ctx.request_repaint();
+                            }
+                        }
+                        DmEvent::ReadReceipt(read_at) => {
+                            if push_read_receipt(&mut app_data.dm_peer_read_at, peer, read_at) {
+                                ctx.request_repaint();
+                            }
+                        }
+                        DmEvent::CallSignal(signal, created_at) => {
+                            let is_offer = matches!(signal, CallSignal::Offer { .. });
+                            call::handle_call_signal(&mut app_data, &tokio::runtime::Handle::current(), peer, signal, created_at);
+                            if is_offer && app_data.call.as_ref().is_some_and(|call| call.peer == peer) {
+                                desktop_notify::notify(&app_data, NotificationCategory::DirectMessage, &peer, tr!("📞 着信があります"));
+                            }
+                            ctx.request_repaint();
+                        }
+                        DmEvent::PrivateStatus(post) => {
+                            if private_status::push_private_status(&mut app_data, *post) {
+                                ctx.request_repaint();
+                            }
+                        }
+                    }
+                }
+                Err(e) => error!("Failed to decrypt DM {}: {e}", event.id),
+            }
+        }
+    }
+}
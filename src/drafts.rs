@@ -0,0 +1,71 @@
+use eframe::egui;
+use std::time::Duration;
+use tracing::error;
+
+use crate::cache_db::{DB_DRAFTS, LmdbCache};
+use crate::types::{Drafts, NostrStatusAppInternal};
+
+// 入力欄の内容を保存する間隔
+const AUTOSAVE_INTERVAL_SECS: u64 = 3;
+
+// 保存した下書きを読み込む
+pub fn load_drafts(cache_db: &LmdbCache, pubkey_hex: &str) -> Drafts {
+    match cache_db.read_value::<Drafts>(DB_DRAFTS, pubkey_hex) {
+        Ok(drafts) => drafts.unwrap_or_default(),
+        Err(e) => {
+            error!("Failed to load the drafts: {e}");
+            Drafts::default()
+        }
+    }
+}
+
+fn current_drafts(app_data: &NostrStatusAppInternal) -> Drafts {
+    let dm_peer = if app_data.dm_message_input.is_empty() { None } else { app_data.selected_conversation };
+    Drafts {
+        status: app_data.status_message_input.clone(),
+        note: app_data.note_input.clone(),
+        dm: app_data.dm_message_input.clone(),
+        dm_peer,
+    }
+}
+
+// ステータス・ノート・トークの入力欄の内容を保存する。空なら保存済みのものも空にする
+pub fn save_drafts(app_data: &mut NostrStatusAppInternal) {
+    let Some(signer) = &app_data.my_signer else {
+        return;
+    };
+    let pubkey_hex = signer.public_key().to_string();
+    let drafts = current_drafts(app_data);
+    if let Err(e) = app_data.cache_db.write_value(DB_DRAFTS, &pubkey_hex, &drafts) {
+        error!("Failed to save the drafts: {e}");
+        return;
+    }
+    app_data.saved_drafts = drafts;
+}
+
+// 数秒ごとに、前に保存したときから入力欄が変わっていれば保存する
+// 入力をやめると再描画されないので、まだ保存していないものがあれば次の保存の時刻に起こしてもらう
+pub fn autosave(app_data: &mut NostrStatusAppInternal, ctx: &egui::Context) {
+    if !app_data.is_logged_in || current_drafts(app_data) == app_data.saved_drafts {
+        return;
+    }
+    let interval = Duration::from_secs(AUTOSAVE_INTERVAL_SECS);
+    let elapsed = app_data.drafts_saved_at.elapsed();
+    if elapsed < interval {
+        ctx.request_repaint_after(interval - elapsed);
+        return;
+    }
+    app_data.drafts_saved_at = std::time::Instant::now();
+    save_drafts(app_data);
+}
+
+// ログインしたアカウントの下書きを入力欄に戻す。トークの下書きは書いていた相手との会話を開いておく
+pub fn restore_drafts(app_data: &mut NostrStatusAppInternal, drafts: Drafts) {
+    app_data.status_message_input = drafts.status.clone();
+    app_data.note_input = drafts.note.clone();
+    app_data.dm_message_input = drafts.dm.clone();
+    if drafts.dm_peer.is_some() {
+        app_data.selected_conversation = drafts.dm_peer;
+    }
+    app_data.saved_drafts = drafts;
+}
@@ -1,13 +1,14 @@
-use async_tungstenite::tokio::connect_async;
-use async_tungstenite::tungstenite::Message;
+use async_wsocket::Message;
 use futures_util::{SinkExt, StreamExt};
-use nostr::PublicKey;
+use nostr::{PublicKey, RelayUrl, Url};
 use serde::Deserialize;
 use serde_json::json;
 use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
-use std::time::Duration;
 use tokio::time::timeout;
+use tracing::{error, info};
+
+use crate::network;
 
 #[derive(Deserialize, Debug)]
 pub struct RawNostrEvent {
@@ -36,7 +37,7 @@ pub async fn fetch_emoji_sets(
         let pk_hex_clone = pk_hex.clone();
         async move {
             timeout(
-                Duration::from_secs(10),
+                network::fetch_timeout(),
                 fetch_from_relay(&url, Some(&pk_hex_clone), None)
             ).await
         }
@@ -53,7 +54,7 @@ pub async fn fetch_emoji_sets(
 
     // Stage 2: Fetch emojis from pointers if any were found
     if !all_pointers.is_empty() {
-        println!("Found {} emoji set pointers. Fetching referenced sets...", all_pointers.len());
+        info!("Found {} emoji set pointers. Fetching referenced sets...", all_pointers.len());
 
         let authors: Vec<String> = all_pointers.iter().map(|p| p.pubkey.to_string()).collect();
         let d_tags: Vec<String> = all_pointers.iter().map(|p| p.d_identifier.clone()).collect();
@@ -69,7 +70,7 @@ pub async fn fetch_emoji_sets(
             let filter = secondary_filter.clone();
             async move {
                 timeout(
-                    Duration::from_secs(10),
+                    network::fetch_timeout(),
                     fetch_from_relay(&url, None, Some(filter))
                 ).await
             }
@@ -92,7 +93,15 @@ pub async fn fetch_from_relay(
     primary_pubkey_hex: Option<&str>,
     secondary_filter: Option<serde_json::Value>,
 ) -> Result<(HashMap<String, String>, Vec<EventPointer>), Box<dyn std::error::Error + Send + Sync>> {
-    let (ws_stream, _) = connect_async(url).await.map_err(|e| format!("Connection to {} failed: {}", url, e))?;
+    // ほかのリレーへの接続と同じく、Tor のみの設定とプロキシに従う
+    let relay_url = RelayUrl::parse(url)?;
+    if !network::is_relay_allowed(&relay_url) {
+        return Err(format!("{} is not allowed by the network settings", url).into());
+    }
+    let ws_url = Url::parse(relay_url.as_str())?;
+    let ws_stream = async_wsocket::connect(&ws_url, &network::connection_mode(), network::fetch_timeout())
+        .await
+        .map_err(|e| format!("Connection to {} failed: {}", url, e))?;
     let (mut write, mut read) = ws_stream.split();
 
     let sub_id = format!("emoji-fetch-{}", rand::random::<u32>());
@@ -115,7 +124,7 @@ pub async fn fetch_from_relay(
             let message = match message_result {
                 Ok(m) => m,
                 Err(e) => {
-                    eprintln!("Error reading message from {}: {}", url, e);
+                    error!("Error reading message from {}: {}", url, e);
                     break;
                 }
             };
@@ -129,46 +138,43 @@ pub async fn fetch_from_relay(
                 if let Some(msg_type) = parsed.get(0).and_then(|v| v.as_str()) {
                     match msg_type {
                         "EVENT" => {
-                            if let (Some(sid), Some(event_json)) = (parsed.get(1).and_then(|v| v.as_str()), parsed.get(2)) {
-                                if sid == sub_id {
-                                    if let Ok(event) = serde_json::from_value::<RawNostrEvent>(event_json.clone()) {
-                                        let mut is_emoji_list = false;
-                                        let mut is_pointer_list = false;
-
-                                        if event.kind == 30030 {
-                                            is_emoji_list = true;
-                                        } else if event.kind == 10030 {
-                                            if event.tags.iter().any(|t| t.get(0).map_or(false, |v| v == "d") && t.get(1).map_or(false, |v| v == "emojis")) {
-                                                is_emoji_list = true;
-                                            } else if event.tags.iter().any(|t| t.get(0).map_or(false, |v| v == "a")) {
-                                                is_pointer_list = true;
-                                            }
-                                        }
+                            if let (Some(sid), Some(event_json)) = (parsed.get(1).and_then(|v| v.as_str()), parsed.get(2))
+                                && sid == sub_id
+                                && let Ok(event) = serde_json::from_value::<RawNostrEvent>(event_json.clone()) {
+                                let mut is_emoji_list = false;
+                                let mut is_pointer_list = false;
+
+                                if event.kind == 30030 {
+                                    is_emoji_list = true;
+                                } else if event.kind == 10030 {
+                                    if event.tags.iter().any(|t| t.first().is_some_and(|v| v == "d") && t.get(1).is_some_and(|v| v == "emojis")) {
+                                        is_emoji_list = true;
+                                    } else if event.tags.iter().any(|t| t.first().is_some_and(|v| v == "a")) {
+                                        is_pointer_list = true;
+                                    }
+                                }
 
-                                        if is_emoji_list {
-                                            for tag in &event.tags {
-                                                if tag.len() >= 3 && tag[0] == "emoji" {
-                                                    let shortcode = &tag[1];
-                                                    let image_url = tag[2].clone();
-                                                    let shortcode_key = shortcode.trim_matches(':').to_string();
-                                                    if !shortcode_key.is_empty() {
-                                                        emojis.insert(shortcode_key, image_url);
-                                                    }
-                                                }
+                                if is_emoji_list {
+                                    for tag in &event.tags {
+                                        if tag.len() >= 3 && tag[0] == "emoji" {
+                                            let shortcode = &tag[1];
+                                            let image_url = tag[2].clone();
+                                            let shortcode_key = shortcode.trim_matches(':').to_string();
+                                            if !shortcode_key.is_empty() {
+                                                emojis.insert(shortcode_key, image_url);
                                             }
                                         }
+                                    }
+                                }
 
-                                        if is_pointer_list {
-                                            for tag in &event.tags {
-                                                if tag.len() >= 2 && tag[0] == "a" {
-                                                    let parts: Vec<&str> = tag[1].split(':').collect();
-                                                    if parts.len() == 3 && parts[0] == "30030" {
-                                                        if let Ok(pubkey) = PublicKey::from_str(parts[1]) {
-                                                            let d_identifier = parts[2].to_string();
-                                                            pointers.push(EventPointer { pubkey, d_identifier });
-                                                        }
-                                                    }
-                                                }
+                                if is_pointer_list {
+                                    for tag in &event.tags {
+                                        if tag.len() >= 2 && tag[0] == "a" {
+                                            let parts: Vec<&str> = tag[1].split(':').collect();
+                                            if parts.len() == 3 && parts[0] == "30030"
+                                                && let Ok(pubkey) = PublicKey::from_str(parts[1]) {
+                                                let d_identifier = parts[2].to_string();
+                                                pointers.push(EventPointer { pubkey, d_identifier });
                                             }
                                         }
                                     }
@@ -176,9 +182,8 @@ pub async fn fetch_from_relay(
                             }
                         },
                         "EOSE" => {
-                            if let Some(sid) = parsed.get(1).and_then(|v| v.as_str()) {
-                                if sid == sub_id { break; }
-                            }
+                            if let Some(sid) = parsed.get(1).and_then(|v| v.as_str())
+                                && sid == sub_id { break; }
                         },
                         _ => {}
                     }
@@ -187,8 +192,8 @@ pub async fn fetch_from_relay(
         }
     };
 
-    if timeout(Duration::from_secs(10), read_loop).await.is_err() {
-        eprintln!("Timeout while waiting for messages from {}", url);
+    if timeout(network::fetch_timeout(), read_loop).await.is_err() {
+        error!("Timeout while waiting for messages from {}", url);
     }
 
     let close_message = json!(["CLOSE", sub_id]);
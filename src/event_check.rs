@@ -0,0 +1,56 @@
+use nostr::{Event, EventId};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{LazyLock, Mutex};
+use tracing::warn;
+
+// リレーから届いたイベントをそのまま信用せず、表示する前に id と署名を確かめる
+// 確かめた結果と、そのイベントが届いたリレーを id ごとに覚えておき、投稿の横の印に使う
+
+// 覚えておく件数の上限。超えたら一度すべて忘れる
+const MAX_CHECKED_EVENTS: usize = 50_000;
+
+#[derive(Clone, Debug, Default)]
+pub struct EventCheck {
+    pub valid: bool,
+    // 届いたリレー。データベースから読んだだけのイベントでは空
+    pub relays: BTreeSet<String>,
+}
+
+static CHECKED_EVENTS: LazyLock<Mutex<HashMap<EventId, EventCheck>>> = LazyLock::new(Default::default);
+
+// 署名の検証は重いので、一度確かめた id では本文と id が合っているかだけを見る
+fn check(event: &Event, relay_url: Option<&str>) -> bool {
+    let mut checked = CHECKED_EVENTS.lock().unwrap();
+    if !checked.contains_key(&event.id) && checked.len() >= MAX_CHECKED_EVENTS {
+        checked.clear();
+    }
+    let entry = checked.entry(event.id).or_insert_with(|| {
+        let valid = event.verify().is_ok();
+        if !valid {
+            warn!("Dropped event {} with an invalid id or signature from {}", event.id, relay_url.unwrap_or("the database"));
+        }
+        EventCheck { valid, relays: BTreeSet::new() }
+    });
+    if !entry.valid || !event.verify_id() {
+        return false;
+    }
+    if let Some(relay_url) = relay_url {
+        entry.relays.insert(relay_url.to_string());
+    }
+    true
+}
+
+// リレーから届いたイベントを確かめる。不正なら false を返すので、受け取らずに捨てる
+pub fn check_received(event: &Event, relay_url: &str) -> bool {
+    check(event, Some(relay_url))
+}
+
+// データベースから読んだイベントなど、リレーを通らずに手に入れたものから不正なものを取り除く
+pub fn keep_valid(events: impl IntoIterator<Item = Event>) -> Vec<Event> {
+    events.into_iter().filter(|event| check(event, None)).collect()
+}
+
+// まだ確かめていない (キャッシュから表示しているだけの) イベントでは None
+pub fn event_check(event_id: &EventId) -> Option<EventCheck> {
+    CHECKED_EVENTS.lock().unwrap().get(event_id).cloned()
+}
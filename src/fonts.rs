@@ -0,0 +1,127 @@
+use eframe::egui;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tracing::{error, info};
+
+use crate::{APP_BOLD_FONT, APP_FONT};
+use crate::i18n::tr;
+use crate::types::{Settings, UiFont};
+
+const BUNDLED_FONT_NAME: &str = "LINESeedJP";
+const BUNDLED_BOLD_FONT_NAME: &str = "LINESeedJP-Bold";
+// 太字で表示する部分 (本文の **太字** など) に使うフォント
+const BOLD_FONT_FAMILY: &str = "Bold";
+const SYSTEM_CJK_FONT_NAME: &str = "SystemCJK";
+const CUSTOM_FONT_NAME: &str = "Custom";
+
+// 同梱の LINE Seed JP には中国語・韓国語の字形がないので、OS に入っている CJK フォントを探して後ろに足す
+// 見つかった最初のものを使う
+const SYSTEM_CJK_FONT_CANDIDATES: &[&str] = &[
+    // Linux (Debian / Ubuntu / Fedora / Arch)
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/google-noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/google-noto-sans-cjk-fonts/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/truetype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/truetype/droid/DroidSansFallbackFull.ttf",
+    "/usr/share/fonts/wenquanyi/wqy-microhei/wqy-microhei.ttc",
+    // macOS
+    "/System/Library/Fonts/Hiragino Sans GB.ttc",
+    "/System/Library/Fonts/PingFang.ttc",
+    "/System/Library/Fonts/AppleSDGothicNeo.ttc",
+    // Windows
+    "C:\\Windows\\Fonts\\YuGothR.ttc",
+    "C:\\Windows\\Fonts\\msyh.ttc",
+    "C:\\Windows\\Fonts\\malgun.ttf",
+    "C:\\Windows\\Fonts\\msgothic.ttc",
+];
+
+// 最後に切り替えたフォントの設定。同じなら大きなフォントファイルを読み直さない
+static APPLIED_FONT: Mutex<Option<(UiFont, String)>> = Mutex::new(None);
+
+fn find_system_cjk_font() -> Option<(String, Vec<u8>)> {
+    SYSTEM_CJK_FONT_CANDIDATES.iter().find_map(|path| {
+        let bytes = std::fs::read(path).ok()?;
+        Some((path.to_string(), bytes))
+    })
+}
+
+pub fn bold_font_family() -> egui::FontFamily {
+    egui::FontFamily::Name(BOLD_FONT_FAMILY.into())
+}
+
+// 設定に合わせて、選んだフォントを先頭に、残りを足りない字形の代わりに使う順で並べる
+fn build_font_definitions(settings: &Settings) -> Result<egui::FontDefinitions, String> {
+    let mut fonts = egui::FontDefinitions::default();
+    fonts.font_data.insert(BUNDLED_FONT_NAME.to_owned(), egui::FontData::from_static(APP_FONT).into());
+
+    let has_system_font = match find_system_cjk_font() {
+        Some((path, bytes)) => {
+            info!("Using {path} as the CJK fallback font");
+            fonts.font_data.insert(SYSTEM_CJK_FONT_NAME.to_owned(), Arc::new(egui::FontData::from_owned(bytes)));
+            true
+        }
+        None => {
+            info!("No system CJK font found");
+            false
+        }
+    };
+    fonts.font_data.insert(BUNDLED_BOLD_FONT_NAME.to_owned(), egui::FontData::from_static(APP_BOLD_FONT).into());
+
+    let mut order: Vec<&str> = match settings.ui_font {
+        UiFont::Bundled => vec![BUNDLED_FONT_NAME],
+        UiFont::System if has_system_font => vec![SYSTEM_CJK_FONT_NAME],
+        UiFont::System => return Err(tr!("OS の CJK フォントが見つかりませんでした").to_string()),
+        UiFont::Custom => {
+            let path = settings.custom_font_path.trim();
+            let bytes = std::fs::read(Path::new(path)).map_err(|e| tr!("フォントを読み込めませんでした ({}): {}", path, e))?;
+            fonts.font_data.insert(CUSTOM_FONT_NAME.to_owned(), Arc::new(egui::FontData::from_owned(bytes)));
+            vec![CUSTOM_FONT_NAME]
+        }
+    };
+    for fallback in [BUNDLED_FONT_NAME, SYSTEM_CJK_FONT_NAME] {
+        if !order.contains(&fallback) && fonts.font_data.contains_key(fallback) {
+            order.push(fallback);
+        }
+    }
+
+    // 本文は選んだフォントを優先し、等幅は英数字を等幅フォントのまま、日本語などだけ代わりに使う
+    let proportional = fonts.families.entry(egui::FontFamily::Proportional).or_default();
+    for (i, name) in order.iter().enumerate() {
+        proportional.insert(i, name.to_string());
+    }
+    let monospace = fonts.families.entry(egui::FontFamily::Monospace).or_default();
+    monospace.extend(order.iter().map(|name| name.to_string()));
+    // 太字は同梱のフォントを選んでいるときだけ。ほかのフォントでは太字の代わりに普通の太さで表示する
+    let mut bold: Vec<String> = order.iter().map(|name| name.to_string()).collect();
+    if settings.ui_font == UiFont::Bundled {
+        bold.insert(0, BUNDLED_BOLD_FONT_NAME.to_owned());
+    }
+    fonts.families.insert(bold_font_family(), bold);
+    Ok(fonts)
+}
+
+// フォントを設定に合わせて切り替える。読み込めなければ同梱のフォントに戻してエラーを返す
+pub fn apply_fonts(ctx: &egui::Context, settings: &Settings) -> Result<(), String> {
+    let selected = (settings.ui_font, settings.custom_font_path.trim().to_string());
+    let mut applied = APPLIED_FONT.lock().unwrap();
+    if applied.as_ref() == Some(&selected) {
+        return Ok(());
+    }
+    match build_font_definitions(settings) {
+        Ok(fonts) => {
+            ctx.set_fonts(fonts);
+            *applied = Some(selected);
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to load the selected font: {e}");
+            let fallback = Settings { ui_font: UiFont::Bundled, ..settings.clone() };
+            if let Ok(fonts) = build_font_definitions(&fallback) {
+                ctx.set_fonts(fonts);
+                *applied = Some((UiFont::Bundled, selected.1));
+            }
+            Err(e)
+        }
+    }
+}
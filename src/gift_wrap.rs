@@ -0,0 +1,65 @@
+use nostr::nips::nip59::UnwrappedGift;
+use nostr::{Event, EventBuilder, Filter, Kind, PublicKey, Tag, Timestamp, UnsignedEvent};
+use nostr_sdk::Client;
+use tracing::error;
+
+use crate::i18n::tr;
+use crate::signer::AppSigner;
+
+// ギフトラップ (NIP-59) で包んで送る非公開のイベント。DM、既読、通話の合図、非公開のステータスで使う
+// rumor は署名しないので、送り主と時刻は seal の中にだけあり、リレーからは見えない
+
+// ギフトラップのcreated_atは最大2日程度過去にずらされるため、その分さかのぼって購読する
+const GIFT_WRAP_TIME_SKEW_SECS: u64 = 2 * 24 * 60 * 60;
+
+// 自分宛てのギフトラップの購読条件
+pub fn filter_for(my_pubkey: PublicKey, since: Option<Timestamp>) -> Filter {
+    let filter = Filter::new().kind(Kind::GiftWrap).pubkey(my_pubkey);
+    match since {
+        Some(since) => filter.since(Timestamp::from(since.as_u64().saturating_sub(GIFT_WRAP_TIME_SKEW_SECS))),
+        None => filter,
+    }
+}
+
+// rumor を相手宛てに包んで送る。期限を付けると、リレーはそれ以降ギフトラップを捨ててよい (NIP-40)
+pub async fn send(
+    client: &Client,
+    keys: &AppSigner,
+    receiver: PublicKey,
+    rumor: UnsignedEvent,
+    expiration: Option<Timestamp>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let extra_tags = expiration.map(Tag::expiration);
+    let gift_wrap = EventBuilder::gift_wrap(keys, &receiver, rumor, extra_tags).await?;
+    client.send_event(&gift_wrap).await?;
+    Ok(())
+}
+
+// 相手宛てと、ほかの端末でも読めるように自分宛ての控えをギフトラップする。控えを作れなくても失敗にはしない
+pub async fn wrap_with_copy(
+    keys: &AppSigner,
+    receiver: PublicKey,
+    rumor: UnsignedEvent,
+) -> Result<Vec<Event>, Box<dyn std::error::Error + Send + Sync>> {
+    let mut gift_wraps = vec![EventBuilder::gift_wrap(keys, &receiver, rumor.clone(), None).await?];
+    if receiver != keys.public_key() {
+        match EventBuilder::gift_wrap(keys, &keys.public_key(), rumor, None).await {
+            Ok(copy) => gift_wraps.push(copy),
+            Err(e) => error!("Failed to wrap a copy of the gift wrap: {e}"),
+        }
+    }
+    Ok(gift_wraps)
+}
+
+// 受け取ったギフトラップを開き、(送り主, rumor) を返す
+// rumor の作者が seal の作者と違うものはなりすましなので捨てる
+pub async fn unwrap(keys: &AppSigner, event: &Event) -> Result<(PublicKey, UnsignedEvent), Box<dyn std::error::Error + Send + Sync>> {
+    if event.kind != Kind::GiftWrap {
+        return Err(tr!("ギフトラップではないイベントです").into());
+    }
+    let UnwrappedGift { sender, rumor } = UnwrappedGift::from_gift_wrap(keys, event).await?;
+    if rumor.pubkey != sender {
+        return Err(tr!("rumor の作者と送り主が一致しません").into());
+    }
+    Ok((sender, rumor))
+}
@@ -0,0 +1,107 @@
+use eframe::egui;
+use nostr::{Event, Filter, Kind, PublicKey, Timestamp};
+use nostr_sdk::{Client, RelayPoolNotification};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::error;
+
+use crate::i18n::tr;
+use crate::nostr_client::fetch_profiles_for_pubkeys;
+use crate::replaceable;
+use crate::types::{AppTab, NostrStatusAppInternal};
+
+// 1つのリレーに要求する過去分の件数 (REQ の limit はリレーごとに効く)
+const GLOBAL_FEED_RELAY_LIMIT: usize = 50;
+// 遡る時間
+const GLOBAL_FEED_SINCE_SECS: u64 = 60 * 60;
+// 手元に残す件数の上限。超えたら古いものから捨てる
+const MAX_GLOBAL_EVENTS: usize = 300;
+// 名前の分からない作者のプロフィールをまとめて取得する間隔
+const PROFILE_FETCH_INTERVAL_SECS: u64 = 5;
+
+// 新しい順に並べて加える。重複と上限を超えた分は捨てる。加えたら true を返す
+fn push_global_event(events: &mut Vec<Event>, event: Event) -> bool {
+    if event.content.is_empty() || event.is_expired() || replaceable::is_superseded(events, &event) {
+        return false;
+    }
+    replaceable::remove_older_versions(events, &event);
+    let position = events.partition_point(|e| e.created_at > event.created_at);
+    if position >= MAX_GLOBAL_EVENTS {
+        return false;
+    }
+    events.insert(position, event);
+    events.truncate(MAX_GLOBAL_EVENTS);
+    true
+}
+
+// 作者を絞らずに最近のノートとステータスを購読し続けるタスク
+// グローバルタブを離れるか、設定で無効にしたら購読を解除して終了する
+pub async fn run_global_feed(
+    client: Client,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    ctx: egui::Context,
+) {
+    let mut notifications = client.notifications();
+    let filter = Filter::new()
+        .kinds([Kind::TextNote, Kind::from(30315)])
+        .since(Timestamp::now() - Duration::from_secs(GLOBAL_FEED_SINCE_SECS))
+        .limit(GLOBAL_FEED_RELAY_LIMIT);
+    let subscription_id = match client.subscribe(filter, None).await {
+        Ok(output) => Some(output.val),
+        Err(e) => {
+            error!("Failed to subscribe to the global feed: {e}");
+            app_data_arc.lock().unwrap().report_error(tr!("グローバルフィードを購読できませんでした: {}", e));
+            None
+        }
+    };
+
+    let mut unknown_authors: HashSet<PublicKey> = HashSet::new();
+    let mut last_profile_fetch = Instant::now();
+    while let Some(subscription_id) = &subscription_id {
+        let still_open = {
+            let app_data = app_data_arc.lock().unwrap();
+            app_data.is_logged_in && app_data.settings.global_feed_enabled && app_data.current_tab == AppTab::Global
+        };
+        if !still_open {
+            break;
+        }
+
+        if !unknown_authors.is_empty() && last_profile_fetch.elapsed() >= Duration::from_secs(PROFILE_FETCH_INTERVAL_SECS) {
+            let authors: Vec<PublicKey> = unknown_authors.drain().collect();
+            match fetch_profiles_for_pubkeys(&client, authors).await {
+                Ok(profiles) => {
+                    app_data_arc.lock().unwrap().profiles.extend(profiles);
+                    ctx.request_repaint();
+                }
+                Err(e) => error!("Failed to fetch profiles for the global feed: {e}"),
+            }
+            last_profile_fetch = Instant::now();
+        }
+
+        let notification = match tokio::time::timeout(Duration::from_secs(2), notifications.recv()).await {
+            Err(_) => continue,
+            Ok(Ok(notification)) => notification,
+            Ok(Err(RecvError::Lagged(_))) => continue,
+            Ok(Err(RecvError::Closed)) => break,
+        };
+        if let RelayPoolNotification::Event { subscription_id: received_id, event, .. } = notification {
+            if &received_id != subscription_id {
+                continue;
+            }
+            let mut app_data = app_data_arc.lock().unwrap();
+            if !app_data.profiles.contains_key(&event.pubkey) {
+                unknown_authors.insert(event.pubkey);
+            }
+            if push_global_event(&mut app_data.global_events, *event) {
+                ctx.request_repaint();
+            }
+        }
+    }
+
+    if let Some(subscription_id) = subscription_id {
+        client.unsubscribe(&subscription_id).await;
+    }
+    app_data_arc.lock().unwrap().global_feed_started = false;
+}
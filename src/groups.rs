@@ -0,0 +1,306 @@
+use eframe::egui;
+use nostr::{
+    Alphabet, Event, EventBuilder, Filter, Kind, PublicKey, SingleLetterTag, Tag, TagKind, Timestamp,
+};
+use nostr_sdk::{Client, RelayPoolNotification};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::error;
+
+use crate::audit_log;
+use crate::i18n::tr;
+use crate::network::{self, new_client};
+use crate::signer::AppSigner;
+use crate::types::{GroupInfo, GroupMessage, NostrStatusAppInternal};
+
+// NIP-29で使うイベントの種類
+const KIND_GROUP_CHAT_MESSAGE: u16 = 9;
+const KIND_JOIN_REQUEST: u16 = 9021;
+const KIND_LEAVE_REQUEST: u16 = 9022;
+const KIND_GROUP_METADATA: u16 = 39000;
+const KIND_GROUP_MEMBERS: u16 = 39002;
+
+fn h_tag(group_id: &str) -> Tag {
+    Tag::custom(TagKind::SingleLetter(SingleLetterTag::lowercase(Alphabet::H)), [group_id])
+}
+
+fn tag_value<'a>(event: &'a Event, name: &str) -> Option<&'a str> {
+    event
+        .tags
+        .iter()
+        .map(|tag| tag.as_slice())
+        .find(|values| values.first().map(String::as_str) == Some(name))
+        .and_then(|values| values.get(1).map(String::as_str))
+}
+
+fn has_tag(event: &Event, name: &str) -> bool {
+    event
+        .tags
+        .iter()
+        .any(|tag| tag.as_slice().first().map(String::as_str) == Some(name))
+}
+
+// kind 39000 のグループメタデータを GroupInfo に変換する
+fn parse_group_metadata(relay_url: &str, event: &Event) -> Option<GroupInfo> {
+    let id = tag_value(event, "d")?.to_string();
+    Some(GroupInfo {
+        name: tag_value(event, "name").unwrap_or(&id).to_string(),
+        picture: tag_value(event, "picture").unwrap_or_default().to_string(),
+        about: tag_value(event, "about").unwrap_or_default().to_string(),
+        is_private: has_tag(event, "private"),
+        is_closed: has_tag(event, "closed"),
+        relay_url: relay_url.to_string(),
+        id,
+    })
+}
+
+fn to_group_message(my_pubkey: PublicKey, event: &Event) -> GroupMessage {
+    GroupMessage {
+        id: event.id,
+        sender: event.pubkey,
+        content: event.content.clone(),
+        created_at: event.created_at,
+        is_mine: event.pubkey == my_pubkey,
+    }
+}
+
+// グループにメッセージを追加する。重複は無視し、時系列順を保つ
+pub fn push_group_message(
+    group_messages: &mut HashMap<String, Vec<GroupMessage>>,
+    group_key: &str,
+    message: GroupMessage,
+) -> bool {
+    let messages = group_messages.entry(group_key.to_string()).or_default();
+    if messages.iter().any(|m| m.id == message.id) {
+        return false;
+    }
+    messages.push(message);
+    messages.sort_by_key(|m| m.created_at);
+    true
+}
+
+async fn connect_group_relay(
+    keys: &AppSigner,
+    relay_url: &str,
+) -> Result<Client, Box<dyn std::error::Error + Send + Sync>> {
+    let client = new_client(keys.clone());
+    client.add_relay(relay_url).await?;
+    client.try_connect_relay(relay_url, Duration::from_secs(10)).await?;
+    Ok(client)
+}
+
+// リレーが公開しているグループの一覧を取得する
+pub async fn fetch_group_directory(
+    keys: &AppSigner,
+    relay_url: &str,
+) -> Result<Vec<GroupInfo>, Box<dyn std::error::Error + Send + Sync>> {
+    let client = connect_group_relay(keys, relay_url).await?;
+    let filter = Filter::new().kind(Kind::from(KIND_GROUP_METADATA)).limit(500);
+    let events = network::fetch_events(&client, filter).await?;
+    client.shutdown().await;
+
+    let mut groups: Vec<GroupInfo> = events
+        .iter()
+        .filter_map(|event| parse_group_metadata(relay_url, event))
+        .collect();
+    groups.sort_by_key(|g| g.name.to_lowercase());
+    Ok(groups)
+}
+
+// 参加中のグループ一覧(NIP-51 kind 10009)を取得する
+pub async fn fetch_joined_groups(
+    client: &Client,
+    keys: &AppSigner,
+) -> Result<Vec<GroupInfo>, Box<dyn std::error::Error + Send + Sync>> {
+    let filter = Filter::new()
+        .author(keys.public_key())
+        .kind(Kind::SimpleGroups)
+        .limit(1);
+    let events = network::fetch_events(client, filter).await?;
+    let Some(event) = events.into_iter().max_by_key(|e| e.created_at) else {
+        return Ok(Vec::new());
+    };
+
+    let groups = event
+        .tags
+        .iter()
+        .map(|tag| tag.as_slice())
+        .filter(|values| values.first().map(String::as_str) == Some("group") && values.len() >= 3)
+        .map(|values| GroupInfo {
+            id: values[1].clone(),
+            relay_url: values[2].clone(),
+            name: values.get(3).cloned().unwrap_or_else(|| values[1].clone()),
+            ..Default::default()
+        })
+        .collect();
+    Ok(groups)
+}
+
+// 参加中のグループ一覧(kind 10009)を発行する
+pub async fn publish_joined_groups(
+    client: &Client,
+    keys: &AppSigner,
+    groups: &[GroupInfo],
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let tags: Vec<Tag> = groups
+        .iter()
+        .map(|g| {
+            Tag::custom(
+                TagKind::custom("group"),
+                [g.id.clone(), g.relay_url.clone(), g.name.clone()],
+            )
+        })
+        .collect();
+    let event = EventBuilder::new(Kind::SimpleGroups, "")
+        .tags(tags)
+        .sign(keys)
+        .await?;
+    client.send_event(&event).await?;
+    Ok(())
+}
+
+// グループへの参加・退出リクエスト(kind 9021 / 9022)を送る
+pub async fn send_membership_request(
+    keys: &AppSigner,
+    group: &GroupInfo,
+    join: bool,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let kind = if join { KIND_JOIN_REQUEST } else { KIND_LEAVE_REQUEST };
+    let client = connect_group_relay(keys, &group.relay_url).await?;
+    let event = EventBuilder::new(Kind::from(kind), "")
+        .tags([h_tag(&group.id)])
+        .sign(keys)
+        .await?;
+    let result = client.send_event(&event).await;
+    client.shutdown().await;
+    result?;
+    Ok(())
+}
+
+// グループにメッセージ(kind 9)を送る
+pub async fn send_group_message(
+    client: &Client,
+    keys: &AppSigner,
+    group: &GroupInfo,
+    content: &str,
+) -> Result<GroupMessage, Box<dyn std::error::Error + Send + Sync>> {
+    let event = EventBuilder::new(Kind::from(KIND_GROUP_CHAT_MESSAGE), content)
+        .tags([h_tag(&group.id)])
+        .sign(keys)
+        .await?;
+    client.send_event_to([group.relay_url.as_str()], &event).await?;
+    Ok(to_group_message(keys.public_key(), &event))
+}
+
+// 選択中のグループのメタデータ・メンバー・過去ログを読み込み、新着メッセージを購読し続けるタスク
+// 選択が別のグループに変わったら終了する
+pub async fn run_group_listener(
+    keys: AppSigner,
+    group: GroupInfo,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    ctx: egui::Context,
+) {
+    let group_key = group.key();
+    let client = match connect_group_relay(&keys, &group.relay_url).await {
+        Ok(client) => client,
+        Err(e) => {
+            error!("Failed to connect to group relay {}: {e}", group.relay_url);
+            let mut app_data = app_data_arc.lock().unwrap();
+            app_data.group_error = Some(tr!("グループのリレーに接続できませんでした: {}", e));
+            ctx.request_repaint();
+            return;
+        }
+    };
+    app_data_arc.lock().unwrap().group_client = Some(client.clone());
+
+    let since = Timestamp::now();
+    let mut notifications = client.notifications();
+
+    let metadata_filter = Filter::new()
+        .kind(Kind::from(KIND_GROUP_METADATA))
+        .identifier(&group.id);
+    let members_filter = Filter::new()
+        .kind(Kind::from(KIND_GROUP_MEMBERS))
+        .identifier(&group.id);
+    let messages_filter = Filter::new()
+        .kind(Kind::from(KIND_GROUP_CHAT_MESSAGE))
+        .custom_tag(SingleLetterTag::lowercase(Alphabet::H), &group.id);
+
+    let metadata = network::fetch_events(&client, metadata_filter)
+        .await
+        .ok()
+        .and_then(|events| events.first().and_then(|e| parse_group_metadata(&group.relay_url, e)));
+    let members: Vec<PublicKey> = match network::fetch_events(&client, members_filter).await {
+        Ok(events) => events
+            .first()
+            .map(|e| e.tags.public_keys().copied().collect())
+            .unwrap_or_default(),
+        Err(e) => {
+            error!("Failed to fetch group members: {e}");
+            Vec::new()
+        }
+    };
+    let history = network::fetch_events(&client, messages_filter.clone().limit(200))
+        .await;
+
+    {
+        let mut app_data = app_data_arc.lock().unwrap();
+        if let Some(metadata) = metadata {
+            for joined in app_data.joined_groups.iter_mut().filter(|g| g.key() == group_key) {
+                *joined = metadata.clone();
+            }
+            if app_data.selected_group.as_ref().map(GroupInfo::key) == Some(group_key.clone()) {
+                app_data.selected_group = Some(metadata);
+            }
+        }
+        app_data.group_members.insert(group_key.clone(), members);
+        match history {
+            Ok(events) => {
+                let my_pubkey = keys.public_key();
+                for event in events.iter() {
+                    push_group_message(&mut app_data.group_messages, &group_key, to_group_message(my_pubkey, event));
+                }
+            }
+            Err(e) => error!("Failed to fetch group messages: {e}"),
+        }
+        ctx.request_repaint();
+    }
+
+    if let Err(e) = client.subscribe(messages_filter.since(since), None).await {
+        error!("Failed to subscribe to group messages: {e}");
+    }
+
+    loop {
+        // 一定時間ごとに選択中のグループが変わっていないか確認する
+        let still_selected = {
+            let app_data = app_data_arc.lock().unwrap();
+            app_data.is_logged_in && app_data.selected_group.as_ref().map(GroupInfo::key) == Some(group_key.clone())
+        };
+        if !still_selected {
+            break;
+        }
+
+        let notification = match tokio::time::timeout(Duration::from_secs(2), notifications.recv()).await {
+            Err(_) => continue,
+            Ok(Ok(notification)) => notification,
+            Ok(Err(RecvError::Lagged(_))) => continue,
+            Ok(Err(RecvError::Closed)) => break,
+        };
+        if let RelayPoolNotification::Message { relay_url, message } = &notification {
+            audit_log::record_relay_message(relay_url, message);
+        }
+        if let RelayPoolNotification::Event { event, .. } = notification {
+            if event.kind != Kind::from(KIND_GROUP_CHAT_MESSAGE) {
+                continue;
+            }
+            let mut app_data = app_data_arc.lock().unwrap();
+            if push_group_message(&mut app_data.group_messages, &group_key, to_group_message(keys.public_key(), &event)) {
+                ctx.request_repaint();
+            }
+        }
+    }
+
+    client.shutdown().await;
+}
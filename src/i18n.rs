@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::{LazyLock, RwLock};
+
+use crate::types::Language;
+
+mod en;
+
+// 画面の文字列は日本語のままコードに書き、英語のときは en.rs の表で置き換える
+// 表にない文字列は日本語のまま表示する
+
+static LANGUAGE: RwLock<Language> = RwLock::new(Language::Japanese);
+
+static ENGLISH: LazyLock<HashMap<&'static str, &'static str>> = LazyLock::new(|| en::TRANSLATIONS.iter().copied().collect());
+
+pub fn set_language(language: Language) {
+    *LANGUAGE.write().unwrap() = language;
+}
+
+pub fn language() -> Language {
+    *LANGUAGE.read().unwrap()
+}
+
+pub fn translate(japanese: &'static str) -> &'static str {
+    match language() {
+        Language::Japanese => japanese,
+        Language::English => ENGLISH.get(japanese).copied().unwrap_or(japanese),
+    }
+}
+
+// テンプレートの "{}" を前から順に、"{0}" "{1}" を番号の引数で置き換える
+// 英語では語順が変わるので、訳の方では番号で指定できるようにしておく
+pub fn fill(template: &str, args: &[&dyn Display]) -> String {
+    let mut result = String::with_capacity(template.len());
+    let mut next = 0;
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                result.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                result.push('}');
+            }
+            '{' => {
+                let mut index = String::new();
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        break;
+                    }
+                    index.push(c);
+                }
+                let index = if index.is_empty() {
+                    next += 1;
+                    next - 1
+                } else {
+                    index.parse().unwrap_or(usize::MAX)
+                };
+                if let Some(arg) = args.get(index) {
+                    result.push_str(&arg.to_string());
+                }
+            }
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+// tr!("日本語") は表示する言語の文字列を返す。引数があれば "{}" を埋めた String を返す
+macro_rules! tr {
+    ($japanese:literal) => {
+        $crate::i18n::translate($japanese)
+    };
+    ($japanese:literal, $($arg:expr),+ $(,)?) => {
+        $crate::i18n::fill($crate::i18n::translate($japanese), &[$(&$arg as &dyn std::fmt::Display),+])
+    };
+}
+pub(crate) use tr;
@@ -0,0 +1,952 @@
+// 日本語の文字列 → 英語の文字列
+// "{}" の数はそろえる。語順を変えたいときは "{0}" "{1}" で引数の番号を指定する
+pub const TRANSLATIONS: &[(&str, &str)] = &[
+    // accounts.rs
+    ("このアカウントの鍵は保存されていません", "No key is stored for this account"),
+    ("今のパスフレーズが違います", "The current passphrase is incorrect"),
+    ("保存されている鍵がログイン中のアカウントと一致しません", "The stored key does not match the logged-in account"),
+    ("暗号化し直した鍵を確認できませんでした", "Could not verify the re-encrypted key"),
+    ("設定ファイルを書き換えられませんでした: {}", "Could not rewrite the config file: {}"),
+    ("キーチェーンに鍵を保存できませんでした: {}", "Could not save the key to the keychain: {}"),
+    ("パスフレーズが違います", "Incorrect passphrase"),
+    ("アカウント {}", "Account {}"),
+    ("ログインしてください", "Please log in"),
+    ("アカウントを切り替えられませんでした: {}", "Could not switch accounts: {}"),
+    ("取り消せる間だった {} 件は、次にログインしたときに送信します", "{} held send(s) will be sent the next time you log in"),
+    // argon2id.rs
+    ("Argon2id のパラメータが不正です", "Invalid Argon2id parameters"),
+    ("Argon2id のメモリ量が少なすぎます", "The Argon2id memory cost is too low"),
+    // audit_log.rs
+    ("書き出し先のフォルダが見つかりません", "Could not find a folder to export to"),
+    // badges.rs
+    ("バッジの授与に署名できませんでした: {}", "Could not sign the badge award: {}"),
+    ("バッジを贈りました", "Badge awarded"),
+    ("バッジの授与を送信できませんでした。送信キューに残し、接続が戻ったら再送します", "Could not send the badge award. It stays in the outbox and will be resent when the connection is back"),
+    // call.rs
+    ("この環境では音声通話に対応していません", "Voice calls are not supported on this system"),
+    ("通話を始められませんでした: {}", "Could not start the call: {}"),
+    ("通話に応答できませんでした: {}", "Could not answer the call: {}"),
+    ("通話をつなげませんでした: {}", "Could not connect the call: {}"),
+    ("相手が通話を断りました", "The call was declined"),
+    ("通話が終了しました", "The call has ended"),
+    ("応答がありませんでした", "No answer"),
+    // call/webrtc_backend.rs
+    ("スピーカーが見つかりません", "No speaker was found"),
+    ("このスピーカーの形式には対応していません", "This speaker's sample format is not supported"),
+    ("通話の準備ができていません", "The call is not ready yet"),
+    ("Tor のみの設定では通話できません", "Calls are not available in Tor-only mode"),
+    // camera.rs
+    ("カメラを開けませんでした: {}", "Could not open the camera: {}"),
+    ("カメラの映像を読み取れませんでした: {}", "Could not read the camera image: {}"),
+    ("カメラを使えませんでした", "Could not use the camera"),
+    ("このビルドではカメラを使えません", "This build cannot use a camera"),
+    // communities.rs
+    ("コミュニティに投稿できませんでした: {}", "Could not post to the community: {}"),
+    ("コミュニティに投稿しました", "Posted to the community"),
+    ("コミュニティに投稿しました。モデレーターが承認すると表示されます", "Posted to the community. It will appear once a moderator approves it"),
+    ("投稿を送信できませんでした。送信キューに残し、接続が戻ったら再送します", "Could not send the post. It stays in the outbox and will be resent when the connection is back"),
+    ("投稿を承認できませんでした: {}", "Could not approve the post: {}"),
+    ("投稿を承認しました", "Post approved"),
+    ("承認を送信できませんでした。送信キューに残し、接続が戻ったら再送します", "Could not send the approval. It stays in the outbox and will be resent when the connection is back"),
+    // desktop_notify.rs
+    ("{} からのメッセージ", "Message from {}"),
+    ("{} がメンションしました", "{} mentioned you"),
+    ("{} のステータス", "Status from {}"),
+    // dm.rs
+    ("NIP-04 DMにpタグがありません", "The NIP-04 DM has no p tag"),
+    ("NIP-17 DM以外のギフトラップです", "The gift wrap is not a NIP-17 DM"),
+    ("DMではないイベントです", "The event is not a DM"),
+    ("📞 着信があります", "📞 Incoming call"),
+    // fonts.rs
+    ("OS の CJK フォントが見つかりませんでした", "No system CJK font was found"),
+    ("フォントを読み込めませんでした ({}): {}", "Could not load the font ({}): {}"),
+    // gift_wrap.rs
+    ("ギフトラップではないイベントです", "The event is not a gift wrap"),
+    ("rumor の作者と送り主が一致しません", "The rumor author does not match the sender"),
+    // global_feed.rs
+    ("グローバルフィードを購読できませんでした: {}", "Could not subscribe to the global feed: {}"),
+    // groups.rs
+    ("グループのリレーに接続できませんでした: {}", "Could not connect to the group relay: {}"),
+    // labels.rs
+    ("ラベルに署名できませんでした: {}", "Could not sign the label: {}"),
+    ("ラベルを付けました", "Label added"),
+    ("ラベルを送信できませんでした。送信キューに残し、接続が戻ったら再送します", "Could not send the label. It stays in the outbox and will be resent when the connection is back"),
+    // main.rs
+    ("プロフィールを取得中...", "Fetching profile..."),
+    // media.rs
+    (
+        "画像 (png, jpg, gif, webp) か音声 (m4a, mp3, ogg, wav) のファイルを選んでください",
+        "Choose an image (png, jpg, gif, webp) or audio (m4a, mp3, ogg, wav) file",
+    ),
+    ("Blossom サーバーの応答に URL がありません", "The Blossom server response has no URL"),
+    // network.rs
+    ("Torのみの設定のため .onion 以外のリレーには接続しません", "Tor-only mode is on, so non-.onion relays are not used"),
+    ("プロキシのアドレスが不正です ({}): {}", "Invalid proxy address ({}): {}"),
+    ("プロキシのアドレスを解決できません: {}", "Could not resolve the proxy address: {}"),
+    (
+        "Torのみを有効にするにはプロキシ (例: 127.0.0.1:9050) を指定してください",
+        "Set a proxy (e.g. 127.0.0.1:9050) to enable Tor-only mode",
+    ),
+    // nostr_client.rs
+    (
+        "NIP-65リレーリストを取得するためにDiscoverリレーに並列接続中...\n",
+        "Connecting to discover relays in parallel to fetch the NIP-65 relay list...\n",
+    ),
+    ("  Discoverリレー追加: {}\n", "  Added discover relay: {}\n"),
+    ("  Discoverリレー追加失敗: {} - エラー: {}\n", "  Failed to add discover relay: {} - error: {}\n"),
+    ("NIP-65リレーリストイベントを検索中 (最大{}秒)..\n", "Searching for the NIP-65 relay list event (up to {} seconds)..\n"),
+    ("NIP-65イベント検索タイムアウト。\n", "Timed out searching for the NIP-65 event.\n"),
+    ("NIP-65リレーリストイベントを受信しました。\n", "Received the NIP-65 relay list event.\n"),
+    ("--- NIP-65で受信したリレー情報 ---\n", "--- Relays received via NIP-65 ---\n"),
+    ("  有効なNIP-65リレーは受信しませんでした。\n", "  No valid NIP-65 relays were received.\n"),
+    ("\nNIP-65で検出されたリレーに並列接続中...\n", "\nConnecting to the NIP-65 relays in parallel...\n"),
+    ("  リレー追加: {}\n", "  Added relay: {}\n"),
+    ("  リレー追加失敗 - エラー: {}\n", "  Failed to add relay - error: {}\n"),
+    (
+        "\nNIP-65リレーリストが見つからなかったため、デフォルトのリレーに並列接続します。\n",
+        "\nNo NIP-65 relay list was found, connecting to the default relays in parallel.\n",
+    ),
+    ("  デフォルトリレー追加: {}\n", "  Added default relay: {}\n"),
+    ("  デフォルトリレー追加失敗: {} - エラー: {}\n", "  Failed to add default relay: {} - error: {}\n"),
+    ("\n手動で登録したリレーを反映中...\n", "\nApplying manually added relays...\n"),
+    ("  手動リレー無効: {}\n", "  Manual relay disabled: {}\n"),
+    ("  手動リレー追加: {}\n", "  Added manual relay: {}\n"),
+    ("  手動リレー追加失敗: {} - エラー: {}\n", "  Failed to add manual relay: {} - error: {}\n"),
+    ("接続できるリレーがありません。", "There are no relays to connect to."),
+    ("\n--- 現在接続中のリレー ({}件) ---\n", "\n--- Connected relays ({}) ---\n"),
+    ("{}\n\n--- 現在接続中のリレー ---\n{}", "{}\n\n--- Connected relays ---\n{}"),
+    ("リレーURLが正しくありません: {} ({})", "Invalid relay URL: {} ({})"),
+    ("公開するリレーがありません", "There are no relays to publish"),
+    ("リレーリストを公開できませんでした: {} / {}", "Could not publish the relay list: {} / {}"),
+    // note.rs
+    ("ノートに署名できませんでした: {}", "Could not sign the note: {}"),
+    ("ノートを投稿しました", "Note posted"),
+    ("ノートを投稿します", "Posting the note"),
+    (
+        "ノートを送信できませんでした。送信キューに残し、接続が戻ったら再送します",
+        "Could not send the note. It stays in the outbox and will be resent when the connection is back",
+    ),
+    ("返信に署名できませんでした: {}", "Could not sign the reply: {}"),
+    ("リポストする投稿が見つかりませんでした", "Could not find the post to repost"),
+    ("リポストに署名できませんでした: {}", "Could not sign the repost: {}"),
+    ("リポストしました", "Reposted"),
+    (
+        "リポストを送信できませんでした。送信キューに残し、接続が戻ったら再送します",
+        "Could not send the repost. It stays in the outbox and will be resent when the connection is back",
+    ),
+    // outbox.rs
+    ("接続中のリレーがありません", "No relays are connected"),
+    ("送信を取り消しました", "Sending undone"),
+    // pins.rs
+    ("ピン留めを保存できませんでした: {}", "Could not save the pins: {}"),
+    // pow.rs
+    ("PoW の計算を中止しました", "PoW computation was cancelled"),
+    // private_status.rs
+    ("フォローセットを取得できませんでした: {}", "Could not fetch the follow sets: {}"),
+    ("非公開のステータスを送れませんでした", "Could not send the private status"),
+    ("非公開のステータスは {} 人中 {} 人にだけ送れました", "The private status reached only {1} of {0} people"),
+    ("「{}」の {} 人にステータスを送りました", "Sent the status to {1} people in \"{0}\""),
+    // profile_card.rs
+    ("カードの画像を作れませんでした", "Could not create the card image"),
+    ("保存先のフォルダが見つかりません", "Could not find a folder to save to"),
+    // report.rs
+    ("通報に署名できませんでした: {}", "Could not sign the report: {}"),
+    ("通報しました", "Reported"),
+    (
+        "通報を送信できませんでした。送信キューに残し、接続が戻ったら再送します",
+        "Could not send the report. It stays in the outbox and will be resent when the connection is back",
+    ),
+    // settings_sync.rs
+    ("設定をほかの端末と共有できませんでした: {}", "Could not share the settings with your other devices: {}"),
+    // sign_gate.rs
+    ("プロフィール", "Profile"),
+    ("ノート", "Note"),
+    ("フォローリスト", "Follow list"),
+    ("削除", "Deletion"),
+    ("リポスト", "Repost"),
+    ("リアクション", "Reaction"),
+    ("トークの封筒 (シール)", "Chat envelope (seal)"),
+    ("ファイルのメッセージ", "File message"),
+    ("通報", "Report"),
+    ("ZAP のリクエスト", "Zap request"),
+    ("ミュートリスト", "Mute list"),
+    ("リレーリスト", "Relay list"),
+    ("ブックマーク", "Bookmarks"),
+    ("トークのリレー", "Chat relays"),
+    ("リレーへのログイン", "Relay login"),
+    ("リモート署名者への接続", "Remote signer connection"),
+    ("フォローセット", "Follow set"),
+    ("アプリのデータ", "App data"),
+    ("ステータス", "Status"),
+    // status_history.rs
+    ("ステータスに署名できませんでした: {}", "Could not sign the status: {}"),
+    ("ステータスを公開しました", "Status published"),
+    ("ステータスを公開します", "Publishing the status"),
+    (
+        "ステータスを送信できませんでした。送信キューに残し、接続が戻ったら再送します",
+        "Could not send the status. It stays in the outbox and will be resent when the connection is back",
+    ),
+    ("以前のステータスを公開し直しました", "Republished the earlier status"),
+    ("ステータスの消去に署名できませんでした: {}", "Could not sign the status removal: {}"),
+    ("ステータスを消去しました", "Status cleared"),
+    (
+        "ステータスの消去を送信できませんでした。送信キューに残し、接続が戻ったら再送します",
+        "Could not send the status removal. It stays in the outbox and will be resent when the connection is back",
+    ),
+    // tray.rs
+    ("未読のトーク {} 件", "{} unread chat messages"),
+    ("定型文は設定で追加できます", "Add status presets in the settings"),
+    ("ステータスを変更", "Change status"),
+    ("ウィンドウを表示", "Show window"),
+    ("ウィンドウを隠す", "Hide window"),
+    ("アプリを終了", "Quit"),
+    // voice.rs
+    ("録音に失敗しました", "Recording failed"),
+    ("マイクが見つかりません", "No microphone was found"),
+    ("このマイクの形式には対応していません", "This microphone's sample format is not supported"),
+    ("音を録れませんでした", "Nothing was recorded"),
+    ("このビルドでは録音できません", "Recording is not available in this build"),
+    // ui.rs
+    ("ホーム", "Home"),
+    ("検索", "Search"),
+    ("グローバル", "Global"),
+    ("友だち", "Friends"),
+    ("トーク", "Chats"),
+    ("グループ", "Groups"),
+    ("チャンネル", "Channels"),
+    ("コミュニティ", "Communities"),
+    ("リレー", "Relays"),
+    ("ウォレット", "Wallet"),
+    ("設定", "Settings"),
+    ("ログ", "Logs"),
+    ("署名の記録", "Signing log"),
+    ("リレー通信", "Relay traffic"),
+    ("今すぐロック", "Lock now"),
+    ("テーマ: ライト", "Theme: Light"),
+    ("テーマ: ダーク", "Theme: Dark"),
+    ("テーマ: システムに合わせる", "Theme: Follow system"),
+    ("なう", "Now"),
+    ("投稿する", "Post"),
+    // ui/account_switcher.rs
+    ("＋ アカウントを追加", "+ Add account"),
+    ("バックグラウンドで接続中。パスフレーズなしで切り替えられます", "Connected in the background. You can switch without a passphrase"),
+    ("パスフレーズ", "Passphrase"),
+    ("切り替え", "Switch"),
+    ("キャンセル", "Cancel"),
+    ("切断", "Disconnect"),
+    ("バックグラウンドの接続を切る", "Close the background connection"),
+    ("切り替え中...", "Switching..."),
+    // ui/attachment.rs
+    ("画像や音声を添付", "Attach an image or audio"),
+    ("画像か音声のファイルのパス (ウィンドウにドロップしても入ります)", "Path to an image or audio file (you can also drop it on the window)"),
+    ("アップロード", "Upload"),
+    ("アップロード中...", "Uploading..."),
+    (
+        "このビルドでは録音できません。録音した音声ファイルを添付すると、ボイスメッセージとして送れます",
+        "Recording is not available in this build. Attach a recorded audio file to send it as a voice message",
+    ),
+    ("🎤 録音", "🎤 Record"),
+    ("■ 止めて添付", "■ Stop and attach"),
+    ("録音を始められませんでした: {}", "Could not start recording: {}"),
+    ("ファイルをアップロードしました", "File uploaded"),
+    ("ファイルをアップロードできませんでした: {}", "Could not upload the file: {}"),
+    // ui/audit_log_view.rs
+    ("🔄 再読み込み", "🔄 Reload"),
+    ("クリップボードにコピー", "Copy to clipboard"),
+    ("ファイルに書き出す", "Export to file"),
+    ("イベントの ID をコピー", "Copy the event ID"),
+    ("まだ署名したイベントはありません", "No events have been signed yet"),
+    (
+        "このアプリが署名したイベントは、公開したかどうかにかかわらずすべてここに残ります",
+        "Every event this app signs is recorded here, whether or not it was published",
+    ),
+    ("リレーの応答なし", "No relay response"),
+    ("受理", "Accepted"),
+    ("拒否", "Rejected"),
+    ("署名の記録をコピーしました", "Copied the signing log"),
+    ("署名の記録を読み込めませんでした: {}", "Could not read the signing log: {}"),
+    ("{} に書き出しました", "Exported to {}"),
+    ("署名の記録を書き出せませんでした: {}", "Could not export the signing log: {}"),
+    // ui/badge_dialog.rs
+    ("バッジを贈る", "Award badge"),
+    ("自分のバッジを読み込み中...", "Loading your badges..."),
+    ("まだバッジを作っていません。バッジ (NIP-58) を作成できるアプリで定義を公開すると、ここから贈れます", "You have not created any badges yet. Publish a badge definition (NIP-58) from an app that can create badges to award it from here"),
+    ("贈る", "Award"),
+    ("授与は公開されます。相手がプロフィールに表示すると、バッジとして見えるようになります", "Awards are public. The badge appears once the recipient shows it on their profile"),
+    ("{} にバッジを贈ります", "Award a badge to {}"),
+    ("バッジを読み込めませんでした: {}", "Could not load your badges: {}"),
+    // ui/bookmarks_view.rs
+    ("Keep に保存", "Save to Keep"),
+    ("Keep から外す", "Remove from Keep"),
+    ("Keep に保存しました", "Saved to Keep"),
+    ("Keep から外しました", "Removed from Keep"),
+    ("記事", "Articles"),
+    ("記事を取得できませんでした: {}", "Could not fetch articles: {}"),
+    ("記事が見つかりませんでした", "The article was not found"),
+    ("(タイトルなし)", "(Untitled)"),
+    ("読む", "Read"),
+    ("({} に更新)", "(updated {})"),
+    ("記事を読む", "Read the article"),
+    ("← 記事の一覧に戻る", "← Back to articles"),
+    ("フォローしている人が書いた長文の記事 (NIP-23) です", "Long-form articles (NIP-23) written by people you follow"),
+    ("フォローしている人の記事はまだありません", "No articles from people you follow yet"),
+    ("ブックマークを更新できませんでした: {}", "Could not update the bookmarks: {}"),
+    ("Keep の投稿を取得できませんでした: {}", "Could not fetch the posts in Keep: {}"),
+    ("読み込み中...", "Loading..."),
+    ("保存した投稿はまだありません。タイムラインの 🔖 で保存できます。", "No saved posts yet. Save posts with 🔖 on the timeline."),
+    ("すべて", "All"),
+    ("件はリレーで見つかりませんでした", "could not be found on the relays"),
+    // ui/call_view.rs
+    ("音声通話", "Voice call"),
+    ("着信中…", "Incoming call…"),
+    ("呼び出し中…", "Calling…"),
+    ("通話中", "In call"),
+    ("📞 応答", "📞 Answer"),
+    ("終了", "End"),
+    ("この環境では音声の送受信ができません", "Audio cannot be sent or received on this system"),
+    // ui/channels_view.rs
+    ("チャンネル一覧の取得に失敗しました: {}", "Failed to fetch the channel list: {}"),
+    ("更新", "Refresh"),
+    ("新しいチャンネル", "New channel"),
+    ("チャンネル名", "Channel name"),
+    ("説明 (任意)", "Description (optional)"),
+    ("作成", "Create"),
+    ("非表示のチャンネルも表示", "Show hidden channels"),
+    ("チャンネルが見つかりません。", "No channels found."),
+    ("非表示", "Hide"),
+    ("再表示", "Unhide"),
+    ("左のリストからチャンネルを選択してください。", "Select a channel from the list on the left."),
+    ("このユーザーをミュート", "Mute this user"),
+    ("ミュート中のユーザー", "Muted users"),
+    ("解除", "Unmute"),
+    ("メッセージを入力", "Type a message"),
+    ("送信", "Send"),
+    ("チャンネルの作成に失敗しました: {}", "Failed to create the channel: {}"),
+    ("作成者: {}", "Created by: {}"),
+    ("送信に失敗しました: {}", "Failed to send: {}"),
+    ("チャンネルにメッセージを送信できませんでした: {}", "Could not send the message to the channel: {}"),
+    ("ミュートの公開に失敗しました: {}", "Failed to publish the mute: {}"),
+    // ui/chats_view.rs
+    ("フォロー中の人とつながりのない人からのトークです。返信するとリストに移ります", "Chats from people with no connection to the people you follow. Replying moves them to the list"),
+    ("メッセージリクエスト {}件", "Message requests ({})"),
+    ("メッセージを送信します", "Sending the message"),
+    ("ボイスメッセージ", "Voice message"),
+    ("再生", "Play"),
+    ("停止", "Stop"),
+    ("既定のプレイヤーで再生", "Play in the default player"),
+    ("既読", "Read"),
+    ("たった今", "just now"),
+    ("{}分前", "{}m ago"),
+    ("{}時間前", "{}h ago"),
+    ("昨日 {}", "yesterday {}"),
+    ("npub1... またはhex公開鍵", "npub1... or hex public key"),
+    ("トークを開始", "Start chat"),
+    ("まだトークはありません。", "No chats yet."),
+    ("左のリストからトークを選択してください。", "Select a chat from the list on the left."),
+    (" が入力中…", " is typing…"),
+    ("絵文字だけを送るとスタンプとして大きく表示されます", "A message with a single emoji is shown large as a sticker"),
+    ("📌 ピン留め", "📌 Pin"),
+    ("ピン留めを外す", "Unpin"),
+    ("公開鍵が不正です: {}", "Invalid public key: {}"),
+    ("メッセージを送信できませんでした: {}", "Could not send the message: {}"),
+    (
+        "メッセージを送信できませんでした。送信キューに残し、接続が戻ったら再送します",
+        "Could not send the message. It stays in the outbox and will be resent when the connection is back",
+    ),
+    // ui/communities_view.rs
+    ("コミュニティ一覧の取得に失敗しました: {}", "Failed to fetch communities: {}"),
+    ("コミュニティの投稿を取得できませんでした: {}", "Could not fetch the community posts: {}"),
+    ("コミュニティが見つかりません。", "No communities found."),
+    ("左のリストからコミュニティを選択してください。", "Select a community from the list on the left."),
+    ("モデレーター", "Moderator"),
+    ("承認待ちの投稿も表示", "Show posts awaiting approval"),
+    ("承認待ち", "Awaiting approval"),
+    ("承認", "Approve"),
+    ("承認された投稿はまだありません", "No approved posts yet"),
+    ("コミュニティに投稿", "Post to the community"),
+    ("投稿はモデレーターが承認すると、ほかの人にも表示されます", "Posts become visible to others once a moderator approves them"),
+    ("作成者: {}・モデレーター {}人", "Created by {} · {} moderators"),
+    // ui/content_warning.rs
+    ("注意書きを付ける", "Add a content warning"),
+    ("理由 (例: ネタバレ)", "Reason (e.g. spoilers)"),
+    ("⚠ 注意書きのある投稿", "⚠ Post with a content warning"),
+    ("内容を表示", "Show content"),
+    ("隠す", "Hide"),
+    // ui/emoji.rs
+    ("カスタム絵文字が設定されていません。", "No custom emoji are set up."),
+    // ui/emoji_picker.rs
+    ("絵文字を入力", "Insert emoji"),
+    ("絵文字を検索 (例: smile、笑顔)", "Search emoji (e.g. smile)"),
+    ("最近使った絵文字", "Recently used"),
+    // ui/friends_view.rs
+    ("フォローリストの更新に失敗しました: {}", "Failed to update the follow list: {}"),
+    ("まだ誰もフォローしていません。", "You are not following anyone yet."),
+    ("ステータスなし", "No status"),
+    ("フォロー", "Follow"),
+    ("アンフォロー", "Unfollow"),
+    ("星を付けると新しいステータスを通知します", "Star to be notified of new statuses"),
+    ("星を外す", "Unstar"),
+    ("お気に入りにするとリストの先頭に並びます", "Favorites are listed at the top"),
+    ("お気に入りから外す", "Remove from favorites"),
+    ("📷 QR を読み取る", "📷 Scan QR"),
+    ("🖼 QR を読み取る", "🖼 Scan QR"),
+    ("📷 カメラで読み取る", "📷 Scan with camera"),
+    ("カメラを止める", "Stop camera"),
+    (
+        "QR コードの画像ファイルを選ぶか、ウィンドウにドロップしてください。読み取った内容 (nostr:npub1... など) を貼り付けることもできます",
+        "Choose an image file of a QR code or drop it on the window. You can also paste the scanned contents (nostr:npub1... etc.)",
+    ),
+    ("QR コードの画像ファイルのパス", "Path to the QR code image file"),
+    ("画像から読み取る", "Read from image"),
+    ("読み取る", "Read"),
+    ("QR コードの相手", "Person from the QR code"),
+    ("フォローしてトーク", "Follow and chat"),
+    ("フォロー中", "Following"),
+    ("閉じる", "Close"),
+    ("QR コードの内容を読み取れませんでした: {}", "Could not read the QR code contents: {}"),
+    ("設定の保存に失敗しました: {}", "Failed to save the settings: {}"),
+    // ui/global_view.rs
+    ("フォロー中の人とつながりのない人の投稿", "Post from someone with no connection to the people you follow"),
+    (
+        "接続中のリレーに届いた最近のノートとステータスです。フォローしていない人も含みます",
+        "Recent notes and statuses from the connected relays, including people you don't follow",
+    ),
+    ("投稿を待っています...", "Waiting for posts..."),
+    // ui/groups_view.rs
+    ("参加中グループの更新に失敗しました: {}", "Failed to update your groups: {}"),
+    ("wss://... (NIP-29対応リレー)", "wss://... (NIP-29 relay)"),
+    ("グループを探す", "Find groups"),
+    ("参加中のグループ", "Your groups"),
+    ("参加中のグループはありません。", "You have not joined any groups."),
+    ("グループ一覧", "Group directory"),
+    ("参加", "Join"),
+    ("退出", "Leave"),
+    ("左のリストからグループを選択してください。", "Select a group from the list on the left."),
+    ("メンバー", "Members"),
+    ("参加中グループを取得できませんでした: {}", "Could not fetch your groups: {}"),
+    ("グループ一覧の取得に失敗しました: {}", "Failed to fetch the group directory: {}"),
+    ("グループにメッセージを送信できませんでした: {}", "Could not send the message to the group: {}"),
+    // ui/hashtag_view.rs
+    ("#{} の投稿を取得できませんでした: {}", "Could not fetch posts for #{}: {}"),
+    ("このハッシュタグの投稿は見つかりませんでした", "No posts found for this hashtag"),
+    // ui/home_view.rs
+    ("ラベルを付ける", "Add label"),
+    ("ラベル「{}」", "Label \"{}\""),
+    ("新規投稿", "New post"),
+    ("いまどうしてる？", "What's happening?"),
+    ("公開", "Publish"),
+    ("最新の投稿を取得", "Fetch latest posts"),
+    ("タイムラインに投稿はまだありません。", "No posts on the timeline yet."),
+    ("送信キュー", "Outbox"),
+    ("送信待ち", "Pending"),
+    ("送信済み", "Sent"),
+    ("破棄", "Discard"),
+    ("送信済みを消去", "Clear sent"),
+    ("リレーの応答", "Relay responses"),
+    ("拒否したリレーに再送", "Resend to rejecting relays"),
+    ("ステータスの履歴", "Status history"),
+    ("再公開", "Republish"),
+    ("ステータスを消去", "Clear status"),
+    ("期限:", "Expires:"),
+    ("なし", "None"),
+    ("30分", "30 min"),
+    ("1時間", "1 hour"),
+    ("4時間", "4 hours"),
+    ("指定", "Custom"),
+    (" 分", " min"),
+    ("予約する:", "Schedule:"),
+    ("自動で消去:", "Clear automatically:"),
+    (" 時間後", " hours later"),
+    ("ノートを書く (@名前 でメンション)", "Write a note (@name to mention)"),
+    ("投稿", "Post"),
+    ("予約中", "Scheduled"),
+    ("消去", "Clear"),
+    ("取り消し", "Cancel"),
+    ("今のステータス", "Current status"),
+    ("種類:", "Type:"),
+    ("💬 一般", "💬 General"),
+    ("🎵 音楽", "🎵 Music"),
+    ("🎤 ポッドキャスト", "🎤 Podcast"),
+    ("🏷 カスタム", "🏷 Custom"),
+    ("d タグ (例: gaming)", "d tag (e.g. gaming)"),
+    ("🏷 その他", "🏷 Other"),
+    ("🔒 非公開:", "🔒 Private:"),
+    (
+        "公開せず、選んだフォローセットの人だけにギフトラップで送ります",
+        "Instead of publishing, send it gift-wrapped only to the people in the chosen follow set",
+    ),
+    ("フォローセットを選択", "Choose a follow set"),
+    ("フォローセット (NIP-51) がありません", "No follow sets (NIP-51)"),
+    ("フォローセットを読み込み直す", "Reload follow sets"),
+    ("一部の人だけに届いた非公開のステータス", "Private status sent only to some people"),
+    ("ステータスは{}文字までです", "Statuses can be up to {} characters"),
+    ("カスタムの種類には d タグを入力してください", "Enter a d tag for the custom type"),
+    ("予約の日時には未来の時刻を指定してください", "The scheduled time must be in the future"),
+    ("予約の日時は「2025-01-31 18:30」の形式で入力してください", "Enter the scheduled time as \"2025-01-31 18:30\""),
+    ("非公開のステータスは予約や自動の消去に対応していません", "Private statuses cannot be scheduled or cleared automatically"),
+    ("送り先のフォローセットを選んでください", "Choose a follow set to send to"),
+    ("ステータスの公開を予約しました", "Status scheduled"),
+    ("カスタム絵文字", "Custom emoji"),
+    ("絵文字を選択", "Choose an emoji"),
+    ("音楽ステータスを設定", "Set music status"),
+    ("曲名", "Track"),
+    ("URL（任意）", "URL (optional)"),
+    ("ステータスを設定", "Set status"),
+    ("ポッドキャストステータスを設定", "Set podcast status"),
+    ("エピソードのタイトル", "Episode title"),
+    ("コジ10 小島秀夫の『最高の10時にしよう』", "Hideo Kojima's Koji10"),
+    ("{}回試行: {}", "{} attempts: {}"),
+    ("タイムラインを取得できませんでした: {}", "Could not fetch the timeline: {}"),
+    ("更新中...", "Refreshing..."),
+    ("あと {}時間{}分", "{}h {}m left"),
+    ("あと {}分", "{} min left"),
+    ("あと {}秒", "{} s left"),
+    ("引用", "Quote"),
+    ("{} sats をZAP (右クリックで金額を指定)", "Zap {} sats (right-click to choose the amount)"),
+    ("金額を指定してZAP", "Zap a custom amount"),
+    // ui/identity_badge.rs
+    ("証明の投稿を確認済み", "Proof post verified"),
+    ("証明の投稿にこのユーザーの公開鍵が見つかりません", "The proof post does not contain this user's public key"),
+    ("このサービスの証明は確認できません。リンクから投稿を確かめてください", "Proofs on this service cannot be verified. Check the post from the link"),
+    ("証明の投稿を取得できませんでした", "Could not fetch the proof post"),
+    ("証明の投稿を確認中", "Checking the proof post"),
+    ("ユーザー名 (Mastodon は example.social/@user)", "Username (example.social/@user for Mastodon)"),
+    ("Gist・ツイート・投稿の ID", "Gist, tweet or post ID"),
+    ("＋ 追加", "+ Add"),
+    ("証明の文をコピー", "Copy proof text"),
+    ("証明の文を GitHub の Gist や Mastodon などに公開し、その ID を入力してください", "Publish the proof text as a GitHub Gist, Mastodon post or similar, then enter its ID"),
+    // ui/image_cache.rs
+    ("ブラウザで開く", "Open in browser"),
+    // ui/link_view.rs
+    ("参照先の投稿が見つかりませんでした", "The referenced post was not found"),
+    ("参照先の投稿を取得できませんでした: {}", "Could not fetch the referenced post: {}"),
+    // ui/login_view.rs
+    ("アカウントが見つかりません", "Account not found"),
+    ("NWC の自動接続に失敗しました: {}", "NWC auto-connect failed: {}"),
+    ("ウォレット (NWC) に接続できませんでした: {}", "Could not connect to the wallet (NWC): {}"),
+    ("リレーに接続しています...", "Connecting to relays..."),
+    ("プロフィールを読み込みました", "Profile loaded"),
+    ("タイムラインを読み込んでいます...", "Loading the timeline..."),
+    ("データを更新できませんでした: {}", "Failed to refresh data: {}"),
+    ("リレーから最新のデータを取得できませんでした: {}", "Could not fetch the latest data from the relays: {}"),
+    ("リモート署名者の承認ページで許可してください", "Approve the request on the remote signer's page"),
+    ("bunker:// から始まるURIを入力してください", "Enter a URI starting with bunker://"),
+    ("Torのみの設定のため、このリレーは使えません: {}", "This relay cannot be used in Tor-only mode: {}"),
+    ("リモート署名者に接続中...", "Connecting to the remote signer..."),
+    ("リモート署名者に接続しました", "Connected to the remote signer"),
+    ("リモート署名者への接続に失敗しました: {}", "Failed to connect to the remote signer: {}"),
+    ("ログインまたは登録", "Log in or sign up"),
+    ("秘密鍵 (nsec / ncryptsec / シードフレーズ):", "Secret key (nsec / ncryptsec / seed phrase):"),
+    ("nsec1... / ncryptsec1... / 12〜24 個の英単語", "nsec1... / ncryptsec1... / 12 to 24 English words"),
+    ("パスフレーズ:", "Passphrase:"),
+    ("パスワード", "Password"),
+    ("パスフレーズの確認:", "Confirm passphrase:"),
+    ("パスワードを再入力", "Re-enter password"),
+    ("ログイン", "Log in"),
+    ("登録", "Sign up"),
+    ("新しい鍵を生成", "Generate a new key"),
+    (
+        "このシードフレーズは今しか表示されません。失うとアカウントを復元できないので、順番どおり紙に書き留めて安全な場所に保管してください。",
+        "This seed phrase is shown only now. Without it the account cannot be recovered, so write the words down in order and keep them somewhere safe.",
+    ),
+    ("書き留めました", "I've written it down"),
+    ("書き留めたシードフレーズから、次の番号の単語を入力してください", "Enter the words at these positions from the seed phrase you wrote down"),
+    ("✔ シードフレーズを確認しました", "✔ Seed phrase confirmed"),
+    ("単語をもう一度表示", "Show the words again"),
+    ("BIP-39 パスフレーズ (任意):", "BIP-39 passphrase (optional):"),
+    ("設定していなければ空のまま", "Leave empty if you didn't set one"),
+    ("アカウント番号:", "Account number:"),
+    ("名前:", "Name:"),
+    ("プロフィールに表示する名前", "Name shown on your profile"),
+    ("生成した鍵を破棄", "Discard the generated key"),
+    ("アカウント:", "Account:"),
+    ("別のアカウントを追加", "Add another account"),
+    ("追加をやめる", "Stop adding"),
+    ("🔒 ロックしました。続けるにはパスフレーズを入力してください", "🔒 Locked. Enter your passphrase to continue"),
+    ("← {} に戻る", "← Back to {}"),
+    ("ログインに失敗しました: {}", "Login failed: {}"),
+    ("ログインに失敗しました。パスフレーズを確認してください: {}", "Login failed. Check your passphrase: {}"),
+    ("シードフレーズを生成できませんでした: {}", "Could not generate a seed phrase: {}"),
+    ("パスフレーズが一致しません", "Passphrases do not match"),
+    ("リレーからデータを取得できませんでした: {}", "Could not fetch data from the relays: {}"),
+    ("鍵の登録に失敗しました: {}", "Failed to register the key: {}"),
+    ("リモート署名者でログイン (NIP-46)", "Log in with a remote signer (NIP-46)"),
+    (
+        "bunker URI を使うと、秘密鍵をこのアプリに入力せずにログインできます。",
+        "With a bunker URI you can log in without entering your secret key in this app.",
+    ),
+    ("接続", "Connect"),
+    ("前回の署名者に再接続", "Reconnect to the last signer"),
+    ("保存した接続を削除", "Delete the saved connection"),
+    ("承認ページを開く", "Open the approval page"),
+    ("保存済みの接続情報を削除できませんでした: {}", "Could not delete the saved connection: {}"),
+    // ui/logs_view.rs
+    ("表示するレベル:", "Level:"),
+    ("まだログはありません", "No logs yet"),
+    ("不具合を報告するときは、コピーしたログを添えてください", "When reporting a bug, please include the copied logs"),
+    ("ログをコピーしました", "Copied the logs"),
+    // ui/nip05_badge.rs
+    ("NIP-05 で確認済み", "Verified with NIP-05"),
+    ("NIP-05 の公開鍵がこのユーザーと一致しません", "The NIP-05 public key does not match this user"),
+    ("NIP-05 を確認できませんでした", "Could not verify NIP-05"),
+    ("NIP-05 を確認中", "Verifying NIP-05"),
+    // ui/signature_badge.rs
+    ("署名を確認済み (保存済みのイベント)", "Signature verified (stored event)"),
+    ("署名が不正なイベントです", "This event has an invalid signature"),
+    ("署名を未確認 (キャッシュから表示しています)", "Signature not verified yet (shown from cache)"),
+    ("署名を確認済み。受信したリレー:\n{}", "Signature verified. Received from:\n{}"),
+    // ui/notes_view.rs
+    ("知らない人の返信 {}件", "{} replies from strangers"),
+    ("ノートを取得できませんでした: {}", "Could not fetch notes: {}"),
+    ("コメントを書く", "Add a comment"),
+    ("「{}」", "\"{}\""),
+    ("返信", "Reply"),
+    ("🔁 {} がリポスト", "🔁 Reposted by {}"),
+    ("返信 {}件", "{} replies"),
+    ("フォロー中のユーザーのノートはまだありません。", "No notes from the people you follow yet."),
+    ("返信を書く", "Write a reply"),
+    ("返信する", "Reply"),
+    ("{} への返信: {}", "Reply to {}: {}"),
+    // ui/profile_view.rs
+    ("プロフィールを保存", "Save profile"),
+    ("ログアウト", "Log out"),
+    ("署名方式", "Signing method"),
+    ("リモート署名者 (NIP-46)", "Remote signer (NIP-46)"),
+    ("このアプリに保存した秘密鍵", "Secret key stored in this app"),
+    ("接続状態を確認", "Check connection"),
+    ("暗号化した秘密鍵 (ncryptsec) をコピー", "Copy the encrypted secret key (ncryptsec)"),
+    ("鍵の保存先:", "Key storage:"),
+    ("設定ファイル", "Config file"),
+    ("OSのキーチェーン", "OS keychain"),
+    ("秘密鍵 (nsec) を書き出す", "Export the secret key (nsec)"),
+    ("相手のアプリでこの QR コードを読み取ると、友だちに追加できます", "Scan this QR code in another app to add you as a friend"),
+    ("プロフィールカードを PNG で保存", "Save profile card as PNG"),
+    ("外部アカウント (NIP-39)", "External identities (NIP-39)"),
+    ("プロフィール情報", "Profile"),
+    ("自己紹介:", "About:"),
+    ("画像URL:", "Picture URL:"),
+    ("プロフィールを保存しました！", "Profile saved!"),
+    ("プロフィールを保存しました", "Profile saved"),
+    ("プロフィールの保存に失敗しました: {}", "Failed to save the profile: {}"),
+    ("プロフィールの保存中にエラー: {}", "Error while saving the profile: {}"),
+    ("公開鍵とログアウト", "Public key and logout"),
+    ("あなたの公開鍵 (npub)", "Your public key (npub)"),
+    ("クリックしてコピー", "Click to copy"),
+    ("コピー", "Copy"),
+    ("署名者のリレーに接続中: {}/{}", "Connected to signer relays: {}/{}"),
+    ("暗号化された鍵を読み出せませんでした: {}", "Could not read the encrypted key: {}"),
+    ("保存先を変更できませんでした: {}", "Could not change the key storage: {}"),
+    ("{} に保存しました", "Saved to {}"),
+    ("プロフィールカードを保存できませんでした: {}", "Could not save the profile card: {}"),
+    ("秘密鍵を書き出す", "Export secret key"),
+    ("秘密鍵を表示するには、パスフレーズを入力してください", "Enter your passphrase to show the secret key"),
+    ("表示する", "Show"),
+    ("確認中...", "Checking..."),
+    (
+        "この秘密鍵を知っている人は、あなたとして投稿やトークができます。ほかの人に見せないでください。",
+        "Anyone who knows this secret key can post and chat as you. Do not show it to anyone.",
+    ),
+    ("モバイルの署名アプリで読み取れます", "Can be scanned by a mobile signer app"),
+    ("秒後に隠します", "seconds until hidden"),
+    ("表示できませんでした: {}", "Could not show it: {}"),
+    // ui/qr.rs
+    ("画像に QR コードが見つかりません", "No QR code was found in the image"),
+    ("QR コードを読み取れませんでした", "Could not read the QR code"),
+    // ui/relay_debug_view.rs
+    ("送受信を記録する", "Record sent and received messages"),
+    ("リレー:", "Relay:"),
+    ("含む文字列", "Contains"),
+    ("まだ記録はありません", "Nothing recorded yet"),
+    ("記録を有効にすると、リレーとの REQ・EVENT・EOSE・OK・NOTICE などをそのまま表示します", "When recording is on, raw REQ, EVENT, EOSE, OK, NOTICE and other relay messages are shown here"),
+    ("記録は最新の数千件だけを残し、長いメッセージは途中で切ります", "Only the latest few thousand messages are kept, and long messages are cut off"),
+    ("(長いため省略)", "(truncated)"),
+    ("記録をコピーしました", "Copied the recorded messages"),
+    // ui/relays_view.rs
+    ("現在の接続", "Current connections"),
+    ("再接続", "Reconnect"),
+    ("リレーリストを編集", "Edit relay list"),
+    ("あなたのリレーリスト (NIP-65)", "Your relay list (NIP-65)"),
+    ("リレーを追加", "Add relay"),
+    ("読み取り", "Read"),
+    ("書き込み", "Write"),
+    ("手動で追加したリレー", "Manually added relays"),
+    (
+        "NIP-65のリレーリストとあわせて接続します。変更は再接続で反映されます",
+        "Used together with the NIP-65 relay list. Changes take effect when you reconnect",
+    ),
+    ("(無効)", "(disabled)"),
+    ("発見リレーとデフォルトリレーは「設定」タブで変更できます", "Discover and default relays can be changed in the Settings tab"),
+    ("リレーリストを公開", "Publish relay list"),
+    ("リレーの状態を取得しています...", "Fetching relay status..."),
+    ("状態", "Status"),
+    ("最終受信", "Last received"),
+    ("遅延", "Latency"),
+    ("受信イベント数", "Events received"),
+    ("接続中", "Connected"),
+    ("接続処理中", "Connecting"),
+    ("リレーに接続できませんでした: {}", "Could not connect to the relays: {}"),
+    ("{} は登録済みです", "{} is already added"),
+    ("リレーURLが正しくありません: {}", "Invalid relay URL: {}"),
+    ("リレーの保存に失敗しました: {}", "Failed to save the relays: {}"),
+    ("リレーリストを公開しました ({}件)", "Published the relay list ({} relays)"),
+    ("公開に失敗しました: {}", "Failed to publish: {}"),
+    ("リレーリストを公開できませんでした: {}", "Could not publish the relay list: {}"),
+    // ui/report_dialog.rs
+    ("スパム", "Spam"),
+    ("なりすまし", "Impersonation"),
+    ("違法な内容", "Illegal content"),
+    ("暴言・ヘイト", "Profanity or hate"),
+    ("性的な内容", "Sexual content"),
+    ("マルウェア", "Malware"),
+    ("その他", "Other"),
+    ("このユーザーを通報します", "Report this user"),
+    ("この投稿を通報します", "Report this post"),
+    ("理由:", "Reason:"),
+    ("補足 (任意)", "Details (optional)"),
+    ("通報する", "Report"),
+    (
+        "通報は公開され、モデレーションに対応したリレーやクライアントが参考にします",
+        "Reports are public and used by relays and clients that support moderation",
+    ),
+    // ui/search_view.rs
+    (
+        "検索リレーで検索できませんでした。手元のキャッシュから探します: {}",
+        "Could not search the search relays. Searching the local cache instead: {}",
+    ),
+    ("キーワード", "Keywords"),
+    ("🔍 検索", "🔍 Search"),
+    ("検索リレー (NIP-50) の結果", "Results from search relays (NIP-50)"),
+    ("手元のキャッシュから探した結果", "Results from the local cache"),
+    ("ユーザー", "Users"),
+    ("ノート・ステータス", "Notes and statuses"),
+    ("見つかりませんでした", "Nothing found"),
+    ("検索中...", "Searching..."),
+    ("「{}」: {}", "\"{}\": {}"),
+    // ui/settings_view.rs
+    ("表示", "Display"),
+    ("テーマ:", "Theme:"),
+    ("ライト", "Light"),
+    ("ダーク", "Dark"),
+    ("システムに合わせる", "Follow system"),
+    ("配色:", "Colors:"),
+    ("クラシック", "Classic"),
+    ("LINE 風 (白と緑)", "LINE style (white and green)"),
+    ("言語:", "Language:"),
+    ("日本語", "日本語"),
+    ("フォント:", "Font:"),
+    ("LINE Seed JP (同梱)", "LINE Seed JP (bundled)"),
+    ("OS の CJK フォント", "System CJK font"),
+    ("ファイルを指定", "Choose a file"),
+    (
+        "選んだフォントにない文字 (中国語・韓国語など) は、ほかのフォントで表示します。保存すると切り替わります",
+        "Characters missing from the chosen font (Chinese, Korean, etc.) are drawn with other fonts. Takes effect when saved",
+    ),
+    ("画面の拡大率:", "UI scale:"),
+    ("文字の大きさ:", "Text size:"),
+    ("画面の拡大率は、つまみを離すと反映されます", "The UI scale is applied when you release the slider"),
+    ("プレビュー:", "Previews:"),
+    ("本文中の画像とリンクのプレビューを表示する", "Show previews of images and links in posts"),
+    ("オフにすると、画像やリンク先のページを読み込みません", "When off, images and linked pages are not loaded"),
+    ("書式:", "Formatting:"),
+    ("本文の太字・斜体・コード・箇条書きを表示する", "Show bold, italics, code and bullet lists in posts"),
+    ("切ると、記号を含めて書いたままの本文を表示します", "When off, posts are shown as written, including the symbols"),
+    ("発見リレー (他ユーザーを見つけるため)", "Discover relays (to find other users)"),
+    ("検索リレー (NIP-50。空欄なら手元のキャッシュから探します)", "Search relays (NIP-50; leave empty to search the local cache)"),
+    ("デフォルトリレー (フォールバック用)", "Default relays (fallback)"),
+    ("通信", "Network"),
+    ("リレーの応答待ち時間 (秒):", "Relay timeout (seconds):"),
+    ("リレーリストを探す時間 (秒):", "Relay list discovery timeout (seconds):"),
+    ("接続を待つ時間 (秒):", "Connection wait (seconds):"),
+    ("取得に失敗したときにやり直す回数:", "Retries when a fetch fails:"),
+    ("タイムラインの取得件数:", "Timeline fetch limit:"),
+    ("グローバルフィード:", "Global feed:"),
+    ("タブを表示する", "Show the tab"),
+    ("フォローしていない人も含めた最近のノートとステータスを表示します", "Shows recent notes and statuses, including from people you don't follow"),
+    ("投稿のPoW難易度 (NIP-13):", "PoW difficulty for posts (NIP-13):"),
+    ("0で無効。値を大きくすると投稿に時間がかかります。計算は通知から中止できます", "0 disables it. Higher values make posting slower. You can cancel the work from the notification"),
+    ("送信を取り消せる時間:", "Undo send window:"),
+    ("すぐ送る", "Send immediately"),
+    ("ステータス・ノート・トークを送る前に待ち、その間は通知から取り消せます", "Waits before sending statuses, notes and chat messages so you can undo them from the notification"),
+    ("{}秒", "{}s"),
+    ("タイムスタンプの証明 (NIP-03):", "Timestamp proofs (NIP-03):"),
+    ("公開したステータスを OpenTimestamps で記録する", "Timestamp published statuses with OpenTimestamps"),
+    ("ステータスの ID をカレンダーサーバーに送り、ビットコインのブロックに記録されたら (数時間後) 証明を公開します", "Sends the status ID to a calendar server and publishes the proof once it is recorded in a Bitcoin block (after a few hours)"),
+    ("リレーと通信の設定は次回の接続から反映されます", "Relay and network settings take effect from the next connection"),
+    ("プロキシ", "Proxy"),
+    ("SOCKS5プロキシ (host:port):", "SOCKS5 proxy (host:port):"),
+    ("例: Torなら 127.0.0.1:9050。空欄なら直接接続します", "e.g. 127.0.0.1:9050 for Tor. Leave empty to connect directly"),
+    ("Torのみ (.onion 以外のリレーには接続しない)", "Tor only (do not connect to non-.onion relays)"),
+    (
+        "プロキシの設定は保存後に作られる接続から反映されます。すべてに反映するには再ログインしてください",
+        "Proxy settings apply to connections made after saving. Log in again to apply them everywhere",
+    ),
+    ("定型文", "Quick phrases"),
+    ("新規投稿の入力欄の上に並び、押すだけで入力できます", "Shown above the new post box; click one to insert it"),
+    ("例: 移動中", "e.g. On the move"),
+    ("追加", "Add"),
+    ("ミュートする語", "Muted words"),
+    ("1行に1つ。この語を含む投稿はタイムラインやフィードに表示しません", "One per line. Posts containing these words are hidden from the timeline and feeds"),
+    ("ラベル (NIP-32)", "Labels (NIP-32)"),
+    ("参考にする人 (npub か hex で1行に1つ)", "Trusted labelers (one npub or hex per line)"),
+    ("表示しないラベル", "Labels to hide"),
+    ("本文を隠すラベル", "Labels to collapse"),
+    ("参考にする人と自分が投稿や投稿者に付けたラベル (例: nsfw, spam) に合わせて、タイムラインの投稿を隠したり畳んだりします。ラベルは1行に1つ", "Timeline posts are hidden or collapsed based on labels (e.g. nsfw, spam) that you or your trusted labelers put on posts or their authors. One label per line"),
+    ("知らない人 (Web of Trust)", "Strangers (web of trust)"),
+    ("フォロー中の人とつながりのない人の返信・投稿を畳み、トークはリクエストに分ける", "Collapse replies and posts from people with no connection to the people you follow, and move their chats to requests"),
+    ("知り合いとして扱う人数:", "Follows needed to trust:"),
+    ("フォロー中の人のうち、この人数以上がフォローしている人は知り合いとして扱います。フォローリストは受信済みのものを使い、1時間ごとに数え直します", "People followed by at least this many of the people you follow are trusted. Contact lists are taken from stored events and recounted every hour"),
+    ("フォローリストを数えています...", "Counting contact lists..."),
+    (
+        "テーマ、言語、定型文、ミュートする語はログイン中のアカウントに暗号化して保存し、ほかの端末と共有します",
+        "The theme, language, quick phrases and muted words are saved encrypted to your account and shared with your other devices",
+    ),
+    ("連携", "Integrations"),
+    ("再生中の曲を音楽ステータスとして公開する", "Publish the playing track as your music status"),
+    (
+        "再生中のプレイヤーの曲名とアーティストを、曲の残り時間を期限にして公開します",
+        "Publishes the track and artist from the playing player, expiring when the track ends",
+    ),
+    ("この OS ではまだ対応していません (Linux、Windows、macOS のみ)", "Not supported on this OS yet (Linux, Windows and macOS only)"),
+    ("この OS ではまだ対応していません (Linux のみ)", "Not supported on this OS yet (Linux only)"),
+    ("画像の添付", "Attachments"),
+    ("メディアサーバー:", "Media server:"),
+    ("方式:", "Protocol:"),
+    (
+        "添付した画像のアップロード先です。NIP-96 では NIP-98 で、Blossom では認可イベントで署名します",
+        "Where attachments are uploaded. NIP-96 uploads are signed with NIP-98, Blossom uploads with an authorization event",
+    ),
+    ("通知", "Notifications"),
+    ("新しいメッセージ (DM)", "New messages (DM)"),
+    ("自分へのメンション", "Mentions of you"),
+    ("星を付けた友だちの新しいステータス", "New statuses from starred friends"),
+    (
+        "ウィンドウを見ていないときにデスクトップ通知を出します。友だちタブの ☆ で星を付けられます",
+        "Shows desktop notifications when the window is not focused. Star friends with ☆ in the Friends tab",
+    ),
+    ("トークを開いたら既読を相手に知らせる (NIP-17 のトークのみ)", "Send read receipts when opening a chat (NIP-17 chats only)"),
+    ("メッセージの入力中であることを相手に知らせる", "Let others know when you are typing"),
+    (
+        "入力中の知らせは暗号化されないため、誰とトークしているかがリレーから分かります",
+        "Typing notices are not encrypted, so relays can see who you are chatting with",
+    ),
+    ("保存", "Save"),
+    ("初期値に戻す", "Reset to defaults"),
+    ("セキュリティ", "Security"),
+    ("自動ロックまでの時間 (分):", "Auto-lock after (minutes):"),
+    (
+        "操作がないままこの時間が過ぎたら、鍵をメモリから消してパスフレーズの入力に戻ります。0 なら自動ではロックしません",
+        "After this long without activity, the key is wiped from memory and the passphrase is asked again. 0 disables auto-lock",
+    ),
+    ("署名する前に内容を確認する", "Review events before signing"),
+    (
+        "投稿やリアクションなど、署名するイベントの種類・タグ・本文をダイアログで確認してから署名します",
+        "Shows the kind, tags and content of posts, reactions and other events in a dialog before signing",
+    ),
+    ("確認せずに署名する種類:", "Kinds signed without review:"),
+    ("(なし。すべて確認します)", "(None. Everything is reviewed)"),
+    ("kind の番号", "Kind number"),
+    ("確認するようにする", "Review again"),
+    ("kind は 0〜65535 の番号で入力してください", "Enter a kind number from 0 to 65535"),
+    ("パスフレーズを変更", "Change passphrase"),
+    ("このアプリに保存した秘密鍵を、新しいパスフレーズで暗号化し直します", "Re-encrypts the secret key stored in this app with a new passphrase"),
+    ("設定を保存しました", "Settings saved"),
+    ("今のパスフレーズ:", "Current passphrase:"),
+    ("新しいパスフレーズ:", "New passphrase:"),
+    ("新しいパスフレーズの確認:", "Confirm new passphrase:"),
+    ("変更", "Change"),
+    ("暗号化し直しています...", "Re-encrypting..."),
+    ("新しいパスフレーズを入力してください", "Enter a new passphrase"),
+    ("新しいパスフレーズが一致しません", "The new passphrases do not match"),
+    ("今と同じパスフレーズです", "That is the current passphrase"),
+    (
+        "ほかの端末にこのアカウントを保存している場合、そちらのパスフレーズは変わりません",
+        "If this account is saved on other devices, their passphrases are not changed",
+    ),
+    ("パスフレーズを変更しました", "Passphrase changed"),
+    ("変更できませんでした。設定ファイルは元のままです: {}", "Could not change it. The config file was left as it was: {}"),
+    // ui/sign_dialog.rs
+    ("署名の確認", "Review signature"),
+    ("次のイベントに署名しようとしています。内容を確認してください", "You are about to sign the following event. Please review it"),
+    ("タグ:", "Tags:"),
+    ("本文:", "Content:"),
+    ("(なし)", "(none)"),
+    ("(空)", "(empty)"),
+    ("この種類は今後確認せずに署名する", "Sign this kind without review from now on"),
+    ("署名する", "Sign"),
+    ("件が確認を待っています", "waiting for review"),
+    ("ほかに {} {}", "{} more {}"),
+    // ui/timestamp_proof_badge.rs
+    ("タイムスタンプの証明: ビットコインのブロックに記録されるのを待っています", "Timestamp proof: waiting to be recorded in a Bitcoin block"),
+    ("タイムスタンプの証明: ビットコインのブロックに記録され、証明 (kind 1040) を公開しました。記録はカレンダーの応答のみで確認しています", "Timestamp proof: recorded in a Bitcoin block and the proof (kind 1040) was published. The record is confirmed by the calendar's response only"),
+    ("タイムスタンプの証明を作れませんでした: {}", "Could not create a timestamp proof: {}"),
+    // ui/toast.rs
+    ("取り消す", "Undo"),
+    ("{} (あと {}秒)", "{} ({}s left)"),
+    ("中止", "Cancel"),
+    ("PoW を計算中 (難易度 {}、{}回試行)", "Computing PoW (difficulty {}, {} attempts)"),
+    // ui/user_profile_view.rs
+    ("プロフィールを取得できませんでした: {}", "Could not fetch the profile: {}"),
+    ("読み書き", "Read and write"),
+    ("npub をコピー", "Copy npub"),
+    ("メッセージ", "Message"),
+    ("ステータスはありません", "No status"),
+    ("リレーリスト (NIP-65) は公開されていません", "No relay list (NIP-65) is published"),
+    ("最近のノート", "Recent notes"),
+    ("まだノートはありません", "No notes yet"),
+    ("フォロワー", "Followers"),
+    ("フォローリストは公開されていません", "No follow list is published"),
+    ("接続中のリレーが件数の問い合わせ (NIP-45) に対応していません", "The connected relays do not support counts (NIP-45)"),
+    ("🏅 バッジを贈る", "🏅 Award badge"),
+    ("バッジ", "Badges"),
+    ("発行:", "Issued by:"),
+    // ui/wallet_view.rs
+    ("ウォレット機能を使うにはログインしてください。", "Log in to use the wallet."),
+    ("ウォレット接続済み", "Wallet connected"),
+    ("接続を解除", "Disconnect"),
+    ("接続の解除に失敗しました: {}", "Failed to disconnect: {}"),
+    ("ワンクリックZAPの金額 (sats):", "One-click zap amount (sats):"),
+    ("履歴を更新", "Refresh history"),
+    ("Zap履歴の取得エラー: {}", "Error fetching zap history: {}"),
+    ("Zap履歴はありません。", "No zap history."),
+    ("不明なユーザー", "Unknown user"),
+    ("Nostrウォレットに接続", "Connect a Nostr wallet"),
+    (
+        "Nostr Wallet ConnectのURIと、暗号化のためのメインパスフレーズを入力してください。",
+        "Enter your Nostr Wallet Connect URI and your main passphrase for encryption.",
+    ),
+    ("アプリのパスフレーズ:", "App passphrase:"),
+    ("保存して接続", "Save and connect"),
+    ("保存と接続に失敗しました: {}", "Failed to save and connect: {}"),
+    ("ウォレットの保存と接続に失敗しました: {}", "Failed to save and connect the wallet: {}"),
+    ("パスフレーズは空にできません", "The passphrase cannot be empty"),
+    ("NWC URIにリレーURLが含まれていません", "The NWC URI does not contain a relay URL"),
+    ("ZAPに失敗しました: {}", "Zap failed: {}"),
+    ("取得中...", "Fetching..."),
+    ("Nostrクライアントが接続されていません", "The Nostr client is not connected"),
+    ("ログインしていません", "Not logged in"),
+    ("Descriptionタグが見つかりません", "Description tag not found"),
+    ("受信者の公開鍵が見つかりません", "Recipient public key not found"),
+    // ui/zap.rs
+    ("ライトニングアドレスが設定されていません", "No lightning address is set"),
+    ("金額は {} から {} sats の間で指定してください", "The amount must be between {} and {} sats"),
+    ("請求書を作成", "Create invoice"),
+    ("NWCで支払う", "Pay with NWC"),
+    ("ZAPを送る", "Send a zap"),
+    ("{} にZAPします", "Zap {}"),
+    ("QRコードを作成できません: {}", "Could not create the QR code: {}"),
+    ("支払いに失敗しました: {}", "Payment failed: {}"),
+    ("NWC未接続のため、QRコードをウォレットで読み取って支払ってください。", "NWC is not connected, so scan the QR code with your wallet to pay."),
+    ("金額 (sats):", "Amount (sats):"),
+    ("請求書の取得に失敗しました: {}", "Failed to fetch the invoice: {}"),
+    ("無効な金額です", "Invalid amount"),
+    ("ワンクリックZAPの金額が不正です", "The one-click zap amount is invalid"),
+    ("金額が大きすぎます", "The amount is too large"),
+    ("請求書を読み取れません: {}", "Could not read the invoice: {}"),
+    ("請求書の金額が指定した金額と違います", "The invoice amount does not match the requested amount"),
+    ("請求書がZAPリクエストに対応していません", "The invoice does not match the zap request"),
+    ("ウォレットから {} 秒以内に返事がありませんでした", "The wallet did not respond within {} seconds"),
+    ("ウォレットとの接続が切れました", "Lost the connection to the wallet"),
+    ("ウォレットが支払いを断りました: {}", "The wallet refused the payment: {}"),
+    ("ウォレットから予期しない返事が届きました", "Received an unexpected response from the wallet"),
+    ("ZAPしました (プリイメージ: {}...)", "Zapped (preimage: {}...)"),
+];
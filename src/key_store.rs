@@ -0,0 +1,82 @@
+use keyring::Entry;
+use tracing::error;
+
+use crate::types::{KeyStorage, StoredAccount};
+
+// OSのキーチェーンに登録するときのサービス名とアカウント名
+const KEYRING_SERVICE: &str = "N";
+const KEYRING_USER: &str = "encrypted_secret_key";
+
+// アカウントごとに別のエントリにする。公開鍵が分からない以前の形式のアカウントは元の名前のまま
+fn keyring_entry(account: &StoredAccount) -> Result<Entry, keyring::Error> {
+    if account.pubkey.is_empty() {
+        Entry::new(KEYRING_SERVICE, KEYRING_USER)
+    } else {
+        Entry::new(KEYRING_SERVICE, &format!("{KEYRING_USER}:{}", account.pubkey))
+    }
+}
+
+// 設定に応じて、暗号化された秘密鍵 (ncryptsec) を設定ファイルかキーチェーンから読み出す
+pub fn load_encrypted_secret_key(
+    account: &StoredAccount,
+) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    match account.key_storage {
+        KeyStorage::File => Ok(account.encrypted_secret_key.clone()),
+        KeyStorage::Keyring => Ok(keyring_entry(account)?.get_password()?),
+    }
+}
+
+// 暗号化された秘密鍵を現在の保存先に書き込む。設定ファイル自体の保存は呼び出し側で行う
+pub fn store_encrypted_secret_key(
+    account: &mut StoredAccount,
+    encrypted_secret_key: String,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    match account.key_storage {
+        KeyStorage::File => account.encrypted_secret_key = encrypted_secret_key,
+        KeyStorage::Keyring => {
+            keyring_entry(account)?.set_password(&encrypted_secret_key)?;
+            account.encrypted_secret_key.clear();
+        }
+    }
+    Ok(())
+}
+
+// 保存先を切り替え、暗号化された秘密鍵を新しい保存先へ移す
+pub fn change_key_storage(
+    account: &mut StoredAccount,
+    storage: KeyStorage,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    if account.key_storage == storage {
+        return Ok(());
+    }
+    let encrypted_secret_key = load_encrypted_secret_key(account)?;
+    let previous = account.key_storage;
+    account.key_storage = storage;
+    store_encrypted_secret_key(account, encrypted_secret_key)?;
+    // 移し終えたらキーチェーン側の古いエントリを削除する
+    if previous == KeyStorage::Keyring
+        && let Err(e) = keyring_entry(account)?.delete_credential() {
+        error!("Failed to delete the keyring entry: {e}");
+    }
+    Ok(())
+}
+
+// 以前の形式から移したアカウントに公開鍵を記録する。キーチェーンのエントリもアカウントごとの名前へ移す
+pub fn set_account_pubkey(
+    account: &mut StoredAccount,
+    pubkey: String,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    if account.pubkey == pubkey {
+        return Ok(());
+    }
+    let encrypted_secret_key = load_encrypted_secret_key(account)?;
+    let previous_entry = (account.key_storage == KeyStorage::Keyring).then(|| keyring_entry(account)).transpose()?;
+    account.pubkey = pubkey;
+    store_encrypted_secret_key(account, encrypted_secret_key)?;
+    if let Some(entry) = previous_entry
+        && let Err(e) = entry.delete_credential() {
+        error!("Failed to delete the old keyring entry: {e}");
+    }
+    Ok(())
+}
+
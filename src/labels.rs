@@ -0,0 +1,218 @@
+use nostr::{Event, EventBuilder, EventId, Filter, Kind, PublicKey, Tag};
+use nostr_sdk::Client;
+use std::collections::{BTreeSet, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+use crate::i18n::tr;
+use crate::network;
+use crate::outbox;
+use crate::signer::AppSigner;
+use crate::types::{NostrStatusAppInternal, ReportTarget, Settings};
+
+// ラベル (NIP-32, kind 1985)
+// 信頼する人が付けたラベルを集め、設定したラベルの付いた投稿をタイムラインで隠したり畳んだりする
+// e タグのあるラベルは投稿だけに、e タグのないラベルは p タグのユーザーに付いたものとして扱う
+
+// このアプリから付けるラベルの名前空間
+const LABEL_NAMESPACE: &str = "ugc";
+// 投稿メニューから付けられるラベル
+pub const LABEL_PRESETS: [&str; 4] = ["nsfw", "spam", "violence", "impersonation"];
+// タイムラインの新しい投稿のラベルを確かめる間隔と、1回に問い合わせる件数
+const LABEL_CHECK_INTERVAL_SECS: u64 = 60;
+const LABEL_BATCH_SIZE: usize = 200;
+
+// タイムラインでの扱い
+#[derive(Debug, Clone, PartialEq)]
+pub enum LabelAction {
+    Hide,
+    // 本文を隠して、付いたラベルを理由として出す
+    Collapse(String),
+}
+
+fn label_lines(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.lines().map(|line| line.trim().to_lowercase()).filter(|line| !line.is_empty())
+}
+
+// 設定の「参考にする人」に自分を加えたもの。読めない行は飛ばす
+pub fn trusted_labelers(settings: &Settings, my_pubkey: PublicKey) -> Vec<PublicKey> {
+    let mut labelers: Vec<PublicKey> = settings
+        .trusted_labelers
+        .lines()
+        .filter_map(|line| PublicKey::parse(line.trim()).ok())
+        .collect();
+    if !labelers.contains(&my_pubkey) {
+        labelers.push(my_pubkey);
+    }
+    labelers
+}
+
+// 投稿と投稿者に付いたラベルから扱いを決める。隠す設定のラベルが優先する
+pub fn label_action<'a>(settings: &Settings, labels: impl IntoIterator<Item = &'a String>) -> Option<LabelAction> {
+    let hidden: HashSet<String> = label_lines(&settings.hidden_labels).collect();
+    let collapsed: HashSet<String> = label_lines(&settings.collapsed_labels).collect();
+    let mut action = None;
+    for label in labels {
+        if hidden.contains(label) {
+            return Some(LabelAction::Hide);
+        }
+        if action.is_none() && collapsed.contains(label) {
+            action = Some(LabelAction::Collapse(label.clone()));
+        }
+    }
+    action
+}
+
+// l タグの値。大文字と小文字は区別しない
+fn label_values(event: &Event) -> BTreeSet<String> {
+    event
+        .tags
+        .iter()
+        .filter_map(|tag| {
+            let values = tag.as_slice();
+            (values.first().map(String::as_str) == Some("l")).then(|| values.get(1).map(|value| value.trim().to_lowercase())).flatten()
+        })
+        .filter(|value| !value.is_empty())
+        .collect()
+}
+
+fn merge_label_event(app_data: &mut NostrStatusAppInternal, event: &Event) {
+    let values = label_values(event);
+    if values.is_empty() {
+        return;
+    }
+    let event_ids: Vec<EventId> = event.tags.event_ids().copied().collect();
+    if event_ids.is_empty() {
+        for pubkey in event.tags.public_keys() {
+            app_data.pubkey_labels.entry(*pubkey).or_default().extend(values.iter().cloned());
+        }
+    } else {
+        for event_id in event_ids {
+            app_data.event_labels.entry(event_id).or_default().extend(values.iter().cloned());
+        }
+    }
+}
+
+async fn fetch_labels(
+    client: &Client,
+    labelers: &[PublicKey],
+    event_ids: Vec<EventId>,
+    pubkeys: Vec<PublicKey>,
+) -> Result<Vec<Event>, Box<dyn std::error::Error + Send + Sync>> {
+    let base = Filter::new().kind(Kind::Label).authors(labelers.iter().copied());
+    let mut events = Vec::new();
+    if !event_ids.is_empty() {
+        events.extend(network::fetch_events(client, base.clone().events(event_ids)).await?);
+    }
+    if !pubkeys.is_empty() {
+        events.extend(network::fetch_events(client, base.pubkeys(pubkeys)).await?);
+    }
+    Ok(events)
+}
+
+// タイムラインに届いた投稿と投稿者のラベルを、信頼する人の分だけ定期的に取得する
+pub async fn run_label_worker(client: Client, keys: AppSigner, session: u64, app_data_arc: Arc<Mutex<NostrStatusAppInternal>>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(LABEL_CHECK_INTERVAL_SECS));
+    let mut last_labelers: Vec<PublicKey> = Vec::new();
+    loop {
+        interval.tick().await;
+        let (labelers, event_ids, pubkeys) = {
+            let mut app_data = app_data_arc.lock().unwrap();
+            // ロックやログアウトの後は終了する
+            if !app_data.is_current_session(session) {
+                break;
+            }
+            // 参考にする人が変わったら、集めたラベルを捨てて確かめ直す
+            let labelers = trusted_labelers(&app_data.settings, keys.public_key());
+            if labelers != last_labelers {
+                app_data.event_labels.clear();
+                app_data.pubkey_labels.clear();
+                app_data.labels_checked_events.clear();
+                app_data.labels_checked_pubkeys.clear();
+                last_labelers = labelers.clone();
+            }
+            let event_ids: Vec<EventId> = app_data
+                .timeline_posts
+                .iter()
+                .filter(|post| !post.is_private && !app_data.labels_checked_events.contains(&post.id))
+                .map(|post| post.id)
+                .take(LABEL_BATCH_SIZE)
+                .collect();
+            let pubkeys: Vec<PublicKey> = app_data
+                .timeline_posts
+                .iter()
+                .map(|post| post.author_pubkey)
+                .filter(|pubkey| !app_data.labels_checked_pubkeys.contains(pubkey))
+                .collect::<HashSet<_>>()
+                .into_iter()
+                .take(LABEL_BATCH_SIZE)
+                .collect();
+            (labelers, event_ids, pubkeys)
+        };
+        if event_ids.is_empty() && pubkeys.is_empty() {
+            continue;
+        }
+
+        match fetch_labels(&client, &labelers, event_ids.clone(), pubkeys.clone()).await {
+            Ok(events) => {
+                let mut app_data = app_data_arc.lock().unwrap();
+                // 取得している間に参考にする人が変わっていたら、次の回に取り直す
+                if trusted_labelers(&app_data.settings, keys.public_key()) != labelers {
+                    continue;
+                }
+                for event in &events {
+                    merge_label_event(&mut app_data, event);
+                }
+                app_data.labels_checked_events.extend(event_ids);
+                app_data.labels_checked_pubkeys.extend(pubkeys);
+                app_data.should_repaint = true;
+            }
+            Err(e) => warn!("Failed to fetch labels: {e}"),
+        }
+    }
+}
+
+async fn sign_label(signer: &AppSigner, target: &ReportTarget, label: &str) -> Result<Event, Box<dyn std::error::Error + Send + Sync>> {
+    let target_tag = match target.event_id {
+        Some(event_id) => Tag::event(event_id),
+        None => Tag::public_key(target.public_key),
+    };
+    let tags = vec![
+        Tag::parse(["L", LABEL_NAMESPACE])?,
+        Tag::parse(["l", label, LABEL_NAMESPACE])?,
+        target_tag,
+    ];
+    Ok(EventBuilder::new(Kind::Label, "").tags(tags).sign(signer).await?)
+}
+
+// 投稿 (event_id があるとき) かユーザーにラベルを付ける。送れなかった場合も送信キューに残るので、署名できたら true を返す
+pub async fn publish_label(
+    client: Client,
+    signer: AppSigner,
+    target: ReportTarget,
+    label: String,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+) -> bool {
+    let event = match sign_label(&signer, &target, &label).await {
+        Ok(event) => event,
+        Err(e) => {
+            error!("Failed to sign the label: {e}");
+            app_data_arc.lock().unwrap().report_error(tr!("ラベルに署名できませんでした: {}", e));
+            return false;
+        }
+    };
+
+    let sent = outbox::send_or_queue(&client, event.clone(), &app_data_arc).await;
+    let mut app_data = app_data_arc.lock().unwrap();
+    // 自分のラベルはいつも参考にするので、すぐにタイムラインに反映する
+    merge_label_event(&mut app_data, &event);
+    app_data.should_repaint = true;
+    if sent {
+        info!("Label published with event id: {:?}", event.id);
+        app_data.notify(tr!("ラベルを付けました"));
+    } else {
+        app_data.report_error(tr!("ラベルを送信できませんでした。送信キューに残し、接続が戻ったら再送します"));
+    }
+    true
+}
@@ -0,0 +1,104 @@
+use chrono::{DateTime, Local};
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::sync::Mutex;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+
+// ログタブに残す行数。古いものから捨てる
+const MAX_LOG_ENTRIES: usize = 2000;
+
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub time: DateTime<Local>,
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+}
+
+impl LogEntry {
+    // 不具合報告に貼り付けるときの1行の形式
+    pub fn to_line(&self) -> String {
+        format!("{} {:>5} {}: {}", self.time.format("%Y-%m-%d %H:%M:%S"), self.level, self.target, self.message)
+    }
+}
+
+static LOG_BUFFER: Mutex<VecDeque<LogEntry>> = Mutex::new(VecDeque::new());
+
+// イベントのフィールドを1行の文字列にまとめる
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{value:?}");
+        } else {
+            let _ = write!(self.message, " {}={value:?}", field.name());
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            let _ = write!(self.message, " {}={value}", field.name());
+        }
+    }
+}
+
+// 受け取ったログをリングバッファに積むレイヤー
+struct RingBufferLayer;
+
+impl<S: Subscriber> Layer<S> for RingBufferLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        let entry = LogEntry {
+            time: Local::now(),
+            level: *event.metadata().level(),
+            target: event.metadata().target().to_string(),
+            message: visitor.message,
+        };
+        let mut buffer = LOG_BUFFER.lock().unwrap();
+        if buffer.len() >= MAX_LOG_ENTRIES {
+            buffer.pop_front();
+        }
+        buffer.push_back(entry);
+    }
+}
+
+// 標準出力とログタブの両方にログを出す。依存クレートのログは警告以上に絞る
+pub fn init() {
+    let filter = Targets::new()
+        .with_target(env!("CARGO_CRATE_NAME"), Level::DEBUG)
+        .with_default(Level::WARN);
+    if let Err(e) = tracing_subscriber::registry()
+        .with(tracing_subscriber::fmt::layer())
+        .with(RingBufferLayer)
+        .with(filter)
+        .try_init()
+    {
+        eprintln!("Failed to initialize logging: {e}");
+    }
+}
+
+// 指定したレベルとそれより重要なログを古い順に返す
+pub fn entries(level: Level) -> Vec<LogEntry> {
+    LOG_BUFFER
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|entry| entry.level <= level)
+        .cloned()
+        .collect()
+}
+
+pub fn clear() {
+    LOG_BUFFER.lock().unwrap().clear();
+}
@@ -1,18 +1,70 @@
+mod accounts;
+mod argon2id;
+mod articles;
+mod audit_log;
+mod badges;
+mod bookmarks;
 mod cache_db;
+mod call;
+mod camera;
+mod channels;
+mod communities;
+mod desktop_notify;
+mod dm;
+mod drafts;
+mod gift_wrap;
+mod groups;
+mod i18n;
+mod key_store;
+mod labels;
+mod logs;
+mod media;
+mod private_status;
 mod emoji_loader;
+mod event_check;
+mod fonts;
+mod nip06;
+mod nip39;
 mod nip49;
+mod nip78;
+mod pins;
+mod pow;
+mod profile_card;
+mod network;
 mod nostr_client;
+mod opentimestamps;
+mod outbox;
+mod read_state;
+mod relay_debug;
+mod relay_monitor;
+mod replaceable;
+mod settings;
+mod settings_sync;
+mod sign_gate;
+mod signer;
+mod status_history;
+mod now_playing;
+mod scheduler;
+mod note;
+mod global_feed;
+mod report;
+mod search;
+mod wot;
+mod tray;
+mod voice;
 mod ui;
 mod types;
 
 use eframe::egui;
-use std::collections::{HashMap, HashSet};
-use std::path::Path;
-use std::sync::{Arc, Mutex};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex};
 use tokio::runtime::Runtime;
+use unicode_segmentation::UnicodeSegmentation;
 use std::fs;
-use nostr::PublicKey;
+use nostr::{PublicKey, Timestamp};
 use regex::Regex;
+use tracing::{error, info};
 
 mod theme;
 
@@ -20,12 +72,122 @@ use crate::cache_db::{LmdbCache, DB_FOLLOWED, DB_PROFILES, DB_RELAYS};
 use crate::types::*;
 
 
+const APP_DIR_NAME: &str = "N"; // OSの設定ディレクトリ内に作るフォルダ名
+const WINDOW_TITLE: &str = "N"; // ウィンドウのタイトル。未読があれば件数を前に付ける
 const CONFIG_FILE: &str = "config.json"; // 設定ファイル名
+const BUNKER_FILE: &str = "bunker.json"; // リモート署名者の接続情報
+const SETTINGS_FILE: &str = "settings.json"; // アプリの設定
+const AUDIT_LOG_FILE: &str = "audit_log.jsonl"; // 署名したイベントの記録
 
-const DB_PATH: &str = "cache_db";
+const DB_DIR: &str = "cache_db"; // プロフィールやタイムラインのキャッシュ (LMDB)
+const EVENT_DB_DIR: &str = "events"; // 受信したイベントを保存するデータベース
+const IMAGE_CACHE_DIR: &str = "images"; // 取得した画像のキャッシュ (LMDB)
 const CACHE_DIR: &str = "cache"; // Re-added for migration
 
+// 画面とプロフィールカードで使うフォント
+pub const APP_FONT: &[u8] = include_bytes!("../assets/fonts/LINESeedJP_TTF_Rg.ttf");
+pub const APP_BOLD_FONT: &[u8] = include_bytes!("../assets/fonts/LINESeedJP_20241105/Desktop/TTF/LINESeedJP_TTF_Bd.ttf");
+
 const MAX_STATUS_LENGTH: usize = 140; // ステータス最大文字数
+const STATUS_URL_LENGTH: usize = 23; // URL は長さにかかわらずこの文字数として数える (Twitter と同じ)
+
+static URL_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"https?://\S+").unwrap());
+
+// ステータスの文字数。絵文字や結合文字は chars() では複数になるので、見た目の1文字 (書記素クラスタ) で数える
+fn status_length(text: &str) -> usize {
+    let mut length = 0;
+    let mut last_end = 0;
+    for url in URL_REGEX.find_iter(text) {
+        length += text[last_end..url.start()].graphemes(true).count() + STATUS_URL_LENGTH;
+        last_end = url.end();
+    }
+    length + text[last_end..].graphemes(true).count()
+}
+
+// 設定ファイルを置くディレクトリ。OS標準の設定ディレクトリが取得できない場合はカレントディレクトリを使う
+fn config_dir() -> PathBuf {
+    dirs::config_dir()
+        .map(|dir| dir.join(APP_DIR_NAME))
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+pub fn config_file_path() -> PathBuf {
+    config_dir().join(CONFIG_FILE)
+}
+
+pub fn bunker_file_path() -> PathBuf {
+    config_dir().join(BUNKER_FILE)
+}
+
+pub fn settings_file_path() -> PathBuf {
+    config_dir().join(SETTINGS_FILE)
+}
+
+pub fn audit_log_file_path() -> PathBuf {
+    config_dir().join(AUDIT_LOG_FILE)
+}
+
+// データベースを置くディレクトリ。OS標準のデータディレクトリが取得できない場合はカレントディレクトリを使う
+fn data_dir() -> PathBuf {
+    dirs::data_dir()
+        .map(|dir| dir.join(APP_DIR_NAME))
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+fn event_db_path() -> PathBuf {
+    data_dir().join(EVENT_DB_DIR)
+}
+
+fn cache_db_path() -> PathBuf {
+    data_dir().join(DB_DIR)
+}
+
+// OS標準のキャッシュディレクトリが取得できない場合はデータディレクトリに置く
+fn image_cache_path() -> PathBuf {
+    dirs::cache_dir()
+        .map(|dir| dir.join(APP_DIR_NAME))
+        .unwrap_or_else(data_dir)
+        .join(IMAGE_CACHE_DIR)
+}
+
+// 別のファイルシステムへは rename できないので、コピーしてから削除する
+// LMDB のディレクトリには中にファイルしかないので、1階層だけコピーすればよい
+fn move_path(old_path: &Path, new_path: &Path) -> std::io::Result<()> {
+    if fs::rename(old_path, new_path).is_ok() {
+        return Ok(());
+    }
+    if old_path.is_dir() {
+        fs::create_dir_all(new_path)?;
+        for entry in fs::read_dir(old_path)? {
+            let entry = entry?;
+            fs::copy(entry.path(), new_path.join(entry.file_name()))?;
+        }
+        fs::remove_dir_all(old_path)
+    } else {
+        fs::copy(old_path, new_path)?;
+        fs::remove_file(old_path)
+    }
+}
+
+// 以前のバージョンがカレントディレクトリに作った設定ファイルとキャッシュを、設定ディレクトリとデータディレクトリへ移す
+fn migrate_config_files() -> std::io::Result<()> {
+    let dir = config_dir();
+    fs::create_dir_all(&dir)?;
+    fs::create_dir_all(data_dir())?;
+    let moves = [CONFIG_FILE, BUNKER_FILE]
+        .map(|file_name| (file_name, dir.join(file_name)))
+        .into_iter()
+        .chain([(DB_DIR, cache_db_path())]);
+    for (file_name, new_path) in moves {
+        let old_path = Path::new(file_name);
+        if !old_path.exists() || new_path.exists() {
+            continue;
+        }
+        move_path(old_path, &new_path)?;
+        info!("Moved {file_name} to {}", new_path.display());
+    }
+    Ok(())
+}
 
 async fn migrate_data_from_files(
     cache_db: &LmdbCache,
@@ -35,7 +197,7 @@ async fn migrate_data_from_files(
         return Ok(());
     }
 
-    println!("Old cache directory found. Starting data migration...");
+    info!("Old cache directory found. Starting data migration...");
 
     let mut files_by_pubkey: HashMap<String, Vec<std::path::PathBuf>> = HashMap::new();
     let re = Regex::new(r"([a-f0-9]{64})_.*\.json")?;
@@ -43,37 +205,35 @@ async fn migrate_data_from_files(
     for entry in fs::read_dir(cache_path)? {
         let entry = entry?;
         let path = entry.path();
-        if path.is_file() {
-            if let Some(captures) = re.captures(path.file_name().unwrap().to_str().unwrap()) {
-                if let Some(pubkey) = captures.get(1) {
-                    files_by_pubkey
-                        .entry(pubkey.as_str().to_string())
-                        .or_default()
-                        .push(path);
-                }
-            }
+        if path.is_file()
+            && let Some(captures) = re.captures(path.file_name().unwrap().to_str().unwrap())
+            && let Some(pubkey) = captures.get(1) {
+            files_by_pubkey
+                .entry(pubkey.as_str().to_string())
+                .or_default()
+                .push(path);
         }
     }
 
     for (pubkey_hex, paths) in files_by_pubkey {
-        println!("Migrating data for pubkey: {}", pubkey_hex);
+        info!("Migrating data for pubkey: {}", pubkey_hex);
         for path in paths {
             let file_name = path.file_name().unwrap().to_str().unwrap();
             if file_name.ends_with("_followed.json") {
                 let content = fs::read_to_string(&path)?;
                 let cache: Cache<HashSet<PublicKey>> = serde_json::from_str(&content)?;
                 cache_db.write_cache(DB_FOLLOWED, &pubkey_hex, &cache.data)?;
-                println!("  - Migrated followed list.");
+                info!("  - Migrated followed list.");
             } else if file_name.ends_with("_nip65.json") {
                 let content = fs::read_to_string(&path)?;
                 let cache: Cache<Vec<(String, Option<String>)>> = serde_json::from_str(&content)?;
                 cache_db.write_cache(DB_RELAYS, &pubkey_hex, &cache.data)?;
-                println!("  - Migrated NIP-65 relays.");
+                info!("  - Migrated NIP-65 relays.");
             } else if file_name.ends_with("_profile.json") {
                 let content = fs::read_to_string(&path)?;
                 let cache: Cache<ProfileMetadata> = serde_json::from_str(&content)?;
                 cache_db.write_cache(DB_PROFILES, &pubkey_hex, &cache.data)?;
-                println!("  - Migrated profile metadata.");
+                info!("  - Migrated profile metadata.");
             }
         }
     }
@@ -81,7 +241,7 @@ async fn migrate_data_from_files(
     // Rename the old cache directory to prevent re-migration
     let migrated_path = Path::new("cache_migrated");
     fs::rename(cache_path, migrated_path)?;
-    println!("Data migration complete. Old cache directory renamed to 'cache_migrated'.");
+    info!("Data migration complete. Old cache directory renamed to 'cache_migrated'.");
 
     Ok(())
 }
@@ -90,6 +250,7 @@ async fn migrate_data_from_files(
 pub struct NostrStatusApp {
     data: Arc<Mutex<NostrStatusAppInternal>>,
     runtime: Runtime, // Tokio Runtimeを保持
+    tray: Option<tray::TrayIcon>, // システムトレイのアイコン。出せないビルドや環境では None
 }
 
 impl NostrStatusApp {
@@ -97,75 +258,44 @@ impl NostrStatusApp {
         let runtime = Runtime::new().expect("Failed to create Tokio runtime");
 
         // egui のスタイル設定
-        _cc.egui_ctx.set_pixels_per_point(1.2); // UIのスケールを調整
         let mut style = (*_cc.egui_ctx.style()).clone();
 
-        // --- フォント設定 ---
-        let mut fonts = egui::FontDefinitions::default();
-
-        fonts.font_data.insert(
-            "LINESeedJP".to_owned(),
-            egui::FontData::from_static(include_bytes!("../assets/fonts/LINESeedJP_TTF_Rg.ttf"))
-                .into(),
-        );
+        let settings = settings::load_settings();
+        i18n::set_language(settings.language);
+        theme::apply_ui_scale(&_cc.egui_ctx, settings.ui_scale); // UIのスケールを調整
 
-        fonts
-            .families
-            .entry(egui::FontFamily::Proportional)
-            .or_default()
-            .insert(0, "LINESeedJP".to_owned());
+        // --- フォント設定 ---
+        if let Err(e) = fonts::apply_fonts(&_cc.egui_ctx, &settings) {
+            error!("Falling back to the bundled font: {e}");
+        }
 
-        fonts
-            .families
-            .entry(egui::FontFamily::Monospace)
-            .or_default()
-            .push("LINESeedJP".to_owned());
+        // --- スタイル調整 ---
+        let (accounts, selected_login_account) = accounts::load_account_summaries();
+        if let Err(e) = network::apply_network_settings(&settings) {
+            error!("Failed to apply network settings: {e}");
+        }
+        sign_gate::apply_sign_settings(&settings);
+        ui::markdown::apply_markdown_settings(&settings);
 
-        _cc.egui_ctx.set_fonts(fonts);
+        // テキストスタイルはライトとダークで共通
+        style.text_styles = theme::text_styles(settings.font_size);
 
-        // --- スタイル調整 ---
-        style.visuals = theme::light_visuals(); // ライトモードを基準にする
-
-        // 角丸やテキストスタイルは共通で設定
-        let corner_radius = 6.0;
-        style.visuals.widgets.noninteractive.corner_radius = corner_radius.into();
-        style.visuals.widgets.inactive.corner_radius = corner_radius.into();
-        style.visuals.widgets.hovered.corner_radius = corner_radius.into();
-        style.visuals.widgets.active.corner_radius = corner_radius.into();
-        style.visuals.widgets.open.corner_radius = corner_radius.into();
-
-        style.text_styles = [
-            (
-                egui::TextStyle::Heading,
-                egui::FontId::new(20.0, egui::FontFamily::Proportional),
-            ),
-            (
-                egui::TextStyle::Body,
-                egui::FontId::new(13.0, egui::FontFamily::Proportional),
-            ),
-            (
-                egui::TextStyle::Monospace,
-                egui::FontId::new(12.0, egui::FontFamily::Monospace),
-            ),
-            (
-                egui::TextStyle::Button,
-                egui::FontId::new(13.0, egui::FontFamily::Proportional),
-            ),
-            (
-                egui::TextStyle::Small,
-                egui::FontId::new(11.0, egui::FontFamily::Proportional),
-            ),
-        ]
-        .into();
-
-        _cc.egui_ctx.set_style(style);
+        theme::install_styles(&_cc.egui_ctx, style, settings.theme_preset);
+        theme::apply_theme_preference(&_cc.egui_ctx, settings.theme);
 
         let lmdb_cache =
-            LmdbCache::new(Path::new(DB_PATH)).expect("Failed to initialize LMDB cache");
+            LmdbCache::new(&cache_db_path()).expect("Failed to initialize LMDB cache");
+        let image_cache_db =
+            LmdbCache::new_image_cache(&image_cache_path()).expect("Failed to initialize the image cache");
+        // 開けなかった場合はイベントを保存せずに動かす
+        if let Err(e) = network::open_event_database(&event_db_path()) {
+            error!("Failed to open the event database: {e}");
+        }
 
         let app_data_internal = NostrStatusAppInternal {
             nwc_uri_input: String::new(),
             cache_db: lmdb_cache,
+            image_cache_db,
             is_logged_in: false,
             status_message_input: String::new(),
             show_post_dialog: false,
@@ -174,7 +304,22 @@ impl NostrStatusApp {
             secret_key_input: String::new(),
             passphrase_input: String::new(),
             confirm_passphrase_input: String::new(),
+            generated_keys: None,
+            generated_key_backed_up: false,
+            generated_mnemonic: None,
+            mnemonic_verify_positions: Vec::new(),
+            mnemonic_verify_inputs: Vec::new(),
+            mnemonic_passphrase_input: String::new(),
+            mnemonic_account_input: 0,
+            new_profile_name_input: String::new(),
+            key_storage: KeyStorage::default(),
+            key_storage_error: None,
+            bunker_uri_input: String::new(),
+            remote_signer_status: None,
+            bunker_auth_url: None,
     current_status_type: StatusType::General,
+    status_expiry: StatusExpiry::Never,
+    custom_expiry_minutes: 90,
     show_music_dialog: false,
     music_track_input: String::new(),
     music_url_input: String::new(),
@@ -182,24 +327,160 @@ impl NostrStatusApp {
     podcast_episode_input: String::new(),
     podcast_url_input: String::new(),
             nostr_client: None,
-            my_keys: None,
+            my_signer: None,
             followed_pubkeys: HashSet::new(),
-            followed_pubkeys_display: String::new(),
+            selected_friend: None,
+            follow_pubkey_input: String::new(),
+            follow_error: None,
+            show_scan_qr: false,
+            scan_qr_input: String::new(),
+            scan_qr_image_input: String::new(),
+            is_decoding_qr: false,
+            camera_scan: None,
+            scanned_profile: None,
+            profiles: HashMap::new(),
             timeline_posts: Vec::new(),
             should_repaint: false,
             is_loading: false,
+            login_stage: None,
             current_tab: AppTab::Home,
-            connected_relays_display: String::new(),
+            relay_status_rows: Vec::new(),
+            relay_monitor_started: false,
+            relay_supervisor_started: false,
+            outbox: Vec::new(),
+            outbox_worker_started: false,
+            now_playing_worker_started: false,
+            timestamp_proofs: Vec::new(),
+            opentimestamps_worker_started: false,
+            event_labels: HashMap::new(),
+            pubkey_labels: HashMap::new(),
+            labels_checked_events: HashSet::new(),
+            labels_checked_pubkeys: HashSet::new(),
+            label_worker_started: false,
+            wot_follow_counts: HashMap::new(),
+            wot_loaded: false,
+            wot_worker_started: false,
+            revealed_stranger_posts: HashSet::new(),
+            scheduled_statuses: Vec::new(),
+            next_scheduled_id: 0,
+            scheduler_started: false,
+            schedule_enabled: false,
+            schedule_time_input: String::new(),
+            auto_clear_enabled: false,
+            auto_clear_hours: 2,
+            new_preset_input: String::new(),
+            note_input: String::new(),
+            note_content_warning: false,
+            note_content_warning_reason: String::new(),
+            status_content_warning: false,
+            status_content_warning_reason: String::new(),
+            status_private: false,
+            follow_sets: Vec::new(),
+            selected_follow_set: None,
+            is_loading_follow_sets: false,
+            revealed_content_warnings: HashSet::new(),
+            is_posting_note: false,
+            notes: Vec::new(),
+            notes_loaded: false,
+            is_loading_notes: false,
+            bookmarked_ids: Vec::new(),
+            bookmarks_requested: false,
+            bookmark_events: Vec::new(),
+            bookmark_events_loaded: false,
+            is_loading_bookmarks: false,
+            is_updating_bookmark: false,
+            bookmark_kind_filter: None,
+            articles: Vec::new(),
+            articles_loaded: false,
+            is_loading_articles: false,
+            open_article: None,
+            is_loading_article: false,
+            pins: PinnedItems::default(),
+            pins_requested: false,
+            pins_loaded: false,
+            synced_settings: None,
+            settings_sync_started: false,
+            accounts,
+            selected_login_account,
+            adding_account: false,
+            switch_account_target: None,
+            switch_passphrase_input: String::new(),
+            is_switching_account: false,
+            secondary_account: None,
+            last_activity: std::time::Instant::now(),
+            saved_drafts: Drafts::default(),
+            drafts_saved_at: std::time::Instant::now(),
+            is_locked: false,
+            session_generation: 0,
+            sign_always_allow_kind: false,
+            auto_approve_kind_input: String::new(),
+            show_passphrase_dialog: false,
+            old_passphrase_input: String::new(),
+            new_passphrase_input: String::new(),
+            confirm_new_passphrase_input: String::new(),
+            is_changing_passphrase: false,
+            passphrase_change_error: None,
+            show_export_key_dialog: false,
+            export_key_passphrase_input: String::new(),
+            is_exporting_key: false,
+            export_key_error: None,
+            exported_secret_key: None,
+            is_saving_profile_card: false,
+            reply_target: None,
+            reply_input: String::new(),
+            quote_target: None,
+            quote_input: String::new(),
+            report_target: None,
+            report_reason: nostr::nips::nip56::Report::Spam,
+            report_comment: String::new(),
+            is_sending_report: false,
+            badge_award_target: None,
+            my_badge_definitions: None,
+            badge_award_selected: None,
+            is_awarding_badge: false,
+            link_view: None,
+            link_view_event: None,
+            is_loading_link_view: false,
+            global_events: Vec::new(),
+            global_feed_started: false,
+            search_query: String::new(),
+            search_results: None,
+            is_searching: false,
+            hashtag_feed: None,
+            hashtag_events: Vec::new(),
+            is_loading_hashtag_feed: false,
+            user_profile: None,
+            is_loading_user_profile: false,
+            status_history: Vec::new(),
+            current_statuses: BTreeMap::new(),
+            custom_d_tag_input: String::new(),
+            timeline_status_filter: None,
+            last_error: None,
+            toasts: Vec::new(),
+            held_sends: Vec::new(),
+            pow_jobs: Vec::new(),
+            log_level: tracing::Level::INFO,
+            audit_entries: Vec::new(),
+            audit_entries_loaded: false,
+            relay_debug_relay_filter: None,
+            relay_debug_type_filter: None,
+            relay_debug_search: String::new(),
             nip01_profile_display: String::new(), // ここを初期化
             editable_profile: ProfileMetadata::default(), // 編集可能なプロファイルデータ
-            profile_fetch_status: "Fetching profile...".to_string(), // プロファイル取得状態
+            my_identities: Vec::new(),
+            profile_fetch_status: i18n::tr!("プロフィールを取得中...").to_string(), // プロファイル取得状態
             // リレーリスト編集用のフィールドを初期化
             nip65_relays: Vec::new(),
-            discover_relays_editor: "wss://purplepag.es\nwss://directory.yabu.me".to_string(),
-            default_relays_editor:
-                "wss://relay.damus.io\nwss://relay.nostr.wirednet.jp\nwss://yabu.me".to_string(),
-            current_theme: AppTheme::Light,
+            current_theme: theme::current_theme(&_cc.egui_ctx, settings.theme_preset),
+            settings,
+            settings_status: None,
+            manual_relay_input: String::new(),
+            manual_relay_error: None,
+            relay_list_status: None,
             image_cache: HashMap::new(),
+            nip05_cache: HashMap::new(),
+            identity_cache: HashMap::new(),
+            link_previews: HashMap::new(),
             nwc_passphrase_input: String::new(),
             nwc: None,
             nwc_client: None,
@@ -207,9 +488,78 @@ impl NostrStatusApp {
             zap_history: Vec::new(),
             zap_history_fetch_status: String::new(),
             is_fetching_zap_history: false,
+            conversations: HashMap::new(),
+            read_state: ReadState::starting_at(Timestamp::now()),
+            dm_peer_read_at: HashMap::new(),
+            dm_peer_typing: HashMap::new(),
+            attach_target: None,
+            attach_path_input: String::new(),
+            is_uploading_media: false,
+            voice_recording: None,
+            uploaded_media: HashMap::new(),
+            call: None,
+            call_backend: call::default_backend(),
+            show_chat_emoji_picker: false,
+            emoji_search_input: String::new(),
+            last_typing_sent: None,
+            window_title_unread: 0,
+            selected_conversation: None,
+            dm_message_input: String::new(),
+            new_chat_pubkey_input: String::new(),
+            dm_listener_started: false,
+            notification_listener_started: false,
+            window_focused: true,
+            is_sending_dm: false,
+            dm_error: None,
+            group_relay_input: "wss://groups.fiatjaf.com".to_string(),
+            group_directory: Vec::new(),
+            joined_groups: Vec::new(),
+            joined_groups_loaded: false,
+            selected_group: None,
+            group_messages: HashMap::new(),
+            group_members: HashMap::new(),
+            group_message_input: String::new(),
+            group_client: None,
+            group_listener_key: None,
+            is_loading_groups: false,
+            is_sending_group_message: false,
+            group_error: None,
+            channels: Vec::new(),
+            channels_loaded: false,
+            selected_channel: None,
+            channel_messages: HashMap::new(),
+            channel_message_input: String::new(),
+            channel_listener_key: None,
+            channel_prefs: ChannelPrefs::default(),
+            show_hidden_channels: false,
+            new_channel_name_input: String::new(),
+            new_channel_about_input: String::new(),
+            is_loading_channels: false,
+            is_sending_channel_message: false,
+            channel_error: None,
+            communities: Vec::new(),
+            communities_loaded: false,
+            is_loading_communities: false,
+            selected_community: None,
+            community_posts: Vec::new(),
+            community_posts_key: None,
+            is_loading_community_posts: false,
+            community_show_pending: false,
+            community_post_input: String::new(),
+            is_posting_community: false,
             show_zap_dialog: false,
             zap_amount_input: String::new(),
-            zap_target_post: None,
+            zap_target: None,
+            zap_invoice: None,
+            is_fetching_zap_invoice: false,
+            is_paying_zap_invoice: false,
+            zap_error: None,
+            zap_totals: HashMap::new(),
+            counted_zap_receipts: HashSet::new(),
+            zap_listener_started: false,
+            default_zap_amount_input: "21".to_string(),
+            pending_zaps: HashSet::new(),
+            zap_status_message: None,
         };
         let data = Arc::new(Mutex::new(app_data_internal));
 
@@ -227,26 +577,33 @@ impl NostrStatusApp {
                 app_data.cache_db.clone()
             };
             if let Err(e) = migrate_data_from_files(&cache_db_clone).await {
-                eprintln!("Data migration failed: {e}");
+                error!("Data migration failed: {e}");
             }
 
             let mut app_data = data_clone.lock().unwrap();
-            // println!("Checking config file...");
+            // info!("Checking config file...");
 
-            if Path::new(CONFIG_FILE).exists() {
-                // println!("Existing user: Please enter your passphrase.");
+            if config_file_path().exists() {
+                // info!("Existing user: Please enter your passphrase.");
             } else {
-                // println!("First-time setup: Enter your secret key and set a passphrase.");
+                // info!("First-time setup: Enter your secret key and set a passphrase.");
             }
             app_data.should_repaint = true;
         });
 
-        Self { data, runtime }
+        let tray = tray::start(&_cc.egui_ctx, data.clone(), runtime.handle().clone());
+
+        Self { data, runtime, tray }
     }
 }
 
 fn main() -> eframe::Result<()> {
     env_logger::init(); // 必要に応じて有効化
+    logs::init();
+
+    if let Err(e) = migrate_config_files() {
+        error!("Failed to move config files to the config directory: {e}");
+    }
 
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default().with_inner_size([900.0, 700.0]),
@@ -254,8 +611,33 @@ fn main() -> eframe::Result<()> {
     };
 
     eframe::run_native(
-        "N",
+        WINDOW_TITLE,
         options,
         Box::new(|cc| Ok(Box::new(NostrStatusApp::new(cc)))),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_length_counts_graphemes() {
+        assert_eq!(status_length(""), 0);
+        assert_eq!(status_length("abc"), 3);
+        assert_eq!(status_length("こんにちは"), 5);
+        // 結合した絵文字や濁点も見た目の 1 文字として数える
+        assert_eq!(status_length("👨‍👩‍👧"), 1);
+        assert_eq!(status_length("🇯🇵"), 1);
+        assert_eq!(status_length("か\u{3099}"), 1);
+    }
+
+    #[test]
+    fn status_length_counts_urls_as_fixed_length() {
+        assert_eq!(status_length("https://example.com"), STATUS_URL_LENGTH);
+        assert_eq!(status_length("見て https://example.com/a/very/long/path?with=query"), 3 + STATUS_URL_LENGTH);
+        assert_eq!(status_length("http://a.b と https://c.d です"), STATUS_URL_LENGTH * 2 + 3 + 3);
+        // スキームのないものは URL として扱わない
+        assert_eq!(status_length("example.com"), 11);
+    }
+}
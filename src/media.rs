@@ -0,0 +1,257 @@
+use base64::{Engine as _, engine::general_purpose};
+use nostr::nips::nip96::{self, ServerConfig, UploadRequest, UploadResponse};
+use nostr::{EventBuilder, JsonUtil, Kind, Tag, Timestamp, Url};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::i18n::tr;
+use crate::network;
+use crate::signer::AppSigner;
+use crate::types::{MediaServerKind, Settings};
+
+// アップロードを待つ時間。画像は大きいことがあるので長めにとる
+const UPLOAD_TIMEOUT_SECS: u64 = 60;
+// Blossom の認可イベント (BUD-01) の種類と有効期間
+const BLOSSOM_AUTH_KIND: u16 = 24242;
+const BLOSSOM_AUTH_EXPIRY_SECS: u64 = 5 * 60;
+
+// アップロードしたファイル。投稿するときに imeta タグ (NIP-92) を付けるのに使う
+#[derive(Debug, Clone, PartialEq)]
+pub struct UploadedMedia {
+    pub url: String,
+    pub mime_type: String,
+    pub sha256: String,
+    pub dimensions: Option<(u32, u32)>,
+    // 音声の長さ。WAV のときだけ読み取れる
+    pub duration_secs: Option<f32>,
+}
+
+impl UploadedMedia {
+    pub fn imeta_tag(&self) -> Option<Tag> {
+        let mut fields = vec![
+            "imeta".to_string(),
+            format!("url {}", self.url),
+            format!("m {}", self.mime_type),
+            format!("x {}", self.sha256),
+        ];
+        if let Some((width, height)) = self.dimensions {
+            fields.push(format!("dim {width}x{height}"));
+        }
+        if let Some(duration) = self.duration_secs {
+            fields.push(format!("duration {duration:.1}"));
+        }
+        Tag::parse(fields).ok()
+    }
+}
+
+// 本文に含まれている、このセッションでアップロードしたファイルの imeta タグを返す
+pub fn imeta_tags(content: &str, uploaded: &HashMap<String, UploadedMedia>) -> Vec<Tag> {
+    uploaded
+        .values()
+        .filter(|media| content.contains(&media.url))
+        .filter_map(UploadedMedia::imeta_tag)
+        .collect()
+}
+
+// 拡張子から MIME タイプを決める。画像と音声のほかは添付できない
+fn media_mime_type(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?.to_lowercase();
+    match extension.as_str() {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "gif" => Some("image/gif"),
+        "webp" => Some("image/webp"),
+        "m4a" => Some("audio/mp4"),
+        "mp3" => Some("audio/mpeg"),
+        "ogg" | "opus" => Some("audio/ogg"),
+        "wav" => Some("audio/wav"),
+        _ => None,
+    }
+}
+
+// WAV のヘッダーから長さを計算する。fmt チャンクのバイトレートと data チャンクの大きさを使う
+fn wav_duration_secs(data: &[u8]) -> Option<f32> {
+    if data.len() < 12 || &data[0..4] != b"RIFF" || &data[8..12] != b"WAVE" {
+        return None;
+    }
+    let mut offset = 12;
+    let mut byte_rate = None;
+    while offset + 8 <= data.len() {
+        let chunk_id = &data[offset..offset + 4];
+        let chunk_size = u32::from_le_bytes(data[offset + 4..offset + 8].try_into().ok()?) as usize;
+        let body = offset + 8;
+        if chunk_id == b"fmt " && body + 12 <= data.len() {
+            byte_rate = Some(u32::from_le_bytes(data[body + 8..body + 12].try_into().ok()?));
+        } else if chunk_id == b"data" {
+            let byte_rate = byte_rate.filter(|rate| *rate > 0)?;
+            return Some(chunk_size as f32 / byte_rate as f32);
+        }
+        // チャンクは2バイト境界にそろえる
+        offset = body + chunk_size + chunk_size % 2;
+    }
+    None
+}
+
+fn image_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    image::ImageReader::new(Cursor::new(data)).with_guessed_format().ok()?.into_dimensions().ok()
+}
+
+fn http_agent() -> ureq::Agent {
+    network::http_agent(Duration::from_secs(UPLOAD_TIMEOUT_SECS))
+}
+
+// 画像か音声のファイルを設定のメディアサーバーにアップロードする
+pub async fn upload_media(
+    signer: &AppSigner,
+    settings: &Settings,
+    path: &Path,
+) -> Result<UploadedMedia, Box<dyn std::error::Error + Send + Sync>> {
+    let mime_type = media_mime_type(path).ok_or(tr!("画像 (png, jpg, gif, webp) か音声 (m4a, mp3, ogg, wav) のファイルを選んでください"))?;
+    let data = tokio::fs::read(path).await?;
+    let server_url = Url::parse(settings.media_server_url.trim())?;
+    let file_name = path.file_name().and_then(|name| name.to_str()).unwrap_or("file").to_string();
+    let sha256 = hex::encode(Sha256::digest(&data));
+    let dimensions = if mime_type.starts_with("image/") { image_dimensions(&data) } else { None };
+    let duration_secs = if mime_type == "audio/wav" { wav_duration_secs(&data) } else { None };
+
+    let url = match settings.media_server_kind {
+        MediaServerKind::Nip96 => upload_nip96(signer, server_url, data, &file_name, mime_type).await?,
+        MediaServerKind::Blossom => upload_blossom(signer, server_url, data, &file_name, mime_type, &sha256).await?,
+    };
+    Ok(UploadedMedia {
+        url,
+        mime_type: mime_type.to_string(),
+        sha256,
+        dimensions,
+        duration_secs,
+    })
+}
+
+// NIP-96: サーバーの設定を読み、NIP-98 で署名した multipart/form-data を送る
+async fn upload_nip96(
+    signer: &AppSigner,
+    server_url: Url,
+    data: Vec<u8>,
+    file_name: &str,
+    mime_type: &str,
+) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let config_url = nip96::get_server_config_url(&server_url)?;
+    let config_json = tokio::task::spawn_blocking(move || -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(http_agent().get(config_url.as_str()).call()?.into_string()?)
+    })
+    .await??;
+    let config = ServerConfig::from_json(config_json)?;
+    let request = UploadRequest::new(signer, &config, &data).await?;
+
+    let boundary = format!("----N{:016x}", rand::random::<u64>());
+    let mut body = Vec::with_capacity(data.len() + 256);
+    body.extend_from_slice(
+        format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{file_name}\"\r\nContent-Type: {mime_type}\r\n\r\n"
+        )
+        .as_bytes(),
+    );
+    body.extend_from_slice(&data);
+    body.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+
+    let api_url = request.url().to_string();
+    let authorization = request.authorization().to_string();
+    let response_json = tokio::task::spawn_blocking(move || -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(http_agent()
+            .post(&api_url)
+            .set("Authorization", &authorization)
+            .set("Content-Type", &format!("multipart/form-data; boundary={boundary}"))
+            .send_bytes(&body)?
+            .into_string()?)
+    })
+    .await??;
+    let response = UploadResponse::from_json(response_json)?;
+    Ok(response.download_url()?.to_string())
+}
+
+// Blossom: 認可イベントを Authorization ヘッダーに入れて /upload に PUT する
+async fn upload_blossom(
+    signer: &AppSigner,
+    server_url: Url,
+    data: Vec<u8>,
+    file_name: &str,
+    mime_type: &str,
+    sha256: &str,
+) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let expiration = Timestamp::from(Timestamp::now().as_u64() + BLOSSOM_AUTH_EXPIRY_SECS);
+    let auth_event = EventBuilder::new(Kind::Custom(BLOSSOM_AUTH_KIND), format!("Upload {file_name}"))
+        .tag(Tag::hashtag("upload"))
+        .tag(Tag::parse(["x", sha256])?)
+        .tag(Tag::expiration(expiration))
+        .sign(signer)
+        .await?;
+    let authorization = format!("Nostr {}", general_purpose::STANDARD.encode(auth_event.as_json()));
+    let upload_url = server_url.join("/upload")?.to_string();
+    let mime_type = mime_type.to_string();
+    let response = tokio::task::spawn_blocking(move || -> Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(http_agent()
+            .put(&upload_url)
+            .set("Authorization", &authorization)
+            .set("Content-Type", &mime_type)
+            .send_bytes(&data)?
+            .into_json()?)
+    })
+    .await??;
+    let url = response.get("url").and_then(|url| url.as_str()).ok_or(tr!("Blossom サーバーの応答に URL がありません"))?;
+    Ok(url.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(id: &[u8], body: &[u8]) -> Vec<u8> {
+        let mut chunk = [id, &(body.len() as u32).to_le_bytes()].concat();
+        chunk.extend_from_slice(body);
+        if body.len() % 2 == 1 {
+            chunk.push(0);
+        }
+        chunk
+    }
+
+    fn wav(chunks: &[Vec<u8>]) -> Vec<u8> {
+        let body = chunks.concat();
+        [&b"RIFF"[..], &(body.len() as u32 + 4).to_le_bytes(), b"WAVE", &body].concat()
+    }
+
+    // 8 kHz・16 ビット・モノラル (1 秒 16000 バイト)
+    fn fmt_chunk() -> Vec<u8> {
+        let mut body = Vec::new();
+        body.extend_from_slice(&1u16.to_le_bytes());
+        body.extend_from_slice(&1u16.to_le_bytes());
+        body.extend_from_slice(&8000u32.to_le_bytes());
+        body.extend_from_slice(&16000u32.to_le_bytes());
+        body.extend_from_slice(&2u16.to_le_bytes());
+        body.extend_from_slice(&16u16.to_le_bytes());
+        chunk(b"fmt ", &body)
+    }
+
+    #[test]
+    fn wav_duration_from_the_data_chunk() {
+        let data = wav(&[fmt_chunk(), chunk(b"data", &[0; 8000])]);
+        assert_eq!(wav_duration_secs(&data), Some(0.5));
+    }
+
+    #[test]
+    fn wav_duration_skips_padded_chunks() {
+        // 奇数の長さのチャンクのあとは 1 バイト詰め物がある
+        let data = wav(&[fmt_chunk(), chunk(b"LIST", b"odd"), chunk(b"data", &[0; 32000])]);
+        assert_eq!(wav_duration_secs(&data), Some(2.0));
+    }
+
+    #[test]
+    fn wav_duration_needs_a_wav_with_a_byte_rate() {
+        assert_eq!(wav_duration_secs(b"not a wav file"), None);
+        // fmt より前に data がある
+        assert_eq!(wav_duration_secs(&wav(&[chunk(b"data", &[0; 16]), fmt_chunk()])), None);
+        assert_eq!(wav_duration_secs(&wav(&[fmt_chunk()])), None);
+    }
+}
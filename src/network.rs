@@ -0,0 +1,235 @@
+use nostr::util::BoxedFuture;
+use nostr::{Event, Filter, RelayUrl, SubscriptionId};
+use nostr_sdk::prelude::{
+    AdmitPolicy, AdmitStatus, Connection, ConnectionMode, Events, IntoNostrSigner, PolicyError, RelayOptions, TryIntoUrl,
+};
+use nostr_sdk::{Client, ClientOptions, NostrLMDB, pool};
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::path::Path;
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::Duration;
+use tracing::warn;
+
+use crate::event_check;
+use crate::i18n::tr;
+use crate::relay_debug::RecordingTransport;
+use crate::types::Settings;
+
+// リレーへの接続方法と待ち時間。設定からアプリ全体に適用し、以降に作るクライアントと取得はすべてこれに従う
+#[derive(Debug, Clone, Copy)]
+struct NetworkConfig {
+    proxy: Option<SocketAddr>,
+    tor_only: bool,
+    // リレーからイベントを取得するときに待つ時間
+    fetch_timeout: Duration,
+    // 発見リレーで自分の NIP-65 リレーリストを探す時間
+    discovery_timeout: Duration,
+    // リレーに接続してからつながるのを待つ時間。取得をやり直す前にもこれだけ待つ
+    connect_wait: Duration,
+    // 取得に失敗したときにやり直す回数
+    retry_count: u32,
+}
+
+static NETWORK_CONFIG: RwLock<NetworkConfig> = RwLock::new(NetworkConfig {
+    proxy: None,
+    tor_only: false,
+    fetch_timeout: Duration::from_secs(10),
+    discovery_timeout: Duration::from_secs(10),
+    connect_wait: Duration::from_secs(2),
+    retry_count: 1,
+});
+
+// HTTP の取得に使うプロキシ。ureq の Proxy はコピーできないので、NETWORK_CONFIG とは別に持つ
+static HTTP_PROXY: RwLock<Option<ureq::Proxy>> = RwLock::new(None);
+
+// 受信したイベントを保存するデータベース。すべてのクライアントで共有し、オフラインでの起動時に表示に使う
+static EVENT_DATABASE: OnceLock<Arc<NostrLMDB>> = OnceLock::new();
+
+// 届いたイベントの id と署名を確かめ、不正なものは受け取らない
+// Torのみのときは、.onion 以外のリレーへの接続も拒否する
+#[derive(Debug)]
+struct RelayPolicy {
+    tor_only: bool,
+}
+
+impl AdmitPolicy for RelayPolicy {
+    fn admit_connection<'a>(
+        &'a self,
+        relay_url: &'a RelayUrl,
+    ) -> BoxedFuture<'a, Result<AdmitStatus, PolicyError>> {
+        Box::pin(async move {
+            if !self.tor_only || relay_url.is_onion() {
+                Ok(AdmitStatus::Success)
+            } else {
+                Ok(AdmitStatus::rejected(tr!("Torのみの設定のため .onion 以外のリレーには接続しません")))
+            }
+        })
+    }
+
+    fn admit_event<'a>(
+        &'a self,
+        relay_url: &'a RelayUrl,
+        _subscription_id: &'a SubscriptionId,
+        event: &'a Event,
+    ) -> BoxedFuture<'a, Result<AdmitStatus, PolicyError>> {
+        Box::pin(async move {
+            if event_check::check_received(event, relay_url.as_str()) {
+                Ok(AdmitStatus::Success)
+            } else {
+                Ok(AdmitStatus::rejected("invalid id or signature"))
+            }
+        })
+    }
+}
+
+// "host:port" 形式のプロキシのアドレスを解決する。空欄ならプロキシを使わない
+pub fn parse_proxy_addr(proxy: &str) -> Result<Option<SocketAddr>, String> {
+    let proxy = proxy.trim();
+    if proxy.is_empty() {
+        return Ok(None);
+    }
+    proxy
+        .to_socket_addrs()
+        .map_err(|e| tr!("プロキシのアドレスが不正です ({}): {}", proxy, e))?
+        .next()
+        .map(Some)
+        .ok_or_else(|| tr!("プロキシのアドレスを解決できません: {}", proxy))
+}
+
+// 設定のプロキシとTorのみの指定を、これから作るクライアントに適用する。待ち時間とやり直す回数はすぐに反映する
+pub fn apply_network_settings(settings: &Settings) -> Result<(), String> {
+    {
+        let mut config = NETWORK_CONFIG.write().unwrap();
+        config.fetch_timeout = settings.relay_timeout();
+        config.discovery_timeout = Duration::from_secs(settings.discovery_timeout_secs);
+        config.connect_wait = Duration::from_secs(settings.connect_wait_secs);
+        config.retry_count = settings.fetch_retry_count;
+    }
+    let proxy = parse_proxy_addr(&settings.proxy)?;
+    if settings.tor_only && proxy.is_none() {
+        return Err(tr!("Torのみを有効にするにはプロキシ (例: 127.0.0.1:9050) を指定してください").to_string());
+    }
+    // 名前解決もプロキシに任せるよう、SOCKS5 でホスト名のまま渡す
+    let http_proxy = match proxy {
+        Some(proxy) => Some(
+            ureq::Proxy::new(format!("socks5://{}:{}", proxy.ip(), proxy.port()))
+                .map_err(|e| tr!("プロキシのアドレスが不正です ({}): {}", settings.proxy.trim(), e))?,
+        ),
+        None => None,
+    };
+    *HTTP_PROXY.write().unwrap() = http_proxy;
+    let mut config = NETWORK_CONFIG.write().unwrap();
+    config.proxy = proxy;
+    config.tor_only = settings.tor_only;
+    Ok(())
+}
+
+// 通話は UDP で直接つなぐので、Tor のみのときは使わない
+#[cfg(feature = "call")]
+pub fn is_tor_only() -> bool {
+    network_config().tor_only
+}
+
+// リレー以外の HTTP の取得 (NIP-05、リンクカード、画像、ZAP、アップロードなど) に使うエージェント
+// プロキシを設定していれば、リレーと同じくプロキシを通す
+pub fn http_agent(timeout: Duration) -> ureq::Agent {
+    let mut builder = ureq::AgentBuilder::new().timeout(timeout);
+    if let Some(proxy) = HTTP_PROXY.read().unwrap().clone() {
+        builder = builder.proxy(proxy);
+    }
+    builder.build()
+}
+
+// イベントのデータベースを開く。起動時に一度だけ呼ぶ
+pub fn open_event_database(path: &Path) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    std::fs::create_dir_all(path)?;
+    let database = NostrLMDB::open(path)?;
+    EVENT_DATABASE.set(Arc::new(database)).map_err(|_| "event database is already open")?;
+    Ok(())
+}
+
+fn network_config() -> NetworkConfig {
+    *NETWORK_CONFIG.read().unwrap()
+}
+
+// プロキシ設定とイベントのデータベースを反映したクライアントを作る。Client::new の代わりに使う
+pub fn new_client<T>(signer: T) -> Client
+where
+    T: IntoNostrSigner,
+{
+    let config = network_config();
+    let mut opts = ClientOptions::new();
+    if let Some(proxy) = config.proxy {
+        opts = opts.connection(Connection::new().proxy(proxy));
+    }
+    let mut builder = Client::builder().signer(signer).opts(opts).websocket_transport(RecordingTransport);
+    if let Some(database) = EVENT_DATABASE.get() {
+        builder = builder.database(database.clone());
+    }
+    builder.admit_policy(RelayPolicy { tor_only: config.tor_only }).build()
+}
+
+// NIP-46のリモート署名者など、Client を経由しない接続に使うリレーの設定
+pub fn relay_options() -> RelayOptions {
+    RelayOptions::new().connection_mode(connection_mode())
+}
+
+// リレーに直接 WebSocket でつなぐときの接続方法
+pub fn connection_mode() -> ConnectionMode {
+    match network_config().proxy {
+        Some(proxy) => ConnectionMode::proxy(proxy),
+        None => ConnectionMode::Direct,
+    }
+}
+
+pub fn fetch_timeout() -> Duration {
+    network_config().fetch_timeout
+}
+
+pub fn discovery_timeout() -> Duration {
+    network_config().discovery_timeout
+}
+
+pub fn connect_wait() -> Duration {
+    network_config().connect_wait
+}
+
+// 失敗したら、つながるのを待ってから設定の回数だけやり直す
+async fn with_retry<T, F, Fut>(mut attempt: F) -> Result<T, nostr_sdk::client::Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, nostr_sdk::client::Error>>,
+{
+    let config = network_config();
+    let mut retries = 0;
+    loop {
+        match attempt().await {
+            Err(e) if retries < config.retry_count => {
+                retries += 1;
+                warn!("Failed to fetch events, retrying ({retries}/{}): {e}", config.retry_count);
+                tokio::time::sleep(config.connect_wait).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+// client.fetch_events の代わりに使う。設定の待ち時間で取得し、失敗したらやり直す
+pub async fn fetch_events(client: &Client, filter: Filter) -> Result<Events, nostr_sdk::client::Error> {
+    with_retry(|| client.fetch_events(filter.clone(), fetch_timeout())).await
+}
+
+// client.fetch_events_from の代わりに使う
+pub async fn fetch_events_from<I, U>(client: &Client, urls: I, filter: Filter) -> Result<Events, nostr_sdk::client::Error>
+where
+    I: IntoIterator<Item = U> + Clone,
+    U: TryIntoUrl,
+    pool::pool::Error: From<<U as TryIntoUrl>::Err>,
+{
+    with_retry(|| client.fetch_events_from(urls.clone(), filter.clone(), fetch_timeout())).await
+}
+
+// Torのみの設定で接続してよいリレーか
+pub fn is_relay_allowed(relay_url: &RelayUrl) -> bool {
+    !network_config().tor_only || relay_url.is_onion()
+}
@@ -0,0 +1,45 @@
+use bip39::Mnemonic;
+use nostr::Keys;
+use nostr::nips::nip06::FromMnemonic;
+use rand::rngs::OsRng;
+use rand::{Rng, seq::index};
+use zeroize::{Zeroize, Zeroizing};
+
+// 新しい鍵のシードフレーズは 12 単語 (128 ビット)
+const NEW_MNEMONIC_ENTROPY_BYTES: usize = 16;
+// 書き留めたか確かめるときに入力してもらう単語の数
+const VERIFY_WORD_COUNT: usize = 3;
+
+/// Generates a new English BIP-39 mnemonic for a fresh key.
+pub fn generate_mnemonic() -> Result<Zeroizing<String>, Box<dyn std::error::Error + Send + Sync>> {
+    let mut entropy = [0u8; NEW_MNEMONIC_ENTROPY_BYTES];
+    OsRng.fill(&mut entropy);
+    let result = Mnemonic::from_entropy(&entropy);
+    entropy.zeroize();
+    Ok(Zeroizing::new(result?.to_string()))
+}
+
+/// Returns true when the input has the word count of a BIP-39 mnemonic rather than a single key string.
+pub fn looks_like_mnemonic(input: &str) -> bool {
+    matches!(input.split_whitespace().count(), 12 | 15 | 18 | 21 | 24)
+}
+
+/// Derives the keys for `m/44'/1237'/<account>'/0/0` as described in NIP-06.
+/// An empty passphrase means no BIP-39 passphrase.
+pub fn keys_from_mnemonic(
+    mnemonic: &str,
+    passphrase: &str,
+    account: u32,
+) -> Result<Keys, Box<dyn std::error::Error + Send + Sync>> {
+    // 大文字や余分な空白が混ざっていても同じ単語として扱う
+    let normalized = Zeroizing::new(mnemonic.split_whitespace().map(str::to_lowercase).collect::<Vec<_>>().join(" "));
+    let passphrase = (!passphrase.is_empty()).then_some(passphrase);
+    Ok(Keys::from_mnemonic_with_account(normalized.as_str(), passphrase, Some(account))?)
+}
+
+/// Picks the word positions (0-based, ascending) the user must type back to confirm the backup.
+pub fn pick_verify_positions(word_count: usize) -> Vec<usize> {
+    let mut positions = index::sample(&mut OsRng, word_count, VERIFY_WORD_COUNT.min(word_count)).into_vec();
+    positions.sort_unstable();
+    positions
+}
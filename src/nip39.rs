@@ -0,0 +1,120 @@
+use nostr::nips::nip19::ToBech32;
+use nostr::nips::nip39::{ExternalIdentity, Identity};
+use nostr::{PublicKey, Tag, TagStandard, Tags};
+use std::time::Duration;
+use tracing::error;
+
+use crate::network;
+use crate::types::IdentityState;
+
+// プロフィール (kind 0) の i タグで、外部のアカウントが自分のものだと主張する (NIP-39)
+// 証明は相手のサービスに投稿した「この公開鍵を持っている」という文。GitHub と Mastodon は投稿を読んで確かめる
+
+// 証明の投稿を待つ時間
+const PROOF_TIMEOUT_SECS: u64 = 10;
+
+// 証明の投稿に書いてもらう文。後ろに npub を付ける
+pub const PROOF_TEXT_PREFIX: &str = "Verifying that I control the following Nostr public key:";
+
+pub fn identities(tags: &Tags) -> Vec<Identity> {
+    tags.iter()
+        .filter_map(|tag| match tag.as_standardized() {
+            Some(TagStandard::ExternalIdentity(identity)) => Some(identity.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+// 入力途中で空欄のあるものは付けない
+pub fn identity_tags(identities: &[Identity]) -> Vec<Tag> {
+    identities
+        .iter()
+        .filter(|identity| !identity.ident.trim().is_empty() && !identity.proof.trim().is_empty())
+        .map(|identity| {
+            Tag::from_standardized(TagStandard::ExternalIdentity(Identity {
+                platform: identity.platform.clone(),
+                ident: identity.ident.trim().to_string(),
+                proof: identity.proof.trim().to_string(),
+            }))
+        })
+        .collect()
+}
+
+pub fn platform_name(platform: &ExternalIdentity) -> &'static str {
+    match platform {
+        ExternalIdentity::GitHub => "GitHub",
+        ExternalIdentity::Twitter => "Twitter",
+        ExternalIdentity::Mastodon => "Mastodon",
+        ExternalIdentity::Telegram => "Telegram",
+    }
+}
+
+// 証明の投稿の URL
+pub fn proof_url(identity: &Identity) -> String {
+    match identity.platform {
+        ExternalIdentity::GitHub => format!("https://gist.github.com/{}/{}", identity.ident, identity.proof),
+        ExternalIdentity::Twitter => format!("https://twitter.com/{}/status/{}", identity.ident, identity.proof),
+        // ident は "mastodon.social/@user" の形
+        ExternalIdentity::Mastodon => format!("https://{}/{}", identity.ident, identity.proof),
+        ExternalIdentity::Telegram => format!("https://t.me/{}", identity.proof),
+    }
+}
+
+// 公開鍵と主張の組をキーにする。どちらかが変われば確認し直す
+pub fn cache_key(public_key: &PublicKey, identity: &Identity) -> String {
+    format!("{public_key}:{}:{}:{}", identity.platform, identity.ident, identity.proof)
+}
+
+fn agent() -> ureq::Agent {
+    network::http_agent(Duration::from_secs(PROOF_TIMEOUT_SECS))
+}
+
+// 証明の投稿を取得する。投稿がなければ None
+fn fetch_json(url: &str) -> Result<Option<serde_json::Value>, Box<dyn std::error::Error + Send + Sync>> {
+    match agent().get(url).call() {
+        Ok(response) => Ok(Some(response.into_json()?)),
+        Err(ureq::Error::Status(404, _)) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+// Gist の持ち主が ident と同じで、どれかのファイルに npub が書かれていれば確認済み
+fn verify_github(npub: &str, identity: &Identity) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    let Some(gist) = fetch_json(&format!("https://api.github.com/gists/{}", identity.proof))? else {
+        return Ok(false);
+    };
+    let owner = gist["owner"]["login"].as_str().unwrap_or_default();
+    let has_npub = gist["files"]
+        .as_object()
+        .is_some_and(|files| files.values().any(|file| file["content"].as_str().is_some_and(|content| content.contains(npub))));
+    Ok(owner.eq_ignore_ascii_case(&identity.ident) && has_npub)
+}
+
+// トゥートの投稿者が ident のユーザーで、本文に npub が書かれていれば確認済み
+fn verify_mastodon(npub: &str, identity: &Identity) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    let (instance, username) = identity.ident.split_once("/@").ok_or("invalid Mastodon identity")?;
+    let Some(status) = fetch_json(&format!("https://{instance}/api/v1/statuses/{}", identity.proof))? else {
+        return Ok(false);
+    };
+    let author = status["account"]["username"].as_str().unwrap_or_default();
+    let content = status["content"].as_str().unwrap_or_default();
+    Ok(author.eq_ignore_ascii_case(username) && content.contains(npub))
+}
+
+pub fn fetch_identity_state(public_key: &PublicKey, identity: &Identity) -> IdentityState {
+    let Ok(npub) = public_key.to_bech32();
+    let result = match identity.platform {
+        ExternalIdentity::GitHub => verify_github(&npub, identity),
+        ExternalIdentity::Mastodon => verify_mastodon(&npub, identity),
+        // API の鍵がないと投稿を読めない
+        ExternalIdentity::Twitter | ExternalIdentity::Telegram => return IdentityState::Unverifiable,
+    };
+    match result {
+        Ok(true) => IdentityState::Verified,
+        Ok(false) => IdentityState::Mismatch,
+        Err(e) => {
+            error!("Failed to verify the {} identity {}: {e}", identity.platform, identity.ident);
+            IdentityState::Failed
+        }
+    }
+}
@@ -5,64 +5,92 @@ use chacha20poly1305::{
 use pbkdf2::pbkdf2_hmac;
 use sha2::Sha256;
 use base64::{Engine as _, engine::general_purpose};
+use nostr::nips::nip19::{FromBech32, ToBech32};
+use nostr::nips::nip49::{EncryptedSecretKey, KeySecurity};
+use nostr::SecretKey;
 use rand::Rng;
 use rand::rngs::OsRng;
+use zeroize::Zeroizing;
 
-const PBKDF2_ROUNDS: u32 = 100_000;
+use crate::argon2id;
+use crate::types::Kdf;
 
-/// Encrypts plaintext using NIP-49 spec.
-/// Returns a tuple of `(encrypted_base64_string, salt_base64_string)`.
-pub fn encrypt(
-    plaintext: &[u8],
-    passphrase: &str,
-) -> Result<(String, String), Box<dyn std::error::Error + Send + Sync>> {
-    let mut salt_bytes = [0u8; 16];
-    OsRng.fill(&mut salt_bytes);
-    let salt_base64 = general_purpose::STANDARD.encode(salt_bytes);
-
-    let mut derived_key_bytes = [0u8; 32];
-    pbkdf2_hmac::<Sha256>(
-        passphrase.as_bytes(),
-        &salt_bytes,
-        PBKDF2_ROUNDS,
-        &mut derived_key_bytes,
-    );
-    let cipher_key = Key::from_slice(&derived_key_bytes);
-    let cipher = ChaCha20Poly1305::new(cipher_key);
+// NIP-49 が推奨する scrypt のコストパラメータ (2^16)
+const SCRYPT_LOG_N: u8 = 16;
+// 以前のバージョンが保存していた独自形式の接頭辞
+const LEGACY_PREFIX: &str = "#nip49:";
 
-    let mut nonce_bytes: [u8; 12] = [0u8; 12];
-    OsRng.fill(&mut nonce_bytes);
-    let nonce = Nonce::from_slice(&nonce_bytes);
+/// Encrypts a secret key into a standard NIP-49 `ncryptsec1...` string.
+pub fn encrypt_secret_key(
+    secret_key: &SecretKey,
+    passphrase: &str,
+) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let encrypted = EncryptedSecretKey::new(secret_key, passphrase, SCRYPT_LOG_N, KeySecurity::Medium)?;
+    Ok(encrypted.to_bech32()?)
+}
 
-    let ciphertext_with_tag = cipher
-        .encrypt(nonce, plaintext)
-        .map_err(|e| format!("NIP-49 encryption error: {e:?}"))?;
+/// Decrypts a stored secret key.
+/// Accepts both `ncryptsec1...` and the legacy `#nip49:` format, which needs the config salt.
+/// The legacy format was always written with PBKDF2.
+pub fn decrypt_secret_key(
+    encrypted: &str,
+    passphrase: &str,
+    salt_base64: &str,
+) -> Result<SecretKey, Box<dyn std::error::Error + Send + Sync>> {
+    if is_legacy_format(encrypted) {
+        let decrypted_bytes = decrypt(encrypted, passphrase, salt_base64, &Kdf::legacy())?;
+        return Ok(SecretKey::from_slice(&decrypted_bytes)?);
+    }
+    let encrypted = EncryptedSecretKey::from_bech32(encrypted.trim())?;
+    encrypted
+        .decrypt(passphrase)
+        .map_err(|_| "Incorrect passphrase or corrupted data".into())
+}
 
-    let mut encoded_data = ciphertext_with_tag;
-    encoded_data.extend_from_slice(nonce_bytes.as_ref());
+/// Returns true if the value was written in the legacy `#nip49:` format.
+pub fn is_legacy_format(encrypted: &str) -> bool {
+    encrypted.starts_with(LEGACY_PREFIX)
+}
 
-    let nip49_encoded = format!("#nip49:{}", general_purpose::STANDARD.encode(&encoded_data));
+/// Generates a random salt for `encrypt_with_salt`.
+pub fn generate_salt() -> String {
+    let mut salt_bytes = [0u8; 16];
+    OsRng.fill(&mut salt_bytes);
+    general_purpose::STANDARD.encode(salt_bytes)
+}
 
-    Ok((nip49_encoded, salt_base64))
+/// Derives a 32-byte key from the passphrase and the config salt with the given KDF.
+/// The key is wiped from memory when dropped.
+fn derive_key(
+    passphrase: &str,
+    salt_base64: &str,
+    kdf: &Kdf,
+) -> Result<Zeroizing<[u8; 32]>, Box<dyn std::error::Error + Send + Sync>> {
+    let salt_bytes = general_purpose::STANDARD.decode(salt_base64)?;
+    let mut derived_key_bytes = Zeroizing::new([0u8; 32]);
+    match *kdf {
+        Kdf::Pbkdf2 { iterations } => {
+            pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), &salt_bytes, iterations, derived_key_bytes.as_mut());
+        }
+        Kdf::Argon2id { memory_kib, iterations, parallelism } => {
+            let hash = argon2id::hash(passphrase.as_bytes(), &salt_bytes, memory_kib, iterations, parallelism, 32)?;
+            derived_key_bytes.copy_from_slice(&hash);
+        }
+    }
+    Ok(derived_key_bytes)
 }
 
-/// Encrypts plaintext using NIP-49 spec with a pre-existing salt.
+/// Encrypts plaintext with a passphrase-derived key (Argon2id or PBKDF2 + ChaCha20-Poly1305).
+/// Used for settings other than the secret key, such as the NWC URI.
 /// Returns the encrypted_base64_string.
 pub fn encrypt_with_salt(
     plaintext: &[u8],
     passphrase: &str,
     salt_base64: &str,
+    kdf: &Kdf,
 ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
-    let salt_bytes = general_purpose::STANDARD.decode(salt_base64)?;
-
-    let mut derived_key_bytes = [0u8; 32];
-    pbkdf2_hmac::<Sha256>(
-        passphrase.as_bytes(),
-        &salt_bytes,
-        PBKDF2_ROUNDS,
-        &mut derived_key_bytes,
-    );
-    let cipher_key = Key::from_slice(&derived_key_bytes);
+    let derived_key_bytes = derive_key(passphrase, salt_base64, kdf)?;
+    let cipher_key = Key::from_slice(derived_key_bytes.as_ref());
     let cipher = ChaCha20Poly1305::new(cipher_key);
 
     let mut nonce_bytes: [u8; 12] = [0u8; 12];
@@ -76,35 +104,29 @@ pub fn encrypt_with_salt(
     let mut encoded_data = ciphertext_with_tag;
     encoded_data.extend_from_slice(nonce_bytes.as_ref());
 
-    let nip49_encoded = format!("#nip49:{}", general_purpose::STANDARD.encode(&encoded_data));
+    let nip49_encoded = format!("{LEGACY_PREFIX}{}", general_purpose::STANDARD.encode(&encoded_data));
 
     Ok(nip49_encoded)
 }
 
 
-/// Decrypts a NIP-49 encoded string.
+/// Decrypts a string produced by `encrypt_with_salt`. `kdf` must be the one it was encrypted with.
+/// The plaintext is wiped from memory when dropped.
 pub fn decrypt(
     nip49_encoded: &str,
     passphrase: &str,
     salt_base64: &str,
-) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
-    if !nip49_encoded.starts_with("#nip49:") {
+    kdf: &Kdf,
+) -> Result<Zeroizing<Vec<u8>>, Box<dyn std::error::Error + Send + Sync>> {
+    if !is_legacy_format(nip49_encoded) {
         return Err("Invalid NIP-49 format".into());
     }
 
-    let salt_bytes = general_purpose::STANDARD.decode(salt_base64)?;
-
-    let mut derived_key_bytes = [0u8; 32];
-    pbkdf2_hmac::<Sha256>(
-        passphrase.as_bytes(),
-        &salt_bytes,
-        PBKDF2_ROUNDS,
-        &mut derived_key_bytes,
-    );
-    let cipher_key = Key::from_slice(&derived_key_bytes);
+    let derived_key_bytes = derive_key(passphrase, salt_base64, kdf)?;
+    let cipher_key = Key::from_slice(derived_key_bytes.as_ref());
     let cipher = ChaCha20Poly1305::new(cipher_key);
 
-    let decoded_bytes = general_purpose::STANDARD.decode(&nip49_encoded[7..])?;
+    let decoded_bytes = general_purpose::STANDARD.decode(&nip49_encoded[LEGACY_PREFIX.len()..])?;
     if decoded_bytes.len() < 12 {
         return Err("Invalid NIP-49 payload".into());
     }
@@ -116,5 +138,82 @@ pub fn decrypt(
         .decrypt(nonce, ciphertext_and_tag)
         .map_err(|_| "Incorrect passphrase or corrupted data")?;
 
-    Ok(decrypted_bytes)
+    Ok(Zeroizing::new(decrypted_bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // テストでは時間をかけないよう、軽いパラメータにする
+    const FAST_PBKDF2: Kdf = Kdf::Pbkdf2 { iterations: 1_000 };
+    const FAST_ARGON2ID: Kdf = Kdf::Argon2id { memory_kib: 64, iterations: 1, parallelism: 1 };
+
+    #[test]
+    fn legacy_secret_key_migrates_to_ncryptsec() {
+        let secret_key = SecretKey::generate();
+        let salt = generate_salt();
+        let legacy = encrypt_with_salt(secret_key.as_secret_bytes(), "passphrase", &salt, &Kdf::legacy()).unwrap();
+        assert!(is_legacy_format(&legacy));
+        let decrypted = decrypt_secret_key(&legacy, "passphrase", &salt).unwrap();
+        assert_eq!(decrypted, secret_key);
+
+        // ログイン時と同じく、復号した鍵を ncryptsec で保存し直す
+        let migrated = encrypt_secret_key(&decrypted, "passphrase").unwrap();
+        assert!(migrated.starts_with("ncryptsec1"));
+        assert!(!is_legacy_format(&migrated));
+        // ncryptsec は塩を自分で持っているので、設定ファイルの塩は使わない
+        assert_eq!(decrypt_secret_key(&migrated, "passphrase", "").unwrap(), secret_key);
+        assert!(decrypt_secret_key(&migrated, "wrong passphrase", "").is_err());
+    }
+
+    #[test]
+    fn legacy_secret_key_rejects_wrong_passphrase() {
+        let secret_key = SecretKey::generate();
+        let salt = generate_salt();
+        let legacy = encrypt_with_salt(secret_key.as_secret_bytes(), "passphrase", &salt, &Kdf::legacy()).unwrap();
+        assert!(decrypt_secret_key(&legacy, "wrong passphrase", &salt).is_err());
+    }
+
+    #[test]
+    fn round_trips_with_each_kdf() {
+        let salt = generate_salt();
+        for kdf in [FAST_PBKDF2, FAST_ARGON2ID] {
+            let encrypted = encrypt_with_salt(b"nostr+walletconnect://example", "passphrase", &salt, &kdf).unwrap();
+            assert!(is_legacy_format(&encrypted));
+            let decrypted = decrypt(&encrypted, "passphrase", &salt, &kdf).unwrap();
+            assert_eq!(decrypted.as_slice(), b"nostr+walletconnect://example");
+            assert!(decrypt(&encrypted, "wrong passphrase", &salt, &kdf).is_err());
+        }
+    }
+
+    #[test]
+    fn decrypt_needs_the_kdf_it_was_encrypted_with() {
+        let salt = generate_salt();
+        let encrypted = encrypt_with_salt(b"secret", "passphrase", &salt, &FAST_PBKDF2).unwrap();
+        assert!(decrypt(&encrypted, "passphrase", &salt, &FAST_ARGON2ID).is_err());
+        assert!(decrypt(&encrypted, "passphrase", &generate_salt(), &FAST_PBKDF2).is_err());
+    }
+
+    #[test]
+    fn derive_key_selects_the_kdf() {
+        let salt = general_purpose::STANDARD.encode(b"somesalt");
+        let pbkdf2 = derive_key("password", &salt, &FAST_PBKDF2).unwrap();
+        let mut expected = [0u8; 32];
+        pbkdf2_hmac::<Sha256>(b"password", b"somesalt", 1_000, &mut expected);
+        assert_eq!(*pbkdf2, expected);
+
+        let argon2 = derive_key("password", &salt, &FAST_ARGON2ID).unwrap();
+        let expected = argon2id::hash(b"password", b"somesalt", 64, 1, 1, 32).unwrap();
+        assert_eq!(argon2.as_slice(), expected.as_slice());
+        assert_ne!(*argon2, *pbkdf2);
+    }
+
+    #[test]
+    fn decrypt_rejects_other_formats() {
+        let salt = generate_salt();
+        assert!(decrypt("ncryptsec1abc", "passphrase", &salt, &FAST_PBKDF2).is_err());
+        assert!(decrypt(&format!("{LEGACY_PREFIX}AAAA"), "passphrase", &salt, &FAST_PBKDF2).is_err());
+        assert!(decrypt_secret_key("not a key", "passphrase", &salt).is_err());
+    }
 }
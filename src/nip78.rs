@@ -0,0 +1,54 @@
+use nostr::{EventBuilder, Filter, Kind, NostrSigner, Tag, Timestamp};
+use nostr_sdk::Client;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use crate::network;
+use crate::signer::AppSigner;
+
+// アプリ固有のデータ (NIP-78, kind 30078) を、自分宛てに NIP-44 で暗号化して保存する
+// 端末をまたいで同じデータを使えるが、リレーやほかの人からは中身が読めない
+
+// ほかのアプリのデータと区別するため d タグの前に付ける
+const APP_DATA_PREFIX: &str = "N";
+
+fn app_data_d_tag(key: &str) -> String {
+    format!("{APP_DATA_PREFIX}/{key}")
+}
+
+// 保存したデータと、それを保存した時刻を返す。まだ保存していなければ None
+pub async fn fetch_app_data<T: DeserializeOwned>(
+    client: &Client,
+    signer: &AppSigner,
+    key: &str,
+) -> Result<Option<(T, Timestamp)>, Box<dyn std::error::Error + Send + Sync>> {
+    let filter = Filter::new()
+        .author(signer.public_key())
+        .kind(Kind::ApplicationSpecificData)
+        .identifier(app_data_d_tag(key))
+        .limit(1);
+    let events = network::fetch_events(client, filter).await?;
+    // 複数のリレーから返ってきた場合は最新のものを使う
+    let Some(event) = events.into_iter().max_by_key(|event| event.created_at) else {
+        return Ok(None);
+    };
+    let json = signer.nip44_decrypt(&signer.public_key(), &event.content).await?;
+    Ok(Some((serde_json::from_str(&json)?, event.created_at)))
+}
+
+// データを暗号化して保存し、保存した時刻を返す
+pub async fn publish_app_data<T: Serialize>(
+    client: &Client,
+    signer: &AppSigner,
+    key: &str,
+    value: &T,
+) -> Result<Timestamp, Box<dyn std::error::Error + Send + Sync>> {
+    let json = serde_json::to_string(value)?;
+    let content = signer.nip44_encrypt(&signer.public_key(), &json).await?;
+    let event = EventBuilder::new(Kind::ApplicationSpecificData, content)
+        .tag(Tag::identifier(app_data_d_tag(key)))
+        .sign(signer)
+        .await?;
+    client.send_event(&event).await?;
+    Ok(event.created_at)
+}
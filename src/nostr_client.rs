@@ -1,33 +1,46 @@
 use futures::future::join_all;
-use nostr::{Filter, Keys, Kind, PublicKey, Tag as NostrTag, nips::nip19::ToBech32};
-use nostr_sdk::{Client, ClientOptions as Options, SubscribeAutoCloseOptions};
+use futures::stream::{FuturesUnordered, StreamExt};
+use nostr::{
+    Event, EventBuilder, Filter, Kind, PublicKey, RelayUrl, Tag as NostrTag, TagStandard, Timestamp,
+    nips::{nip19::{FromBech32, Nip19Profile, ToBech32}, nip21::FromNostrUri},
+};
+use nostr_sdk::{Client, SubscribeAutoCloseOptions, SyncDirection, SyncOptions, nips::nip65::RelayMetadata, prelude::Events};
 use std::collections::{HashMap, HashSet};
+use std::sync::{LazyLock, Mutex};
 use std::time::Duration;
-
-use crate::types::{ProfileMetadata, TimelinePost};
+use tracing::{error, info};
+
+use crate::event_check;
+use crate::i18n::tr;
+use crate::network::{self, new_client};
+use crate::nip39;
+use crate::note;
+use crate::replaceable::dedupe_events;
+use crate::signer::AppSigner;
+use crate::types::{EditableRelay, ProfileCounts, ProfileMetadata, Settings, TimelinePost, UserProfileData};
+
+// プロフィールページに表示する最近のノートの件数
+const USER_PROFILE_NOTES_LIMIT: usize = 20;
+// アウトボックスモデルで1人あたりに問い合わせる書き込みリレーの上限
+const MAX_WRITE_RELAYS_PER_AUTHOR: usize = 3;
+// NIP-45 の COUNT の返事を待つ時間
+const COUNT_TIMEOUT_SECS: u64 = 5;
 
 // NIP-65とフォールバックを考慮したリレー接続関数
 pub async fn connect_to_relays_with_nip65(
     client: &Client,
-    keys: &Keys,
+    keys: &AppSigner,
     discover_relays_str: &str,
     default_relays_str: &str,
+    manual_relays: &[EditableRelay],
 ) -> Result<(String, Vec<(String, Option<String>)>), Box<dyn std::error::Error + Send + Sync>> {
     let bootstrap_relays: Vec<String> =
         discover_relays_str.lines().map(|s| s.to_string()).collect();
 
-    let client_opts = Options::new();
-    let discover_client = Client::builder()
-        .signer(keys.clone())
-        .opts(client_opts)
-        .build();
-    discover_client.connect().await;
-    discover_client
-        .wait_for_connection(Duration::from_secs(30))
-        .await;
+    let discover_client = new_client(keys.clone());
 
     let mut status_log = String::new();
-    status_log.push_str("NIP-65リレーリストを取得するためにDiscoverリレーに並列接続中...\n");
+    status_log.push_str(tr!("NIP-65リレーリストを取得するためにDiscoverリレーに並列接続中...\n"));
 
     let add_relay_futures = bootstrap_relays.iter().map(|url| {
         let discover_client = &discover_client;
@@ -39,21 +52,23 @@ pub async fn connect_to_relays_with_nip65(
     for (i, result) in results.into_iter().enumerate() {
         let url = &bootstrap_relays[i];
         match result {
-            Ok(_) => status_log.push_str(&format!("  Discoverリレー追加: {url}\n")),
+            Ok(_) => status_log.push_str(&tr!("  Discoverリレー追加: {}\n", url)),
             Err(e) => {
-                status_log.push_str(&format!("  Discoverリレー追加失敗: {url} - エラー: {e}\n"))
+                status_log.push_str(&tr!("  Discoverリレー追加失敗: {} - エラー: {}\n", url, e))
             }
         }
     }
 
-    discover_client.connect().await; // Connect discover_client
-    tokio::time::sleep(Duration::from_secs(2)).await; // Discoverリレー接続安定待ち
+    discover_client.connect().await;
+    // Discoverリレーにつながるのを待つ。全部つながればすぐに進む
+    discover_client.wait_for_connection(network::connect_wait()).await;
 
     let filter = Filter::new()
         .authors(vec![keys.public_key()])
         .kind(Kind::RelayList);
 
-    status_log.push_str("NIP-65リレーリストイベントを検索中 (最大10秒)..\n"); // Timeout reduced
+    let discovery_timeout = network::discovery_timeout();
+    status_log.push_str(&tr!("NIP-65リレーリストイベントを検索中 (最大{}秒)..\n", discovery_timeout.as_secs()));
     let timeout_filter_id = discover_client
         .subscribe(filter, Some(SubscribeAutoCloseOptions::default()))
         .await?;
@@ -62,27 +77,25 @@ pub async fn connect_to_relays_with_nip65(
     let mut received_nip65_event = false;
 
     tokio::select! {
-        _ = tokio::time::sleep(Duration::from_secs(10)) => { // Timeout reduced
-            status_log.push_str("NIP-65イベント検索タイムアウト。\n");
+        _ = tokio::time::sleep(discovery_timeout) => {
+            status_log.push_str(tr!("NIP-65イベント検索タイムアウト。\n"));
         }
         _ = async {
             let mut notifications = discover_client.notifications();
             while let Ok(notification) = notifications.recv().await {
-                if let nostr_sdk::RelayPoolNotification::Event { event, .. } = notification {
-                    if event.kind == Kind::RelayList && event.pubkey == keys.public_key() {
-                        status_log.push_str("NIP-65リレーリストイベントを受信しました。\n");
-                        for tag in event.tags.iter() {
-                            let tag_vec = tag.clone().to_vec();
-                            if tag_vec.get(0).map(|s| s.as_str()) == Some("r") {
-                                if let Some(url) = tag_vec.get(1) {
-                                    let policy = tag_vec.get(2).cloned();
-                                    nip65_relays.push((url.clone(), policy));
-                                }
-                            }
+                if let nostr_sdk::RelayPoolNotification::Event { event, .. } = notification
+                    && event.kind == Kind::RelayList && event.pubkey == keys.public_key() {
+                    status_log.push_str(tr!("NIP-65リレーリストイベントを受信しました。\n"));
+                    for tag in event.tags.iter() {
+                        let tag_vec = tag.clone().to_vec();
+                        if tag_vec.first().map(|s| s.as_str()) == Some("r")
+                            && let Some(url) = tag_vec.get(1) {
+                            let policy = tag_vec.get(2).cloned();
+                            nip65_relays.push((url.clone(), policy));
                         }
-                        received_nip65_event = true;
-                        break;
                     }
+                    received_nip65_event = true;
+                    break;
                 }
             }
         } => {}
@@ -91,9 +104,9 @@ pub async fn connect_to_relays_with_nip65(
     discover_client.unsubscribe(&timeout_filter_id).await;
     discover_client.shutdown().await;
 
-    status_log.push_str("--- NIP-65で受信したリレー情報 ---\n");
+    status_log.push_str(tr!("--- NIP-65で受信したリレー情報 ---\n"));
     if nip65_relays.is_empty() {
-        status_log.push_str("  有効なNIP-65リレーは受信しませんでした。\n");
+        status_log.push_str(tr!("  有効なNIP-65リレーは受信しませんでした。\n"));
     } else {
         for (url, policy) in &nip65_relays {
             status_log.push_str(&format!("  URL: {url}, Policy: {policy:?}\n"));
@@ -106,7 +119,7 @@ pub async fn connect_to_relays_with_nip65(
         std::collections::HashMap::new();
 
     if received_nip65_event && !nip65_relays.is_empty() {
-        status_log.push_str("\nNIP-65で検出されたリレーに並列接続中...\n");
+        status_log.push_str(tr!("\nNIP-65で検出されたリレーに並列接続中...\n"));
         let _ = client.remove_all_relays().await;
 
         let relays_to_add: Vec<_> = nip65_relays
@@ -124,13 +137,13 @@ pub async fn connect_to_relays_with_nip65(
         let results = join_all(add_relay_futures).await;
         for result in results {
             match result {
-                Ok(url) => status_log.push_str(&format!("  リレー追加: {url}\n")),
-                Err(e) => status_log.push_str(&format!("  リレー追加失敗 - エラー: {e}\n")), // URL might not be available on error
+                Ok(url) => status_log.push_str(&tr!("  リレー追加: {}\n", url)),
+                Err(e) => status_log.push_str(&tr!("  リレー追加失敗 - エラー: {}\n", e)), // URL might not be available on error
             }
         }
     } else {
         status_log.push_str(
-            "\nNIP-65リレーリストが見つからなかったため、デフォルトのリレーに並列接続します。\n",
+            tr!("\nNIP-65リレーリストが見つからなかったため、デフォルトのリレーに並列接続します。\n"),
         );
         let _ = client.remove_all_relays().await;
 
@@ -150,26 +163,43 @@ pub async fn connect_to_relays_with_nip65(
         for (i, result) in results.into_iter().enumerate() {
             let url = &fallback_relays[i];
             match result {
-                Ok(_) => status_log.push_str(&format!("  デフォルトリレー追加: {url}\n")),
-                Err(e) => status_log.push_str(&format!(
-                    "  デフォルトリレー追加失敗: {url} - エラー: {e}\n"
-                )),
+                Ok(_) => status_log.push_str(&tr!("  デフォルトリレー追加: {}\n", url)),
+                Err(e) => status_log.push_str(&tr!("  デフォルトリレー追加失敗: {} - エラー: {}\n", url, e)),
             }
         }
     }
 
+    // 手動で登録したリレーはNIP-65の結果より優先し、読み書きの設定どおりに追加し直す
+    if !manual_relays.is_empty() {
+        status_log.push_str(tr!("\n手動で登録したリレーを反映中...\n"));
+    }
+    for relay in manual_relays {
+        let url = relay.url.trim();
+        let _ = client.remove_relay(url).await;
+        let result = match (relay.read, relay.write) {
+            (true, true) => client.add_relay(url).await,
+            (true, false) => client.add_read_relay(url).await,
+            (false, true) => client.add_write_relay(url).await,
+            (false, false) => {
+                status_log.push_str(&tr!("  手動リレー無効: {}\n", url));
+                continue;
+            }
+        };
+        match result {
+            Ok(_) => status_log.push_str(&tr!("  手動リレー追加: {}\n", url)),
+            Err(e) => status_log.push_str(&tr!("  手動リレー追加失敗: {} - エラー: {}\n", url, e)),
+        }
+    }
+
     client.connect().await;
-    tokio::time::sleep(Duration::from_secs(2)).await; // 接続安定待ち
+    client.wait_for_connection(network::connect_wait()).await;
 
     let relays = client.relays().await;
     if relays.is_empty() {
-        return Err("接続できるリレーがありません。".into());
+        return Err(tr!("接続できるリレーがありません。").into());
     }
 
-    status_log.push_str(&format!(
-        "\n--- 現在接続中のリレー ({}件) ---\n",
-        relays.len()
-    ));
+    status_log.push_str(&tr!("\n--- 現在接続中のリレー ({}件) ---\n", relays.len()));
     for (url, relay) in relays.iter() {
         let status = relay.status();
         status_log.push_str(&format!("  - {url}: {status:?}\n"));
@@ -178,7 +208,7 @@ pub async fn connect_to_relays_with_nip65(
     }
     status_log.push_str("---------------------------------\n");
 
-    let full_log = format!(
+    let full_log = tr!(
         "{}\n\n--- 現在接続中のリレー ---\n{}",
         status_log,
         current_connected_relays.join("\n")
@@ -186,86 +216,269 @@ pub async fn connect_to_relays_with_nip65(
     Ok((full_log, nip65_relays))
 }
 
-// フォローしているユーザーのリレーリスト(kind:10002)を取得する関数
-pub async fn fetch_relays_for_followed_users(
+// フォローしているユーザーのリレーリスト(kind:10002)から、作者ごとの書き込みリレーを取得する関数
+pub async fn fetch_write_relays_for_followed_users(
     discover_client: &Client,
     pubkeys: Vec<PublicKey>,
-) -> Result<Vec<String>, Box<dyn std::error::Error + Send + Sync>> {
+) -> Result<HashMap<PublicKey, Vec<String>>, Box<dyn std::error::Error + Send + Sync>> {
     if pubkeys.is_empty() {
-        return Ok(Vec::new());
+        return Ok(HashMap::new());
     }
 
     let filter = Filter::new().authors(pubkeys).kind(Kind::RelayList);
 
-    let events = discover_client
-        .fetch_events(filter, Duration::from_secs(10))
-        .await?;
+    let events = network::fetch_events(discover_client, filter).await?;
 
-    let mut relay_urls = std::collections::HashSet::new();
+    // 作者ごとに一番新しいリレーリストだけを使う
+    let mut latest: HashMap<PublicKey, Event> = HashMap::new();
     for event in events {
+        match latest.get(&event.pubkey) {
+            Some(existing) if existing.created_at >= event.created_at => {}
+            _ => {
+                latest.insert(event.pubkey, event);
+            }
+        }
+    }
+
+    let mut write_relays: HashMap<PublicKey, Vec<String>> = HashMap::new();
+    for (pubkey, event) in latest {
+        let mut urls = Vec::new();
         for tag in event.tags.iter() {
             let tag_parts = tag.clone().to_vec();
-            if tag_parts.get(0).map(|s| s.as_str()) == Some("r") {
-                if let Some(url) = tag_parts.get(1) {
-                    relay_urls.insert(url.clone());
-                }
+            if tag_parts.first().map(|s| s.as_str()) != Some("r") {
+                continue;
+            }
+            // 用途なし (読み書き) か "write" のリレーにその人の投稿がある
+            let is_write = matches!(tag_parts.get(2).map(|s| s.as_str()), None | Some("write"));
+            if let Some(url) = tag_parts.get(1).filter(|_| is_write)
+                && !urls.contains(url) {
+                urls.push(url.clone());
+            }
+            if urls.len() >= MAX_WRITE_RELAYS_PER_AUTHOR {
+                break;
             }
         }
+        if !urls.is_empty() {
+            write_relays.insert(pubkey, urls);
+        }
     }
 
-    Ok(relay_urls.into_iter().collect())
+    Ok(write_relays)
 }
 
-// NIP-01 プロファイルメタデータを取得する関数
-pub async fn fetch_nip01_profile(
-    client: &Client,
-    public_key: PublicKey,
-) -> Result<(ProfileMetadata, String), Box<dyn std::error::Error + Send + Sync>> {
-    let nip01_filter = Filter::new()
-        .authors(vec![public_key])
-        .kind(Kind::Metadata)
-        .limit(1);
-    let nip01_filter_id = client
-        .subscribe(nip01_filter, Some(SubscribeAutoCloseOptions::default()))
-        .await?;
+// 作者ごとの書き込みリレーを、リレーごとに問い合わせる作者の一覧に組み替える
+// リレーリストを公開していない作者はデフォルトリレーで探す
+fn build_outbox_relay_map(
+    authors: &HashSet<PublicKey>,
+    write_relays: &HashMap<PublicKey, Vec<String>>,
+    fallback_relays: &[String],
+) -> HashMap<String, HashSet<PublicKey>> {
+    let mut relay_map: HashMap<String, HashSet<PublicKey>> = HashMap::new();
+    for author in authors {
+        let urls = write_relays.get(author).map(|urls| urls.as_slice()).unwrap_or(fallback_relays);
+        for url in urls {
+            relay_map.entry(url.clone()).or_default().insert(*author);
+        }
+    }
+    relay_map
+}
 
-    let mut profile_json_string = String::new();
-    let mut received_nip01 = false;
+// NIP-77 の同期を使えなかったリレー。起動している間は通常の取得だけを使う
+static NEGENTROPY_UNSUPPORTED: LazyLock<Mutex<HashSet<String>>> = LazyLock::new(Default::default);
 
-    tokio::select! {
-        _ = tokio::time::sleep(Duration::from_secs(10)) => {
-            eprintln!("NIP-01 profile fetch timed out.");
+// 対応しているリレーとは negentropy (NIP-77) で手元のデータベースとの差分だけを受け取り、
+// データベースから結果を返す。対応していなければ通常どおり取得する
+async fn fetch_events_with_sync(
+    client: &Client,
+    url: &str,
+    filter: Filter,
+    timeout: Duration,
+) -> Result<Events, Box<dyn std::error::Error + Send + Sync>> {
+    let supported = !NEGENTROPY_UNSUPPORTED.lock().unwrap().contains(url);
+    if supported {
+        let opts = SyncOptions::default()
+            .direction(SyncDirection::Down)
+            .initial_timeout(timeout);
+        match client.sync_with([url], filter.clone(), &opts).await {
+            Ok(output) if !output.success.is_empty() => {
+                info!("Synced {} new events from {url}", output.received.len());
+                return Ok(client.database().query(filter).await?);
+            }
+            Ok(output) => {
+                let reason = output.failed.values().next().cloned().unwrap_or_default();
+                info!("Negentropy sync is not available on {url}: {reason}");
+                NEGENTROPY_UNSUPPORTED.lock().unwrap().insert(url.to_string());
+            }
+            Err(e) => {
+                info!("Negentropy sync is not available on {url}: {e}");
+                NEGENTROPY_UNSUPPORTED.lock().unwrap().insert(url.to_string());
+            }
         }
-        _ = async {
-            let mut notifications = client.notifications();
-            while let Ok(notification) = notifications.recv().await {
-                if let nostr_sdk::RelayPoolNotification::Event { event, .. } = notification {
-                    if event.kind == Kind::Metadata && event.pubkey == public_key {
-                        profile_json_string = event.content.clone();
-                        received_nip01 = true;
-                        break;
+    }
+    Ok(client.fetch_events_from([url], filter, timeout).await?)
+}
+
+// リレーごとにその作者の分だけを問い合わせ、結果を重複なしでまとめる (アウトボックスモデル)
+// on_events には、応答したリレーの分を届いた順に渡す
+async fn fetch_events_from_outboxes(
+    client: &Client,
+    relay_map: &HashMap<String, HashSet<PublicKey>>,
+    filter: Filter,
+    timeout: Duration,
+    mut on_events: impl FnMut(&[Event]),
+) -> Vec<Event> {
+    let mut fetches: FuturesUnordered<_> = relay_map
+        .iter()
+        .map(|(url, authors)| {
+            let filter = filter.clone().authors(authors.iter().copied());
+            async move { (url, fetch_events_with_sync(client, url, filter, timeout).await) }
+        })
+        .collect();
+
+    let mut seen = HashSet::new();
+    let mut merged = Vec::new();
+    while let Some((url, result)) = fetches.next().await {
+        match result {
+            Ok(events) => {
+                // 同期した分はデータベースから返すので、ここでも確かめておく
+                let events = event_check::keep_valid(events);
+                on_events(&events);
+                for event in events {
+                    if seen.insert(event.id) {
+                        merged.push(event);
                     }
                 }
             }
-        } => {},
+            Err(e) => error!("Failed to fetch events from {url}: {e}"),
+        }
     }
-    client.unsubscribe(&nip01_filter_id).await;
+    merged.sort_by_key(|event| std::cmp::Reverse(event.created_at));
+    merged
+}
 
-    if received_nip01 {
-        let profile_metadata: ProfileMetadata = serde_json::from_str(&profile_json_string)?;
-        Ok((profile_metadata, profile_json_string))
-    } else {
-        let default_metadata = ProfileMetadata::default();
-        let default_json = serde_json::to_string_pretty(&default_metadata)?;
-        Ok((default_metadata, default_json)) // プロファイルが見つからなかった場合はデフォルト値を返す
+// 自分のフォローリスト (kind 3) とプロフィール (kind 0) を発見リレーとデフォルトのリレーから取得する
+// 自分のリレーリストを探すのと並行して行い、ログインを待たせないようにする
+pub async fn fetch_own_lists_from_bootstrap(
+    keys: &AppSigner,
+    discover_relays_str: &str,
+    default_relays_str: &str,
+) -> Result<Events, Box<dyn std::error::Error + Send + Sync>> {
+    let bootstrap_client = new_client(keys.clone());
+    let relay_urls: HashSet<&str> = discover_relays_str
+        .lines()
+        .chain(default_relays_str.lines())
+        .map(str::trim)
+        .filter(|url| !url.is_empty())
+        .collect();
+    for url in relay_urls {
+        if let Err(e) = bootstrap_client.add_relay(url).await {
+            error!("Failed to add relay {url}: {e}");
+        }
+    }
+    bootstrap_client.connect().await;
+    bootstrap_client.wait_for_connection(network::connect_wait()).await;
+
+    let filter = Filter::new()
+        .author(keys.public_key())
+        .kinds([Kind::ContactList, Kind::Metadata]);
+    let result = network::fetch_events(&bootstrap_client, filter).await;
+    bootstrap_client.shutdown().await;
+    Ok(result?)
+}
+
+// 編集したリレーリストから NIP-65 (kind 10002) を作成し、自分のリレーと発見リレーの両方に公開する
+// 戻り値はキャッシュと同じ (URL, "read" / "write" / なし) の形式
+pub async fn publish_relay_list(
+    client: &Client,
+    keys: &AppSigner,
+    relays: &[EditableRelay],
+    discover_relays: &str,
+    timeout: Duration,
+) -> Result<Vec<(String, Option<String>)>, Box<dyn std::error::Error + Send + Sync>> {
+    let mut tags = Vec::new();
+    let mut published_relays: Vec<(String, Option<String>)> = Vec::new();
+    for relay in relays {
+        let url_str = relay.url.trim();
+        // 読み書きのどちらも外したリレーはリストに載せない
+        if url_str.is_empty() || (!relay.read && !relay.write) {
+            continue;
+        }
+        let url = RelayUrl::parse(url_str).map_err(|e| tr!("リレーURLが正しくありません: {} ({})", url_str, e))?;
+        if published_relays.iter().any(|(u, _)| u == url.as_str()) {
+            continue;
+        }
+        let policy = match (relay.read, relay.write) {
+            (true, false) => Some(RelayMetadata::Read),
+            (false, true) => Some(RelayMetadata::Write),
+            _ => None,
+        };
+        published_relays.push((url.to_string(), policy.map(|p| p.to_string())));
+        tags.push(NostrTag::relay_metadata(url, policy));
+    }
+    if tags.is_empty() {
+        return Err(tr!("公開するリレーがありません").into());
+    }
+
+    let event = EventBuilder::new(Kind::RelayList, "").tags(tags).sign(keys).await?;
+
+    let discover_client = new_client(keys.clone());
+    for relay_url in discover_relays.lines().filter(|url| !url.trim().is_empty()) {
+        discover_client.add_relay(relay_url.trim()).await?;
+    }
+    discover_client.connect().await;
+    discover_client.wait_for_connection(timeout).await;
+
+    let (own_result, discover_result) =
+        tokio::join!(client.send_event(&event), discover_client.send_event(&event));
+    discover_client.shutdown().await;
+    match (own_result, discover_result) {
+        (Err(own_error), Err(discover_error)) => {
+            Err(tr!("リレーリストを公開できませんでした: {} / {}", own_error, discover_error).into())
+        }
+        _ => Ok(published_relays),
     }
 }
 
+// 新しく作った鍵の最初のプロフィール(kind 0)をデフォルトリレーに公開する
+pub async fn publish_initial_profile(
+    keys: &AppSigner,
+    default_relays: &str,
+    profile: &ProfileMetadata,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let temp_client = new_client(keys.clone());
+    for relay_url in default_relays.lines().filter(|url| !url.trim().is_empty()) {
+        temp_client.add_relay(relay_url.trim()).await?;
+    }
+    temp_client.connect().await;
+    temp_client.wait_for_connection(network::fetch_timeout()).await;
+
+    let event = EventBuilder::new(Kind::Metadata, serde_json::to_string(profile)?)
+        .sign(keys)
+        .await?;
+    let result = temp_client.send_event(&event).await;
+    temp_client.shutdown().await;
+    result?;
+    Ok(())
+}
+
+// npub / nprofile / hex / nostr:URI を公開鍵とリレーヒントに変換する
+pub fn parse_profile_input(
+    input: &str,
+) -> Result<(PublicKey, Option<RelayUrl>), Box<dyn std::error::Error + Send + Sync>> {
+    let input = input.trim();
+    if let Ok(profile) = Nip19Profile::from_bech32(input).or_else(|_| Nip19Profile::from_nostr_uri(input)) {
+        return Ok((profile.public_key, profile.relays.into_iter().next()));
+    }
+    Ok((PublicKey::parse(input)?, None))
+}
+
 // NIP-02 コンタクトリストを更新する関数
+// 既存のpタグ(リレーヒントやペットネーム)とcontentはそのまま残して再発行する
 pub async fn update_contact_list(
     client: &Client,
-    keys: &Keys,
+    keys: &AppSigner,
     pubkey_to_modify: PublicKey,
+    relay_hint: Option<RelayUrl>,
     follow: bool, // trueでフォロー、falseでアンフォロー
 ) -> Result<HashSet<PublicKey>, Box<dyn std::error::Error + Send + Sync>> {
     // 1. 現在のコンタクトリストを取得
@@ -273,10 +486,13 @@ pub async fn update_contact_list(
         .authors(vec![keys.public_key()])
         .kind(Kind::ContactList)
         .limit(1);
-    let events = client.fetch_events(filter, Duration::from_secs(10)).await?;
+    let events = network::fetch_events(client, filter).await?;
 
-    let mut current_tags: Vec<NostrTag> = if let Some(event) = events.first() {
-        event.tags.clone().into_iter().collect()
+    // 複数のリレーから返ってきた場合は最新のものを使う
+    let latest_event = events.into_iter().max_by_key(|e| e.created_at);
+    let content = latest_event.as_ref().map(|e| e.content.clone()).unwrap_or_default();
+    let mut current_tags: Vec<NostrTag> = if let Some(event) = latest_event {
+        event.tags.into_iter().collect()
     } else {
         Vec::new()
     };
@@ -295,8 +511,13 @@ pub async fn update_contact_list(
     // 2. フォローリストを変更
     if follow {
         if followed_pubkeys.insert(pubkey_to_modify) {
-            current_tags.push(NostrTag::public_key(pubkey_to_modify));
-            println!("Following {}", pubkey_to_modify.to_bech32()?);
+            current_tags.push(NostrTag::from_standardized(TagStandard::PublicKey {
+                public_key: pubkey_to_modify,
+                relay_url: relay_hint,
+                alias: None,
+                uppercase: false,
+            }));
+            info!("Following {}", pubkey_to_modify.to_bech32()?);
         }
     } else if followed_pubkeys.remove(&pubkey_to_modify) {
         current_tags.retain(|tag| {
@@ -306,22 +527,53 @@ pub async fn update_contact_list(
                 true
             }
         });
-        println!("Unfollowing {}", pubkey_to_modify.to_bech32()?);
+        info!("Unfollowing {}", pubkey_to_modify.to_bech32()?);
     }
 
     // 3. 新しいコンタクトリストイベントを作成して送信
     use nostr::EventBuilder;
-    let event = EventBuilder::new(Kind::ContactList, "")
+    let event = EventBuilder::new(Kind::ContactList, content)
         .tags(current_tags)
         .sign(keys)
         .await?;
     client.send_event(&event).await?;
 
-    println!("Contact list updated successfully.");
+    info!("Contact list updated successfully.");
 
     Ok(followed_pubkeys)
 }
 
+// フォローしているユーザーのNIP-01メタデータをまとめて取得する関数
+pub async fn fetch_profiles_for_pubkeys(
+    client: &Client,
+    pubkeys: Vec<PublicKey>,
+) -> Result<HashMap<PublicKey, ProfileMetadata>, Box<dyn std::error::Error + Send + Sync>> {
+    let mut profiles: HashMap<PublicKey, ProfileMetadata> = HashMap::new();
+    if pubkeys.is_empty() {
+        return Ok(profiles);
+    }
+
+    let filter = Filter::new().authors(pubkeys).kind(Kind::Metadata);
+    let events = network::fetch_events(client, filter).await?;
+
+    // 複数のリレーから同じユーザーのkind:0が届いた場合は最新のものを採用する
+    let mut latest_created_at: HashMap<PublicKey, Timestamp> = HashMap::new();
+    for event in events {
+        if latest_created_at
+            .get(&event.pubkey)
+            .is_some_and(|created_at| *created_at >= event.created_at)
+        {
+            continue;
+        }
+        if let Ok(metadata) = serde_json::from_str::<ProfileMetadata>(&event.content) {
+            latest_created_at.insert(event.pubkey, event.created_at);
+            profiles.insert(event.pubkey, metadata);
+        }
+    }
+
+    Ok(profiles)
+}
+
 pub async fn get_profile_metadata(
     pubkey: PublicKey,
     client: &Client,
@@ -332,7 +584,7 @@ pub async fn get_profile_metadata(
     let relays = client.relays().await;
     let relay_urls: Vec<String> = relays.keys().map(|url| url.to_string()).collect();
 
-    let events = client.fetch_events_from(relay_urls, filter, Duration::from_secs(5)).await?;
+    let events = client.fetch_events_from(relay_urls, filter, network::fetch_timeout()).await?;
 
     if let Some(event) = events.first() {
         let metadata: ProfileMetadata = serde_json::from_str(&event.content)?;
@@ -343,86 +595,237 @@ pub async fn get_profile_metadata(
     }
 }
 
+// ステータスのイベントからタイムラインの投稿を作る
+pub fn timeline_post_from_event(event: &Event, author_metadata: ProfileMetadata) -> TimelinePost {
+    let emojis = note::emojis_from_tags(event.tags.iter());
+
+    TimelinePost {
+        id: event.id,
+        kind: event.kind,
+        author_pubkey: event.pubkey,
+        author_metadata,
+        content: event.content.clone(),
+        created_at: event.created_at,
+        emojis,
+        tags: event.tags.clone().to_vec(),
+        is_private: false,
+    }
+}
+
 pub async fn fetch_timeline_events(
-    keys: &Keys,
-    discover_relays: &str,
+    keys: &AppSigner,
+    settings: &Settings,
     followed_pubkeys: &HashSet<PublicKey>,
+    mut on_posts: impl FnMut(Vec<TimelinePost>),
 ) -> Result<Vec<TimelinePost>, Box<dyn std::error::Error + Send + Sync>> {
     let mut timeline_posts = Vec::new();
     if followed_pubkeys.is_empty() {
         return Ok(timeline_posts);
     }
 
-    let temp_discover_client = Client::new(keys.clone());
-    for relay_url in discover_relays.lines().filter(|url| !url.trim().is_empty()) {
+    let temp_discover_client = new_client(keys.clone());
+    for relay_url in settings.discover_relays.lines().filter(|url| !url.trim().is_empty()) {
         temp_discover_client.add_relay(relay_url.trim()).await?;
     }
     temp_discover_client.connect().await;
     let followed_pubkeys_vec: Vec<PublicKey> = followed_pubkeys.iter().cloned().collect();
-    let write_relay_urls =
-        fetch_relays_for_followed_users(&temp_discover_client, followed_pubkeys_vec).await?;
+    let write_relays =
+        fetch_write_relays_for_followed_users(&temp_discover_client, followed_pubkeys_vec).await?;
     temp_discover_client.shutdown().await;
 
-    if !write_relay_urls.is_empty() {
-        let temp_fetch_client = Client::new(keys.clone());
-        for url in &write_relay_urls {
-            temp_fetch_client.add_relay(url.clone()).await?;
+    let fallback_relays: Vec<String> = settings
+        .default_relays
+        .lines()
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty())
+        .collect();
+    let relay_map = build_outbox_relay_map(followed_pubkeys, &write_relays, &fallback_relays);
+
+    if !relay_map.is_empty() {
+        let temp_fetch_client = new_client(keys.clone());
+        for url in relay_map.keys() {
+            if let Err(e) = temp_fetch_client.add_relay(url.as_str()).await {
+                error!("Failed to add relay {url}: {e}");
+            }
         }
         temp_fetch_client.connect().await;
 
         let timeline_filter = Filter::new()
-            .authors(followed_pubkeys.clone())
             .kind(Kind::from(30315))
-            .limit(20);
-        let status_events = temp_fetch_client
-            .fetch_events(timeline_filter, Duration::from_secs(10))
-            .await?;
+            .limit(settings.timeline_limit);
+        // 複数のリレーから同じステータスの古い版が届くことがあるので、最新のものだけにする
+        // 応答したリレーの分から先に渡す。名前や画像はあとでまとめて取得するので、ここではまだ付けない
+        let stream_posts = |events: &[Event]| {
+            let posts: Vec<TimelinePost> = events
+                .iter()
+                .filter(|event| !event.is_expired())
+                .map(|event| timeline_post_from_event(event, ProfileMetadata::default()))
+                .collect();
+            if !posts.is_empty() {
+                on_posts(posts);
+            }
+        };
+        let mut status_events = dedupe_events(
+            fetch_events_from_outboxes(&temp_fetch_client, &relay_map, timeline_filter, settings.relay_timeout(), stream_posts).await,
+        );
+        status_events.truncate(settings.timeline_limit);
 
         if !status_events.is_empty() {
             let author_pubkeys: HashSet<PublicKey> =
                 status_events.iter().map(|e| e.pubkey).collect();
-            let metadata_filter = Filter::new()
-                .authors(author_pubkeys.into_iter())
-                .kind(Kind::Metadata);
-            let metadata_events = temp_fetch_client
-                .fetch_events(metadata_filter, Duration::from_secs(5))
-                .await?;
+            let metadata_filter = Filter::new().kind(Kind::Metadata);
+            let metadata_relay_map = build_outbox_relay_map(&author_pubkeys, &write_relays, &fallback_relays);
+            let metadata_events = fetch_events_from_outboxes(
+                &temp_fetch_client,
+                &metadata_relay_map,
+                metadata_filter,
+                network::fetch_timeout(),
+                |_| {},
+            )
+            .await;
             let mut profiles: HashMap<PublicKey, ProfileMetadata> = HashMap::new();
+            // 新しい順に並んでいるので、最初に見つかったものを使う
             for event in metadata_events {
+                if profiles.contains_key(&event.pubkey) {
+                    continue;
+                }
                 if let Ok(metadata) = serde_json::from_str::<ProfileMetadata>(&event.content) {
                     profiles.insert(event.pubkey, metadata);
                 }
             }
 
             for event in status_events {
-                let emojis = event
-                    .tags
-                    .iter()
-                    .filter_map(|tag| {
-                        if let Some(nostr::TagStandard::Emoji { shortcode, url }) =
-                            tag.as_standardized()
-                        {
-                            Some((shortcode.to_string(), url.to_string()))
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
-
-                timeline_posts.push(TimelinePost {
-                    id: event.id,
-                    kind: event.kind,
-                    author_pubkey: event.pubkey,
-                    author_metadata: profiles.get(&event.pubkey).cloned().unwrap_or_default(),
-                    content: event.content.clone(),
-                    created_at: event.created_at,
-                    emojis,
-                    tags: event.tags.to_vec(),
-                });
+                // 期限切れのステータスは表示しない (NIP-40)
+                if event.is_expired() {
+                    continue;
+                }
+                let author_metadata = profiles.get(&event.pubkey).cloned().unwrap_or_default();
+                timeline_posts.push(timeline_post_from_event(&event, author_metadata));
             }
-            timeline_posts.sort_by_key(|p| std::cmp::Reverse(p.created_at));
         }
         temp_fetch_client.shutdown().await;
     }
     Ok(timeline_posts)
-}
\ No newline at end of file
+}
+// 他のユーザーのプロフィールページの内容 (kind 0, NIP-65, NIP-38, 最近のノート) をまとめて取得する
+pub async fn fetch_user_profile(
+    client: &Client,
+    public_key: PublicKey,
+) -> Result<UserProfileData, Box<dyn std::error::Error + Send + Sync>> {
+    let profile_filter = Filter::new()
+        .author(public_key)
+        .kinds([Kind::Metadata, Kind::RelayList, Kind::ContactList, Kind::from(30315)]);
+    let notes_filter = Filter::new()
+        .author(public_key)
+        .kind(Kind::TextNote)
+        .limit(USER_PROFILE_NOTES_LIMIT);
+    let (profile_events, note_events) = tokio::join!(
+        network::fetch_events(client, profile_filter),
+        network::fetch_events(client, notes_filter),
+    );
+    let profile_events = profile_events?;
+    let note_events = note_events?;
+
+    let mut metadata_event: Option<Event> = None;
+    let mut relay_list_event: Option<Event> = None;
+    let mut contact_list_event: Option<Event> = None;
+    let mut latest_statuses: HashMap<String, Event> = HashMap::new();
+    for event in profile_events {
+        let latest = match event.kind {
+            Kind::Metadata => &mut metadata_event,
+            Kind::RelayList => &mut relay_list_event,
+            Kind::ContactList => &mut contact_list_event,
+            _ => {
+                let d_tag = event.tags.identifier().unwrap_or_default().to_string();
+                match latest_statuses.get(&d_tag) {
+                    Some(existing) if existing.created_at >= event.created_at => {}
+                    _ => {
+                        latest_statuses.insert(d_tag, event);
+                    }
+                }
+                continue;
+            }
+        };
+        if latest.as_ref().is_none_or(|existing| existing.created_at < event.created_at) {
+            *latest = Some(event);
+        }
+    }
+
+    let identities = metadata_event.as_ref().map(|event| nip39::identities(&event.tags)).unwrap_or_default();
+    let metadata = metadata_event
+        .and_then(|event| serde_json::from_str::<ProfileMetadata>(&event.content).ok())
+        .unwrap_or_default();
+    let mut relays: Vec<(String, Option<String>)> = Vec::new();
+    if let Some(event) = relay_list_event {
+        for tag in event.tags.iter() {
+            let tag_vec = tag.clone().to_vec();
+            if tag_vec.first().map(|s| s.as_str()) == Some("r")
+                && let Some(url) = tag_vec.get(1) {
+                relays.push((url.clone(), tag_vec.get(2).cloned()));
+            }
+        }
+    }
+    // 消去済み (空) と期限切れのステータスは表示しない
+    let mut statuses: Vec<Event> = latest_statuses
+        .into_values()
+        .filter(|event| !event.content.is_empty() && !event.is_expired())
+        .collect();
+    statuses.sort_by_key(|event| std::cmp::Reverse(event.created_at));
+    let mut notes: Vec<Event> = note_events.into_iter().collect();
+    notes.sort_by_key(|event| std::cmp::Reverse(event.created_at));
+
+    let following_count = contact_list_event.map(|event| event.tags.public_keys().count());
+
+    Ok(UserProfileData {
+        public_key,
+        metadata,
+        identities,
+        relays,
+        statuses,
+        notes,
+        following_count,
+        counts: None,
+        badges: None,
+    })
+}
+
+// NIP-45 の COUNT に答えなかったリレー。起動している間は問い合わせない
+static COUNT_UNSUPPORTED: LazyLock<Mutex<HashSet<RelayUrl>>> = LazyLock::new(Default::default);
+
+// 接続中のリレーに COUNT を送り、一番大きい値を返す。どのリレーも答えなければ None
+async fn count_events_on_relays(client: &Client, filter: Filter) -> Option<usize> {
+    let relays = client.relays().await;
+    let count_futures = relays
+        .into_iter()
+        .filter(|(url, relay)| relay.is_connected() && !COUNT_UNSUPPORTED.lock().unwrap().contains(url))
+        .map(|(url, relay)| {
+            let filter = filter.clone();
+            async move {
+                let result = relay.count_events(filter, Duration::from_secs(COUNT_TIMEOUT_SECS)).await;
+                (url, result)
+            }
+        });
+
+    let mut max_count: Option<usize> = None;
+    for (url, result) in join_all(count_futures).await {
+        match result {
+            Ok(count) => max_count = Some(max_count.map_or(count, |max| max.max(count))),
+            Err(e) => {
+                info!("COUNT is not available on {url}: {e}");
+                COUNT_UNSUPPORTED.lock().unwrap().insert(url);
+            }
+        }
+    }
+    max_count
+}
+
+// プロフィールページに出すフォロワー数と ZAP の数を数える
+pub async fn fetch_profile_counts(client: &Client, public_key: PublicKey) -> ProfileCounts {
+    let followers_filter = Filter::new().kind(Kind::ContactList).pubkey(public_key);
+    let zaps_filter = Filter::new().kind(Kind::ZapReceipt).pubkey(public_key);
+    let (followers, zaps) = tokio::join!(
+        count_events_on_relays(client, followers_filter),
+        count_events_on_relays(client, zaps_filter),
+    );
+    ProfileCounts { followers, zaps }
+}
@@ -0,0 +1,479 @@
+use nostr::nips::nip10::Marker;
+use nostr::nips::nip19::{FromBech32, Nip19, Nip19Event, Nip19Profile, ToBech32};
+use nostr::{Event, EventBuilder, EventId, Filter, JsonUtil, Kind, PublicKey, Tag, TagStandard};
+use nostr_sdk::Client;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+use tracing::{error, info};
+
+use crate::i18n::tr;
+use crate::network;
+use crate::nostr_client::fetch_profiles_for_pubkeys;
+use crate::outbox;
+use crate::pow;
+use crate::replaceable;
+use crate::signer::AppSigner;
+use crate::types::{AudioClip, NostrLink, NostrStatusAppInternal, ProfileMetadata, QuoteTarget};
+
+// 候補として出すメンションの件数
+const MAX_MENTION_SUGGESTIONS: usize = 5;
+// ノートタブで取得するノートの件数
+const NOTES_FETCH_LIMIT: usize = 100;
+
+// 本文の `@npub1...` や `nostr:nevent1...` を NIP-27 の `nostr:` 参照にそろえ、対応する p / q / t タグを作る
+pub fn prepare_note(content: &str) -> (String, Vec<Tag>) {
+    let re = Regex::new(r"(?:nostr:|@)((?:npub|nprofile|note|nevent)1[02-9ac-hj-np-z]+)").unwrap();
+    let mut tags: Vec<Tag> = Vec::new();
+    let mut push_tag = |tag: Tag| {
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    };
+    let normalized = re.replace_all(content, |caps: &regex::Captures| {
+        let bech32 = &caps[1];
+        match Nip19::from_bech32(bech32) {
+            Ok(Nip19::Pubkey(public_key)) => push_tag(Tag::public_key(public_key)),
+            Ok(Nip19::Profile(profile)) => push_tag(Tag::public_key(profile.public_key)),
+            Ok(Nip19::EventId(event_id)) => {
+                if let Ok(tag) = Tag::parse(["q", &event_id.to_hex()]) {
+                    push_tag(tag);
+                }
+            }
+            Ok(Nip19::Event(event)) => {
+                if let Ok(tag) = Tag::parse(["q", &event.event_id.to_hex()]) {
+                    push_tag(tag);
+                }
+                if let Some(author) = event.author {
+                    push_tag(Tag::public_key(author));
+                }
+            }
+            // 読めないものは書かれたまま残す
+            _ => return caps[0].to_string(),
+        }
+        format!("nostr:{bech32}")
+    });
+    // ハッシュタグは t タグにする (NIP-24)
+    for (_, hashtag) in find_hashtags(&normalized) {
+        push_tag(Tag::hashtag(hashtag));
+    }
+    (normalized.into_owned(), tags)
+}
+
+// 本文中のハッシュタグを探す。URL のアンカーのような単語の途中の # は拾わない
+pub fn find_hashtags(content: &str) -> Vec<(Range<usize>, String)> {
+    let re = Regex::new(r"#([\p{L}\p{N}_]+)").unwrap();
+    re.captures_iter(content)
+        .filter_map(|caps| {
+            let whole = caps.get(0)?;
+            let at_word_start = content[..whole.start()].chars().next_back().is_none_or(|c| c.is_whitespace());
+            if !at_word_start {
+                return None;
+            }
+            Some((whole.range(), caps[1].to_lowercase()))
+        })
+        .collect()
+}
+
+// 本文中の http(s) の URL を探す。文末の句読点や閉じかっこは URL に含めない
+pub fn find_web_urls(content: &str) -> Vec<(Range<usize>, String)> {
+    let re = Regex::new(r#"https?://[^\s<>"'「」、。）]+"#).unwrap();
+    re.find_iter(content)
+        .map(|m| {
+            let url = m.as_str().trim_end_matches(['.', ',', '!', '?', ')', ']', ':', ';']);
+            (m.start()..m.start() + url.len(), url.to_string())
+        })
+        .collect()
+}
+
+// 拡張子で画像の URL かどうかを見る。クエリやアンカーは無視する
+pub fn is_image_url(url: &str) -> bool {
+    let path = url.split(['?', '#']).next().unwrap_or_default().to_lowercase();
+    [".png", ".jpg", ".jpeg", ".gif", ".webp"].iter().any(|extension| path.ends_with(extension))
+}
+
+// 注意書き (NIP-36) のタグを作る。理由が空なら理由なしにする
+pub fn content_warning_tag(reason: &str) -> Tag {
+    let reason = reason.trim();
+    Tag::from_standardized(TagStandard::ContentWarning {
+        reason: (!reason.is_empty()).then(|| reason.to_string()),
+    })
+}
+
+// 注意書き (NIP-36) があれば理由を返す。理由のない注意書きは空文字列になる
+pub fn content_warning<'a>(tags: impl IntoIterator<Item = &'a Tag>) -> Option<String> {
+    tags.into_iter().find_map(|tag| match tag.as_standardized() {
+        Some(TagStandard::ContentWarning { reason }) => Some(reason.clone().unwrap_or_default()),
+        _ => None,
+    })
+}
+
+// 本文の :shortcode: のうち、手元にあるカスタム絵文字に emoji タグ (NIP-30) を付ける
+pub fn emoji_tags(content: &str, emojis: &HashMap<String, String>) -> Vec<Tag> {
+    let re = Regex::new(r":(\w+):").unwrap();
+    let mut used: HashSet<&str> = HashSet::new();
+    let mut tags = Vec::new();
+    for cap in re.captures_iter(content) {
+        let shortcode = cap.get(1).unwrap().as_str();
+        if !used.insert(shortcode) {
+            continue;
+        }
+        if let Some(url) = emojis.get(shortcode)
+            && let Ok(tag) = Tag::parse(["emoji", shortcode, url]) {
+            tags.push(tag);
+        }
+    }
+    tags
+}
+
+// imeta タグ (NIP-92) の url, m, duration を読む
+fn imeta_fields<'a>(tags: impl IntoIterator<Item = &'a Tag>) -> Vec<(String, Option<&'a str>, Option<f32>)> {
+    tags.into_iter()
+        .filter(|tag| tag.as_slice().first().is_some_and(|name| name == "imeta"))
+        .filter_map(|tag| {
+            let mut url = None;
+            let mut mime_type = None;
+            let mut duration = None;
+            for field in &tag.as_slice()[1..] {
+                match field.split_once(' ') {
+                    Some(("url", value)) => url = Some(value.to_string()),
+                    Some(("m", value)) => mime_type = Some(value),
+                    Some(("duration", value)) => duration = value.parse().ok(),
+                    _ => {}
+                }
+            }
+            Some((url?, mime_type, duration))
+        })
+        .collect()
+}
+
+// imeta タグから画像の URL を取り出す。MIME タイプのないものも画像として扱う
+pub fn imeta_image_urls<'a>(tags: impl IntoIterator<Item = &'a Tag>) -> Vec<String> {
+    imeta_fields(tags)
+        .into_iter()
+        .filter(|(_, mime_type, _)| mime_type.is_none_or(|mime_type| mime_type.starts_with("image/")))
+        .map(|(url, _, _)| url)
+        .collect()
+}
+
+// imeta タグから音声 (ボイスメッセージ) を取り出す
+pub fn imeta_audio_clips<'a>(tags: impl IntoIterator<Item = &'a Tag>) -> Vec<AudioClip> {
+    imeta_fields(tags)
+        .into_iter()
+        .filter(|(_, mime_type, _)| mime_type.is_some_and(|mime_type| mime_type.starts_with("audio/")))
+        .map(|(url, _, duration_secs)| AudioClip { url, duration_secs })
+        .collect()
+}
+
+// emoji タグから shortcode と画像の URL の組を取り出す
+pub fn emojis_from_tags<'a>(tags: impl IntoIterator<Item = &'a Tag>) -> HashMap<String, String> {
+    tags.into_iter()
+        .filter_map(|tag| match tag.as_standardized() {
+            Some(TagStandard::Emoji { shortcode, url }) => Some((shortcode.to_string(), url.to_string())),
+            _ => None,
+        })
+        .collect()
+}
+
+// 本文中の NIP-19 の文字列を探す。`nostr:` (NIP-21) が付いていないものは単語の先頭にあるときだけ拾う
+pub fn find_nostr_links(content: &str) -> Vec<(Range<usize>, NostrLink)> {
+    let re = Regex::new(r"(nostr:)?((?:npub|nprofile|note|nevent|naddr)1[02-9ac-hj-np-z]+)").unwrap();
+    re.captures_iter(content)
+        .filter_map(|caps| {
+            let whole = caps.get(0)?;
+            let has_scheme = caps.get(1).is_some();
+            let at_word_start = content[..whole.start()].chars().next_back().is_none_or(|c| c.is_whitespace() || c == '@');
+            if !has_scheme && !at_word_start {
+                return None;
+            }
+            let link = match Nip19::from_bech32(&caps[2]).ok()? {
+                Nip19::Pubkey(public_key) => NostrLink::Profile(public_key),
+                Nip19::Profile(profile) => NostrLink::Profile(profile.public_key),
+                Nip19::EventId(event_id) => NostrLink::Event(event_id),
+                Nip19::Event(event) => NostrLink::Event(event.event_id),
+                Nip19::Coordinate(coordinate) => NostrLink::Address(coordinate.coordinate),
+                _ => return None,
+            };
+            Some((whole.range(), link))
+        })
+        .collect()
+}
+
+// 入力中の末尾の `@名前` に合うフォロー中のユーザーを返す。`@` だけなら先頭から候補を出す
+pub fn mention_suggestions(
+    content: &str,
+    followed: &HashSet<PublicKey>,
+    profiles: &HashMap<PublicKey, ProfileMetadata>,
+) -> Vec<(PublicKey, String)> {
+    let Some(query) = content.rsplit(char::is_whitespace).next().and_then(|word| word.strip_prefix('@')) else {
+        return Vec::new();
+    };
+    if query.starts_with("npub1") || query.starts_with("nprofile1") {
+        return Vec::new();
+    }
+    let query = query.to_lowercase();
+    let mut suggestions: Vec<(PublicKey, String)> = followed
+        .iter()
+        .filter_map(|public_key| profiles.get(public_key).map(|metadata| (public_key, metadata)))
+        .filter(|(_, metadata)| {
+            metadata.name.to_lowercase().contains(&query) || metadata.display_name.to_lowercase().contains(&query)
+        })
+        .map(|(public_key, metadata)| (*public_key, metadata.display_name_or_npub(public_key)))
+        .collect();
+    suggestions.sort_by(|a, b| a.1.cmp(&b.1));
+    suggestions.truncate(MAX_MENTION_SUGGESTIONS);
+    suggestions
+}
+
+// 末尾の `@名前` を選んだユーザーへの `nostr:nprofile1...` 参照 (NIP-27) に置き換える。p タグは公開時に prepare_note が付ける
+pub fn insert_mention(content: &mut String, public_key: &PublicKey) {
+    let Ok(nprofile) = Nip19Profile::new(*public_key, Vec::new()).to_bech32() else {
+        return;
+    };
+    if let Some(at) = content.rfind('@') {
+        content.truncate(at);
+    }
+    content.push_str(&format!("nostr:{nprofile} "));
+}
+
+// テキストノート (kind 1) を公開する。送れなかった場合も送信キューに残るので、署名して送信を取り消さなければ true を返す
+pub async fn publish_note(
+    client: Client,
+    signer: AppSigner,
+    content: String,
+    content_warning: Option<String>,
+    imeta_tags: Vec<Tag>,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+) -> bool {
+    let (content, mut tags) = prepare_note(&content);
+    if let Some(reason) = content_warning {
+        tags.push(content_warning_tag(&reason));
+    }
+    tags.extend(imeta_tags);
+    let event = match pow::sign_with_pow(EventBuilder::text_note(content).tags(tags), &signer, &app_data_arc).await {
+        Ok(event) => event,
+        Err(e) => {
+            error!("Failed to sign the note: {e}");
+            app_data_arc.lock().unwrap().report_error(tr!("ノートに署名できませんでした: {}", e));
+            return false;
+        }
+    };
+
+    send_note(client, event, app_data_arc).await
+}
+
+// 署名済みのノートを送り、ノートタブにもすぐ表示する。送信を取り消したら false を返す
+async fn send_note(client: Client, event: Event, app_data_arc: Arc<Mutex<NostrStatusAppInternal>>) -> bool {
+    let Some(sent) = outbox::send_or_queue_with_undo(&client, vec![event.clone()], &app_data_arc, tr!("ノートを投稿します")).await else {
+        return false;
+    };
+    let mut app_data = app_data_arc.lock().unwrap();
+    if sent {
+        info!("Note published with event id: {:?}", event.id);
+        app_data.notify(tr!("ノートを投稿しました"));
+    } else {
+        app_data.report_error(tr!("ノートを送信できませんでした。送信キューに残し、接続が戻ったら再送します"));
+    }
+    app_data.notes.insert(0, event);
+    true
+}
+
+// ノートの返信先を NIP-10 の e タグから読む。(スレッドの起点, 直接の返信先) を返す
+pub fn thread_refs(event: &Event) -> (Option<EventId>, Option<EventId>) {
+    let e_tags: Vec<(EventId, Option<Marker>)> = event
+        .tags
+        .iter()
+        .filter_map(|tag| match tag.as_standardized() {
+            Some(TagStandard::Event { event_id, marker, uppercase: false, .. }) => Some((*event_id, *marker)),
+            _ => None,
+        })
+        .collect();
+    let root = e_tags.iter().find(|(_, marker)| *marker == Some(Marker::Root)).map(|(id, _)| *id);
+    let reply = e_tags.iter().find(|(_, marker)| *marker == Some(Marker::Reply)).map(|(id, _)| *id);
+    if root.is_some() || reply.is_some() {
+        return (root.or(reply), reply.or(root));
+    }
+    // マーカーのない古い形式は、最初が起点、最後が返信先
+    match (e_tags.first(), e_tags.last()) {
+        (Some((first, _)), Some((last, _))) => (Some(*first), Some(*last)),
+        _ => (None, None),
+    }
+}
+
+// フォロー中のユーザーのノートと、それへの返信を取得する。知らないユーザーのプロフィールもまとめて返す
+pub async fn fetch_notes(
+    client: &Client,
+    authors: Vec<PublicKey>,
+    known_profiles: HashSet<PublicKey>,
+) -> Result<(Vec<Event>, HashMap<PublicKey, ProfileMetadata>), Box<dyn std::error::Error + Send + Sync>> {
+    let filter = Filter::new()
+        .authors(authors)
+        .kinds([Kind::TextNote, Kind::Repost])
+        .limit(NOTES_FETCH_LIMIT);
+    let mut notes: HashMap<EventId, Event> = network::fetch_events(client, filter)
+        .await?
+        .into_iter()
+        .map(|event| (event.id, event))
+        .collect();
+
+    let note_ids: Vec<EventId> = notes.values().filter(|event| event.kind == Kind::TextNote).map(|event| event.id).collect();
+    if !note_ids.is_empty() {
+        let filter = Filter::new().kind(Kind::TextNote).events(note_ids).limit(NOTES_FETCH_LIMIT * 2);
+        for event in network::fetch_events(client, filter).await? {
+            notes.entry(event.id).or_insert(event);
+        }
+    }
+
+    let mut notes: Vec<Event> = notes.into_values().collect();
+    notes.sort_by_key(|event| std::cmp::Reverse(event.created_at));
+
+    // リポストされたノートの作者も名前を表示できるようにする
+    let unknown: HashSet<PublicKey> = notes
+        .iter()
+        .flat_map(|event| std::iter::once(event.pubkey).chain(reposted_event(event).map(|inner| inner.pubkey)))
+        .filter(|pubkey| !known_profiles.contains(pubkey))
+        .collect();
+    let profiles = fetch_profiles_for_pubkeys(client, unknown.into_iter().collect()).await?;
+    Ok((notes, profiles))
+}
+
+// ハッシュタグの付いたノートとステータスを接続中のリレーから取得する
+pub async fn fetch_hashtag_feed(
+    client: &Client,
+    hashtag: &str,
+    known_profiles: HashSet<PublicKey>,
+) -> Result<(Vec<Event>, HashMap<PublicKey, ProfileMetadata>), Box<dyn std::error::Error + Send + Sync>> {
+    let filter = Filter::new()
+        .hashtag(hashtag)
+        .kinds([Kind::TextNote, Kind::from(30315)])
+        .limit(NOTES_FETCH_LIMIT);
+    let events = network::fetch_events(client, filter).await?;
+    // 同じステータスは最新の版だけにして、消去済み (空) と期限切れのステータスは表示しない
+    let mut events: Vec<Event> = replaceable::dedupe_events(events)
+        .into_iter()
+        .filter(|event| !event.content.is_empty() && !event.is_expired())
+        .collect();
+    events.sort_by_key(|event| std::cmp::Reverse(event.created_at));
+
+    let unknown: HashSet<PublicKey> = events
+        .iter()
+        .map(|event| event.pubkey)
+        .filter(|pubkey| !known_profiles.contains(pubkey))
+        .collect();
+    let profiles = fetch_profiles_for_pubkeys(client, unknown.into_iter().collect()).await?;
+    Ok((events, profiles))
+}
+
+// ノートに返信する (NIP-10)。返信先がスレッドの途中なら起点も e タグに入れる
+pub async fn publish_reply(
+    client: Client,
+    signer: AppSigner,
+    content: String,
+    reply_to: Event,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+) -> bool {
+    let (content, mut tags) = prepare_note(&content);
+    let (root, _) = thread_refs(&reply_to);
+    let event_tag = |event_id: EventId, marker: Marker| {
+        Tag::from_standardized(TagStandard::Event {
+            event_id,
+            relay_url: None,
+            marker: Some(marker),
+            public_key: None,
+            uppercase: false,
+        })
+    };
+    match root {
+        Some(root) => {
+            tags.push(event_tag(root, Marker::Root));
+            tags.push(event_tag(reply_to.id, Marker::Reply));
+        }
+        None => tags.push(event_tag(reply_to.id, Marker::Root)),
+    }
+    // 返信先の作者と、そのスレッドの参加者に届くようにする
+    let mut mentioned: Vec<PublicKey> = vec![reply_to.pubkey];
+    mentioned.extend(reply_to.tags.public_keys().copied());
+    for public_key in mentioned {
+        let tag = Tag::public_key(public_key);
+        if public_key != signer.public_key() && !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+
+    let event = match pow::sign_with_pow(EventBuilder::text_note(content).tags(tags), &signer, &app_data_arc).await {
+        Ok(event) => event,
+        Err(e) => {
+            error!("Failed to sign the reply: {e}");
+            app_data_arc.lock().unwrap().report_error(tr!("返信に署名できませんでした: {}", e));
+            return false;
+        }
+    };
+    send_note(client, event, app_data_arc).await
+}
+
+// リポスト (NIP-18) の本文に入っている元のイベント
+pub fn reposted_event(event: &Event) -> Option<Event> {
+    if event.kind != Kind::Repost && event.kind != Kind::GenericRepost {
+        return None;
+    }
+    Event::from_json(&event.content).ok().filter(|inner| inner.verify().is_ok())
+}
+
+// イベントをリポストする (NIP-18)。元のイベントはローカルの DB になければリレーから取る
+pub async fn repost(
+    client: Client,
+    signer: AppSigner,
+    event_id: EventId,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+) {
+    let stored = match client.database().event_by_id(&event_id).await {
+        Ok(event) => event,
+        Err(e) => {
+            error!("Failed to read {event_id} from the database: {e}");
+            None
+        }
+    };
+    let original = match stored {
+        Some(event) => Some(event),
+        None => network::fetch_events(&client, Filter::new().id(event_id))
+            .await
+            .ok()
+            .and_then(|events| events.first_owned()),
+    };
+    let Some(original) = original else {
+        app_data_arc.lock().unwrap().report_error(tr!("リポストする投稿が見つかりませんでした"));
+        return;
+    };
+
+    let event = match EventBuilder::repost(&original, None).sign(&signer).await {
+        Ok(event) => event,
+        Err(e) => {
+            error!("Failed to sign the repost: {e}");
+            app_data_arc.lock().unwrap().report_error(tr!("リポストに署名できませんでした: {}", e));
+            return;
+        }
+    };
+    let sent = outbox::send_or_queue(&client, event.clone(), &app_data_arc).await;
+    let mut app_data = app_data_arc.lock().unwrap();
+    if sent {
+        app_data.notify(tr!("リポストしました"));
+    } else {
+        app_data.report_error(tr!("リポストを送信できませんでした。送信キューに残し、接続が戻ったら再送します"));
+    }
+    app_data.notes.insert(0, event);
+}
+
+// コメントを付けて引用する (NIP-18)。本文の末尾に nevent を付け、q タグは prepare_note が作る
+pub async fn publish_quote(
+    client: Client,
+    signer: AppSigner,
+    content: String,
+    target: QuoteTarget,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+) -> bool {
+    let Ok(nevent) = Nip19Event::new(target.event_id).author(target.author).kind(target.kind).to_bech32() else {
+        return false;
+    };
+    publish_note(client, signer, format!("{}\n\nnostr:{nevent}", content.trim_end()), None, Vec::new(), app_data_arc).await
+}
@@ -0,0 +1,239 @@
+use nostr::{EventBuilder, Kind, Tag, Timestamp};
+use nostr_sdk::Client;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{error, info};
+
+use crate::signer::AppSigner;
+use crate::types::NostrStatusAppInternal;
+use crate::{outbox, pow, status_history};
+
+// 再生中の曲を確認する間隔
+const POLL_INTERVAL_SECS: u64 = 10;
+// 曲の長さがわからないときの期限
+const DEFAULT_EXPIRY_SECS: u64 = 10 * 60;
+// 曲の終わりから少し余裕を持たせて期限にする
+const EXPIRY_MARGIN_SECS: u64 = 30;
+
+#[derive(Debug, Clone)]
+pub struct NowPlaying {
+    pub title: String,
+    pub artist: String,
+    pub url: Option<String>,
+    pub remaining: Option<Duration>,
+}
+
+impl NowPlaying {
+    fn content(&self) -> String {
+        if self.artist.is_empty() {
+            self.title.clone()
+        } else {
+            format!("{} - {}", self.title, self.artist)
+        }
+    }
+}
+
+// 再生中の曲を読み取れる OS か。Linux は MPRIS、Windows は SMTC、macOS は MediaRemote から読む
+pub fn is_supported() -> bool {
+    cfg!(any(target_os = "linux", target_os = "windows", target_os = "macos"))
+}
+
+// MPRIS に対応したプレイヤーのうち、再生中のものから曲の情報を読む
+#[cfg(target_os = "linux")]
+fn current_track() -> Option<NowPlaying> {
+    use mpris::{PlaybackStatus, PlayerFinder};
+
+    let player = PlayerFinder::new().ok()?.find_active().ok()?;
+    if player.get_playback_status().ok()? != PlaybackStatus::Playing {
+        return None;
+    }
+    let metadata = player.get_metadata().ok()?;
+    let title = metadata.title().filter(|title| !title.is_empty())?.to_string();
+    let artist = metadata.artists().map(|artists| artists.join(", ")).unwrap_or_default();
+    // ローカルファイルのパスは公開しない
+    let url = metadata.url().filter(|url| url.starts_with("https://") || url.starts_with("http://")).map(str::to_string);
+    let remaining = metadata
+        .length()
+        .map(|length| length.saturating_sub(player.get_position().unwrap_or_default()));
+    Some(NowPlaying { title, artist, url, remaining })
+}
+
+// Windows のメディアの操作 (SMTC) から、いま操作の対象になっているセッションの曲を読む
+#[cfg(target_os = "windows")]
+fn current_track() -> Option<NowPlaying> {
+    use windows::Media::Control::{
+        GlobalSystemMediaTransportControlsSessionManager, GlobalSystemMediaTransportControlsSessionPlaybackStatus,
+    };
+
+    let manager = GlobalSystemMediaTransportControlsSessionManager::RequestAsync().ok()?.get().ok()?;
+    let session = manager.GetCurrentSession().ok()?;
+    let status = session.GetPlaybackInfo().ok()?.PlaybackStatus().ok()?;
+    if status != GlobalSystemMediaTransportControlsSessionPlaybackStatus::Playing {
+        return None;
+    }
+    let properties = session.TryGetMediaPropertiesAsync().ok()?.get().ok()?;
+    let title = properties.Title().ok()?.to_string_lossy();
+    if title.is_empty() {
+        return None;
+    }
+    let artist = properties.Artist().map(|artist| artist.to_string_lossy()).unwrap_or_default();
+    // 長さを伝えないプレイヤーでは終わりの時刻が 0 になる
+    let remaining = session.GetTimelineProperties().ok().and_then(|timeline| {
+        let end = timeline.EndTime().ok()?.Duration;
+        let position = timeline.Position().ok()?.Duration;
+        // TimeSpan は 100 ナノ秒単位
+        (end > 0).then(|| Duration::from_nanos((end - position).max(0) as u64 * 100))
+    });
+    // SMTC は曲の URL を持たない
+    Some(NowPlaying { title, artist, url: None, remaining })
+}
+
+#[cfg(target_os = "macos")]
+use media_remote::current_track;
+
+// MediaRemote (非公開のフレームワーク) から、システムの「再生中」に出ている曲を読む
+// MPNowPlayingInfoCenter は自分のアプリの情報しか読めないので使わない
+// macOS 15.4 以降は許可されたアプリにしか返さないので、そのときは読めない
+#[cfg(target_os = "macos")]
+mod media_remote {
+    use block2::{Block, RcBlock};
+    use objc2::runtime::AnyObject;
+    use objc2_foundation::{NSDate, NSDictionary, NSNumber, NSString};
+    use std::ffi::c_void;
+    use std::sync::{mpsc, OnceLock};
+    use std::time::Duration;
+
+    use super::NowPlaying;
+
+    type NowPlayingInfo = NSDictionary<NSString, AnyObject>;
+    type GetNowPlayingInfo = unsafe extern "C" fn(*mut c_void, &Block<dyn Fn(*const NowPlayingInfo)>);
+
+    unsafe extern "C" {
+        fn dispatch_get_global_queue(identifier: isize, flags: usize) -> *mut c_void;
+    }
+
+    const MEDIA_REMOTE_PATH: &str = "/System/Library/PrivateFrameworks/MediaRemote.framework/MediaRemote";
+    // 結果は別のキューから返ってくるので、待つ時間の上限を決めておく
+    const MEDIA_REMOTE_TIMEOUT_SECS: u64 = 2;
+
+    // 一度開いたら閉じない。読めないときは次から開き直さない
+    static MEDIA_REMOTE: OnceLock<Option<libloading::Library>> = OnceLock::new();
+
+    pub fn current_track() -> Option<NowPlaying> {
+        let library = MEDIA_REMOTE.get_or_init(|| unsafe { libloading::Library::new(MEDIA_REMOTE_PATH) }.ok()).as_ref()?;
+        let get_now_playing_info = unsafe { library.get::<GetNowPlayingInfo>(b"MRMediaRemoteGetNowPlayingInfo\0") }.ok()?;
+        let (sender, receiver) = mpsc::channel();
+        let handler = RcBlock::new(move |info: *const NowPlayingInfo| {
+            let _ = sender.send(unsafe { info.as_ref() }.and_then(read_info));
+        });
+        unsafe { get_now_playing_info(dispatch_get_global_queue(0, 0), &handler) };
+        receiver.recv_timeout(Duration::from_secs(MEDIA_REMOTE_TIMEOUT_SECS)).ok().flatten()
+    }
+
+    fn read_info(info: &NowPlayingInfo) -> Option<NowPlaying> {
+        let value = |key: &str| info.objectForKey(&NSString::from_str(key));
+        let string = |key: &str| value(key)?.downcast::<NSString>().ok().map(|string| string.to_string());
+        let number = |key: &str| value(key)?.downcast::<NSNumber>().ok().map(|number| number.doubleValue());
+
+        // 一時停止中は速度が 0 になる
+        let rate = number("kMRMediaRemoteNowPlayingInfoPlaybackRate").unwrap_or(0.0);
+        if rate <= 0.0 {
+            return None;
+        }
+        let title = string("kMRMediaRemoteNowPlayingInfoTitle").filter(|title| !title.is_empty())?;
+        let artist = string("kMRMediaRemoteNowPlayingInfoArtist").unwrap_or_default();
+        let remaining = number("kMRMediaRemoteNowPlayingInfoDuration").map(|length| {
+            // 経過時間は Timestamp の時点のものなので、そこから進んだ分を足す
+            let since_update = value("kMRMediaRemoteNowPlayingInfoTimestamp")
+                .and_then(|timestamp| timestamp.downcast::<NSDate>().ok())
+                .map(|timestamp| -timestamp.timeIntervalSinceNow())
+                .unwrap_or(0.0);
+            let elapsed = number("kMRMediaRemoteNowPlayingInfoElapsedTime").unwrap_or(0.0) + since_update * rate;
+            Duration::from_secs_f64((length - elapsed).max(0.0))
+        });
+        Some(NowPlaying { title, artist, url: None, remaining })
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+fn current_track() -> Option<NowPlaying> {
+    None
+}
+
+// 再生中の曲を music ステータス (NIP-38) として公開する。期限は曲の残り時間に合わせる
+async fn publish(
+    client: &Client,
+    signer: &AppSigner,
+    track: &NowPlaying,
+    app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>,
+) -> Option<Timestamp> {
+    let lifetime = track
+        .remaining
+        .map(|remaining| remaining.as_secs() + EXPIRY_MARGIN_SECS)
+        .unwrap_or(DEFAULT_EXPIRY_SECS);
+    let expiration = Timestamp::now() + Duration::from_secs(lifetime);
+    let mut tags = vec![Tag::identifier("music".to_string()), Tag::expiration(expiration)];
+    if let Some(url) = &track.url
+        && let Ok(tag) = Tag::parse(["r", url]) {
+        tags.push(tag);
+    }
+
+    let builder = EventBuilder::new(Kind::from(30315), track.content()).tags(tags);
+    let event = match pow::sign_with_pow(builder, signer, app_data_arc).await {
+        Ok(event) => event,
+        Err(e) => {
+            error!("Failed to sign the now playing status: {e}");
+            return None;
+        }
+    };
+    let sent = outbox::send_or_queue(client, event.clone(), app_data_arc).await;
+    // 曲ごとに履歴が埋まらないよう、今のステータスだけを更新する
+    status_history::set_current(&mut app_data_arc.lock().unwrap(), &event);
+    if sent {
+        info!("Published now playing status: {}", event.content);
+    }
+    Some(expiration)
+}
+
+// 設定で有効なときに、再生中の曲が変わったら music ステータスを更新する
+pub async fn run_now_playing_worker(
+    client: Client,
+    keys: AppSigner,
+    session: u64,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+) {
+    let mut interval = tokio::time::interval(Duration::from_secs(POLL_INTERVAL_SECS));
+    // 最後に公開した曲 (曲名, アーティスト) とその期限
+    let mut published: Option<(String, String, Timestamp)> = None;
+    loop {
+        interval.tick().await;
+        let enabled = {
+            let app_data = app_data_arc.lock().unwrap();
+            // ロックやログアウトの後は終了する
+            if !app_data.is_current_session(session) {
+                break;
+            }
+            app_data.settings.now_playing_enabled
+        };
+        if !enabled {
+            published = None;
+            continue;
+        }
+
+        let track = tokio::task::spawn_blocking(current_track).await.ok().flatten();
+        let Some(track) = track else {
+            // 停止中は公開し直さない。公開済みのステータスは期限で消える
+            published = None;
+            continue;
+        };
+        let is_unchanged = published.as_ref().is_some_and(|(title, artist, expiration)| {
+            *title == track.title && *artist == track.artist && *expiration > Timestamp::now()
+        });
+        if is_unchanged {
+            continue;
+        }
+        if let Some(expiration) = publish(&client, &keys, &track, &app_data_arc).await {
+            published = Some((track.title, track.artist, expiration));
+        }
+    }
+}
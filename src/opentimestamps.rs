@@ -0,0 +1,343 @@
+use base64::{Engine as _, engine::general_purpose};
+use nostr::{Event, EventBuilder, Kind, Tag, Timestamp};
+use nostr_sdk::Client;
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+use crate::cache_db::{DB_OPENTIMESTAMPS, LmdbCache};
+use crate::network;
+use crate::outbox;
+use crate::signer::AppSigner;
+use crate::types::{NostrStatusAppInternal, TimestampProof, TimestampProofState};
+
+// 公開したステータスの id を OpenTimestamps のカレンダーに送り、ビットコインのブロックに記録されたら
+// その証明を kind 1040 (NIP-03) のイベントとして公開する
+// 証明はカレンダーから受け取ったものをそのまま使い、ブロックヘッダーとの照合はしない
+
+// 上から順に試し、最初に受け付けたカレンダーで証明を進める
+const CALENDARS: [&str; 2] = [
+    "https://alice.btc.calendar.opentimestamps.org",
+    "https://bob.btc.calendar.opentimestamps.org",
+];
+const CALENDAR_TIMEOUT_SECS: u64 = 15;
+// ブロックに記録されたかを問い合わせる間隔。記録されるまでは数時間かかる
+const UPGRADE_INTERVAL_SECS: u64 = 30 * 60;
+// 残しておく証明の件数。古いものから捨てる
+const MAX_PROOFS: usize = 200;
+const MAX_RESPONSE_BYTES: u64 = 64 * 1024;
+const OTS_KIND: u16 = 1040;
+const ACCEPT: &str = "application/vnd.opentimestamps.v1";
+
+// .ots ファイルの先頭
+const HEADER_MAGIC: &[u8] = b"\x00OpenTimestamps\x00\x00Proof\x00\xbf\x89\xe2\xe8\x84\xe8\x92\x94";
+const VERSION: u8 = 1;
+const OP_SHA256: u8 = 0x08;
+const OP_APPEND: u8 = 0xf0;
+const OP_PREPEND: u8 = 0xf1;
+const ATTESTATION: u8 = 0x00;
+const PENDING_TAG: [u8; 8] = [0x83, 0xdf, 0xe3, 0x0d, 0x2e, 0xf9, 0x0c, 0x8e];
+const BITCOIN_TAG: [u8; 8] = [0x05, 0x88, 0x96, 0x0d, 0x73, 0xd7, 0x19, 0x01];
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self.pos.checked_add(len).filter(|end| *end <= self.bytes.len()).ok_or("truncated timestamp")?;
+        let bytes = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    // LEB128 の符号なし整数
+    fn varuint(&mut self) -> Result<u64, String> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err("varuint is too long".to_string())
+    }
+
+    fn varbytes(&mut self) -> Result<&'a [u8], String> {
+        let len = self.varuint()?;
+        self.take(usize::try_from(len).map_err(|e| e.to_string())?)
+    }
+}
+
+// カレンダーの応答をたどり、保留中の証明が付いたコミットメントと、その証明が始まる位置を返す
+// カレンダーの応答は分岐のない一本道なので、分岐や sha256 以外のハッシュが出てきたら諦める
+fn pending_commitment(digest: &[u8], proof: &[u8]) -> Result<(Vec<u8>, usize), String> {
+    let mut reader = Reader { bytes: proof, pos: 0 };
+    let mut message = digest.to_vec();
+    loop {
+        let start = reader.pos;
+        match reader.byte()? {
+            OP_SHA256 => message = Sha256::digest(&message).to_vec(),
+            OP_APPEND => message.extend_from_slice(reader.varbytes()?),
+            OP_PREPEND => message = [reader.varbytes()?, message.as_slice()].concat(),
+            ATTESTATION => {
+                let tag = reader.take(PENDING_TAG.len())?;
+                reader.varbytes()?;
+                if tag != PENDING_TAG {
+                    return Err("the calendar returned an unexpected attestation".to_string());
+                }
+                return Ok((message, start));
+            }
+            op => return Err(format!("unsupported timestamp op {op:#04x}")),
+        }
+    }
+}
+
+fn http_agent() -> ureq::Agent {
+    network::http_agent(Duration::from_secs(CALENDAR_TIMEOUT_SECS))
+}
+
+fn read_body(response: ureq::Response) -> std::io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    response.into_reader().take(MAX_RESPONSE_BYTES).read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn submit_to_calendar(calendar: &str, digest: &[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+    let response = http_agent().post(&format!("{calendar}/digest")).set("Accept", ACCEPT).send_bytes(digest)?;
+    let proof = read_body(response)?;
+    pending_commitment(digest, &proof)?;
+    Ok(proof)
+}
+
+// 受け付けたカレンダーと、受け取った保留中の証明を返す
+async fn submit(digest: [u8; 32]) -> Result<(String, Vec<u8>), Box<dyn std::error::Error + Send + Sync>> {
+    tokio::task::spawn_blocking(move || -> Result<(String, Vec<u8>), Box<dyn std::error::Error + Send + Sync>> {
+        let mut last_error: Box<dyn std::error::Error + Send + Sync> = "no calendar".into();
+        for calendar in CALENDARS {
+            match submit_to_calendar(calendar, &digest) {
+                Ok(proof) => return Ok((calendar.to_string(), proof)),
+                Err(e) => {
+                    warn!("OpenTimestamps calendar {calendar} failed: {e}");
+                    last_error = e;
+                }
+            }
+        }
+        Err(last_error)
+    })
+    .await?
+}
+
+pub fn load_proofs(cache_db: &LmdbCache, pubkey_hex: &str) -> Vec<TimestampProof> {
+    match cache_db.read_value::<Vec<TimestampProof>>(DB_OPENTIMESTAMPS, pubkey_hex) {
+        Ok(proofs) => proofs.unwrap_or_default(),
+        Err(e) => {
+            error!("Failed to load the timestamp proofs: {e}");
+            Vec::new()
+        }
+    }
+}
+
+fn save_proofs(app_data: &NostrStatusAppInternal) {
+    let Some(signer) = &app_data.my_signer else {
+        return;
+    };
+    if let Err(e) = app_data.cache_db.write_value(DB_OPENTIMESTAMPS, &signer.public_key().to_string(), &app_data.timestamp_proofs) {
+        error!("Failed to save the timestamp proofs: {e}");
+    }
+}
+
+// 公開したイベントの証明を作り始める。設定で有効にしていなければ何もしない
+pub async fn stamp(event: Event, app_data_arc: Arc<Mutex<NostrStatusAppInternal>>) {
+    if !app_data_arc.lock().unwrap().settings.opentimestamps_enabled {
+        return;
+    }
+    let (calendar, pending_proof, state) = match submit(event.id.to_bytes()).await {
+        Ok((calendar, proof)) => {
+            info!("Submitted event {} to {calendar}", event.id);
+            (calendar, proof, TimestampProofState::Pending)
+        }
+        Err(e) => {
+            error!("Failed to submit event {} to OpenTimestamps: {e}", event.id);
+            (String::new(), Vec::new(), TimestampProofState::Failed(e.to_string()))
+        }
+    };
+
+    let mut app_data = app_data_arc.lock().unwrap();
+    // 送っている間にログアウトやアカウントの切り替えをしていたら捨てる
+    if app_data.my_signer.as_ref().map(|signer| signer.public_key()) != Some(event.pubkey) {
+        return;
+    }
+    app_data.timestamp_proofs.retain(|proof| proof.event_id != event.id);
+    app_data.timestamp_proofs.push(TimestampProof {
+        event_id: event.id,
+        kind: event.kind.as_u16(),
+        calendar,
+        pending_proof,
+        state,
+        submitted_at: Timestamp::now(),
+    });
+    if app_data.timestamp_proofs.len() > MAX_PROOFS {
+        let excess = app_data.timestamp_proofs.len() - MAX_PROOFS;
+        app_data.timestamp_proofs.drain(..excess);
+    }
+    save_proofs(&app_data);
+}
+
+// ブロックに記録されていれば .ots ファイルの中身を、まだなら None を返す
+async fn upgrade(proof: &TimestampProof) -> Result<Option<Vec<u8>>, Box<dyn std::error::Error + Send + Sync>> {
+    let digest = proof.event_id.to_bytes();
+    let (commitment, attestation_start) = pending_commitment(&digest, &proof.pending_proof)?;
+    let url = format!("{}/timestamp/{}", proof.calendar, hex::encode(&commitment));
+    let upgraded = tokio::task::spawn_blocking(move || -> Result<Option<Vec<u8>>, Box<dyn std::error::Error + Send + Sync>> {
+        match http_agent().get(&url).set("Accept", ACCEPT).call() {
+            Ok(response) => Ok(Some(read_body(response)?)),
+            // まだ集約されていない
+            Err(ureq::Error::Status(404, _)) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    })
+    .await??;
+    // ブロックに記録されるまで、カレンダーは保留中の証明だけを返す
+    let Some(upgraded) = upgraded.filter(|bytes| bytes.windows(BITCOIN_TAG.len()).any(|window| window == BITCOIN_TAG)) else {
+        return Ok(None);
+    };
+
+    // 保留中の証明を、カレンダーから受け取ったブロックまでの経路に置き換える
+    let mut ots = Vec::with_capacity(HEADER_MAGIC.len() + 2 + digest.len() + attestation_start + upgraded.len());
+    ots.extend_from_slice(HEADER_MAGIC);
+    ots.push(VERSION);
+    ots.push(OP_SHA256);
+    ots.extend_from_slice(&digest);
+    ots.extend_from_slice(&proof.pending_proof[..attestation_start]);
+    ots.extend_from_slice(&upgraded);
+    Ok(Some(ots))
+}
+
+async fn publish_attestation(
+    client: &Client,
+    keys: &AppSigner,
+    proof: &TimestampProof,
+    ots: &[u8],
+    app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let event = EventBuilder::new(Kind::Custom(OTS_KIND), general_purpose::STANDARD.encode(ots))
+        .tag(Tag::event(proof.event_id))
+        .tag(Tag::parse(["k", &proof.kind.to_string()])?)
+        .sign(keys)
+        .await?;
+    // 送れなかったときは送信キューが再送する
+    outbox::send_or_queue(client, event, app_data_arc).await;
+    Ok(())
+}
+
+// 保留中の証明がブロックに記録されたかを定期的に問い合わせ、記録されていれば kind 1040 を公開する
+pub async fn run_upgrade_worker(client: Client, keys: AppSigner, session: u64, app_data_arc: Arc<Mutex<NostrStatusAppInternal>>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(UPGRADE_INTERVAL_SECS));
+    loop {
+        interval.tick().await;
+        let pending: Vec<TimestampProof> = {
+            let app_data = app_data_arc.lock().unwrap();
+            // ロックやログアウトの後は終了する
+            if !app_data.is_current_session(session) {
+                break;
+            }
+            app_data
+                .timestamp_proofs
+                .iter()
+                .filter(|proof| proof.state == TimestampProofState::Pending)
+                .cloned()
+                .collect()
+        };
+
+        for proof in pending {
+            let ots = match upgrade(&proof).await {
+                Ok(Some(ots)) => ots,
+                Ok(None) => continue,
+                Err(e) => {
+                    warn!("Failed to upgrade the timestamp of {}: {e}", proof.event_id);
+                    continue;
+                }
+            };
+            if let Err(e) = publish_attestation(&client, &keys, &proof, &ots, &app_data_arc).await {
+                error!("Failed to publish the timestamp attestation of {}: {e}", proof.event_id);
+                continue;
+            }
+            info!("Published the timestamp attestation of {}", proof.event_id);
+            let mut app_data = app_data_arc.lock().unwrap();
+            if let Some(entry) = app_data.timestamp_proofs.iter_mut().find(|entry| entry.event_id == proof.event_id) {
+                entry.state = TimestampProofState::Attested;
+            }
+            save_proofs(&app_data);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn varbytes(bytes: &[u8]) -> Vec<u8> {
+        [&[bytes.len() as u8][..], bytes].concat()
+    }
+
+    fn pending_attestation(url: &str) -> Vec<u8> {
+        [&[ATTESTATION][..], &PENDING_TAG, &varbytes(&varbytes(url.as_bytes()))].concat()
+    }
+
+    #[test]
+    fn varuint_reads_leb128() {
+        let mut reader = Reader { bytes: &[0x01, 0xac, 0x02, 0xff], pos: 0 };
+        assert_eq!(reader.varuint().unwrap(), 1);
+        assert_eq!(reader.varuint().unwrap(), 300);
+        // 続きのバイトがないまま終わっている
+        assert!(reader.varuint().is_err());
+    }
+
+    #[test]
+    fn varbytes_checks_the_length() {
+        let mut reader = Reader { bytes: &[0x02, 0xaa, 0xbb, 0x03, 0xcc], pos: 0 };
+        assert_eq!(reader.varbytes().unwrap(), &[0xaa, 0xbb]);
+        assert!(reader.varbytes().is_err());
+    }
+
+    #[test]
+    fn pending_commitment_follows_the_ops() {
+        let digest = [0x11u8; 32];
+        let proof = [
+            &[OP_APPEND][..],
+            &varbytes(&[0xaa, 0xbb]),
+            &[OP_SHA256],
+            &[OP_PREPEND],
+            &varbytes(&[0xcc]),
+            &pending_attestation("https://alice.btc.calendar.opentimestamps.org"),
+        ]
+        .concat();
+        let (commitment, start) = pending_commitment(&digest, &proof).unwrap();
+        let hashed = Sha256::digest([&digest[..], &[0xaa, 0xbb]].concat());
+        assert_eq!(commitment, [&[0xcc][..], hashed.as_slice()].concat());
+        assert_eq!(start, 8);
+        assert_eq!(proof[start], ATTESTATION);
+    }
+
+    #[test]
+    fn pending_commitment_rejects_unexpected_proofs() {
+        let digest = [0x11u8; 32];
+        // 保留中以外の証明
+        let bitcoin = [&[ATTESTATION][..], &BITCOIN_TAG, &varbytes(&[0x01])].concat();
+        assert!(pending_commitment(&digest, &bitcoin).is_err());
+        // 分岐 (0xff) などの知らない操作
+        assert!(pending_commitment(&digest, &[0xff]).is_err());
+        // 途中で切れている
+        assert!(pending_commitment(&digest, &[OP_APPEND, 0x05, 0xaa]).is_err());
+        assert!(pending_commitment(&digest, &[OP_SHA256]).is_err());
+    }
+}
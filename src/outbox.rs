@@ -0,0 +1,342 @@
+use eframe::egui;
+use nostr::{Event, EventId, Timestamp};
+use nostr_sdk::prelude::Output;
+use nostr_sdk::{Client, RelayStatus};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{error, info};
+
+use crate::cache_db::{LmdbCache, DB_OUTBOX};
+use crate::i18n::tr;
+use crate::types::{HeldSend, NostrStatusAppInternal, OutboxEntry, OutboxState, RelayPublishResult};
+
+// 送信待ちのイベントを再送する間隔
+const RETRY_INTERVAL_SECS: u64 = 15;
+// 送信済みとして表示しておく件数
+const MAX_SENT_ENTRIES: usize = 20;
+
+static NEXT_HELD_SEND_ID: AtomicU64 = AtomicU64::new(0);
+
+// 保存済みの送信キューを読み込む
+pub fn load_outbox(cache_db: &LmdbCache, pubkey_hex: &str) -> Vec<OutboxEntry> {
+    match cache_db.read_value::<Vec<OutboxEntry>>(DB_OUTBOX, pubkey_hex) {
+        Ok(outbox) => outbox.unwrap_or_default(),
+        Err(e) => {
+            error!("Failed to load the outbox: {e}");
+            Vec::new()
+        }
+    }
+}
+
+// 送信済みの古いものを捨ててから送信キューを保存する
+fn save_outbox(app_data: &mut NostrStatusAppInternal) {
+    let sent_count = app_data.outbox.iter().filter(|e| e.state == OutboxState::Sent).count();
+    let mut to_drop = sent_count.saturating_sub(MAX_SENT_ENTRIES);
+    app_data.outbox.retain(|entry| {
+        if to_drop > 0 && entry.state == OutboxState::Sent {
+            to_drop -= 1;
+            return false;
+        }
+        true
+    });
+
+    let Some(signer) = &app_data.my_signer else {
+        return;
+    };
+    let pubkey_hex = signer.public_key().to_string();
+    if let Err(e) = app_data.cache_db.write_value(DB_OUTBOX, &pubkey_hex, &app_data.outbox) {
+        error!("Failed to save the outbox: {e}");
+    }
+}
+
+// 送信結果を記録する。リレーごとの結果は上書きし、1つでも受け取ったリレーがあれば送信済みにする
+fn record_result(entry: &mut OutboxEntry, result: Result<Output<EventId>, String>) {
+    entry.attempts += 1;
+    let output = match result {
+        Ok(output) => output,
+        Err(e) => {
+            entry.last_error = Some(e);
+            return;
+        }
+    };
+    let mut results: Vec<RelayPublishResult> = output
+        .success
+        .into_iter()
+        .map(|url| RelayPublishResult { url: url.to_string(), error: None })
+        .chain(output.failed.into_iter().map(|(url, e)| RelayPublishResult { url: url.to_string(), error: Some(e) }))
+        .collect();
+    results.sort_by(|a, b| a.url.cmp(&b.url));
+    for result in results {
+        match entry.relay_results.iter_mut().find(|r| r.url == result.url) {
+            Some(existing) => *existing = result,
+            None => entry.relay_results.push(result),
+        }
+    }
+
+    if entry.relay_results.iter().any(|r| r.error.is_none()) {
+        entry.state = OutboxState::Sent;
+        entry.last_error = None;
+    } else {
+        entry.last_error = Some(
+            entry
+                .relay_results
+                .iter()
+                .find_map(|r| r.error.clone())
+                .unwrap_or_else(|| tr!("接続中のリレーがありません").to_string()),
+        );
+    }
+}
+
+fn queue_entry(event: Event, not_before: Option<Timestamp>) -> OutboxEntry {
+    OutboxEntry {
+        event,
+        state: OutboxState::Pending,
+        attempts: 0,
+        last_error: None,
+        relay_results: Vec::new(),
+        not_before,
+    }
+}
+
+// 再送してよい送信待ちのものか。取り消せる間のものは、アプリを閉じるとき以外は送らない
+fn is_due(entry: &OutboxEntry, now: Timestamp) -> bool {
+    entry.state == OutboxState::Pending && entry.not_before.is_none_or(|not_before| not_before <= now)
+}
+
+// 設定した秒数だけ送らずに待ち、その間は通知の「取り消す」で取りやめられるようにしてから送る
+// 待っている間にアプリを閉じたりロックしたりしても消えないよう、イベントは送信予定の時刻を付けて送信キューに入れておく
+// 取り消されたかログアウトしたなら None、そうでなければすべて送れたかどうかを返す
+pub async fn send_or_queue_with_undo(
+    client: &Client,
+    events: Vec<Event>,
+    app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>,
+    description: &str,
+) -> Option<bool> {
+    let hold = {
+        let mut app_data = app_data_arc.lock().unwrap();
+        let delay = Duration::from_secs(app_data.settings.undo_send_secs);
+        (!delay.is_zero()).then(|| {
+            let id = NEXT_HELD_SEND_ID.fetch_add(1, Ordering::Relaxed);
+            let not_before = Timestamp::now() + delay;
+            app_data.outbox.extend(events.iter().map(|event| queue_entry(event.clone(), Some(not_before))));
+            save_outbox(&mut app_data);
+            app_data.held_sends.push(HeldSend {
+                id,
+                description: description.to_string(),
+                send_at: Instant::now() + delay,
+                event_ids: events.iter().map(|event| event.id).collect(),
+            });
+            app_data.should_repaint = true;
+            (id, delay, app_data.session_generation)
+        })
+    };
+    let Some((id, delay, session)) = hold else {
+        let mut sent = true;
+        for event in events {
+            sent &= send_or_queue(client, event, app_data_arc).await;
+        }
+        return Some(sent);
+    };
+    tokio::time::sleep(delay).await;
+
+    {
+        let mut app_data = app_data_arc.lock().unwrap();
+        // ロックやログアウトのあとは、送信キューに残した分を次にログインしたときに送る
+        if !app_data.is_current_session(session) {
+            return None;
+        }
+        let held = app_data.held_sends.iter().any(|held| held.id == id);
+        app_data.held_sends.retain(|held| held.id != id);
+        app_data.should_repaint = true;
+        if !held {
+            info!("Sending \"{description}\" was undone.");
+            return None;
+        }
+    }
+    let mut sent = true;
+    for event in events {
+        sent &= send_queued(client, &event, app_data_arc, session).await;
+    }
+    Some(sent)
+}
+
+// 送信を取りやめ、送信キューに入れておいたイベントも消す
+pub fn undo(app_data: &mut NostrStatusAppInternal, id: u64) {
+    let event_ids: Vec<EventId> = app_data
+        .held_sends
+        .iter()
+        .filter(|held| held.id == id)
+        .flat_map(|held| held.event_ids.iter().copied())
+        .collect();
+    app_data.held_sends.retain(|held| held.id != id);
+    app_data.outbox.retain(|entry| !event_ids.contains(&entry.event.id));
+    save_outbox(app_data);
+    app_data.notify(tr!("送信を取り消しました"));
+}
+
+// 送信キューに入れてあるイベントを送り、結果を記録する
+async fn send_queued(client: &Client, event: &Event, app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>, session: u64) -> bool {
+    let result = client.send_event(event).await.map_err(|e| e.to_string());
+    let mut app_data = app_data_arc.lock().unwrap();
+    if !app_data.is_current_session(session) {
+        return result.is_ok();
+    }
+    let Some(entry) = app_data.outbox.iter_mut().find(|entry| entry.event.id == event.id) else {
+        return result.is_ok();
+    };
+    entry.not_before = None;
+    record_result(entry, result);
+    let sent = entry.state == OutboxState::Sent;
+    if let Some(e) = &entry.last_error {
+        error!("Failed to publish {}, queued for retry: {e}", entry.event.id);
+    }
+    save_outbox(&mut app_data);
+    app_data.should_repaint = true;
+    sent
+}
+
+// 署名済みのイベントを送信する。送れなかったときは送信キューに残して後で再送する
+pub async fn send_or_queue(
+    client: &Client,
+    event: Event,
+    app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>,
+) -> bool {
+    let result = client.send_event(&event).await.map_err(|e| e.to_string());
+
+    let mut app_data = app_data_arc.lock().unwrap();
+    let mut entry = queue_entry(event, None);
+    record_result(&mut entry, result);
+    let sent = entry.state == OutboxState::Sent;
+    if let Some(e) = &entry.last_error {
+        error!("Failed to publish {}, queued for retry: {e}", entry.event.id);
+    }
+    app_data.outbox.push(entry);
+    save_outbox(&mut app_data);
+    app_data.should_repaint = true;
+    sent
+}
+
+// 拒否したリレーにだけ送り直す
+pub async fn retry_failed_relays(
+    client: Client,
+    event_id: EventId,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+) {
+    let (event, failed_urls) = {
+        let app_data = app_data_arc.lock().unwrap();
+        let Some(entry) = app_data.outbox.iter().find(|entry| entry.event.id == event_id) else {
+            return;
+        };
+        let failed_urls: Vec<String> = entry
+            .relay_results
+            .iter()
+            .filter(|r| r.error.is_some())
+            .map(|r| r.url.clone())
+            .collect();
+        (entry.event.clone(), failed_urls)
+    };
+    if failed_urls.is_empty() {
+        return;
+    }
+
+    let result = client.send_event_to(failed_urls, &event).await.map_err(|e| e.to_string());
+    let mut app_data = app_data_arc.lock().unwrap();
+    if let Some(entry) = app_data.outbox.iter_mut().find(|entry| entry.event.id == event_id) {
+        record_result(entry, result);
+    }
+    save_outbox(&mut app_data);
+    app_data.should_repaint = true;
+}
+
+// 送信待ちのイベントを送信キューから取り除く
+pub fn discard(app_data: &mut NostrStatusAppInternal, event_id: EventId) {
+    app_data.outbox.retain(|entry| entry.event.id != event_id);
+    save_outbox(app_data);
+}
+
+// 送信済みの表示をまとめて消す
+pub fn clear_sent(app_data: &mut NostrStatusAppInternal) {
+    app_data.outbox.retain(|entry| entry.state == OutboxState::Pending);
+    save_outbox(app_data);
+}
+
+// アプリを閉じる前に、送信待ちのイベントをもう一度だけ送ってみる。送れなかったものは次に起動したときに再送する
+// 取り消せる間に閉じたものも、取り消されてはいないので送る
+pub async fn flush(client: &Client, app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>) {
+    let pending: Vec<Event> = app_data_arc
+        .lock()
+        .unwrap()
+        .outbox
+        .iter()
+        .filter(|entry| entry.state == OutboxState::Pending)
+        .map(|entry| entry.event.clone())
+        .collect();
+    if pending.is_empty() {
+        return;
+    }
+    info!("Flushing {} queued events before exit.", pending.len());
+    for event in pending {
+        let result = client.send_event(&event).await.map_err(|e| e.to_string());
+        let mut app_data = app_data_arc.lock().unwrap();
+        if let Some(entry) = app_data.outbox.iter_mut().find(|entry| entry.event.id == event.id) {
+            entry.not_before = None;
+            record_result(entry, result);
+        }
+    }
+    save_outbox(&mut app_data_arc.lock().unwrap());
+}
+
+// リレーに接続できているときに、送信待ちのイベントを順に再送する
+pub async fn run_outbox_worker(
+    client: Client,
+    session: u64,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    ctx: egui::Context,
+) {
+    let mut interval = tokio::time::interval(Duration::from_secs(RETRY_INTERVAL_SECS));
+    loop {
+        interval.tick().await;
+        let pending: Vec<Event> = {
+            let app_data = app_data_arc.lock().unwrap();
+            // ロックやログアウトの後は終了する
+            if !app_data.is_current_session(session) {
+                break;
+            }
+            let now = Timestamp::now();
+            // 送信予定の時刻は秒単位なので、取り消しの通知が消える前に来ることがある。取り消せる間のものは send_or_queue_with_undo に任せる
+            let held: HashSet<EventId> = app_data.held_sends.iter().flat_map(|held| held.event_ids.iter().copied()).collect();
+            app_data
+                .outbox
+                .iter()
+                .filter(|entry| is_due(entry, now) && !held.contains(&entry.event.id))
+                .map(|entry| entry.event.clone())
+                .collect()
+        };
+        if pending.is_empty() {
+            continue;
+        }
+        let is_online = client
+            .relays()
+            .await
+            .values()
+            .any(|relay| relay.status() == RelayStatus::Connected);
+        if !is_online {
+            continue;
+        }
+
+        for event in pending {
+            let result = client.send_event(&event).await.map_err(|e| e.to_string());
+            let mut app_data = app_data_arc.lock().unwrap();
+            if let Some(entry) = app_data.outbox.iter_mut().find(|entry| entry.event.id == event.id) {
+                record_result(entry, result);
+                if entry.state == OutboxState::Sent {
+                    info!("Queued event {} was published.", event.id);
+                }
+            }
+        }
+        save_outbox(&mut app_data_arc.lock().unwrap());
+        ctx.request_repaint();
+    }
+}
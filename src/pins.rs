@@ -0,0 +1,77 @@
+use nostr::PublicKey;
+use nostr_sdk::Client;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use tracing::error;
+
+use crate::i18n::tr;
+use crate::nip78;
+use crate::signer::AppSigner;
+use crate::types::{NostrStatusAppInternal, PinnedItems};
+
+// トークのピン留めと友だちのお気に入りは、NIP-78 のアプリデータにして端末間で共有する
+const PINS_KEY: &str = "pins";
+
+// ログインしたときに読み込む。読み込めるまでは、リレーのデータを上書きしないよう変更できなくする
+pub async fn load_pins(client: Client, keys: AppSigner, app_data_arc: Arc<Mutex<NostrStatusAppInternal>>) {
+    match nip78::fetch_app_data::<PinnedItems>(&client, &keys, PINS_KEY).await {
+        Ok(pins) => {
+            let mut app_data = app_data_arc.lock().unwrap();
+            app_data.pins = pins.map(|(pins, _)| pins).unwrap_or_default();
+            app_data.pins_loaded = true;
+            app_data.should_repaint = true;
+        }
+        Err(e) => error!("Failed to fetch the pinned items: {e}"),
+    }
+}
+
+fn toggle(set: &mut HashSet<PublicKey>, pubkey: PublicKey) {
+    if !set.remove(&pubkey) {
+        set.insert(pubkey);
+    }
+}
+
+pub fn toggle_pinned_conversation(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    peer: PublicKey,
+) {
+    if !app_data.pins_loaded {
+        return;
+    }
+    toggle(&mut app_data.pins.pinned_conversations, peer);
+    spawn_save_pins(app_data, app_data_arc, runtime_handle);
+}
+
+pub fn toggle_favorite_friend(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    pubkey: PublicKey,
+) {
+    if !app_data.pins_loaded {
+        return;
+    }
+    toggle(&mut app_data.pins.favorite_friends, pubkey);
+    spawn_save_pins(app_data, app_data_arc, runtime_handle);
+}
+
+// 画面にはすぐ反映し、保存は裏で行う
+fn spawn_save_pins(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+) {
+    let (Some(client), Some(keys)) = (app_data.nostr_client.clone(), app_data.my_signer.clone()) else {
+        return;
+    };
+    let pins = app_data.pins.clone();
+    app_data.should_repaint = true;
+    runtime_handle.spawn(async move {
+        if let Err(e) = nip78::publish_app_data(&client, &keys, PINS_KEY, &pins).await {
+            error!("Failed to save the pinned items: {e}");
+            app_data_arc.lock().unwrap().report_error(tr!("ピン留めを保存できませんでした: {}", e));
+        }
+    });
+}
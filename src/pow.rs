@@ -0,0 +1,108 @@
+use nostr::{Event, EventBuilder, EventId, Tag, UnsignedEvent};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tracing::info;
+
+use crate::i18n::tr;
+use crate::signer::AppSigner;
+use crate::types::{NostrStatusAppInternal, PowJob};
+
+// 投稿に NIP-13 の PoW (nonce タグ) を付けてから署名する
+// ノンスの探索は画面と非同期のランタイムを止めないよう、別のスレッドで CPU の数だけ並列に行う
+
+// 試した回数を数え直す間隔。毎回数えるとスレッドの間で取り合いになる
+const ATTEMPTS_BATCH: u64 = 1024;
+
+static NEXT_POW_JOB_ID: AtomicU64 = AtomicU64::new(0);
+
+// スレッドごとにずらしたノンスを試し、見つけたものか、中止されたら None を返す
+fn mine(unsigned: &UnsignedEvent, difficulty: u8, attempts: &AtomicU64, cancelled: &AtomicBool) -> Option<(u128, EventId)> {
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let found = AtomicBool::new(false);
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|start| {
+                let found = &found;
+                scope.spawn(move || {
+                    let mut tags = unsigned.tags.clone();
+                    let mut nonce = start as u128;
+                    let mut batch = 0;
+                    while !found.load(Ordering::Relaxed) && !cancelled.load(Ordering::Relaxed) {
+                        tags.push(Tag::pow(nonce, difficulty));
+                        let id = EventId::new(&unsigned.pubkey, &unsigned.created_at, &unsigned.kind, &tags, &unsigned.content);
+                        tags.pop();
+                        if id.check_pow(difficulty) {
+                            found.store(true, Ordering::Relaxed);
+                            return Some((nonce, id));
+                        }
+                        nonce += threads as u128;
+                        batch += 1;
+                        if batch == ATTEMPTS_BATCH {
+                            attempts.fetch_add(batch, Ordering::Relaxed);
+                            batch = 0;
+                        }
+                    }
+                    None
+                })
+            })
+            .collect();
+        workers.into_iter().filter_map(|worker| worker.join().ok().flatten()).next()
+    })
+}
+
+// 設定の難易度で PoW を付けて署名する。難易度が 0 ならそのまま署名する
+// 計算している間は通知に進み具合を出し、中止されたらエラーを返す
+pub async fn sign_with_pow(
+    builder: EventBuilder,
+    signer: &AppSigner,
+    app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>,
+) -> Result<Event, Box<dyn std::error::Error + Send + Sync>> {
+    let difficulty = app_data_arc.lock().unwrap().settings.pow_difficulty;
+    let mut unsigned = builder.build(signer.public_key());
+    if difficulty == 0 {
+        return Ok(unsigned.sign(signer).await?);
+    }
+
+    let job = PowJob {
+        id: NEXT_POW_JOB_ID.fetch_add(1, Ordering::Relaxed),
+        difficulty,
+        attempts: Arc::new(AtomicU64::new(0)),
+        cancelled: Arc::new(AtomicBool::new(false)),
+    };
+    {
+        let mut app_data = app_data_arc.lock().unwrap();
+        app_data.pow_jobs.push(job.clone());
+        app_data.should_repaint = true;
+    }
+    let started_at = std::time::Instant::now();
+    let mined = {
+        let unsigned = unsigned.clone();
+        let attempts = job.attempts.clone();
+        let cancelled = job.cancelled.clone();
+        tokio::task::spawn_blocking(move || mine(&unsigned, difficulty, &attempts, &cancelled)).await
+    };
+    {
+        let mut app_data = app_data_arc.lock().unwrap();
+        app_data.pow_jobs.retain(|j| j.id != job.id);
+        app_data.should_repaint = true;
+    }
+
+    let (nonce, id) = mined?.ok_or(tr!("PoW の計算を中止しました"))?;
+    info!("Mined a difficulty {difficulty} nonce after {:?}", started_at.elapsed());
+    unsigned.tags.push(Tag::pow(nonce, difficulty));
+    unsigned.id = Some(id);
+    Ok(unsigned.sign(signer).await?)
+}
+
+// 計算を中止する。探索しているスレッドは次のノンスを試す前に止まる
+pub fn cancel(app_data: &mut NostrStatusAppInternal, id: u64) {
+    for job in app_data.pow_jobs.iter().filter(|job| job.id == id) {
+        job.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+pub fn cancel_all(app_data: &mut NostrStatusAppInternal) {
+    for job in app_data.pow_jobs.drain(..) {
+        job.cancelled.store(true, Ordering::Relaxed);
+    }
+}
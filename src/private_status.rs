@@ -0,0 +1,162 @@
+use nostr::{Event, EventBuilder, Filter, Kind, NostrSigner, PublicKey, Tag, TagKind, Timestamp, UnsignedEvent};
+use nostr_sdk::Client;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::error;
+
+use crate::gift_wrap;
+use crate::i18n::tr;
+use crate::network;
+use crate::note;
+use crate::replaceable;
+use crate::signer::AppSigner;
+use crate::types::{FollowSet, NostrStatusAppInternal, ProfileMetadata, TimelinePost};
+
+// フォローセットの非公開の項目 (NIP-51) は、自分宛てに NIP-44 で暗号化したタグの配列
+async fn private_members(signer: &AppSigner, event: &Event) -> Vec<PublicKey> {
+    if event.content.is_empty() {
+        return Vec::new();
+    }
+    let decrypted = match signer.nip44_decrypt(&event.pubkey, &event.content).await {
+        Ok(decrypted) => decrypted,
+        Err(e) => {
+            error!("Failed to decrypt the private items of the follow set: {e}");
+            return Vec::new();
+        }
+    };
+    let tags: Vec<Vec<String>> = serde_json::from_str(&decrypted).unwrap_or_default();
+    tags.iter()
+        .filter(|tag| tag.first().map(String::as_str) == Some("p"))
+        .filter_map(|tag| PublicKey::parse(tag.get(1)?).ok())
+        .collect()
+}
+
+// 自分のフォローセットを取得する。同じ d タグのものは新しい方を使う
+pub async fn fetch_follow_sets(client: &Client, signer: &AppSigner) -> Result<Vec<FollowSet>, Box<dyn std::error::Error + Send + Sync>> {
+    let filter = Filter::new().author(signer.public_key()).kind(Kind::FollowSet);
+    let events = network::fetch_events(client, filter).await?;
+    let mut latest: HashMap<String, Event> = HashMap::new();
+    for event in events {
+        let d_tag = event.tags.identifier().unwrap_or_default().to_string();
+        if latest.get(&d_tag).is_none_or(|current| current.created_at < event.created_at) {
+            latest.insert(d_tag, event);
+        }
+    }
+
+    let mut follow_sets = Vec::new();
+    for (d_tag, event) in latest {
+        let mut members: Vec<PublicKey> = event.tags.public_keys().copied().collect();
+        for member in private_members(signer, &event).await {
+            if !members.contains(&member) {
+                members.push(member);
+            }
+        }
+        if members.is_empty() {
+            continue;
+        }
+        let title = event
+            .tags
+            .find(TagKind::Title)
+            .and_then(|tag| tag.content())
+            .filter(|title| !title.is_empty())
+            .unwrap_or(&d_tag)
+            .to_string();
+        follow_sets.push(FollowSet { d_tag, title, members });
+    }
+    follow_sets.sort_by(|a, b| a.title.cmp(&b.title));
+    Ok(follow_sets)
+}
+
+pub fn spawn_fetch_follow_sets(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+) {
+    let (Some(client), Some(signer)) = (app_data.nostr_client.clone(), app_data.my_signer.clone()) else {
+        return;
+    };
+    app_data.is_loading_follow_sets = true;
+    runtime_handle.spawn(async move {
+        let result = fetch_follow_sets(&client, &signer).await;
+        let mut app_data = app_data_arc.lock().unwrap();
+        match result {
+            Ok(follow_sets) => {
+                // 選んでいたセットがなくなっていたら、最初のセットにする
+                if !app_data.selected_follow_set.as_ref().is_some_and(|d_tag| follow_sets.iter().any(|set| &set.d_tag == d_tag)) {
+                    app_data.selected_follow_set = follow_sets.first().map(|set| set.d_tag.clone());
+                }
+                app_data.follow_sets = follow_sets;
+            }
+            Err(e) => {
+                error!("Failed to fetch the follow sets: {e}");
+                app_data.report_error(tr!("フォローセットを取得できませんでした: {}", e));
+            }
+        }
+        app_data.is_loading_follow_sets = false;
+        app_data.should_repaint = true;
+    });
+}
+
+// ステータスを公開せず、フォローセットの人それぞれにギフトラップで送る。1人にでも送れたら true を返す
+pub async fn publish(
+    client: Client,
+    signer: AppSigner,
+    content: String,
+    mut tags: Vec<Tag>,
+    expiry: Option<Duration>,
+    follow_set: FollowSet,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+) -> bool {
+    // 期限はステータスとギフトラップの両方に付け、届いた側でも消えるようにする (NIP-40)
+    let expiration = expiry.map(|expiry| Timestamp::now() + expiry);
+    if let Some(expiration) = expiration {
+        tags.push(Tag::expiration(expiration));
+    }
+    let rumor = EventBuilder::new(Kind::from(30315), content).tags(tags).build(signer.public_key());
+
+    let recipients: Vec<PublicKey> = follow_set.members.iter().copied().filter(|member| *member != signer.public_key()).collect();
+    let mut sent = 0;
+    for recipient in &recipients {
+        match gift_wrap::send(&client, &signer, *recipient, rumor.clone(), expiration).await {
+            Ok(()) => sent += 1,
+            Err(e) => error!("Failed to send the private status to {recipient}: {e}"),
+        }
+    }
+
+    let mut app_data = app_data_arc.lock().unwrap();
+    if sent == 0 {
+        app_data.report_error(tr!("非公開のステータスを送れませんでした"));
+        return false;
+    }
+    if sent < recipients.len() {
+        app_data.report_error(tr!("非公開のステータスは {} 人中 {} 人にだけ送れました", recipients.len(), sent));
+    } else {
+        app_data.notify(tr!("「{}」の {} 人にステータスを送りました", follow_set.title, sent));
+    }
+    true
+}
+
+// ギフトラップから取り出したステータスをタイムラインの投稿にする。プロフィールは表示するときに引く
+pub fn timeline_post_from_rumor(mut rumor: UnsignedEvent) -> TimelinePost {
+    TimelinePost {
+        id: rumor.id(),
+        kind: rumor.kind,
+        author_pubkey: rumor.pubkey,
+        author_metadata: ProfileMetadata::default(),
+        content: rumor.content.clone(),
+        created_at: rumor.created_at,
+        emojis: note::emojis_from_tags(rumor.tags.iter()),
+        tags: rumor.tags.to_vec(),
+        is_private: true,
+    }
+}
+
+// 届いた非公開のステータスをタイムラインに入れる。同じ人の同じ d タグのものは新しい方だけ残す
+// フォローしていない人からのものは、公開のステータスと同じく表示しない
+pub fn push_private_status(app_data: &mut NostrStatusAppInternal, post: TimelinePost) -> bool {
+    if post.is_expired() || !app_data.followed_pubkeys.contains(&post.author_pubkey) {
+        return false;
+    }
+    replaceable::merge_timeline_posts(&mut app_data.timeline_posts, [post]) > 0
+}
@@ -0,0 +1,160 @@
+use image::{Rgba, RgbaImage, imageops};
+use nostr::PublicKey;
+use qrcode::{Color, QrCode};
+use resvg::{tiny_skia, usvg};
+use std::path::PathBuf;
+use tracing::error;
+
+use crate::APP_FONT;
+use crate::i18n::tr;
+use crate::ui::identicon::Identicon;
+
+// 友だちに送れるプロフィールカード (LINE の「QRコードで友だち追加」にあたる)
+// 名前と QR コードを SVG で描いて PNG にし、アイコンはその上に重ねる
+
+const CARD_WIDTH: u32 = 480;
+const CARD_HEIGHT: u32 = 680;
+const AVATAR_SIZE: u32 = 120;
+const AVATAR_TOP: u32 = 48;
+const QR_SIZE: u32 = 300;
+const QR_TOP: u32 = 260;
+// QR コードの周囲に確保する余白 (モジュール数)
+const QR_QUIET_ZONE: usize = 4;
+const HEADER_COLOR: &str = "#06C755";
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+// QR コードの黒いモジュールを SVG の path にする
+fn qr_path(data: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let code = QrCode::new(data.as_bytes())?;
+    let width = code.width();
+    let module_size = QR_SIZE as f32 / (width + QR_QUIET_ZONE * 2) as f32;
+    let left = (CARD_WIDTH - QR_SIZE) as f32 / 2.0;
+    let mut path = String::new();
+    for (i, color) in code.to_colors().iter().enumerate() {
+        if *color != Color::Dark {
+            continue;
+        }
+        let x = left + (i % width + QR_QUIET_ZONE) as f32 * module_size;
+        let y = QR_TOP as f32 + (i / width + QR_QUIET_ZONE) as f32 * module_size;
+        path.push_str(&format!("M{x:.2} {y:.2}h{module_size:.2}v{module_size:.2}h-{module_size:.2}z"));
+    }
+    Ok(path)
+}
+
+fn card_svg(name: &str, npub: &str, qr_data: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let center = CARD_WIDTH / 2;
+    let avatar_center_y = AVATAR_TOP + AVATAR_SIZE / 2;
+    let avatar_radius = AVATAR_SIZE / 2 + 4;
+    let name_y = AVATAR_TOP + AVATAR_SIZE + 48;
+    let npub_y = QR_TOP + QR_SIZE + 36;
+    let hint_y = CARD_HEIGHT - 36;
+    // npub は長いので前後だけ見せる
+    let short_npub = format!("{}…{}", &npub[..16.min(npub.len())], &npub[npub.len().saturating_sub(8)..]);
+    Ok(format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{CARD_WIDTH}" height="{CARD_HEIGHT}" viewBox="0 0 {CARD_WIDTH} {CARD_HEIGHT}">
+<rect width="{CARD_WIDTH}" height="{CARD_HEIGHT}" fill="#FFFFFF"/>
+<rect width="{CARD_WIDTH}" height="{avatar_center_y}" fill="{HEADER_COLOR}"/>
+<circle cx="{center}" cy="{avatar_center_y}" r="{avatar_radius}" fill="#FFFFFF"/>
+<text x="{center}" y="{name_y}" font-size="28" text-anchor="middle" fill="#111111">{name}</text>
+<path d="{qr}" fill="#000000"/>
+<text x="{center}" y="{npub_y}" font-size="14" text-anchor="middle" fill="#666666">{short_npub}</text>
+<text x="{center}" y="{hint_y}" font-size="14" text-anchor="middle" fill="#999999">Nostr のアプリで QR コードを読み取ると友だちに追加できます</text>
+</svg>"##,
+        name = escape_xml(name),
+        qr = qr_path(qr_data)?,
+        short_npub = escape_xml(&short_npub),
+    ))
+}
+
+// アイコンを丸く切り抜いて、カードの上の真ん中に重ねる
+fn overlay_avatar(card: &mut RgbaImage, avatar_bytes: &[u8]) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let avatar = image::load_from_memory(avatar_bytes)?;
+    let mut avatar = avatar.resize_to_fill(AVATAR_SIZE, AVATAR_SIZE, imageops::FilterType::Lanczos3).to_rgba8();
+    let radius = AVATAR_SIZE as f32 / 2.0;
+    for (x, y, pixel) in avatar.enumerate_pixels_mut() {
+        let dx = x as f32 + 0.5 - radius;
+        let dy = y as f32 + 0.5 - radius;
+        // 縁は少しぼかしてギザギザを目立たなくする
+        let coverage = (radius - (dx * dx + dy * dy).sqrt()).clamp(0.0, 1.0);
+        let Rgba([r, g, b, a]) = *pixel;
+        *pixel = Rgba([r, g, b, (a as f32 * coverage) as u8]);
+    }
+    let left = (CARD_WIDTH - AVATAR_SIZE) / 2;
+    imageops::overlay(card, &avatar, left as i64, AVATAR_TOP as i64);
+    Ok(())
+}
+
+// アイコンがないときは、アプリの中と同じ identicon を丸く描く
+fn overlay_identicon(card: &mut RgbaImage, pubkey: &PublicKey) {
+    let identicon = Identicon::new(pubkey);
+    let (cell, offset) = Identicon::layout(AVATAR_SIZE as f32);
+    let to_rgba = |color: eframe::egui::Color32| Rgba(color.to_array());
+    let radius = AVATAR_SIZE as f32 / 2.0;
+    let left = (CARD_WIDTH - AVATAR_SIZE) / 2;
+    for y in 0..AVATAR_SIZE {
+        for x in 0..AVATAR_SIZE {
+            let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+            if (px - radius).powi(2) + (py - radius).powi(2) > radius * radius {
+                continue;
+            }
+            let column = ((px - offset) / cell).floor();
+            let row = ((py - offset) / cell).floor();
+            let filled = column >= 0.0 && row >= 0.0 && identicon.cells.contains(&(column as usize, row as usize));
+            let color = if filled { identicon.foreground } else { identicon.background };
+            card.put_pixel(left + x, AVATAR_TOP + y, to_rgba(color));
+        }
+    }
+}
+
+// プロフィールカードを PNG にする。アイコンを読み込めなければ identicon を描く
+pub fn render_profile_card(
+    name: &str,
+    npub: &str,
+    avatar_bytes: Option<&[u8]>,
+) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+    let mut options = usvg::Options::default();
+    options.fontdb_mut().load_font_data(APP_FONT.to_vec());
+    if let Some(family) = options.fontdb.faces().next().and_then(|face| face.families.first()) {
+        options.font_family = family.0.clone();
+    }
+    let svg = card_svg(name, npub, &format!("nostr:{npub}"))?;
+    let tree = usvg::Tree::from_str(&svg, &options)?;
+    let mut pixmap = tiny_skia::Pixmap::new(CARD_WIDTH, CARD_HEIGHT).ok_or(tr!("カードの画像を作れませんでした"))?;
+    resvg::render(&tree, tiny_skia::Transform::default(), &mut pixmap.as_mut());
+
+    let mut card = RgbaImage::from_raw(CARD_WIDTH, CARD_HEIGHT, pixmap.take()).ok_or(tr!("カードの画像を作れませんでした"))?;
+    let avatar_drawn = match avatar_bytes.map(|bytes| overlay_avatar(&mut card, bytes)) {
+        Some(Ok(())) => true,
+        Some(Err(e)) => {
+            error!("Failed to draw the avatar on the profile card: {e}");
+            false
+        }
+        None => false,
+    };
+    if !avatar_drawn {
+        overlay_identicon(&mut card, &PublicKey::parse(npub)?);
+    }
+    let mut png = Vec::new();
+    card.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)?;
+    Ok(png)
+}
+
+// プロフィールカードをダウンロードフォルダに保存し、保存したファイルのパスを返す
+pub fn save_profile_card(
+    name: &str,
+    npub: &str,
+    avatar_bytes: Option<&[u8]>,
+) -> Result<PathBuf, Box<dyn std::error::Error + Send + Sync>> {
+    let png = render_profile_card(name, npub, avatar_bytes)?;
+    let dir = dirs::download_dir().or_else(dirs::home_dir).ok_or(tr!("保存先のフォルダが見つかりません"))?;
+    let path = dir.join(format!("N-profile-card-{}.png", &npub[..16.min(npub.len())]));
+    std::fs::write(&path, png)?;
+    Ok(path)
+}
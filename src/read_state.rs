@@ -0,0 +1,117 @@
+use nostr::{PublicKey, Timestamp};
+use tracing::error;
+
+use crate::cache_db::{DB_READ_STATE, LmdbCache};
+use crate::types::{NostrStatusAppInternal, ReadState};
+
+// 保存したものがなければ、今より前はすべて既読とする
+pub fn load_read_state(cache_db: &LmdbCache, pubkey_hex: &str) -> ReadState {
+    match cache_db.read_value::<ReadState>(DB_READ_STATE, pubkey_hex) {
+        Ok(read_state) => read_state.unwrap_or_else(|| ReadState::starting_at(Timestamp::now())),
+        Err(e) => {
+            error!("Failed to load the read state: {e}");
+            ReadState::starting_at(Timestamp::now())
+        }
+    }
+}
+
+fn save_read_state(app_data: &NostrStatusAppInternal) {
+    let Some(signer) = &app_data.my_signer else {
+        return;
+    };
+    let pubkey_hex = signer.public_key().to_string();
+    if let Err(e) = app_data.cache_db.write_value(DB_READ_STATE, &pubkey_hex, &app_data.read_state) {
+        error!("Failed to save the read state: {e}");
+    }
+}
+
+fn my_public_key(app_data: &NostrStatusAppInternal) -> Option<PublicKey> {
+    app_data.my_signer.as_ref().map(|signer| signer.public_key())
+}
+
+// 最後に会話を開いてから相手が送ってきたメッセージの数
+pub fn unread_dm_count(app_data: &NostrStatusAppInternal, peer: &PublicKey) -> usize {
+    let read_at = app_data
+        .read_state
+        .conversations
+        .get(peer)
+        .copied()
+        .unwrap_or(app_data.read_state.conversations_since);
+    app_data
+        .conversations
+        .get(peer)
+        .map_or(0, |messages| messages.iter().filter(|m| !m.is_mine && m.created_at > read_at).count())
+}
+
+pub fn total_unread_dm_count(app_data: &NostrStatusAppInternal) -> usize {
+    app_data.conversations.keys().map(|peer| unread_dm_count(app_data, peer)).sum()
+}
+
+// 最後にホームを開いてから届いた、他の人のステータス
+pub fn unread_home_count(app_data: &NostrStatusAppInternal) -> usize {
+    let me = my_public_key(app_data);
+    app_data
+        .timeline_posts
+        .iter()
+        .filter(|post| Some(post.author_pubkey) != me && post.created_at > app_data.read_state.home)
+        .count()
+}
+
+// 最後にノートを開いてから届いた、自分へのメンション
+pub fn unread_mention_count(app_data: &NostrStatusAppInternal) -> usize {
+    let Some(me) = my_public_key(app_data) else {
+        return 0;
+    };
+    app_data
+        .notes
+        .iter()
+        .filter(|note| {
+            note.pubkey != me && note.created_at > app_data.read_state.mentions && note.tags.public_keys().any(|pk| *pk == me)
+        })
+        .count()
+}
+
+// 既読にした位置が進んだときだけ保存する
+fn advance(read_at: &mut Timestamp, latest: Option<Timestamp>) -> bool {
+    match latest {
+        Some(latest) if latest > *read_at => {
+            *read_at = latest;
+            true
+        }
+        _ => false,
+    }
+}
+
+// 既読の位置が進んだら true を返す
+pub fn mark_conversation_read(app_data: &mut NostrStatusAppInternal, peer: PublicKey) -> bool {
+    let latest = app_data
+        .conversations
+        .get(&peer)
+        .and_then(|messages| messages.iter().filter(|m| !m.is_mine).map(|m| m.created_at).max());
+    let since = app_data.read_state.conversations_since;
+    let read_at = app_data.read_state.conversations.entry(peer).or_insert(since);
+    let advanced = advance(read_at, latest);
+    if advanced {
+        save_read_state(app_data);
+    }
+    advanced
+}
+
+pub fn mark_home_read(app_data: &mut NostrStatusAppInternal) {
+    let latest = app_data.timeline_posts.iter().map(|post| post.created_at).max();
+    if advance(&mut app_data.read_state.home, latest) {
+        save_read_state(app_data);
+    }
+}
+
+pub fn mark_mentions_read(app_data: &mut NostrStatusAppInternal) {
+    let latest = app_data.notes.iter().map(|note| note.created_at).max();
+    if advance(&mut app_data.read_state.mentions, latest) {
+        save_read_state(app_data);
+    }
+}
+
+// 未読があれば "名前 (件数)" にする
+pub fn with_badge(label: &str, unread: usize) -> String {
+    if unread > 0 { format!("{label} ({unread})") } else { label.to_string() }
+}
@@ -0,0 +1,161 @@
+use async_wsocket::{ConnectionMode, Message};
+use chrono::{DateTime, Local};
+use futures::{Sink, StreamExt};
+use nostr::Url;
+use nostr::util::BoxedFuture;
+use nostr_sdk::pool::transport::error::TransportError;
+use nostr_sdk::pool::transport::websocket::{BoxSink, BoxStream, DefaultWebsocketTransport, WebSocketTransport};
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+// リレーとの間で送受信した生のメッセージ (REQ / EVENT / EOSE / OK / NOTICE など) を残す開発者向けの記録
+// 記録するのは「リレー通信」タブで有効にしている間だけ
+
+// 残す件数。古いものから捨てる
+const MAX_PROTOCOL_MESSAGES: usize = 3000;
+// 1件に残す長さ。長いイベントは途中で切る
+const MAX_RAW_BYTES: usize = 4096;
+
+static RECORDING: AtomicBool = AtomicBool::new(false);
+static MESSAGES: Mutex<VecDeque<ProtocolMessage>> = Mutex::new(VecDeque::new());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Sent,
+    Received,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProtocolMessage {
+    pub time: DateTime<Local>,
+    pub relay: String,
+    pub direction: Direction,
+    // 配列の先頭の "REQ" や "EOSE"
+    pub message_type: String,
+    pub raw: String,
+    pub truncated: bool,
+}
+
+impl ProtocolMessage {
+    // クリップボードにコピーするときの1行の形式
+    pub fn to_line(&self) -> String {
+        let arrow = match self.direction {
+            Direction::Sent => "→",
+            Direction::Received => "←",
+        };
+        format!("{} {arrow} {} {}", self.time.format("%H:%M:%S%.3f"), self.relay, self.raw)
+    }
+}
+
+// JSON 全体を読まずに、先頭の要素の文字列だけを取り出す
+fn message_type(raw: &str) -> String {
+    raw.trim_start()
+        .strip_prefix('[')
+        .map(str::trim_start)
+        .and_then(|rest| rest.strip_prefix('"'))
+        .and_then(|rest| rest.split_once('"'))
+        .map(|(message_type, _)| message_type.to_string())
+        .unwrap_or_else(|| "?".to_string())
+}
+
+fn record(relay: &str, direction: Direction, raw: &str) {
+    if !is_recording() {
+        return;
+    }
+    let mut end = raw.len().min(MAX_RAW_BYTES);
+    while !raw.is_char_boundary(end) {
+        end -= 1;
+    }
+    let message = ProtocolMessage {
+        time: Local::now(),
+        relay: relay.to_string(),
+        direction,
+        message_type: message_type(raw),
+        raw: raw[..end].to_string(),
+        truncated: end < raw.len(),
+    };
+    let mut messages = MESSAGES.lock().unwrap();
+    if messages.len() >= MAX_PROTOCOL_MESSAGES {
+        messages.pop_front();
+    }
+    messages.push_back(message);
+}
+
+pub fn is_recording() -> bool {
+    RECORDING.load(Ordering::Relaxed)
+}
+
+pub fn set_recording(recording: bool) {
+    RECORDING.store(recording, Ordering::Relaxed);
+}
+
+// 古い順に返す
+pub fn messages() -> Vec<ProtocolMessage> {
+    MESSAGES.lock().unwrap().iter().cloned().collect()
+}
+
+pub fn clear() {
+    MESSAGES.lock().unwrap().clear();
+}
+
+// 送るメッセージを記録してから、そのまま渡す
+struct RecordingSink {
+    inner: BoxSink,
+    relay: String,
+}
+
+impl Sink<Message> for RecordingSink {
+    type Error = TransportError;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.inner).poll_ready(cx)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: Message) -> Result<(), Self::Error> {
+        if let Message::Text(text) = &item {
+            record(&self.relay, Direction::Sent, text);
+        }
+        Pin::new(&mut self.inner).start_send(item)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.inner).poll_close(cx)
+    }
+}
+
+// 通常の WebSocket の接続に、送受信の記録を挟む。network::new_client で使う
+#[derive(Debug)]
+pub struct RecordingTransport;
+
+impl WebSocketTransport for RecordingTransport {
+    fn support_ping(&self) -> bool {
+        DefaultWebsocketTransport.support_ping()
+    }
+
+    fn connect<'a>(
+        &'a self,
+        url: &'a Url,
+        mode: &'a ConnectionMode,
+        timeout: Duration,
+    ) -> BoxedFuture<'a, Result<(BoxSink, BoxStream), TransportError>> {
+        Box::pin(async move {
+            let (sink, stream) = DefaultWebsocketTransport.connect(url, mode, timeout).await?;
+            let relay = url.as_str().trim_end_matches('/').to_string();
+            let sink: BoxSink = Box::new(RecordingSink { inner: sink, relay: relay.clone() });
+            let stream: BoxStream = Box::new(stream.inspect(move |item| {
+                if let Ok(Message::Text(text)) = item {
+                    record(&relay, Direction::Received, text);
+                }
+            }));
+            Ok((sink, stream))
+        })
+    }
+}
@@ -0,0 +1,175 @@
+use eframe::egui;
+use nostr::{RelayMessage, RelayUrl, Timestamp};
+use nostr_sdk::{Client, RelayPoolNotification, RelayStatus};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{error, info};
+
+use crate::audit_log;
+use crate::types::{AppTab, NostrStatusAppInternal, RelayStatusRow};
+
+// リレー一覧の表を更新する間隔
+const REFRESH_INTERVAL_SECS: u64 = 3;
+
+// 切断されたリレーを確認する間隔と、再接続の待ち時間の範囲
+const SUPERVISOR_INTERVAL_SECS: u64 = 5;
+const INITIAL_BACKOFF_SECS: u64 = 5;
+const MAX_BACKOFF_SECS: u64 = 300;
+// 切断状態がこれ以上続いたら、SDKの自動再接続を待たずにつなぎ直す
+const STALE_DISCONNECT_SECS: u64 = 60;
+const RECONNECT_TIMEOUT_SECS: u64 = 10;
+
+// リレーごとの再接続の状態
+struct Backoff {
+    disconnected_since: Instant,
+    next_attempt: Instant,
+    delay: Duration,
+}
+
+// リレーごとに受信したメッセージの集計
+#[derive(Default)]
+struct RelayActivity {
+    last_message_at: Option<Timestamp>,
+    events_received: u64,
+}
+
+// 接続中のリレーの状態を定期的に集計し、リレータブの表に反映する
+pub async fn run_relay_monitor(
+    client: Client,
+    session: u64,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    ctx: egui::Context,
+) {
+    let mut notifications = client.notifications();
+    let mut activity: HashMap<RelayUrl, RelayActivity> = HashMap::new();
+    let mut interval = tokio::time::interval(Duration::from_secs(REFRESH_INTERVAL_SECS));
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {
+                let mut rows: Vec<RelayStatusRow> = client.relays().await.into_iter().map(|(url, relay)| {
+                    let stats = relay.stats();
+                    let entry = activity.get(&url);
+                    RelayStatusRow {
+                        url: url.to_string(),
+                        status: relay.status(),
+                        last_message_at: entry.and_then(|a| a.last_message_at),
+                        latency: stats.latency(),
+                        events_received: entry.map(|a| a.events_received).unwrap_or(0),
+                    }
+                }).collect();
+                rows.sort_by(|a, b| a.url.cmp(&b.url));
+
+                let mut app_data = app_data_arc.lock().unwrap();
+                // ロックやログアウト、アカウント切り替えの後は集計を終了する
+                if !app_data.is_current_session(session) {
+                    app_data.relay_status_rows.clear();
+                    break;
+                }
+                if app_data.relay_status_rows != rows {
+                    app_data.relay_status_rows = rows;
+                    if app_data.current_tab == AppTab::Relays {
+                        ctx.request_repaint();
+                    }
+                }
+            }
+            notification = notifications.recv() => {
+                let notification = match notification {
+                    Ok(notification) => notification,
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                };
+                if let RelayPoolNotification::Message { relay_url, message } = notification {
+                    audit_log::record_relay_message(&relay_url, &message);
+                    let entry = activity.entry(relay_url).or_default();
+                    entry.last_message_at = Some(Timestamp::now());
+                    if matches!(message, RelayMessage::Event { .. }) {
+                        entry.events_received += 1;
+                    }
+                }
+            }
+        }
+    }
+}
+
+// 利用者がロックやログアウト、アカウントの切り替えをしていないか確認する
+fn is_same_session(app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>, session: u64) -> bool {
+    app_data_arc.lock().unwrap().is_current_session(session)
+}
+
+// 再接続したリレーに、そのリレーで張っていた購読を張り直す
+async fn resubscribe(client: &Client, url: &RelayUrl) {
+    for (id, filters) in client.subscriptions().await {
+        let Some(filter) = filters.get(url) else {
+            continue;
+        };
+        if let Err(e) = client.subscribe_with_id_to([url.clone()], id.clone(), filter.clone(), None).await {
+            error!("Failed to resubscribe {id} on {url}: {e}");
+        }
+    }
+}
+
+// 切断されたリレーを監視し、指数バックオフで再接続を試みる
+pub async fn run_relay_supervisor(
+    client: Client,
+    session: u64,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+) {
+    let mut backoffs: HashMap<RelayUrl, Backoff> = HashMap::new();
+    let mut interval = tokio::time::interval(Duration::from_secs(SUPERVISOR_INTERVAL_SECS));
+
+    loop {
+        interval.tick().await;
+        if !is_same_session(&app_data_arc, session) {
+            break;
+        }
+
+        let relays = client.relays().await;
+        // 一覧から外されたリレーの状態は捨てる
+        backoffs.retain(|url, _| relays.contains_key(url));
+
+        for (url, relay) in relays {
+            let status = relay.status();
+            let now = Instant::now();
+            if !matches!(status, RelayStatus::Disconnected | RelayStatus::Terminated) {
+                if status == RelayStatus::Connected && backoffs.remove(&url).is_some() {
+                    info!("Relay {url} is back online.");
+                }
+                continue;
+            }
+
+            let backoff = backoffs.entry(url.clone()).or_insert_with(|| Backoff {
+                disconnected_since: now,
+                next_attempt: now,
+                delay: Duration::from_secs(INITIAL_BACKOFF_SECS),
+            });
+            // 切断直後はSDKの自動再接続に任せる
+            if status == RelayStatus::Disconnected
+                && now.duration_since(backoff.disconnected_since) < Duration::from_secs(STALE_DISCONNECT_SECS)
+            {
+                continue;
+            }
+            if now < backoff.next_attempt {
+                continue;
+            }
+
+            if status == RelayStatus::Disconnected {
+                relay.disconnect();
+            }
+            match client.try_connect_relay(url.clone(), Duration::from_secs(RECONNECT_TIMEOUT_SECS)).await {
+                Ok(()) => {
+                    info!("Reconnected to {url}.");
+                    resubscribe(&client, &url).await;
+                    backoffs.remove(&url);
+                }
+                Err(e) => {
+                    error!("Failed to reconnect to {url} (retrying in {}s): {e}", backoff.delay.as_secs());
+                    backoff.next_attempt = Instant::now() + backoff.delay;
+                    backoff.delay = (backoff.delay * 2).min(Duration::from_secs(MAX_BACKOFF_SECS));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use nostr::{Event, EventId, Timestamp};
+
+use crate::types::TimelinePost;
+
+// リレーによっては同じイベントや、すでに書き換えられた古い版を返してくる
+// 置き換え可能なイベント (NIP-01。ステータスの kind 30315 など) は、作者・kind・d タグごとに一番新しいものだけを残す
+
+// 作成時刻が同じなら id の小さい方を新しい版とみなす (NIP-01)
+fn is_newer(created_at: Timestamp, id: &EventId, other_created_at: Timestamp, other_id: &EventId) -> bool {
+    created_at > other_created_at || (created_at == other_created_at && id < other_id)
+}
+
+// 同じものの別の版か。置き換え可能でない kind では常に false
+fn is_same_address(a: &Event, b: &Event) -> bool {
+    (a.kind.is_replaceable() || a.kind.is_addressable())
+        && a.kind == b.kind
+        && a.pubkey == b.pubkey
+        && a.tags.identifier() == b.tags.identifier()
+}
+
+// events に同じイベントか、同じものの新しい版がすでにあるか
+pub fn is_superseded(events: &[Event], event: &Event) -> bool {
+    events.iter().any(|e| {
+        e.id == event.id || (is_same_address(e, event) && is_newer(e.created_at, &e.id, event.created_at, &event.id))
+    })
+}
+
+// event に置き換えられる古い版を events から取り除く
+pub fn remove_older_versions(events: &mut Vec<Event>, event: &Event) {
+    events.retain(|e| !(is_same_address(e, event) && is_newer(event.created_at, &event.id, e.created_at, &e.id)));
+}
+
+// 重なったイベントと古い版を取り除く。新しい版は古い版があった位置に入れるので、並び順はそのまま
+pub fn dedupe_events(events: impl IntoIterator<Item = Event>) -> Vec<Event> {
+    let mut kept: Vec<Event> = Vec::new();
+    for event in events {
+        if is_superseded(&kept, &event) {
+            continue;
+        }
+        match kept.iter().position(|e| is_same_address(e, &event)) {
+            Some(index) => kept[index] = event,
+            None => kept.push(event),
+        }
+    }
+    kept
+}
+
+// 同じ人の同じ種類のステータスか。フォローセット限定のものは公開のものとは別に扱う
+fn is_same_status(a: &TimelinePost, b: &TimelinePost) -> bool {
+    a.kind.is_addressable()
+        && a.kind == b.kind
+        && a.author_pubkey == b.author_pubkey
+        && a.is_private == b.is_private
+        && a.d_tag() == b.d_tag()
+}
+
+// タイムラインに posts を加えて新しい順に並べ直し、加えた件数を返す
+// すでにあるものや古い版は加えず、加えたもので置き換えられる古い版は取り除く
+pub fn merge_timeline_posts(timeline: &mut Vec<TimelinePost>, posts: impl IntoIterator<Item = TimelinePost>) -> usize {
+    let mut added = 0;
+    for post in posts {
+        let superseded = timeline.iter().any(|p| {
+            p.id == post.id || (is_same_status(p, &post) && is_newer(p.created_at, &p.id, post.created_at, &post.id))
+        });
+        if superseded {
+            continue;
+        }
+        timeline.retain(|p| !is_same_status(p, &post));
+        timeline.push(post);
+        added += 1;
+    }
+    if added > 0 {
+        timeline.sort_by_key(|p| std::cmp::Reverse(p.created_at));
+    }
+    added
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nostr::{EventBuilder, Keys, Kind, Tag};
+
+    fn status(keys: &Keys, d: &str, content: &str, created_at: u64) -> Event {
+        EventBuilder::new(Kind::Custom(30315), content)
+            .tag(Tag::identifier(d))
+            .custom_created_at(Timestamp::from(created_at))
+            .sign_with_keys(keys)
+            .unwrap()
+    }
+
+    fn note(keys: &Keys, content: &str, created_at: u64) -> Event {
+        EventBuilder::text_note(content).custom_created_at(Timestamp::from(created_at)).sign_with_keys(keys).unwrap()
+    }
+
+    fn post(event: &Event, is_private: bool) -> TimelinePost {
+        TimelinePost {
+            id: event.id,
+            kind: event.kind,
+            author_pubkey: event.pubkey,
+            author_metadata: Default::default(),
+            content: event.content.clone(),
+            created_at: event.created_at,
+            emojis: Default::default(),
+            tags: event.tags.iter().cloned().collect(),
+            is_private,
+        }
+    }
+
+    #[test]
+    fn dedupe_keeps_the_newest_version_in_place() {
+        let keys = Keys::generate();
+        let old = status(&keys, "general", "old", 100);
+        let music = status(&keys, "music", "song", 150);
+        let new = status(&keys, "general", "new", 200);
+        let events = dedupe_events([old.clone(), music.clone(), new.clone(), old.clone()]);
+        // 新しい版は古い版があった位置に入る
+        assert_eq!(events.iter().map(|e| e.id).collect::<Vec<_>>(), vec![new.id, music.id]);
+    }
+
+    #[test]
+    fn dedupe_separates_authors_and_keeps_regular_events() {
+        let (alice, bob) = (Keys::generate(), Keys::generate());
+        let alice_status = status(&alice, "general", "alice", 100);
+        let bob_status = status(&bob, "general", "bob", 200);
+        let first = note(&alice, "first", 100);
+        let second = note(&alice, "second", 200);
+        let events = dedupe_events([alice_status.clone(), bob_status.clone(), first.clone(), second.clone(), first.clone()]);
+        assert_eq!(events.iter().map(|e| e.id).collect::<Vec<_>>(), vec![alice_status.id, bob_status.id, first.id, second.id]);
+    }
+
+    #[test]
+    fn same_timestamp_prefers_the_smaller_id() {
+        let keys = Keys::generate();
+        let a = status(&keys, "general", "a", 100);
+        let b = status(&keys, "general", "b", 100);
+        let newer = if a.id < b.id { &a } else { &b };
+        assert_eq!(dedupe_events([a.clone(), b.clone()])[0].id, newer.id);
+        assert_eq!(dedupe_events([b.clone(), a.clone()])[0].id, newer.id);
+    }
+
+    #[test]
+    fn remove_older_versions_keeps_newer_ones() {
+        let keys = Keys::generate();
+        let old = status(&keys, "general", "old", 100);
+        let new = status(&keys, "general", "new", 200);
+        let mut events = vec![old.clone(), note(&keys, "note", 150)];
+        remove_older_versions(&mut events, &new);
+        assert_eq!(events.len(), 1);
+        assert!(!is_superseded(&events, &new));
+
+        let mut events = vec![new.clone()];
+        remove_older_versions(&mut events, &old);
+        assert_eq!(events.len(), 1);
+        assert!(is_superseded(&events, &old));
+    }
+
+    #[test]
+    fn merge_replaces_statuses_and_sorts_newest_first() {
+        let keys = Keys::generate();
+        let old = status(&keys, "general", "old", 100);
+        let text = note(&keys, "note", 150);
+        let new = status(&keys, "general", "new", 200);
+        let mut timeline = vec![post(&old, false)];
+
+        assert_eq!(merge_timeline_posts(&mut timeline, [post(&text, false), post(&new, false)]), 2);
+        assert_eq!(timeline.iter().map(|p| p.id).collect::<Vec<_>>(), vec![new.id, text.id]);
+        // すでにあるものや古い版は加えない
+        assert_eq!(merge_timeline_posts(&mut timeline, [post(&old, false), post(&text, false)]), 0);
+        assert_eq!(timeline.len(), 2);
+    }
+
+    #[test]
+    fn merge_keeps_private_statuses_apart() {
+        let keys = Keys::generate();
+        let public = status(&keys, "general", "public", 200);
+        let private = status(&keys, "general", "private", 100);
+        let mut timeline = vec![post(&public, false)];
+        assert_eq!(merge_timeline_posts(&mut timeline, [post(&private, true)]), 1);
+        assert_eq!(timeline.iter().map(|p| p.id).collect::<Vec<_>>(), vec![public.id, private.id]);
+    }
+}
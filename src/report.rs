@@ -0,0 +1,47 @@
+use nostr::nips::nip56::Report;
+use nostr::{EventBuilder, Tag, TagStandard};
+use nostr_sdk::Client;
+use std::sync::{Arc, Mutex};
+use tracing::{error, info};
+
+use crate::i18n::tr;
+use crate::outbox;
+use crate::signer::AppSigner;
+use crate::types::{NostrStatusAppInternal, ReportTarget};
+
+// ユーザーか投稿を通報する (NIP-56)。送れなかった場合も送信キューに残るので、署名できたら true を返す
+pub async fn publish_report(
+    client: Client,
+    signer: AppSigner,
+    target: ReportTarget,
+    reason: Report,
+    comment: String,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+) -> bool {
+    // 投稿の通報は e タグに理由を付け、作者は p タグで示す
+    let tags = match target.event_id {
+        Some(event_id) => vec![
+            Tag::from_standardized(TagStandard::EventReport(event_id, reason)),
+            Tag::public_key(target.public_key),
+        ],
+        None => vec![Tag::from_standardized(TagStandard::PublicKeyReport(target.public_key, reason))],
+    };
+    let event = match EventBuilder::report(tags, comment.trim()).sign(&signer).await {
+        Ok(event) => event,
+        Err(e) => {
+            error!("Failed to sign the report: {e}");
+            app_data_arc.lock().unwrap().report_error(tr!("通報に署名できませんでした: {}", e));
+            return false;
+        }
+    };
+
+    let sent = outbox::send_or_queue(&client, event.clone(), &app_data_arc).await;
+    let mut app_data = app_data_arc.lock().unwrap();
+    if sent {
+        info!("Report published with event id: {:?}", event.id);
+        app_data.notify(tr!("通報しました"));
+    } else {
+        app_data.report_error(tr!("通報を送信できませんでした。送信キューに残し、接続が戻ったら再送します"));
+    }
+    true
+}
@@ -0,0 +1,65 @@
+use eframe::egui;
+use nostr::Timestamp;
+use nostr_sdk::Client;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::info;
+
+use crate::signer::AppSigner;
+use crate::status_history;
+use crate::types::{NostrStatusAppInternal, ScheduledAction, ScheduledStatus};
+
+// 予約の時刻を確認する間隔
+const CHECK_INTERVAL_SECS: u64 = 5;
+
+// 公開・消去を予約する
+pub fn schedule(app_data: &mut NostrStatusAppInternal, run_at: Timestamp, d_tag: String, action: ScheduledAction) {
+    let id = app_data.next_scheduled_id;
+    app_data.next_scheduled_id += 1;
+    app_data.scheduled_statuses.push(ScheduledStatus { id, run_at, d_tag, action });
+    app_data.scheduled_statuses.sort_by_key(|item| item.run_at);
+}
+
+pub fn cancel(app_data: &mut NostrStatusAppInternal, id: u64) {
+    app_data.scheduled_statuses.retain(|item| item.id != id);
+}
+
+// 時刻の来た予約を順に実行する。アプリを起動している間だけ動く
+pub async fn run_scheduler(
+    client: Client,
+    keys: AppSigner,
+    session: u64,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    ctx: egui::Context,
+) {
+    let mut interval = tokio::time::interval(Duration::from_secs(CHECK_INTERVAL_SECS));
+    loop {
+        interval.tick().await;
+        let due: Vec<ScheduledStatus> = {
+            let mut app_data = app_data_arc.lock().unwrap();
+            // ロックやログアウトの後は終了する
+            if !app_data.is_current_session(session) {
+                break;
+            }
+            let now = Timestamp::now();
+            let (due, pending) = std::mem::take(&mut app_data.scheduled_statuses)
+                .into_iter()
+                .partition(|item| item.run_at <= now);
+            app_data.scheduled_statuses = pending;
+            due
+        };
+
+        for item in due {
+            info!("Running scheduled status {} for d-tag {}", item.id, item.d_tag);
+            match item.action {
+                ScheduledAction::Publish { content, tags, expiry } => {
+                    status_history::publish(client.clone(), keys.clone(), content, tags, expiry, app_data_arc.clone()).await;
+                }
+                ScheduledAction::Clear => {
+                    status_history::clear_status(client.clone(), keys.clone(), item.d_tag, app_data_arc.clone()).await;
+                }
+            }
+            ctx.request_repaint();
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use nostr::{Event, Filter, Kind, PublicKey};
+use std::collections::HashMap;
+
+use crate::network::new_client;
+use crate::nostr_client::fetch_profiles_for_pubkeys;
+use crate::replaceable;
+use crate::signer::AppSigner;
+use crate::types::{NostrStatusAppInternal, ProfileMetadata, SearchHit, SearchResults, Settings};
+
+// 1回の検索で受け取る件数
+const SEARCH_LIMIT: usize = 50;
+
+fn hit_from_event(event: &Event) -> SearchHit {
+    SearchHit {
+        id: event.id,
+        kind: event.kind,
+        author: event.pubkey,
+        content: event.content.clone(),
+        created_at: event.created_at,
+        tags: event.tags.clone().to_vec(),
+    }
+}
+
+// 検索用リレーに NIP-50 の search フィルターを送り、プロフィール・ノート・ステータスを探す
+pub async fn search_relays(
+    keys: AppSigner,
+    settings: Settings,
+    query: String,
+) -> Result<(SearchResults, HashMap<PublicKey, ProfileMetadata>), Box<dyn std::error::Error + Send + Sync>> {
+    let search_client = new_client(keys);
+    for relay_url in settings.search_relays.lines().filter(|url| !url.trim().is_empty()) {
+        search_client.add_relay(relay_url.trim()).await?;
+    }
+    search_client.connect().await;
+
+    let filter = Filter::new()
+        .search(query.clone())
+        .kinds([Kind::Metadata, Kind::TextNote, Kind::from(30315)])
+        .limit(SEARCH_LIMIT);
+    let result = search_client.fetch_events(filter, settings.relay_timeout()).await;
+    let events = match result {
+        Ok(events) => events,
+        Err(e) => {
+            search_client.shutdown().await;
+            return Err(e.into());
+        }
+    };
+
+    let mut profiles: HashMap<PublicKey, ProfileMetadata> = HashMap::new();
+    let mut results = SearchResults { query, from_relays: true, ..Default::default() };
+    // 検索リレーが同じプロフィールやステータスの古い版を返すことがあるので、最新のものだけを使う
+    for event in replaceable::dedupe_events(events).iter() {
+        if event.kind == Kind::Metadata {
+            if let Ok(metadata) = serde_json::from_str::<ProfileMetadata>(&event.content) {
+                profiles.insert(event.pubkey, metadata);
+                results.profiles.push(event.pubkey);
+            }
+        } else if !event.content.is_empty() && !event.is_expired() {
+            results.posts.push(hit_from_event(event));
+        }
+    }
+    results.posts.sort_by_key(|hit| std::cmp::Reverse(hit.created_at));
+
+    // ヒットした投稿の作者の名前も表示できるようにする
+    let authors: Vec<PublicKey> = results
+        .posts
+        .iter()
+        .map(|hit| hit.author)
+        .filter(|author| !profiles.contains_key(author))
+        .collect();
+    let author_profiles = fetch_profiles_for_pubkeys(&search_client, authors).await;
+    search_client.shutdown().await;
+    profiles.extend(author_profiles?);
+    Ok((results, profiles))
+}
+
+// 検索用リレーがないときは、手元にあるプロフィール・ノート・タイムラインから部分一致で探す
+pub fn search_local(app_data: &NostrStatusAppInternal, query: &str) -> SearchResults {
+    let needle = query.to_lowercase();
+    let matches = |text: &str| text.to_lowercase().contains(&needle);
+
+    let mut results = SearchResults { query: query.to_string(), from_relays: false, ..Default::default() };
+    for (public_key, metadata) in &app_data.profiles {
+        if matches(&metadata.name) || matches(&metadata.display_name) || matches(&metadata.nip05) || matches(&metadata.about) {
+            results.profiles.push(*public_key);
+        }
+    }
+    for event in &app_data.notes {
+        if event.kind == Kind::TextNote && matches(&event.content) {
+            results.posts.push(hit_from_event(event));
+        }
+    }
+    for post in &app_data.timeline_posts {
+        if matches(&post.content) && !results.posts.iter().any(|hit| hit.id == post.id) {
+            results.posts.push(SearchHit {
+                id: post.id,
+                kind: post.kind,
+                author: post.author_pubkey,
+                content: post.content.clone(),
+                created_at: post.created_at,
+                tags: post.tags.clone(),
+            });
+        }
+    }
+    results.posts.sort_by_key(|hit| std::cmp::Reverse(hit.created_at));
+    results.posts.truncate(SEARCH_LIMIT);
+    results
+}
@@ -0,0 +1,30 @@
+use std::fs;
+use tracing::error;
+
+use crate::accounts::write_file_atomically;
+use crate::settings_file_path;
+use crate::types::Settings;
+
+// 設定ファイルを読み込む。存在しない・壊れている場合は初期値を使う
+pub fn load_settings() -> Settings {
+    let path = settings_file_path();
+    if !path.exists() {
+        return Settings::default();
+    }
+    match fs::read_to_string(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|settings_str| serde_json::from_str(&settings_str).map_err(|e| e.to_string()))
+    {
+        Ok(settings) => settings,
+        Err(e) => {
+            error!("Failed to load settings, using defaults: {e}");
+            Settings::default()
+        }
+    }
+}
+
+pub fn save_settings(settings: &Settings) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let settings_json = serde_json::to_string_pretty(settings)?;
+    write_file_atomically(&settings_file_path(), settings_json.as_bytes())?;
+    Ok(())
+}
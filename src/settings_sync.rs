@@ -0,0 +1,167 @@
+use eframe::egui;
+use nostr::Timestamp;
+use nostr_sdk::Client;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use tracing::{error, info};
+
+use crate::i18n::{self, tr};
+use crate::nip78;
+use crate::settings::save_settings;
+use crate::signer::AppSigner;
+use crate::theme::{apply_theme_preference, apply_theme_preset};
+use crate::types::{Language, NostrStatusAppInternal, Settings, ThemePreference, ThemePreset};
+
+// 表示の設定を NIP-78 のアプリデータに暗号化して保存し、ほかの端末と同じにする
+// リレーや通信の設定は端末ごとに違ってよいので共有しない
+const SETTINGS_KEY: &str = "settings";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncedSettings {
+    pub theme: ThemePreference,
+    #[serde(default)]
+    pub theme_preset: ThemePreset,
+    pub language: Language,
+    #[serde(default)]
+    pub status_presets: Vec<String>,
+    #[serde(default)]
+    pub muted_words: String,
+}
+
+impl SyncedSettings {
+    pub fn from_settings(settings: &Settings) -> Self {
+        Self {
+            theme: settings.theme,
+            theme_preset: settings.theme_preset,
+            language: settings.language,
+            status_presets: settings.status_presets.clone(),
+            muted_words: settings.muted_words.clone(),
+        }
+    }
+
+    fn apply_to(&self, settings: &mut Settings) {
+        settings.theme = self.theme;
+        settings.theme_preset = self.theme_preset;
+        settings.language = self.language;
+        settings.status_presets = self.status_presets.clone();
+        settings.muted_words = self.muted_words.clone();
+    }
+}
+
+// 両方で変わっていたときは、新しい方の値を使い、一覧は新しい方の順番で両方の項目を残す
+fn merge(newer: &SyncedSettings, older: &SyncedSettings) -> SyncedSettings {
+    let mut status_presets = newer.status_presets.clone();
+    for preset in &older.status_presets {
+        if !status_presets.contains(preset) {
+            status_presets.push(preset.clone());
+        }
+    }
+    let mut muted_words: Vec<&str> = newer.muted_words.lines().collect();
+    for word in older.muted_words.lines() {
+        if !muted_words.contains(&word) {
+            muted_words.push(word);
+        }
+    }
+    SyncedSettings {
+        theme: newer.theme,
+        theme_preset: newer.theme_preset,
+        language: newer.language,
+        status_presets,
+        muted_words: muted_words.join("\n"),
+    }
+}
+
+// 合わせた結果を設定に入れて保存し、テーマや言語が変わっていれば切り替える
+fn apply_synced(app_data: &mut NostrStatusAppInternal, ctx: &egui::Context, synced: SyncedSettings, synced_at: Timestamp) {
+    synced.apply_to(&mut app_data.settings);
+    app_data.settings.synced_settings_updated_at = synced_at.as_u64();
+    app_data.settings.synced_settings_synced_at = synced_at.as_u64();
+    if let Err(e) = save_settings(&app_data.settings) {
+        error!("Failed to save the synced settings: {e}");
+    }
+    apply_theme_preference(ctx, app_data.settings.theme);
+    apply_theme_preset(ctx, app_data.settings.theme_preset);
+    i18n::set_language(app_data.settings.language);
+    app_data.synced_settings = Some(synced);
+    app_data.should_repaint = true;
+}
+
+// ログインしたときにリレーの設定と手元の設定を合わせる
+// 前回合わせてから片方だけが変わっていればそちらを使い、両方変わっていれば merge する
+pub async fn sync_on_login(client: Client, keys: AppSigner, app_data_arc: Arc<Mutex<NostrStatusAppInternal>>, ctx: egui::Context) {
+    let remote = match nip78::fetch_app_data::<SyncedSettings>(&client, &keys, SETTINGS_KEY).await {
+        Ok(remote) => remote,
+        Err(e) => {
+            error!("Failed to fetch the synced settings: {e}");
+            return;
+        }
+    };
+    let (local, updated_at, synced_at) = {
+        let app_data = app_data_arc.lock().unwrap();
+        let settings = &app_data.settings;
+        (SyncedSettings::from_settings(settings), settings.synced_settings_updated_at, settings.synced_settings_synced_at)
+    };
+    let local_changed = updated_at > synced_at;
+
+    let to_publish = match remote {
+        Some((remote, remote_at)) if remote_at.as_u64() > synced_at => {
+            if !local_changed {
+                info!("Applying the settings synced from another device");
+                apply_synced(&mut app_data_arc.lock().unwrap(), &ctx, remote, remote_at);
+                return;
+            }
+            if remote_at.as_u64() > updated_at { merge(&remote, &local) } else { merge(&local, &remote) }
+        }
+        Some((remote, remote_at)) if !local_changed => {
+            let mut app_data = app_data_arc.lock().unwrap();
+            app_data.synced_settings = Some(remote);
+            app_data.settings.synced_settings_synced_at = remote_at.as_u64();
+            return;
+        }
+        _ => local,
+    };
+
+    match nip78::publish_app_data(&client, &keys, SETTINGS_KEY, &to_publish).await {
+        Ok(published_at) => apply_synced(&mut app_data_arc.lock().unwrap(), &ctx, to_publish, published_at),
+        Err(e) => error!("Failed to publish the synced settings: {e}"),
+    }
+}
+
+// 設定を保存したときに呼ぶ。共有する設定が変わっていればリレーにも保存する
+// ログイン直後でまだ合わせていないときは、変えた時刻だけ残して sync_on_login に任せる
+pub fn sync_if_changed(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+) {
+    let current = SyncedSettings::from_settings(&app_data.settings);
+    if app_data.synced_settings.as_ref() == Some(&current) {
+        return;
+    }
+    app_data.settings.synced_settings_updated_at = Timestamp::now().as_u64();
+    if let Err(e) = save_settings(&app_data.settings) {
+        error!("Failed to save settings: {e}");
+    }
+    if app_data.synced_settings.is_none() {
+        return;
+    }
+    let (Some(client), Some(keys)) = (app_data.nostr_client.clone(), app_data.my_signer.clone()) else {
+        return;
+    };
+    app_data.synced_settings = Some(current.clone());
+    runtime_handle.spawn(async move {
+        match nip78::publish_app_data(&client, &keys, SETTINGS_KEY, &current).await {
+            Ok(published_at) => {
+                let mut app_data = app_data_arc.lock().unwrap();
+                app_data.settings.synced_settings_synced_at = published_at.as_u64();
+                if let Err(e) = save_settings(&app_data.settings) {
+                    error!("Failed to save settings: {e}");
+                }
+            }
+            Err(e) => {
+                error!("Failed to publish the synced settings: {e}");
+                app_data_arc.lock().unwrap().report_error(tr!("設定をほかの端末と共有できませんでした: {}", e));
+            }
+        }
+    });
+}
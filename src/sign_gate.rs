@@ -0,0 +1,111 @@
+use eframe::egui;
+use nostr::{Kind, SignerError, UnsignedEvent};
+use std::collections::{BTreeSet, VecDeque};
+use std::sync::{Mutex, OnceLock, RwLock};
+use tokio::sync::oneshot;
+
+use crate::i18n::tr;
+use crate::types::Settings;
+
+// 署名する前に内容を確認する。許可した kind 以外のイベントは、ダイアログで承認されるまで署名を待たせる
+// 署名はすべて AppSigner を通るので、あとから投稿の機能を足しても確認を通らずに署名されることはない
+
+#[derive(Debug, Clone)]
+struct SignRules {
+    enabled: bool,
+    auto_approve_kinds: BTreeSet<u16>,
+}
+
+static SIGN_RULES: RwLock<SignRules> = RwLock::new(SignRules { enabled: false, auto_approve_kinds: BTreeSet::new() });
+
+struct PendingSignature {
+    unsigned: UnsignedEvent,
+    responder: oneshot::Sender<bool>,
+}
+
+static PENDING: Mutex<VecDeque<PendingSignature>> = Mutex::new(VecDeque::new());
+
+// 確認を待つイベントが来たときに画面を描き直すため
+static REPAINT_CONTEXT: OnceLock<egui::Context> = OnceLock::new();
+
+// 設定の確認の有無と許可した kind を、これからの署名に適用する
+pub fn apply_sign_settings(settings: &Settings) {
+    *SIGN_RULES.write().unwrap() = SignRules {
+        enabled: settings.confirm_before_signing,
+        auto_approve_kinds: settings.auto_approve_kinds.clone(),
+    };
+}
+
+pub fn set_repaint_context(ctx: &egui::Context) {
+    REPAINT_CONTEXT.get_or_init(|| ctx.clone());
+}
+
+fn needs_confirmation(kind: Kind) -> bool {
+    let rules = SIGN_RULES.read().unwrap();
+    rules.enabled && !rules.auto_approve_kinds.contains(&kind.as_u16())
+}
+
+// 署名してよいか確かめる。確認が必要なら、ダイアログで答えるまで待つ
+pub async fn approve(unsigned: &UnsignedEvent) -> Result<(), SignerError> {
+    if !needs_confirmation(unsigned.kind) {
+        return Ok(());
+    }
+    let (responder, response) = oneshot::channel();
+    PENDING.lock().unwrap().push_back(PendingSignature { unsigned: unsigned.clone(), responder });
+    if let Some(ctx) = REPAINT_CONTEXT.get() {
+        ctx.request_repaint();
+    }
+    match response.await {
+        Ok(true) => Ok(()),
+        _ => Err(SignerError::from("The signature request was rejected")),
+    }
+}
+
+// 確認を待っている先頭のイベントと、待っている件数
+pub fn front() -> Option<(UnsignedEvent, usize)> {
+    let pending = PENDING.lock().unwrap();
+    pending.front().map(|request| (request.unsigned.clone(), pending.len()))
+}
+
+// 先頭のイベントに答える
+pub fn respond(approved: bool) {
+    if let Some(request) = PENDING.lock().unwrap().pop_front() {
+        // 待っていたタスクが先に終わっていれば送れないが、それでよい
+        let _ = request.responder.send(approved);
+    }
+}
+
+// 待っているものをすべて断る。ログアウトやロックのときに呼ぶ
+pub fn reject_all() {
+    for request in PENDING.lock().unwrap().drain(..) {
+        let _ = request.responder.send(false);
+    }
+}
+
+// ダイアログと設定画面に出す kind の名前
+pub fn kind_label(kind: Kind) -> String {
+    let name = match kind.as_u16() {
+        0 => tr!("プロフィール"),
+        1 => tr!("ノート"),
+        3 => tr!("フォローリスト"),
+        5 => tr!("削除"),
+        6 => tr!("リポスト"),
+        7 => tr!("リアクション"),
+        13 => tr!("トークの封筒 (シール)"),
+        15 => tr!("ファイルのメッセージ"),
+        16 => tr!("リポスト"),
+        1984 => tr!("通報"),
+        9734 => tr!("ZAP のリクエスト"),
+        10000 => tr!("ミュートリスト"),
+        10002 => tr!("リレーリスト"),
+        10003 => tr!("ブックマーク"),
+        10050 => tr!("トークのリレー"),
+        22242 => tr!("リレーへのログイン"),
+        24133 => tr!("リモート署名者への接続"),
+        30000 => tr!("フォローセット"),
+        30078 => tr!("アプリのデータ"),
+        30315 => tr!("ステータス"),
+        _ => return format!("kind {}", kind.as_u16()),
+    };
+    format!("{name} (kind {})", kind.as_u16())
+}
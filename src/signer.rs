@@ -0,0 +1,134 @@
+use nostr::signer::SignerBackend;
+use nostr::util::BoxedFuture;
+use nostr::{Event, Keys, NostrSigner, PublicKey, SignerError, UnsignedEvent};
+use nostr_connect::client::NostrConnect;
+use std::sync::{Arc, RwLock};
+
+use crate::{audit_log, sign_gate};
+
+// ログイン中のユーザーの署名者。秘密鍵を直接持つ場合とNIP-46のリモート署名者(bunker)の場合がある
+// 公開鍵はログイン時に一度だけ取得して保持し、UIからは同期的に参照できるようにする
+#[derive(Debug, Clone)]
+pub struct AppSigner {
+    public_key: PublicKey,
+    // 秘密鍵はすべての複製で共有し、wipe で一度に消せるようにする。リモート署名者なら None
+    keys: Arc<RwLock<Option<Keys>>>,
+    remote: Option<NostrConnect>,
+}
+
+impl AppSigner {
+    pub fn from_keys(keys: Keys) -> Self {
+        Self {
+            public_key: keys.public_key(),
+            keys: Arc::new(RwLock::new(Some(keys))),
+            remote: None,
+        }
+    }
+
+    // リモート署名者に接続してユーザーの公開鍵を取得する
+    pub async fn from_remote(remote: NostrConnect) -> Result<Self, SignerError> {
+        let public_key = remote.get_public_key().await?;
+        Ok(Self {
+            public_key,
+            keys: Arc::new(RwLock::new(None)),
+            remote: Some(remote),
+        })
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        self.public_key
+    }
+
+    pub fn remote(&self) -> Option<&NostrConnect> {
+        self.remote.as_ref()
+    }
+
+    // 秘密鍵をメモリから消す。SecretKey は drop するときに中身を上書きする
+    // 複製を持ったまま動いているタスクがあっても、それ以降は署名できなくなる
+    pub fn wipe(&self) {
+        drop(self.keys.write().unwrap().take());
+    }
+
+    // 処理の間だけ使う秘密鍵の複製。ロックを await の間持たないよう複製して返す
+    fn local_keys(&self) -> Result<Keys, SignerError> {
+        self.keys.read().unwrap().clone().ok_or_else(|| SignerError::from("The secret key has been wiped"))
+    }
+}
+
+impl NostrSigner for AppSigner {
+    fn backend(&self) -> SignerBackend<'_> {
+        match &self.remote {
+            Some(remote) => remote.backend(),
+            None => SignerBackend::Keys,
+        }
+    }
+
+    fn get_public_key(&self) -> BoxedFuture<'_, Result<PublicKey, SignerError>> {
+        Box::pin(async move { Ok(self.public_key) })
+    }
+
+    fn sign_event(&self, unsigned: UnsignedEvent) -> BoxedFuture<'_, Result<Event, SignerError>> {
+        Box::pin(async move {
+            // 許可していない kind なら、ダイアログで承認されるまで待つ
+            sign_gate::approve(&unsigned).await?;
+            let event = match &self.remote {
+                Some(remote) => remote.sign_event(unsigned).await?,
+                None => self.local_keys()?.sign_event(unsigned).await?,
+            };
+            audit_log::record_signed(&event);
+            Ok(event)
+        })
+    }
+
+    fn nip04_encrypt<'a>(
+        &'a self,
+        public_key: &'a PublicKey,
+        content: &'a str,
+    ) -> BoxedFuture<'a, Result<String, SignerError>> {
+        Box::pin(async move {
+            match &self.remote {
+                Some(remote) => remote.nip04_encrypt(public_key, content).await,
+                None => self.local_keys()?.nip04_encrypt(public_key, content).await,
+            }
+        })
+    }
+
+    fn nip04_decrypt<'a>(
+        &'a self,
+        public_key: &'a PublicKey,
+        encrypted_content: &'a str,
+    ) -> BoxedFuture<'a, Result<String, SignerError>> {
+        Box::pin(async move {
+            match &self.remote {
+                Some(remote) => remote.nip04_decrypt(public_key, encrypted_content).await,
+                None => self.local_keys()?.nip04_decrypt(public_key, encrypted_content).await,
+            }
+        })
+    }
+
+    fn nip44_encrypt<'a>(
+        &'a self,
+        public_key: &'a PublicKey,
+        content: &'a str,
+    ) -> BoxedFuture<'a, Result<String, SignerError>> {
+        Box::pin(async move {
+            match &self.remote {
+                Some(remote) => remote.nip44_encrypt(public_key, content).await,
+                None => self.local_keys()?.nip44_encrypt(public_key, content).await,
+            }
+        })
+    }
+
+    fn nip44_decrypt<'a>(
+        &'a self,
+        public_key: &'a PublicKey,
+        payload: &'a str,
+    ) -> BoxedFuture<'a, Result<String, SignerError>> {
+        Box::pin(async move {
+            match &self.remote {
+                Some(remote) => remote.nip44_decrypt(public_key, payload).await,
+                None => self.local_keys()?.nip44_decrypt(public_key, payload).await,
+            }
+        })
+    }
+}
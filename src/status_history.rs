@@ -0,0 +1,229 @@
+use nostr::nips::nip01::Coordinate;
+use nostr::nips::nip09::EventDeletionRequest;
+use nostr::{Event, EventBuilder, Filter, Kind, Tag, TagKind, Timestamp};
+use nostr_sdk::Client;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{error, info};
+
+use crate::cache_db::{LmdbCache, DB_STATUS_HISTORY};
+use crate::i18n::tr;
+use crate::opentimestamps;
+use crate::outbox;
+use crate::pow;
+use crate::signer::AppSigner;
+use crate::types::{NostrStatusAppInternal, StatusHistoryEntry};
+
+// 残しておく履歴の件数
+const MAX_HISTORY_ENTRIES: usize = 100;
+// 消去する前に最新のステータスを探す時間
+const FETCH_CURRENT_STATUS_TIMEOUT_SECS: u64 = 5;
+
+pub fn load_status_history(cache_db: &LmdbCache, pubkey_hex: &str) -> Vec<StatusHistoryEntry> {
+    match cache_db.read_value::<Vec<StatusHistoryEntry>>(DB_STATUS_HISTORY, pubkey_hex) {
+        Ok(history) => history.unwrap_or_default(),
+        Err(e) => {
+            error!("Failed to load the status history: {e}");
+            Vec::new()
+        }
+    }
+}
+
+// d タグごとの今のステータスは、履歴と同じ DB に別のキーで保存する
+fn current_statuses_key(pubkey_hex: &str) -> String {
+    format!("{pubkey_hex}:current")
+}
+
+pub fn load_current_statuses(cache_db: &LmdbCache, pubkey_hex: &str) -> BTreeMap<String, StatusHistoryEntry> {
+    match cache_db.read_value::<BTreeMap<String, StatusHistoryEntry>>(DB_STATUS_HISTORY, &current_statuses_key(pubkey_hex)) {
+        Ok(current) => current.unwrap_or_default(),
+        Err(e) => {
+            error!("Failed to load the current statuses: {e}");
+            BTreeMap::new()
+        }
+    }
+}
+
+fn save_current_statuses(app_data: &NostrStatusAppInternal) {
+    let Some(signer) = &app_data.my_signer else {
+        return;
+    };
+    let key = current_statuses_key(&signer.public_key().to_string());
+    if let Err(e) = app_data.cache_db.write_value(DB_STATUS_HISTORY, &key, &app_data.current_statuses) {
+        error!("Failed to save the current statuses: {e}");
+    }
+}
+
+fn entry_from_event(event: &Event) -> StatusHistoryEntry {
+    StatusHistoryEntry {
+        d_tag: event.tags.identifier().unwrap_or_default().to_string(),
+        content: event.content.clone(),
+        tags: event.tags.clone().to_vec(),
+        created_at: event.created_at,
+    }
+}
+
+// 公開したステータスをその d タグの今のステータスにする。履歴には残さない
+pub fn set_current(app_data: &mut NostrStatusAppInternal, event: &Event) {
+    let entry = entry_from_event(event);
+    app_data.current_statuses.insert(entry.d_tag.clone(), entry);
+    save_current_statuses(app_data);
+}
+
+// 公開したステータスを履歴の先頭に加え、その d タグの今のステータスにする。同じ種類・同じ内容の古い履歴は取り除く
+pub fn record(app_data: &mut NostrStatusAppInternal, event: &Event) {
+    let entry = entry_from_event(event);
+    app_data
+        .status_history
+        .retain(|e| !(e.d_tag == entry.d_tag && e.content == entry.content));
+    app_data.status_history.insert(0, entry);
+    app_data.status_history.truncate(MAX_HISTORY_ENTRIES);
+    set_current(app_data, event);
+
+    let Some(signer) = &app_data.my_signer else {
+        return;
+    };
+    let pubkey_hex = signer.public_key().to_string();
+    if let Err(e) = app_data.cache_db.write_value(DB_STATUS_HISTORY, &pubkey_hex, &app_data.status_history) {
+        error!("Failed to save the status history: {e}");
+    }
+}
+
+// ステータスに署名して公開し、履歴に残す。送れなかった場合も送信キューに残るので、署名して送信を取り消さなければ true を返す
+pub async fn publish(
+    client: Client,
+    signer: AppSigner,
+    content: String,
+    mut tags: Vec<Tag>,
+    expiry: Option<Duration>,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+) -> bool {
+    // 期限を過ぎたステータスはリレーとクライアントが捨てる (NIP-40)
+    if let Some(expiry) = expiry {
+        tags.push(Tag::expiration(Timestamp::now() + expiry));
+    }
+
+    let builder = EventBuilder::new(Kind::from(30315), content).tags(tags);
+    let event = match pow::sign_with_pow(builder, &signer, &app_data_arc).await {
+        Ok(event) => event,
+        Err(e) => {
+            error!("Failed to create event: {e}");
+            app_data_arc.lock().unwrap().report_error(tr!("ステータスに署名できませんでした: {}", e));
+            return false;
+        }
+    };
+
+    let Some(sent) = outbox::send_or_queue_with_undo(&client, vec![event.clone()], &app_data_arc, tr!("ステータスを公開します")).await else {
+        return false;
+    };
+    tokio::spawn(opentimestamps::stamp(event.clone(), app_data_arc.clone()));
+    let mut app_data = app_data_arc.lock().unwrap();
+    record(&mut app_data, &event);
+    if sent {
+        info!("Status published with event id: {:?}", event.id);
+        app_data.notify(tr!("ステータスを公開しました"));
+    } else {
+        app_data.report_error(tr!("ステータスを送信できませんでした。送信キューに残し、接続が戻ったら再送します"));
+    }
+    true
+}
+
+// 履歴のステータスを今の時刻で公開し直す。期限付きだった場合は同じ長さの期限を付け直す
+pub async fn republish(
+    client: Client,
+    signer: AppSigner,
+    entry: StatusHistoryEntry,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+) {
+    let now = Timestamp::now();
+    let mut tags: Vec<Tag> = Vec::new();
+    for tag in entry.tags {
+        if tag.kind() == TagKind::Expiration {
+            if let Some(expiration) = tag.content().and_then(|s| s.parse::<u64>().ok()) {
+                let lifetime = expiration.saturating_sub(entry.created_at.as_u64());
+                tags.push(Tag::expiration(Timestamp::from(now.as_u64() + lifetime)));
+            }
+        } else {
+            tags.push(tag);
+        }
+    }
+
+    let builder = EventBuilder::new(Kind::from(30315), entry.content).tags(tags);
+    let event = match pow::sign_with_pow(builder, &signer, &app_data_arc).await {
+        Ok(event) => event,
+        Err(e) => {
+            error!("Failed to sign the restored status: {e}");
+            app_data_arc.lock().unwrap().report_error(tr!("ステータスに署名できませんでした: {}", e));
+            return;
+        }
+    };
+
+    let sent = outbox::send_or_queue(&client, event.clone(), &app_data_arc).await;
+    tokio::spawn(opentimestamps::stamp(event.clone(), app_data_arc.clone()));
+    let mut app_data = app_data_arc.lock().unwrap();
+    record(&mut app_data, &event);
+    if sent {
+        app_data.notify(tr!("以前のステータスを公開し直しました"));
+    } else {
+        app_data.report_error(tr!("ステータスを送信できませんでした。送信キューに残し、接続が戻ったら再送します"));
+    }
+}
+
+// 今のステータスを消去する。最新のイベントの削除要求 (NIP-09) と、空のステータスでの置き換えを公開する
+pub async fn clear_status(
+    client: Client,
+    signer: AppSigner,
+    d_tag: String,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+) {
+    let public_key = signer.public_key();
+    let status_kind = Kind::from(30315);
+    let filter = Filter::new()
+        .author(public_key)
+        .kind(status_kind)
+        .identifier(d_tag.clone())
+        .limit(1);
+    let latest = match client.fetch_events(filter, Duration::from_secs(FETCH_CURRENT_STATUS_TIMEOUT_SECS)).await {
+        Ok(events) => events.first_owned(),
+        Err(e) => {
+            error!("Failed to fetch the current status: {e}");
+            None
+        }
+    };
+
+    let mut request = EventDeletionRequest::new()
+        .coordinate(Coordinate::new(status_kind, public_key).identifier(d_tag.clone()));
+    if let Some(event) = &latest {
+        request = request.id(event.id);
+    }
+    let deletion = EventBuilder::delete(request).sign(&signer).await;
+    let replacement = EventBuilder::new(status_kind, "")
+        .tag(Tag::identifier(d_tag.clone()))
+        .sign(&signer)
+        .await;
+    let (deletion, replacement) = match (deletion, replacement) {
+        (Ok(deletion), Ok(replacement)) => (deletion, replacement),
+        (Err(e), _) | (_, Err(e)) => {
+            error!("Failed to sign the status deletion: {e}");
+            app_data_arc.lock().unwrap().report_error(tr!("ステータスの消去に署名できませんでした: {}", e));
+            return;
+        }
+    };
+
+    let deletion_sent = outbox::send_or_queue(&client, deletion, &app_data_arc).await;
+    let replacement_sent = outbox::send_or_queue(&client, replacement, &app_data_arc).await;
+
+    let mut app_data = app_data_arc.lock().unwrap();
+    app_data.current_statuses.remove(&d_tag);
+    save_current_statuses(&app_data);
+    app_data.timeline_posts.retain(|post| {
+        !(post.author_pubkey == public_key
+            && post.tags.iter().any(|tag| tag.kind() == TagKind::d() && tag.content() == Some(d_tag.as_str())))
+    });
+    if deletion_sent && replacement_sent {
+        app_data.notify(tr!("ステータスを消去しました"));
+    } else {
+        app_data.report_error(tr!("ステータスの消去を送信できませんでした。送信キューに残し、接続が戻ったら再送します"));
+    }
+}
@@ -1,4 +1,83 @@
 use eframe::egui;
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+
+use crate::types::{AppTheme, ThemePreference, ThemePreset};
+
+// 角丸はライトとダークで共通
+const CORNER_RADIUS: f32 = 6.0;
+// 設定で選べる画面の拡大率と本文の文字の大きさ (pt)
+pub const UI_SCALE_RANGE: RangeInclusive<f32> = 0.75..=3.0;
+pub const FONT_SIZE_RANGE: RangeInclusive<f32> = 9.0..=24.0;
+// 見出しや小さい文字は、本文が 13pt のときの大きさと同じ比率にする
+const BASE_FONT_SIZE: f32 = 13.0;
+
+fn visuals_for(theme: AppTheme) -> egui::Visuals {
+    let mut visuals = match theme {
+        AppTheme::Light => light_visuals(),
+        AppTheme::Dark => dark_visuals(),
+        AppTheme::LineLight => line_light_visuals(),
+        AppTheme::LineDark => line_dark_visuals(),
+    };
+    let widgets = &mut visuals.widgets;
+    for widget in [&mut widgets.noninteractive, &mut widgets.inactive, &mut widgets.hovered, &mut widgets.active, &mut widgets.open] {
+        widget.corner_radius = CORNER_RADIUS.into();
+    }
+    visuals
+}
+
+// ライトとダークのスタイルを両方登録しておく。どちらを使うかは apply_theme_preference で選ぶ
+pub fn install_styles(ctx: &egui::Context, base: egui::Style, preset: ThemePreset) {
+    ctx.set_style_of(egui::Theme::Light, base.clone());
+    ctx.set_style_of(egui::Theme::Dark, base);
+    apply_theme_preset(ctx, preset);
+}
+
+// 配色を切り替える。ライトとダークの両方を差し替えるので、「システム」のままでも配色が変わる
+pub fn apply_theme_preset(ctx: &egui::Context, preset: ThemePreset) {
+    for (egui_theme, dark) in [(egui::Theme::Light, false), (egui::Theme::Dark, true)] {
+        ctx.style_mut_of(egui_theme, |style| style.visuals = visuals_for(AppTheme::new(preset, dark)));
+    }
+}
+
+// 本文の大きさから、ほかのテキストスタイルの大きさを決める。ライトとダークで共通
+pub fn text_styles(font_size: f32) -> BTreeMap<egui::TextStyle, egui::FontId> {
+    let ratio = font_size.clamp(*FONT_SIZE_RANGE.start(), *FONT_SIZE_RANGE.end()) / BASE_FONT_SIZE;
+    [
+        (egui::TextStyle::Heading, egui::FontId::new(20.0 * ratio, egui::FontFamily::Proportional)),
+        (egui::TextStyle::Body, egui::FontId::new(13.0 * ratio, egui::FontFamily::Proportional)),
+        (egui::TextStyle::Monospace, egui::FontId::new(12.0 * ratio, egui::FontFamily::Monospace)),
+        (egui::TextStyle::Button, egui::FontId::new(13.0 * ratio, egui::FontFamily::Proportional)),
+        (egui::TextStyle::Small, egui::FontId::new(11.0 * ratio, egui::FontFamily::Proportional)),
+    ]
+    .into()
+}
+
+// 文字の大きさをその場で変える
+pub fn apply_font_size(ctx: &egui::Context, font_size: f32) {
+    for egui_theme in [egui::Theme::Light, egui::Theme::Dark] {
+        ctx.style_mut_of(egui_theme, |style| style.text_styles = text_styles(font_size));
+    }
+}
+
+// 画面全体の拡大率を変える
+pub fn apply_ui_scale(ctx: &egui::Context, ui_scale: f32) {
+    ctx.set_pixels_per_point(ui_scale.clamp(*UI_SCALE_RANGE.start(), *UI_SCALE_RANGE.end()));
+}
+
+// 設定のテーマに切り替える。「システム」のときは OS の設定が変わると egui が追従する
+pub fn apply_theme_preference(ctx: &egui::Context, preference: ThemePreference) {
+    ctx.set_theme(match preference {
+        ThemePreference::Light => egui::ThemePreference::Light,
+        ThemePreference::Dark => egui::ThemePreference::Dark,
+        ThemePreference::System => egui::ThemePreference::System,
+    });
+}
+
+// いま表示しているテーマ。「システム」のときは OS の設定で決まる
+pub fn current_theme(ctx: &egui::Context, preset: ThemePreset) -> AppTheme {
+    AppTheme::new(preset, ctx.theme() == egui::Theme::Dark)
+}
 
 // --- ライトモードのVisualsを返す関数 ---
 pub fn light_visuals() -> egui::Visuals {
@@ -78,3 +157,61 @@ pub fn dark_visuals() -> egui::Visuals {
 
     visuals
 }
+
+// LINE の緑 (#06C755)
+const LINE_GREEN: egui::Color32 = egui::Color32::from_rgb(6, 199, 85);
+
+// --- LINE 風の配色 (ライト) ---
+// 白地に緑のアクセント。ボタンも緑がかった色にする
+pub fn line_light_visuals() -> egui::Visuals {
+    let mut visuals = light_visuals();
+    let background_color = egui::Color32::WHITE;
+    let text_color = egui::Color32::BLACK;
+
+    visuals.window_fill = background_color;
+    visuals.panel_fill = background_color;
+    visuals.faint_bg_color = egui::Color32::from_rgb(245, 247, 246);
+    visuals.extreme_bg_color = egui::Color32::from_rgb(240, 243, 241);
+    visuals.hyperlink_color = LINE_GREEN;
+    visuals.selection.bg_fill = LINE_GREEN.linear_multiply(0.3);
+    visuals.selection.stroke = egui::Stroke::new(1.0, text_color);
+
+    let widget_visuals = &mut visuals.widgets;
+    widget_visuals.inactive.bg_fill = egui::Color32::from_rgb(232, 245, 236);
+    widget_visuals.inactive.weak_bg_fill = widget_visuals.inactive.bg_fill;
+    widget_visuals.hovered.bg_fill = egui::Color32::from_rgb(205, 238, 216);
+    widget_visuals.hovered.weak_bg_fill = widget_visuals.hovered.bg_fill;
+    widget_visuals.active.bg_fill = egui::Color32::from_rgb(180, 230, 196);
+    widget_visuals.active.weak_bg_fill = widget_visuals.active.bg_fill;
+    widget_visuals.active.fg_stroke = egui::Stroke::new(1.0, LINE_GREEN);
+
+    visuals
+}
+
+// --- LINE 風の配色 (ダーク) ---
+pub fn line_dark_visuals() -> egui::Visuals {
+    let mut visuals = dark_visuals();
+    let background_color = egui::Color32::from_rgb(17, 17, 17);
+    let panel_color = egui::Color32::from_rgb(30, 30, 30);
+    let text_color = egui::Color32::from_gray(230);
+
+    visuals.window_fill = background_color;
+    visuals.panel_fill = panel_color;
+    visuals.faint_bg_color = background_color;
+    visuals.extreme_bg_color = egui::Color32::from_gray(45);
+    visuals.hyperlink_color = LINE_GREEN;
+    visuals.selection.bg_fill = LINE_GREEN.linear_multiply(0.4);
+    visuals.selection.stroke = egui::Stroke::new(1.0, text_color);
+
+    let widget_visuals = &mut visuals.widgets;
+    widget_visuals.noninteractive.bg_fill = panel_color;
+    widget_visuals.inactive.bg_fill = egui::Color32::from_rgb(36, 52, 42);
+    widget_visuals.inactive.weak_bg_fill = widget_visuals.inactive.bg_fill;
+    widget_visuals.hovered.bg_fill = egui::Color32::from_rgb(44, 72, 55);
+    widget_visuals.hovered.weak_bg_fill = widget_visuals.hovered.bg_fill;
+    widget_visuals.active.bg_fill = egui::Color32::from_rgb(52, 92, 66);
+    widget_visuals.active.weak_bg_fill = widget_visuals.active.bg_fill;
+    widget_visuals.active.fg_stroke = egui::Stroke::new(1.0, LINE_GREEN);
+
+    visuals
+}
@@ -0,0 +1,244 @@
+use eframe::egui;
+use std::sync::{Arc, Mutex};
+
+use crate::types::NostrStatusAppInternal;
+
+// システムトレイのアイコン。今は Linux (StatusNotifierItem) で tray 機能を有効にしたときのみ出せる
+// メニューから定型文のステータスを公開したり、ウィンドウを出したり隠したりできる。未読のトークがあればアイコンに印を付ける
+
+// トレイに出す内容。変わったときだけアイコンとメニューを作り直す
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(not(all(feature = "tray", target_os = "linux")), allow(dead_code))]
+pub struct TrayState {
+    pub unread_dms: usize,
+    pub status_presets: Vec<String>,
+    pub can_publish: bool,
+}
+
+#[cfg(all(feature = "tray", target_os = "linux"))]
+mod backend {
+    use super::*;
+    use ksni::menu::{StandardItem, SubMenu};
+    use ksni::{Icon, MenuItem, Status, ToolTip, TrayMethods};
+    use nostr::Tag;
+    use tracing::{info, warn};
+
+    use crate::i18n::tr;
+    use crate::{note, status_history};
+
+    // アイコンの大きさ (ピクセル)
+    const ICON_SIZE: i32 = 32;
+    // アイコンの色 (RGB)。LINE 風の緑と、未読の印の赤
+    const ICON_COLOR: [u8; 3] = [0x06, 0xc7, 0x55];
+    const BADGE_COLOR: [u8; 3] = [0xff, 0x33, 0x4b];
+
+    struct AppTray {
+        state: TrayState,
+        window_hidden: bool,
+        ctx: egui::Context,
+        app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+        runtime_handle: tokio::runtime::Handle,
+    }
+
+    // 定型文をそのまま一般のステータスとして公開する。ホームの投稿と同じく、公開している間は is_loading を立てる
+    fn publish_preset(app_data_arc: Arc<Mutex<NostrStatusAppInternal>>, runtime_handle: &tokio::runtime::Handle, preset: String) {
+        runtime_handle.spawn(async move {
+            let (client, signer, emojis) = {
+                let mut app_data = app_data_arc.lock().unwrap();
+                let (Some(client), Some(signer)) = (app_data.nostr_client.clone(), app_data.my_signer.clone()) else {
+                    return;
+                };
+                if !app_data.is_logged_in || app_data.is_loading {
+                    return;
+                }
+                app_data.is_loading = true;
+                app_data.should_repaint = true;
+                (client, signer, app_data.my_emojis.clone())
+            };
+            info!("Publishing a status preset from the tray...");
+            let (content, mut tags) = note::prepare_note(&preset);
+            tags.extend(note::emoji_tags(&content, &emojis));
+            tags.push(Tag::identifier("general"));
+            status_history::publish(client, signer, content, tags, None, app_data_arc.clone()).await;
+            let mut app_data = app_data_arc.lock().unwrap();
+            app_data.is_loading = false;
+            app_data.should_repaint = true;
+        });
+    }
+
+    impl AppTray {
+        fn set_window_hidden(&mut self, hidden: bool) {
+            self.window_hidden = hidden;
+            self.ctx.send_viewport_cmd(egui::ViewportCommand::Visible(!hidden));
+            if !hidden {
+                self.ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+            }
+        }
+    }
+
+    // 丸いアイコンを ARGB (ネットワークバイトオーダー) で描く。未読があれば右上に赤い丸を重ねる
+    fn draw_icon(with_badge: bool) -> Icon {
+        let size = ICON_SIZE as f32;
+        let badge_center = size * 0.75;
+        let mut data = Vec::with_capacity((ICON_SIZE * ICON_SIZE * 4) as usize);
+        for y in 0..ICON_SIZE {
+            for x in 0..ICON_SIZE {
+                let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+                let in_circle = (px - size / 2.0).hypot(py - size / 2.0) <= size / 2.0 - 1.0;
+                let in_badge = with_badge && (px - badge_center).hypot(py - size / 4.0) <= size / 4.0;
+                let pixel = if in_badge {
+                    [0xff, BADGE_COLOR[0], BADGE_COLOR[1], BADGE_COLOR[2]]
+                } else if in_circle {
+                    [0xff, ICON_COLOR[0], ICON_COLOR[1], ICON_COLOR[2]]
+                } else {
+                    [0, 0, 0, 0]
+                };
+                data.extend_from_slice(&pixel);
+            }
+        }
+        Icon { width: ICON_SIZE, height: ICON_SIZE, data }
+    }
+
+    impl ksni::Tray for AppTray {
+        fn id(&self) -> String {
+            crate::WINDOW_TITLE.to_string()
+        }
+
+        fn title(&self) -> String {
+            if self.state.unread_dms > 0 {
+                format!("({}) {}", self.state.unread_dms, crate::WINDOW_TITLE)
+            } else {
+                crate::WINDOW_TITLE.to_string()
+            }
+        }
+
+        fn status(&self) -> Status {
+            if self.state.unread_dms > 0 { Status::NeedsAttention } else { Status::Active }
+        }
+
+        fn icon_pixmap(&self) -> Vec<Icon> {
+            vec![draw_icon(false)]
+        }
+
+        fn attention_icon_pixmap(&self) -> Vec<Icon> {
+            vec![draw_icon(true)]
+        }
+
+        fn tool_tip(&self) -> ToolTip {
+            let description = if self.state.unread_dms > 0 { tr!("未読のトーク {} 件", self.state.unread_dms) } else { String::new() };
+            ToolTip { title: self.title(), description, ..Default::default() }
+        }
+
+        // アイコンをクリックしたらウィンドウを出す
+        fn activate(&mut self, _x: i32, _y: i32) {
+            self.set_window_hidden(false);
+        }
+
+        fn menu(&self) -> Vec<MenuItem<Self>> {
+            let mut presets: Vec<MenuItem<Self>> = self
+                .state
+                .status_presets
+                .iter()
+                .map(|preset| {
+                    let content = preset.clone();
+                    StandardItem {
+                        label: preset.replace('_', "__"),
+                        enabled: self.state.can_publish,
+                        activate: Box::new(move |tray: &mut Self| publish_preset(tray.app_data_arc.clone(), &tray.runtime_handle, content.clone())),
+                        ..Default::default()
+                    }
+                    .into()
+                })
+                .collect();
+            if presets.is_empty() {
+                presets.push(StandardItem { label: tr!("定型文は設定で追加できます").to_string(), enabled: false, ..Default::default() }.into());
+            }
+
+            let mut items: Vec<MenuItem<Self>> = Vec::new();
+            if self.state.unread_dms > 0 {
+                items.push(StandardItem { label: tr!("未読のトーク {} 件", self.state.unread_dms), enabled: false, ..Default::default() }.into());
+                items.push(MenuItem::Separator);
+            }
+            items.push(SubMenu { label: tr!("ステータスを変更").to_string(), submenu: presets, ..Default::default() }.into());
+            items.push(MenuItem::Separator);
+            let hidden = self.window_hidden;
+            items.push(
+                StandardItem {
+                    label: if hidden { tr!("ウィンドウを表示") } else { tr!("ウィンドウを隠す") }.to_string(),
+                    activate: Box::new(move |tray: &mut Self| tray.set_window_hidden(!hidden)),
+                    ..Default::default()
+                }
+                .into(),
+            );
+            items.push(
+                StandardItem {
+                    label: tr!("アプリを終了").to_string(),
+                    activate: Box::new(|tray: &mut Self| {
+                        tray.set_window_hidden(false);
+                        tray.ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                    }),
+                    ..Default::default()
+                }
+                .into(),
+            );
+            items
+        }
+    }
+
+    pub struct TrayIcon {
+        handle: ksni::Handle<AppTray>,
+        state: TrayState,
+        runtime_handle: tokio::runtime::Handle,
+    }
+
+    impl TrayIcon {
+        pub fn update(&mut self, state: TrayState) {
+            if state == self.state {
+                return;
+            }
+            self.state = state.clone();
+            let handle = self.handle.clone();
+            self.runtime_handle.spawn(async move {
+                handle.update(|tray| tray.state = state).await;
+            });
+        }
+    }
+
+    // トレイのサービスを始める。トレイに対応したデスクトップでなければログに残して None を返す
+    pub fn start(ctx: &egui::Context, app_data_arc: Arc<Mutex<NostrStatusAppInternal>>, runtime_handle: tokio::runtime::Handle) -> Option<TrayIcon> {
+        let tray = AppTray {
+            state: TrayState::default(),
+            window_hidden: false,
+            ctx: ctx.clone(),
+            app_data_arc,
+            runtime_handle: runtime_handle.clone(),
+        };
+        match runtime_handle.block_on(tray.spawn()) {
+            Ok(handle) => Some(TrayIcon { handle, state: TrayState::default(), runtime_handle }),
+            Err(e) => {
+                warn!("The system tray is not available: {e}");
+                None
+            }
+        }
+    }
+}
+
+#[cfg(not(all(feature = "tray", target_os = "linux")))]
+mod backend {
+    use super::*;
+
+    // このビルドではトレイを作らないので、値を持たない
+    pub enum TrayIcon {}
+
+    impl TrayIcon {
+        pub fn update(&mut self, _state: TrayState) {
+            match *self {}
+        }
+    }
+
+    pub fn start(_ctx: &egui::Context, _app_data_arc: Arc<Mutex<NostrStatusAppInternal>>, _runtime_handle: tokio::runtime::Handle) -> Option<TrayIcon> {
+        None
+    }
+}
+
+pub use backend::{start, TrayIcon};
@@ -1,20 +1,115 @@
 use eframe::egui;
-use nostr::{nips::nip47::NostrWalletConnectURI, PublicKey, Timestamp, Keys, EventId, Kind};
+use nostr::{nips::{nip19::ToBech32, nip39::Identity, nip47::NostrWalletConnectURI}, PublicKey, Timestamp, Keys, Event, EventId, Kind, RelayUrl, Tag};
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64};
+use std::time::{Duration, Instant};
 use chrono::{DateTime, Utc};
-use nostr_sdk::Client;
+use nostr_sdk::{Client, RelayStatus};
 
 use crate::cache_db::LmdbCache;
+use crate::call::{CallMediaBackend, CallSession};
+use crate::camera::CameraScan;
+use crate::media::UploadedMedia;
+use crate::settings_sync::SyncedSettings;
+use crate::signer::AppSigner;
+use crate::voice::Recording;
 
 // --- Pub-used structs and enums ---
 
-#[derive(Serialize, Deserialize)]
+// 設定ファイル。このアプリに秘密鍵を保存したアカウントを並べ、最後に使ったものを覚えておく
+// kdf はこれから暗号化するときに使う方式で、設定ファイルを書き換えればパラメータを変えられる
+#[derive(Serialize, Deserialize, Default)]
 pub struct Config {
+    #[serde(default)]
+    pub accounts: Vec<StoredAccount>,
+    #[serde(default)]
+    pub active_account: usize,
+    #[serde(default)]
+    pub kdf: Kdf,
+}
+
+// パスフレーズから暗号鍵を作る方式 (ウォレットの接続先などの暗号化に使う)
+// 秘密鍵そのものは他のクライアントに取り込めるよう、NIP-49 (scrypt) の ncryptsec で保存する
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(tag = "algorithm", rename_all = "lowercase")]
+pub enum Kdf {
+    Pbkdf2 { iterations: u32 },
+    Argon2id { memory_kib: u32, iterations: u32, parallelism: u32 },
+}
+
+impl Kdf {
+    // 以前のバージョンが使っていた PBKDF2 (SHA-256, 10万回)
+    pub fn legacy() -> Self {
+        Kdf::Pbkdf2 { iterations: 100_000 }
+    }
+}
+
+impl Default for Kdf {
+    // OWASP の推奨 (メモリ 64 MiB 以上) に合わせる
+    fn default() -> Self {
+        Kdf::Argon2id { memory_kib: 64 * 1024, iterations: 3, parallelism: 1 }
+    }
+}
+
+impl Config {
+    pub fn active(&self) -> Option<&StoredAccount> {
+        self.accounts.get(self.active_account)
+    }
+
+    pub fn active_mut(&mut self) -> Option<&mut StoredAccount> {
+        self.accounts.get_mut(self.active_account)
+    }
+}
+
+// アカウントごとの暗号化した秘密鍵とウォレットの接続先
+// pubkey は以前の形式から移したアカウントでは、次にログインするまで空のまま
+#[derive(Serialize, Deserialize, Clone)]
+pub struct StoredAccount {
+    pub label: String,
+    #[serde(default)]
+    pub pubkey: String,
     pub encrypted_secret_key: String,
     pub salt: String,
     #[serde(default)]
     pub encrypted_nwc_uri: Option<String>,
+    #[serde(default)]
+    pub key_storage: KeyStorage,
+    // 記録がないのは以前のバージョンが書いた設定なので PBKDF2
+    #[serde(default = "Kdf::legacy")]
+    pub kdf: Kdf,
+}
+
+// サイドパネルとログイン画面に出すアカウントの一覧
+#[derive(Clone)]
+pub struct AccountSummary {
+    pub label: String,
+    pub pubkey: String,
+}
+
+// 切り替える前のアカウント。接続したままにして、DM とメンションを通知する
+pub struct SecondaryAccount {
+    pub label: String,
+    pub signer: AppSigner,
+    pub client: Client,
+    pub unread: usize,
+}
+
+// 暗号化した秘密鍵の保存先
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum KeyStorage {
+    #[default]
+    File,
+    Keyring,
+}
+
+// NIP-46 のリモート署名者への接続情報。再接続用に bunker URI とこのアプリ用の鍵を保存する
+// アプリ用の鍵はユーザーの秘密鍵ではなく、署名者側でいつでも失効させられる
+#[derive(Serialize, Deserialize)]
+pub struct BunkerSession {
+    pub uri: String,
+    pub app_secret_key: String,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -44,6 +139,8 @@ pub struct ProfileMetadata {
     #[serde(default)]
     pub name: String,
     #[serde(default)]
+    pub display_name: String,
+    #[serde(default)]
     pub about: String,
     #[serde(default)]
     pub picture: String,
@@ -53,22 +150,315 @@ pub struct ProfileMetadata {
     pub emojis: Vec<[String; 2]>,
     #[serde(default)]
     pub lud16: String,
+    #[serde(default)]
+    pub lud06: String,
     #[serde(flatten)]
     pub extra: HashMap<String, serde_json::Value>,
 }
 
-#[derive(Debug, Clone, Default)]
+impl ProfileMetadata {
+    // display_name → name → 短縮したnpub の順で表示名を決める
+    pub fn display_name_or_npub(&self, pubkey: &PublicKey) -> String {
+        if !self.display_name.is_empty() {
+            self.display_name.clone()
+        } else if !self.name.is_empty() {
+            self.name.clone()
+        } else {
+            short_npub(pubkey)
+        }
+    }
+}
+
+pub fn short_npub(pubkey: &PublicKey) -> String {
+    let npub = pubkey.to_bech32().unwrap_or_default();
+    if npub.len() < 12 {
+        return npub;
+    }
+    format!("{}...{}", &npub[0..8], &npub[npub.len() - 4..])
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct EditableRelay {
     pub url: String,
     pub read: bool,
     pub write: bool,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OutboxState {
+    Pending,
+    Sent,
+}
+
+// 送信を取り消せる間、送らずに待っているもの。通知と一緒に「取り消す」を表示する
+#[derive(Debug, Clone)]
+pub struct HeldSend {
+    pub id: u64,
+    pub description: String,
+    pub send_at: Instant,
+    // 送信キューに入れてある、取り消したら消すイベント
+    pub event_ids: Vec<EventId>,
+}
+
+// PoW (NIP-13) を計算している投稿。試した回数を表示し、中止できるようにする
+#[derive(Debug, Clone)]
+pub struct PowJob {
+    pub id: u64,
+    pub difficulty: u8,
+    pub attempts: Arc<AtomicU64>,
+    pub cancelled: Arc<AtomicBool>,
+}
+
+// 送信キューの1件。署名済みのイベントをそのまま保存し、再送に使う
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutboxEntry {
+    pub event: Event,
+    pub state: OutboxState,
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    // 各リレーの応答 (NIP-20のOK)。拒否されたリレーは理由を持つ
+    #[serde(default)]
+    pub relay_results: Vec<RelayPublishResult>,
+    // 送信を取り消せる間は、この時刻まで再送しない
+    #[serde(default)]
+    pub not_before: Option<Timestamp>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RelayPublishResult {
+    pub url: String,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToastKind {
+    Info,
+    Error,
+}
+
+// 画面の隅に一定時間だけ表示する通知
+#[derive(Debug, Clone)]
+pub struct Toast {
+    pub kind: ToastKind,
+    pub message: String,
+    pub created_at: Instant,
+}
+
+impl Toast {
+    pub fn new(kind: ToastKind, message: String) -> Self {
+        Self { kind, message, created_at: Instant::now() }
+    }
+}
+
+// 自分が公開したステータス (kind 30315) の履歴。公開し直せるように内容とタグを残す
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusHistoryEntry {
+    pub d_tag: String,
+    pub content: String,
+    pub tags: Vec<Tag>,
+    pub created_at: Timestamp,
+}
+
+impl StatusHistoryEntry {
+    pub fn expiration(&self) -> Option<Timestamp> {
+        expiration_of(&self.tags)
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expiration().is_some_and(|expiration| expiration <= Timestamp::now())
+    }
+}
+
+// expiration タグ (NIP-40) の期限
+fn expiration_of(tags: &[Tag]) -> Option<Timestamp> {
+    tags.iter().find_map(|tag| match tag.as_standardized() {
+        Some(nostr::TagStandard::Expiration(timestamp)) => Some(*timestamp),
+        _ => None,
+    })
+}
+
+// 本文中の nostr: 参照 (NIP-21) とハッシュタグの行き先
+#[derive(Debug, Clone, PartialEq)]
+pub enum NostrLink {
+    Profile(PublicKey),
+    Event(EventId),
+    Address(nostr::nips::nip01::Coordinate),
+    // 小文字にそろえたハッシュタグ (# は付けない)
+    Hashtag(String),
+}
+
+// 引用 (NIP-18) する投稿
+#[derive(Debug, Clone)]
+pub struct QuoteTarget {
+    pub event_id: EventId,
+    pub author: PublicKey,
+    pub kind: Kind,
+    pub preview: String,
+}
+
+// バッジの定義 (NIP-58, kind 30009)。画像は URL のまま持つ
+#[derive(Debug, Clone, PartialEq)]
+pub struct Badge {
+    pub issuer: PublicKey,
+    pub d_tag: String,
+    pub name: String,
+    pub description: String,
+    pub image: String,
+    // 小さく表示するときの画像。なければ image を使う
+    pub thumb: String,
+}
+
+// 友だちの輪 (Web of Trust) で見た投稿者。上ほど信頼できる
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TrustLevel {
+    // 自分か、自分がフォローしている人
+    Followed,
+    // フォロー中の人がフォローしている人
+    FollowedByFollows,
+    Unknown,
+}
+
+// 通報 (NIP-56) の対象。投稿の通報なら event_id にその投稿を入れる
+#[derive(Debug, Clone)]
+pub struct ReportTarget {
+    pub public_key: PublicKey,
+    pub event_id: Option<EventId>,
+}
+
+// 検索でヒットしたノートやステータス
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub id: EventId,
+    pub kind: Kind,
+    pub author: PublicKey,
+    pub content: String,
+    pub created_at: Timestamp,
+    pub tags: Vec<Tag>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SearchResults {
+    pub query: String,
+    pub profiles: Vec<PublicKey>,
+    pub posts: Vec<SearchHit>,
+    // 検索用リレー (NIP-50) の結果なら true、手元のキャッシュから探したなら false
+    pub from_relays: bool,
+}
+
+// どこまで読んだか。アカウントごとに保存し、未読の件数に使う
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReadState {
+    // 会話ごとに、最後に読んだ相手のメッセージの時刻
+    pub conversations: HashMap<PublicKey, Timestamp>,
+    // これより前のメッセージは既読とする (初めてログインした時刻)
+    pub conversations_since: Timestamp,
+    pub home: Timestamp,
+    pub mentions: Timestamp,
+}
+
+impl ReadState {
+    pub fn starting_at(timestamp: Timestamp) -> Self {
+        Self {
+            conversations: HashMap::new(),
+            conversations_since: timestamp,
+            home: timestamp,
+            mentions: timestamp,
+        }
+    }
+}
+
+// 入力途中で送っていない投稿。アプリを閉じたりアカウントを切り替えたりしても残るよう、アカウントごとに保存する
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Drafts {
+    pub status: String,
+    pub note: String,
+    // トークの入力欄は会話を切り替えても残るので、開いていた相手と一緒に保存する
+    pub dm: String,
+    pub dm_peer: Option<PublicKey>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TimestampProofState {
+    // カレンダーが受け付け、ビットコインのブロックに記録されるのを待っている
+    Pending,
+    // ブロックに記録され、kind 1040 を公開した
+    Attested,
+    Failed(String),
+}
+
+// 公開したステータスの OpenTimestamps (NIP-03) の証明
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimestampProof {
+    pub event_id: EventId,
+    pub kind: u16,
+    pub calendar: String,
+    // カレンダーから受け取った、イベントの id からカレンダーのコミットメントまでの経路と保留中の証明
+    pub pending_proof: Vec<u8>,
+    pub state: TimestampProofState,
+    pub submitted_at: Timestamp,
+}
+
+// 他のユーザーのプロフィールページに表示する内容
+#[derive(Debug, Clone)]
+pub struct UserProfileData {
+    pub public_key: PublicKey,
+    pub metadata: ProfileMetadata,
+    // プロフィールの i タグで主張している外部アカウント (NIP-39)
+    pub identities: Vec<Identity>,
+    // NIP-65 のリレーリスト。(URL, "read" / "write" / なし)
+    pub relays: Vec<(String, Option<String>)>,
+    // d タグごとの今のステータス (NIP-38)
+    pub statuses: Vec<Event>,
+    pub notes: Vec<Event>,
+    // コンタクトリスト (kind 3) の p タグの数。公開されていなければ None
+    pub following_count: Option<usize>,
+    // NIP-45 の COUNT で数える。取得中は None
+    pub counts: Option<ProfileCounts>,
+    // プロフィールに表示しているバッジ (NIP-58)。取得中は None
+    pub badges: Option<Vec<Badge>>,
+}
+
+// リレーに数えてもらった件数。どのリレーも COUNT に答えなければ None
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProfileCounts {
+    pub followers: Option<usize>,
+    pub zaps: Option<usize>,
+}
+
+// 予約したステータスの操作
+#[derive(Debug, Clone)]
+pub enum ScheduledAction {
+    // 指定の時刻に公開する。期限は公開した時刻から数える
+    Publish { content: String, tags: Vec<Tag>, expiry: Option<Duration> },
+    // 指定の時刻にその d タグのステータスを消去する
+    Clear,
+}
+
+#[derive(Debug, Clone)]
+pub struct ScheduledStatus {
+    pub id: u64,
+    pub run_at: Timestamp,
+    pub d_tag: String,
+    pub action: ScheduledAction,
+}
+
+// リレータブの接続状況の表の1行分
+#[derive(Debug, Clone, PartialEq)]
+pub struct RelayStatusRow {
+    pub url: String,
+    pub status: RelayStatus,
+    pub last_message_at: Option<Timestamp>,
+    pub latency: Option<Duration>,
+    pub events_received: u64,
+}
+
 #[derive(Clone, Copy, Debug)]
 pub enum ImageKind {
     Avatar,
     Emoji,
     ProfilePicture,
+    Preview,
+    Badge,
 }
 
 #[derive(Clone)]
@@ -90,6 +480,14 @@ pub struct ZapReceipt {
     pub zapped_event_id: Option<EventId>,
 }
 
+// ZAPの送り先。ステータスへのZAPならeventにIDと種類が入る
+#[derive(Debug, Clone)]
+pub struct ZapTarget {
+    pub pubkey: PublicKey,
+    pub metadata: ProfileMetadata,
+    pub event: Option<(EventId, Kind)>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TimelinePost {
     pub id: EventId,
@@ -102,20 +500,444 @@ pub struct TimelinePost {
     pub emojis: HashMap<String, String>,
     #[serde(default)]
     pub tags: Vec<nostr::Tag>,
+    // フォローセットの人だけにギフトラップで届いたステータス。リレーには公開されていない
+    #[serde(default)]
+    pub is_private: bool,
+}
+
+impl TimelinePost {
+    pub fn expiration(&self) -> Option<Timestamp> {
+        expiration_of(&self.tags)
+    }
+
+    // ステータスの種類を表す d タグ (NIP-38)
+    pub fn d_tag(&self) -> &str {
+        self.tags
+            .iter()
+            .find(|tag| tag.kind() == nostr::TagKind::d())
+            .and_then(|tag| tag.content())
+            .unwrap_or_default()
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expiration().is_some_and(|expiration| expiration <= Timestamp::now())
+    }
+}
+
+// ピン留めしたトークとお気に入りの友だち。リストの先頭に並べる
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PinnedItems {
+    #[serde(default)]
+    pub pinned_conversations: HashSet<PublicKey>,
+    #[serde(default)]
+    pub favorite_friends: HashSet<PublicKey>,
+}
+
+// フォローセット (NIP-51, kind 30000)。非公開のステータスの送り先に使う
+#[derive(Debug, Clone, PartialEq)]
+pub struct FollowSet {
+    pub d_tag: String,
+    pub title: String,
+    pub members: Vec<PublicKey>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DmProtocol {
+    Nip04,
+    Nip17,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub id: EventId,
+    pub sender: PublicKey,
+    pub content: String,
+    pub created_at: Timestamp,
+    pub is_mine: bool,
+    pub protocol: DmProtocol,
+    // 本文の :shortcode: に対応するカスタム絵文字 (NIP-30)
+    #[serde(default)]
+    pub emojis: HashMap<String, String>,
+    // imeta タグ (NIP-92) で添付された画像
+    #[serde(default)]
+    pub images: Vec<String>,
+    #[serde(default)]
+    pub audio_clips: Vec<AudioClip>,
+}
+
+// メッセージに添付された音声
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AudioClip {
+    pub url: String,
+    pub duration_secs: Option<f32>,
+}
+
+// NIP-29 リレーベースのグループ
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GroupInfo {
+    pub id: String,
+    pub relay_url: String,
+    pub name: String,
+    pub picture: String,
+    pub about: String,
+    pub is_private: bool,
+    pub is_closed: bool,
+}
+
+impl GroupInfo {
+    // NIP-29の "<host>'<group-id>" 形式の識別子
+    pub fn key(&self) -> String {
+        format!("{}'{}", self.relay_url, self.id)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupMessage {
+    pub id: EventId,
+    pub sender: PublicKey,
+    pub content: String,
+    pub created_at: Timestamp,
+    pub is_mine: bool,
+}
+
+// NIP-28 パブリックチャンネル
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChannelInfo {
+    pub id: EventId,
+    pub creator: PublicKey,
+    pub name: String,
+    pub about: String,
+    pub picture: String,
+    pub created_at: Timestamp,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChannelMessage {
+    pub id: EventId,
+    pub sender: PublicKey,
+    pub content: String,
+    pub created_at: Timestamp,
+    pub is_mine: bool,
+}
+
+// NIP-72 のコミュニティ (kind 34550)。作成者とモデレーターが投稿を承認する
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommunityInfo {
+    pub coordinate: nostr::nips::nip01::Coordinate,
+    pub name: String,
+    pub description: String,
+    pub image: String,
+    pub moderators: Vec<PublicKey>,
+    pub created_at: Timestamp,
+}
+
+impl CommunityInfo {
+    // 作成者も承認できる
+    pub fn is_moderator(&self, public_key: &PublicKey) -> bool {
+        self.coordinate.public_key == *public_key || self.moderators.contains(public_key)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CommunityPost {
+    pub event: Event,
+    pub approved: bool,
+}
+
+// 非表示にしたチャンネルとミュートしたユーザー(ローカルに保存)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChannelPrefs {
+    #[serde(default)]
+    pub hidden_channels: HashSet<EventId>,
+    #[serde(default)]
+    pub muted_users: HashSet<PublicKey>,
+}
+
+// リンク先のページの OpenGraph の情報
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LinkPreview {
+    pub title: String,
+    pub description: String,
+    pub image: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LinkPreviewState {
+    Loading,
+    Loaded(LinkPreview),
+    // 取得できなかったか、プレビューにできるページではなかった
+    Failed,
+}
+
+// NIP-05 の確認結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Nip05State {
+    Checking,
+    Verified,
+    // .well-known/nostr.json の公開鍵が一致しない
+    Mismatch,
+    // 取得できなかった。キャッシュには残さない
+    Failed,
+}
+
+// 外部アカウントの主張 (NIP-39) の確認結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IdentityState {
+    Checking,
+    Verified,
+    // 証明の投稿がない、または投稿に公開鍵が書かれていない
+    Mismatch,
+    // Twitter と Telegram は投稿を読めないので確かめない
+    Unverifiable,
+    // 取得できなかった。キャッシュには残さない
+    Failed,
 }
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub enum AppTab {
     Home,
+    Notes,
+    // フォローしている人の長文の記事 (NIP-23)
+    Articles,
+    // ブックマークした投稿 (NIP-51)
+    Bookmarks,
+    // 作者を絞らない最近の投稿。設定で有効にしたときだけ表示する
+    Global,
+    Search,
+    Friends,
+    Chats,
+    Groups,
+    Channels,
+    // モデレーター付きのコミュニティ (NIP-72)
+    Communities,
     Relays,
     Wallet,
     Profile,
+    // 他のユーザーのプロフィールページ
+    UserProfile(PublicKey),
+    // hashtag_feed のハッシュタグのフィード
+    Hashtag,
+    Settings,
+    Logs,
+    AuditLog,
+    // リレーとの生のメッセージを見る開発者向けのタブ
+    RelayDebug,
 }
 
+// 実際に表示しているテーマ。色を選ぶときに使う
 #[derive(Debug, PartialEq, Eq, Copy, Clone, Serialize, Deserialize)]
 pub enum AppTheme {
     Light,
     Dark,
+    LineLight,
+    LineDark,
+}
+
+// 画面の文字のフォント。選んだものにない字形はほかのフォントで補う
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default, Serialize, Deserialize)]
+pub enum UiFont {
+    // 同梱の LINE Seed JP
+    #[default]
+    Bundled,
+    // OS に入っている CJK フォント (Noto Sans CJK など)
+    System,
+    // custom_font_path のフォント
+    Custom,
+}
+
+// テーマの配色。ライトとダークのそれぞれにある
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default, Serialize, Deserialize)]
+pub enum ThemePreset {
+    #[default]
+    Classic,
+    // LINE のような白と緑の配色
+    Line,
+}
+
+// 設定で選ぶテーマ。以前の設定の "Light" / "Dark" もそのまま読める
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Serialize, Deserialize)]
+pub enum ThemePreference {
+    Light,
+    Dark,
+    System,
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default, Serialize, Deserialize)]
+pub enum Language {
+    #[default]
+    Japanese,
+    English,
+}
+
+// 画像をアップロードするメディアサーバーの方式
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default, Serialize, Deserialize)]
+pub enum MediaServerKind {
+    #[default]
+    Nip96,
+    Blossom,
+}
+
+// 画像を添付する入力欄
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum AttachTarget {
+    Note,
+    Chat,
+}
+
+// 絵文字ピッカーで絵文字を入れる入力欄
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum EmojiTarget {
+    Status,
+    Note,
+    Chat,
+}
+
+// アプリの設定。設定ディレクトリの settings.json に保存し、起動時に読み込む
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub theme: ThemePreference,
+    pub theme_preset: ThemePreset,
+    pub ui_font: UiFont,
+    pub custom_font_path: String,
+    // 画面全体の拡大率 (egui の pixels_per_point) と本文の文字の大きさ (pt)
+    pub ui_scale: f32,
+    pub font_size: f32,
+    pub language: Language,
+    pub discover_relays: String,
+    pub default_relays: String,
+    pub relay_timeout_secs: u64,
+    // 発見リレーで NIP-65 のリレーリストを探す時間と、リレーに接続してからつながるのを待つ時間
+    pub discovery_timeout_secs: u64,
+    pub connect_wait_secs: u64,
+    // リレーからの取得に失敗したときにやり直す回数
+    pub fetch_retry_count: u32,
+    pub timeline_limit: usize,
+    pub pow_difficulty: u8,
+    // 公開を押してから実際に送るまで待つ秒数。その間は取り消せる。0 ならすぐ送る
+    pub undo_send_secs: u64,
+    // 公開したステータスを OpenTimestamps (NIP-03) で記録する
+    pub opentimestamps_enabled: bool,
+    // NIP-65 とは別に、このアプリだけで使うリレー
+    pub manual_relays: Vec<EditableRelay>,
+    // SOCKS5プロキシ ("host:port")。空欄なら直接接続する
+    pub proxy: String,
+    pub tor_only: bool,
+    // 再生中の曲を music ステータスとして自動で公開する
+    pub now_playing_enabled: bool,
+    // 新規投稿の入力欄の上に並べる定型文
+    pub status_presets: Vec<String>,
+    // 絵文字ピッカーで最近使った絵文字。新しいものが先頭
+    pub recent_emojis: Vec<String>,
+    // 検索タブで使う NIP-50 対応のリレー。空ならキャッシュから探す
+    pub search_relays: String,
+    // フォローしていない人も含めたグローバルフィードのタブを表示する
+    pub global_feed_enabled: bool,
+    // デスクトップ通知を出す種類
+    pub notify_direct_messages: bool,
+    pub notify_mentions: bool,
+    pub notify_starred_statuses: bool,
+    // トークを開いたときに既読を相手に知らせる (NIP-17 のトークのみ)
+    pub send_read_receipts: bool,
+    // メッセージの入力中であることを相手に知らせる
+    pub send_typing_indicators: bool,
+    // 星を付けた友だち。新しいステータスを通知する
+    pub starred_friends: HashSet<PublicKey>,
+    // 添付した画像のアップロード先
+    pub media_server_url: String,
+    pub media_server_kind: MediaServerKind,
+    // 本文中の画像とリンクのプレビューを表示する
+    pub show_media_previews: bool,
+    // 本文の **太字** や `コード` などの書式を表示する。切ると書いたままの本文を表示する
+    pub render_markdown: bool,
+    // この語を含む投稿はタイムラインに表示しない。1行に1つ
+    pub muted_words: String,
+    // ラベル (NIP-32) を参考にする人。npub か hex で1行に1つ。自分が付けたラベルはいつも使う
+    pub trusted_labelers: String,
+    // このラベルが付いた投稿は表示しない・本文を隠す。1行に1つ
+    pub hidden_labels: String,
+    pub collapsed_labels: String,
+    // フォロー中の人とつながりのない人の返信・グローバルの投稿を畳み、トークはリクエストに分ける
+    pub wot_enabled: bool,
+    // フォロー中の人のうち、この人数以上がフォローしていれば知り合いとして扱う
+    pub wot_min_follows: u32,
+    // 端末間で共有する設定 (settings_sync) を最後に変えた時刻と、最後にリレーと合わせた時刻 (UNIX 秒)
+    pub synced_settings_updated_at: u64,
+    pub synced_settings_synced_at: u64,
+    // 操作がないままこの分数が過ぎたら、鍵をメモリから消してパスフレーズの入力に戻る。0 なら自動ではロックしない
+    pub auto_lock_minutes: u64,
+    // 署名する前に内容を確認する。auto_approve_kinds の kind は確認せずに署名する
+    pub confirm_before_signing: bool,
+    pub auto_approve_kinds: BTreeSet<u16>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            theme: ThemePreference::Light,
+            theme_preset: ThemePreset::Classic,
+            ui_font: UiFont::Bundled,
+            custom_font_path: String::new(),
+            ui_scale: 1.2,
+            font_size: 13.0,
+            language: Language::Japanese,
+            discover_relays: "wss://purplepag.es\nwss://directory.yabu.me".to_string(),
+            default_relays: "wss://relay.damus.io\nwss://relay.nostr.wirednet.jp\nwss://yabu.me".to_string(),
+            relay_timeout_secs: 10,
+            discovery_timeout_secs: 10,
+            connect_wait_secs: 2,
+            fetch_retry_count: 1,
+            timeline_limit: 20,
+            pow_difficulty: 0,
+            undo_send_secs: 0,
+            opentimestamps_enabled: false,
+            manual_relays: Vec::new(),
+            proxy: String::new(),
+            tor_only: false,
+            now_playing_enabled: false,
+            status_presets: vec!["会議中".to_string(), "お昼休み".to_string(), "AFK".to_string()],
+            recent_emojis: Vec::new(),
+            search_relays: "wss://relay.nostr.band\nwss://search.nos.today".to_string(),
+            global_feed_enabled: false,
+            notify_direct_messages: true,
+            notify_mentions: true,
+            notify_starred_statuses: true,
+            send_read_receipts: true,
+            send_typing_indicators: true,
+            starred_friends: HashSet::new(),
+            media_server_url: "https://nostr.build".to_string(),
+            media_server_kind: MediaServerKind::Nip96,
+            show_media_previews: true,
+            render_markdown: true,
+            muted_words: String::new(),
+            trusted_labelers: String::new(),
+            hidden_labels: "spam".to_string(),
+            collapsed_labels: "nsfw".to_string(),
+            wot_enabled: true,
+            wot_min_follows: 1,
+            synced_settings_updated_at: 0,
+            synced_settings_synced_at: 0,
+            auto_lock_minutes: 0,
+            confirm_before_signing: false,
+            auto_approve_kinds: BTreeSet::new(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn relay_timeout(&self) -> Duration {
+        Duration::from_secs(self.relay_timeout_secs)
+    }
+
+    // 本文にミュートする語が含まれているか。大文字と小文字は区別しない
+    pub fn is_muted(&self, content: &str) -> bool {
+        let content = content.to_lowercase();
+        self.muted_words
+            .lines()
+            .map(str::trim)
+            .filter(|word| !word.is_empty())
+            .any(|word| content.contains(&word.to_lowercase()))
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
@@ -123,41 +945,128 @@ pub enum StatusType {
     General,
     Music,
     Podcast,
+    Custom,
+}
+
+// タイムラインでまとめて表示するステータスの種類
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum StatusGroup {
+    General,
+    Music,
+    Other,
+}
+
+impl StatusGroup {
+    pub fn from_d_tag(d_tag: &str) -> Self {
+        match d_tag {
+            "general" => StatusGroup::General,
+            "music" => StatusGroup::Music,
+            _ => StatusGroup::Other,
+        }
+    }
+
+    pub fn icon(&self) -> &'static str {
+        match self {
+            StatusGroup::General => "💬",
+            StatusGroup::Music => "🎵",
+            StatusGroup::Other => "🏷",
+        }
+    }
+}
+
+// ステータスの有効期限 (NIP-40)
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum StatusExpiry {
+    Never,
+    Minutes30,
+    Hour1,
+    Hours4,
+    Custom,
+}
+
+impl StatusExpiry {
+    // 期限までの時間。Custom は分単位の入力を使う
+    pub fn duration(&self, custom_minutes: u64) -> Option<Duration> {
+        match self {
+            StatusExpiry::Never => None,
+            StatusExpiry::Minutes30 => Some(Duration::from_secs(30 * 60)),
+            StatusExpiry::Hour1 => Some(Duration::from_secs(60 * 60)),
+            StatusExpiry::Hours4 => Some(Duration::from_secs(4 * 60 * 60)),
+            StatusExpiry::Custom => Some(Duration::from_secs(custom_minutes * 60)),
+        }
+    }
 }
 
 impl AppTheme {
+    pub fn new(preset: ThemePreset, dark: bool) -> Self {
+        match (preset, dark) {
+            (ThemePreset::Classic, false) => AppTheme::Light,
+            (ThemePreset::Classic, true) => AppTheme::Dark,
+            (ThemePreset::Line, false) => AppTheme::LineLight,
+            (ThemePreset::Line, true) => AppTheme::LineDark,
+        }
+    }
+
     pub fn card_background_color(&self) -> egui::Color32 {
         match self {
             AppTheme::Light => egui::Color32::from_white_alpha(250),
             AppTheme::Dark => egui::Color32::from_rgb(44, 44, 46),
+            AppTheme::LineLight => egui::Color32::from_rgb(247, 248, 250),
+            AppTheme::LineDark => egui::Color32::from_rgb(34, 36, 35),
         }
     }
 
     pub fn text_color(&self) -> egui::Color32 {
         match self {
-            AppTheme::Light => egui::Color32::BLACK,
-            AppTheme::Dark => egui::Color32::WHITE,
+            AppTheme::Light | AppTheme::LineLight => egui::Color32::BLACK,
+            AppTheme::Dark | AppTheme::LineDark => egui::Color32::WHITE,
+        }
+    }
+
+    pub fn my_bubble_color(&self) -> egui::Color32 {
+        match self {
+            AppTheme::Light => egui::Color32::from_rgb(140, 230, 110),
+            AppTheme::Dark => egui::Color32::from_rgb(50, 120, 60),
+            AppTheme::LineLight => egui::Color32::from_rgb(133, 226, 73),
+            AppTheme::LineDark => egui::Color32::from_rgb(4, 150, 64),
+        }
+    }
+
+    pub fn peer_bubble_color(&self) -> egui::Color32 {
+        match self {
+            AppTheme::Light => egui::Color32::from_gray(235),
+            AppTheme::Dark => egui::Color32::from_rgb(58, 58, 60),
+            AppTheme::LineLight => egui::Color32::from_gray(238),
+            AppTheme::LineDark => egui::Color32::from_gray(48),
         }
     }
 
     pub fn danger_zone_background_color(&self) -> egui::Color32 {
         match self {
-            AppTheme::Light => egui::Color32::from_rgb(255, 235, 238),
-            AppTheme::Dark => egui::Color32::from_rgb(60, 40, 40),
+            AppTheme::Light | AppTheme::LineLight => egui::Color32::from_rgb(255, 235, 238),
+            AppTheme::Dark | AppTheme::LineDark => egui::Color32::from_rgb(60, 40, 40),
         }
     }
 
     pub fn danger_zone_stroke_color(&self) -> egui::Color32 {
         match self {
-            AppTheme::Light => egui::Color32::from_rgb(255, 180, 180),
-            AppTheme::Dark => egui::Color32::from_rgb(120, 60, 60),
+            AppTheme::Light | AppTheme::LineLight => egui::Color32::from_rgb(255, 180, 180),
+            AppTheme::Dark | AppTheme::LineDark => egui::Color32::from_rgb(120, 60, 60),
         }
     }
 }
 
+// 書き出すために表示している秘密鍵。hide_at を過ぎたら消す
+pub struct ExportedSecretKey {
+    pub nsec: zeroize::Zeroizing<String>,
+    pub hide_at: Instant,
+}
+
 pub struct NostrStatusAppInternal {
     pub nwc_uri_input: String,
     pub cache_db: LmdbCache,
+    // 画像のキャッシュ。cache_db とは別に OS のキャッシュディレクトリに置く
+    pub image_cache_db: LmdbCache,
     pub is_logged_in: bool,
     pub status_message_input: String,
     pub show_post_dialog: bool,
@@ -166,7 +1075,24 @@ pub struct NostrStatusAppInternal {
     pub secret_key_input: String,
     pub passphrase_input: String,
     pub confirm_passphrase_input: String,
+    pub generated_keys: Option<Keys>,
+    pub generated_key_backed_up: bool,
+    // 新しい鍵のシードフレーズ (NIP-06)。確認の単語の位置が空のあいだは単語を表示する
+    pub generated_mnemonic: Option<zeroize::Zeroizing<String>>,
+    pub mnemonic_verify_positions: Vec<usize>,
+    pub mnemonic_verify_inputs: Vec<String>,
+    // シードフレーズから取り込むときの BIP-39 パスフレーズとアカウント番号
+    pub mnemonic_passphrase_input: String,
+    pub mnemonic_account_input: u32,
+    pub new_profile_name_input: String,
+    pub key_storage: KeyStorage,
+    pub key_storage_error: Option<String>,
+    pub bunker_uri_input: String,
+    pub remote_signer_status: Option<String>,
+    pub bunker_auth_url: Option<String>,
     pub current_status_type: StatusType,
+    pub status_expiry: StatusExpiry,
+    pub custom_expiry_minutes: u64,
     pub show_music_dialog: bool,
     pub music_track_input: String,
     pub music_url_input: String,
@@ -174,22 +1100,184 @@ pub struct NostrStatusAppInternal {
     pub podcast_episode_input: String,
     pub podcast_url_input: String,
     pub nostr_client: Option<Client>,
-    pub my_keys: Option<Keys>,
+    pub my_signer: Option<AppSigner>,
     pub followed_pubkeys: HashSet<PublicKey>,
-    pub followed_pubkeys_display: String,
+    pub selected_friend: Option<PublicKey>,
+    pub follow_pubkey_input: String,
+    pub follow_error: Option<String>,
+    pub show_scan_qr: bool,
+    pub scan_qr_input: String,
+    pub scan_qr_image_input: String,
+    pub is_decoding_qr: bool,
+    // カメラで読み取っている間だけある。捨てるとカメラも止まる
+    pub camera_scan: Option<CameraScan>,
+    pub scanned_profile: Option<(PublicKey, Option<RelayUrl>)>,
+    pub profiles: HashMap<PublicKey, ProfileMetadata>,
     pub timeline_posts: Vec<TimelinePost>,
     pub should_repaint: bool,
     pub is_loading: bool,
+    // ログイン後にタイムラインなどを読み込んでいる間の進み具合。読み込み終えたら None
+    pub login_stage: Option<String>,
     pub current_tab: AppTab,
-    pub connected_relays_display: String,
+    pub relay_status_rows: Vec<RelayStatusRow>,
+    pub relay_monitor_started: bool,
+    pub relay_supervisor_started: bool,
+    pub outbox: Vec<OutboxEntry>,
+    pub outbox_worker_started: bool,
+    pub now_playing_worker_started: bool,
+    pub timestamp_proofs: Vec<TimestampProof>,
+    pub opentimestamps_worker_started: bool,
+    // 信頼する人が投稿やユーザーに付けたラベル (NIP-32)。確認済みの投稿とユーザーは checked に入れる
+    pub event_labels: HashMap<EventId, BTreeSet<String>>,
+    pub pubkey_labels: HashMap<PublicKey, BTreeSet<String>>,
+    pub labels_checked_events: HashSet<EventId>,
+    pub labels_checked_pubkeys: HashSet<PublicKey>,
+    pub label_worker_started: bool,
+    // フォロー中の人のフォローリストから数えた、その人をフォローしているフォロー中の人の数
+    pub wot_follow_counts: HashMap<PublicKey, u32>,
+    pub wot_loaded: bool,
+    pub wot_worker_started: bool,
+    // グローバルで開いた、つながりのない人の投稿
+    pub revealed_stranger_posts: HashSet<EventId>,
+    // 予約した公開・消去と、それを実行するタスク
+    pub scheduled_statuses: Vec<ScheduledStatus>,
+    pub next_scheduled_id: u64,
+    pub scheduler_started: bool,
+    pub schedule_enabled: bool,
+    pub schedule_time_input: String,
+    pub auto_clear_enabled: bool,
+    pub auto_clear_hours: u64,
+    pub new_preset_input: String,
+    // ホームのノート (kind 1) の入力欄
+    pub note_input: String,
+    pub is_posting_note: bool,
+    // 投稿に付ける注意書き (NIP-36)。ノートとステータスで別々に持つ
+    pub note_content_warning: bool,
+    pub note_content_warning_reason: String,
+    pub status_content_warning: bool,
+    pub status_content_warning_reason: String,
+    // 非公開のステータスとして、選んだフォローセットの人だけに送る
+    pub status_private: bool,
+    pub follow_sets: Vec<FollowSet>,
+    pub selected_follow_set: Option<String>,
+    pub is_loading_follow_sets: bool,
+    // 注意書きを押して本文を表示した投稿
+    pub revealed_content_warnings: HashSet<EventId>,
+    // ノートタブのノート (kind 1) と返信の入力
+    pub notes: Vec<Event>,
+    pub notes_loaded: bool,
+    pub is_loading_notes: bool,
+    // ブックマーク (NIP-51, kind 10003) と Keep タブの投稿
+    pub bookmarked_ids: Vec<EventId>,
+    pub bookmarks_requested: bool,
+    pub bookmark_events: Vec<Event>,
+    pub bookmark_events_loaded: bool,
+    pub is_loading_bookmarks: bool,
+    pub is_updating_bookmark: bool,
+    pub bookmark_kind_filter: Option<Kind>,
+    // 記事タブの記事 (NIP-23, kind 30023) と、開いて読んでいる記事
+    pub articles: Vec<Event>,
+    pub articles_loaded: bool,
+    pub is_loading_articles: bool,
+    pub open_article: Option<Event>,
+    pub is_loading_article: bool,
+    pub pins: PinnedItems,
+    pub pins_requested: bool,
+    pub pins_loaded: bool,
+    // リレーと合わせた共有の設定。ログインしてから合わせ終わるまでは None
+    pub synced_settings: Option<SyncedSettings>,
+    pub settings_sync_started: bool,
+    // このアプリに保存したアカウントと、切り替えの入力
+    pub accounts: Vec<AccountSummary>,
+    pub selected_login_account: usize,
+    pub adding_account: bool,
+    pub switch_account_target: Option<usize>,
+    pub switch_passphrase_input: String,
+    pub is_switching_account: bool,
+    pub secondary_account: Option<SecondaryAccount>,
+    // 最後にキーやマウスの操作があった時刻。自動ロックに使う
+    pub last_activity: Instant,
+    // 最後に保存した下書きと、その時刻。変わったものだけを数秒ごとに保存する
+    pub saved_drafts: Drafts,
+    pub drafts_saved_at: Instant,
+    // ロックしてログイン画面に戻ったところ
+    pub is_locked: bool,
+    // ログアウトやロックのたびに増える番号。それより前に始まった読み込みの結果は捨てる
+    pub session_generation: u64,
+    // 署名の確認ダイアログで「この種類は今後確認しない」を選んでいる
+    pub sign_always_allow_kind: bool,
+    // 設定画面で確認せずに署名する kind を足す入力欄
+    pub auto_approve_kind_input: String,
+    // パスフレーズの変更ダイアログ
+    pub show_passphrase_dialog: bool,
+    pub old_passphrase_input: String,
+    pub new_passphrase_input: String,
+    pub confirm_new_passphrase_input: String,
+    pub is_changing_passphrase: bool,
+    pub passphrase_change_error: Option<String>,
+    // 秘密鍵の書き出しダイアログ
+    pub show_export_key_dialog: bool,
+    pub export_key_passphrase_input: String,
+    pub is_exporting_key: bool,
+    pub export_key_error: Option<String>,
+    pub exported_secret_key: Option<ExportedSecretKey>,
+    pub is_saving_profile_card: bool,
+    pub reply_target: Option<Event>,
+    pub reply_input: String,
+    pub quote_target: Option<QuoteTarget>,
+    pub quote_input: String,
+    // 通報のダイアログ
+    pub report_target: Option<ReportTarget>,
+    pub report_reason: nostr::nips::nip56::Report,
+    pub report_comment: String,
+    pub is_sending_report: bool,
+    // バッジを贈るダイアログ。贈る相手と、選べる自分のバッジ (読み込み中は None)
+    pub badge_award_target: Option<PublicKey>,
+    pub my_badge_definitions: Option<Vec<Badge>>,
+    pub badge_award_selected: Option<usize>,
+    pub is_awarding_badge: bool,
+    // nostr: 参照から開いたプロフィール・投稿の表示
+    pub link_view: Option<NostrLink>,
+    pub link_view_event: Option<Event>,
+    pub is_loading_link_view: bool,
+    // グローバルフィード
+    pub global_events: Vec<Event>,
+    pub global_feed_started: bool,
+    // 検索タブ
+    pub search_query: String,
+    pub search_results: Option<SearchResults>,
+    pub is_searching: bool,
+    // 開いているハッシュタグのフィード
+    pub hashtag_feed: Option<String>,
+    pub hashtag_events: Vec<Event>,
+    pub is_loading_hashtag_feed: bool,
+    // 開いている他のユーザーのプロフィールページ
+    pub user_profile: Option<UserProfileData>,
+    pub is_loading_user_profile: bool,
+    pub status_history: Vec<StatusHistoryEntry>,
+    // d タグごとの今のステータス
+    pub current_statuses: BTreeMap<String, StatusHistoryEntry>,
+    pub custom_d_tag_input: String,
+    // タイムラインに表示する種類。None ならすべて
+    pub timeline_status_filter: Option<StatusGroup>,
     pub nip01_profile_display: String,
     pub editable_profile: ProfileMetadata,
+    // プロフィールの i タグ (NIP-39)。本文の JSON とは別に編集して保存する
+    pub my_identities: Vec<Identity>,
     pub profile_fetch_status: String,
     pub nip65_relays: Vec<EditableRelay>,
-    pub discover_relays_editor: String,
-    pub default_relays_editor: String,
+    pub settings: Settings,
+    pub settings_status: Option<String>,
+    pub manual_relay_input: String,
+    pub manual_relay_error: Option<String>,
+    pub relay_list_status: Option<String>,
     pub current_theme: AppTheme,
     pub image_cache: HashMap<String, ImageState>,
+    // "公開鍵:NIP-05" ごとの確認結果
+    pub nip05_cache: HashMap<String, Nip05State>,
+    pub identity_cache: HashMap<String, IdentityState>,
+    // URL ごとのリンクのプレビュー
+    pub link_previews: HashMap<String, LinkPreviewState>,
 
     // NWC
     pub nwc_passphrase_input: String,
@@ -199,8 +1287,127 @@ pub struct NostrStatusAppInternal {
     pub zap_history: Vec<ZapReceipt>,
     pub zap_history_fetch_status: String,
     pub is_fetching_zap_history: bool,
+    // DM
+    pub conversations: HashMap<PublicKey, Vec<ChatMessage>>,
+    pub read_state: ReadState,
+    // 相手から届いた既読。この時刻までの自分のメッセージを既読と表示する
+    pub dm_peer_read_at: HashMap<PublicKey, Timestamp>,
+    // 相手から入力中のイベントが最後に届いた時刻
+    pub dm_peer_typing: HashMap<PublicKey, Instant>,
+    pub show_chat_emoji_picker: bool,
+    // 絵文字ピッカーの検索欄
+    pub emoji_search_input: String,
+    // 画像の添付。開いている入力欄と、このセッションでアップロードしたファイル (URL ごと)
+    pub attach_target: Option<AttachTarget>,
+    pub attach_path_input: String,
+    pub is_uploading_media: bool,
+    // 録音中のボイスメッセージ。捨てると録音も止まる
+    pub voice_recording: Option<Recording>,
+    pub uploaded_media: HashMap<String, UploadedMedia>,
+    // 音声通話。合図は DM の購読で受け取り、音声はバックエンドに任せる
+    pub call: Option<CallSession>,
+    pub call_backend: Arc<dyn CallMediaBackend>,
+    // 最後に入力中を知らせた相手と時刻。送りすぎないように間隔をあける
+    pub last_typing_sent: Option<(PublicKey, Instant)>,
+    // ウィンドウのタイトルに出している未読数
+    pub window_title_unread: usize,
+    pub selected_conversation: Option<PublicKey>,
+    pub dm_message_input: String,
+    pub new_chat_pubkey_input: String,
+    pub dm_listener_started: bool,
+    pub notification_listener_started: bool,
+    // ウィンドウを見ているときはデスクトップ通知を出さない
+    pub window_focused: bool,
+    pub is_sending_dm: bool,
+    pub dm_error: Option<String>,
+    // グループ (NIP-29)
+    pub group_relay_input: String,
+    pub group_directory: Vec<GroupInfo>,
+    pub joined_groups: Vec<GroupInfo>,
+    pub joined_groups_loaded: bool,
+    pub selected_group: Option<GroupInfo>,
+    pub group_messages: HashMap<String, Vec<GroupMessage>>,
+    pub group_members: HashMap<String, Vec<PublicKey>>,
+    pub group_message_input: String,
+    pub group_client: Option<Client>,
+    pub group_listener_key: Option<String>,
+    pub is_loading_groups: bool,
+    pub is_sending_group_message: bool,
+    pub group_error: Option<String>,
+    // チャンネル (NIP-28)
+    pub channels: Vec<ChannelInfo>,
+    pub channels_loaded: bool,
+    pub selected_channel: Option<ChannelInfo>,
+    pub channel_messages: HashMap<EventId, Vec<ChannelMessage>>,
+    pub channel_message_input: String,
+    pub channel_listener_key: Option<EventId>,
+    pub channel_prefs: ChannelPrefs,
+    pub show_hidden_channels: bool,
+    pub new_channel_name_input: String,
+    pub new_channel_about_input: String,
+    pub is_loading_channels: bool,
+    pub is_sending_channel_message: bool,
+    pub channel_error: Option<String>,
+    // コミュニティ (NIP-72)。投稿は選んでいるコミュニティのものだけを持つ
+    pub communities: Vec<CommunityInfo>,
+    pub communities_loaded: bool,
+    pub is_loading_communities: bool,
+    pub selected_community: Option<CommunityInfo>,
+    pub community_posts: Vec<CommunityPost>,
+    pub community_posts_key: Option<String>,
+    pub is_loading_community_posts: bool,
+    // モデレーターのときだけ、承認待ちの投稿も表示できる
+    pub community_show_pending: bool,
+    pub community_post_input: String,
+    pub is_posting_community: bool,
     // ZAP
     pub show_zap_dialog: bool,
     pub zap_amount_input: String,
-    pub zap_target_post: Option<TimelinePost>,
+    pub zap_target: Option<ZapTarget>,
+    pub zap_invoice: Option<String>,
+    pub is_fetching_zap_invoice: bool,
+    pub is_paying_zap_invoice: bool,
+    pub zap_error: Option<String>,
+    pub zap_totals: HashMap<EventId, u64>,
+    pub counted_zap_receipts: HashSet<EventId>,
+    pub zap_listener_started: bool,
+    pub default_zap_amount_input: String,
+    pub pending_zaps: HashSet<EventId>,
+    pub zap_status_message: Option<String>,
+    // 画面に出す通知。last_error は閉じるまでバナーとして残す
+    pub last_error: Option<String>,
+    pub toasts: Vec<Toast>,
+    pub held_sends: Vec<HeldSend>,
+    pub pow_jobs: Vec<PowJob>,
+    // ログタブで表示する最も詳しいレベル
+    pub log_level: tracing::Level,
+    // 署名の記録タブに出す記録。タブを開いたときに読み込む
+    pub audit_entries: Vec<crate::audit_log::AuditEntry>,
+    pub audit_entries_loaded: bool,
+    // リレー通信タブの絞り込み。None ならすべて表示する
+    pub relay_debug_relay_filter: Option<String>,
+    pub relay_debug_type_filter: Option<String>,
+    pub relay_debug_search: String,
+}
+
+impl NostrStatusAppInternal {
+    // 処理の結果を通知として表示する
+    pub fn notify(&mut self, message: impl Into<String>) {
+        self.toasts.push(Toast::new(ToastKind::Info, message.into()));
+        self.should_repaint = true;
+    }
+
+    // 失敗を通知し、バナーにも残す
+    pub fn report_error(&mut self, message: impl Into<String>) {
+        let message = message.into();
+        self.toasts.push(Toast::new(ToastKind::Error, message.clone()));
+        self.last_error = Some(message);
+        self.should_repaint = true;
+    }
+
+    // session_generation の番号で始めた読み込みや常駐タスクが、まだ同じログインの中にいるか
+    // 同じアカウントでロックを解除し直しても番号は変わるので、前のログインのタスクはここで終わらせる
+    pub fn is_current_session(&self, session: u64) -> bool {
+        self.session_generation == session
+    }
 }
@@ -1,32 +1,152 @@
 pub mod login_view;
+pub mod account_switcher;
 pub mod home_view;
+pub mod notes_view;
+pub mod articles_view;
+pub mod bookmarks_view;
+pub mod global_view;
+pub mod search_view;
+pub mod friends_view;
+pub mod chats_view;
+pub mod call_view;
+pub mod groups_view;
+pub mod channels_view;
+pub mod communities_view;
 pub mod relays_view;
 pub mod profile_view;
 pub mod wallet_view;
+pub mod settings_view;
+pub mod logs_view;
+pub mod audit_log_view;
+pub mod relay_debug_view;
+pub mod link_view;
+pub mod link_preview;
+pub mod markdown;
+pub mod user_profile_view;
+pub mod hashtag_view;
+pub mod mention;
+pub mod report_dialog;
+pub mod badge_dialog;
+pub mod sign_dialog;
+pub mod content_warning;
+pub mod attachment;
+pub mod emoji;
+pub mod emoji_picker;
 pub mod image_cache;
+pub mod identicon;
+pub mod nip05_badge;
+pub mod identity_badge;
+pub mod signature_badge;
 pub mod zap;
+pub mod qr;
+pub mod toast;
+pub mod timestamp;
+pub mod timestamp_proof_badge;
 
 use eframe::egui::{self, Margin};
+use std::time::{Duration, Instant};
+use tracing::{error, info};
 // nostr v0.43.0 / nostr-sdk: RelayMetadata は nostr_sdk::nips::nip65 に移動したため import する
 use crate::{
+    i18n::tr,
     NostrStatusApp,
-    theme::{dark_visuals, light_visuals},
+    WINDOW_TITLE,
+    accounts,
+    bookmarks,
+    desktop_notify,
+    dm,
+    drafts,
+    labels,
+    now_playing,
+    opentimestamps,
+    outbox,
+    pins,
+    read_state,
+    relay_monitor,
+    scheduler,
+    settings::save_settings,
+    settings_sync,
+    theme,
+    tray,
     types::*,
+    wot,
 };
 
+// 終了時の後始末を待つ上限。送れなかったものは送信キューに残っているので、次回に再送する
+const SHUTDOWN_TIMEOUT_SECS: u64 = 5;
+
 impl eframe::App for NostrStatusApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         let mut app_data = self.data.lock().unwrap();
 
-        let home_tab_text = "ホーム";
-        let relays_tab_text = "リレー";
-        let wallet_tab_text = "ウォレット";
-        let profile_tab_text = "プロフィール";
+        let home_tab_text = tr!("ホーム");
+        let notes_tab_text = tr!("ノート");
+        let articles_tab_text = tr!("記事");
+        let bookmarks_tab_text = "Keep";
+        let search_tab_text = tr!("検索");
+        let global_tab_text = tr!("グローバル");
+        let friends_tab_text = tr!("友だち");
+        let chats_tab_text = tr!("トーク");
+        let groups_tab_text = tr!("グループ");
+        let channels_tab_text = tr!("チャンネル");
+        let communities_tab_text = tr!("コミュニティ");
+        let relays_tab_text = tr!("リレー");
+        let wallet_tab_text = tr!("ウォレット");
+        let profile_tab_text = tr!("プロフィール");
+        let settings_tab_text = tr!("設定");
+        let logs_tab_text = tr!("ログ");
+        let audit_log_tab_text = tr!("署名の記録");
+        let relay_debug_tab_text = tr!("リレー通信");
+        let lock_now_hover_text = tr!("今すぐロック");
+        let light_theme_hover_text = tr!("テーマ: ライト");
+        let dark_theme_hover_text = tr!("テーマ: ダーク");
+        let system_theme_hover_text = tr!("テーマ: システムに合わせる");
+
+        // 「システム」のテーマは OS の設定に合わせて変わるので、毎回表示中のテーマを確かめる
+        app_data.current_theme = theme::current_theme(ctx, app_data.settings.theme_preset);
+
+        // 最小化・非アクティブのときだけデスクトップ通知を出す
+        app_data.window_focused = ctx.input(|i| i.viewport().focused.unwrap_or(true));
+
+        // 未読のトークがあればタスクバーから分かるようにウィンドウのタイトルに件数を出す
+        let unread_dms = read_state::total_unread_dm_count(&app_data);
+        if unread_dms != app_data.window_title_unread {
+            app_data.window_title_unread = unread_dms;
+            let title = if unread_dms > 0 { format!("({unread_dms}) {WINDOW_TITLE}") } else { WINDOW_TITLE.to_string() };
+            ctx.send_viewport_cmd(egui::ViewportCommand::Title(title));
+        }
+        if let Some(tray) = &mut self.tray {
+            tray.update(tray::TrayState {
+                unread_dms,
+                status_presets: app_data.settings.status_presets.clone(),
+                can_publish: app_data.is_logged_in && !app_data.is_loading,
+            });
+        }
 
         // app_data_arc をクローンして非同期タスクに渡す
         let app_data_arc_clone = self.data.clone();
         let runtime_handle = self.runtime.handle().clone();
 
+        // 操作がないまま決めた時間が過ぎたら、鍵を消してロックする
+        if ctx.input(|i| !i.events.is_empty() || i.pointer.is_moving()) {
+            app_data.last_activity = Instant::now();
+        }
+        let has_keys = app_data.is_logged_in || app_data.secondary_account.is_some();
+        if has_keys && app_data.settings.auto_lock_minutes > 0 {
+            let timeout = Duration::from_secs(app_data.settings.auto_lock_minutes * 60);
+            let idle = app_data.last_activity.elapsed();
+            if idle >= timeout {
+                info!("Locking after {} minutes without input", app_data.settings.auto_lock_minutes);
+                accounts::lock(&mut app_data, &runtime_handle);
+            } else {
+                // 操作がなければ再描画されないので、ロックする時刻に起こしてもらう
+                ctx.request_repaint_after(timeout - idle);
+            }
+        }
+
+        // 落ちても入力途中の投稿が残るよう、下書きをこまめに保存する
+        drafts::autosave(&mut app_data, ctx);
+
         let panel_frame = egui::Frame::default()
             .inner_margin(Margin::same(15))
             .fill(ctx.style().visuals.panel_fill);
@@ -38,30 +158,95 @@ impl eframe::App for NostrStatusApp {
                 ui.add_space(5.0);
 
                 ui.horizontal(|ui| {
-                    ui.heading("なう");
+                    ui.heading(tr!("なう"));
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                        let (icon, new_theme) = match app_data.current_theme {
-                            AppTheme::Light => ("☀️", AppTheme::Dark),
-                            AppTheme::Dark => ("🌙", AppTheme::Light),
+                        if app_data.is_logged_in && ui.button("🔒").on_hover_text(lock_now_hover_text).clicked() {
+                            accounts::lock(&mut app_data, &runtime_handle);
+                        }
+                        // ライト → ダーク → システム の順に切り替える
+                        let (icon, hover_text, new_theme) = match app_data.settings.theme {
+                            ThemePreference::Light => ("☀️", light_theme_hover_text, ThemePreference::Dark),
+                            ThemePreference::Dark => ("🌙", dark_theme_hover_text, ThemePreference::System),
+                            ThemePreference::System => ("🖥", system_theme_hover_text, ThemePreference::Light),
                         };
-                        if ui.button(icon).clicked() {
-                            app_data.current_theme = new_theme;
-                            let new_visuals = match new_theme {
-                                AppTheme::Light => light_visuals(),
-                                AppTheme::Dark => dark_visuals(),
-                            };
-                            ctx.set_visuals(new_visuals);
+                        if ui.button(icon).on_hover_text(hover_text).clicked() {
+                            app_data.settings.theme = new_theme;
+                            if let Err(e) = save_settings(&app_data.settings) {
+                                error!("Failed to save settings: {e}");
+                            }
+                            settings_sync::sync_if_changed(&mut app_data, self.data.clone(), &runtime_handle);
+                            theme::apply_theme_preference(ctx, new_theme);
                         }
                     });
                 });
 
+                if app_data.is_logged_in {
+                    ui.add_space(10.0);
+                    account_switcher::draw_account_switcher(ui, &mut app_data, self.data.clone(), &runtime_handle);
+                }
+
                 ui.add_space(15.0);
 
                 ui.with_layout(egui::Layout::top_down_justified(egui::Align::LEFT), |ui| {
                     ui.style_mut().spacing.item_spacing.y = 12.0; // ボタン間の垂直スペース
 
-                    ui.selectable_value(&mut app_data.current_tab, AppTab::Home, home_tab_text);
+                    let home_label = read_state::with_badge(home_tab_text, read_state::unread_home_count(&app_data));
+                    ui.selectable_value(&mut app_data.current_tab, AppTab::Home, home_label);
                     if app_data.is_logged_in {
+                        let notes_label = read_state::with_badge(notes_tab_text, read_state::unread_mention_count(&app_data));
+                        ui.selectable_value(
+                            &mut app_data.current_tab,
+                            AppTab::Notes,
+                            notes_label,
+                        );
+                        ui.selectable_value(
+                            &mut app_data.current_tab,
+                            AppTab::Articles,
+                            articles_tab_text,
+                        );
+                        ui.selectable_value(
+                            &mut app_data.current_tab,
+                            AppTab::Bookmarks,
+                            bookmarks_tab_text,
+                        );
+                        if app_data.settings.global_feed_enabled {
+                            ui.selectable_value(
+                                &mut app_data.current_tab,
+                                AppTab::Global,
+                                global_tab_text,
+                            );
+                        }
+                        ui.selectable_value(
+                            &mut app_data.current_tab,
+                            AppTab::Search,
+                            search_tab_text,
+                        );
+                        ui.selectable_value(
+                            &mut app_data.current_tab,
+                            AppTab::Friends,
+                            friends_tab_text,
+                        );
+                        let chats_label = read_state::with_badge(chats_tab_text, unread_dms);
+                        ui.selectable_value(
+                            &mut app_data.current_tab,
+                            AppTab::Chats,
+                            chats_label,
+                        );
+                        ui.selectable_value(
+                            &mut app_data.current_tab,
+                            AppTab::Groups,
+                            groups_tab_text,
+                        );
+                        ui.selectable_value(
+                            &mut app_data.current_tab,
+                            AppTab::Channels,
+                            channels_tab_text,
+                        );
+                        ui.selectable_value(
+                            &mut app_data.current_tab,
+                            AppTab::Communities,
+                            communities_tab_text,
+                        );
                         ui.selectable_value(
                             &mut app_data.current_tab,
                             AppTab::Relays,
@@ -78,13 +263,17 @@ impl eframe::App for NostrStatusApp {
                             profile_tab_text,
                         );
                     }
+                    ui.selectable_value(&mut app_data.current_tab, AppTab::Settings, settings_tab_text);
+                    ui.selectable_value(&mut app_data.current_tab, AppTab::Logs, logs_tab_text);
+                    ui.selectable_value(&mut app_data.current_tab, AppTab::AuditLog, audit_log_tab_text);
+                    ui.selectable_value(&mut app_data.current_tab, AppTab::RelayDebug, relay_debug_tab_text);
                 });
 
                 if app_data.is_logged_in {
                     ui.add_space(20.0);
 
                     // --- 投稿ボタン ---
-                    let post_button_text = egui::RichText::new("投稿する").size(14.0).strong();
+                    let post_button_text = egui::RichText::new(tr!("投稿する")).size(14.0).strong();
                     let button = egui::Button::new(post_button_text)
                         .min_size(egui::vec2(ui.available_width(), 40.0))
                         .corner_radius(egui::CornerRadius::from(8.0));
@@ -99,16 +288,55 @@ impl eframe::App for NostrStatusApp {
             .frame(panel_frame)
             .show(ctx, |ui| {
 
+            toast::draw_error_banner(ui, &mut app_data);
+
             // ui.add_enabled_ui(!app_data.is_loading, |ui| { // この行を削除
                 if !app_data.is_logged_in {
-                    if app_data.current_tab == AppTab::Home {
-                        login_view::draw_login_view(ui, &mut app_data, app_data_arc_clone, runtime_handle);
+                    match app_data.current_tab {
+                        AppTab::Settings => settings_view::draw_settings_view(ui, ctx, &mut app_data, app_data_arc_clone, runtime_handle),
+                        AppTab::Logs => logs_view::draw_logs_view(ui, ctx, &mut app_data),
+                        AppTab::AuditLog => audit_log_view::draw_audit_log_view(ui, ctx, &mut app_data),
+                        AppTab::RelayDebug => relay_debug_view::draw_relay_debug_view(ui, ctx, &mut app_data),
+                        _ => login_view::draw_login_view(ui, &mut app_data, app_data_arc_clone, runtime_handle),
                     }
                 } else {
                     match app_data.current_tab {
                         AppTab::Home => {
+                            // 開いているタブの内容は既読にする
+                            read_state::mark_home_read(&mut app_data);
                             home_view::draw_home_view(ui, ctx, &mut app_data, app_data_arc_clone, runtime_handle);
                         },
+                        AppTab::Notes => {
+                            read_state::mark_mentions_read(&mut app_data);
+                            notes_view::draw_notes_view(ui, ctx, &mut app_data, app_data_arc_clone, runtime_handle);
+                        },
+                        AppTab::Articles => {
+                            articles_view::draw_articles_view(ui, ctx, &mut app_data, app_data_arc_clone, runtime_handle);
+                        },
+                        AppTab::Bookmarks => {
+                            bookmarks_view::draw_bookmarks_view(ui, ctx, &mut app_data, app_data_arc_clone, runtime_handle);
+                        },
+                        AppTab::Global => {
+                            global_view::draw_global_view(ui, ctx, &mut app_data, app_data_arc_clone, runtime_handle);
+                        },
+                        AppTab::Search => {
+                            search_view::draw_search_view(ui, ctx, &mut app_data, app_data_arc_clone, runtime_handle);
+                        },
+                        AppTab::Friends => {
+                            friends_view::draw_friends_view(ui, ctx, &mut app_data, app_data_arc_clone, runtime_handle);
+                        },
+                        AppTab::Chats => {
+                            chats_view::draw_chats_view(ui, ctx, &mut app_data, app_data_arc_clone, runtime_handle);
+                        },
+                        AppTab::Groups => {
+                            groups_view::draw_groups_view(ui, ctx, &mut app_data, app_data_arc_clone, runtime_handle);
+                        },
+                        AppTab::Channels => {
+                            channels_view::draw_channels_view(ui, ctx, &mut app_data, app_data_arc_clone, runtime_handle);
+                        },
+                        AppTab::Communities => {
+                            communities_view::draw_communities_view(ui, ctx, &mut app_data, app_data_arc_clone, runtime_handle);
+                        },
                         AppTab::Relays => {
                            relays_view::draw_relays_view(ui, &mut app_data, app_data_arc_clone, runtime_handle);
                         },
@@ -118,11 +346,115 @@ impl eframe::App for NostrStatusApp {
                         AppTab::Profile => {
                             profile_view::draw_profile_view(ui, ctx, &mut app_data, app_data_arc_clone, runtime_handle);
                         },
+                        AppTab::UserProfile(public_key) => {
+                            user_profile_view::draw_user_profile_view(ui, ctx, &mut app_data, app_data_arc_clone, runtime_handle, public_key);
+                        },
+                        AppTab::Hashtag => {
+                            hashtag_view::draw_hashtag_view(ui, ctx, &mut app_data, app_data_arc_clone, runtime_handle);
+                        },
+                        AppTab::Settings => {
+                            settings_view::draw_settings_view(ui, ctx, &mut app_data, app_data_arc_clone, runtime_handle);
+                        },
+                        AppTab::Logs => {
+                            logs_view::draw_logs_view(ui, ctx, &mut app_data);
+                        },
+                        AppTab::AuditLog => {
+                            audit_log_view::draw_audit_log_view(ui, ctx, &mut app_data);
+                        },
+                        AppTab::RelayDebug => {
+                            relay_debug_view::draw_relay_debug_view(ui, ctx, &mut app_data);
+                        },
                     }
                 }
             // }); // この閉じ括弧も削除
         });
 
+        // ZAPダイアログはどのタブからでも開けるようにここで描画する
+        zap::draw_zap_dialog(ctx, &mut app_data, self.data.clone(), self.runtime.handle().clone());
+        notes_view::draw_quote_dialog(ctx, &mut app_data, self.data.clone(), self.runtime.handle().clone());
+        report_dialog::draw_report_dialog(ctx, &mut app_data, self.data.clone(), self.runtime.handle().clone());
+        badge_dialog::draw_badge_award_dialog(ctx, &mut app_data, self.data.clone(), self.runtime.handle().clone());
+        link_view::draw_link_view(ctx, &mut app_data, self.data.clone(), self.runtime.handle().clone());
+        call_view::draw_call_window(ctx, &mut app_data, self.runtime.handle());
+        sign_dialog::draw_sign_dialog(ctx, &mut app_data);
+        profile_view::draw_export_key_dialog(ctx, &mut app_data, self.data.clone(), self.runtime.handle());
+        toast::draw_toasts(ctx, &mut app_data);
+
+        // ログイン処理が終わってリレーに接続できたらDMの購読を開始する
+        if app_data.is_logged_in && !app_data.is_loading && !app_data.dm_listener_started
+            && let (Some(client), Some(keys)) = (app_data.nostr_client.clone(), app_data.my_signer.clone()) {
+            app_data.dm_listener_started = true;
+            let app_data_arc = self.data.clone();
+            let ctx_clone = ctx.clone();
+            self.runtime.spawn(dm::run_dm_listener(client, keys, app_data.session_generation, app_data_arc, ctx_clone));
+        }
+        // どの投稿をブックマークしているかは、ログインしたときに一度だけ読み込む
+        if app_data.is_logged_in && !app_data.is_loading && !app_data.bookmarks_requested
+            && let (Some(client), Some(keys)) = (app_data.nostr_client.clone(), app_data.my_signer.clone()) {
+            app_data.bookmarks_requested = true;
+            self.runtime.spawn(bookmarks::load_bookmarks(client, keys, self.data.clone()));
+        }
+        if app_data.is_logged_in && !app_data.is_loading && !app_data.settings_sync_started
+            && let (Some(client), Some(keys)) = (app_data.nostr_client.clone(), app_data.my_signer.clone()) {
+            app_data.settings_sync_started = true;
+            self.runtime.spawn(settings_sync::sync_on_login(client, keys, self.data.clone(), ctx.clone()));
+        }
+        if app_data.is_logged_in && !app_data.is_loading && !app_data.pins_requested
+            && let (Some(client), Some(keys)) = (app_data.nostr_client.clone(), app_data.my_signer.clone()) {
+            app_data.pins_requested = true;
+            self.runtime.spawn(pins::load_pins(client, keys, self.data.clone()));
+        }
+        if app_data.is_logged_in && !app_data.is_loading && !app_data.notification_listener_started && desktop_notify::is_supported()
+            && let (Some(client), Some(keys)) = (app_data.nostr_client.clone(), app_data.my_signer.clone()) {
+            app_data.notification_listener_started = true;
+            self.runtime.spawn(desktop_notify::run_notification_listener(client, keys, app_data.session_generation, self.data.clone(), ctx.clone()));
+        }
+        if app_data.is_logged_in && !app_data.is_loading && !app_data.zap_listener_started
+            && let (Some(client), Some(keys)) = (app_data.nostr_client.clone(), app_data.my_signer.clone()) {
+            app_data.zap_listener_started = true;
+            self.runtime.spawn(zap::run_zap_receipt_listener(client, keys, app_data.session_generation, self.data.clone(), ctx.clone()));
+        }
+        if app_data.is_logged_in && !app_data.is_loading && !app_data.relay_monitor_started
+            && let Some(client) = app_data.nostr_client.clone() {
+            app_data.relay_monitor_started = true;
+            self.runtime.spawn(relay_monitor::run_relay_monitor(client, app_data.session_generation, self.data.clone(), ctx.clone()));
+        }
+        if app_data.is_logged_in && !app_data.is_loading && !app_data.relay_supervisor_started
+            && let Some(client) = app_data.nostr_client.clone() {
+            app_data.relay_supervisor_started = true;
+            self.runtime.spawn(relay_monitor::run_relay_supervisor(client, app_data.session_generation, self.data.clone()));
+        }
+        if app_data.is_logged_in && !app_data.is_loading && !app_data.outbox_worker_started
+            && let Some(client) = app_data.nostr_client.clone() {
+            app_data.outbox_worker_started = true;
+            self.runtime.spawn(outbox::run_outbox_worker(client, app_data.session_generation, self.data.clone(), ctx.clone()));
+        }
+        if app_data.is_logged_in && !app_data.is_loading && !app_data.now_playing_worker_started
+            && let (Some(client), Some(keys)) = (app_data.nostr_client.clone(), app_data.my_signer.clone()) {
+            app_data.now_playing_worker_started = true;
+            self.runtime.spawn(now_playing::run_now_playing_worker(client, keys, app_data.session_generation, self.data.clone()));
+        }
+        if app_data.is_logged_in && !app_data.is_loading && !app_data.opentimestamps_worker_started
+            && let (Some(client), Some(keys)) = (app_data.nostr_client.clone(), app_data.my_signer.clone()) {
+            app_data.opentimestamps_worker_started = true;
+            self.runtime.spawn(opentimestamps::run_upgrade_worker(client, keys, app_data.session_generation, self.data.clone()));
+        }
+        if app_data.is_logged_in && !app_data.is_loading && !app_data.label_worker_started
+            && let (Some(client), Some(keys)) = (app_data.nostr_client.clone(), app_data.my_signer.clone()) {
+            app_data.label_worker_started = true;
+            self.runtime.spawn(labels::run_label_worker(client, keys, app_data.session_generation, self.data.clone()));
+        }
+        if app_data.is_logged_in && !app_data.is_loading && !app_data.wot_worker_started
+            && let Some(client) = app_data.nostr_client.clone() {
+            app_data.wot_worker_started = true;
+            self.runtime.spawn(wot::run_wot_worker(client, app_data.session_generation, self.data.clone()));
+        }
+        if app_data.is_logged_in && !app_data.is_loading && !app_data.scheduler_started
+            && let (Some(client), Some(keys)) = (app_data.nostr_client.clone(), app_data.my_signer.clone()) {
+            app_data.scheduler_started = true;
+            self.runtime.spawn(scheduler::run_scheduler(client, keys, app_data.session_generation, self.data.clone(), ctx.clone()));
+        }
+
         // update メソッドの最後に should_repaint をチェックし、再描画をリクエスト
         if app_data.should_repaint {
             ctx.request_repaint();
@@ -134,4 +466,12 @@ impl eframe::App for NostrStatusApp {
             ctx.request_repaint();
         }
     }
+
+    // ウィンドウを閉じたら、ランタイムが止まって送信の途中で切れる前に接続を片付ける
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        let shutdown = accounts::shutdown(self.data.clone(), self.runtime.handle().clone());
+        if self.runtime.block_on(tokio::time::timeout(Duration::from_secs(SHUTDOWN_TIMEOUT_SECS), shutdown)).is_err() {
+            error!("Timed out while closing relay connections; exiting anyway.");
+        }
+    }
 }
@@ -0,0 +1,111 @@
+use eframe::egui;
+use std::sync::{Arc, Mutex};
+use zeroize::Zeroize;
+
+use crate::{accounts, i18n::tr, read_state, types::*};
+
+// サイドパネルのアカウント切り替え。バックグラウンドのアカウントには未読の件数を出す
+pub fn draw_account_switcher(
+    ui: &mut egui::Ui,
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+) {
+    let add_account_text = tr!("＋ アカウントを追加");
+    let background_hover_text = tr!("バックグラウンドで接続中。パスフレーズなしで切り替えられます");
+    let passphrase_hint_text = tr!("パスフレーズ");
+    let switch_button_text = tr!("切り替え");
+    let cancel_button_text = tr!("キャンセル");
+    let disconnect_button_text = tr!("切断");
+    let disconnect_hover_text = tr!("バックグラウンドの接続を切る");
+    let switching_text = tr!("切り替え中...");
+
+    let Some(my_public_key) = app_data.my_signer.as_ref().map(|signer| signer.public_key()) else {
+        return;
+    };
+    let my_pubkey_hex = my_public_key.to_hex();
+    let current_label = app_data
+        .accounts
+        .iter()
+        .find(|account| account.pubkey == my_pubkey_hex)
+        .map(|account| account.label.clone())
+        .unwrap_or_else(|| short_npub(&my_public_key));
+    let secondary_pubkey_hex = app_data.secondary_account.as_ref().map(|account| account.signer.public_key().to_hex());
+
+    let mut switch_to_secondary = false;
+    let mut stored_target: Option<usize> = None;
+    let mut add_account = false;
+
+    egui::ComboBox::from_id_salt("account_switcher")
+        .selected_text(current_label)
+        .width(ui.available_width())
+        .show_ui(ui, |ui| {
+            for (index, account) in app_data.accounts.iter().enumerate() {
+                if account.pubkey == my_pubkey_hex {
+                    let _ = ui.selectable_label(true, &account.label);
+                } else if Some(&account.pubkey) == secondary_pubkey_hex.as_ref() {
+                    continue;
+                } else if ui.selectable_label(false, &account.label).clicked() {
+                    stored_target = Some(index);
+                }
+            }
+            // リモート署名者のアカウントは一覧にないので、バックグラウンドにいるときだけここに出す
+            if let Some(secondary) = &app_data.secondary_account {
+                let label = read_state::with_badge(&format!("● {}", secondary.label), secondary.unread);
+                if ui.selectable_label(false, label).on_hover_text(background_hover_text).clicked() {
+                    switch_to_secondary = true;
+                }
+            }
+            ui.separator();
+            if ui.selectable_label(false, add_account_text).clicked() {
+                add_account = true;
+            }
+        });
+
+    if let Some(secondary) = &app_data.secondary_account {
+        let label = read_state::with_badge(&format!("● {}", secondary.label), secondary.unread);
+        let mut disconnect = false;
+        ui.horizontal(|ui| {
+            ui.small(label).on_hover_text(background_hover_text);
+            disconnect = ui.small_button(disconnect_button_text).on_hover_text(disconnect_hover_text).clicked();
+        });
+        if disconnect {
+            accounts::disconnect_secondary(app_data, runtime_handle);
+        }
+    }
+
+    if let Some(index) = stored_target {
+        app_data.switch_account_target = Some(index);
+        app_data.switch_passphrase_input.zeroize();
+    }
+    if let Some(index) = app_data.switch_account_target {
+        if app_data.is_switching_account {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label(switching_text);
+            });
+        } else {
+            let response = ui.add(egui::TextEdit::singleline(&mut app_data.switch_passphrase_input)
+                .password(true)
+                .hint_text(passphrase_hint_text));
+            let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            ui.horizontal(|ui| {
+                if ui.button(switch_button_text).clicked() || submitted {
+                    accounts::spawn_switch_to_stored(app_data, app_data_arc.clone(), runtime_handle, ui.ctx(), index);
+                }
+                if ui.button(cancel_button_text).clicked() {
+                    app_data.switch_account_target = None;
+                    app_data.switch_passphrase_input.zeroize();
+                }
+            });
+        }
+    }
+
+    if switch_to_secondary {
+        accounts::spawn_switch_to_secondary(app_data, app_data_arc, runtime_handle, ui.ctx());
+    } else if add_account {
+        // 今のアカウントはバックグラウンドに残し、登録画面を開く
+        accounts::move_to_background(app_data, app_data_arc, runtime_handle, ui.ctx());
+        app_data.adding_account = true;
+    }
+}
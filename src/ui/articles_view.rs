@@ -0,0 +1,438 @@
+use eframe::egui;
+use nostr::nips::nip01::Coordinate;
+use nostr::{Event, EventId};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use tracing::error;
+
+use crate::{
+    articles::{article_coordinate, article_info, fetch_article, fetch_articles},
+    fonts,
+    i18n::tr,
+    note,
+    types::*,
+    ui::{bookmarks_view, content_warning, image_cache, link_view, markdown, signature_badge, timestamp},
+};
+
+// 一覧に出す要約の長さ。要約のない記事は本文の先頭を使う
+const MAX_SUMMARY_CHARS: usize = 140;
+const THUMBNAIL_SIZE: egui::Vec2 = egui::vec2(72.0, 72.0);
+
+fn spawn_fetch_articles(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+) {
+    let (Some(client), Some(signer)) = (app_data.nostr_client.clone(), app_data.my_signer.clone()) else {
+        return;
+    };
+    let mut authors = app_data.followed_pubkeys.iter().copied().collect::<Vec<_>>();
+    authors.push(signer.public_key());
+    let known_profiles: HashSet<_> = app_data.profiles.keys().copied().collect();
+    app_data.articles_loaded = true;
+    app_data.is_loading_articles = true;
+    app_data.should_repaint = true;
+    runtime_handle.spawn(async move {
+        let result = fetch_articles(&client, authors, known_profiles).await;
+        let mut app_data = app_data_arc.lock().unwrap();
+        match result {
+            Ok((articles, profiles)) => {
+                app_data.articles = articles;
+                app_data.profiles.extend(profiles);
+            }
+            Err(e) => {
+                error!("Failed to fetch articles: {e}");
+                app_data.report_error(tr!("記事を取得できませんでした: {}", e));
+            }
+        }
+        app_data.is_loading_articles = false;
+        app_data.should_repaint = true;
+    });
+}
+
+// 記事を記事タブで開く。手元にあればそれを、なければリレーから取得して表示する
+pub fn open_article(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    coordinate: Coordinate,
+) {
+    app_data.current_tab = AppTab::Articles;
+    app_data.should_repaint = true;
+    let known = app_data
+        .articles
+        .iter()
+        .chain(app_data.bookmark_events.iter())
+        .find(|event| event.kind == coordinate.kind && article_coordinate(event) == coordinate)
+        .cloned();
+    if let Some(event) = known {
+        app_data.open_article = Some(event);
+        return;
+    }
+    let Some(client) = app_data.nostr_client.clone() else {
+        return;
+    };
+    app_data.open_article = None;
+    app_data.is_loading_article = true;
+    runtime_handle.spawn(async move {
+        let result = fetch_article(&client, coordinate).await;
+        let mut app_data = app_data_arc.lock().unwrap();
+        match result {
+            Ok(Some(event)) => app_data.open_article = Some(event),
+            Ok(None) => app_data.report_error(tr!("記事が見つかりませんでした")),
+            Err(e) => {
+                error!("Failed to fetch the article: {e}");
+                app_data.report_error(tr!("記事を取得できませんでした: {}", e));
+            }
+        }
+        app_data.is_loading_article = false;
+        app_data.should_repaint = true;
+    });
+}
+
+// 要約がなければ本文の先頭を、書式の記号を含めたまま短くして使う
+fn article_summary(event: &Event) -> String {
+    if let Some(summary) = article_info(event).summary {
+        return summary.to_string();
+    }
+    let text = event.content.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= MAX_SUMMARY_CHARS {
+        return text;
+    }
+    let shortened: String = text.chars().take(MAX_SUMMARY_CHARS).collect();
+    format!("{shortened}…")
+}
+
+// 一覧の記事1件。タイトルか「読む」が押されたら true を返す
+fn draw_article_card(
+    ui: &mut egui::Ui,
+    app_data: &NostrStatusAppInternal,
+    event: &Event,
+    urls_to_load: &mut Vec<(String, ImageKind)>,
+    link_to_open: &mut Option<NostrLink>,
+) -> bool {
+    let untitled_text = tr!("(タイトルなし)");
+    let read_button_text = tr!("読む");
+
+    let card_frame = egui::Frame {
+        inner_margin: egui::Margin::same(12),
+        corner_radius: 8.0.into(),
+        shadow: eframe::epaint::Shadow::NONE,
+        fill: app_data.current_theme.card_background_color(),
+        ..Default::default()
+    };
+    let info = article_info(event);
+    let metadata = app_data.profiles.get(&event.pubkey).cloned().unwrap_or_default();
+    let mut open = false;
+    card_frame.show(ui, |ui| {
+        ui.set_width(ui.available_width());
+        ui.horizontal(|ui| {
+            if let Some(image) = info.image.filter(|_| app_data.settings.show_media_previews) {
+                image_cache::draw_avatar(ui, &app_data.image_cache, image, ImageKind::Preview, THUMBNAIL_SIZE, 6.0, urls_to_load);
+            }
+            ui.vertical(|ui| {
+                let title = egui::RichText::new(info.title.unwrap_or(untitled_text)).family(fonts::bold_font_family()).size(16.0);
+                if ui.link(title).clicked() {
+                    open = true;
+                }
+                ui.horizontal(|ui| {
+                    image_cache::draw_user_avatar(
+                        ui,
+                        &app_data.image_cache,
+                        &event.pubkey,
+                        &metadata.picture,
+                        egui::vec2(20.0, 20.0),
+                        4.0,
+                        urls_to_load,
+                    );
+                    if ui.link(metadata.display_name_or_npub(&event.pubkey)).clicked() {
+                        *link_to_open = Some(NostrLink::Profile(event.pubkey));
+                    }
+                    timestamp::draw_timestamp(ui, info.published_at);
+                    signature_badge::draw_signature_badge(ui, &event.id);
+                });
+                ui.add(egui::Label::new(egui::RichText::new(article_summary(event)).color(egui::Color32::GRAY)).wrap());
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui.small_button(read_button_text).clicked() {
+                        open = true;
+                    }
+                });
+            });
+        });
+    });
+    open
+}
+
+// 1行だけの画像 (![説明](URL))
+fn image_line(line: &str) -> Option<String> {
+    let links = markdown::find_links(line);
+    let [link] = links.as_slice() else {
+        return None;
+    };
+    (link.is_image && link.range == (0..line.len())).then(|| link.url.clone())
+}
+
+// 「# 見出し」の深さと文字
+fn heading_line(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    let text = line[level..].strip_prefix(' ')?;
+    (1..=6).contains(&level).then_some((level, text.trim()))
+}
+
+fn draw_paragraph(
+    ui: &mut egui::Ui,
+    app_data: &NostrStatusAppInternal,
+    lines: &mut Vec<&str>,
+    color: egui::Color32,
+    link_to_open: &mut Option<NostrLink>,
+) {
+    if lines.is_empty() {
+        return;
+    }
+    let text = lines.join("\n");
+    lines.clear();
+    ui.horizontal_wrapped(|ui| {
+        link_view::draw_text_with_links(ui, &text, color, &app_data.profiles, link_to_open);
+    });
+    ui.add_space(6.0);
+}
+
+fn draw_code_block(ui: &mut egui::Ui, lines: &[&str]) {
+    egui::Frame::new()
+        .fill(ui.visuals().code_bg_color)
+        .corner_radius(6.0)
+        .inner_margin(egui::Margin::same(8))
+        .show(ui, |ui| {
+            ui.set_width(ui.available_width());
+            ui.add(egui::Label::new(egui::RichText::new(lines.join("\n")).monospace()).wrap());
+        });
+    ui.add_space(6.0);
+}
+
+// 記事の本文 (markdown) を描く。見出し・画像・引用・コードブロック・区切り線は行ごとに見て、段落の中は draw_text_with_links に任せる
+// 書式を表示しない設定のときは書いたままの本文を描く
+fn draw_article_body(
+    ui: &mut egui::Ui,
+    app_data: &NostrStatusAppInternal,
+    content: &str,
+    urls_to_load: &mut Vec<(String, ImageKind)>,
+    link_to_open: &mut Option<NostrLink>,
+) {
+    let text_color = ui.visuals().text_color();
+    if !markdown::is_enabled() {
+        ui.horizontal_wrapped(|ui| {
+            link_view::draw_text_with_links(ui, content, text_color, &app_data.profiles, link_to_open);
+        });
+        return;
+    }
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut code_block: Option<Vec<&str>> = None;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if let Some(code_lines) = code_block.as_mut() {
+            if trimmed.starts_with("```") {
+                draw_code_block(ui, code_lines);
+                code_block = None;
+            } else {
+                code_lines.push(line);
+            }
+            continue;
+        }
+        if trimmed.starts_with("```") {
+            draw_paragraph(ui, app_data, &mut paragraph, text_color, link_to_open);
+            code_block = Some(Vec::new());
+        } else if trimmed.is_empty() {
+            draw_paragraph(ui, app_data, &mut paragraph, text_color, link_to_open);
+        } else if let Some((level, heading)) = heading_line(trimmed) {
+            draw_paragraph(ui, app_data, &mut paragraph, text_color, link_to_open);
+            ui.add_space(4.0);
+            let size = ui.style().text_styles[&egui::TextStyle::Heading].size * (1.0 - (level - 1) as f32 * 0.1).max(0.7);
+            ui.add(egui::Label::new(egui::RichText::new(heading).family(fonts::bold_font_family()).size(size)).wrap());
+            ui.add_space(4.0);
+        } else if matches!(trimmed, "---" | "***" | "___") {
+            draw_paragraph(ui, app_data, &mut paragraph, text_color, link_to_open);
+            ui.separator();
+        } else if let Some(url) = image_line(trimmed) {
+            draw_paragraph(ui, app_data, &mut paragraph, text_color, link_to_open);
+            if app_data.settings.show_media_previews {
+                image_cache::draw_image_previews(ui, &app_data.image_cache, &[url], urls_to_load);
+                ui.add_space(6.0);
+            } else {
+                paragraph.push(line);
+            }
+        } else if let Some(quote) = trimmed.strip_prefix('>') {
+            // 引用は続けて1つの段落にまとめず、行ごとに字下げして薄い色で描く
+            draw_paragraph(ui, app_data, &mut paragraph, text_color, link_to_open);
+            egui::Frame::new().inner_margin(egui::Margin { left: 12, ..Default::default() }).show(ui, |ui| {
+                let mut quote_lines = vec![quote.trim_start()];
+                draw_paragraph(ui, app_data, &mut quote_lines, egui::Color32::GRAY, link_to_open);
+            });
+        } else {
+            paragraph.push(line);
+        }
+    }
+    // 閉じていないコードブロックは最後までをコードとして描く
+    if let Some(code_lines) = code_block {
+        draw_code_block(ui, &code_lines);
+    }
+    draw_paragraph(ui, app_data, &mut paragraph, text_color, link_to_open);
+}
+
+enum ArticleAction {
+    Back,
+    ToggleBookmark(EventId),
+}
+
+fn draw_article_reader(
+    ui: &mut egui::Ui,
+    app_data: &NostrStatusAppInternal,
+    event: &Event,
+    urls_to_load: &mut Vec<(String, ImageKind)>,
+    link_to_open: &mut Option<NostrLink>,
+    content_warning_toggle: &mut Option<EventId>,
+) -> Option<ArticleAction> {
+    let back_button_text = tr!("← 記事の一覧に戻る");
+    let untitled_text = tr!("(タイトルなし)");
+
+    let info = article_info(event);
+    let metadata = app_data.profiles.get(&event.pubkey).cloned().unwrap_or_default();
+    let mut action = None;
+    ui.horizontal(|ui| {
+        if ui.button(back_button_text).clicked() {
+            action = Some(ArticleAction::Back);
+        }
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            if bookmarks_view::draw_bookmark_button(ui, app_data, event.id) {
+                action = Some(ArticleAction::ToggleBookmark(event.id));
+            }
+        });
+    });
+    ui.add_space(10.0);
+
+    egui::ScrollArea::vertical()
+        .id_salt("article_reader_scroll_area")
+        .show(ui, |ui| {
+            ui.set_width(ui.available_width());
+            ui.add(egui::Label::new(egui::RichText::new(info.title.unwrap_or(untitled_text)).heading()).wrap());
+            ui.horizontal(|ui| {
+                image_cache::draw_user_avatar(
+                    ui,
+                    &app_data.image_cache,
+                    &event.pubkey,
+                    &metadata.picture,
+                    egui::vec2(24.0, 24.0),
+                    4.0,
+                    urls_to_load,
+                );
+                if ui.link(egui::RichText::new(metadata.display_name_or_npub(&event.pubkey)).strong()).clicked() {
+                    *link_to_open = Some(NostrLink::Profile(event.pubkey));
+                }
+                timestamp::draw_timestamp(ui, info.published_at);
+                signature_badge::draw_signature_badge(ui, &event.id);
+                if event.created_at > info.published_at {
+                    let updated = timestamp::format_relative(event.created_at);
+                    ui.label(egui::RichText::new(tr!("({} に更新)", updated)).small().color(egui::Color32::GRAY))
+                        .on_hover_text(timestamp::format_absolute(event.created_at));
+                }
+            });
+            ui.add_space(8.0);
+            if let Some(image) = info.image.filter(|_| app_data.settings.show_media_previews) {
+                image_cache::draw_image_previews(ui, &app_data.image_cache, &[image.to_string()], urls_to_load);
+                ui.add_space(8.0);
+            }
+            if let Some(summary) = info.summary {
+                ui.add(egui::Label::new(egui::RichText::new(summary).italics().color(egui::Color32::GRAY)).wrap());
+                ui.add_space(8.0);
+            }
+            ui.separator();
+            ui.add_space(8.0);
+            let content_warning = note::content_warning(event.tags.iter());
+            if content_warning::draw_content_warning(ui, &app_data.revealed_content_warnings, event.id, content_warning.as_deref(), content_warning_toggle) {
+                draw_article_body(ui, app_data, &event.content, urls_to_load, link_to_open);
+            }
+        });
+    action
+}
+
+// フォローしている人の長文の記事 (NIP-23) の一覧と、開いた記事
+pub fn draw_articles_view(
+    ui: &mut egui::Ui,
+    ctx: &egui::Context,
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: tokio::runtime::Handle,
+) {
+    let articles_heading_text = tr!("記事");
+    let articles_hint_text = tr!("フォローしている人が書いた長文の記事 (NIP-23) です");
+    let reload_button_text = tr!("🔄 再読み込み");
+    let loading_text = tr!("読み込み中...");
+    let no_articles_text = tr!("フォローしている人の記事はまだありません");
+
+    let mut urls_to_load: Vec<(String, ImageKind)> = Vec::new();
+    let mut link_to_open: Option<NostrLink> = None;
+    let mut content_warning_toggle: Option<EventId> = None;
+
+    if app_data.is_loading_article {
+        ui.horizontal(|ui| {
+            ui.spinner();
+            ui.label(loading_text);
+        });
+        return;
+    }
+    if let Some(event) = app_data.open_article.clone() {
+        match draw_article_reader(ui, app_data, &event, &mut urls_to_load, &mut link_to_open, &mut content_warning_toggle) {
+            Some(ArticleAction::Back) => app_data.open_article = None,
+            Some(ArticleAction::ToggleBookmark(event_id)) => {
+                bookmarks_view::spawn_toggle_bookmark(app_data, app_data_arc.clone(), &runtime_handle, event_id);
+            }
+            None => {}
+        }
+    } else {
+        if !app_data.articles_loaded && !app_data.is_loading_articles {
+            spawn_fetch_articles(app_data, app_data_arc.clone(), &runtime_handle);
+        }
+        ui.horizontal(|ui| {
+            ui.heading(articles_heading_text);
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui.add_enabled(!app_data.is_loading_articles, egui::Button::new(reload_button_text)).clicked() {
+                    spawn_fetch_articles(app_data, app_data_arc.clone(), &runtime_handle);
+                }
+            });
+        });
+        ui.small(articles_hint_text);
+        ui.add_space(10.0);
+
+        if app_data.is_loading_articles {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label(loading_text);
+            });
+            return;
+        }
+        if app_data.articles.is_empty() {
+            ui.label(no_articles_text);
+            return;
+        }
+
+        let mut article_to_open: Option<Event> = None;
+        egui::ScrollArea::vertical()
+            .id_salt("articles_scroll_area")
+            .show(ui, |ui| {
+                for event in app_data.articles.iter().filter(|event| !app_data.settings.is_muted(&event.content)) {
+                    if draw_article_card(ui, app_data, event, &mut urls_to_load, &mut link_to_open) {
+                        article_to_open = Some(event.clone());
+                    }
+                    ui.add_space(6.0);
+                }
+            });
+        if article_to_open.is_some() {
+            app_data.open_article = article_to_open;
+        }
+    }
+
+    content_warning::toggle_revealed(&mut app_data.revealed_content_warnings, content_warning_toggle);
+    if let Some(link) = link_to_open {
+        link_view::open_link(app_data, app_data_arc.clone(), &runtime_handle, link);
+    }
+    image_cache::load_images(ctx, app_data, app_data_arc, &runtime_handle, urls_to_load);
+}
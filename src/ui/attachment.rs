@@ -0,0 +1,163 @@
+use eframe::egui;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tracing::error;
+
+use crate::i18n::tr;
+use crate::media::{self, UploadedMedia};
+use crate::signer::AppSigner;
+use crate::types::{AttachTarget, NostrStatusAppInternal, Settings};
+use crate::voice;
+
+fn target_input(app_data: &mut NostrStatusAppInternal, target: AttachTarget) -> &mut String {
+    match target {
+        AttachTarget::Note => &mut app_data.note_input,
+        AttachTarget::Chat => &mut app_data.dm_message_input,
+    }
+}
+
+// 画像・音声の添付ボタン。押すと draw_attach_panel でファイルを選べるようになる
+pub fn draw_attach_button(ui: &mut egui::Ui, app_data: &mut NostrStatusAppInternal, target: AttachTarget) {
+    let attach_hover_text = tr!("画像や音声を添付");
+
+    let is_open = app_data.attach_target == Some(target);
+    if ui.selectable_label(is_open, "🖼").on_hover_text(attach_hover_text).clicked() {
+        app_data.attach_target = if is_open { None } else { Some(target) };
+    }
+}
+
+// 添付するファイルのパスの入力欄。ウィンドウにドロップしたファイルも受け付ける
+pub fn draw_attach_panel(
+    ui: &mut egui::Ui,
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    target: AttachTarget,
+) {
+    let path_hint_text = tr!("画像か音声のファイルのパス (ウィンドウにドロップしても入ります)");
+    let upload_button_text = tr!("アップロード");
+    let uploading_text = tr!("アップロード中...");
+    let voice_hint_text = tr!("このビルドでは録音できません。録音した音声ファイルを添付すると、ボイスメッセージとして送れます");
+    let record_button_text = tr!("🎤 録音");
+    let stop_recording_button_text = tr!("■ 止めて添付");
+    let cancel_recording_button_text = tr!("取り消し");
+
+    if app_data.attach_target != Some(target) {
+        return;
+    }
+    if let Some(path) = ui.ctx().input(|i| i.raw.dropped_files.iter().find_map(|file| file.path.clone())) {
+        app_data.attach_path_input = path.display().to_string();
+    }
+    ui.horizontal(|ui| {
+        ui.add(
+            egui::TextEdit::singleline(&mut app_data.attach_path_input)
+                .desired_width(ui.available_width() - 110.0)
+                .hint_text(path_hint_text),
+        );
+        let can_upload = !app_data.is_uploading_media && !app_data.attach_path_input.trim().is_empty();
+        if ui.add_enabled(can_upload, egui::Button::new(upload_button_text)).clicked() {
+            spawn_upload(app_data, app_data_arc.clone(), runtime_handle, target, None);
+        }
+    });
+    if target == AttachTarget::Chat && !voice::is_supported() {
+        ui.label(egui::RichText::new(voice_hint_text).small().color(egui::Color32::GRAY));
+    } else if target == AttachTarget::Chat {
+        ui.horizontal(|ui| match &app_data.voice_recording {
+            None => {
+                if ui.add_enabled(!app_data.is_uploading_media, egui::Button::new(record_button_text)).clicked() {
+                    match voice::start_recording() {
+                        Ok(recording) => app_data.voice_recording = Some(recording),
+                        Err(e) => {
+                            error!("Failed to start recording: {e}");
+                            app_data.report_error(tr!("録音を始められませんでした: {}", e));
+                        }
+                    }
+                }
+            }
+            Some(recording) => {
+                let seconds = recording.elapsed().as_secs();
+                ui.label(egui::RichText::new(format!("● {}:{:02}", seconds / 60, seconds % 60)).color(egui::Color32::RED));
+                // 経過時間を進めるために描き直す
+                ui.ctx().request_repaint_after(std::time::Duration::from_millis(500));
+                if ui.button(stop_recording_button_text).clicked() {
+                    let recording = app_data.voice_recording.take();
+                    spawn_upload(app_data, app_data_arc.clone(), runtime_handle, target, recording);
+                } else if ui.button(cancel_recording_button_text).clicked() {
+                    app_data.voice_recording = None;
+                }
+            }
+        });
+    }
+    if app_data.is_uploading_media {
+        ui.horizontal(|ui| {
+            ui.spinner();
+            ui.label(uploading_text);
+        });
+    }
+}
+
+// 録音を止めて WAV をアップロードする。一時ファイルは送ったら消す
+async fn upload_recording(
+    signer: &AppSigner,
+    settings: &Settings,
+    recording: voice::Recording,
+) -> Result<UploadedMedia, Box<dyn std::error::Error + Send + Sync>> {
+    let path = tokio::task::spawn_blocking(move || recording.finish()).await??;
+    let result = media::upload_media(signer, settings, &path).await;
+    if let Err(e) = tokio::fs::remove_file(&path).await {
+        error!("Failed to remove the recorded file: {e}");
+    }
+    result
+}
+
+// アップロードが終わったら URL を入力欄に足す。imeta タグは投稿するときに付ける
+// recording があれば入力欄のパスではなく録音した音声を送る
+fn spawn_upload(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    target: AttachTarget,
+    recording: Option<voice::Recording>,
+) {
+    let Some(signer) = app_data.my_signer.clone() else {
+        return;
+    };
+    let path = PathBuf::from(app_data.attach_path_input.trim());
+    let settings = app_data.settings.clone();
+    let session = app_data.session_generation;
+    app_data.is_uploading_media = true;
+    app_data.should_repaint = true;
+    runtime_handle.spawn(async move {
+        let is_recording = recording.is_some();
+        let result = match recording {
+            Some(recording) => upload_recording(&signer, &settings, recording).await,
+            None => media::upload_media(&signer, &settings, &path).await,
+        };
+        let mut app_data = app_data_arc.lock().unwrap();
+        // ログアウトやロックのあとなら、次のセッションの入力欄には足さない
+        if !app_data.is_current_session(session) {
+            return;
+        }
+        match result {
+            Ok(uploaded) => {
+                let input = target_input(&mut app_data, target);
+                if !input.is_empty() && !input.ends_with(char::is_whitespace) {
+                    input.push(' ');
+                }
+                input.push_str(&uploaded.url);
+                app_data.uploaded_media.insert(uploaded.url.clone(), uploaded);
+                if !is_recording {
+                    app_data.attach_path_input.clear();
+                }
+                app_data.attach_target = None;
+                app_data.notify(tr!("ファイルをアップロードしました"));
+            }
+            Err(e) => {
+                error!("Failed to upload the file: {e}");
+                app_data.report_error(tr!("ファイルをアップロードできませんでした: {}", e));
+            }
+        }
+        app_data.is_uploading_media = false;
+        app_data.should_repaint = true;
+    });
+}
@@ -0,0 +1,123 @@
+use eframe::egui;
+use nostr::nips::nip19::ToBech32;
+use tracing::error;
+
+use crate::{audit_log, i18n::tr, sign_gate, types::*, ui::chats_view::to_local};
+
+// このアプリが署名したイベントと、リレーが受け付けたかどうかの一覧
+pub fn draw_audit_log_view(
+    ui: &mut egui::Ui,
+    ctx: &egui::Context,
+    app_data: &mut NostrStatusAppInternal,
+) {
+    let audit_log_heading_text = tr!("署名の記録");
+    let reload_button_text = tr!("🔄 再読み込み");
+    let copy_button_text = tr!("クリップボードにコピー");
+    let export_button_text = tr!("ファイルに書き出す");
+    let copy_id_hover_text = tr!("イベントの ID をコピー");
+    let no_entries_text = tr!("まだ署名したイベントはありません");
+    let audit_log_hint_text = tr!("このアプリが署名したイベントは、公開したかどうかにかかわらずすべてここに残ります");
+    let no_relay_result_text = tr!("リレーの応答なし");
+    let accepted_text = tr!("受理");
+    let rejected_text = tr!("拒否");
+
+    if !app_data.audit_entries_loaded {
+        app_data.audit_entries = audit_log::load_entries();
+        app_data.audit_entries_loaded = true;
+    }
+
+    let card_frame = egui::Frame {
+        inner_margin: egui::Margin::same(12),
+        corner_radius: 8.0.into(),
+        shadow: eframe::epaint::Shadow::NONE,
+        fill: app_data.current_theme.card_background_color(),
+        ..Default::default()
+    };
+
+    ui.heading(audit_log_heading_text);
+    ui.add_space(10.0);
+    ui.horizontal(|ui| {
+        if ui.button(reload_button_text).clicked() {
+            app_data.audit_entries_loaded = false;
+        }
+        if ui.add_enabled(!app_data.audit_entries.is_empty(), egui::Button::new(copy_button_text)).clicked() {
+            match audit_log::export() {
+                Ok(content) => {
+                    ctx.copy_text(content);
+                    app_data.notify(tr!("署名の記録をコピーしました"));
+                }
+                Err(e) => {
+                    error!("Failed to read the audit log: {e}");
+                    app_data.report_error(tr!("署名の記録を読み込めませんでした: {}", e));
+                }
+            }
+        }
+        if ui.add_enabled(!app_data.audit_entries.is_empty(), egui::Button::new(export_button_text)).clicked() {
+            match audit_log::export_to_file() {
+                Ok(path) => app_data.notify(tr!("{} に書き出しました", path.display())),
+                Err(e) => {
+                    error!("Failed to export the audit log: {e}");
+                    app_data.report_error(tr!("署名の記録を書き出せませんでした: {}", e));
+                }
+            }
+        }
+    });
+    ui.small(audit_log_hint_text);
+    ui.add_space(10.0);
+
+    card_frame.show(ui, |ui| {
+        if app_data.audit_entries.is_empty() {
+            ui.label(no_entries_text);
+            return;
+        }
+        egui::ScrollArea::vertical()
+            .id_salt("audit_log_scroll_area")
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                egui::Grid::new("audit_log_grid")
+                    .num_columns(5)
+                    .striped(true)
+                    .spacing([12.0, 6.0])
+                    .show(ui, |ui| {
+                        for entry in &app_data.audit_entries {
+                            ui.label(to_local(entry.signed_at).format("%Y-%m-%d %H:%M:%S").to_string());
+                            ui.label(sign_gate::kind_label(entry.kind));
+                            let account = app_data
+                                .accounts
+                                .iter()
+                                .find(|account| account.pubkey == entry.pubkey.to_hex())
+                                .map(|account| account.label.clone())
+                                .unwrap_or_else(|| short_npub(&entry.pubkey));
+                            ui.label(account);
+                            let note_id = entry.id.to_bech32().unwrap_or_else(|_| entry.id.to_hex());
+                            let short_id = format!("{}…", note_id.chars().take(16).collect::<String>());
+                            if ui.small_button(short_id).on_hover_text(copy_id_hover_text).clicked() {
+                                ctx.copy_text(note_id);
+                            }
+                            if entry.relays.is_empty() {
+                                ui.label(egui::RichText::new(no_relay_result_text).color(egui::Color32::GRAY));
+                            } else {
+                                let accepted = entry.relays.iter().filter(|result| result.accepted).count();
+                                let rejected = entry.relays.len() - accepted;
+                                let details: Vec<String> = entry
+                                    .relays
+                                    .iter()
+                                    .map(|result| {
+                                        let status = if result.accepted { accepted_text } else { rejected_text };
+                                        if result.message.is_empty() {
+                                            format!("{status}: {}", result.relay)
+                                        } else {
+                                            format!("{status}: {} ({})", result.relay, result.message)
+                                        }
+                                    })
+                                    .collect();
+                                let summary = format!("✔ {accepted}  ✖ {rejected}");
+                                let color = if rejected > 0 { ui.visuals().warn_fg_color } else { ui.visuals().text_color() };
+                                ui.label(egui::RichText::new(summary).color(color)).on_hover_text(details.join("\n"));
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+    });
+}
@@ -0,0 +1,153 @@
+use eframe::egui;
+use nostr::PublicKey;
+use std::sync::{Arc, Mutex};
+use tracing::error;
+
+use crate::{
+    badges::{award_badge, fetch_my_badge_definitions},
+    i18n::tr,
+    types::*,
+    ui::image_cache,
+};
+
+// 一覧に出すバッジの画像の大きさ
+const BADGE_THUMBNAIL_SIZE: egui::Vec2 = egui::vec2(32.0, 32.0);
+
+// バッジの小さな画像。thumb がなければ image を使い、どちらもなければ絵文字を出す
+pub fn draw_badge_image(
+    ui: &mut egui::Ui,
+    image_cache_map: &std::collections::HashMap<String, ImageState>,
+    badge: &Badge,
+    size: egui::Vec2,
+    urls_to_load: &mut Vec<(String, ImageKind)>,
+) -> egui::Response {
+    let url = if badge.thumb.is_empty() { &badge.image } else { &badge.thumb };
+    if url.is_empty() {
+        return ui.add_sized(size, egui::Label::new(egui::RichText::new("🏅").size(size.y * 0.7)));
+    }
+    image_cache::draw_avatar(ui, image_cache_map, url, ImageKind::Badge, size, 6.0, urls_to_load)
+}
+
+// 自分が作ったバッジを選んで贈るダイアログ。どのタブからでも開けるよう、ここで描く
+pub fn draw_badge_award_dialog(
+    ctx: &egui::Context,
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: tokio::runtime::Handle,
+) {
+    let window_title_text = tr!("バッジを贈る");
+    let loading_text = tr!("自分のバッジを読み込み中...");
+    let no_badges_text = tr!("まだバッジを作っていません。バッジ (NIP-58) を作成できるアプリで定義を公開すると、ここから贈れます");
+    let award_button_text = tr!("贈る");
+    let cancel_button_text = tr!("キャンセル");
+    let award_hint_text = tr!("授与は公開されます。相手がプロフィールに表示すると、バッジとして見えるようになります");
+
+    let Some(recipient) = app_data.badge_award_target else {
+        return;
+    };
+    let mut urls_to_load: Vec<(String, ImageKind)> = Vec::new();
+    let mut close = false;
+    egui::Window::new(window_title_text)
+        .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            let name = app_data
+                .profiles
+                .get(&recipient)
+                .map(|metadata| metadata.display_name_or_npub(&recipient))
+                .unwrap_or_else(|| short_npub(&recipient));
+            ui.label(tr!("{} にバッジを贈ります", name));
+            ui.add_space(5.0);
+            match &app_data.my_badge_definitions {
+                None => {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label(loading_text);
+                    });
+                }
+                Some(badges) if badges.is_empty() => {
+                    ui.label(egui::RichText::new(no_badges_text).color(egui::Color32::GRAY));
+                }
+                Some(badges) => {
+                    egui::ScrollArea::vertical().id_salt("badge_award_scroll_area").max_height(240.0).show(ui, |ui| {
+                        for (i, badge) in badges.iter().enumerate() {
+                            ui.horizontal(|ui| {
+                                draw_badge_image(ui, &app_data.image_cache, badge, BADGE_THUMBNAIL_SIZE, &mut urls_to_load);
+                                let label = if badge.name.is_empty() { &badge.d_tag } else { &badge.name };
+                                let response = ui.radio_value(&mut app_data.badge_award_selected, Some(i), label);
+                                if !badge.description.is_empty() {
+                                    response.on_hover_text(&badge.description);
+                                }
+                            });
+                        }
+                    });
+                }
+            }
+            ui.small(award_hint_text);
+            ui.add_space(5.0);
+            ui.horizontal(|ui| {
+                let selected = app_data
+                    .badge_award_selected
+                    .and_then(|i| app_data.my_badge_definitions.as_ref().and_then(|badges| badges.get(i)))
+                    .cloned();
+                if ui.add_enabled(selected.is_some() && !app_data.is_awarding_badge, egui::Button::new(award_button_text)).clicked()
+                    && let (Some(client), Some(signer), Some(badge)) = (app_data.nostr_client.clone(), app_data.my_signer.clone(), selected) {
+                    app_data.is_awarding_badge = true;
+                    let cloned_app_data_arc = app_data_arc.clone();
+                    runtime_handle.spawn(async move {
+                        let awarded = award_badge(client, signer, badge, recipient, cloned_app_data_arc.clone()).await;
+                        let mut data = cloned_app_data_arc.lock().unwrap();
+                        if awarded {
+                            data.badge_award_target = None;
+                        }
+                        data.is_awarding_badge = false;
+                        data.should_repaint = true;
+                    });
+                }
+                if ui.button(cancel_button_text).clicked() {
+                    close = true;
+                }
+                if app_data.is_awarding_badge {
+                    ui.spinner();
+                }
+            });
+        });
+    if close {
+        app_data.badge_award_target = None;
+    }
+    image_cache::load_images(ctx, app_data, app_data_arc, &runtime_handle, urls_to_load);
+}
+
+// バッジを贈るダイアログを開く。自分のバッジはログインしている間に一度だけ読み込む
+pub fn open_badge_award_dialog(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    recipient: PublicKey,
+) {
+    app_data.badge_award_target = Some(recipient);
+    app_data.badge_award_selected = None;
+    if app_data.my_badge_definitions.is_some() {
+        return;
+    }
+    let (Some(client), Some(signer)) = (app_data.nostr_client.clone(), app_data.my_signer.clone()) else {
+        return;
+    };
+    runtime_handle.spawn(async move {
+        let result = fetch_my_badge_definitions(&client, signer.public_key()).await;
+        let mut app_data = app_data_arc.lock().unwrap();
+        if app_data.my_signer.as_ref().map(|signer| signer.public_key()) != Some(signer.public_key()) {
+            return;
+        }
+        match result {
+            Ok(badges) => app_data.my_badge_definitions = Some(badges),
+            Err(e) => {
+                error!("Failed to fetch my badge definitions: {e}");
+                app_data.report_error(tr!("バッジを読み込めませんでした: {}", e));
+                app_data.badge_award_target = None;
+            }
+        }
+        app_data.should_repaint = true;
+    });
+}
@@ -0,0 +1,187 @@
+use eframe::egui;
+use nostr::{EventId, Kind};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use tracing::error;
+
+use crate::{
+    bookmarks::{fetch_bookmarked_events, update_bookmarks},
+    i18n::tr,
+    types::*,
+    ui::{content_warning, hashtag_view, image_cache, link_preview, link_view},
+};
+
+// ブックマークのボタン。ブックマーク済みなら選択された見た目にする
+pub fn draw_bookmark_button(ui: &mut egui::Ui, app_data: &NostrStatusAppInternal, event_id: EventId) -> bool {
+    let add_hover_text = tr!("Keep に保存");
+    let remove_hover_text = tr!("Keep から外す");
+
+    let is_bookmarked = app_data.bookmarked_ids.contains(&event_id);
+    let hover_text = if is_bookmarked { remove_hover_text } else { add_hover_text };
+    ui.add_enabled(!app_data.is_updating_bookmark, egui::Button::new("🔖").small().selected(is_bookmarked))
+        .on_hover_text(hover_text)
+        .clicked()
+}
+
+// ブックマークしていなければ足し、していれば外す
+pub fn spawn_toggle_bookmark(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    event_id: EventId,
+) {
+    if app_data.is_updating_bookmark {
+        return;
+    }
+    let (Some(client), Some(keys)) = (app_data.nostr_client.clone(), app_data.my_signer.clone()) else {
+        return;
+    };
+    let add = !app_data.bookmarked_ids.contains(&event_id);
+    app_data.is_updating_bookmark = true;
+    app_data.should_repaint = true;
+    runtime_handle.spawn(async move {
+        let result = update_bookmarks(&client, &keys, event_id, add).await;
+        let mut app_data = app_data_arc.lock().unwrap();
+        match result {
+            Ok(ids) => {
+                app_data.bookmarked_ids = ids;
+                if add {
+                    // 次に Keep を開いたときに取り直す
+                    app_data.bookmark_events_loaded = false;
+                    app_data.notify(tr!("Keep に保存しました"));
+                } else {
+                    app_data.bookmark_events.retain(|event| event.id != event_id);
+                    app_data.notify(tr!("Keep から外しました"));
+                }
+            }
+            Err(e) => {
+                error!("Failed to update the bookmark list: {e}");
+                app_data.report_error(tr!("ブックマークを更新できませんでした: {}", e));
+            }
+        }
+        app_data.is_updating_bookmark = false;
+        app_data.should_repaint = true;
+    });
+}
+
+fn spawn_fetch_bookmarked_events(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+) {
+    let Some(client) = app_data.nostr_client.clone() else {
+        return;
+    };
+    let ids = app_data.bookmarked_ids.clone();
+    let known_profiles: HashSet<_> = app_data.profiles.keys().copied().collect();
+    app_data.bookmark_events_loaded = true;
+    app_data.is_loading_bookmarks = true;
+    runtime_handle.spawn(async move {
+        let result = fetch_bookmarked_events(&client, ids, known_profiles).await;
+        let mut app_data = app_data_arc.lock().unwrap();
+        match result {
+            Ok((events, profiles)) => {
+                app_data.bookmark_events = events;
+                app_data.profiles.extend(profiles);
+            }
+            Err(e) => {
+                error!("Failed to fetch the bookmarked events: {e}");
+                app_data.report_error(tr!("Keep の投稿を取得できませんでした: {}", e));
+            }
+        }
+        app_data.is_loading_bookmarks = false;
+        app_data.should_repaint = true;
+    });
+}
+
+// ブックマークしたステータス・ノート・記事の一覧 (LINE の Keep にあたる)
+pub fn draw_bookmarks_view(
+    ui: &mut egui::Ui,
+    ctx: &egui::Context,
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: tokio::runtime::Handle,
+) {
+    let bookmarks_heading_text = "Keep";
+    let reload_button_text = tr!("🔄 再読み込み");
+    let loading_text = tr!("読み込み中...");
+    let no_bookmarks_text = tr!("保存した投稿はまだありません。タイムラインの 🔖 で保存できます。");
+    let filter_all_text = tr!("すべて");
+    let filter_statuses_text = tr!("ステータス");
+    let filter_notes_text = tr!("ノート");
+    let filter_articles_text = tr!("記事");
+    let remove_button_text = tr!("Keep から外す");
+    let missing_text = tr!("件はリレーで見つかりませんでした");
+
+    if !app_data.bookmark_events_loaded && !app_data.is_loading_bookmarks {
+        spawn_fetch_bookmarked_events(app_data, app_data_arc.clone(), &runtime_handle);
+    }
+
+    ui.horizontal(|ui| {
+        ui.heading(bookmarks_heading_text);
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            if ui.add_enabled(!app_data.is_loading_bookmarks, egui::Button::new(reload_button_text)).clicked() {
+                spawn_fetch_bookmarked_events(app_data, app_data_arc.clone(), &runtime_handle);
+            }
+        });
+    });
+    ui.horizontal(|ui| {
+        ui.selectable_value(&mut app_data.bookmark_kind_filter, None, filter_all_text);
+        ui.selectable_value(&mut app_data.bookmark_kind_filter, Some(Kind::from(30315)), filter_statuses_text);
+        ui.selectable_value(&mut app_data.bookmark_kind_filter, Some(Kind::TextNote), filter_notes_text);
+        ui.selectable_value(&mut app_data.bookmark_kind_filter, Some(Kind::LongFormTextNote), filter_articles_text);
+    });
+    ui.add_space(10.0);
+
+    if app_data.is_loading_bookmarks {
+        ui.horizontal(|ui| {
+            ui.spinner();
+            ui.label(loading_text);
+        });
+        return;
+    }
+    if app_data.bookmarked_ids.is_empty() {
+        ui.label(no_bookmarks_text);
+        return;
+    }
+    let missing = app_data.bookmarked_ids.len().saturating_sub(app_data.bookmark_events.len());
+    if missing > 0 {
+        ui.label(egui::RichText::new(format!("{missing} {missing_text}")).small().color(egui::Color32::GRAY));
+    }
+
+    let mut urls_to_load: Vec<(String, ImageKind)> = Vec::new();
+    let mut link_to_open: Option<NostrLink> = None;
+    let mut content_warning_toggle: Option<EventId> = None;
+    let mut links_to_preview: Vec<String> = Vec::new();
+    let mut bookmark_to_remove: Option<EventId> = None;
+
+    egui::ScrollArea::vertical()
+        .id_salt("bookmarks_scroll_area")
+        .show(ui, |ui| {
+            let visible = app_data
+                .bookmark_events
+                .iter()
+                .filter(|event| app_data.bookmark_kind_filter.is_none_or(|kind| event.kind == kind));
+            for event in visible {
+                hashtag_view::draw_feed_event(ui, app_data, event, &mut urls_to_load, &mut link_to_open, &mut content_warning_toggle, &mut links_to_preview);
+                ui.horizontal(|ui| {
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if ui.add_enabled(!app_data.is_updating_bookmark, egui::Button::new(remove_button_text).small()).clicked() {
+                            bookmark_to_remove = Some(event.id);
+                        }
+                    });
+                });
+                ui.add_space(6.0);
+            }
+        });
+
+    content_warning::toggle_revealed(&mut app_data.revealed_content_warnings, content_warning_toggle);
+    if let Some(event_id) = bookmark_to_remove {
+        spawn_toggle_bookmark(app_data, app_data_arc.clone(), &runtime_handle, event_id);
+    }
+    if let Some(link) = link_to_open {
+        link_view::open_link(app_data, app_data_arc.clone(), &runtime_handle, link);
+    }
+    link_preview::fetch_link_previews(app_data, app_data_arc.clone(), &runtime_handle, links_to_preview);
+    image_cache::load_images(ctx, app_data, app_data_arc, &runtime_handle, urls_to_load);
+}
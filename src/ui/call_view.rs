@@ -0,0 +1,69 @@
+use eframe::egui;
+use std::time::Duration;
+
+use crate::call::{self, CallState};
+use crate::i18n::tr;
+use crate::types::{ImageKind, NostrStatusAppInternal};
+use crate::ui::image_cache;
+
+// 着信・呼び出し中・通話中のウィンドウ。どのタブからでも見えるように ui.rs から描く
+pub fn draw_call_window(ctx: &egui::Context, app_data: &mut NostrStatusAppInternal, runtime_handle: &tokio::runtime::Handle) {
+    let call_window_title_text = tr!("音声通話");
+    let incoming_text = tr!("着信中…");
+    let outgoing_text = tr!("呼び出し中…");
+    let connected_text = tr!("通話中");
+    let accept_button_text = tr!("📞 応答");
+    let decline_button_text = tr!("拒否");
+    let hang_up_button_text = tr!("終了");
+    let no_audio_text = tr!("この環境では音声の送受信ができません");
+
+    call::tick(app_data, runtime_handle);
+    let Some(session) = app_data.call.clone() else {
+        return;
+    };
+    let metadata = app_data.profiles.get(&session.peer).cloned().unwrap_or_default();
+    let name = metadata.display_name_or_npub(&session.peer);
+    let mut urls_to_load: Vec<(String, ImageKind)> = Vec::new();
+
+    egui::Window::new(call_window_title_text)
+        .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 40.0))
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                image_cache::draw_user_avatar(ui, &app_data.image_cache, &session.peer, &metadata.picture, egui::vec2(64.0, 64.0), 32.0, &mut urls_to_load);
+                ui.add_space(5.0);
+                ui.label(egui::RichText::new(&name).strong());
+                let status_text = match &session.state {
+                    CallState::Incoming { .. } => incoming_text.to_string(),
+                    CallState::Outgoing => outgoing_text.to_string(),
+                    CallState::Connected { since } => {
+                        let seconds = since.elapsed().as_secs();
+                        format!("{connected_text} {}:{:02}", seconds / 60, seconds % 60)
+                    }
+                };
+                ui.label(status_text);
+                if !app_data.call_backend.is_available() {
+                    ui.label(egui::RichText::new(no_audio_text).small().color(egui::Color32::GRAY));
+                }
+                ui.add_space(10.0);
+                ui.horizontal(|ui| match session.state {
+                    CallState::Incoming { .. } => {
+                        if ui.add_enabled(app_data.call_backend.is_available(), egui::Button::new(accept_button_text)).clicked() {
+                            call::accept_call(app_data, runtime_handle);
+                        }
+                        if ui.button(decline_button_text).clicked() {
+                            call::decline_call(app_data, runtime_handle);
+                        }
+                    }
+                    CallState::Outgoing | CallState::Connected { .. } => {
+                        if ui.button(hang_up_button_text).clicked() {
+                            call::hang_up(app_data, runtime_handle);
+                        }
+                    }
+                });
+            });
+        });
+    // 通話時間の表示と呼び出しの時間切れのために描き直す
+    ctx.request_repaint_after(Duration::from_secs(1));
+}
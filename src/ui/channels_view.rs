@@ -0,0 +1,377 @@
+use eframe::egui;
+use nostr::PublicKey;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tracing::error;
+
+use crate::{
+    cache_db::DB_CHANNEL_PREFS,
+    channels::{create_channel, fetch_channels, mute_channel_user, push_channel_message, run_channel_listener, send_channel_message},
+    i18n::tr,
+    types::*,
+    ui::{
+        chats_view::{draw_date_separator, draw_message_bubble, to_local, BubbleMessage},
+        image_cache,
+    },
+};
+
+// 非表示・ミュート設定をLMDBに保存する
+fn save_channel_prefs(app_data: &NostrStatusAppInternal) {
+    if let Some(keys) = &app_data.my_signer {
+        let pubkey_hex = keys.public_key().to_string();
+        if let Err(e) = app_data.cache_db.write_value(DB_CHANNEL_PREFS, &pubkey_hex, &app_data.channel_prefs) {
+            error!("Failed to save channel prefs: {e}");
+        }
+    }
+}
+
+fn spawn_fetch_channels(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+) {
+    let Some(client) = app_data.nostr_client.clone() else {
+        return;
+    };
+    app_data.is_loading_channels = true;
+    app_data.should_repaint = true;
+    runtime_handle.spawn(async move {
+        let result = fetch_channels(&client).await;
+        let mut app_data = app_data_arc.lock().unwrap();
+        match result {
+            Ok(channels) => {
+                app_data.channels = channels;
+                app_data.channel_error = None;
+            }
+            Err(e) => {
+                error!("Failed to fetch channels: {e}");
+                app_data.channel_error = Some(tr!("チャンネル一覧の取得に失敗しました: {}", e));
+            }
+        }
+        app_data.is_loading_channels = false;
+        app_data.should_repaint = true;
+    });
+}
+
+pub fn draw_channels_view(
+    ui: &mut egui::Ui,
+    ctx: &egui::Context,
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: tokio::runtime::Handle,
+) {
+    let channels_heading_text = tr!("チャンネル");
+    let refresh_button_text = tr!("更新");
+    let new_channel_text = tr!("新しいチャンネル");
+    let channel_name_hint_text = tr!("チャンネル名");
+    let channel_about_hint_text = tr!("説明 (任意)");
+    let create_button_text = tr!("作成");
+    let show_hidden_text = tr!("非表示のチャンネルも表示");
+    let no_channels_text = tr!("チャンネルが見つかりません。");
+    let hide_button_text = tr!("非表示");
+    let unhide_button_text = tr!("再表示");
+    let select_channel_text = tr!("左のリストからチャンネルを選択してください。");
+    let mute_button_text = tr!("このユーザーをミュート");
+    let muted_users_text = tr!("ミュート中のユーザー");
+    let unmute_button_text = tr!("解除");
+    let message_hint_text = tr!("メッセージを入力");
+    let send_button_text = tr!("送信");
+    let mut urls_to_load: Vec<(String, ImageKind)> = Vec::new();
+    // チャンネル・グループのメッセージのカスタム絵文字はまだ読まない
+    let no_emojis = HashMap::new();
+
+    let card_frame = egui::Frame {
+        inner_margin: egui::Margin::same(12),
+        corner_radius: 8.0.into(),
+        shadow: eframe::epaint::Shadow::NONE,
+        fill: app_data.current_theme.card_background_color(),
+        ..Default::default()
+    };
+
+    // 初回表示時に保存済みの設定とチャンネル一覧を読み込む
+    if !app_data.channels_loaded && app_data.nostr_client.is_some() {
+        app_data.channels_loaded = true;
+        if let Some(keys) = &app_data.my_signer {
+            let pubkey_hex = keys.public_key().to_string();
+            match app_data.cache_db.read_value::<ChannelPrefs>(DB_CHANNEL_PREFS, &pubkey_hex) {
+                Ok(prefs) => app_data.channel_prefs = prefs.unwrap_or_default(),
+                Err(e) => error!("Failed to load channel prefs: {e}"),
+            }
+        }
+        spawn_fetch_channels(app_data, app_data_arc.clone(), &runtime_handle);
+    }
+
+    // 選択中のチャンネルが変わったら購読タスクを開始する
+    let selected_id = app_data.selected_channel.as_ref().map(|c| c.id);
+    if selected_id != app_data.channel_listener_key {
+        app_data.channel_listener_key = selected_id;
+        if let (Some(channel_id), Some(client), Some(keys)) =
+            (selected_id, app_data.nostr_client.clone(), app_data.my_signer.clone())
+        {
+            runtime_handle.spawn(run_channel_listener(client, keys, channel_id, app_data_arc.clone(), ctx.clone()));
+        }
+    }
+
+    ui.horizontal(|ui| {
+        ui.heading(channels_heading_text);
+        if ui.add_enabled(!app_data.is_loading_channels, egui::Button::new(refresh_button_text)).clicked() {
+            spawn_fetch_channels(app_data, app_data_arc.clone(), &runtime_handle);
+        }
+        if app_data.is_loading_channels {
+            ui.spinner();
+        }
+    });
+    ui.add_space(10.0);
+
+    let mut prefs_changed = false;
+    let mut user_to_mute: Option<PublicKey> = None;
+
+    ui.columns(2, |columns| {
+        // --- チャンネル一覧 ---
+        card_frame.show(&mut columns[0], |ui| {
+            egui::CollapsingHeader::new(new_channel_text)
+                .id_salt("new_channel")
+                .show(ui, |ui| {
+                    ui.add(egui::TextEdit::singleline(&mut app_data.new_channel_name_input).hint_text(channel_name_hint_text));
+                    ui.add(egui::TextEdit::singleline(&mut app_data.new_channel_about_input).hint_text(channel_about_hint_text));
+                    let can_create = !app_data.is_loading_channels && !app_data.new_channel_name_input.trim().is_empty();
+                    if ui.add_enabled(can_create, egui::Button::new(create_button_text)).clicked()
+                        && let (Some(client), Some(keys)) = (app_data.nostr_client.clone(), app_data.my_signer.clone()) {
+                        let name = app_data.new_channel_name_input.trim().to_string();
+                        let about = app_data.new_channel_about_input.trim().to_string();
+                        app_data.is_loading_channels = true;
+                        app_data.should_repaint = true;
+                        let cloned_app_data_arc = app_data_arc.clone();
+                        runtime_handle.spawn(async move {
+                            let result = create_channel(&client, &keys, &name, &about).await;
+                            let mut app_data = cloned_app_data_arc.lock().unwrap();
+                            match result {
+                                Ok(channel) => {
+                                    app_data.channels.insert(0, channel.clone());
+                                    app_data.selected_channel = Some(channel);
+                                    app_data.new_channel_name_input.clear();
+                                    app_data.new_channel_about_input.clear();
+                                    app_data.channel_error = None;
+                                }
+                                Err(e) => {
+                                    error!("Failed to create channel: {e}");
+                                    app_data.channel_error = Some(tr!("チャンネルの作成に失敗しました: {}", e));
+                                    app_data.report_error(tr!("チャンネルの作成に失敗しました: {}", e));
+                                }
+                            }
+                            app_data.is_loading_channels = false;
+                            app_data.should_repaint = true;
+                        });
+                    }
+                });
+            ui.checkbox(&mut app_data.show_hidden_channels, show_hidden_text);
+            ui.separator();
+
+            let visible_channels: Vec<ChannelInfo> = app_data
+                .channels
+                .iter()
+                .filter(|c| app_data.show_hidden_channels || !app_data.channel_prefs.hidden_channels.contains(&c.id))
+                .cloned()
+                .collect();
+            if visible_channels.is_empty() {
+                ui.label(no_channels_text);
+            }
+
+            egui::ScrollArea::vertical()
+                .id_salt("channel_list_scroll_area")
+                .show(ui, |ui| {
+                    for channel in visible_channels {
+                        let is_selected = app_data.selected_channel.as_ref().map(|c| c.id) == Some(channel.id);
+                        let is_hidden = app_data.channel_prefs.hidden_channels.contains(&channel.id);
+                        ui.horizontal(|ui| {
+                            image_cache::draw_avatar(
+                                ui,
+                                &app_data.image_cache,
+                                &channel.picture,
+                                ImageKind::Avatar,
+                                egui::vec2(24.0, 24.0),
+                                4.0,
+                                &mut urls_to_load,
+                            );
+                            let name = if channel.name.is_empty() { channel.id.to_hex()[..8].to_string() } else { channel.name.clone() };
+                            if ui.selectable_label(is_selected, name).clicked() {
+                                app_data.selected_channel = Some(channel.clone());
+                            }
+                            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                let button_text = if is_hidden { unhide_button_text } else { hide_button_text };
+                                if ui.small_button(button_text).clicked() {
+                                    if is_hidden {
+                                        app_data.channel_prefs.hidden_channels.remove(&channel.id);
+                                    } else {
+                                        app_data.channel_prefs.hidden_channels.insert(channel.id);
+                                        if is_selected {
+                                            app_data.selected_channel = None;
+                                        }
+                                    }
+                                    prefs_changed = true;
+                                }
+                            });
+                        });
+                    }
+                });
+        });
+
+        // --- チャンネルのトーク ---
+        card_frame.show(&mut columns[1], |ui| {
+            let Some(channel) = app_data.selected_channel.clone() else {
+                ui.label(select_channel_text);
+                return;
+            };
+
+            ui.label(egui::RichText::new(&channel.name).strong());
+            if !channel.about.is_empty() {
+                ui.label(egui::RichText::new(&channel.about).small().color(egui::Color32::GRAY));
+            }
+            let creator_name = app_data
+                .profiles
+                .get(&channel.creator)
+                .map_or_else(|| short_npub(&channel.creator), |p| p.display_name_or_npub(&channel.creator));
+            ui.label(egui::RichText::new(tr!("作成者: {}", creator_name)).small().color(egui::Color32::GRAY));
+
+            let muted_users: Vec<PublicKey> = app_data.channel_prefs.muted_users.iter().copied().collect();
+            if !muted_users.is_empty() {
+                egui::CollapsingHeader::new(format!("{} ({})", muted_users_text, muted_users.len()))
+                    .id_salt("channel_muted_users")
+                    .show(ui, |ui| {
+                        for pubkey in muted_users {
+                            ui.horizontal(|ui| {
+                                ui.label(short_npub(&pubkey));
+                                if ui.small_button(unmute_button_text).clicked() {
+                                    app_data.channel_prefs.muted_users.remove(&pubkey);
+                                    prefs_changed = true;
+                                }
+                            });
+                        }
+                    });
+            }
+            ui.separator();
+
+            let messages: Vec<ChannelMessage> = app_data
+                .channel_messages
+                .get(&channel.id)
+                .map(|messages| {
+                    messages
+                        .iter()
+                        .filter(|m| !app_data.channel_prefs.muted_users.contains(&m.sender))
+                        .cloned()
+                        .collect()
+                })
+                .unwrap_or_default();
+            egui::ScrollArea::vertical()
+                .id_salt(("channel_messages_scroll_area", channel.id))
+                .max_height(ui.available_height() - 60.0)
+                .stick_to_bottom(true)
+                .auto_shrink([false, false])
+                .show(ui, |ui| {
+                    let mut previous: Option<&ChannelMessage> = None;
+                    for message in &messages {
+                        let local = to_local(message.created_at);
+                        if previous.is_none_or(|p| to_local(p.created_at).date_naive() != local.date_naive()) {
+                            draw_date_separator(ui, &local);
+                        }
+                        let show_avatar = previous.is_none_or(|p| p.sender != message.sender);
+                        let sender_profile = app_data.profiles.get(&message.sender);
+                        if show_avatar && !message.is_mine {
+                            let name = sender_profile
+                                .map_or_else(|| short_npub(&message.sender), |p| p.display_name_or_npub(&message.sender));
+                            // 名前を右クリックするとミュートできる
+                            ui.label(egui::RichText::new(name).small().color(egui::Color32::GRAY))
+                                .context_menu(|ui| {
+                                    if ui.button(mute_button_text).clicked() {
+                                        user_to_mute = Some(message.sender);
+                                        ui.close();
+                                    }
+                                });
+                        }
+                        draw_message_bubble(
+                            ui,
+                            BubbleMessage {
+                                content: &message.content,
+                                created_at: message.created_at,
+                                is_mine: message.is_mine,
+                                sender: &message.sender,
+                                sender_picture: sender_profile.map(|p| p.picture.as_str()).unwrap_or_default(),
+                                show_avatar,
+                                is_read: false,
+                                emojis: &no_emojis,
+                                images: &[],
+                                audio_clips: &[],
+                            },
+                            app_data.current_theme,
+                            &app_data.image_cache,
+                            &mut urls_to_load,
+                        );
+                        previous = Some(message);
+                    }
+                });
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut app_data.channel_message_input)
+                        .desired_width(ui.available_width() - 60.0)
+                        .hint_text(message_hint_text),
+                );
+                let can_send = !app_data.is_sending_channel_message && !app_data.channel_message_input.trim().is_empty();
+                if ui.add_enabled(can_send, egui::Button::new(send_button_text)).clicked() {
+                    let (Some(client), Some(keys)) = (app_data.nostr_client.clone(), app_data.my_signer.clone()) else {
+                        return;
+                    };
+                    let content = std::mem::take(&mut app_data.channel_message_input);
+                    app_data.is_sending_channel_message = true;
+                    app_data.should_repaint = true;
+
+                    let cloned_app_data_arc = app_data_arc.clone();
+                    runtime_handle.spawn(async move {
+                        let result = send_channel_message(&client, &keys, channel.id, &content).await;
+                        let mut app_data = cloned_app_data_arc.lock().unwrap();
+                        match result {
+                            Ok(message) => {
+                                push_channel_message(&mut app_data.channel_messages, channel.id, message);
+                                app_data.channel_error = None;
+                            }
+                            Err(e) => {
+                                error!("Failed to send channel message: {e}");
+                                app_data.channel_error = Some(tr!("送信に失敗しました: {}", e));
+                                app_data.report_error(tr!("チャンネルにメッセージを送信できませんでした: {}", e));
+                                app_data.channel_message_input = content;
+                            }
+                        }
+                        app_data.is_sending_channel_message = false;
+                        app_data.should_repaint = true;
+                    });
+                }
+            });
+        });
+    });
+
+    if let Some(pubkey) = user_to_mute {
+        app_data.channel_prefs.muted_users.insert(pubkey);
+        prefs_changed = true;
+        if let (Some(client), Some(keys)) = (app_data.nostr_client.clone(), app_data.my_signer.clone()) {
+            let app_data_arc = app_data_arc.clone();
+            runtime_handle.spawn(async move {
+                if let Err(e) = mute_channel_user(&client, &keys, pubkey).await {
+                    error!("Failed to publish channel mute: {e}");
+                    app_data_arc.lock().unwrap().report_error(tr!("ミュートの公開に失敗しました: {}", e));
+                }
+            });
+        }
+    }
+    if prefs_changed {
+        save_channel_prefs(app_data);
+        app_data.should_repaint = true;
+    }
+
+    if let Some(error) = &app_data.channel_error {
+        ui.add_space(10.0);
+        ui.colored_label(egui::Color32::RED, error);
+    }
+
+    image_cache::load_images(ctx, app_data, app_data_arc, &runtime_handle, urls_to_load);
+}
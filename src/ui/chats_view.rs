@@ -0,0 +1,516 @@
+use chrono::{DateTime, Local};
+use eframe::egui;
+use nostr::{PublicKey, Timestamp};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::error;
+
+use crate::{
+    call,
+    dm::{preferred_protocol, push_chat_message, send_read_receipt, send_typing, sign_dm},
+    i18n::tr,
+    media,
+    outbox,
+    pins,
+    read_state,
+    types::*,
+    ui::{attachment, emoji, emoji_picker, image_cache, markdown, timestamp},
+    voice,
+    wot,
+};
+
+// 入力中を知らせる間隔
+const TYPING_SEND_INTERVAL: Duration = Duration::from_secs(3);
+// ボイスメッセージの再生ボタン。アプリの中で再生できないビルドでは、既定のプレイヤーで開く
+fn draw_audio_clip(ui: &mut egui::Ui, clip: &AudioClip) {
+    let voice_message_text = tr!("ボイスメッセージ");
+    let play_hover_text = if voice::is_supported() { tr!("再生") } else { tr!("既定のプレイヤーで再生") };
+    let stop_hover_text = tr!("停止");
+
+    let is_playing = voice::is_playing(&clip.url);
+    let icon = if is_playing { "■" } else { "▶" };
+    let label = match clip.duration_secs {
+        Some(duration) => {
+            let seconds = duration.round() as u64;
+            format!("{icon} {voice_message_text} {}:{:02}", seconds / 60, seconds % 60)
+        }
+        None => format!("{icon} {voice_message_text}"),
+    };
+    if ui.button(label).on_hover_text(if is_playing { stop_hover_text } else { play_hover_text }).clicked() {
+        voice::toggle_playback(ui.ctx(), &clip.url);
+    }
+}
+
+// 相手の入力中の表示を消すまでの時間
+const TYPING_DISPLAY_TIMEOUT: Duration = Duration::from_secs(6);
+// スタンプ (絵文字1つだけのメッセージ) の大きさ
+const STICKER_SIZE: f32 = 120.0;
+
+pub fn to_local(timestamp: Timestamp) -> DateTime<Local> {
+    DateTime::from_timestamp(timestamp.as_u64() as i64, 0)
+        .unwrap_or_default()
+        .with_timezone(&Local)
+}
+
+// LINEのように日付が変わるところに区切りを入れる
+pub fn draw_date_separator(ui: &mut egui::Ui, date: &DateTime<Local>) {
+    let text = timestamp::format_date(date);
+    ui.add_space(6.0);
+    ui.vertical_centered(|ui| {
+        egui::Frame::new()
+            .fill(ui.visuals().extreme_bg_color)
+            .corner_radius(10.0)
+            .inner_margin(egui::Margin::symmetric(10, 2))
+            .show(ui, |ui| {
+                ui.label(egui::RichText::new(text).small().color(egui::Color32::GRAY));
+            });
+    });
+    ui.add_space(6.0);
+}
+
+// 吹き出しに表示するメッセージ。DMとグループで共通に使う
+pub struct BubbleMessage<'a> {
+    pub content: &'a str,
+    pub created_at: Timestamp,
+    pub is_mine: bool,
+    pub sender: &'a PublicKey,
+    pub sender_picture: &'a str,
+    pub show_avatar: bool,
+    // 自分のメッセージを相手が読んだ
+    pub is_read: bool,
+    pub emojis: &'a HashMap<String, String>,
+    pub images: &'a [String],
+    pub audio_clips: &'a [AudioClip],
+}
+
+// 自分のメッセージは右寄せ、相手のメッセージはアバター付きで左寄せの吹き出しにする
+pub fn draw_message_bubble(
+    ui: &mut egui::Ui,
+    message: BubbleMessage,
+    theme: AppTheme,
+    image_cache: &std::collections::HashMap<String, ImageState>,
+    urls_to_load: &mut Vec<(String, ImageKind)>,
+) {
+    let read_text = tr!("既読");
+    let time_text = egui::RichText::new(to_local(message.created_at).format("%H:%M").to_string())
+        .small()
+        .color(egui::Color32::GRAY);
+    let max_bubble_width = ui.available_width() * 0.65;
+    let (layout, fill) = if message.is_mine {
+        (egui::Layout::right_to_left(egui::Align::BOTTOM), theme.my_bubble_color())
+    } else {
+        (egui::Layout::left_to_right(egui::Align::BOTTOM), theme.peer_bubble_color())
+    };
+
+    ui.with_layout(layout, |ui| {
+        if !message.is_mine {
+            let avatar_size = egui::vec2(28.0, 28.0);
+            if message.show_avatar {
+                image_cache::draw_user_avatar(
+                    ui,
+                    image_cache,
+                    message.sender,
+                    message.sender_picture,
+                    avatar_size,
+                    14.0,
+                    urls_to_load,
+                );
+            } else {
+                ui.add_space(avatar_size.x + ui.spacing().item_spacing.x);
+            }
+        }
+        // 絵文字1つだけのメッセージは吹き出しなしでスタンプとして大きく出す
+        if let Some(url) = emoji::sticker_url(message.content, message.emojis) {
+            emoji::draw_emoji_image(ui, image_cache, url, egui::vec2(STICKER_SIZE, STICKER_SIZE), urls_to_load);
+        } else {
+            egui::Frame::new()
+                .fill(fill)
+                .corner_radius(12.0)
+                .inner_margin(egui::Margin::symmetric(10, 6))
+                .show(ui, |ui| {
+                    ui.set_max_width(max_bubble_width);
+                    if message.emojis.is_empty() && !markdown::is_enabled() {
+                        ui.add(egui::Label::new(egui::RichText::new(message.content).color(theme.text_color())).wrap());
+                    } else {
+                        ui.horizontal_wrapped(|ui| {
+                            emoji::draw_text_with_emojis(ui, message.content, theme.text_color(), message.emojis, image_cache, urls_to_load);
+                        });
+                    }
+                    image_cache::draw_image_previews(ui, image_cache, message.images, urls_to_load);
+                    for clip in message.audio_clips {
+                        draw_audio_clip(ui, clip);
+                    }
+                });
+        }
+        if message.is_read {
+            ui.vertical(|ui| {
+                ui.label(egui::RichText::new(read_text).small().color(egui::Color32::GRAY));
+                ui.label(time_text).on_hover_text(timestamp::format_absolute(message.created_at));
+            });
+        } else {
+            ui.label(time_text).on_hover_text(timestamp::format_absolute(message.created_at));
+        }
+    });
+    ui.add_space(4.0);
+}
+
+// 最後に読んだ相手のメッセージを添えて既読を送る。NIP-04 のトークでは送らない
+fn spawn_read_receipt(app_data: &NostrStatusAppInternal, runtime_handle: &tokio::runtime::Handle, peer: PublicKey) {
+    let (Some(client), Some(keys)) = (app_data.nostr_client.clone(), app_data.my_signer.clone()) else {
+        return;
+    };
+    let Some(messages) = app_data.conversations.get(&peer) else {
+        return;
+    };
+    if preferred_protocol(messages) != DmProtocol::Nip17 {
+        return;
+    }
+    let Some(last_read) = messages.iter().rev().find(|m| !m.is_mine).map(|m| m.id) else {
+        return;
+    };
+    runtime_handle.spawn(async move {
+        if let Err(e) = send_read_receipt(&client, &keys, peer, last_read).await {
+            error!("Failed to send a read receipt: {e}");
+        }
+    });
+}
+
+// 入力中であることを知らせる。同じ相手には TYPING_SEND_INTERVAL ごとに1回だけ送る
+fn spawn_typing(app_data: &mut NostrStatusAppInternal, runtime_handle: &tokio::runtime::Handle, peer: PublicKey) {
+    if !app_data.settings.send_typing_indicators {
+        return;
+    }
+    if app_data
+        .last_typing_sent
+        .is_some_and(|(sent_to, sent_at)| sent_to == peer && sent_at.elapsed() < TYPING_SEND_INTERVAL)
+    {
+        return;
+    }
+    let (Some(client), Some(keys)) = (app_data.nostr_client.clone(), app_data.my_signer.clone()) else {
+        return;
+    };
+    app_data.last_typing_sent = Some((peer, Instant::now()));
+    runtime_handle.spawn(async move {
+        if let Err(e) = send_typing(&client, &keys, peer).await {
+            error!("Failed to send a typing indicator: {e}");
+        }
+    });
+}
+
+// まだ返信していない、フォロー中の人とつながりのない人とのトーク。ピン留めしたものは除く
+fn is_message_request(app_data: &NostrStatusAppInternal, peer: &PublicKey) -> bool {
+    !app_data.pins.pinned_conversations.contains(peer)
+        && wot::is_stranger(app_data, peer)
+        && !app_data.conversations.get(peer).is_some_and(|messages| messages.iter().any(|message| message.is_mine))
+}
+
+// 会話リストの1行。押すとその会話を開き、右クリックでピン留めできる
+fn draw_conversation_row(
+    ui: &mut egui::Ui,
+    app_data: &mut NostrStatusAppInternal,
+    peer: PublicKey,
+    last_message: Option<ChatMessage>,
+    pin_to_toggle: &mut Option<PublicKey>,
+) {
+    let pin_button_text = tr!("📌 ピン留め");
+    let unpin_button_text = tr!("ピン留めを外す");
+
+    let name = app_data
+        .profiles
+        .get(&peer)
+        .map_or_else(|| short_npub(&peer), |p| p.display_name_or_npub(&peer));
+    let last_message_at = last_message.as_ref().map(|m| m.created_at);
+    let preview = last_message
+        .map(|m| m.content.lines().next().unwrap_or_default().to_string())
+        .unwrap_or_default();
+    let is_selected = app_data.selected_conversation == Some(peer);
+    let unread = read_state::unread_dm_count(app_data, &peer);
+    let is_pinned = app_data.pins.pinned_conversations.contains(&peer);
+    let pin_mark = if is_pinned { "📌 " } else { "" };
+    let time = last_message_at.map(timestamp::format_relative).unwrap_or_default();
+    let label = format!("{pin_mark}{}  {time}\n{preview}", read_state::with_badge(&name, unread));
+    let mut response = ui.selectable_label(is_selected, label);
+    if let Some(last_message_at) = last_message_at {
+        response = response.on_hover_text(timestamp::format_absolute(last_message_at));
+    }
+    if response.clicked() {
+        app_data.selected_conversation = Some(peer);
+    }
+    response.context_menu(|ui| {
+        let pin_button_text = if is_pinned { unpin_button_text } else { pin_button_text };
+        if ui.add_enabled(app_data.pins_loaded, egui::Button::new(pin_button_text)).clicked() {
+            *pin_to_toggle = Some(peer);
+            ui.close();
+        }
+    });
+}
+
+pub fn draw_chats_view(
+    ui: &mut egui::Ui,
+    ctx: &egui::Context,
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: tokio::runtime::Handle,
+) {
+    let chats_heading_text = tr!("トーク");
+    let new_chat_hint_text = tr!("npub1... またはhex公開鍵");
+    let start_chat_button_text = tr!("トークを開始");
+    let no_conversations_text = tr!("まだトークはありません。");
+    let select_conversation_text = tr!("左のリストからトークを選択してください。");
+    let message_hint_text = tr!("メッセージを入力");
+    let send_button_text = tr!("送信");
+    let typing_suffix_text = tr!(" が入力中…");
+    let call_hover_text = tr!("音声通話");
+    let call_unavailable_hover_text = tr!("この環境では音声通話に対応していません");
+    let sticker_hint_text = tr!("絵文字だけを送るとスタンプとして大きく表示されます");
+    let custom_emoji_hover_text = tr!("カスタム絵文字");
+    let message_requests_hint_text = tr!("フォロー中の人とつながりのない人からのトークです。返信するとリストに移ります");
+    let mut pin_to_toggle: Option<PublicKey> = None;
+    let mut urls_to_load: Vec<(String, ImageKind)> = Vec::new();
+
+    let card_frame = egui::Frame {
+        inner_margin: egui::Margin::same(12),
+        corner_radius: 8.0.into(),
+        shadow: eframe::epaint::Shadow::NONE,
+        fill: app_data.current_theme.card_background_color(),
+        ..Default::default()
+    };
+
+    ui.heading(chats_heading_text);
+    ui.add_space(10.0);
+
+    ui.columns(2, |columns| {
+        // --- 会話リスト ---
+        card_frame.show(&mut columns[0], |ui| {
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut app_data.new_chat_pubkey_input)
+                        .desired_width(ui.available_width() - 90.0)
+                        .hint_text(new_chat_hint_text),
+                );
+                if ui.button(start_chat_button_text).clicked() {
+                    match PublicKey::parse(app_data.new_chat_pubkey_input.trim()) {
+                        Ok(pubkey) => {
+                            app_data.conversations.entry(pubkey).or_default();
+                            app_data.selected_conversation = Some(pubkey);
+                            app_data.new_chat_pubkey_input.clear();
+                            app_data.dm_error = None;
+                        }
+                        Err(e) => {
+                            app_data.dm_error = Some(tr!("公開鍵が不正です: {}", e));
+                        }
+                    }
+                }
+            });
+            ui.add_space(10.0);
+
+            // ピン留めしたトークを先頭にし、その中では最新メッセージが新しい順に並べる
+            let mut peers: Vec<(PublicKey, Option<ChatMessage>)> = app_data
+                .conversations
+                .iter()
+                .map(|(peer, messages)| (*peer, messages.last().cloned()))
+                .collect();
+            peers.sort_by_key(|(peer, last)| {
+                (
+                    !app_data.pins.pinned_conversations.contains(peer),
+                    std::cmp::Reverse(last.as_ref().map(|m| m.created_at)),
+                )
+            });
+
+            if peers.is_empty() {
+                ui.label(no_conversations_text);
+            }
+
+            // つながりのない人から届いただけのトークは、メッセージリクエストとして下にまとめる
+            let (requests, peers): (Vec<_>, Vec<_>) = peers.into_iter().partition(|(peer, _)| is_message_request(app_data, peer));
+
+            egui::ScrollArea::vertical()
+                .id_salt("conversation_list_scroll_area")
+                .show(ui, |ui| {
+                    for (peer, last_message) in peers {
+                        draw_conversation_row(ui, app_data, peer, last_message, &mut pin_to_toggle);
+                    }
+                    if !requests.is_empty() {
+                        ui.add_space(5.0);
+                        let unread: usize = requests.iter().map(|(peer, _)| read_state::unread_dm_count(app_data, peer)).sum();
+                        egui::CollapsingHeader::new(read_state::with_badge(&tr!("メッセージリクエスト {}件", requests.len()), unread))
+                            .id_salt("message_requests")
+                            .default_open(false)
+                            .show(ui, |ui| {
+                                ui.small(message_requests_hint_text);
+                                for (peer, last_message) in requests {
+                                    draw_conversation_row(ui, app_data, peer, last_message, &mut pin_to_toggle);
+                                }
+                            });
+                    }
+                });
+        });
+
+        // --- メッセージ表示と入力 ---
+        card_frame.show(&mut columns[1], |ui| {
+            let Some(peer) = app_data.selected_conversation else {
+                ui.label(select_conversation_text);
+                return;
+            };
+            // 開いている会話は既読にし、NIP-17 のトークなら相手にも知らせる
+            if read_state::mark_conversation_read(app_data, peer) && app_data.settings.send_read_receipts {
+                spawn_read_receipt(app_data, &runtime_handle, peer);
+            }
+
+            let name = app_data
+                .profiles
+                .get(&peer)
+                .map_or_else(|| short_npub(&peer), |p| p.display_name_or_npub(&peer));
+            ui.horizontal(|ui| {
+                ui.label(egui::RichText::new(&name).strong());
+                let typing_elapsed = app_data.dm_peer_typing.get(&peer).map(|at| at.elapsed());
+                if let Some(elapsed) = typing_elapsed.filter(|elapsed| *elapsed < TYPING_DISPLAY_TIMEOUT) {
+                    ui.label(egui::RichText::new(format!("{name}{typing_suffix_text}")).small().italics().color(egui::Color32::GRAY));
+                    ctx.request_repaint_after(TYPING_DISPLAY_TIMEOUT - elapsed);
+                }
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    let can_call = app_data.call_backend.is_available() && app_data.call.is_none();
+                    let hover_text = if app_data.call_backend.is_available() { call_hover_text } else { call_unavailable_hover_text };
+                    if ui.add_enabled(can_call, egui::Button::new("📞")).on_hover_text(hover_text).on_disabled_hover_text(hover_text).clicked() {
+                        call::start_call(app_data, &runtime_handle, peer);
+                    }
+                });
+            });
+            ui.separator();
+
+            let messages = app_data.conversations.get(&peer).cloned().unwrap_or_default();
+            let peer_picture = app_data
+                .profiles
+                .get(&peer)
+                .map(|p| p.picture.clone())
+                .unwrap_or_default();
+            let peer_read_at = app_data.dm_peer_read_at.get(&peer).copied();
+            // 会話ごとにスクロール状態を分け、開いたときに最新メッセージが見えるようにする
+            egui::ScrollArea::vertical()
+                .id_salt(("conversation_messages_scroll_area", peer))
+                .max_height(ui.available_height() - 60.0)
+                .stick_to_bottom(true)
+                .auto_shrink([false, false])
+                .show(ui, |ui| {
+                    let mut previous: Option<&ChatMessage> = None;
+                    for message in &messages {
+                        let local = to_local(message.created_at);
+                        if previous.is_none_or(|p| to_local(p.created_at).date_naive() != local.date_naive()) {
+                            draw_date_separator(ui, &local);
+                        }
+                        let show_avatar = previous.is_none_or(|p| p.is_mine || p.sender != message.sender);
+                        draw_message_bubble(
+                            ui,
+                            BubbleMessage {
+                                content: &message.content,
+                                created_at: message.created_at,
+                                is_mine: message.is_mine,
+                                sender: &message.sender,
+                                sender_picture: &peer_picture,
+                                show_avatar,
+                                is_read: message.is_mine && peer_read_at.is_some_and(|read_at| message.created_at <= read_at),
+                                emojis: &message.emojis,
+                                images: &message.images,
+                                audio_clips: &message.audio_clips,
+                            },
+                            app_data.current_theme,
+                            &app_data.image_cache,
+                            &mut urls_to_load,
+                        );
+                        previous = Some(message);
+                    }
+                });
+
+            ui.separator();
+            if app_data.show_chat_emoji_picker {
+                ui.label(egui::RichText::new(sticker_hint_text).small().color(egui::Color32::GRAY));
+                egui::ScrollArea::vertical()
+                    .id_salt("chat_emoji_picker_scroll_area")
+                    .max_height(120.0)
+                    .show(ui, |ui| {
+                        if let Some(shortcode) = emoji::draw_emoji_grid(ui, &app_data.my_emojis, &app_data.image_cache, egui::vec2(40.0, 40.0), &mut urls_to_load) {
+                            app_data.dm_message_input.push_str(&format!(":{shortcode}:"));
+                        }
+                    });
+                ui.separator();
+            }
+            attachment::draw_attach_panel(ui, app_data, app_data_arc.clone(), &runtime_handle, AttachTarget::Chat);
+            ui.horizontal(|ui| {
+                emoji_picker::draw_emoji_picker_button(ui, app_data, EmojiTarget::Chat);
+                if ui.selectable_label(app_data.show_chat_emoji_picker, "😀").on_hover_text(custom_emoji_hover_text).clicked() {
+                    app_data.show_chat_emoji_picker = !app_data.show_chat_emoji_picker;
+                }
+                attachment::draw_attach_button(ui, app_data, AttachTarget::Chat);
+                let input_response = ui.add(
+                    egui::TextEdit::singleline(&mut app_data.dm_message_input)
+                        .desired_width(ui.available_width() - 60.0)
+                        .hint_text(message_hint_text),
+                );
+                if input_response.changed() && !app_data.dm_message_input.is_empty() {
+                    spawn_typing(app_data, &runtime_handle, peer);
+                }
+                let can_send = !app_data.is_sending_dm && !app_data.dm_message_input.trim().is_empty();
+                if ui.add_enabled(can_send, egui::Button::new(send_button_text)).clicked() {
+                    let (Some(client), Some(keys)) = (app_data.nostr_client.clone(), app_data.my_signer.clone()) else {
+                        return;
+                    };
+                    let content = std::mem::take(&mut app_data.dm_message_input);
+                    let protocol = preferred_protocol(&messages);
+                    let my_emojis = app_data.my_emojis.clone();
+                    let imeta_tags = media::imeta_tags(&content, &app_data.uploaded_media);
+                    app_data.show_chat_emoji_picker = false;
+                    app_data.is_sending_dm = true;
+                    app_data.should_repaint = true;
+
+                    let cloned_app_data_arc = app_data_arc.clone();
+                    runtime_handle.spawn(async move {
+                        let (message, events) = match sign_dm(&keys, peer, &content, protocol, &my_emojis, imeta_tags).await {
+                            Ok(signed) => signed,
+                            Err(e) => {
+                                error!("Failed to sign DM: {e}");
+                                let mut app_data = cloned_app_data_arc.lock().unwrap();
+                                app_data.dm_error = Some(tr!("送信に失敗しました: {}", e));
+                                app_data.report_error(tr!("メッセージを送信できませんでした: {}", e));
+                                app_data.dm_message_input = content;
+                                app_data.is_sending_dm = false;
+                                app_data.should_repaint = true;
+                                return;
+                            }
+                        };
+                        let result = outbox::send_or_queue_with_undo(&client, events, &cloned_app_data_arc, tr!("メッセージを送信します")).await;
+                        let mut app_data = cloned_app_data_arc.lock().unwrap();
+                        match result {
+                            // 取り消したメッセージは入力欄に戻す。ロックやログアウトのあとは戻さない
+                            None => {
+                                let same_account = app_data.my_signer.as_ref().is_some_and(|signer| signer.public_key() == keys.public_key());
+                                if same_account && app_data.dm_message_input.is_empty() {
+                                    app_data.dm_message_input = content;
+                                }
+                            }
+                            Some(sent) => {
+                                push_chat_message(&mut app_data.conversations, peer, message);
+                                app_data.dm_error = None;
+                                if !sent {
+                                    app_data.report_error(tr!("メッセージを送信できませんでした。送信キューに残し、接続が戻ったら再送します"));
+                                }
+                            }
+                        }
+                        app_data.is_sending_dm = false;
+                        app_data.should_repaint = true;
+                    });
+                }
+            });
+        });
+    });
+
+    if let Some(error) = &app_data.dm_error {
+        ui.add_space(10.0);
+        ui.colored_label(egui::Color32::RED, error);
+    }
+
+    if let Some(peer) = pin_to_toggle {
+        pins::toggle_pinned_conversation(app_data, app_data_arc.clone(), &runtime_handle, peer);
+    }
+    image_cache::load_images(ctx, app_data, app_data_arc, &runtime_handle, urls_to_load);
+}
@@ -0,0 +1,313 @@
+use eframe::egui;
+use nostr::PublicKey;
+use std::sync::{Arc, Mutex};
+use tracing::error;
+
+use crate::{
+    communities::{approve_post, fetch_communities, fetch_community_posts, submit_post},
+    i18n::tr,
+    nostr_client::fetch_profiles_for_pubkeys,
+    types::*,
+    ui::{image_cache, link_view, timestamp},
+};
+
+fn spawn_fetch_communities(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+) {
+    let Some(client) = app_data.nostr_client.clone() else {
+        return;
+    };
+    app_data.is_loading_communities = true;
+    app_data.should_repaint = true;
+    runtime_handle.spawn(async move {
+        let result = fetch_communities(&client).await;
+        let mut app_data = app_data_arc.lock().unwrap();
+        match result {
+            Ok(communities) => app_data.communities = communities,
+            Err(e) => {
+                error!("Failed to fetch communities: {e}");
+                app_data.report_error(tr!("コミュニティ一覧の取得に失敗しました: {}", e));
+            }
+        }
+        app_data.is_loading_communities = false;
+        app_data.should_repaint = true;
+    });
+}
+
+// 選んだコミュニティの投稿と承認を読み込み、知らない投稿者のプロフィールも取得する
+fn spawn_fetch_community_posts(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    community: CommunityInfo,
+) {
+    let Some(client) = app_data.nostr_client.clone() else {
+        return;
+    };
+    app_data.is_loading_community_posts = true;
+    app_data.should_repaint = true;
+    runtime_handle.spawn(async move {
+        let result = fetch_community_posts(&client, &community).await;
+        let unknown: Vec<PublicKey> = {
+            let mut app_data = app_data_arc.lock().unwrap();
+            // 読み込み中に別のコミュニティが選ばれていたら結果は使わない
+            if app_data.selected_community.as_ref() != Some(&community) {
+                return;
+            }
+            app_data.is_loading_community_posts = false;
+            app_data.should_repaint = true;
+            match result {
+                Ok(posts) => {
+                    let unknown = posts
+                        .iter()
+                        .map(|post| post.event.pubkey)
+                        .chain([community.coordinate.public_key])
+                        .filter(|pubkey| !app_data.profiles.contains_key(pubkey))
+                        .collect::<std::collections::HashSet<_>>()
+                        .into_iter()
+                        .collect();
+                    app_data.community_posts = posts;
+                    unknown
+                }
+                Err(e) => {
+                    error!("Failed to fetch community posts: {e}");
+                    app_data.report_error(tr!("コミュニティの投稿を取得できませんでした: {}", e));
+                    return;
+                }
+            }
+        };
+        if unknown.is_empty() {
+            return;
+        }
+        match fetch_profiles_for_pubkeys(&client, unknown).await {
+            Ok(profiles) => {
+                let mut app_data = app_data_arc.lock().unwrap();
+                app_data.profiles.extend(profiles);
+                app_data.should_repaint = true;
+            }
+            Err(e) => error!("Failed to fetch community author profiles: {e}"),
+        }
+    });
+}
+
+pub fn draw_communities_view(
+    ui: &mut egui::Ui,
+    ctx: &egui::Context,
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: tokio::runtime::Handle,
+) {
+    let communities_heading_text = tr!("コミュニティ");
+    let refresh_button_text = tr!("更新");
+    let no_communities_text = tr!("コミュニティが見つかりません。");
+    let select_community_text = tr!("左のリストからコミュニティを選択してください。");
+    let moderator_text = tr!("モデレーター");
+    let show_pending_text = tr!("承認待ちの投稿も表示");
+    let pending_text = tr!("承認待ち");
+    let approve_button_text = tr!("承認");
+    let no_posts_text = tr!("承認された投稿はまだありません");
+    let post_hint_text = tr!("コミュニティに投稿");
+    let post_button_text = tr!("投稿");
+    let moderation_hint_text = tr!("投稿はモデレーターが承認すると、ほかの人にも表示されます");
+    let mut urls_to_load: Vec<(String, ImageKind)> = Vec::new();
+    let mut link_to_open: Option<NostrLink> = None;
+
+    let card_frame = egui::Frame {
+        inner_margin: egui::Margin::same(12),
+        corner_radius: 8.0.into(),
+        shadow: eframe::epaint::Shadow::NONE,
+        fill: app_data.current_theme.card_background_color(),
+        ..Default::default()
+    };
+
+    // 初回表示時にコミュニティ一覧を読み込む
+    if !app_data.communities_loaded && app_data.nostr_client.is_some() {
+        app_data.communities_loaded = true;
+        spawn_fetch_communities(app_data, app_data_arc.clone(), &runtime_handle);
+    }
+
+    // 選択中のコミュニティが変わったら投稿を読み込み直す
+    let selected_key = app_data.selected_community.as_ref().map(|community| community.coordinate.to_string());
+    if selected_key != app_data.community_posts_key {
+        app_data.community_posts_key = selected_key;
+        app_data.community_posts.clear();
+        app_data.community_show_pending = false;
+        if let Some(community) = app_data.selected_community.clone() {
+            spawn_fetch_community_posts(app_data, app_data_arc.clone(), &runtime_handle, community);
+        }
+    }
+
+    let my_pubkey = app_data.my_signer.as_ref().map(|signer| signer.public_key());
+
+    ui.horizontal(|ui| {
+        ui.heading(communities_heading_text);
+        if ui.add_enabled(!app_data.is_loading_communities, egui::Button::new(refresh_button_text)).clicked() {
+            spawn_fetch_communities(app_data, app_data_arc.clone(), &runtime_handle);
+            if let Some(community) = app_data.selected_community.clone() {
+                spawn_fetch_community_posts(app_data, app_data_arc.clone(), &runtime_handle, community);
+            }
+        }
+        if app_data.is_loading_communities || app_data.is_loading_community_posts {
+            ui.spinner();
+        }
+    });
+    ui.add_space(10.0);
+
+    let mut to_approve: Option<CommunityPost> = None;
+
+    ui.columns(2, |columns| {
+        // --- コミュニティ一覧 ---
+        card_frame.show(&mut columns[0], |ui| {
+            if app_data.communities.is_empty() && !app_data.is_loading_communities {
+                ui.label(no_communities_text);
+            }
+            egui::ScrollArea::vertical()
+                .id_salt("community_list_scroll_area")
+                .show(ui, |ui| {
+                    for community in &app_data.communities {
+                        let is_selected = app_data.selected_community.as_ref() == Some(community);
+                        ui.horizontal(|ui| {
+                            image_cache::draw_avatar(
+                                ui,
+                                &app_data.image_cache,
+                                &community.image,
+                                ImageKind::Avatar,
+                                egui::vec2(24.0, 24.0),
+                                4.0,
+                                &mut urls_to_load,
+                            );
+                            let response = ui.selectable_label(is_selected, &community.name);
+                            let response = if community.description.is_empty() { response } else { response.on_hover_text(&community.description) };
+                            if response.clicked() {
+                                app_data.selected_community = Some(community.clone());
+                            }
+                            if my_pubkey.is_some_and(|pubkey| community.is_moderator(&pubkey)) {
+                                ui.label(egui::RichText::new(moderator_text).small().color(egui::Color32::GRAY));
+                            }
+                        });
+                    }
+                });
+        });
+
+        // --- コミュニティの投稿 ---
+        card_frame.show(&mut columns[1], |ui| {
+            let Some(community) = app_data.selected_community.clone() else {
+                ui.label(select_community_text);
+                return;
+            };
+            let is_moderator = my_pubkey.is_some_and(|pubkey| community.is_moderator(&pubkey));
+
+            ui.horizontal(|ui| {
+                image_cache::draw_avatar(
+                    ui,
+                    &app_data.image_cache,
+                    &community.image,
+                    ImageKind::Avatar,
+                    egui::vec2(40.0, 40.0),
+                    6.0,
+                    &mut urls_to_load,
+                );
+                ui.vertical(|ui| {
+                    ui.label(egui::RichText::new(&community.name).strong());
+                    let owner = &community.coordinate.public_key;
+                    let owner_name = app_data.profiles.get(owner).map_or_else(|| short_npub(owner), |p| p.display_name_or_npub(owner));
+                    ui.label(
+                        egui::RichText::new(tr!("作成者: {}・モデレーター {}人", owner_name, community.moderators.len()))
+                            .small()
+                            .color(egui::Color32::GRAY),
+                    );
+                });
+            });
+            if !community.description.is_empty() {
+                ui.label(egui::RichText::new(&community.description).small().color(egui::Color32::GRAY));
+            }
+            if is_moderator {
+                ui.checkbox(&mut app_data.community_show_pending, show_pending_text);
+            }
+            ui.separator();
+
+            // 承認された投稿と自分の投稿を出す。モデレーターは承認待ちのものも見られる
+            let posts: Vec<CommunityPost> = app_data
+                .community_posts
+                .iter()
+                .filter(|post| post.approved || (is_moderator && app_data.community_show_pending) || Some(post.event.pubkey) == my_pubkey)
+                .cloned()
+                .collect();
+            egui::ScrollArea::vertical()
+                .id_salt(("community_posts_scroll_area", community.coordinate.to_string()))
+                .max_height(ui.available_height() - 110.0)
+                .auto_shrink([false, false])
+                .show(ui, |ui| {
+                    if posts.is_empty() && !app_data.is_loading_community_posts {
+                        ui.label(egui::RichText::new(no_posts_text).color(egui::Color32::GRAY));
+                    }
+                    for post in &posts {
+                        let author = &post.event.pubkey;
+                        let metadata = app_data.profiles.get(author).cloned().unwrap_or_default();
+                        ui.horizontal(|ui| {
+                            image_cache::draw_user_avatar(ui, &app_data.image_cache, author, &metadata.picture, egui::vec2(24.0, 24.0), 4.0, &mut urls_to_load);
+                            if ui.link(egui::RichText::new(metadata.display_name_or_npub(author)).strong()).clicked() {
+                                link_to_open = Some(NostrLink::Profile(*author));
+                            }
+                            timestamp::draw_timestamp(ui, post.event.created_at);
+                            if !post.approved {
+                                ui.label(egui::RichText::new(pending_text).small().color(ui.visuals().warn_fg_color));
+                                if is_moderator && ui.small_button(approve_button_text).clicked() {
+                                    to_approve = Some(post.clone());
+                                }
+                            }
+                        });
+                        ui.horizontal_wrapped(|ui| {
+                            link_view::draw_text_with_links(ui, &post.event.content, ui.visuals().text_color(), &app_data.profiles, &mut link_to_open);
+                        });
+                        ui.separator();
+                    }
+                });
+
+            ui.add(
+                egui::TextEdit::multiline(&mut app_data.community_post_input)
+                    .desired_rows(2)
+                    .desired_width(f32::INFINITY)
+                    .hint_text(post_hint_text),
+            );
+            ui.horizontal(|ui| {
+                let can_post = !app_data.is_posting_community && !app_data.community_post_input.trim().is_empty();
+                if ui.add_enabled(can_post, egui::Button::new(post_button_text)).clicked()
+                    && let (Some(client), Some(signer)) = (app_data.nostr_client.clone(), app_data.my_signer.clone()) {
+                    let content = std::mem::take(&mut app_data.community_post_input);
+                    app_data.is_posting_community = true;
+                    app_data.should_repaint = true;
+                    let cloned_app_data_arc = app_data_arc.clone();
+                    runtime_handle.spawn(async move {
+                        let posted = submit_post(client, signer, community, content.clone(), cloned_app_data_arc.clone()).await;
+                        let mut app_data = cloned_app_data_arc.lock().unwrap();
+                        if !posted {
+                            app_data.community_post_input = content;
+                        }
+                        app_data.is_posting_community = false;
+                        app_data.should_repaint = true;
+                    });
+                }
+                if app_data.is_posting_community {
+                    ui.spinner();
+                }
+                if !is_moderator {
+                    ui.small(moderation_hint_text);
+                }
+            });
+        });
+    });
+
+    if let Some(post) = to_approve
+        && let (Some(client), Some(signer), Some(community)) =
+            (app_data.nostr_client.clone(), app_data.my_signer.clone(), app_data.selected_community.clone())
+    {
+        runtime_handle.spawn(approve_post(client, signer, community, post.event, app_data_arc.clone()));
+    }
+    if let Some(link) = link_to_open {
+        link_view::open_link(app_data, app_data_arc.clone(), &runtime_handle, link);
+    }
+    image_cache::load_images(ctx, app_data, app_data_arc, &runtime_handle, urls_to_load);
+}
@@ -0,0 +1,56 @@
+use eframe::egui;
+use nostr::EventId;
+use std::collections::HashSet;
+
+use crate::i18n::tr;
+
+// 投稿に注意書き (NIP-36) を付けるかと、その理由の入力欄
+pub fn draw_content_warning_input(ui: &mut egui::Ui, enabled: &mut bool, reason: &mut String) {
+    let checkbox_text = tr!("注意書きを付ける");
+    let reason_hint_text = tr!("理由 (例: ネタバレ)");
+
+    ui.horizontal(|ui| {
+        ui.checkbox(enabled, checkbox_text);
+        ui.add_enabled(
+            *enabled,
+            egui::TextEdit::singleline(reason)
+                .desired_width(180.0)
+                .hint_text(reason_hint_text),
+        );
+    });
+}
+
+// 注意書きの付いた投稿は本文を隠して理由だけを出す。本文を描いてよいときは true を返す
+// 表示・非表示が押されたら toggle に入れるので、呼び出し側で toggle_revealed に渡す
+pub fn draw_content_warning(
+    ui: &mut egui::Ui,
+    revealed: &HashSet<EventId>,
+    event_id: EventId,
+    reason: Option<&str>,
+    toggle: &mut Option<EventId>,
+) -> bool {
+    let warning_text = tr!("⚠ 注意書きのある投稿");
+    let show_button_text = tr!("内容を表示");
+    let hide_button_text = tr!("隠す");
+
+    let Some(reason) = reason else {
+        return true;
+    };
+    let is_revealed = revealed.contains(&event_id);
+    ui.horizontal_wrapped(|ui| {
+        let label = if reason.is_empty() { warning_text.to_string() } else { format!("{warning_text}: {reason}") };
+        ui.label(egui::RichText::new(label).color(ui.visuals().warn_fg_color));
+        let button_text = if is_revealed { hide_button_text } else { show_button_text };
+        if ui.small_button(button_text).clicked() {
+            *toggle = Some(event_id);
+        }
+    });
+    is_revealed
+}
+
+pub fn toggle_revealed(revealed: &mut HashSet<EventId>, toggle: Option<EventId>) {
+    if let Some(event_id) = toggle
+        && !revealed.remove(&event_id) {
+        revealed.insert(event_id);
+    }
+}
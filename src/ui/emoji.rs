@@ -0,0 +1,105 @@
+use eframe::egui;
+use regex::Regex;
+use std::collections::HashMap;
+
+use crate::i18n::tr;
+use crate::types::{ImageKind, ImageState};
+use crate::ui::markdown;
+
+// カスタム絵文字の画像を1つ描く。まだ読み込んでいなければ urls_to_load に積む
+pub fn draw_emoji_image(
+    ui: &mut egui::Ui,
+    image_cache: &HashMap<String, ImageState>,
+    url: &str,
+    size: egui::Vec2,
+    urls_to_load: &mut Vec<(String, ImageKind)>,
+) -> egui::Response {
+    let (rect, response) = ui.allocate_exact_size(size, egui::Sense::click());
+    match image_cache.get(url) {
+        Some(ImageState::Loaded(texture_handle)) => {
+            egui::Image::new(texture_handle).fit_to_exact_size(size).paint_at(ui, rect);
+        }
+        Some(ImageState::Loading) => {
+            ui.put(rect, egui::Spinner::new());
+        }
+        Some(ImageState::Failed) => {
+            ui.painter().text(rect.center(), egui::Align2::CENTER_CENTER, "💔", egui::FontId::default(), ui.visuals().error_fg_color);
+        }
+        None => {
+            if !urls_to_load.iter().any(|(u, _)| u == url) {
+                urls_to_load.push((url.to_string(), ImageKind::Emoji));
+            }
+            ui.put(rect, egui::Spinner::new());
+        }
+    }
+    response
+}
+
+// 本文が :shortcode: 1つだけで、その絵文字があればスタンプとして大きく表示する
+pub fn sticker_url<'a>(content: &str, emojis: &'a HashMap<String, String>) -> Option<&'a String> {
+    let re = Regex::new(r"^:(\w+):$").unwrap();
+    let shortcode = re.captures(content.trim())?.get(1)?.as_str().to_string();
+    emojis.get(&shortcode)
+}
+
+// :shortcode: を絵文字の画像にして本文を描く。ほかの部分は書式 (markdown) を付けて描く。呼び出し側で horizontal_wrapped の中に置く
+pub fn draw_text_with_emojis(
+    ui: &mut egui::Ui,
+    text: &str,
+    color: egui::Color32,
+    emojis: &HashMap<String, String>,
+    image_cache: &HashMap<String, ImageState>,
+    urls_to_load: &mut Vec<(String, ImageKind)>,
+) {
+    let re = Regex::new(r":(\w+):").unwrap();
+    let found: Vec<(std::ops::Range<usize>, &String)> = re
+        .captures_iter(text)
+        .filter_map(|cap| Some((cap.get(0).unwrap().range(), emojis.get(cap.get(1).unwrap().as_str())?)))
+        .collect();
+    // :snake_case: のような shortcode の中の _ は書式として扱わない
+    let emoji_ranges: Vec<std::ops::Range<usize>> = found.iter().map(|(range, _)| range.clone()).collect();
+    let spans = markdown::parse(text, &emoji_ranges);
+    let mut last_end = 0;
+    for (range, url) in found {
+        markdown::draw_spans(ui, text, &spans, last_end..range.start, color);
+        draw_emoji_image(ui, image_cache, url, egui::vec2(20.0, 20.0), urls_to_load);
+        last_end = range.end;
+    }
+    markdown::draw_spans(ui, text, &spans, last_end..text.len(), color);
+}
+
+// 手元のカスタム絵文字を並べる。押された shortcode を返す
+pub fn draw_emoji_grid(
+    ui: &mut egui::Ui,
+    emojis: &HashMap<String, String>,
+    image_cache: &HashMap<String, ImageState>,
+    size: egui::Vec2,
+    urls_to_load: &mut Vec<(String, ImageKind)>,
+) -> Option<String> {
+    let no_emojis_text = tr!("カスタム絵文字が設定されていません。");
+
+    if emojis.is_empty() {
+        ui.label(no_emojis_text);
+        return None;
+    }
+    let mut shortcodes: Vec<&String> = emojis.keys().collect();
+    shortcodes.sort();
+    let mut picked = None;
+    ui.with_layout(egui::Layout::left_to_right(egui::Align::TOP).with_main_wrap(true), |ui| {
+        for shortcode in shortcodes {
+            let response = draw_emoji_image(ui, image_cache, &emojis[shortcode], size, urls_to_load);
+            if response.hovered() {
+                ui.painter().rect_stroke(
+                    response.rect.expand(2.0),
+                    egui::CornerRadius::from(4.0),
+                    ui.visuals().widgets.hovered.bg_stroke,
+                    egui::StrokeKind::Outside,
+                );
+            }
+            if response.on_hover_text(format!(":{shortcode}:")).clicked() {
+                picked = Some(shortcode.clone());
+            }
+        }
+    });
+    picked
+}
@@ -0,0 +1,274 @@
+use eframe::egui;
+
+use crate::i18n::tr;
+use crate::settings::save_settings;
+use crate::types::{EmojiTarget, NostrStatusAppInternal};
+
+// 最近使った絵文字として覚えておく数
+const MAX_RECENT_EMOJIS: usize = 16;
+const EMOJI_BUTTON_SIZE: f32 = 28.0;
+const PICKER_WIDTH: f32 = 300.0;
+
+// 選べる絵文字と、検索に使う英語と日本語の言葉
+const EMOJIS: &[(&str, &str)] = &[
+    // 顔
+    ("😀", "grinning smile happy 笑顔 にっこり"),
+    ("😃", "smiley smile happy 笑顔"),
+    ("😄", "smile laugh happy 笑顔 わらい"),
+    ("😁", "grin beaming にやり 笑顔"),
+    ("😆", "laughing xd 爆笑 わらい"),
+    ("😅", "sweat smile 苦笑い 汗"),
+    ("🤣", "rofl rolling laughing 爆笑"),
+    ("😂", "joy tears laughing 泣き笑い 爆笑"),
+    ("🙂", "slightly smiling 微笑み"),
+    ("😉", "wink ウインク"),
+    ("😊", "blush smiling 照れ 笑顔"),
+    ("😇", "innocent halo angel 天使"),
+    ("🥰", "smiling hearts love 好き ハート"),
+    ("😍", "heart eyes love 好き 目がハート"),
+    ("🤩", "star struck excited すごい キラキラ"),
+    ("😘", "kiss love キス"),
+    ("😋", "yum delicious おいしい"),
+    ("😛", "tongue べー"),
+    ("😜", "winking tongue crazy おどけ"),
+    ("🤪", "zany crazy おかしい"),
+    ("🤔", "thinking hmm 考え中 うーん"),
+    ("🤗", "hug hugging ハグ"),
+    ("🤫", "shush quiet 静かに ないしょ"),
+    ("🤭", "giggle hand over mouth くすくす"),
+    ("😐", "neutral 無表情"),
+    ("😑", "expressionless 真顔"),
+    ("😶", "no mouth speechless 無言"),
+    ("😏", "smirk どや にやり"),
+    ("😒", "unamused 不満"),
+    ("🙄", "eye roll あきれ"),
+    ("😬", "grimace 気まずい"),
+    ("😌", "relieved ほっと 安心"),
+    ("😔", "pensive しょんぼり"),
+    ("😪", "sleepy 眠い"),
+    ("😴", "sleeping zzz 寝る おやすみ"),
+    ("😷", "mask sick マスク 風邪"),
+    ("🤒", "thermometer sick 熱 病気"),
+    ("🥵", "hot 暑い"),
+    ("🥶", "cold 寒い"),
+    ("😵", "dizzy くらくら"),
+    ("🤯", "mind blown 衝撃"),
+    ("🥳", "party celebrate パーティー お祝い"),
+    ("😎", "cool sunglasses かっこいい サングラス"),
+    ("🤓", "nerd オタク"),
+    ("😕", "confused 困惑"),
+    ("😟", "worried 心配"),
+    ("😮", "open mouth surprised おどろき"),
+    ("😲", "astonished びっくり"),
+    ("🥺", "pleading puppy eyes うるうる お願い"),
+    ("😢", "cry sad 悲しい 涙"),
+    ("😭", "sob crying 号泣"),
+    ("😱", "scream fear 恐怖 ぎゃー"),
+    ("😤", "triumph huff ふんっ"),
+    ("😡", "angry rage 怒り"),
+    ("😠", "angry 怒る"),
+    ("🤬", "cursing 激怒"),
+    ("😈", "devil smiling 悪魔"),
+    ("💀", "skull dead ドクロ 死んだ"),
+    ("💩", "poop うんち"),
+    ("🤡", "clown ピエロ"),
+    ("👻", "ghost おばけ"),
+    ("👽", "alien 宇宙人"),
+    ("🤖", "robot ロボット"),
+    ("😺", "cat smile 猫 ねこ"),
+    // 手と人
+    ("👍", "thumbs up like good いいね"),
+    ("👎", "thumbs down bad よくない"),
+    ("👏", "clap applause 拍手"),
+    ("🙌", "raised hands hooray ばんざい"),
+    ("🙏", "pray please thanks お願い ありがとう"),
+    ("🤝", "handshake 握手"),
+    ("👋", "wave hello bye 手を振る こんにちは バイバイ"),
+    ("✌️", "victory peace ピース"),
+    ("🤞", "crossed fingers luck 幸運"),
+    ("👌", "ok okay オーケー"),
+    ("🤙", "call me shaka 電話して"),
+    ("💪", "muscle strong 筋肉 がんばる"),
+    ("👀", "eyes look 目 見てる"),
+    ("🫡", "salute 敬礼 了解"),
+    ("🙇", "bow 土下座 お辞儀"),
+    ("🤷", "shrug さあ わからない"),
+    ("🙆", "ok gesture オッケー"),
+    ("🙅", "no gesture ダメ"),
+    // ハートと記号
+    ("❤️", "red heart love 好き ハート"),
+    ("🧡", "orange heart ハート"),
+    ("💛", "yellow heart ハート"),
+    ("💚", "green heart ハート"),
+    ("💙", "blue heart ハート"),
+    ("💜", "purple heart nostr ハート"),
+    ("🖤", "black heart ハート"),
+    ("🤍", "white heart ハート"),
+    ("💔", "broken heart 失恋"),
+    ("💕", "two hearts ハート"),
+    ("💯", "hundred perfect 満点"),
+    ("✨", "sparkles キラキラ"),
+    ("⭐", "star 星"),
+    ("🔥", "fire hot 炎 熱い"),
+    ("⚡", "zap lightning 雷 ザップ"),
+    ("💤", "zzz sleep 眠い"),
+    ("💢", "anger ムカッ"),
+    ("💦", "sweat droplets 汗"),
+    ("💬", "speech balloon 吹き出し"),
+    ("💡", "idea bulb ひらめき"),
+    ("✅", "check done 完了 チェック"),
+    ("❌", "cross no バツ"),
+    ("❓", "question 質問 はてな"),
+    ("❗", "exclamation 注意 びっくり"),
+    ("⚠️", "warning 警告"),
+    ("🎉", "tada party celebrate お祝い クラッカー"),
+    ("🎊", "confetti お祝い くす玉"),
+    ("🎁", "gift present プレゼント"),
+    ("🎂", "birthday cake 誕生日 ケーキ"),
+    ("🎵", "music note 音楽"),
+    ("🎶", "notes music 音楽"),
+    ("🎮", "game video game ゲーム"),
+    ("📷", "camera photo カメラ 写真"),
+    ("📚", "books study 本 勉強"),
+    ("💻", "laptop computer パソコン 仕事"),
+    ("📱", "phone mobile スマホ"),
+    ("📝", "memo note メモ"),
+    ("📌", "pin ピン"),
+    ("🔔", "bell notification 通知"),
+    ("💰", "money moneybag お金"),
+    ("🚀", "rocket launch ロケット"),
+    ("🏠", "home house 家 おうち"),
+    ("🏢", "office work 会社 仕事"),
+    ("🚃", "train 電車 移動中"),
+    ("🚗", "car 車 ドライブ"),
+    ("✈️", "airplane travel 飛行機 旅行"),
+    // 食べ物
+    ("☕", "coffee カフェ コーヒー"),
+    ("🍵", "tea お茶"),
+    ("🍺", "beer ビール 乾杯"),
+    ("🍻", "cheers beers 乾杯"),
+    ("🍷", "wine ワイン"),
+    ("🍙", "rice ball onigiri おにぎり"),
+    ("🍣", "sushi 寿司"),
+    ("🍜", "ramen noodles ラーメン"),
+    ("🍛", "curry カレー"),
+    ("🍕", "pizza ピザ"),
+    ("🍔", "burger hamburger ハンバーガー"),
+    ("🍰", "cake shortcake ケーキ"),
+    ("🍩", "donut ドーナツ"),
+    ("🍎", "apple りんご"),
+    ("🍓", "strawberry いちご"),
+    // 自然と動物
+    ("☀️", "sun sunny 晴れ 太陽"),
+    ("☁️", "cloud cloudy くもり"),
+    ("☔", "umbrella rain 雨"),
+    ("⛄", "snowman snow 雪"),
+    ("🌙", "moon night 月 夜"),
+    ("🌈", "rainbow 虹"),
+    ("🌸", "cherry blossom sakura 桜"),
+    ("🌻", "sunflower ひまわり"),
+    ("🍀", "clover luck 四つ葉"),
+    ("🍁", "maple autumn 紅葉 秋"),
+    ("🐶", "dog 犬 いぬ"),
+    ("🐱", "cat 猫 ねこ"),
+    ("🐰", "rabbit bunny うさぎ"),
+    ("🐻", "bear くま"),
+    ("🐼", "panda パンダ"),
+    ("🐧", "penguin ペンギン"),
+    ("🐸", "frog かえる"),
+    ("🦩", "flamingo フラミンゴ"),
+    ("🦄", "unicorn ユニコーン"),
+    ("🐢", "turtle かめ"),
+    ("🐟", "fish 魚"),
+];
+
+fn target_input(app_data: &mut NostrStatusAppInternal, target: EmojiTarget) -> &mut String {
+    match target {
+        EmojiTarget::Status => &mut app_data.status_message_input,
+        EmojiTarget::Note => &mut app_data.note_input,
+        EmojiTarget::Chat => &mut app_data.dm_message_input,
+    }
+}
+
+fn matches_query(keywords: &str, query: &str) -> bool {
+    query.split_whitespace().all(|word| keywords.contains(&word.to_lowercase()))
+}
+
+// 絵文字を1つ並べる。押されたら true を返す
+fn draw_emoji_button(ui: &mut egui::Ui, emoji: &str) -> bool {
+    let button = egui::Button::new(egui::RichText::new(emoji).size(EMOJI_BUTTON_SIZE * 0.7)).frame(false);
+    ui.add_sized([EMOJI_BUTTON_SIZE, EMOJI_BUTTON_SIZE], button).clicked()
+}
+
+// 押された絵文字を入力欄の最後に足し、最近使った絵文字の先頭に入れて保存する
+fn insert_emoji(app_data: &mut NostrStatusAppInternal, target: EmojiTarget, emoji: &str) {
+    target_input(app_data, target).push_str(emoji);
+    let recent = &mut app_data.settings.recent_emojis;
+    recent.retain(|recent_emoji| recent_emoji != emoji);
+    recent.insert(0, emoji.to_string());
+    recent.truncate(MAX_RECENT_EMOJIS);
+    if let Err(e) = save_settings(&app_data.settings) {
+        app_data.report_error(tr!("設定の保存に失敗しました: {}", e));
+    }
+}
+
+// 絵文字の入力ボタン。押すと検索できる絵文字の一覧をボタンの下に開く
+// OS の絵文字入力を使わなくても、ステータスやトークに絵文字を入れられる
+pub fn draw_emoji_picker_button(ui: &mut egui::Ui, app_data: &mut NostrStatusAppInternal, target: EmojiTarget) {
+    let emoji_hover_text = tr!("絵文字を入力");
+    let search_hint_text = tr!("絵文字を検索 (例: smile、笑顔)");
+    let recent_label_text = tr!("最近使った絵文字");
+    let no_results_text = tr!("見つかりませんでした");
+
+    let button = ui.button("🙂").on_hover_text(emoji_hover_text);
+    let mut picked = None;
+    egui::Popup::from_toggle_button_response(&button)
+        .close_behavior(egui::PopupCloseBehavior::CloseOnClickOutside)
+        .width(PICKER_WIDTH)
+        .show(|ui| {
+            let search = ui.add(
+                egui::TextEdit::singleline(&mut app_data.emoji_search_input)
+                    .hint_text(search_hint_text)
+                    .desired_width(f32::INFINITY),
+            );
+            if button.clicked() {
+                search.request_focus();
+            }
+            let query = app_data.emoji_search_input.trim().to_lowercase();
+
+            if query.is_empty() && !app_data.settings.recent_emojis.is_empty() {
+                ui.label(egui::RichText::new(recent_label_text).small().color(egui::Color32::GRAY));
+                ui.horizontal_wrapped(|ui| {
+                    ui.spacing_mut().item_spacing = egui::vec2(2.0, 2.0);
+                    for emoji in &app_data.settings.recent_emojis {
+                        if draw_emoji_button(ui, emoji) {
+                            picked = Some(emoji.clone());
+                        }
+                    }
+                });
+                ui.separator();
+            }
+
+            egui::ScrollArea::vertical()
+                .id_salt("emoji_picker_scroll_area")
+                .max_height(200.0)
+                .show(ui, |ui| {
+                    let mut found = false;
+                    ui.horizontal_wrapped(|ui| {
+                        ui.spacing_mut().item_spacing = egui::vec2(2.0, 2.0);
+                        for (emoji, _) in EMOJIS.iter().filter(|(_, keywords)| matches_query(keywords, &query)) {
+                            found = true;
+                            if draw_emoji_button(ui, emoji) {
+                                picked = Some(emoji.to_string());
+                            }
+                        }
+                    });
+                    if !found {
+                        ui.label(no_results_text);
+                    }
+                });
+        });
+    if let Some(emoji) = picked {
+        insert_emoji(app_data, target, &emoji);
+    }
+}
@@ -0,0 +1,500 @@
+use eframe::egui;
+use nostr::{nips::nip19::ToBech32, PublicKey, RelayUrl, Timestamp};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tracing::error;
+
+use crate::{
+    camera,
+    cache_db::{DB_CONTACT_PROFILES, DB_FOLLOWED},
+    i18n::tr,
+    settings::save_settings,
+    nostr_client::{fetch_profiles_for_pubkeys, parse_profile_input, update_contact_list},
+    pins,
+    types::*,
+    ui::{image_cache, nip05_badge, qr, timestamp, user_profile_view, zap},
+};
+
+// 選んだ画像から QR コードを読み取り、内容が公開鍵なら相手として出す
+fn spawn_decode_qr_image(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+) {
+    let path = PathBuf::from(app_data.scan_qr_image_input.trim());
+    app_data.is_decoding_qr = true;
+    app_data.should_repaint = true;
+    runtime_handle.spawn_blocking(move || {
+        let result = qr::decode_qr_image(&path).and_then(|content| parse_profile_input(&content));
+        let mut app_data = app_data_arc.lock().unwrap();
+        app_data.is_decoding_qr = false;
+        app_data.should_repaint = true;
+        // 読み取っている間に閉じられていたら結果は使わない
+        if !app_data.show_scan_qr {
+            return;
+        }
+        match result {
+            Ok(profile) => {
+                app_data.scanned_profile = Some(profile);
+                app_data.scan_qr_image_input.clear();
+                app_data.follow_error = None;
+            }
+            Err(e) => {
+                error!("Failed to read the QR code image: {e}");
+                app_data.follow_error = Some(tr!("QR コードの内容を読み取れませんでした: {}", e));
+            }
+        }
+    });
+}
+
+// コンタクトリストを更新して再発行し、結果をキャッシュに書き込む
+pub fn spawn_contact_list_update(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    pubkey: PublicKey,
+    relay_hint: Option<RelayUrl>,
+    follow: bool,
+) {
+    if app_data.is_loading {
+        return;
+    }
+    let (Some(client), Some(keys)) = (app_data.nostr_client.clone(), app_data.my_signer.clone()) else {
+        return;
+    };
+    let cache_db_clone = app_data.cache_db.clone();
+    let session = app_data.session_generation;
+
+    app_data.is_loading = true;
+    app_data.should_repaint = true;
+
+    runtime_handle.spawn(async move {
+        let result = update_contact_list(&client, &keys, pubkey, relay_hint, follow).await;
+        // 送っている間にロックやログアウトをしていたら、画面には反映しない
+        if !app_data_arc.lock().unwrap().is_current_session(session) {
+            return;
+        }
+        match result {
+            Ok(new_followed_pubkeys) => {
+                // 新しくフォローした相手のプロフィールも取得しておく
+                let new_profiles = if follow {
+                    fetch_profiles_for_pubkeys(&client, vec![pubkey]).await.unwrap_or_else(|e| {
+                        error!("Failed to fetch profile for {pubkey}: {e}");
+                        Default::default()
+                    })
+                } else {
+                    Default::default()
+                };
+
+                let mut app_data = app_data_arc.lock().unwrap();
+                app_data.followed_pubkeys = new_followed_pubkeys;
+                app_data.profiles.extend(new_profiles);
+                app_data.follow_error = None;
+                let pubkey_hex = keys.public_key().to_string();
+                if let Err(e) = cache_db_clone.write_cache(DB_FOLLOWED, &pubkey_hex, &app_data.followed_pubkeys) {
+                    error!("Failed to write follow list cache: {e}");
+                }
+                if let Err(e) = cache_db_clone.write_cache(DB_CONTACT_PROFILES, &pubkey_hex, &app_data.profiles) {
+                    error!("Failed to write contact profiles cache: {e}");
+                }
+            }
+            Err(e) => {
+                error!("Failed to update contact list: {e}");
+                let mut app_data = app_data_arc.lock().unwrap();
+                app_data.follow_error = Some(tr!("フォローリストの更新に失敗しました: {}", e));
+                app_data.report_error(tr!("フォローリストの更新に失敗しました: {}", e));
+            }
+        }
+        let mut app_data = app_data_arc.lock().unwrap();
+        app_data.is_loading = false;
+        app_data.should_repaint = true;
+    });
+}
+
+// タイムラインに流れてきたNIP-38ステータスのうち、期限切れでない最新のものと、その時刻を返す
+fn current_status(posts: &[TimelinePost], pubkey: &PublicKey) -> Option<(String, Timestamp)> {
+    let now = Timestamp::now();
+    posts
+        .iter()
+        .filter(|post| post.author_pubkey == *pubkey && !post.content.is_empty())
+        .filter(|post| {
+            !post.tags.iter().any(|tag| {
+                matches!(tag.as_standardized(), Some(nostr::TagStandard::Expiration(expiration)) if *expiration < now)
+            })
+        })
+        .max_by_key(|post| post.created_at)
+        .map(|post| (post.content.clone(), post.created_at))
+}
+
+pub fn draw_friends_view(
+    ui: &mut egui::Ui,
+    ctx: &egui::Context,
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: tokio::runtime::Handle,
+) {
+    let friends_heading_text = tr!("友だち");
+    let no_friends_text = tr!("まだ誰もフォローしていません。");
+    let open_chat_button_text = tr!("トーク");
+    let no_status_text = tr!("ステータスなし");
+    let zap_button_text = "⚡ ZAP";
+    let follow_hint_text = "npub1... / nprofile1...";
+    let follow_button_text = tr!("フォロー");
+    let unfollow_button_text = tr!("アンフォロー");
+    let star_hover_text = tr!("星を付けると新しいステータスを通知します");
+    let unstar_hover_text = tr!("星を外す");
+    let favorite_hover_text = tr!("お気に入りにするとリストの先頭に並びます");
+    let unfavorite_hover_text = tr!("お気に入りから外す");
+    // カメラがないビルドでは画像ファイルから読むだけなので、そのように見せる
+    let scan_qr_button_text = if camera::is_supported() { tr!("📷 QR を読み取る") } else { tr!("🖼 QR を読み取る") };
+    let camera_button_text = tr!("📷 カメラで読み取る");
+    let stop_camera_button_text = tr!("カメラを止める");
+    let scan_qr_hint_text = tr!("QR コードの画像ファイルを選ぶか、ウィンドウにドロップしてください。読み取った内容 (nostr:npub1... など) を貼り付けることもできます");
+    let qr_image_hint_text = tr!("QR コードの画像ファイルのパス");
+    let read_image_button_text = tr!("画像から読み取る");
+    let read_scanned_button_text = tr!("読み取る");
+    let scanned_heading_text = tr!("QR コードの相手");
+    let follow_and_chat_button_text = tr!("フォローしてトーク");
+    let already_following_text = tr!("フォロー中");
+    let view_profile_button_text = tr!("プロフィール");
+    let close_button_text = tr!("閉じる");
+    let mut urls_to_load: Vec<(String, ImageKind)> = Vec::new();
+
+    let card_frame = egui::Frame {
+        inner_margin: egui::Margin::same(12),
+        corner_radius: 8.0.into(),
+        shadow: eframe::epaint::Shadow::NONE,
+        fill: app_data.current_theme.card_background_color(),
+        ..Default::default()
+    };
+
+    ui.heading(format!("{} {}", friends_heading_text, app_data.followed_pubkeys.len()));
+    ui.add_space(10.0);
+
+    // --- フォロー・アンフォロー ---
+    let mut follow_request: Option<bool> = None;
+    ui.horizontal(|ui| {
+        ui.add(
+            egui::TextEdit::singleline(&mut app_data.follow_pubkey_input)
+                .desired_width(320.0)
+                .hint_text(follow_hint_text),
+        );
+        ui.add_enabled_ui(!app_data.is_loading, |ui| {
+            if ui.button(follow_button_text).clicked() {
+                follow_request = Some(true);
+            }
+            if ui.button(unfollow_button_text).clicked() {
+                follow_request = Some(false);
+            }
+        });
+        if ui.button(scan_qr_button_text).clicked() {
+            app_data.show_scan_qr = !app_data.show_scan_qr;
+            app_data.scan_qr_input.clear();
+            app_data.scan_qr_image_input.clear();
+            app_data.scanned_profile = None;
+            app_data.camera_scan = None;
+        }
+        if app_data.is_loading {
+            ui.spinner();
+        }
+    });
+    if let Some(follow) = follow_request {
+        match parse_profile_input(&app_data.follow_pubkey_input) {
+            Ok((pubkey, relay_hint)) => {
+                app_data.follow_pubkey_input.clear();
+                app_data.follow_error = None;
+                spawn_contact_list_update(app_data, app_data_arc.clone(), &runtime_handle, pubkey, relay_hint, follow);
+            }
+            Err(e) => {
+                app_data.follow_error = Some(tr!("公開鍵が不正です: {}", e));
+            }
+        }
+    }
+    if let Some(error) = &app_data.follow_error {
+        ui.colored_label(egui::Color32::RED, error);
+    }
+
+    // --- QR コードで友だち追加 ---
+    let mut scanned_action: Option<(bool, bool)> = None;
+    let mut scanned_profile_to_open: Option<PublicKey> = None;
+    if !app_data.show_scan_qr {
+        app_data.camera_scan = None;
+    }
+    if let Some(result) = app_data.camera_scan.as_ref().and_then(|scan| scan.poll()) {
+        app_data.camera_scan = None;
+        match result.and_then(|content| parse_profile_input(&content)) {
+            Ok(profile) => {
+                app_data.scanned_profile = Some(profile);
+                app_data.follow_error = None;
+            }
+            Err(e) => {
+                error!("Failed to read the QR code from the camera: {e}");
+                app_data.follow_error = Some(tr!("QR コードの内容を読み取れませんでした: {}", e));
+            }
+        }
+    }
+    if app_data.show_scan_qr {
+        ui.add_space(5.0);
+        card_frame.show(ui, |ui| {
+            match app_data.scanned_profile.clone() {
+                None => {
+                    if camera::is_supported() {
+                        match &mut app_data.camera_scan {
+                            Some(scan) => {
+                                scan.show_preview(ui, 320.0);
+                                if ui.button(stop_camera_button_text).clicked() {
+                                    app_data.camera_scan = None;
+                                }
+                            }
+                            None => {
+                                if ui.button(camera_button_text).clicked() {
+                                    match camera::start_scan(ui.ctx()) {
+                                        Ok(scan) => {
+                                            app_data.camera_scan = Some(scan);
+                                            app_data.follow_error = None;
+                                        }
+                                        Err(e) => {
+                                            error!("Failed to start the camera: {e}");
+                                            app_data.follow_error = Some(e.to_string());
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    ui.small(scan_qr_hint_text);
+                    if let Some(path) = ui.ctx().input(|i| i.raw.dropped_files.iter().find_map(|file| file.path.clone())) {
+                        app_data.scan_qr_image_input = path.display().to_string();
+                    }
+                    let mut decode_image = false;
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            egui::TextEdit::singleline(&mut app_data.scan_qr_image_input)
+                                .desired_width(320.0)
+                                .hint_text(qr_image_hint_text),
+                        );
+                        let can_decode = !app_data.is_decoding_qr && !app_data.scan_qr_image_input.trim().is_empty();
+                        decode_image = ui.add_enabled(can_decode, egui::Button::new(read_image_button_text)).clicked();
+                        if app_data.is_decoding_qr {
+                            ui.spinner();
+                        }
+                    });
+                    if decode_image {
+                        spawn_decode_qr_image(app_data, app_data_arc.clone(), &runtime_handle);
+                    }
+                    ui.horizontal(|ui| {
+                        let response = ui.add(
+                            egui::TextEdit::singleline(&mut app_data.scan_qr_input)
+                                .desired_width(320.0)
+                                .hint_text(follow_hint_text),
+                        );
+                        let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                        if ui.button(read_scanned_button_text).clicked() || submitted {
+                            match parse_profile_input(&app_data.scan_qr_input) {
+                                Ok(profile) => {
+                                    app_data.scanned_profile = Some(profile);
+                                    app_data.follow_error = None;
+                                }
+                                Err(e) => app_data.follow_error = Some(tr!("QR コードの内容を読み取れませんでした: {}", e)),
+                            }
+                        }
+                        if ui.button(close_button_text).clicked() {
+                            app_data.show_scan_qr = false;
+                        }
+                    });
+                }
+                Some((pubkey, _)) => {
+                    ui.label(egui::RichText::new(scanned_heading_text).strong());
+                    let name = app_data.profiles.get(&pubkey).map(|metadata| metadata.display_name_or_npub(&pubkey)).unwrap_or_else(|| short_npub(&pubkey));
+                    let Ok(npub) = pubkey.to_bech32();
+                    ui.label(name);
+                    ui.label(egui::RichText::new(npub).small().monospace().color(egui::Color32::GRAY));
+                    let is_following = app_data.followed_pubkeys.contains(&pubkey);
+                    ui.horizontal(|ui| {
+                        ui.add_enabled_ui(!app_data.is_loading, |ui| {
+                            if is_following {
+                                ui.label(egui::RichText::new(already_following_text).color(egui::Color32::GRAY));
+                            } else {
+                                if ui.button(follow_button_text).clicked() {
+                                    scanned_action = Some((true, false));
+                                }
+                                if ui.button(follow_and_chat_button_text).clicked() {
+                                    scanned_action = Some((true, true));
+                                }
+                            }
+                            if ui.button(open_chat_button_text).clicked() {
+                                scanned_action = Some((false, true));
+                            }
+                        });
+                        if ui.button(view_profile_button_text).clicked() {
+                            scanned_profile_to_open = Some(pubkey);
+                        }
+                        if ui.button(close_button_text).clicked() {
+                            app_data.show_scan_qr = false;
+                        }
+                    });
+                }
+            }
+        });
+    }
+    if let (Some((follow, chat)), Some((pubkey, relay_hint))) = (scanned_action, app_data.scanned_profile.clone()) {
+        if follow {
+            spawn_contact_list_update(app_data, app_data_arc.clone(), &runtime_handle, pubkey, relay_hint, true);
+        }
+        app_data.show_scan_qr = false;
+        app_data.scanned_profile = None;
+        app_data.scan_qr_input.clear();
+        if chat {
+            app_data.conversations.entry(pubkey).or_default();
+            app_data.selected_conversation = Some(pubkey);
+            app_data.current_tab = AppTab::Chats;
+            app_data.should_repaint = true;
+        }
+    }
+    if let Some(pubkey) = scanned_profile_to_open {
+        user_profile_view::open_user_profile(app_data, app_data_arc.clone(), &runtime_handle, pubkey);
+    }
+    ui.add_space(10.0);
+
+    if app_data.followed_pubkeys.is_empty() {
+        ui.label(no_friends_text);
+        return;
+    }
+
+    // お気に入りを先頭にし、その中では表示名順に並べる
+    let mut friends: Vec<(PublicKey, ProfileMetadata, String)> = app_data
+        .followed_pubkeys
+        .iter()
+        .map(|pubkey| {
+            let metadata = app_data.profiles.get(pubkey).cloned().unwrap_or_default();
+            let name = metadata.display_name_or_npub(pubkey);
+            (*pubkey, metadata, name)
+        })
+        .collect();
+    friends.sort_by_key(|(pubkey, _, name)| (!app_data.pins.favorite_friends.contains(pubkey), name.to_lowercase()));
+
+    let mut chat_to_open: Option<PublicKey> = None;
+    let mut profile_to_open: Option<PublicKey> = None;
+    let mut nip05_to_verify: Vec<(PublicKey, String)> = Vec::new();
+    let mut zap_target: Option<ZapTarget> = None;
+    let mut star_toggle: Option<PublicKey> = None;
+    let mut favorite_toggle: Option<PublicKey> = None;
+
+    egui::ScrollArea::vertical()
+        .id_salt("friends_scroll_area")
+        .show(ui, |ui| {
+            for (pubkey, metadata, name) in &friends {
+                let is_selected = app_data.selected_friend == Some(*pubkey);
+                let row = card_frame.show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        image_cache::draw_user_avatar(
+                            ui,
+                            &app_data.image_cache,
+                            pubkey,
+                            &metadata.picture,
+                            egui::vec2(40.0, 40.0),
+                            20.0,
+                            &mut urls_to_load,
+                        );
+                        ui.add_space(8.0);
+                        ui.vertical(|ui| {
+                            ui.horizontal(|ui| {
+                                let name_label = egui::Label::new(egui::RichText::new(name).strong().color(app_data.current_theme.text_color()))
+                                    .sense(egui::Sense::click());
+                                if ui.add(name_label).on_hover_cursor(egui::CursorIcon::PointingHand).clicked() {
+                                    profile_to_open = Some(*pubkey);
+                                }
+                                nip05_badge::draw_nip05_badge(ui, &app_data.nip05_cache, pubkey, &metadata.nip05, &mut nip05_to_verify);
+                            });
+                            match current_status(&app_data.timeline_posts, pubkey) {
+                                Some((status, created_at)) => {
+                                    ui.horizontal(|ui| {
+                                        ui.label(egui::RichText::new(status).small());
+                                        timestamp::draw_timestamp(ui, created_at);
+                                    })
+                                    .response
+                                }
+                                None => ui.label(egui::RichText::new(no_status_text).small().color(egui::Color32::GRAY)),
+                            };
+                        });
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            if ui.button(open_chat_button_text).clicked() {
+                                chat_to_open = Some(*pubkey);
+                            }
+                            let is_starred = app_data.settings.starred_friends.contains(pubkey);
+                            let (star_text, hover_text) = if is_starred { ("★", unstar_hover_text) } else { ("☆", star_hover_text) };
+                            if ui.button(star_text).on_hover_text(hover_text).clicked() {
+                                star_toggle = Some(*pubkey);
+                            }
+                            let is_favorite = app_data.pins.favorite_friends.contains(pubkey);
+                            let (favorite_text, hover_text) = if is_favorite { ("♥", unfavorite_hover_text) } else { ("♡", favorite_hover_text) };
+                            if ui.add_enabled(app_data.pins_loaded, egui::Button::new(favorite_text)).on_hover_text(hover_text).clicked() {
+                                favorite_toggle = Some(*pubkey);
+                            }
+                        });
+                    });
+
+                    // 選択中の友だちはプロフィールの詳細を表示する
+                    if is_selected {
+                        ui.add_space(8.0);
+                        ui.separator();
+                        if !metadata.about.is_empty() {
+                            ui.label(&metadata.about);
+                        }
+                        let Ok(npub) = pubkey.to_bech32();
+                        ui.label(egui::RichText::new(npub).small().monospace().color(egui::Color32::GRAY));
+                        if !metadata.lud16.is_empty() || !metadata.lud06.is_empty() {
+                            ui.horizontal(|ui| {
+                                if !metadata.lud16.is_empty() {
+                                    ui.label(egui::RichText::new(&metadata.lud16).small());
+                                }
+                                if ui.button(zap_button_text).clicked() {
+                                    zap_target = Some(ZapTarget {
+                                        pubkey: *pubkey,
+                                        metadata: metadata.clone(),
+                                        event: None,
+                                    });
+                                }
+                            });
+                        }
+                    }
+                });
+                if row.response.interact(egui::Sense::click()).clicked() {
+                    app_data.selected_friend = if is_selected { None } else { Some(*pubkey) };
+                }
+                ui.add_space(6.0);
+            }
+        });
+
+    if let Some(target) = zap_target {
+        zap::open_zap_dialog(app_data, target);
+    }
+
+    // 星はその場で保存する
+    if let Some(pubkey) = star_toggle {
+        if !app_data.settings.starred_friends.remove(&pubkey) {
+            app_data.settings.starred_friends.insert(pubkey);
+        }
+        if let Err(e) = save_settings(&app_data.settings) {
+            app_data.report_error(tr!("設定の保存に失敗しました: {}", e));
+        }
+    }
+
+    if let Some(pubkey) = favorite_toggle {
+        pins::toggle_favorite_friend(app_data, app_data_arc.clone(), &runtime_handle, pubkey);
+    }
+
+    if let Some(pubkey) = profile_to_open {
+        user_profile_view::open_user_profile(app_data, app_data_arc.clone(), &runtime_handle, pubkey);
+    }
+
+    if let Some(pubkey) = chat_to_open {
+        app_data.conversations.entry(pubkey).or_default();
+        app_data.selected_conversation = Some(pubkey);
+        app_data.current_tab = AppTab::Chats;
+        app_data.should_repaint = true;
+    }
+
+    nip05_badge::verify_nip05s(app_data, app_data_arc.clone(), &runtime_handle, nip05_to_verify);
+    image_cache::load_images(ctx, app_data, app_data_arc, &runtime_handle, urls_to_load);
+}
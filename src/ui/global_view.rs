@@ -0,0 +1,83 @@
+use eframe::egui;
+use nostr::EventId;
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    global_feed::run_global_feed,
+    i18n::tr,
+    types::*,
+    ui::{content_warning, hashtag_view::draw_feed_event, image_cache, link_preview, link_view},
+    wot,
+};
+
+pub fn draw_global_view(
+    ui: &mut egui::Ui,
+    ctx: &egui::Context,
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: tokio::runtime::Handle,
+) {
+    let global_heading_text = tr!("グローバル");
+    let global_hint_text = tr!("接続中のリレーに届いた最近のノートとステータスです。フォローしていない人も含みます");
+    let waiting_text = tr!("投稿を待っています...");
+    let stranger_text = tr!("フォロー中の人とつながりのない人の投稿");
+    let show_button_text = tr!("表示");
+
+    // タブを開いている間だけ購読する。離れるとタスクが終了する
+    if !app_data.global_feed_started
+        && let Some(client) = app_data.nostr_client.clone() {
+        app_data.global_feed_started = true;
+        runtime_handle.spawn(run_global_feed(client, app_data_arc.clone(), ctx.clone()));
+    }
+
+    ui.heading(global_heading_text);
+    ui.small(global_hint_text);
+    ui.add_space(10.0);
+
+    if app_data.global_events.is_empty() {
+        ui.horizontal(|ui| {
+            ui.spinner();
+            ui.label(waiting_text);
+        });
+        return;
+    }
+
+    let mut urls_to_load: Vec<(String, ImageKind)> = Vec::new();
+    let mut link_to_open: Option<NostrLink> = None;
+    let mut content_warning_toggle: Option<EventId> = None;
+    let mut links_to_preview: Vec<String> = Vec::new();
+    let mut stranger_to_reveal: Option<EventId> = None;
+
+    egui::ScrollArea::vertical()
+        .id_salt("global_feed_scroll_area")
+        .show(ui, |ui| {
+            for event in app_data.global_events.iter().filter(|event| !app_data.settings.is_muted(&event.content)) {
+                // つながりのない人の投稿は名前だけの1行に畳む
+                if wot::is_stranger(app_data, &event.pubkey) && !app_data.revealed_stranger_posts.contains(&event.id) {
+                    ui.horizontal(|ui| {
+                        let name = app_data
+                            .profiles
+                            .get(&event.pubkey)
+                            .map_or_else(|| short_npub(&event.pubkey), |p| p.display_name_or_npub(&event.pubkey));
+                        ui.label(egui::RichText::new(format!("{name} · {stranger_text}")).small().color(egui::Color32::GRAY));
+                        if ui.small_button(show_button_text).clicked() {
+                            stranger_to_reveal = Some(event.id);
+                        }
+                    });
+                } else {
+                    draw_feed_event(ui, app_data, event, &mut urls_to_load, &mut link_to_open, &mut content_warning_toggle, &mut links_to_preview);
+                }
+                ui.add_space(6.0);
+            }
+        });
+
+    content_warning::toggle_revealed(&mut app_data.revealed_content_warnings, content_warning_toggle);
+    if let Some(event_id) = stranger_to_reveal {
+        app_data.revealed_stranger_posts.insert(event_id);
+    }
+    if let Some(link) = link_to_open {
+        link_view::open_link(app_data, app_data_arc.clone(), &runtime_handle, link);
+    }
+    link_preview::fetch_link_previews(app_data, app_data_arc.clone(), &runtime_handle, links_to_preview);
+    image_cache::load_images(ctx, app_data, app_data_arc, &runtime_handle, urls_to_load);
+}
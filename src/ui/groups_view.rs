@@ -0,0 +1,338 @@
+use eframe::egui;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tracing::error;
+
+use crate::{
+    groups::{
+        fetch_group_directory, fetch_joined_groups, publish_joined_groups, push_group_message,
+        run_group_listener, send_group_message, send_membership_request,
+    },
+    i18n::tr,
+    types::*,
+    ui::{
+        chats_view::{draw_date_separator, draw_message_bubble, to_local, BubbleMessage},
+        image_cache,
+    },
+};
+
+// 参加・退出リクエストを送り、参加中グループ一覧(kind 10009)を更新する
+fn spawn_membership_update(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    group: GroupInfo,
+    join: bool,
+) {
+    let (Some(client), Some(keys)) = (app_data.nostr_client.clone(), app_data.my_signer.clone()) else {
+        return;
+    };
+    let mut new_joined_groups = app_data.joined_groups.clone();
+    new_joined_groups.retain(|g| g.key() != group.key());
+    if join {
+        new_joined_groups.push(group.clone());
+    }
+    app_data.is_loading_groups = true;
+    app_data.should_repaint = true;
+
+    runtime_handle.spawn(async move {
+        // 参加リクエストが拒否されても一覧には残す(閉じたグループは承認待ちになるため)
+        if let Err(e) = send_membership_request(&keys, &group, join).await {
+            error!("Failed to send group membership request: {e}");
+        }
+        let result = publish_joined_groups(&client, &keys, &new_joined_groups).await;
+        let mut app_data = app_data_arc.lock().unwrap();
+        match result {
+            Ok(()) => {
+                if !join && app_data.selected_group.as_ref().map(GroupInfo::key) == Some(group.key()) {
+                    app_data.selected_group = None;
+                }
+                app_data.joined_groups = new_joined_groups;
+                app_data.group_error = None;
+            }
+            Err(e) => {
+                error!("Failed to publish joined groups: {e}");
+                app_data.group_error = Some(tr!("参加中グループの更新に失敗しました: {}", e));
+                app_data.report_error(tr!("参加中グループの更新に失敗しました: {}", e));
+            }
+        }
+        app_data.is_loading_groups = false;
+        app_data.should_repaint = true;
+    });
+}
+
+pub fn draw_groups_view(
+    ui: &mut egui::Ui,
+    ctx: &egui::Context,
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: tokio::runtime::Handle,
+) {
+    let groups_heading_text = tr!("グループ");
+    let relay_hint_text = tr!("wss://... (NIP-29対応リレー)");
+    let browse_button_text = tr!("グループを探す");
+    let joined_groups_text = tr!("参加中のグループ");
+    let no_joined_groups_text = tr!("参加中のグループはありません。");
+    let directory_text = tr!("グループ一覧");
+    let join_button_text = tr!("参加");
+    let leave_button_text = tr!("退出");
+    let select_group_text = tr!("左のリストからグループを選択してください。");
+    let members_text = tr!("メンバー");
+    let message_hint_text = tr!("メッセージを入力");
+    let send_button_text = tr!("送信");
+    let mut urls_to_load: Vec<(String, ImageKind)> = Vec::new();
+    // チャンネル・グループのメッセージのカスタム絵文字はまだ読まない
+    let no_emojis = HashMap::new();
+
+    let card_frame = egui::Frame {
+        inner_margin: egui::Margin::same(12),
+        corner_radius: 8.0.into(),
+        shadow: eframe::epaint::Shadow::NONE,
+        fill: app_data.current_theme.card_background_color(),
+        ..Default::default()
+    };
+
+    // 初回表示時に参加中のグループ一覧を取得する
+    if !app_data.joined_groups_loaded
+        && let (Some(client), Some(keys)) = (app_data.nostr_client.clone(), app_data.my_signer.clone()) {
+        app_data.joined_groups_loaded = true;
+        app_data.is_loading_groups = true;
+        let cloned_app_data_arc = app_data_arc.clone();
+        runtime_handle.spawn(async move {
+            let result = fetch_joined_groups(&client, &keys).await;
+            let mut app_data = cloned_app_data_arc.lock().unwrap();
+            match result {
+                Ok(groups) => app_data.joined_groups = groups,
+                Err(e) => {
+                    error!("Failed to fetch joined groups: {e}");
+                    app_data.report_error(tr!("参加中グループを取得できませんでした: {}", e));
+                }
+            }
+            app_data.is_loading_groups = false;
+            app_data.should_repaint = true;
+        });
+    }
+
+    // 選択中のグループが変わったら購読タスクを開始する
+    let selected_key = app_data.selected_group.as_ref().map(GroupInfo::key);
+    if selected_key != app_data.group_listener_key {
+        app_data.group_listener_key = selected_key;
+        if let (Some(group), Some(keys)) = (app_data.selected_group.clone(), app_data.my_signer.clone()) {
+            app_data.group_error = None;
+            runtime_handle.spawn(run_group_listener(keys, group, app_data_arc.clone(), ctx.clone()));
+        }
+    }
+
+    ui.horizontal(|ui| {
+        ui.heading(groups_heading_text);
+        if app_data.is_loading_groups {
+            ui.spinner();
+        }
+    });
+    ui.add_space(10.0);
+
+    let mut membership_change: Option<(GroupInfo, bool)> = None;
+
+    ui.columns(2, |columns| {
+        // --- グループ一覧 ---
+        card_frame.show(&mut columns[0], |ui| {
+            ui.label(egui::RichText::new(joined_groups_text).strong());
+            if app_data.joined_groups.is_empty() {
+                ui.label(no_joined_groups_text);
+            }
+            for group in app_data.joined_groups.clone() {
+                let is_selected = app_data.selected_group.as_ref().map(GroupInfo::key) == Some(group.key());
+                ui.horizontal(|ui| {
+                    if ui.selectable_label(is_selected, &group.name).clicked() {
+                        app_data.selected_group = Some(group.clone());
+                    }
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if ui.add_enabled(!app_data.is_loading_groups, egui::Button::new(leave_button_text)).clicked() {
+                            membership_change = Some((group.clone(), false));
+                        }
+                    });
+                });
+            }
+
+            ui.add_space(10.0);
+            ui.separator();
+            ui.label(egui::RichText::new(directory_text).strong());
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut app_data.group_relay_input)
+                        .desired_width(ui.available_width() - 110.0)
+                        .hint_text(relay_hint_text),
+                );
+                if ui.add_enabled(!app_data.is_loading_groups, egui::Button::new(browse_button_text)).clicked()
+                    && let Some(keys) = app_data.my_signer.clone() {
+                    let relay_url = app_data.group_relay_input.trim().to_string();
+                    app_data.is_loading_groups = true;
+                    app_data.should_repaint = true;
+                    let cloned_app_data_arc = app_data_arc.clone();
+                    runtime_handle.spawn(async move {
+                        let result = fetch_group_directory(&keys, &relay_url).await;
+                        let mut app_data = cloned_app_data_arc.lock().unwrap();
+                        match result {
+                            Ok(groups) => {
+                                app_data.group_directory = groups;
+                                app_data.group_error = None;
+                            }
+                            Err(e) => {
+                                error!("Failed to fetch group directory: {e}");
+                                app_data.group_error = Some(tr!("グループ一覧の取得に失敗しました: {}", e));
+                            }
+                        }
+                        app_data.is_loading_groups = false;
+                        app_data.should_repaint = true;
+                    });
+                }
+            });
+
+            egui::ScrollArea::vertical()
+                .id_salt("group_directory_scroll_area")
+                .show(ui, |ui| {
+                    for group in &app_data.group_directory {
+                        let joined = app_data.joined_groups.iter().any(|g| g.key() == group.key());
+                        ui.horizontal(|ui| {
+                            ui.vertical(|ui| {
+                                let lock = if group.is_closed { " 🔒" } else { "" };
+                                ui.label(egui::RichText::new(format!("{}{}", group.name, lock)).strong());
+                                if !group.about.is_empty() {
+                                    ui.label(egui::RichText::new(&group.about).small().color(egui::Color32::GRAY));
+                                }
+                            });
+                            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                let can_join = !joined && !app_data.is_loading_groups;
+                                if ui.add_enabled(can_join, egui::Button::new(join_button_text)).clicked() {
+                                    membership_change = Some((group.clone(), true));
+                                }
+                            });
+                        });
+                        ui.separator();
+                    }
+                });
+        });
+
+        // --- グループトーク ---
+        card_frame.show(&mut columns[1], |ui| {
+            let Some(group) = app_data.selected_group.clone() else {
+                ui.label(select_group_text);
+                return;
+            };
+            let group_key = group.key();
+
+            ui.label(egui::RichText::new(&group.name).strong());
+            if !group.about.is_empty() {
+                ui.label(egui::RichText::new(&group.about).small().color(egui::Color32::GRAY));
+            }
+
+            let members = app_data.group_members.get(&group_key).cloned().unwrap_or_default();
+            egui::CollapsingHeader::new(format!("{} ({})", members_text, members.len()))
+                .id_salt(("group_members", &group_key))
+                .show(ui, |ui| {
+                    for member in &members {
+                        let name = app_data
+                            .profiles
+                            .get(member)
+                            .map_or_else(|| short_npub(member), |p| p.display_name_or_npub(member));
+                        ui.label(name);
+                    }
+                });
+            ui.separator();
+
+            let messages = app_data.group_messages.get(&group_key).cloned().unwrap_or_default();
+            egui::ScrollArea::vertical()
+                .id_salt(("group_messages_scroll_area", &group_key))
+                .max_height(ui.available_height() - 60.0)
+                .stick_to_bottom(true)
+                .auto_shrink([false, false])
+                .show(ui, |ui| {
+                    let mut previous: Option<&GroupMessage> = None;
+                    for message in &messages {
+                        let local = to_local(message.created_at);
+                        if previous.is_none_or(|p| to_local(p.created_at).date_naive() != local.date_naive()) {
+                            draw_date_separator(ui, &local);
+                        }
+                        let show_avatar = previous.is_none_or(|p| p.sender != message.sender);
+                        let sender_profile = app_data.profiles.get(&message.sender);
+                        // グループでは誰の発言かわかるように名前も出す
+                        if show_avatar && !message.is_mine {
+                            let name = sender_profile
+                                .map_or_else(|| short_npub(&message.sender), |p| p.display_name_or_npub(&message.sender));
+                            ui.label(egui::RichText::new(name).small().color(egui::Color32::GRAY));
+                        }
+                        draw_message_bubble(
+                            ui,
+                            BubbleMessage {
+                                content: &message.content,
+                                created_at: message.created_at,
+                                is_mine: message.is_mine,
+                                sender: &message.sender,
+                                sender_picture: sender_profile.map(|p| p.picture.as_str()).unwrap_or_default(),
+                                show_avatar,
+                                is_read: false,
+                                emojis: &no_emojis,
+                                images: &[],
+                                audio_clips: &[],
+                            },
+                            app_data.current_theme,
+                            &app_data.image_cache,
+                            &mut urls_to_load,
+                        );
+                        previous = Some(message);
+                    }
+                });
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut app_data.group_message_input)
+                        .desired_width(ui.available_width() - 60.0)
+                        .hint_text(message_hint_text),
+                );
+                let can_send = !app_data.is_sending_group_message
+                    && app_data.group_client.is_some()
+                    && !app_data.group_message_input.trim().is_empty();
+                if ui.add_enabled(can_send, egui::Button::new(send_button_text)).clicked() {
+                    let (Some(client), Some(keys)) = (app_data.group_client.clone(), app_data.my_signer.clone()) else {
+                        return;
+                    };
+                    let content = std::mem::take(&mut app_data.group_message_input);
+                    app_data.is_sending_group_message = true;
+                    app_data.should_repaint = true;
+
+                    let cloned_app_data_arc = app_data_arc.clone();
+                    runtime_handle.spawn(async move {
+                        let result = send_group_message(&client, &keys, &group, &content).await;
+                        let mut app_data = cloned_app_data_arc.lock().unwrap();
+                        match result {
+                            Ok(message) => {
+                                push_group_message(&mut app_data.group_messages, &group.key(), message);
+                                app_data.group_error = None;
+                            }
+                            Err(e) => {
+                                error!("Failed to send group message: {e}");
+                                app_data.group_error = Some(tr!("送信に失敗しました: {}", e));
+                                app_data.report_error(tr!("グループにメッセージを送信できませんでした: {}", e));
+                                app_data.group_message_input = content;
+                            }
+                        }
+                        app_data.is_sending_group_message = false;
+                        app_data.should_repaint = true;
+                    });
+                }
+            });
+        });
+    });
+
+    if let Some((group, join)) = membership_change {
+        spawn_membership_update(app_data, app_data_arc.clone(), &runtime_handle, group, join);
+    }
+
+    if let Some(error) = &app_data.group_error {
+        ui.add_space(10.0);
+        ui.colored_label(egui::Color32::RED, error);
+    }
+
+    image_cache::load_images(ctx, app_data, app_data_arc, &runtime_handle, urls_to_load);
+}
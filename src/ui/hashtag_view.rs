@@ -0,0 +1,189 @@
+use eframe::egui;
+use nostr::{Event, EventId, Kind};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use tracing::error;
+
+use crate::{
+    articles::{article_coordinate, article_info},
+    fonts,
+    i18n::tr,
+    note::{self, fetch_hashtag_feed},
+    types::*,
+    ui::{content_warning, image_cache, link_preview, link_view, signature_badge, timestamp},
+};
+
+// ハッシュタグのフィードを開き、接続中のリレーから取得する
+pub fn open_hashtag_feed(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    hashtag: String,
+) {
+    app_data.current_tab = AppTab::Hashtag;
+    app_data.should_repaint = true;
+    if app_data.hashtag_feed.as_ref() == Some(&hashtag) && !app_data.hashtag_events.is_empty() {
+        return;
+    }
+    app_data.hashtag_feed = Some(hashtag);
+    spawn_fetch_hashtag_feed(app_data, app_data_arc, runtime_handle);
+}
+
+fn spawn_fetch_hashtag_feed(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+) {
+    let (Some(client), Some(hashtag)) = (app_data.nostr_client.clone(), app_data.hashtag_feed.clone()) else {
+        return;
+    };
+    let known_profiles: HashSet<_> = app_data.profiles.keys().copied().collect();
+    app_data.hashtag_events.clear();
+    app_data.is_loading_hashtag_feed = true;
+    runtime_handle.spawn(async move {
+        let result = fetch_hashtag_feed(&client, &hashtag, known_profiles).await;
+        let mut app_data = app_data_arc.lock().unwrap();
+        // 取得中に別のハッシュタグが開かれていたら結果は使わない
+        if app_data.hashtag_feed.as_ref() != Some(&hashtag) {
+            return;
+        }
+        match result {
+            Ok((events, profiles)) => {
+                app_data.hashtag_events = events;
+                app_data.profiles.extend(profiles);
+            }
+            Err(e) => {
+                error!("Failed to fetch the hashtag feed: {e}");
+                app_data.report_error(tr!("#{} の投稿を取得できませんでした: {}", hashtag, e));
+            }
+        }
+        app_data.is_loading_hashtag_feed = false;
+        app_data.should_repaint = true;
+    });
+}
+
+pub fn draw_hashtag_view(
+    ui: &mut egui::Ui,
+    ctx: &egui::Context,
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: tokio::runtime::Handle,
+) {
+    let reload_button_text = tr!("🔄 再読み込み");
+    let loading_text = tr!("読み込み中...");
+    let no_posts_text = tr!("このハッシュタグの投稿は見つかりませんでした");
+
+    let Some(hashtag) = app_data.hashtag_feed.clone() else {
+        return;
+    };
+    ui.horizontal(|ui| {
+        ui.heading(format!("#{hashtag}"));
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            if ui.add_enabled(!app_data.is_loading_hashtag_feed, egui::Button::new(reload_button_text)).clicked() {
+                spawn_fetch_hashtag_feed(app_data, app_data_arc.clone(), &runtime_handle);
+            }
+        });
+    });
+    ui.add_space(10.0);
+
+    if app_data.is_loading_hashtag_feed {
+        ui.horizontal(|ui| {
+            ui.spinner();
+            ui.label(loading_text);
+        });
+        return;
+    }
+    if app_data.hashtag_events.is_empty() {
+        ui.label(no_posts_text);
+        return;
+    }
+
+    let mut urls_to_load: Vec<(String, ImageKind)> = Vec::new();
+    let mut link_to_open: Option<NostrLink> = None;
+    let mut content_warning_toggle: Option<EventId> = None;
+    let mut links_to_preview: Vec<String> = Vec::new();
+
+    egui::ScrollArea::vertical()
+        .id_salt("hashtag_feed_scroll_area")
+        .show(ui, |ui| {
+            for event in app_data.hashtag_events.iter().filter(|event| !app_data.settings.is_muted(&event.content)) {
+                draw_feed_event(ui, app_data, event, &mut urls_to_load, &mut link_to_open, &mut content_warning_toggle, &mut links_to_preview);
+                ui.add_space(6.0);
+            }
+        });
+
+    content_warning::toggle_revealed(&mut app_data.revealed_content_warnings, content_warning_toggle);
+    if let Some(link) = link_to_open {
+        link_view::open_link(app_data, app_data_arc.clone(), &runtime_handle, link);
+    }
+    link_preview::fetch_link_previews(app_data, app_data_arc.clone(), &runtime_handle, links_to_preview);
+    image_cache::load_images(ctx, app_data, app_data_arc, &runtime_handle, urls_to_load);
+}
+
+// フィードの投稿1件をカードにして描く。ハッシュタグとグローバルのフィード、Keep で使う
+pub fn draw_feed_event(
+    ui: &mut egui::Ui,
+    app_data: &NostrStatusAppInternal,
+    event: &Event,
+    urls_to_load: &mut Vec<(String, ImageKind)>,
+    link_to_open: &mut Option<NostrLink>,
+    content_warning_toggle: &mut Option<EventId>,
+    links_to_preview: &mut Vec<String>,
+) {
+    let untitled_text = tr!("(タイトルなし)");
+    let read_article_button_text = tr!("記事を読む");
+
+    let card_frame = egui::Frame {
+        inner_margin: egui::Margin::same(12),
+        corner_radius: 8.0.into(),
+        shadow: eframe::epaint::Shadow::NONE,
+        fill: app_data.current_theme.card_background_color(),
+        ..Default::default()
+    };
+
+    let metadata = app_data.profiles.get(&event.pubkey).cloned().unwrap_or_default();
+    card_frame.show(ui, |ui| {
+        ui.set_width(ui.available_width());
+        ui.horizontal(|ui| {
+            image_cache::draw_user_avatar(
+                ui,
+                &app_data.image_cache,
+                &event.pubkey,
+                &metadata.picture,
+                egui::vec2(24.0, 24.0),
+                4.0,
+                urls_to_load,
+            );
+            if ui.link(egui::RichText::new(metadata.display_name_or_npub(&event.pubkey)).strong()).clicked() {
+                *link_to_open = Some(NostrLink::Profile(event.pubkey));
+            }
+            let icon = match event.kind {
+                Kind::TextNote => "📝",
+                Kind::LongFormTextNote => "📰",
+                _ => StatusGroup::from_d_tag(event.tags.identifier().unwrap_or_default()).icon(),
+            };
+            ui.label(icon);
+            timestamp::draw_timestamp(ui, event.created_at);
+            signature_badge::draw_signature_badge(ui, &event.id);
+        });
+        // 記事は長いので、タイトルと要約だけを出して記事タブで読んでもらう
+        if event.kind == Kind::LongFormTextNote {
+            let info = article_info(event);
+            ui.label(egui::RichText::new(info.title.unwrap_or(untitled_text)).family(fonts::bold_font_family()));
+            if let Some(summary) = info.summary {
+                ui.add(egui::Label::new(egui::RichText::new(summary).color(egui::Color32::GRAY)).wrap());
+            }
+            if ui.small_button(read_article_button_text).clicked() {
+                *link_to_open = Some(NostrLink::Address(article_coordinate(event)));
+            }
+            return;
+        }
+        let content_warning = note::content_warning(event.tags.iter());
+        if content_warning::draw_content_warning(ui, &app_data.revealed_content_warnings, event.id, content_warning.as_deref(), content_warning_toggle) {
+            ui.horizontal_wrapped(|ui| {
+                link_view::draw_text_with_links(ui, &event.content, ui.visuals().text_color(), &app_data.profiles, link_to_open);
+            });
+            link_preview::draw_previews(ui, app_data, &event.content, event.tags.iter(), urls_to_load, links_to_preview);
+        }
+    });
+}
@@ -1,23 +1,44 @@
 use eframe::egui;
 use std::sync::{Arc, Mutex};
 use std::collections::HashMap;
-use nostr::{EventBuilder, Kind, PublicKey, Tag, nips::nip19::ToBech32, EventId, Timestamp};
+use nostr::{PublicKey, Tag, EventId, Kind, Timestamp};
 use regex::Regex;
+use tracing::{error, info};
 
 use crate::{
+    i18n::tr,
     types::*,
-    nostr_client::{update_contact_list, fetch_timeline_events},
-    cache_db::DB_FOLLOWED,
-    MAX_STATUS_LENGTH,
-    ui::{image_cache, zap},
+    labels::{self, LabelAction},
+    nostr_client::fetch_timeline_events,
+    media,
+    note,
+    outbox,
+    private_status,
+    replaceable,
+    scheduler,
+    status_history,
+    MAX_STATUS_LENGTH, status_length,
+    ui::{attachment, bookmarks_view, chats_view, content_warning, emoji, emoji_picker, friends_view, image_cache, link_preview, link_view, mention, nip05_badge, notes_view, report_dialog, signature_badge, timestamp, timestamp_proof_badge, zap},
 };
 
+// 予約の日時の入力形式 (ローカル時刻)
+const SCHEDULE_TIME_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+// 信頼する人が投稿か投稿者に付けたラベル (NIP-32) による扱い
+fn post_label_action(app_data: &NostrStatusAppInternal, post: &TimelinePost) -> Option<LabelAction> {
+    let event_labels = app_data.event_labels.get(&post.id).into_iter().flatten();
+    let pubkey_labels = app_data.pubkey_labels.get(&post.author_pubkey).into_iter().flatten();
+    labels::label_action(&app_data.settings, event_labels.chain(pubkey_labels))
+}
+
 fn render_post_content(
     ui: &mut egui::Ui,
     app_data: &NostrStatusAppInternal,
     post: &TimelinePost,
     urls_to_load: &mut Vec<(String, ImageKind)>,
     my_emojis: &HashMap<String, String>,
+    link_to_open: &mut Option<NostrLink>,
+    links_to_preview: &mut Vec<String>,
 ) {
     let text_color = app_data.current_theme.text_color();
 
@@ -25,7 +46,7 @@ fn render_post_content(
     let d_tag = post
         .tags
         .iter()
-        .find(|t| (*t).clone().to_vec().get(0).map(|s| s.as_str()) == Some("d"));
+        .find(|t| (*t).clone().to_vec().first().map(|s| s.as_str()) == Some("d"));
 
     if let Some(tag) = d_tag {
         let tag_vec = tag.clone().to_vec();
@@ -38,7 +59,7 @@ fn render_post_content(
                     let r_tag = post
                         .tags
                         .iter()
-                        .find(|t| (*t).clone().to_vec().get(0).map(|s| s.as_str()) == Some("r"));
+                        .find(|t| (*t).clone().to_vec().first().map(|s| s.as_str()) == Some("r"));
                     if let Some(r_tag_value) = r_tag.and_then(|t| t.clone().to_vec().get(1).cloned()) {
                         ui.hyperlink_to(
                             egui::RichText::new(&r_tag_value).small().color(egui::Color32::GRAY),
@@ -62,42 +83,12 @@ fn render_post_content(
 
             let pre_text = &post.content[last_end..full_match.start()];
             if !pre_text.is_empty() {
-                ui.label(egui::RichText::new(pre_text).color(text_color));
+                link_view::draw_text_with_links(ui, pre_text, text_color, &app_data.profiles, link_to_open);
             }
 
             let url = post.emojis.get(shortcode).or_else(|| my_emojis.get(shortcode));
             if let Some(url) = url {
-                let emoji_size = egui::vec2(20.0, 20.0);
-                let url_key = url.to_string();
-
-                match app_data.image_cache.get(&url_key) {
-                    Some(ImageState::Loaded(texture_handle)) => {
-                        let image_widget =
-                            egui::Image::new(texture_handle).fit_to_exact_size(emoji_size);
-                        ui.add(image_widget);
-                    }
-                    Some(ImageState::Loading) => {
-                        let (rect, _) = ui.allocate_exact_size(emoji_size, egui::Sense::hover());
-                        ui.put(rect, egui::Spinner::new());
-                    }
-                    Some(ImageState::Failed) => {
-                        let (rect, _) = ui.allocate_exact_size(emoji_size, egui::Sense::hover());
-                        ui.painter().text(
-                            rect.center(),
-                            egui::Align2::CENTER_CENTER,
-                            "💔".to_string(),
-                            egui::FontId::default(),
-                            ui.visuals().error_fg_color,
-                        );
-                    }
-                    None => {
-                        if !urls_to_load.iter().any(|(u, _)| u == &url_key) {
-                            urls_to_load.push((url_key.clone(), ImageKind::Emoji));
-                        }
-                        let (rect, _) = ui.allocate_exact_size(emoji_size, egui::Sense::hover());
-                        ui.put(rect, egui::Spinner::new());
-                    }
-                }
+                emoji::draw_emoji_image(ui, &app_data.image_cache, url, egui::vec2(20.0, 20.0), urls_to_load);
             } else {
                 ui.label(egui::RichText::new(full_match.as_str()).color(text_color));
             }
@@ -107,9 +98,27 @@ fn render_post_content(
 
         let remaining_text = &post.content[last_end..];
         if !remaining_text.is_empty() {
-            ui.label(egui::RichText::new(remaining_text).color(text_color));
+            link_view::draw_text_with_links(ui, remaining_text, text_color, &app_data.profiles, link_to_open);
         }
     });
+    link_preview::draw_previews(ui, app_data, &post.content, post.tags.iter(), urls_to_load, links_to_preview);
+}
+
+// 新規投稿の入力を片付けてダイアログを閉じる
+fn reset_post_dialog(app_data: &mut NostrStatusAppInternal) {
+    app_data.show_post_dialog = false;
+    app_data.current_status_type = StatusType::General;
+    app_data.status_expiry = StatusExpiry::Never;
+    app_data.schedule_enabled = false;
+    app_data.auto_clear_enabled = false;
+    app_data.status_content_warning = false;
+    app_data.status_content_warning_reason.clear();
+    app_data.status_private = false;
+    app_data.status_message_input.clear();
+    app_data.music_track_input.clear();
+    app_data.music_url_input.clear();
+    app_data.podcast_episode_input.clear();
+    app_data.podcast_url_input.clear();
 }
 
 pub fn draw_home_view(
@@ -120,13 +129,65 @@ pub fn draw_home_view(
     runtime_handle: tokio::runtime::Handle,
 ) {
     let mut urls_to_load: Vec<(String, ImageKind)> = Vec::new();
-    let new_post_window_title_text = "新規投稿";
-    let status_input_hint_text = "いまどうしてる？";
-    let publish_button_text = "公開";
-    let cancel_button_text = "キャンセル";
-    let timeline_heading_text = "ホーム";
-    let fetch_latest_button_text = "最新の投稿を取得";
-    let no_timeline_message_text = "タイムラインに投稿はまだありません。";
+    let mut links_to_preview: Vec<String> = Vec::new();
+    let new_post_window_title_text = tr!("新規投稿");
+    let status_input_hint_text = tr!("いまどうしてる？");
+    let custom_emoji_hover_text = tr!("カスタム絵文字");
+    let publish_button_text = tr!("公開");
+    let cancel_button_text = tr!("キャンセル");
+    let timeline_heading_text = tr!("ホーム");
+    let fetch_latest_button_text = tr!("最新の投稿を取得");
+    let no_timeline_message_text = tr!("タイムラインに投稿はまだありません。");
+    let outbox_heading_text = tr!("送信キュー");
+    let pending_label_text = tr!("送信待ち");
+    let sent_label_text = tr!("送信済み");
+    let discard_button_text = tr!("破棄");
+    let clear_sent_button_text = tr!("送信済みを消去");
+    let relay_results_label_text = tr!("リレーの応答");
+    let accepted_label_text = tr!("受理");
+    let rejected_label_text = tr!("拒否");
+    let retry_failed_button_text = tr!("拒否したリレーに再送");
+    let history_heading_text = tr!("ステータスの履歴");
+    let republish_button_text = tr!("再公開");
+    let clear_status_button_text = tr!("ステータスを消去");
+    let expiry_label_text = tr!("期限:");
+    let expiry_never_text = tr!("なし");
+    let expiry_30_minutes_text = tr!("30分");
+    let expiry_1_hour_text = tr!("1時間");
+    let expiry_4_hours_text = tr!("4時間");
+    let expiry_custom_text = tr!("指定");
+    let expiry_minutes_suffix_text = tr!(" 分");
+    let schedule_checkbox_text = tr!("予約する:");
+    let schedule_time_hint_text = "2025-01-31 18:30";
+    let auto_clear_checkbox_text = tr!("自動で消去:");
+    let auto_clear_hours_suffix_text = tr!(" 時間後");
+    let note_heading_text = tr!("ノート");
+    let note_input_hint_text = tr!("ノートを書く (@名前 でメンション)");
+    let post_note_button_text = tr!("投稿");
+    let scheduled_heading_text = tr!("予約中");
+    let scheduled_publish_text = tr!("公開");
+    let scheduled_clear_text = tr!("消去");
+    let cancel_schedule_button_text = tr!("取り消し");
+    let current_statuses_heading_text = tr!("今のステータス");
+    let clear_button_text = tr!("消去");
+    let status_type_label_text = tr!("種類:");
+    let status_type_general_text = tr!("💬 一般");
+    let status_type_music_text = tr!("🎵 音楽");
+    let status_type_podcast_text = tr!("🎤 ポッドキャスト");
+    let status_type_custom_text = tr!("🏷 カスタム");
+    let custom_d_tag_hint_text = tr!("d タグ (例: gaming)");
+    let filter_all_text = tr!("すべて");
+    let filter_general_text = tr!("💬 一般");
+    let filter_music_text = tr!("🎵 音楽");
+    let filter_other_text = tr!("🏷 その他");
+    let report_button_text = tr!("通報");
+    let label_menu_text = tr!("ラベルを付ける");
+    let private_checkbox_text = tr!("🔒 非公開:");
+    let private_checkbox_hover_text = tr!("公開せず、選んだフォローセットの人だけにギフトラップで送ります");
+    let follow_set_placeholder_text = tr!("フォローセットを選択");
+    let no_follow_sets_text = tr!("フォローセット (NIP-51) がありません");
+    let reload_follow_sets_hover_text = tr!("フォローセットを読み込み直す");
+    let private_status_hover_text = tr!("一部の人だけに届いた非公開のステータス");
 
     let card_frame = egui::Frame {
         inner_margin: egui::Margin::same(12),
@@ -136,98 +197,6 @@ pub fn draw_home_view(
         ..Default::default()
     };
 
-    // --- ZAP Dialog ---
-    if app_data.show_zap_dialog {
-        if let Some(post_to_zap) = app_data.zap_target_post.clone() {
-            let mut close_dialog = false;
-            egui::Window::new("ZAPを送る")
-                .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
-                .collapsible(false)
-                .resizable(false)
-                .show(ctx, |ui| {
-                    ui.vertical_centered_justified(|ui| {
-                        ui.add_space(10.0);
-                        let display_name = if !post_to_zap.author_metadata.name.is_empty() {
-                            post_to_zap.author_metadata.name.clone()
-                        } else {
-                            let pubkey = post_to_zap.author_pubkey.to_bech32().unwrap_or_default();
-                            format!("{}...{}", &pubkey[0..8], &pubkey[pubkey.len()-4..])
-                        };
-                        ui.label(format!("{} にZAPします", display_name));
-                        ui.add_space(10.0);
-                        ui.horizontal(|ui| {
-                            ui.label("金額 (sats):");
-                            ui.add(egui::TextEdit::singleline(&mut app_data.zap_amount_input)
-                                .desired_width(120.0));
-                        });
-                        ui.add_space(10.0);
-                    });
-
-                    ui.separator();
-                    ui.add_space(5.0);
-
-                    ui.horizontal(|ui| {
-                        if ui.button("キャンセル").clicked() {
-                           close_dialog = true;
-                        }
-                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                            if ui.button("ZAP").clicked() {
-                                if let (Some(nwc), Some(nwc_client), Some(my_keys)) =
-                                    (app_data.nwc.as_ref(), app_data.nwc_client.as_ref(), app_data.my_keys.as_ref())
-                                {
-                                    if let Ok(amount_sats) = app_data.zap_amount_input.parse::<u64>() {
-                                        let nwc_clone = nwc.clone();
-                                        let nwc_client_clone = nwc_client.clone();
-                                        let my_keys_clone = my_keys.clone();
-                                        let app_data_clone = app_data_arc.clone();
-
-                                        runtime_handle.spawn(async move {
-                                            {
-                                                let mut data = app_data_clone.lock().unwrap();
-                                                data.should_repaint = true;
-                                            } // Lock is dropped here
-
-                                            let result = zap::send_zap_request(
-                                                &nwc_clone,
-                                                &nwc_client_clone,
-                                                &my_keys_clone,
-                                                post_to_zap.author_pubkey,
-                                                &post_to_zap.author_metadata.lud16,
-                                                amount_sats,
-                                                Some(post_to_zap.id),
-                                                Some(post_to_zap.kind),
-                                            ).await;
-
-                                            let mut data = app_data_clone.lock().unwrap();
-                                            match result {
-                                                Ok(_) => {
-                                                    // ZAPリクエストを送信しました。ウォレットの確認を待っています...
-                                                }
-                                                Err(e) => {
-                                                    eprintln!("ZAPエラー: {}", e);
-                                                }
-                                            }
-                                            data.should_repaint = true;
-                                        });
-
-                                        close_dialog = true;
-
-                                    } else {
-                                        eprintln!("無効な金額です");
-                                    }
-                                } else {
-                                    eprintln!("ZAPにはNWCの接続が必要です");
-                                }
-                            }
-                        });
-                    });
-                });
-            if close_dialog {
-                app_data.show_zap_dialog = false;
-                app_data.zap_target_post = None;
-            }
-        }
-    }
 
 
     if app_data.show_post_dialog {
@@ -244,7 +213,128 @@ pub fn draw_home_view(
                     .show_inside(ui, |ui| {
                         ui.add_space(10.0);
                         ui.horizontal(|ui| {
-                            if ui.button("😀").clicked() {
+                            let status_type_text = |status_type: StatusType| match status_type {
+                                StatusType::General => status_type_general_text,
+                                StatusType::Music => status_type_music_text,
+                                StatusType::Podcast => status_type_podcast_text,
+                                StatusType::Custom => status_type_custom_text,
+                            };
+                            ui.label(status_type_label_text);
+                            egui::ComboBox::from_id_salt("status_type_combo")
+                                .selected_text(status_type_text(app_data.current_status_type))
+                                .show_ui(ui, |ui| {
+                                    for status_type in [StatusType::General, StatusType::Music, StatusType::Custom] {
+                                        ui.selectable_value(&mut app_data.current_status_type, status_type, status_type_text(status_type));
+                                    }
+                                });
+                            if app_data.current_status_type == StatusType::Custom {
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut app_data.custom_d_tag_input)
+                                        .desired_width(140.0)
+                                        .hint_text(custom_d_tag_hint_text),
+                                );
+                            }
+                        });
+                        ui.add_space(5.0);
+                        ui.horizontal(|ui| {
+                            let expiry_text = |expiry: StatusExpiry| match expiry {
+                                StatusExpiry::Never => expiry_never_text,
+                                StatusExpiry::Minutes30 => expiry_30_minutes_text,
+                                StatusExpiry::Hour1 => expiry_1_hour_text,
+                                StatusExpiry::Hours4 => expiry_4_hours_text,
+                                StatusExpiry::Custom => expiry_custom_text,
+                            };
+                            ui.label(expiry_label_text);
+                            egui::ComboBox::from_id_salt("status_expiry_combo")
+                                .selected_text(expiry_text(app_data.status_expiry))
+                                .show_ui(ui, |ui| {
+                                    for expiry in [
+                                        StatusExpiry::Never,
+                                        StatusExpiry::Minutes30,
+                                        StatusExpiry::Hour1,
+                                        StatusExpiry::Hours4,
+                                        StatusExpiry::Custom,
+                                    ] {
+                                        ui.selectable_value(&mut app_data.status_expiry, expiry, expiry_text(expiry));
+                                    }
+                                });
+                            if app_data.status_expiry == StatusExpiry::Custom {
+                                ui.add(
+                                    egui::DragValue::new(&mut app_data.custom_expiry_minutes)
+                                        .range(1..=10080)
+                                        .suffix(expiry_minutes_suffix_text),
+                                );
+                            }
+                        });
+                        ui.add_space(5.0);
+                        ui.horizontal(|ui| {
+                            if ui.checkbox(&mut app_data.schedule_enabled, schedule_checkbox_text).changed()
+                                && app_data.schedule_enabled
+                                && app_data.schedule_time_input.is_empty()
+                            {
+                                let default_time = chrono::Local::now() + chrono::Duration::hours(1);
+                                app_data.schedule_time_input = default_time.format(SCHEDULE_TIME_FORMAT).to_string();
+                            }
+                            ui.add_enabled(
+                                app_data.schedule_enabled,
+                                egui::TextEdit::singleline(&mut app_data.schedule_time_input)
+                                    .desired_width(130.0)
+                                    .hint_text(schedule_time_hint_text),
+                            );
+                            ui.checkbox(&mut app_data.auto_clear_enabled, auto_clear_checkbox_text);
+                            ui.add_enabled(
+                                app_data.auto_clear_enabled,
+                                egui::DragValue::new(&mut app_data.auto_clear_hours)
+                                    .range(1..=168)
+                                    .suffix(auto_clear_hours_suffix_text),
+                            );
+                        });
+                        content_warning::draw_content_warning_input(ui, &mut app_data.status_content_warning, &mut app_data.status_content_warning_reason);
+                        ui.horizontal(|ui| {
+                            let private_response = ui.checkbox(&mut app_data.status_private, private_checkbox_text).on_hover_text(private_checkbox_hover_text);
+                            // 初めて非公開にしたときに自分のフォローセットを読み込む
+                            if private_response.changed()
+                                && app_data.status_private
+                                && app_data.follow_sets.is_empty()
+                                && !app_data.is_loading_follow_sets
+                            {
+                                private_status::spawn_fetch_follow_sets(app_data, app_data_arc.clone(), &runtime_handle);
+                            }
+                            if !app_data.status_private {
+                                return;
+                            }
+                            if app_data.is_loading_follow_sets {
+                                ui.spinner();
+                                return;
+                            }
+                            if app_data.follow_sets.is_empty() {
+                                ui.label(egui::RichText::new(no_follow_sets_text).small().color(egui::Color32::GRAY));
+                            } else {
+                                let selected_text = app_data
+                                    .selected_follow_set
+                                    .as_ref()
+                                    .and_then(|d_tag| app_data.follow_sets.iter().find(|set| &set.d_tag == d_tag))
+                                    .map_or(follow_set_placeholder_text.to_string(), |set| format!("{} ({})", set.title, set.members.len()));
+                                egui::ComboBox::from_id_salt("follow_set_combo")
+                                    .selected_text(selected_text)
+                                    .show_ui(ui, |ui| {
+                                        for set in &app_data.follow_sets {
+                                            ui.selectable_value(
+                                                &mut app_data.selected_follow_set,
+                                                Some(set.d_tag.clone()),
+                                                format!("{} ({})", set.title, set.members.len()),
+                                            );
+                                        }
+                                    });
+                            }
+                            if ui.small_button("🔄").on_hover_text(reload_follow_sets_hover_text).clicked() {
+                                private_status::spawn_fetch_follow_sets(app_data, app_data_arc.clone(), &runtime_handle);
+                            }
+                        });
+                        ui.add_space(5.0);
+                        ui.horizontal(|ui| {
+                            emoji_picker::draw_emoji_picker_button(ui, app_data, EmojiTarget::Status);
+                            if ui.button("😀").on_hover_text(custom_emoji_hover_text).clicked() {
                                 app_data.show_emoji_picker = !app_data.show_emoji_picker;
                             }
 
@@ -258,7 +348,7 @@ pub fn draw_home_view(
                                 app_data.show_podcast_dialog = true;
                             }
 
-                            let count = app_data.status_message_input.chars().count();
+                            let count = status_length(&app_data.status_message_input);
                             let counter_string = if app_data.current_status_type == StatusType::General {
                                 format!("{}/{}", count, MAX_STATUS_LENGTH)
                             } else {
@@ -273,107 +363,156 @@ pub fn draw_home_view(
 
                             ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                                 if ui.button(cancel_button_text).clicked() {
-                                    app_data.show_post_dialog = false;
-                                    app_data.current_status_type = StatusType::General;
-                                    app_data.status_message_input.clear();
-                                    app_data.music_track_input.clear();
-                                    app_data.music_url_input.clear();
-                                    app_data.podcast_episode_input.clear();
-                                    app_data.podcast_url_input.clear();
+                                    reset_post_dialog(app_data);
                                 }
                                 if ui.button(publish_button_text).clicked() && !app_data.is_loading {
                                     let status_message = app_data.status_message_input.clone();
                                     let client_clone_nip38_send = app_data.nostr_client.as_ref().unwrap().clone();
-                                    let keys_clone_nip38_send = app_data.my_keys.clone().unwrap();
+                                    let keys_clone_nip38_send = app_data.my_signer.clone().unwrap();
 
-                                    app_data.is_loading = true;
-                                    app_data.should_repaint = true;
-                                    println!("Publishing NIP-38 status...");
-
-                                    if app_data.current_status_type == StatusType::General && status_message.chars().count() > MAX_STATUS_LENGTH {
-                                        eprintln!("Status is too long (max {MAX_STATUS_LENGTH} chars)");
-                                        app_data.is_loading = false;
-                                        app_data.should_repaint = true;
+                                    if app_data.current_status_type == StatusType::General && status_length(&status_message) > MAX_STATUS_LENGTH {
+                                        error!("Status is too long (max {MAX_STATUS_LENGTH} chars)");
+                                        app_data.report_error(tr!("ステータスは{}文字までです", MAX_STATUS_LENGTH));
                                         return;
                                     }
 
                                     let current_status_type = app_data.current_status_type;
-                                    let music_url = app_data.music_url_input.clone();
-                                    let podcast_url = app_data.podcast_url_input.clone();
+                                    let custom_d_tag = app_data.custom_d_tag_input.trim().to_string();
+                                    if current_status_type == StatusType::Custom && custom_d_tag.is_empty() {
+                                        app_data.report_error(tr!("カスタムの種類には d タグを入力してください"));
+                                        return;
+                                    }
 
-                                    let my_emojis = app_data.my_emojis.clone();
-                                    let cloned_app_data_arc = app_data_arc.clone();
-                                    runtime_handle.spawn(async move {
-                                        let mut tags: Vec<Tag> = Vec::new();
-
-                                        // --- Emoji Tags ---
-                                        let re = Regex::new(r":(\w+):").unwrap();
-                                        let mut used_emojis: std::collections::HashSet<String> = std::collections::HashSet::new();
-                                        for cap in re.captures_iter(&status_message) {
-                                            if let Some(shortcode) = cap.get(1) {
-                                                used_emojis.insert(shortcode.as_str().to_string());
+                                    // 予約するときは入力した日時 (ローカル時刻) を使う
+                                    let run_at = if app_data.schedule_enabled {
+                                        let parsed = chrono::NaiveDateTime::parse_from_str(app_data.schedule_time_input.trim(), SCHEDULE_TIME_FORMAT)
+                                            .ok()
+                                            .and_then(|datetime| datetime.and_local_timezone(chrono::Local).single());
+                                        match parsed {
+                                            Some(datetime) if datetime > chrono::Local::now() => Some(Timestamp::from(datetime.timestamp() as u64)),
+                                            Some(_) => {
+                                                app_data.report_error(tr!("予約の日時には未来の時刻を指定してください"));
+                                                return;
                                             }
-                                        }
-                                        for shortcode in used_emojis {
-                                            if let Some(url) = my_emojis.get(&shortcode) {
-                                                if let Ok(tag) = Tag::parse(["emoji", &shortcode, url]) {
-                                                    tags.push(tag);
-                                                }
+                                            None => {
+                                                app_data.report_error(tr!("予約の日時は「2025-01-31 18:30」の形式で入力してください"));
+                                                return;
                                             }
                                         }
+                                    } else {
+                                        None
+                                    };
+                                    let auto_clear_after = app_data
+                                        .auto_clear_enabled
+                                        .then(|| std::time::Duration::from_secs(app_data.auto_clear_hours * 60 * 60));
+                                    let expiry = app_data.status_expiry.duration(app_data.custom_expiry_minutes);
 
-                                        let d_tag_value = match current_status_type {
-                                            StatusType::General => "general",
-                                            StatusType::Music | StatusType::Podcast => "music",
-                                        };
-                                        tags.push(Tag::identifier(d_tag_value.to_string()));
+                                    // 本文の nostr: 参照 (NIP-27) に合わせて p タグなどを付ける
+                                    let (status_message, mut tags) = note::prepare_note(&status_message);
 
-                                        let r_url = match current_status_type {
-                                            StatusType::Music => music_url,
-                                            StatusType::Podcast => podcast_url,
-                                            _ => String::new(),
-                                        };
+                                    // --- Emoji Tags ---
+                                    tags.extend(note::emoji_tags(&status_message, &app_data.my_emojis));
 
-                                        if !r_url.is_empty() {
-                                            if let Ok(tag) = Tag::parse(["r", &r_url]) {
-                                                tags.push(tag);
-                                            }
-                                        }
+                                    let d_tag_value = match current_status_type {
+                                        StatusType::General => "general".to_string(),
+                                        StatusType::Music | StatusType::Podcast => "music".to_string(),
+                                        StatusType::Custom => custom_d_tag,
+                                    };
+                                    tags.push(Tag::identifier(d_tag_value.clone()));
+                                    if app_data.status_content_warning {
+                                        tags.push(note::content_warning_tag(&app_data.status_content_warning_reason));
+                                    }
 
-                                        if current_status_type != StatusType::General {
-                                            let expiration_time = chrono::Utc::now().timestamp() + 30 * 60; // 30 minutes
-                                            tags.push(Tag::expiration(Timestamp::from(
-                                                expiration_time as u64,
-                                            )));
+                                    let r_url = match current_status_type {
+                                        StatusType::Music => app_data.music_url_input.clone(),
+                                        StatusType::Podcast => app_data.podcast_url_input.clone(),
+                                        _ => String::new(),
+                                    };
+
+                                    if !r_url.is_empty()
+                                        && let Ok(tag) = Tag::parse(["r", &r_url]) {
+                                        tags.push(tag);
+                                    }
+
+                                    // 非公開のステータスはその場でフォローセットの人に送る
+                                    if app_data.status_private {
+                                        if run_at.is_some() || auto_clear_after.is_some() {
+                                            app_data.report_error(tr!("非公開のステータスは予約や自動の消去に対応していません"));
+                                            return;
                                         }
+                                        let follow_set = app_data
+                                            .selected_follow_set
+                                            .as_ref()
+                                            .and_then(|d_tag| app_data.follow_sets.iter().find(|set| &set.d_tag == d_tag))
+                                            .cloned();
+                                        let Some(follow_set) = follow_set else {
+                                            app_data.report_error(tr!("送り先のフォローセットを選んでください"));
+                                            return;
+                                        };
+                                        app_data.is_loading = true;
+                                        app_data.should_repaint = true;
+                                        info!("Sending a private status to {} members...", follow_set.members.len());
 
-                                        let event_result = EventBuilder::new(Kind::from(30315), status_message.clone())
-                                            .tags(tags)
-                                            .sign(&keys_clone_nip38_send)
+                                        let cloned_app_data_arc = app_data_arc.clone();
+                                        runtime_handle.spawn(async move {
+                                            let sent = private_status::publish(
+                                                client_clone_nip38_send,
+                                                keys_clone_nip38_send,
+                                                status_message,
+                                                tags,
+                                                expiry,
+                                                follow_set,
+                                                cloned_app_data_arc.clone(),
+                                            )
                                             .await;
-
-                                        match event_result {
-                                            Ok(event) => match client_clone_nip38_send.send_event(&event).await {
-                                                Ok(event_id) => {
-                                                    println!("Status published with event id: {event_id:?}");
-                                                    let mut data = cloned_app_data_arc.lock().unwrap();
-                                                    data.status_message_input.clear();
-                                                    data.show_post_dialog = false;
-                                                    data.current_status_type = StatusType::General;
-                                                    data.music_track_input.clear();
-                                                    data.music_url_input.clear();
-                                                    data.podcast_episode_input.clear();
-                                                    data.podcast_url_input.clear();
-                                                }
-                                                Err(e) => {
-                                                    eprintln!("Failed to publish status: {e}");
-                                                }
-                                            },
-                                            Err(e) => {
-                                                eprintln!("Failed to create event: {e}");
+                                            let mut data = cloned_app_data_arc.lock().unwrap();
+                                            if sent {
+                                                reset_post_dialog(&mut data);
                                             }
+                                            data.is_loading = false;
+                                            data.should_repaint = true;
+                                        });
+                                        return;
+                                    }
+
+                                    if let Some(run_at) = run_at {
+                                        scheduler::schedule(
+                                            app_data,
+                                            run_at,
+                                            d_tag_value.clone(),
+                                            ScheduledAction::Publish { content: status_message, tags, expiry },
+                                        );
+                                        if let Some(after) = auto_clear_after {
+                                            scheduler::schedule(app_data, run_at + after, d_tag_value, ScheduledAction::Clear);
                                         }
+                                        app_data.notify(tr!("ステータスの公開を予約しました"));
+                                        reset_post_dialog(app_data);
+                                        return;
+                                    }
+
+                                    if let Some(after) = auto_clear_after {
+                                        scheduler::schedule(app_data, Timestamp::now() + after, d_tag_value, ScheduledAction::Clear);
+                                    }
+                                    app_data.is_loading = true;
+                                    app_data.should_repaint = true;
+                                    info!("Publishing NIP-38 status...");
+
+                                    let cloned_app_data_arc = app_data_arc.clone();
+                                    runtime_handle.spawn(async move {
+                                        let published = status_history::publish(
+                                            client_clone_nip38_send,
+                                            keys_clone_nip38_send,
+                                            status_message,
+                                            tags,
+                                            expiry,
+                                            cloned_app_data_arc.clone(),
+                                        )
+                                        .await;
                                         let mut data = cloned_app_data_arc.lock().unwrap();
+                                        // 送れなかった場合も送信キューに残るので、入力は片付ける
+                                        if published {
+                                            reset_post_dialog(&mut data);
+                                        }
                                         data.is_loading = false;
                                         data.should_repaint = true;
                                     });
@@ -384,69 +523,44 @@ pub fn draw_home_view(
 
                 egui::CentralPanel::default().show_inside(ui, |ui| {
                     ui.add_space(15.0);
+                    // 定型文を押すと入力欄に入る
+                    if !app_data.settings.status_presets.is_empty() {
+                        ui.horizontal_wrapped(|ui| {
+                            for preset in &app_data.settings.status_presets {
+                                let chip = egui::Button::new(preset).corner_radius(12.0);
+                                if ui.add(chip).clicked() {
+                                    app_data.status_message_input = preset.clone();
+                                }
+                            }
+                        });
+                        ui.add_space(5.0);
+                    }
                     egui::ScrollArea::vertical().show(ui, |ui| {
-                        ui.add(
+                        let status_response = ui.add(
                             egui::TextEdit::multiline(&mut app_data.status_message_input)
                                 .desired_rows(5)
                                 .desired_width(f32::INFINITY)
                                 .hint_text(status_input_hint_text),
                         );
+                        mention::draw_mention_popup(ui, &status_response, &mut app_data.status_message_input, &app_data.followed_pubkeys, &app_data.profiles);
                     });
                 });
             });
 
         if app_data.show_emoji_picker {
-            egui::Window::new("カスタム絵文字")
+            egui::Window::new(tr!("カスタム絵文字"))
                 .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 180.0)) // Adjust position to be below the post dialog
                 .collapsible(false)
                 .resizable(false)
                 .show(ctx, |ui| {
-                    ui.label("絵文字を選択");
+                    ui.label(tr!("絵文字を選択"));
                     egui::ScrollArea::vertical().max_height(150.0).show(ui, |ui| {
-                        ui.with_layout(egui::Layout::left_to_right(egui::Align::TOP).with_main_wrap(true), |ui| {
-                            if app_data.my_emojis.is_empty() {
-                                ui.label("カスタム絵文字が設定されていません。");
-                            } else {
-                                for (shortcode, url) in app_data.my_emojis.clone().into_iter() {
-                                    let emoji_size = egui::vec2(24.0, 24.0);
-                                    let url_key = url.to_string();
-
-                                    let sense = egui::Sense::click();
-                                    let (rect, response) = ui.allocate_exact_size(emoji_size, sense);
-
-                                    if response.hovered() {
-                                        ui.painter().rect_filled(rect.expand(2.0), egui::CornerRadius::from(4.0), ui.visuals().widgets.hovered.bg_fill);
-                                    }
-
-                                    match app_data.image_cache.get(&url_key) {
-                                        Some(ImageState::Loaded(texture_handle)) => {
-                                            let image = egui::Image::new(texture_handle).fit_to_exact_size(emoji_size);
-                                            image.paint_at(ui, rect);
-                                        }
-                                        Some(ImageState::Loading) => {
-                                            ui.put(rect, egui::Spinner::new());
-                                        }
-                                        Some(ImageState::Failed) => {
-                                            ui.painter().text(rect.center(), egui::Align2::CENTER_CENTER, "💔", egui::FontId::default(), ui.visuals().error_fg_color);
-                                        }
-                                        None => {
-                                            if !urls_to_load.iter().any(|(u, _)| u == &url_key) {
-                                                urls_to_load.push((url_key.clone(), ImageKind::Emoji));
-                                            }
-                                            ui.put(rect, egui::Spinner::new());
-                                        }
-                                    }
-
-                                    if response.clicked() {
-                                        app_data.status_message_input.push_str(&format!(":{}:", shortcode));
-                                        app_data.show_emoji_picker = false;
-                                    }
-                                    response.on_hover_text(&format!(":{}:", shortcode));
-                                }
-                            }
-                        });
+                        if let Some(shortcode) = emoji::draw_emoji_grid(ui, &app_data.my_emojis, &app_data.image_cache, egui::vec2(24.0, 24.0), &mut urls_to_load) {
+                            app_data.status_message_input.push_str(&format!(":{shortcode}:"));
+                            app_data.show_emoji_picker = false;
+                        }
                     });
-                    if ui.button("閉じる").clicked() {
+                    if ui.button(tr!("閉じる")).clicked() {
                         app_data.show_emoji_picker = false;
                     }
                 });
@@ -455,21 +569,21 @@ pub fn draw_home_view(
 
     // --- Music Status Dialog ---
     if app_data.show_music_dialog {
-        egui::Window::new("音楽ステータスを設定")
+        egui::Window::new(tr!("音楽ステータスを設定"))
             .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
             .collapsible(false)
             .resizable(false)
             .show(ctx, |ui| {
                 ui.vertical_centered_justified(|ui| {
                     ui.add_space(10.0);
-                    ui.label("曲名");
+                    ui.label(tr!("曲名"));
                     ui.add(
                         egui::TextEdit::singleline(&mut app_data.music_track_input)
                             .desired_width(f32::INFINITY)
                             .hint_text("Sayonara - Gen Hoshino"),
                     );
                     ui.add_space(10.0);
-                    ui.label("URL（任意）");
+                    ui.label(tr!("URL（任意）"));
                     ui.add(
                         egui::TextEdit::singleline(&mut app_data.music_url_input)
                             .desired_width(f32::INFINITY)
@@ -482,18 +596,18 @@ pub fn draw_home_view(
                 ui.add_space(5.0);
 
                 ui.horizontal(|ui| {
-                    if ui.button("キャンセル").clicked() {
+                    if ui.button(tr!("キャンセル")).clicked() {
                         app_data.show_music_dialog = false;
                         app_data.music_track_input.clear();
                         app_data.music_url_input.clear();
                     }
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                        if ui.button("ステータスを設定").clicked() {
-                            if !app_data.music_track_input.is_empty() {
-                                app_data.status_message_input = app_data.music_track_input.clone();
-                                app_data.current_status_type = StatusType::Music;
-                                app_data.show_music_dialog = false;
-                            }
+                        if ui.button(tr!("ステータスを設定")).clicked()
+                            && !app_data.music_track_input.is_empty() {
+                            app_data.status_message_input = app_data.music_track_input.clone();
+                            app_data.current_status_type = StatusType::Music;
+                            app_data.status_expiry = StatusExpiry::Minutes30;
+                            app_data.show_music_dialog = false;
                         }
                     });
                 });
@@ -502,21 +616,21 @@ pub fn draw_home_view(
 
     // --- Podcast Status Dialog ---
     if app_data.show_podcast_dialog {
-        egui::Window::new("ポッドキャストステータスを設定")
+        egui::Window::new(tr!("ポッドキャストステータスを設定"))
             .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
             .collapsible(false)
             .resizable(false)
             .show(ctx, |ui| {
                 ui.vertical_centered_justified(|ui| {
                     ui.add_space(10.0);
-                    ui.label("エピソードのタイトル");
+                    ui.label(tr!("エピソードのタイトル"));
                     ui.add(
                         egui::TextEdit::singleline(&mut app_data.podcast_episode_input)
                             .desired_width(f32::INFINITY)
-                            .hint_text("コジ10 小島秀夫の『最高の10時にしよう』"),
+                            .hint_text(tr!("コジ10 小島秀夫の『最高の10時にしよう』")),
                     );
                     ui.add_space(10.0);
-                    ui.label("URL（任意）");
+                    ui.label(tr!("URL（任意）"));
                     ui.add(
                         egui::TextEdit::singleline(&mut app_data.podcast_url_input)
                             .desired_width(f32::INFINITY)
@@ -529,24 +643,226 @@ pub fn draw_home_view(
                 ui.add_space(5.0);
 
                 ui.horizontal(|ui| {
-                    if ui.button("キャンセル").clicked() {
+                    if ui.button(tr!("キャンセル")).clicked() {
                         app_data.show_podcast_dialog = false;
                         app_data.podcast_episode_input.clear();
                         app_data.podcast_url_input.clear();
                     }
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                        if ui.button("ステータスを設定").clicked() {
-                            if !app_data.podcast_episode_input.is_empty() {
-                                app_data.status_message_input = app_data.podcast_episode_input.clone();
-                                app_data.current_status_type = StatusType::Podcast;
-                                app_data.show_podcast_dialog = false;
-                            }
+                        if ui.button(tr!("ステータスを設定")).clicked()
+                            && !app_data.podcast_episode_input.is_empty() {
+                            app_data.status_message_input = app_data.podcast_episode_input.clone();
+                            app_data.current_status_type = StatusType::Podcast;
+                            app_data.status_expiry = StatusExpiry::Minutes30;
+                            app_data.show_podcast_dialog = false;
                         }
                     });
                 });
             });
     }
 
+    // --- ノート (kind 1) ---
+    card_frame.show(ui, |ui| {
+        egui::CollapsingHeader::new(egui::RichText::new(note_heading_text).strong())
+            .id_salt("note_composer_header")
+            .show(ui, |ui| {
+                let note_response = ui.add(
+                    egui::TextEdit::multiline(&mut app_data.note_input)
+                        .desired_rows(3)
+                        .desired_width(f32::INFINITY)
+                        .hint_text(note_input_hint_text),
+                );
+                mention::draw_mention_popup(ui, &note_response, &mut app_data.note_input, &app_data.followed_pubkeys, &app_data.profiles);
+                content_warning::draw_content_warning_input(ui, &mut app_data.note_content_warning, &mut app_data.note_content_warning_reason);
+                attachment::draw_attach_panel(ui, app_data, app_data_arc.clone(), &runtime_handle, AttachTarget::Note);
+                ui.horizontal(|ui| {
+                    emoji_picker::draw_emoji_picker_button(ui, app_data, EmojiTarget::Note);
+                    attachment::draw_attach_button(ui, app_data, AttachTarget::Note);
+                    let can_post = !app_data.note_input.trim().is_empty() && !app_data.is_posting_note;
+                    if ui.add_enabled(can_post, egui::Button::new(post_note_button_text)).clicked()
+                        && let (Some(client), Some(signer)) = (app_data.nostr_client.clone(), app_data.my_signer.clone()) {
+                        let content = app_data.note_input.clone();
+                        let content_warning = app_data.note_content_warning.then(|| app_data.note_content_warning_reason.clone());
+                        let imeta_tags = media::imeta_tags(&content, &app_data.uploaded_media);
+                        app_data.is_posting_note = true;
+                        let cloned_app_data_arc = app_data_arc.clone();
+                        runtime_handle.spawn(async move {
+                            let published = note::publish_note(client, signer, content, content_warning, imeta_tags, cloned_app_data_arc.clone()).await;
+                            let mut data = cloned_app_data_arc.lock().unwrap();
+                            if published {
+                                data.note_input.clear();
+                                data.note_content_warning = false;
+                                data.note_content_warning_reason.clear();
+                            }
+                            data.is_posting_note = false;
+                            data.should_repaint = true;
+                        });
+                    }
+                    if app_data.is_posting_note {
+                        ui.spinner();
+                    }
+                });
+            });
+    });
+    ui.add_space(10.0);
+
+    // --- 送信キュー ---
+    if !app_data.outbox.is_empty() {
+        let mut event_to_discard: Option<EventId> = None;
+        let mut event_to_retry: Option<EventId> = None;
+        let mut clear_sent = false;
+        card_frame.show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.strong(outbox_heading_text);
+                if app_data.outbox.iter().any(|entry| entry.state == OutboxState::Sent)
+                    && ui.small_button(clear_sent_button_text).clicked()
+                {
+                    clear_sent = true;
+                }
+            });
+            egui::ScrollArea::vertical().id_salt("outbox_scroll_area").max_height(200.0).show(ui, |ui| {
+                for entry in app_data.outbox.iter().rev() {
+                    ui.horizontal(|ui| {
+                        match entry.state {
+                            OutboxState::Pending => {
+                                ui.colored_label(egui::Color32::from_rgb(220, 160, 40), pending_label_text);
+                            }
+                            OutboxState::Sent => {
+                                ui.colored_label(egui::Color32::from_rgb(60, 170, 90), sent_label_text);
+                            }
+                        }
+                        timestamp::draw_timestamp(ui, entry.event.created_at);
+                        // 削除要求や空のステータスは本文が無く、トークは暗号化されているので種類を表示する
+                        let preview: String = if entry.event.content.is_empty()
+                            || matches!(entry.event.kind, Kind::GiftWrap | Kind::EncryptedDirectMessage)
+                        {
+                            format!("(kind {})", entry.event.kind.as_u16())
+                        } else {
+                            entry.event.content.chars().take(40).collect()
+                        };
+                        let response = ui.label(preview);
+                        if let Some(error) = &entry.last_error {
+                            response.on_hover_text(tr!("{}回試行: {}", entry.attempts, error));
+                        }
+                        if entry.state == OutboxState::Pending && ui.small_button(discard_button_text).clicked() {
+                            event_to_discard = Some(entry.event.id);
+                        }
+                    });
+                    // リレーごとの受理・拒否の結果
+                    if !entry.relay_results.is_empty() {
+                        let accepted = entry.relay_results.iter().filter(|r| r.error.is_none()).count();
+                        egui::CollapsingHeader::new(format!("{relay_results_label_text} ({accepted}/{})", entry.relay_results.len()))
+                            .id_salt(("outbox_relay_results", entry.event.id))
+                            .show(ui, |ui| {
+                                egui::Grid::new(("outbox_relay_results_grid", entry.event.id))
+                                    .num_columns(3)
+                                    .spacing([12.0, 4.0])
+                                    .striped(true)
+                                    .show(ui, |ui| {
+                                        for result in &entry.relay_results {
+                                            ui.label(&result.url);
+                                            match &result.error {
+                                                None => {
+                                                    ui.colored_label(egui::Color32::from_rgb(60, 170, 90), accepted_label_text);
+                                                    ui.label("");
+                                                }
+                                                Some(reason) => {
+                                                    ui.colored_label(ui.visuals().error_fg_color, rejected_label_text);
+                                                    ui.label(reason);
+                                                }
+                                            }
+                                            ui.end_row();
+                                        }
+                                    });
+                                if entry.relay_results.iter().any(|r| r.error.is_some())
+                                    && ui.small_button(retry_failed_button_text).clicked()
+                                {
+                                    event_to_retry = Some(entry.event.id);
+                                }
+                            });
+                    }
+                }
+            });
+        });
+        if let Some(event_id) = event_to_discard {
+            outbox::discard(app_data, event_id);
+        }
+        if let (Some(event_id), Some(client)) = (event_to_retry, app_data.nostr_client.clone()) {
+            runtime_handle.spawn(outbox::retry_failed_relays(client, event_id, app_data_arc.clone()));
+        }
+        if clear_sent {
+            outbox::clear_sent(app_data);
+        }
+        ui.add_space(10.0);
+    }
+
+    // --- 予約中のステータス ---
+    if !app_data.scheduled_statuses.is_empty() {
+        let mut id_to_cancel: Option<u64> = None;
+        card_frame.show(ui, |ui| {
+            ui.label(egui::RichText::new(scheduled_heading_text).strong());
+            ui.add_space(5.0);
+            egui::Grid::new("scheduled_statuses_grid")
+                .num_columns(5)
+                .spacing([12.0, 6.0])
+                .striped(true)
+                .show(ui, |ui| {
+                    for item in &app_data.scheduled_statuses {
+                        ui.label(egui::RichText::new(chats_view::to_local(item.run_at).format("%Y-%m-%d %H:%M").to_string()).small().color(egui::Color32::GRAY));
+                        match &item.action {
+                            ScheduledAction::Publish { .. } => ui.label(scheduled_publish_text),
+                            ScheduledAction::Clear => ui.label(scheduled_clear_text),
+                        };
+                        ui.label(egui::RichText::new(&item.d_tag).small());
+                        match &item.action {
+                            ScheduledAction::Publish { content, .. } => ui.label(content),
+                            ScheduledAction::Clear => ui.label(""),
+                        };
+                        if ui.small_button(cancel_schedule_button_text).clicked() {
+                            id_to_cancel = Some(item.id);
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+        if let Some(id) = id_to_cancel {
+            scheduler::cancel(app_data, id);
+        }
+        ui.add_space(10.0);
+    }
+
+    // --- ステータスの履歴 ---
+    if !app_data.status_history.is_empty() {
+        let mut entry_to_republish: Option<StatusHistoryEntry> = None;
+        card_frame.show(ui, |ui| {
+            egui::CollapsingHeader::new(egui::RichText::new(history_heading_text).strong())
+                .id_salt("status_history_header")
+                .show(ui, |ui| {
+                    egui::ScrollArea::vertical().id_salt("status_history_scroll_area").max_height(200.0).show(ui, |ui| {
+                        egui::Grid::new("status_history_grid")
+                            .num_columns(4)
+                            .spacing([12.0, 6.0])
+                            .striped(true)
+                            .show(ui, |ui| {
+                                for entry in &app_data.status_history {
+                                    timestamp::draw_timestamp(ui, entry.created_at);
+                                    ui.label(egui::RichText::new(&entry.d_tag).small());
+                                    ui.label(&entry.content);
+                                    if ui.add_enabled(!app_data.is_loading, egui::Button::new(republish_button_text).small()).clicked() {
+                                        entry_to_republish = Some(entry.clone());
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                    });
+                });
+        });
+        if let (Some(entry), Some(client), Some(signer)) = (entry_to_republish, app_data.nostr_client.clone(), app_data.my_signer.clone()) {
+            runtime_handle.spawn(status_history::republish(client, signer, entry, app_data_arc.clone()));
+        }
+        ui.add_space(10.0);
+    }
+
     card_frame.show(ui, |ui| {
         ui.horizontal(|ui| {
             ui.heading(timeline_heading_text);
@@ -554,273 +870,329 @@ pub fn draw_home_view(
             let fetch_button = egui::Button::new(egui::RichText::new(fetch_latest_button_text).strong());
             if ui.add_enabled(!app_data.is_loading, fetch_button).clicked() {
                 let followed_pubkeys = app_data.followed_pubkeys.clone();
-                let discover_relays = app_data.discover_relays_editor.clone();
-                let my_keys = app_data.my_keys.clone().unwrap();
+                let settings = app_data.settings.clone();
+                let my_signer = app_data.my_signer.clone().unwrap();
+                let session = app_data.session_generation;
 
                 app_data.is_loading = true;
                 app_data.should_repaint = true;
 
                 let cloned_app_data_arc = app_data_arc.clone();
                 runtime_handle.spawn(async move {
-                    let timeline_result = fetch_timeline_events(&my_keys, &discover_relays, &followed_pubkeys).await;
+                    let timeline_result = fetch_timeline_events(&my_signer, &settings, &followed_pubkeys, |_| {}).await;
 
                     let mut app_data_async = cloned_app_data_arc.lock().unwrap();
+                    // 読み込んでいる間にロックやログアウトをしていたら結果は捨てる
+                    if !app_data_async.is_current_session(session) {
+                        return;
+                    }
                     app_data_async.is_loading = false;
                     match timeline_result {
                         Ok(new_posts) => {
                             if !new_posts.is_empty() {
-                                let mut existing_ids: std::collections::HashSet<EventId> = app_data_async.timeline_posts.iter().map(|p| p.id).collect();
-                                let mut added_posts = 0;
-                                for post in new_posts {
-                                    if !existing_ids.contains(&post.id) {
-                                        existing_ids.insert(post.id);
-                                        app_data_async.timeline_posts.push(post);
-                                        added_posts += 1;
-                                    }
-                                }
-
+                                let added_posts = replaceable::merge_timeline_posts(&mut app_data_async.timeline_posts, new_posts);
                                 if added_posts > 0 {
-                                    app_data_async.timeline_posts.sort_by_key(|p| std::cmp::Reverse(p.created_at));
-                                    println!("Added {} new statuses to the timeline.", added_posts);
+                                    info!("Added {} new statuses to the timeline.", added_posts);
                                 } else {
-                                    println!("No new statuses found.");
+                                    info!("No new statuses found.");
                                 }
                             } else {
-                                println!("Fetched 0 statuses.");
+                                info!("Fetched 0 statuses.");
                             }
                         },
                         Err(e) => {
-                            eprintln!("Failed to fetch timeline: {e}");
+                            error!("Failed to fetch timeline: {e}");
+                            app_data_async.report_error(tr!("タイムラインを取得できませんでした: {}", e));
                         }
                     }
                     app_data_async.should_repaint = true;
                 });
             }
 
+            if ui.add_enabled(!app_data.is_loading, egui::Button::new(clear_status_button_text)).clicked()
+                && let (Some(client), Some(signer)) = (app_data.nostr_client.clone(), app_data.my_signer.clone()) {
+                runtime_handle.spawn(status_history::clear_status(client, signer, "general".to_string(), app_data_arc.clone()));
+            }
+
             if app_data.is_loading {
                 ui.add_space(10.0);
                 ui.spinner();
-                ui.label("更新中...");
+                ui.label(tr!("更新中..."));
             }
         });
         ui.add_space(10.0);
-        let mut pubkey_to_modify: Option<(PublicKey, bool)> = None;
 
-        if app_data.timeline_posts.is_empty() {
+        // d タグごとの今のステータスと、期限付きなら残り時間を表示する
+        let now = Timestamp::now();
+        let current_statuses: Vec<StatusHistoryEntry> = app_data
+            .current_statuses
+            .values()
+            .filter(|entry| !entry.content.is_empty() && !entry.is_expired())
+            .cloned()
+            .collect();
+        let mut d_tag_to_clear: Option<String> = None;
+        if !current_statuses.is_empty() {
+            ui.label(egui::RichText::new(current_statuses_heading_text).strong());
+            egui::Grid::new("current_statuses_grid")
+                .num_columns(4)
+                .spacing([10.0, 4.0])
+                .show(ui, |ui| {
+                    for entry in &current_statuses {
+                        ui.label(format!("{} {}", StatusGroup::from_d_tag(&entry.d_tag).icon(), entry.d_tag));
+                        ui.label(&entry.content);
+                        match entry.expiration() {
+                            Some(expiration) => {
+                                let remaining = expiration.as_u64() - now.as_u64();
+                                let countdown = if remaining >= 3600 {
+                                    tr!("あと {}時間{}分", remaining / 3600, remaining % 3600 / 60)
+                                } else if remaining >= 60 {
+                                    tr!("あと {}分", remaining.div_ceil(60))
+                                } else {
+                                    tr!("あと {}秒", remaining)
+                                };
+                                ui.weak(countdown);
+                                ctx.request_repaint_after(std::time::Duration::from_secs(1));
+                            }
+                            None => {
+                                ui.label("");
+                            }
+                        }
+                        if ui.add_enabled(!app_data.is_loading, egui::Button::new(clear_button_text).small()).clicked() {
+                            d_tag_to_clear = Some(entry.d_tag.clone());
+                        }
+                        ui.end_row();
+                    }
+                });
+            ui.add_space(10.0);
+        }
+        if let (Some(d_tag), Some(client), Some(signer)) = (d_tag_to_clear, app_data.nostr_client.clone(), app_data.my_signer.clone()) {
+            runtime_handle.spawn(status_history::clear_status(client, signer, d_tag, app_data_arc.clone()));
+        }
+
+        // 種類ごとに絞り込んで表示する
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut app_data.timeline_status_filter, None, filter_all_text);
+            ui.selectable_value(&mut app_data.timeline_status_filter, Some(StatusGroup::General), filter_general_text);
+            ui.selectable_value(&mut app_data.timeline_status_filter, Some(StatusGroup::Music), filter_music_text);
+            ui.selectable_value(&mut app_data.timeline_status_filter, Some(StatusGroup::Other), filter_other_text);
+        });
+        ui.add_space(10.0);
+
+        // ログイン直後は、届いた投稿から順に並べながら読み込み中であることを示す
+        if let Some(login_stage) = &app_data.login_stage {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label(login_stage.as_str());
+            });
+            ui.add_space(10.0);
+        }
+
+        // 期限を過ぎたステータスはタイムラインから取り除く
+        app_data.timeline_posts.retain(|post| !post.is_expired());
+        if let Some(next_expiration) = app_data.timeline_posts.iter().filter_map(|post| post.expiration()).min() {
+            let remaining = next_expiration.as_u64().saturating_sub(Timestamp::now().as_u64());
+            ctx.request_repaint_after(std::time::Duration::from_secs(remaining.max(1)));
+        }
+
+        let mut pubkey_to_modify: Option<(PublicKey, bool)> = None;
+        let mut zap_to_send: Option<ZapTarget> = None;
+        let mut zap_to_open: Option<ZapTarget> = None;
+        let mut event_to_repost: Option<EventId> = None;
+        let mut event_to_bookmark: Option<EventId> = None;
+        let mut link_to_open: Option<NostrLink> = None;
+        let mut nip05_to_verify: Vec<(PublicKey, String)> = Vec::new();
+        let mut content_warning_toggle: Option<EventId> = None;
+        let mut label_to_publish: Option<(ReportTarget, String)> = None;
+        let visible_posts: Vec<usize> = app_data
+            .timeline_posts
+            .iter()
+            .enumerate()
+            .filter(|(_, post)| {
+                app_data
+                    .timeline_status_filter
+                    .is_none_or(|group| StatusGroup::from_d_tag(post.d_tag()) == group)
+                    && !app_data.settings.is_muted(&post.content)
+                    && post_label_action(app_data, post) != Some(LabelAction::Hide)
+            })
+            .map(|(i, _)| i)
+            .collect();
+
+        if visible_posts.is_empty() {
             ui.label(no_timeline_message_text);
         } else {
-            let num_posts = app_data.timeline_posts.len();
+            let num_posts = visible_posts.len();
             let row_height = 90.0;
+            let my_pubkey = app_data.my_signer.as_ref().map(|signer| signer.public_key());
 
             egui::ScrollArea::vertical()
                 .id_salt("timeline_scroll_area")
                 .max_height(ui.available_height() - 100.0)
                 .show_rows(ui, row_height, num_posts, |ui, row_range| {
                     for i in row_range {
-                        let post = app_data.timeline_posts[i].clone();
+                        let post = app_data.timeline_posts[visible_posts[i]].clone();
+                        // ログイン時に取得したプロフィールを優先し、なければ投稿取得時のメタデータを使う
+                        let author_metadata = app_data
+                            .profiles
+                            .get(&post.author_pubkey)
+                            .cloned()
+                            .unwrap_or_else(|| post.author_metadata.clone());
                         card_frame.show(ui, |ui| {
                             ui.horizontal(|ui| {
-                                let avatar_size = egui::vec2(32.0, 32.0);
-                                let corner_radius = 4.0;
-                                let url = &post.author_metadata.picture;
-
-                                if !url.is_empty() {
-                                    let url_key = url.to_string();
-                                    let image_state = app_data.image_cache.get(&url_key).cloned();
-
-                                    match image_state {
-                                        Some(ImageState::Loaded(texture_handle)) => {
-                                            let image_widget = egui::Image::new(&texture_handle)
-                                                .corner_radius(corner_radius)
-                                                .fit_to_exact_size(avatar_size);
-                                            ui.add(image_widget);
-                                        }
-                                        Some(ImageState::Loading) => {
-                                            let (rect, _) = ui.allocate_exact_size(avatar_size, egui::Sense::hover());
-                                            ui.painter().rect_filled(rect, corner_radius, ui.style().visuals.widgets.inactive.bg_fill);
-                                            ui.put(rect, egui::Spinner::new());
-                                        }
-                                        Some(ImageState::Failed) => {
-                                            let (rect, _) = ui.allocate_exact_size(avatar_size, egui::Sense::hover());
-                                            ui.painter().rect_filled(rect, corner_radius, ui.style().visuals.error_fg_color.linear_multiply(0.2));
-                                        }
-                                        None => {
-                                            if !urls_to_load.iter().any(|(u, _)| u == &url_key) {
-                                                urls_to_load.push((url_key.clone(), ImageKind::Avatar));
-                                            }
-                                            let (rect, _) = ui.allocate_exact_size(avatar_size, egui::Sense::hover());
-                                            ui.painter().rect_filled(rect, corner_radius, ui.style().visuals.widgets.inactive.bg_fill);
-                                            ui.put(rect, egui::Spinner::new());
-                                        }
-                                    }
-                                } else {
-                                    let (rect, _) = ui.allocate_exact_size(avatar_size, egui::Sense::hover());
-                                    ui.painter().rect_filled(rect, corner_radius, ui.style().visuals.widgets.inactive.bg_fill);
-                                }
+                                image_cache::draw_user_avatar(
+                                    ui,
+                                    &app_data.image_cache,
+                                    &post.author_pubkey,
+                                    &author_metadata.picture,
+                                    egui::vec2(32.0, 32.0),
+                                    4.0,
+                                    &mut urls_to_load,
+                                );
 
                                 ui.add_space(8.0);
 
-                                let display_name = if !post.author_metadata.name.is_empty() {
-                                    post.author_metadata.name.clone()
-                                } else {
-                                    let pubkey = post.author_pubkey.to_bech32().unwrap_or_default();
-                                    format!("{}...{}", &pubkey[0..8], &pubkey[pubkey.len()-4..])
-                                };
-                                ui.label(egui::RichText::new(display_name).strong().color(app_data.current_theme.text_color()));
-
-                                let created_at_datetime = chrono::DateTime::from_timestamp(post.created_at.as_u64() as i64, 0).unwrap();
-                                let local_datetime = created_at_datetime.with_timezone(&chrono::Local);
-                                ui.label(egui::RichText::new(local_datetime.format("%Y-%m-%d %H:%M:%S").to_string()).color(egui::Color32::GRAY).small());
-
-                                if let Some(my_keys) = &app_data.my_keys {
-                                    if post.author_pubkey != my_keys.public_key() {
-                                        // ZAP button
-                                        if !post.author_metadata.lud16.is_empty() {
-                                            if ui.button("⚡").clicked() {
-                                                app_data.zap_target_post = Some(post.clone());
-                                                app_data.show_zap_dialog = true;
-                                                app_data.zap_amount_input = "21".to_string(); // Default amount
-                                            }
-                                        }
+                                let display_name = author_metadata.display_name_or_npub(&post.author_pubkey);
+                                let name_label = egui::Label::new(egui::RichText::new(display_name).strong().color(app_data.current_theme.text_color()))
+                                    .sense(egui::Sense::click());
+                                if ui.add(name_label).on_hover_cursor(egui::CursorIcon::PointingHand).clicked() {
+                                    link_to_open = Some(NostrLink::Profile(post.author_pubkey));
+                                }
+                                nip05_badge::draw_nip05_badge(ui, &app_data.nip05_cache, &post.author_pubkey, &author_metadata.nip05, &mut nip05_to_verify);
+                                ui.label(StatusGroup::from_d_tag(post.d_tag()).icon()).on_hover_text(post.d_tag());
+                                if post.is_private {
+                                    ui.label("🔒").on_hover_text(private_status_hover_text);
+                                }
 
-                                        ui.menu_button("...", |ui| {
-                                            let is_followed = app_data.followed_pubkeys.contains(&post.author_pubkey);
-                                            let button_text = if is_followed { "アンフォロー" } else { "フォロー" };
-                                            if ui.button(button_text).clicked() {
-                                                pubkey_to_modify = Some((post.author_pubkey, !is_followed));
-                                                ui.close();
+                                timestamp::draw_timestamp(ui, post.created_at);
+                                // 非公開のステータスは署名のないイベントとして届くので、確認の印は出さない
+                                if !post.is_private {
+                                    signature_badge::draw_signature_badge(ui, &post.id);
+                                }
+                                if my_pubkey == Some(post.author_pubkey) {
+                                    timestamp_proof_badge::draw_timestamp_proof_badge(ui, app_data.timestamp_proofs.iter().find(|proof| proof.event_id == post.id));
+                                }
+                                if let Some(total_msats) = app_data.zap_totals.get(&post.id) {
+                                    ui.label(egui::RichText::new(format!("⚡{}", total_msats / 1000)).color(egui::Color32::from_rgb(247, 147, 26)).small());
+                                }
+
+                                // 非公開のステータスはリレーにないので、リポストや引用、ZAP はできない
+                                if !post.is_private && ui.small_button("🔁").on_hover_text(tr!("リポスト")).clicked() {
+                                    event_to_repost = Some(post.id);
+                                }
+                                if !post.is_private && ui.small_button(tr!("引用")).clicked() {
+                                    app_data.quote_target = Some(QuoteTarget {
+                                        event_id: post.id,
+                                        author: post.author_pubkey,
+                                        kind: post.kind,
+                                        preview: post.content.chars().take(50).collect(),
+                                    });
+                                }
+                                if !post.is_private && bookmarks_view::draw_bookmark_button(ui, app_data, post.id) {
+                                    event_to_bookmark = Some(post.id);
+                                }
+
+                                if let Some(my_signer) = &app_data.my_signer
+                                    && post.author_pubkey != my_signer.public_key() {
+                                    // ZAP button
+                                    if !post.is_private && (!author_metadata.lud16.is_empty() || !author_metadata.lud06.is_empty()) {
+                                        let zap_target = ZapTarget {
+                                            pubkey: post.author_pubkey,
+                                            metadata: author_metadata.clone(),
+                                            event: Some((post.id, post.kind)),
+                                        };
+                                        if app_data.pending_zaps.contains(&post.id) {
+                                            ui.spinner();
+                                        } else {
+                                            // NWC接続済みなら押すだけでZAP、右クリックで金額を指定できる
+                                            let zap_button = ui.button("⚡").on_hover_text(tr!(
+                                                "{} sats をZAP (右クリックで金額を指定)",
+                                                app_data.default_zap_amount_input
+                                            ));
+                                            if zap_button.clicked() {
+                                                zap_to_send = Some(zap_target.clone());
                                             }
-                                        });
+                                            zap_button.context_menu(|ui| {
+                                                if ui.button(tr!("金額を指定してZAP")).clicked() {
+                                                    zap_to_open = Some(zap_target.clone());
+                                                    ui.close();
+                                                }
+                                            });
+                                        }
                                     }
+
+                                    ui.menu_button("...", |ui| {
+                                        let is_followed = app_data.followed_pubkeys.contains(&post.author_pubkey);
+                                        let button_text = if is_followed { tr!("アンフォロー") } else { tr!("フォロー") };
+                                        if ui.button(button_text).clicked() {
+                                            pubkey_to_modify = Some((post.author_pubkey, !is_followed));
+                                            ui.close();
+                                        }
+                                        if ui.button(report_button_text).clicked() {
+                                            report_dialog::open_report_dialog(
+                                                app_data,
+                                                ReportTarget { public_key: post.author_pubkey, event_id: Some(post.id) },
+                                            );
+                                            ui.close();
+                                        }
+                                        if !post.is_private {
+                                            ui.menu_button(label_menu_text, |ui| {
+                                                for label in labels::LABEL_PRESETS {
+                                                    if ui.button(label).clicked() {
+                                                        label_to_publish = Some((
+                                                            ReportTarget { public_key: post.author_pubkey, event_id: Some(post.id) },
+                                                            label.to_string(),
+                                                        ));
+                                                        ui.close();
+                                                    }
+                                                }
+                                            });
+                                        }
+                                    });
                                 }
                             });
                             ui.add_space(5.0);
-                            render_post_content(ui, app_data, &post, &mut urls_to_load, &app_data.my_emojis);
+                            // 注意書きがなくても、畳む設定のラベルが付いていれば同じように本文を隠す
+                            let content_warning = note::content_warning(&post.tags).or_else(|| match post_label_action(app_data, &post) {
+                                Some(LabelAction::Collapse(label)) => Some(tr!("ラベル「{}」", label)),
+                                _ => None,
+                            });
+                            if content_warning::draw_content_warning(ui, &app_data.revealed_content_warnings, post.id, content_warning.as_deref(), &mut content_warning_toggle) {
+                                render_post_content(ui, app_data, &post, &mut urls_to_load, &app_data.my_emojis, &mut link_to_open, &mut links_to_preview);
+                            }
                         });
                     }
                 });
         }
 
-        // --- Image Loading Logic ---
-
-        // First, try to load images from the LMDB cache for URLs not in memory.
-        let cache_db = app_data.cache_db.clone();
-        let mut still_to_load = Vec::new();
-        for (url_key, kind) in urls_to_load {
-            if let Some(image_bytes) = image_cache::load_from_lmdb(&cache_db, &url_key) {
-                // Image found in cache, process it directly.
-                // This is a simplification; for a smoother UI, this should be async.
-                if let Ok(mut dynamic_image) = image::load_from_memory(&image_bytes) {
-                    let (width, height) = match kind {
-                        ImageKind::Avatar => (32, 32),
-                        ImageKind::Emoji => (20, 20),
-                    _ => (32, 32), // Default for Banner, ProfilePicture, etc.
-                    };
-                    dynamic_image = dynamic_image.thumbnail(width, height);
-                    let color_image = egui::ColorImage::from_rgba_unmultiplied(
-                        [dynamic_image.width() as usize, dynamic_image.height() as usize],
-                        dynamic_image.to_rgba8().as_flat_samples().as_slice(),
-                    );
-                    let texture_handle = ctx.load_texture(
-                        &url_key,
-                        color_image,
-                        Default::default()
-                    );
-                    app_data.image_cache.insert(url_key, ImageState::Loaded(texture_handle));
-                } else {
-                    // Failed to decode, mark as failed.
-                    app_data.image_cache.insert(url_key, ImageState::Failed);
-                }
-            } else {
-                // Not on disk, queue for network download.
-                still_to_load.push((url_key, kind));
-            }
+        nip05_badge::verify_nip05s(app_data, app_data_arc.clone(), &runtime_handle, nip05_to_verify);
+        content_warning::toggle_revealed(&mut app_data.revealed_content_warnings, content_warning_toggle);
+        if let (Some((target, label)), Some(client), Some(signer)) = (label_to_publish, app_data.nostr_client.clone(), app_data.my_signer.clone()) {
+            runtime_handle.spawn(labels::publish_label(client, signer, target, label, app_data_arc.clone()));
         }
-
-        // Fetch remaining images from the network.
-        let data_clone = app_data_arc.clone();
-        for (url_key, kind) in still_to_load {
-            app_data.image_cache.insert(url_key.clone(), ImageState::Loading);
-            app_data.should_repaint = true;
-
-            let app_data_clone = data_clone.clone();
-            let ctx_clone = ctx.clone();
-            let cache_db_for_fetch = app_data.cache_db.clone();
-            let request = ehttp::Request::get(&url_key);
-
-            ehttp::fetch(request, move |result| {
-                let new_state = match result {
-                    Ok(response) => {
-                        if response.ok {
-                            // Save to LMDB cache first.
-                            image_cache::save_to_lmdb(&cache_db_for_fetch, &response.url, &response.bytes);
-
-                            match image::load_from_memory(&response.bytes) {
-                                Ok(mut dynamic_image) => {
-                                    let (width, height) = match kind {
-                                        ImageKind::Avatar => (32, 32),
-                                        ImageKind::Emoji => (20, 20),
-                                    _ => (32, 32), // Default for Banner, ProfilePicture, etc.
-                                    };
-                                    dynamic_image = dynamic_image.thumbnail(width, height);
-
-                                    let color_image = egui::ColorImage::from_rgba_unmultiplied(
-                                        [dynamic_image.width() as usize, dynamic_image.height() as usize],
-                                        dynamic_image.to_rgba8().as_flat_samples().as_slice(),
-                                    );
-                                    let texture_handle = ctx_clone.load_texture(
-                                        &response.url,
-                                        color_image,
-                                        Default::default()
-                                    );
-                                    ImageState::Loaded(texture_handle)
-                                }
-                                Err(_) => ImageState::Failed,
-                            }
-                        } else {
-                            ImageState::Failed
-                        }
-                    }
-                    Err(_) => ImageState::Failed,
-                };
-
-                let mut app_data = app_data_clone.lock().unwrap();
-                app_data.image_cache.insert(url_key, new_state);
-                ctx_clone.request_repaint();
-            });
+        if let Some(link) = link_to_open {
+            link_view::open_link(app_data, app_data_arc.clone(), &runtime_handle, link);
+        }
+        if let Some(event_id) = event_to_repost {
+            notes_view::spawn_repost(app_data, app_data_arc.clone(), &runtime_handle, event_id);
+        }
+        if let Some(event_id) = event_to_bookmark {
+            bookmarks_view::spawn_toggle_bookmark(app_data, app_data_arc.clone(), &runtime_handle, event_id);
+        }
+        if let Some(target) = zap_to_send {
+            zap::spawn_one_click_zap(app_data, app_data_arc.clone(), &runtime_handle, target);
+        }
+        if let Some(target) = zap_to_open {
+            zap::open_zap_dialog(app_data, target);
+        }
+        if let Some(message) = &app_data.zap_status_message {
+            ui.label(egui::RichText::new(message).small());
         }
 
-        if let Some((pubkey, follow)) = pubkey_to_modify {
-            if !app_data.is_loading {
-                let client = app_data.nostr_client.as_ref().unwrap().clone();
-                let keys = app_data.my_keys.as_ref().unwrap().clone();
-                let cache_db_clone = app_data.cache_db.clone();
-
-                app_data.is_loading = true;
-                app_data.should_repaint = true;
+        // --- Image Loading Logic ---
+        link_preview::fetch_link_previews(app_data, app_data_arc.clone(), &runtime_handle, links_to_preview);
+        image_cache::load_images(ctx, app_data, app_data_arc.clone(), &runtime_handle, urls_to_load);
 
-                let cloned_app_data_arc = app_data_arc.clone();
-                runtime_handle.spawn(async move {
-                    match update_contact_list(&client, &keys, pubkey, follow).await {
-                        Ok(new_followed_pubkeys) => {
-                            let mut app_data = cloned_app_data_arc.lock().unwrap();
-                            app_data.followed_pubkeys = new_followed_pubkeys;
-                            if let Some(keys) = &app_data.my_keys {
-                                let pubkey_hex = keys.public_key().to_string();
-                                if let Err(e) = cache_db_clone.write_cache(DB_FOLLOWED, &pubkey_hex, &app_data.followed_pubkeys) {
-                                    eprintln!("Failed to write follow list cache: {e}");
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            eprintln!("Failed to update contact list: {e}");
-                        }
-                    }
-                    let mut app_data = cloned_app_data_arc.lock().unwrap();
-                    app_data.is_loading = false;
-                    app_data.should_repaint = true;
-                });
-            }
+        if let Some((pubkey, follow)) = pubkey_to_modify {
+            friends_view::spawn_contact_list_update(app_data, app_data_arc.clone(), &runtime_handle, pubkey, None, follow);
         }
     });
 
@@ -0,0 +1,60 @@
+use eframe::egui;
+use nostr::PublicKey;
+
+// identicon のマス目の数 (縦横)
+const GRID_SIZE: usize = 5;
+// 丸いアバターでもマス目がはみ出さないよう、描く範囲を小さくする
+const GRID_SCALE: f32 = 0.6;
+
+// 公開鍵から作った模様。同じ公開鍵ならどこでも同じ模様と色になる
+pub struct Identicon {
+    pub background: egui::Color32,
+    pub foreground: egui::Color32,
+    // 塗るマスの (列, 行)
+    pub cells: Vec<(usize, usize)>,
+}
+
+impl Identicon {
+    pub fn new(pubkey: &PublicKey) -> Self {
+        let bytes = pubkey.to_bytes();
+        let hue = u16::from_be_bytes([bytes[0], bytes[1]]) as f32 / u16::MAX as f32;
+        // 左半分と真ん中の列 (5x3) を公開鍵のビットで決め、右半分は鏡写しにする
+        let half = GRID_SIZE.div_ceil(2);
+        let mut cells = Vec::new();
+        for row in 0..GRID_SIZE {
+            for column in 0..half {
+                let bit = row * half + column;
+                if bytes[2 + bit / 8] & (1 << (bit % 8)) == 0 {
+                    continue;
+                }
+                cells.push((column, row));
+                if column != GRID_SIZE - 1 - column {
+                    cells.push((GRID_SIZE - 1 - column, row));
+                }
+            }
+        }
+        Self {
+            background: egui::ecolor::Hsva::new(hue, 0.2, 0.95, 1.0).into(),
+            foreground: egui::ecolor::Hsva::new(hue, 0.6, 0.75, 1.0).into(),
+            cells,
+        }
+    }
+
+    // 正方形 (一辺 side) の中で、1マスの大きさと左上の位置を返す
+    pub fn layout(side: f32) -> (f32, f32) {
+        let grid_side = side * GRID_SCALE;
+        (grid_side / GRID_SIZE as f32, (side - grid_side) / 2.0)
+    }
+}
+
+pub fn paint_identicon(painter: &egui::Painter, rect: egui::Rect, corner_radius: f32, pubkey: &PublicKey) {
+    let identicon = Identicon::new(pubkey);
+    painter.rect_filled(rect, corner_radius, identicon.background);
+    let side = rect.width().min(rect.height());
+    let (cell, offset) = Identicon::layout(side);
+    let origin = rect.center() - egui::vec2(side, side) / 2.0 + egui::vec2(offset, offset);
+    for (column, row) in identicon.cells {
+        let min = origin + egui::vec2(column as f32 * cell, row as f32 * cell);
+        painter.rect_filled(egui::Rect::from_min_size(min, egui::vec2(cell, cell)), 0.0, identicon.foreground);
+    }
+}
@@ -0,0 +1,141 @@
+use eframe::egui;
+use nostr::PublicKey;
+use nostr::nips::nip19::ToBech32;
+use nostr::nips::nip39::{ExternalIdentity, Identity};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tracing::error;
+
+use crate::cache_db::{DB_IDENTITIES, LmdbCache};
+use crate::i18n::tr;
+use crate::nip39;
+use crate::types::{IdentityState, NostrStatusAppInternal};
+
+// プロフィールで選べるサービス
+const EDITABLE_PLATFORMS: [ExternalIdentity; 3] = [ExternalIdentity::GitHub, ExternalIdentity::Twitter, ExternalIdentity::Mastodon];
+
+// 外部アカウントの主張 (NIP-39) を証明へのリンクと確認結果付きで並べる。まだ確認していなければ to_verify に積む
+pub fn draw_identities(
+    ui: &mut egui::Ui,
+    identity_cache: &HashMap<String, IdentityState>,
+    public_key: &PublicKey,
+    identities: &[Identity],
+    to_verify: &mut Vec<(PublicKey, Identity)>,
+) {
+    let verified_hover_text = tr!("証明の投稿を確認済み");
+    let mismatch_hover_text = tr!("証明の投稿にこのユーザーの公開鍵が見つかりません");
+    let unverifiable_hover_text = tr!("このサービスの証明は確認できません。リンクから投稿を確かめてください");
+    let failed_hover_text = tr!("証明の投稿を取得できませんでした");
+    let checking_hover_text = tr!("証明の投稿を確認中");
+
+    if identities.is_empty() {
+        return;
+    }
+    ui.horizontal_wrapped(|ui| {
+        for identity in identities {
+            let (mark, color, hover_text) = match identity_cache.get(&nip39::cache_key(public_key, identity)) {
+                Some(IdentityState::Verified) => ("✓", egui::Color32::from_rgb(0x06, 0xC7, 0x55), verified_hover_text),
+                Some(IdentityState::Mismatch) => ("⚠", ui.visuals().warn_fg_color, mismatch_hover_text),
+                Some(IdentityState::Unverifiable) => ("?", egui::Color32::GRAY, unverifiable_hover_text),
+                Some(IdentityState::Failed) => ("?", egui::Color32::GRAY, failed_hover_text),
+                Some(IdentityState::Checking) => ("…", egui::Color32::GRAY, checking_hover_text),
+                None => {
+                    to_verify.push((*public_key, identity.clone()));
+                    ("…", egui::Color32::GRAY, checking_hover_text)
+                }
+            };
+            ui.label(egui::RichText::new(mark).small().color(color)).on_hover_text(hover_text);
+            let label = format!("{} {}", nip39::platform_name(&identity.platform), identity.ident);
+            let url = nip39::proof_url(identity);
+            ui.hyperlink_to(egui::RichText::new(label).small(), &url).on_hover_text(&url);
+            ui.add_space(6.0);
+        }
+    });
+}
+
+fn load_from_lmdb(cache_db: &LmdbCache, key: &str) -> Option<IdentityState> {
+    cache_db.read_cache::<IdentityState>(DB_IDENTITIES, key).ok().map(|cache| cache.data)
+}
+
+// まだ確認していない主張を確認する。結果は NIP-05 と同じくキャッシュする
+pub fn verify_identities(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    to_verify: Vec<(PublicKey, Identity)>,
+) {
+    for (public_key, identity) in to_verify {
+        let key = nip39::cache_key(&public_key, &identity);
+        if app_data.identity_cache.contains_key(&key) {
+            continue;
+        }
+        app_data.identity_cache.insert(key.clone(), IdentityState::Checking);
+
+        let app_data_clone = app_data_arc.clone();
+        let cache_db = app_data.cache_db.clone();
+        runtime_handle.spawn_blocking(move || {
+            let state = match load_from_lmdb(&cache_db, &key) {
+                Some(state) => state,
+                None => {
+                    let state = nip39::fetch_identity_state(&public_key, &identity);
+                    // 通信の失敗は一時的なことが多いので、次に起動したときに確認し直す
+                    if state != IdentityState::Failed
+                        && let Err(e) = cache_db.write_cache(DB_IDENTITIES, &key, &state) {
+                        error!("Failed to cache the identity result: {e}");
+                    }
+                    state
+                }
+            };
+            let mut app_data = app_data_clone.lock().unwrap();
+            app_data.identity_cache.insert(key, state);
+            app_data.should_repaint = true;
+        });
+    }
+}
+
+// プロフィールの編集欄。サービス・アカウント・証明の投稿の ID を1行ずつ入力する
+pub fn draw_identity_editor(ui: &mut egui::Ui, public_key: Option<PublicKey>, identities: &mut Vec<Identity>) {
+    let ident_hint_text = tr!("ユーザー名 (Mastodon は example.social/@user)");
+    let proof_hint_text = tr!("Gist・ツイート・投稿の ID");
+    let add_button_text = tr!("＋ 追加");
+    let remove_button_text = tr!("削除");
+    let copy_proof_text_button_text = tr!("証明の文をコピー");
+    let proof_hint_label_text = tr!("証明の文を GitHub の Gist や Mastodon などに公開し、その ID を入力してください");
+
+    let mut to_remove: Option<usize> = None;
+    for (i, identity) in identities.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_salt(("identity_platform_combo", i))
+                .selected_text(nip39::platform_name(&identity.platform))
+                .width(90.0)
+                .show_ui(ui, |ui| {
+                    for platform in EDITABLE_PLATFORMS {
+                        let name = nip39::platform_name(&platform);
+                        ui.selectable_value(&mut identity.platform, platform, name);
+                    }
+                });
+            ui.add(egui::TextEdit::singleline(&mut identity.ident).hint_text(ident_hint_text).desired_width(180.0));
+            ui.add(egui::TextEdit::singleline(&mut identity.proof).hint_text(proof_hint_text).desired_width(160.0));
+            if ui.small_button(remove_button_text).clicked() {
+                to_remove = Some(i);
+            }
+        });
+    }
+    if let Some(i) = to_remove {
+        identities.remove(i);
+    }
+    ui.horizontal(|ui| {
+        if ui.small_button(add_button_text).clicked() {
+            identities.push(Identity {
+                platform: ExternalIdentity::GitHub,
+                ident: String::new(),
+                proof: String::new(),
+            });
+        }
+        if let Some(public_key) = public_key
+            && ui.small_button(copy_proof_text_button_text).on_hover_text(proof_hint_label_text).clicked() {
+            let Ok(npub) = public_key.to_bech32();
+            ui.ctx().copy_text(format!("{} {npub}", nip39::PROOF_TEXT_PREFIX));
+        }
+    });
+}
@@ -1,5 +1,27 @@
+use tracing::error;
 use crate::cache_db::LmdbCache;
+use crate::i18n::tr;
+use crate::network;
+use crate::types::{ImageKind, ImageState, NostrStatusAppInternal};
+use crate::ui::identicon;
+use eframe::egui;
+use nostr::PublicKey;
 use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::Read;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// 投稿に添付された画像のサムネイルの大きさ (長い辺)
+const PREVIEW_MAX_SIZE: u32 = 480;
+// これより大きい画像は読み込まない
+const MAX_IMAGE_BYTES: usize = 10 * 1024 * 1024;
+// 画像の取得を諦めるまでの秒数
+const IMAGE_FETCH_TIMEOUT_SECS: u64 = 20;
+// 本文の下に並べるときの大きさの上限
+const PREVIEW_DISPLAY_SIZE: egui::Vec2 = egui::vec2(240.0, 240.0);
+// 画像を読み込むまでの枠の大きさ
+const PREVIEW_PLACEHOLDER_SIZE: egui::Vec2 = egui::vec2(160.0, 120.0);
 
 // Hashes the URL to create a stable key for the DB.
 fn url_to_key(url: &str) -> String {
@@ -16,7 +38,7 @@ pub fn load_from_lmdb(cache_db: &LmdbCache, url: &str) -> Option<Vec<u8>> {
         Ok(None) => None,
         Err(_e) => {
             // It's a cache, so we don't need to be too loud about errors.
-            // eprintln!("Failed to read image from LMDB cache: {}", e);
+            // error!("Failed to read image from LMDB cache: {}", e);
             None
         }
     }
@@ -25,6 +47,191 @@ pub fn load_from_lmdb(cache_db: &LmdbCache, url: &str) -> Option<Vec<u8>> {
 pub fn save_to_lmdb(cache_db: &LmdbCache, url: &str, data: &[u8]) {
     let key = url_to_key(url);
     if let Err(e) = cache_db.write_image_cache(&key, data) {
-        eprintln!("Failed to write image to LMDB cache: {}", e);
+        error!("Failed to write image to LMDB cache: {}", e);
+    }
+}
+
+fn thumbnail_size(kind: ImageKind) -> (u32, u32) {
+    match kind {
+        ImageKind::Avatar => (32, 32),
+        ImageKind::Emoji => (20, 20),
+        ImageKind::ProfilePicture => (100, 100),
+        ImageKind::Preview => (PREVIEW_MAX_SIZE, PREVIEW_MAX_SIZE),
+        ImageKind::Badge => (64, 64),
+    }
+}
+
+// 画像をデコードしてサムネイル化し、テクスチャとして登録する
+fn decode_to_texture(ctx: &egui::Context, url: &str, bytes: &[u8], kind: ImageKind) -> ImageState {
+    match image::load_from_memory(bytes) {
+        Ok(dynamic_image) => {
+            let (width, height) = thumbnail_size(kind);
+            let dynamic_image = dynamic_image.thumbnail(width, height);
+            let color_image = egui::ColorImage::from_rgba_unmultiplied(
+                [dynamic_image.width() as usize, dynamic_image.height() as usize],
+                dynamic_image.to_rgba8().as_flat_samples().as_slice(),
+            );
+            let texture_handle = ctx.load_texture(url, color_image, Default::default());
+            ImageState::Loaded(texture_handle)
+        }
+        Err(_) => ImageState::Failed,
+    }
+}
+
+// プロキシの設定に従って画像を取得する。大きすぎるものは読み込まない
+fn fetch_image(url: &str) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+    let response = network::http_agent(Duration::from_secs(IMAGE_FETCH_TIMEOUT_SECS)).get(url).call()?;
+    let mut bytes = Vec::new();
+    response.into_reader().take(MAX_IMAGE_BYTES as u64 + 1).read_to_end(&mut bytes)?;
+    if bytes.len() > MAX_IMAGE_BYTES {
+        return Err("the image is too large".into());
+    }
+    Ok(bytes)
+}
+
+// 描画中に集めた画像URLを、LMDBキャッシュ→ネットワークの順にバックグラウンドで読み込む
+pub fn load_images(
+    ctx: &egui::Context,
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    urls_to_load: Vec<(String, ImageKind)>,
+) {
+    for (url_key, kind) in urls_to_load {
+        if app_data.image_cache.contains_key(&url_key) {
+            continue;
+        }
+        app_data.image_cache.insert(url_key.clone(), ImageState::Loading);
+        app_data.should_repaint = true;
+
+        let app_data_clone = app_data_arc.clone();
+        let ctx_clone = ctx.clone();
+        let cache_db = app_data.image_cache_db.clone();
+
+        runtime_handle.spawn_blocking(move || {
+            // First, try the on-disk cache.
+            if let Some(image_bytes) = load_from_lmdb(&cache_db, &url_key) {
+                let new_state = decode_to_texture(&ctx_clone, &url_key, &image_bytes, kind);
+                let mut app_data = app_data_clone.lock().unwrap();
+                app_data.image_cache.insert(url_key, new_state);
+                ctx_clone.request_repaint();
+                return;
+            }
+
+            // Not on disk, fetch from the network.
+            let new_state = match fetch_image(&url_key) {
+                Ok(bytes) => {
+                    save_to_lmdb(&cache_db, &url_key, &bytes);
+                    decode_to_texture(&ctx_clone, &url_key, &bytes, kind)
+                }
+                Err(e) => {
+                    error!("Failed to fetch the image {url_key}: {e}");
+                    ImageState::Failed
+                }
+            };
+
+            let mut app_data = app_data_clone.lock().unwrap();
+            app_data.image_cache.insert(url_key, new_state);
+            ctx_clone.request_repaint();
+        });
     }
 }
+
+// アバターを描画する。未読み込みの場合はurls_to_loadに追加してプレースホルダーを表示する
+pub fn draw_avatar(
+    ui: &mut egui::Ui,
+    image_cache: &HashMap<String, ImageState>,
+    url: &str,
+    kind: ImageKind,
+    size: egui::Vec2,
+    corner_radius: f32,
+    urls_to_load: &mut Vec<(String, ImageKind)>,
+) -> egui::Response {
+    let (rect, response) = ui.allocate_exact_size(size, egui::Sense::click());
+    let placeholder_color = ui.style().visuals.widgets.inactive.bg_fill;
+
+    if url.is_empty() {
+        ui.painter().rect_filled(rect, corner_radius, placeholder_color);
+        return response;
+    }
+
+    match image_cache.get(url) {
+        Some(ImageState::Loaded(texture_handle)) => {
+            egui::Image::new(texture_handle)
+                .corner_radius(corner_radius)
+                .fit_to_exact_size(size)
+                .paint_at(ui, rect);
+        }
+        Some(ImageState::Loading) => {
+            ui.painter().rect_filled(rect, corner_radius, placeholder_color);
+            ui.put(rect.shrink(size.x * 0.25), egui::Spinner::new());
+        }
+        Some(ImageState::Failed) => {
+            ui.painter().rect_filled(
+                rect,
+                corner_radius,
+                ui.style().visuals.error_fg_color.linear_multiply(0.2),
+            );
+        }
+        None => {
+            if !urls_to_load.iter().any(|(u, _)| u == url) {
+                urls_to_load.push((url.to_string(), kind));
+            }
+            ui.painter().rect_filled(rect, corner_radius, placeholder_color);
+            ui.put(rect.shrink(size.x * 0.25), egui::Spinner::new());
+        }
+    }
+    response
+}
+
+// ユーザーのアバターを描画する。画像がない、または読み込めなかったときは公開鍵から作った identicon を出す
+pub fn draw_user_avatar(
+    ui: &mut egui::Ui,
+    image_cache: &HashMap<String, ImageState>,
+    pubkey: &PublicKey,
+    url: &str,
+    size: egui::Vec2,
+    corner_radius: f32,
+    urls_to_load: &mut Vec<(String, ImageKind)>,
+) -> egui::Response {
+    if url.is_empty() || matches!(image_cache.get(url), Some(ImageState::Failed)) {
+        let (rect, response) = ui.allocate_exact_size(size, egui::Sense::click());
+        identicon::paint_identicon(ui.painter(), rect, corner_radius, pubkey);
+        return response;
+    }
+    draw_avatar(ui, image_cache, url, ImageKind::Avatar, size, corner_radius, urls_to_load)
+}
+
+// 投稿に添付された画像を、縦横比を保ったまま小さくして並べる。押すとブラウザで開く
+pub fn draw_image_previews(
+    ui: &mut egui::Ui,
+    image_cache: &HashMap<String, ImageState>,
+    image_urls: &[String],
+    urls_to_load: &mut Vec<(String, ImageKind)>,
+) {
+    let open_hover_text = tr!("ブラウザで開く");
+
+    if image_urls.is_empty() {
+        return;
+    }
+    ui.horizontal_wrapped(|ui| {
+        for url in image_urls {
+            let response = match image_cache.get(url) {
+                Some(ImageState::Loaded(texture_handle)) => {
+                    let texture_size = texture_handle.size_vec2();
+                    let scale = (PREVIEW_DISPLAY_SIZE.x / texture_size.x).min(PREVIEW_DISPLAY_SIZE.y / texture_size.y).min(1.0);
+                    ui.add(
+                        egui::Image::new(texture_handle)
+                            .fit_to_exact_size(texture_size * scale)
+                            .corner_radius(6.0)
+                            .sense(egui::Sense::click()),
+                    )
+                }
+                _ => draw_avatar(ui, image_cache, url, ImageKind::Preview, PREVIEW_PLACEHOLDER_SIZE, 6.0, urls_to_load),
+            };
+            if response.on_hover_text(open_hover_text).clicked() {
+                ui.ctx().open_url(egui::OpenUrl::new_tab(url));
+            }
+        }
+    });
+}
@@ -0,0 +1,227 @@
+use eframe::egui;
+use nostr::Tag;
+use regex::Regex;
+use std::io::Read;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::error;
+
+use crate::cache_db::{DB_LINK_PREVIEWS, LmdbCache};
+use crate::network;
+use crate::note;
+use crate::types::{ImageKind, LinkPreview, LinkPreviewState, NostrStatusAppInternal};
+use crate::ui::image_cache;
+
+// ページを待つ時間
+const LINK_PREVIEW_TIMEOUT_SECS: u64 = 10;
+// 読む HTML の上限。OpenGraph のタグは <head> にあるので先頭だけで足りる
+const MAX_HTML_BYTES: u64 = 256 * 1024;
+// 1つの投稿に出すリンクのプレビューの数
+const MAX_LINK_PREVIEWS_PER_POST: usize = 2;
+const MAX_DESCRIPTION_CHARS: usize = 120;
+const LINK_PREVIEW_IMAGE_SIZE: egui::Vec2 = egui::vec2(64.0, 64.0);
+
+// 本文の下に、添付や本文中の画像と、リンク先のページのプレビューを並べる
+// まだ取得していないリンクは links_to_preview に積むので、呼び出し側で fetch_link_previews に渡す
+pub fn draw_previews<'a>(
+    ui: &mut egui::Ui,
+    app_data: &NostrStatusAppInternal,
+    content: &str,
+    tags: impl IntoIterator<Item = &'a Tag>,
+    urls_to_load: &mut Vec<(String, ImageKind)>,
+    links_to_preview: &mut Vec<String>,
+) {
+    if !app_data.settings.show_media_previews {
+        return;
+    }
+    let web_urls = note::find_web_urls(content);
+    let mut image_urls = note::imeta_image_urls(tags);
+    for (_, url) in &web_urls {
+        if note::is_image_url(url) && !image_urls.contains(url) {
+            image_urls.push(url.clone());
+        }
+    }
+    image_cache::draw_image_previews(ui, &app_data.image_cache, &image_urls, urls_to_load);
+
+    let page_urls = web_urls.iter().map(|(_, url)| url).filter(|url| !image_urls.contains(url)).take(MAX_LINK_PREVIEWS_PER_POST);
+    for url in page_urls {
+        match app_data.link_previews.get(url) {
+            Some(LinkPreviewState::Loaded(preview)) => draw_link_card(ui, app_data, url, preview, urls_to_load),
+            Some(LinkPreviewState::Loading) | Some(LinkPreviewState::Failed) => {}
+            None => {
+                if !links_to_preview.contains(url) {
+                    links_to_preview.push(url.clone());
+                }
+            }
+        }
+    }
+}
+
+fn draw_link_card(
+    ui: &mut egui::Ui,
+    app_data: &NostrStatusAppInternal,
+    url: &str,
+    preview: &LinkPreview,
+    urls_to_load: &mut Vec<(String, ImageKind)>,
+) {
+    let host = url.split_once("://").map_or(url, |(_, rest)| rest).split('/').next().unwrap_or_default();
+    let description: String = preview.description.chars().take(MAX_DESCRIPTION_CHARS).collect();
+    let response = egui::Frame::new()
+        .stroke(ui.visuals().widgets.noninteractive.bg_stroke)
+        .corner_radius(6.0)
+        .inner_margin(egui::Margin::same(6))
+        .show(ui, |ui| {
+            ui.set_max_width(360.0);
+            ui.horizontal(|ui| {
+                if let Some(image) = &preview.image {
+                    image_cache::draw_avatar(ui, &app_data.image_cache, image, ImageKind::Preview, LINK_PREVIEW_IMAGE_SIZE, 4.0, urls_to_load);
+                }
+                ui.vertical(|ui| {
+                    ui.label(egui::RichText::new(&preview.title).strong());
+                    if !description.is_empty() {
+                        ui.label(egui::RichText::new(description).small());
+                    }
+                    ui.label(egui::RichText::new(host).small().color(egui::Color32::GRAY));
+                });
+            });
+        })
+        .response
+        .interact(egui::Sense::click());
+    if response.on_hover_cursor(egui::CursorIcon::PointingHand).clicked() {
+        ui.ctx().open_url(egui::OpenUrl::new_tab(url));
+    }
+}
+
+// <meta property="og:title" content="..."> の content を読む。属性の順番が逆のものにも対応する
+fn meta_content(html: &str, property: &str) -> Option<String> {
+    let property = regex::escape(property);
+    let patterns = [
+        format!(r#"(?is)<meta[^>]+(?:property|name)\s*=\s*["']{property}["'][^>]*content\s*=\s*["']([^"']*)["']"#),
+        format!(r#"(?is)<meta[^>]+content\s*=\s*["']([^"']*)["'][^>]*(?:property|name)\s*=\s*["']{property}["']"#),
+    ];
+    patterns.iter().find_map(|pattern| {
+        let re = Regex::new(pattern).ok()?;
+        re.captures(html).map(|caps| decode_entities(caps[1].trim()))
+    })
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#x27;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+fn parse_link_preview(html: &str, page_url: &str) -> Option<LinkPreview> {
+    let title = meta_content(html, "og:title").or_else(|| {
+        let re = Regex::new(r"(?is)<title[^>]*>(.*?)</title>").unwrap();
+        re.captures(html).map(|caps| decode_entities(caps[1].trim()))
+    })?;
+    if title.is_empty() {
+        return None;
+    }
+    let description = meta_content(html, "og:description").or_else(|| meta_content(html, "description")).unwrap_or_default();
+    // 相対 URL の画像はページの URL を基準にする
+    let image = meta_content(html, "og:image").and_then(|image| {
+        let base = nostr::Url::parse(page_url).ok()?;
+        base.join(&image).ok().map(|url| url.to_string())
+    });
+    Some(LinkPreview { title, description, image })
+}
+
+fn fetch_link_preview(url: &str) -> Option<LinkPreview> {
+    let agent = network::http_agent(Duration::from_secs(LINK_PREVIEW_TIMEOUT_SECS));
+    let response = match agent.get(url).call() {
+        Ok(response) => response,
+        Err(e) => {
+            error!("Failed to fetch the link preview for {url}: {e}");
+            return None;
+        }
+    };
+    if !response.content_type().contains("html") {
+        return None;
+    }
+    let mut bytes = Vec::new();
+    if let Err(e) = response.into_reader().take(MAX_HTML_BYTES).read_to_end(&mut bytes) {
+        error!("Failed to read the page for {url}: {e}");
+        return None;
+    }
+    parse_link_preview(&String::from_utf8_lossy(&bytes), url)
+}
+
+fn load_from_lmdb(cache_db: &LmdbCache, url: &str) -> Option<LinkPreview> {
+    cache_db.read_cache::<LinkPreview>(DB_LINK_PREVIEWS, url).ok().map(|cache| cache.data)
+}
+
+// まだ取得していないリンクのプレビューを取得する。結果は 24 時間キャッシュする
+pub fn fetch_link_previews(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    links_to_preview: Vec<String>,
+) {
+    for url in links_to_preview {
+        if app_data.link_previews.contains_key(&url) {
+            continue;
+        }
+        app_data.link_previews.insert(url.clone(), LinkPreviewState::Loading);
+
+        let app_data_clone = app_data_arc.clone();
+        let cache_db = app_data.cache_db.clone();
+        runtime_handle.spawn_blocking(move || {
+            let preview = load_from_lmdb(&cache_db, &url).or_else(|| {
+                let preview = fetch_link_preview(&url)?;
+                if let Err(e) = cache_db.write_cache(DB_LINK_PREVIEWS, &url, &preview) {
+                    error!("Failed to cache the link preview: {e}");
+                }
+                Some(preview)
+            });
+            let mut app_data = app_data_clone.lock().unwrap();
+            let state = preview.map_or(LinkPreviewState::Failed, LinkPreviewState::Loaded);
+            app_data.link_previews.insert(url, state);
+            app_data.should_repaint = true;
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn meta_content_reads_either_attribute_order() {
+        let html = r#"<head>
+            <meta property="og:title" content="Title">
+            <META CONTENT='Reversed' NAME='description'/>
+        </head>"#;
+        assert_eq!(meta_content(html, "og:title").as_deref(), Some("Title"));
+        assert_eq!(meta_content(html, "description").as_deref(), Some("Reversed"));
+        assert_eq!(meta_content(html, "og:image"), None);
+    }
+
+    #[test]
+    fn meta_content_decodes_entities_and_trims() {
+        let html = r#"<meta name="og:title" content="  Tom &amp; Jerry &lt;3 &#39;s &quot;show&quot; ">"#;
+        assert_eq!(meta_content(html, "og:title").as_deref(), Some(r#"Tom & Jerry <3 's "show""#));
+    }
+
+    #[test]
+    fn meta_content_matches_the_whole_property() {
+        // og:title の検索で og:title:alt や ogXtitle に当たらない
+        let html = r#"<meta property="og:title:alt" content="Alt"><meta property="ogXtitle" content="X">"#;
+        assert_eq!(meta_content(html, "og:title"), None);
+    }
+
+    #[test]
+    fn parse_link_preview_falls_back_to_the_title_tag() {
+        let html = r#"<title> Page </title><meta property="og:image" content="/image.png">"#;
+        let preview = parse_link_preview(html, "https://example.com/posts/1").unwrap();
+        assert_eq!(preview.title, "Page");
+        assert_eq!(preview.description, "");
+        // 相対 URL はページの URL から組み立てる
+        assert_eq!(preview.image.as_deref(), Some("https://example.com/image.png"));
+        assert!(parse_link_preview("<p>no title</p>", "https://example.com").is_none());
+    }
+}
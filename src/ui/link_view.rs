@@ -0,0 +1,269 @@
+use eframe::egui;
+use nostr::nips::nip19::ToBech32;
+use nostr::{EventId, Filter, Kind, PublicKey};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+use tracing::error;
+
+use crate::{
+    i18n::tr,
+    network,
+    nostr_client::get_profile_metadata,
+    note,
+    types::*,
+    ui::{articles_view, content_warning, hashtag_view, image_cache, link_preview, markdown, signature_badge, timestamp, user_profile_view},
+};
+
+// 本文中の URL を表示する長さ
+const MAX_WEB_LINK_LABEL_CHARS: usize = 40;
+
+// 参照をそのまま見せずに、名前や短い ID で表示する
+fn link_label(link: &NostrLink, profiles: &HashMap<PublicKey, ProfileMetadata>) -> String {
+    match link {
+        NostrLink::Profile(public_key) => {
+            let name = profiles
+                .get(public_key)
+                .map(|metadata| metadata.display_name_or_npub(public_key))
+                .unwrap_or_else(|| short_npub(public_key));
+            format!("@{name}")
+        }
+        NostrLink::Event(event_id) => {
+            let Ok(note_id) = event_id.to_bech32();
+            format!("📝 {}…", &note_id[..12])
+        }
+        NostrLink::Address(coordinate) => format!("📄 {}:{}", coordinate.kind, coordinate.identifier),
+        NostrLink::Hashtag(hashtag) => format!("#{hashtag}"),
+    }
+}
+
+// URL は長いので、ホストとパスの先頭だけを見せる
+fn web_link_label(url: &str) -> String {
+    let without_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+    if without_scheme.chars().count() <= MAX_WEB_LINK_LABEL_CHARS {
+        return without_scheme.to_string();
+    }
+    let shortened: String = without_scheme.chars().take(MAX_WEB_LINK_LABEL_CHARS).collect();
+    format!("{shortened}…")
+}
+
+// 本文中のリンク
+enum TextLink {
+    Nostr(NostrLink),
+    Web(String),
+}
+
+// 本文を描く。nostr: 参照とハッシュタグはリンクにして、押されたら link_to_open に入れる。horizontal_wrapped の中で呼ぶ
+// http(s) の URL はブラウザで開くリンクにする。リンク以外の部分は書式 (markdown) を付けて描く
+// [文字](URL) で書かれたリンクは、URL の代わりにその文字を見せる
+pub fn draw_text_with_links(
+    ui: &mut egui::Ui,
+    text: &str,
+    color: egui::Color32,
+    profiles: &HashMap<PublicKey, ProfileMetadata>,
+    link_to_open: &mut Option<NostrLink>,
+) {
+    let mut links: Vec<(Range<usize>, TextLink, Option<String>)> =
+        note::find_nostr_links(text).into_iter().map(|(range, link)| (range, TextLink::Nostr(link), None)).collect();
+    links.extend(note::find_hashtags(text).into_iter().map(|(range, hashtag)| (range, TextLink::Nostr(NostrLink::Hashtag(hashtag)), None)));
+    links.extend(note::find_web_urls(text).into_iter().map(|(range, url)| (range, TextLink::Web(url), None)));
+    links.extend(markdown::find_links(text).into_iter().filter_map(|link| {
+        let target = if link.url.starts_with("nostr:") {
+            TextLink::Nostr(note::find_nostr_links(&link.url).into_iter().next()?.1)
+        } else {
+            TextLink::Web(link.url)
+        };
+        let label = if link.is_image { format!("🖼 {}", link.label) } else { link.label };
+        Some((link.range, target, Some(label)))
+    }));
+    // [文字](URL) は中の URL より先に始まるので、並べると先に来る
+    links.sort_by_key(|(range, _, _)| range.start);
+    // URL の中のハッシュタグのように、重なっているものは先に見つかった方だけを使う
+    let mut last_end = 0;
+    links.retain(|(range, _, _)| {
+        let keep = range.start >= last_end;
+        if keep {
+            last_end = range.end;
+        }
+        keep
+    });
+    // URL の中の _ などは書式として扱わない
+    let link_ranges: Vec<Range<usize>> = links.iter().map(|(range, _, _)| range.clone()).collect();
+    let spans = markdown::parse(text, &link_ranges);
+    let mut last_end = 0;
+    for (range, link, label) in links {
+        markdown::draw_spans(ui, text, &spans, last_end..range.start, color);
+        match link {
+            TextLink::Nostr(link) => {
+                let label = label.unwrap_or_else(|| link_label(&link, profiles));
+                if ui.link(label).on_hover_text(&text[range.clone()]).clicked() {
+                    *link_to_open = Some(link);
+                }
+            }
+            TextLink::Web(url) => {
+                let label = label.unwrap_or_else(|| web_link_label(&url));
+                ui.hyperlink_to(label, &url).on_hover_text(&url);
+            }
+        }
+        last_end = range.end;
+    }
+    markdown::draw_spans(ui, text, &spans, last_end..text.len(), color);
+}
+
+// 参照先を開く。プロフィールはプロフィールページに、ハッシュタグはそのフィードに、投稿は取得してウィンドウに表示する
+pub fn open_link(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    link: NostrLink,
+) {
+    match link {
+        NostrLink::Profile(public_key) => {
+            app_data.link_view = None;
+            app_data.link_view_event = None;
+            user_profile_view::open_user_profile(app_data, app_data_arc, runtime_handle, public_key);
+            return;
+        }
+        NostrLink::Hashtag(hashtag) => {
+            app_data.link_view = None;
+            app_data.link_view_event = None;
+            hashtag_view::open_hashtag_feed(app_data, app_data_arc, runtime_handle, hashtag);
+            return;
+        }
+        NostrLink::Address(coordinate) if coordinate.kind == Kind::LongFormTextNote => {
+            app_data.link_view = None;
+            app_data.link_view_event = None;
+            articles_view::open_article(app_data, app_data_arc, runtime_handle, coordinate);
+            return;
+        }
+        NostrLink::Event(_) | NostrLink::Address(_) => {}
+    }
+    app_data.link_view = Some(link.clone());
+    app_data.link_view_event = None;
+    let Some(client) = app_data.nostr_client.clone() else {
+        return;
+    };
+
+    app_data.is_loading_link_view = true;
+    let known_profiles: Vec<PublicKey> = app_data.profiles.keys().copied().collect();
+    runtime_handle.spawn(async move {
+        let filter = match &link {
+            NostrLink::Event(event_id) => Filter::new().id(*event_id),
+            NostrLink::Address(coordinate) => Filter::from(coordinate).limit(1),
+            NostrLink::Profile(_) | NostrLink::Hashtag(_) => return,
+        };
+        let event = match network::fetch_events(&client, filter).await {
+            Ok(events) => {
+                let event = events.first_owned();
+                if event.is_none() {
+                    app_data_arc.lock().unwrap().report_error(tr!("参照先の投稿が見つかりませんでした"));
+                }
+                event
+            }
+            Err(e) => {
+                error!("Failed to fetch the linked event: {e}");
+                app_data_arc.lock().unwrap().report_error(tr!("参照先の投稿を取得できませんでした: {}", e));
+                None
+            }
+        };
+
+        // 投稿の作者の名前も表示できるようにする
+        let author = event.as_ref().map(|event| event.pubkey);
+        let profile = match author.filter(|author| !known_profiles.contains(author)) {
+            Some(author) => match get_profile_metadata(author, &client).await {
+                Ok(metadata) => Some((author, metadata)),
+                Err(e) => {
+                    error!("Failed to fetch the linked profile: {e}");
+                    None
+                }
+            },
+            None => None,
+        };
+
+        let mut app_data = app_data_arc.lock().unwrap();
+        if let Some((author, metadata)) = profile {
+            app_data.profiles.insert(author, metadata);
+        }
+        // 取得中に別の参照が開かれていたら結果は使わない
+        if app_data.link_view.as_ref() == Some(&link) {
+            app_data.link_view_event = event;
+            app_data.is_loading_link_view = false;
+        }
+        app_data.should_repaint = true;
+    });
+}
+
+// 開いた参照先の投稿を表示する
+pub fn draw_link_view(
+    ctx: &egui::Context,
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: tokio::runtime::Handle,
+) {
+    let event_window_title_text = tr!("投稿");
+    let loading_text = tr!("読み込み中...");
+
+    if app_data.link_view.is_none() {
+        return;
+    }
+    let mut urls_to_load: Vec<(String, ImageKind)> = Vec::new();
+    let mut link_to_open: Option<NostrLink> = None;
+    let mut content_warning_toggle: Option<EventId> = None;
+    let mut links_to_preview: Vec<String> = Vec::new();
+    let mut open = true;
+
+    egui::Window::new(event_window_title_text)
+        .open(&mut open)
+        .collapsible(false)
+        .resizable(true)
+        .default_width(360.0)
+        .show(ctx, |ui| {
+            if app_data.is_loading_link_view {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label(loading_text);
+                });
+                return;
+            }
+            let Some(event) = app_data.link_view_event.clone() else {
+                return;
+            };
+            let metadata = app_data.profiles.get(&event.pubkey).cloned().unwrap_or_default();
+            ui.horizontal(|ui| {
+                image_cache::draw_user_avatar(
+                    ui,
+                    &app_data.image_cache,
+                    &event.pubkey,
+                    &metadata.picture,
+                    egui::vec2(32.0, 32.0),
+                    4.0,
+                    &mut urls_to_load,
+                );
+                if ui.link(egui::RichText::new(metadata.display_name_or_npub(&event.pubkey)).strong()).clicked() {
+                    link_to_open = Some(NostrLink::Profile(event.pubkey));
+                }
+                timestamp::draw_timestamp(ui, event.created_at);
+                signature_badge::draw_signature_badge(ui, &event.id);
+                ui.label(egui::RichText::new(format!("kind {}", event.kind)).small().color(egui::Color32::GRAY));
+            });
+            ui.add_space(5.0);
+            let content_warning = note::content_warning(event.tags.iter());
+            if content_warning::draw_content_warning(ui, &app_data.revealed_content_warnings, event.id, content_warning.as_deref(), &mut content_warning_toggle) {
+                ui.horizontal_wrapped(|ui| {
+                    draw_text_with_links(ui, &event.content, ui.visuals().text_color(), &app_data.profiles, &mut link_to_open);
+                });
+                link_preview::draw_previews(ui, app_data, &event.content, event.tags.iter(), &mut urls_to_load, &mut links_to_preview);
+            }
+        });
+
+    if !open {
+        app_data.link_view = None;
+        app_data.link_view_event = None;
+    }
+    content_warning::toggle_revealed(&mut app_data.revealed_content_warnings, content_warning_toggle);
+    if let Some(link) = link_to_open {
+        open_link(app_data, app_data_arc.clone(), &runtime_handle, link);
+    }
+    link_preview::fetch_link_previews(app_data, app_data_arc.clone(), &runtime_handle, links_to_preview);
+    image_cache::load_images(ctx, app_data, app_data_arc, &runtime_handle, urls_to_load);
+}
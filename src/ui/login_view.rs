@@ -1,33 +1,52 @@
 use eframe::egui;
+use std::fmt;
 use std::fs;
-use std::path::Path;
 use std::sync::{Arc, Mutex};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::time::Duration;
-use nostr::{nips::nip47::NostrWalletConnectURI, Filter, Keys, Kind, PublicKey};
-use nostr_sdk::{Client, SubscribeAutoCloseOptions};
+use nostr::{nips::{nip46::NostrConnectURI, nip47::NostrWalletConnectURI, nip65}, util::BoxedFuture, Event, Filter, Keys, Kind, PublicKey, Url};
+use nostr_connect::client::{AuthUrlHandler, NostrConnect};
+use nostr_sdk::Client;
 use std::str::FromStr;
+use tracing::{error, info};
+use zeroize::{Zeroize, Zeroizing};
 
 use crate::{
-    types::{Config, EditableRelay, NostrStatusAppInternal, ProfileMetadata, TimelinePost, AppTab},
-    cache_db::{LmdbCache, DB_FOLLOWED, DB_RELAYS, DB_PROFILES, DB_TIMELINE},
-    CONFIG_FILE,
-    nostr_client::{connect_to_relays_with_nip65, fetch_nip01_profile, fetch_timeline_events}
+    accounts::{add_account, read_config, write_config},
+    drafts::{load_drafts, restore_drafts},
+    event_check,
+    i18n::tr,
+    types::{short_npub, BunkerSession, Config, KeyStorage, Settings, EditableRelay, NostrStatusAppInternal, ProfileMetadata, StoredAccount, TimelinePost, AppTab},
+    cache_db::{LmdbCache, DB_CONTACT_PROFILES, DB_FOLLOWED, DB_RELAYS, DB_PROFILES, DB_TIMELINE},
+    signer::AppSigner,
+    network::{self, is_relay_allowed, new_client, relay_options},
+    nip39,
+    opentimestamps,
+    outbox::load_outbox,
+    replaceable,
+    read_state::{self, load_read_state},
+    status_history::{load_current_statuses, load_status_history},
+    bunker_file_path, config_file_path,
+    nostr_client::{connect_to_relays_with_nip65, fetch_own_lists_from_bootstrap, fetch_profiles_for_pubkeys, fetch_timeline_events, publish_initial_profile, timeline_post_from_event}
 };
 
+// リモート署名者の応答を待つ時間。承認ページでの操作も含むため長めにとる
+const REMOTE_SIGNER_TIMEOUT_SECS: u64 = 120;
+
 // --- Step 1: キャッシュからデータを読み込む ---
 struct CachedData {
     followed_pubkeys: HashSet<PublicKey>,
     nip65_relays: Vec<(String, Option<String>)>,
     profile_metadata: ProfileMetadata,
     timeline_posts: Vec<TimelinePost>,
+    contact_profiles: HashMap<PublicKey, ProfileMetadata>,
 }
 
 fn load_data_from_cache(
     cache_db: &LmdbCache,
     pubkey_hex: &str,
 ) -> Result<CachedData, Box<dyn std::error::Error + Send + Sync>> {
-    println!("Loading data from cache for pubkey: {pubkey_hex}");
+    info!("Loading data from cache for pubkey: {pubkey_hex}");
 
     let followed_cache = cache_db.read_cache::<HashSet<PublicKey>>(DB_FOLLOWED, pubkey_hex)?;
     let nip65_cache =
@@ -36,95 +55,560 @@ fn load_data_from_cache(
     let timeline_cache = cache_db
         .read_cache::<Vec<TimelinePost>>(DB_TIMELINE, pubkey_hex)
         .ok();
+    let contact_profiles_cache = cache_db
+        .read_cache::<HashMap<PublicKey, ProfileMetadata>>(DB_CONTACT_PROFILES, pubkey_hex)
+        .ok();
 
     if followed_cache.is_expired() || nip65_cache.is_expired() || profile_cache.is_expired() {
         return Err("Cache expired".into());
     }
 
-    println!("Successfully loaded data from cache.");
+    info!("Successfully loaded data from cache.");
     Ok(CachedData {
         followed_pubkeys: followed_cache.data,
         nip65_relays: nip65_cache.data,
         profile_metadata: profile_cache.data,
         timeline_posts: timeline_cache.map_or(Vec::new(), |c| c.data),
+        contact_profiles: contact_profiles_cache.map_or(HashMap::new(), |c| c.data),
     })
 }
 
-// --- Step 2: ネットワークから新しいデータを取得 ---
-struct FreshData {
-    followed_pubkeys: HashSet<PublicKey>,
-    timeline_posts: Vec<TimelinePost>,
-    log_message: String,
-    fetched_nip65_relays: Vec<(String, Option<String>)>,
-    profile_metadata: ProfileMetadata,
-    profile_json_string: String,
+// キャッシュが無いか期限切れのときは、前回までに受信したイベントのデータベースから組み立てる
+async fn load_data_from_event_db(
+    client: &Client,
+    pubkey: PublicKey,
+    timeline_limit: usize,
+) -> Result<CachedData, Box<dyn std::error::Error + Send + Sync>> {
+    let database = client.database();
+    let contact_list = database
+        .query(Filter::new().author(pubkey).kind(Kind::ContactList).limit(1))
+        .await?
+        .first_owned();
+    let own_profile = database
+        .query(Filter::new().author(pubkey).kind(Kind::Metadata).limit(1))
+        .await?
+        .first_owned();
+    if contact_list.is_none() && own_profile.is_none() {
+        return Err("no events stored for this account".into());
+    }
+
+    let followed_pubkeys: HashSet<PublicKey> = contact_list
+        .map(|event| event.tags.public_keys().copied().collect())
+        .unwrap_or_default();
+    let profile_metadata = own_profile
+        .and_then(|event| serde_json::from_str::<ProfileMetadata>(&event.content).ok())
+        .unwrap_or_default();
+    let nip65_relays = database
+        .query(Filter::new().author(pubkey).kind(Kind::RelayList).limit(1))
+        .await?
+        .first_owned()
+        .map(|event| {
+            nip65::extract_relay_list(&event)
+                .map(|(url, policy)| (url.to_string(), policy.map(|p| p.to_string())))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut contact_profiles = HashMap::new();
+    let mut timeline_posts = Vec::new();
+    if !followed_pubkeys.is_empty() {
+        let profile_events = database
+            .query(Filter::new().authors(followed_pubkeys.clone()).kind(Kind::Metadata))
+            .await?;
+        for event in event_check::keep_valid(profile_events) {
+            if let Ok(metadata) = serde_json::from_str::<ProfileMetadata>(&event.content) {
+                contact_profiles.insert(event.pubkey, metadata);
+            }
+        }
+        let status_events = database
+            .query(Filter::new().authors(followed_pubkeys.clone()).kind(Kind::from(30315)).limit(timeline_limit))
+            .await?;
+        for event in event_check::keep_valid(status_events) {
+            // 期限切れのステータスは表示しない (NIP-40)
+            if event.is_expired() {
+                continue;
+            }
+            let author_metadata = contact_profiles.get(&event.pubkey).cloned().unwrap_or_default();
+            timeline_posts.push(timeline_post_from_event(&event, author_metadata));
+        }
+        timeline_posts.sort_by_key(|p| std::cmp::Reverse(p.created_at));
+    }
+
+    info!("Loaded data from the event database.");
+    Ok(CachedData {
+        followed_pubkeys,
+        nip65_relays,
+        profile_metadata,
+        timeline_posts,
+        contact_profiles,
+    })
 }
 
-async fn fetch_fresh_data_from_network(
+// --- Step 2: ネットワークから新しいデータを取得し、届いたものから画面に反映する ---
+fn editable_relays(relays: &[(String, Option<String>)]) -> Vec<EditableRelay> {
+    relays.iter().map(|(url, policy)| {
+        let (read, write) = match policy.as_deref() {
+            Some("read") => (true, false),
+            Some("write") => (false, true),
+            _ => (true, true),
+        };
+        EditableRelay { url: url.clone(), read, write }
+    }).collect()
+}
+
+fn latest_own_event(events: &[Event], pubkey: PublicKey, kind: Kind) -> Option<&Event> {
+    events
+        .iter()
+        .filter(|event| event.kind == kind && event.pubkey == pubkey)
+        .max_by_key(|event| event.created_at)
+}
+
+// リレーリストの検索と、フォローリスト・プロフィールの取得を並行して行う
+// フォローとプロフィールがそろったら操作できるようにし、タイムラインはリレーごとに届いた分から表示していく
+async fn load_fresh_data_from_network(
     client: &Client,
-    keys: &Keys,
-    discover_relays: &str,
-    default_relays: &str,
+    signer: &AppSigner,
+    settings: &Settings,
     cache_db: &LmdbCache,
-) -> Result<FreshData, Box<dyn std::error::Error + Send + Sync>> {
-    let pubkey_hex = keys.public_key().to_string();
-
-    println!("Fetching fresh data from network...");
+    app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    session: u64,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let pubkey = signer.public_key();
+    let pubkey_hex = pubkey.to_string();
+
+    info!("Fetching fresh data from network...");
+    {
+        let mut app_data = app_data_arc.lock().unwrap();
+        app_data.login_stage = Some(tr!("リレーに接続しています...").to_string());
+        app_data.should_repaint = true;
+    }
 
-    let (log_message, fetched_nip65_relays) =
-        connect_to_relays_with_nip65(client, keys, discover_relays, default_relays).await?;
+    let (relay_result, bootstrap_result) = tokio::join!(
+        connect_to_relays_with_nip65(client, signer, &settings.discover_relays, &settings.default_relays, &settings.manual_relays),
+        fetch_own_lists_from_bootstrap(signer, &settings.discover_relays, &settings.default_relays),
+    );
+    let (log_message, fetched_nip65_relays) = relay_result?;
+    info!("{log_message}");
     cache_db.write_cache(DB_RELAYS, &pubkey_hex, &fetched_nip65_relays)?;
 
-    println!("Fetching NIP-02 contact list...");
-    let nip02_filter = Filter::new()
-        .authors(vec![keys.public_key()])
-        .kind(Kind::ContactList)
-        .limit(1);
-    let nip02_filter_id = client
-        .subscribe(nip02_filter, Some(SubscribeAutoCloseOptions::default()))
-        .await?;
-
-    let mut followed_pubkeys = HashSet::new();
-    let mut received_nip02 = false;
-
-    tokio::select! {
-        _ = tokio::time::sleep(Duration::from_secs(10)) => {}
-        _ = async {
-            let mut notifications = client.notifications();
-            while let Ok(notification) = notifications.recv().await {
-                if let nostr_sdk::RelayPoolNotification::Event { event, .. } = notification {
-                    if event.kind == Kind::ContactList && event.pubkey == keys.public_key() {
-                        for tag in event.tags.iter() { if let Some(nostr::TagStandard::PublicKey { public_key, .. }) = tag.as_standardized() { followed_pubkeys.insert(*public_key); } }
-                        received_nip02 = true;
-                        break;
-                    }
-                }
-            }
-        } => {},
+    let mut own_events: Vec<Event> = match bootstrap_result {
+        Ok(events) => events.into_iter().collect(),
+        Err(e) => {
+            error!("Failed to fetch the contact list from the discover relays: {e}");
+            Vec::new()
+        }
+    };
+    // 発見リレーで見つからなかったものは、つないだ自分のリレーから取得する
+    let missing_kinds: Vec<Kind> = [Kind::ContactList, Kind::Metadata]
+        .into_iter()
+        .filter(|kind| latest_own_event(&own_events, pubkey, *kind).is_none())
+        .collect();
+    if !missing_kinds.is_empty() {
+        info!("Fetching {missing_kinds:?} from the user's relays...");
+        let filter = Filter::new().author(pubkey).kinds(missing_kinds);
+        own_events.extend(network::fetch_events(client, filter).await?);
     }
-    client.unsubscribe(&nip02_filter_id).await;
 
-    if received_nip02 {
+    let contact_list = latest_own_event(&own_events, pubkey, Kind::ContactList);
+    let followed_pubkeys: HashSet<PublicKey> = contact_list
+        .map(|event| event.tags.public_keys().copied().collect())
+        .unwrap_or_default();
+    if contact_list.is_some() {
         cache_db.write_cache(DB_FOLLOWED, &pubkey_hex, &followed_pubkeys)?;
     }
+    let my_identities = latest_own_event(&own_events, pubkey, Kind::Metadata).map(|event| nip39::identities(&event.tags)).unwrap_or_default();
+    let (profile_metadata, profile_json_string) = match latest_own_event(&own_events, pubkey, Kind::Metadata) {
+        Some(event) => (serde_json::from_str::<ProfileMetadata>(&event.content)?, event.content.clone()),
+        // プロフィールが見つからなかった場合はデフォルト値を使う
+        None => {
+            let default_metadata = ProfileMetadata::default();
+            let default_json = serde_json::to_string_pretty(&default_metadata)?;
+            (default_metadata, default_json)
+        }
+    };
+    cache_db.write_cache(DB_PROFILES, &pubkey_hex, &profile_metadata)?;
+
+    {
+        let mut app_data = app_data_arc.lock().unwrap();
+        if !app_data.is_current_session(session) {
+            return Ok(());
+        }
+        if contact_list.is_some() {
+            app_data.followed_pubkeys = followed_pubkeys.clone();
+        }
+        app_data.nip65_relays = editable_relays(&fetched_nip65_relays);
+        app_data.my_emojis = profile_metadata.emojis
+            .iter()
+            .map(|emoji_pair| (emoji_pair[0].clone(), emoji_pair[1].clone()))
+            .collect();
+        app_data.editable_profile = profile_metadata;
+        app_data.my_identities = my_identities;
+        app_data.nip01_profile_display = profile_json_string;
+        app_data.profile_fetch_status = tr!("プロフィールを読み込みました").to_string();
+        // ここから先はタイムラインを読み込みながら操作できるようにする
+        app_data.is_loading = false;
+        app_data.login_stage = Some(tr!("タイムラインを読み込んでいます...").to_string());
+        app_data.should_repaint = true;
+    }
+
+    // --- Fetch NIP-30/51 Emojis with fallback ---
+    let nip65_relays = fetched_nip65_relays.clone();
+    let app_data_clone_for_emojis = app_data_arc.clone();
+    runtime_handle.spawn(async move {
+        info!("Spawning emoji fetch task for kind:30030...");
+        let nip65_urls: Vec<String> = nip65_relays.iter().map(|(url, _)| url.clone()).collect();
+
+        let mut custom_emojis = crate::emoji_loader::fetch_emoji_sets(&nip65_urls, pubkey).await;
+
+        if custom_emojis.is_empty() {
+            info!("No emojis found in NIP-65 relays, trying default relays...");
+            let default_relays_str = {
+                let app_data = app_data_clone_for_emojis.lock().unwrap();
+                app_data.settings.default_relays.clone()
+            };
+            let default_relay_urls: Vec<String> = default_relays_str.lines().map(String::from).collect();
+            custom_emojis = crate::emoji_loader::fetch_emoji_sets(&default_relay_urls, pubkey).await;
+        }
+
+        if !custom_emojis.is_empty() {
+            info!("Fetched {} custom emojis from kind:30030.", custom_emojis.len());
+            let mut app_data = app_data_clone_for_emojis.lock().unwrap();
+            if app_data.is_current_session(session) {
+                app_data.my_emojis.extend(custom_emojis);
+                app_data.should_repaint = true;
+            }
+        } else {
+            info!("No custom emojis found from NIP-65 or default relays.");
+        }
+    });
+    // --- End Fetch Emojis ---
+
+    info!("Fetching NIP-01 profiles and the timeline for followed users...");
+    let timeline_limit = settings.timeline_limit;
+    let (contact_profiles_result, timeline_result) = tokio::join!(
+        fetch_profiles_for_pubkeys(client, followed_pubkeys.iter().cloned().collect()),
+        fetch_timeline_events(signer, settings, &followed_pubkeys, |posts| {
+            let mut app_data = app_data_arc.lock().unwrap();
+            if !app_data.is_current_session(session) {
+                return;
+            }
+            if replaceable::merge_timeline_posts(&mut app_data.timeline_posts, posts) > 0 {
+                app_data.timeline_posts.truncate(timeline_limit);
+                app_data.should_repaint = true;
+            }
+        }),
+    );
+
+    match contact_profiles_result {
+        Ok(profiles) => {
+            cache_db.write_cache(DB_CONTACT_PROFILES, &pubkey_hex, &profiles)?;
+            let mut app_data = app_data_arc.lock().unwrap();
+            if app_data.is_current_session(session) {
+                app_data.profiles.extend(profiles);
+                app_data.should_repaint = true;
+            }
+        }
+        Err(e) => error!("Failed to fetch profiles for followed users: {e}"),
+    }
 
-    let timeline_posts = fetch_timeline_events(keys, discover_relays, &followed_pubkeys).await?;
+    let mut timeline_posts = timeline_result?;
     cache_db.write_cache(DB_TIMELINE, &pubkey_hex, &timeline_posts)?;
+    let mut app_data = app_data_arc.lock().unwrap();
+    if app_data.is_current_session(session) {
+        // キャッシュや途中まで表示していた内容に、プロフィール付きの最新の内容を重ねる
+        let shown_posts: Vec<TimelinePost> = std::mem::take(&mut app_data.timeline_posts)
+            .into_iter()
+            .filter(|post| app_data.followed_pubkeys.contains(&post.author_pubkey))
+            .collect();
+        replaceable::merge_timeline_posts(&mut timeline_posts, shown_posts);
+        timeline_posts.truncate(timeline_limit);
+        app_data.timeline_posts = timeline_posts;
+        app_data.should_repaint = true;
+    }
+    Ok(())
+}
 
-    let (profile_metadata, profile_json_string) =
-        fetch_nip01_profile(client, keys.public_key()).await?;
-    cache_db.write_cache(DB_PROFILES, &pubkey_hex, &profile_metadata)?;
 
-    Ok(FreshData {
-        followed_pubkeys,
-        timeline_posts,
-        log_message,
-        fetched_nip65_relays,
-        profile_metadata,
-        profile_json_string,
-    })
+// パスフレーズで復号した、保存済みのアカウントの鍵とウォレットの接続先
+pub struct UnlockedAccount {
+    keys: Keys,
+    nwc_uri: Option<NostrWalletConnectURI>,
+    key_storage: KeyStorage,
+}
+
+// 保存したアカウントの秘密鍵をパスフレーズで復号し、最後に使ったアカウントとして記録する
+pub fn unlock_account(
+    account_index: usize,
+    passphrase: &str,
+) -> Result<UnlockedAccount, Box<dyn std::error::Error + Send + Sync>> {
+    let mut config = read_config()?;
+    let target_kdf = config.kdf;
+    let account = config.accounts.get_mut(account_index).ok_or(tr!("アカウントが見つかりません"))?;
+    let encrypted_secret_key = crate::key_store::load_encrypted_secret_key(account)?;
+    let secret_key = crate::nip49::decrypt_secret_key(
+        &encrypted_secret_key,
+        passphrase,
+        &account.salt,
+    )?;
+    // 以前の独自形式で保存されていた鍵は標準の ncryptsec 形式に移行する
+    if crate::nip49::is_legacy_format(&encrypted_secret_key) {
+        let migrated = crate::nip49::encrypt_secret_key(&secret_key, passphrase)?;
+        crate::key_store::store_encrypted_secret_key(account, migrated)?;
+        info!("Migrated the stored secret key to the ncryptsec format.");
+    }
+    let keys = Keys::new(secret_key);
+    // 以前の形式から移したアカウントは、ここで公開鍵とラベルを記録する
+    if account.pubkey.is_empty() {
+        crate::key_store::set_account_pubkey(account, keys.public_key().to_hex())?;
+        account.label = short_npub(&keys.public_key());
+    }
+
+    let decrypted_nwc_bytes = match &account.encrypted_nwc_uri {
+        Some(encrypted_nwc) => Some(crate::nip49::decrypt(
+            encrypted_nwc,
+            passphrase,
+            &account.salt,
+            &account.kdf,
+        )?),
+        None => None,
+    };
+    // 以前の KDF (PBKDF2 など) で暗号化していたものは、新しい塩と設定ファイルの KDF で暗号化し直す
+    // 移せなくてもログインはできるので、失敗したら次回に回す
+    if account.kdf != target_kdf {
+        let salt = crate::nip49::generate_salt();
+        let reencrypted = decrypted_nwc_bytes
+            .as_ref()
+            .map(|bytes| crate::nip49::encrypt_with_salt(bytes, passphrase, &salt, &target_kdf))
+            .transpose();
+        match reencrypted {
+            Ok(encrypted_nwc_uri) => {
+                info!("Migrated the key derivation from {:?} to {:?}.", account.kdf, target_kdf);
+                account.encrypted_nwc_uri = encrypted_nwc_uri;
+                account.salt = salt;
+                account.kdf = target_kdf;
+            }
+            Err(e) => error!("Failed to migrate the key derivation: {e}"),
+        }
+    }
+    let nwc_uri = match decrypted_nwc_bytes {
+        Some(bytes) => Some(NostrWalletConnectURI::from_str(std::str::from_utf8(&bytes)?)?),
+        None => None,
+    };
+    let key_storage = account.key_storage;
+    config.active_account = account_index;
+    write_config(&config)?;
+    Ok(UnlockedAccount { keys, nwc_uri, key_storage })
+}
+
+// 復号した鍵でログインする。ウォレットの接続先が保存されていればつなぐ
+pub async fn start_unlocked_session(
+    unlocked: UnlockedAccount,
+    cache_db: LmdbCache,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: tokio::runtime::Handle,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    {
+        let mut app_data = app_data_arc.lock().unwrap();
+        app_data.key_storage = unlocked.key_storage;
+        crate::accounts::refresh_accounts(&mut app_data);
+        // バックグラウンドに残していた同じアカウントには、二重につながないよう切断する
+        if app_data.secondary_account.as_ref().is_some_and(|account| account.signer.public_key() == unlocked.keys.public_key()) {
+            crate::accounts::disconnect_secondary(&mut app_data, &runtime_handle);
+        }
+    }
+
+    if let Some(uri) = unlocked.nwc_uri {
+        let app_data_for_nwc_task = app_data_arc.clone();
+        runtime_handle.clone().spawn(async move {
+            if let Err(e) =
+                super::wallet_view::connect_nwc(uri, app_data_for_nwc_task.clone())
+                    .await
+            {
+                error!("Failed to connect to NWC: {}", e);
+                let mut app_data =
+                    app_data_for_nwc_task.lock().unwrap();
+                app_data.nwc_error = Some(tr!("NWC の自動接続に失敗しました: {}", e));
+                app_data.report_error(tr!("ウォレット (NWC) に接続できませんでした: {}", e));
+            }
+        });
+    }
+
+    start_session(AppSigner::from_keys(unlocked.keys), cache_db, app_data_arc, runtime_handle).await
 }
 
+// ログイン後の共通処理。署名者でクライアントを作成し、キャッシュを表示してからネットワークの最新データで更新する
+pub async fn start_session(
+    signer: AppSigner,
+    cache_db: LmdbCache,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: tokio::runtime::Handle,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let client = new_client(signer.clone());
+    let pubkey_hex = signer.public_key().to_string();
+    let (settings, session) = {
+        let app_data = app_data_arc.lock().unwrap();
+        (app_data.settings.clone(), app_data.session_generation)
+    };
+    let outbox = load_outbox(&cache_db, &pubkey_hex);
+    let status_history = load_status_history(&cache_db, &pubkey_hex);
+    let current_statuses = load_current_statuses(&cache_db, &pubkey_hex);
+    let read_state = load_read_state(&cache_db, &pubkey_hex);
+    let drafts = load_drafts(&cache_db, &pubkey_hex);
+    let timestamp_proofs = opentimestamps::load_proofs(&cache_db, &pubkey_hex);
+    let cached_data = match load_data_from_cache(&cache_db, &pubkey_hex) {
+        Ok(cached_data) => Ok(cached_data),
+        Err(_) => load_data_from_event_db(&client, signer.public_key(), settings.timeline_limit).await,
+    };
+    let started = {
+        let mut app_data = app_data_arc.lock().unwrap();
+        // キャッシュを読んでいる間にロックされたら、ログインをやめる
+        let current = app_data.is_current_session(session);
+        if current {
+            app_data.my_signer = Some(signer.clone());
+            app_data.nostr_client = Some(client.clone());
+            app_data.outbox = outbox;
+            app_data.status_history = status_history;
+            app_data.current_statuses = current_statuses;
+            app_data.read_state = read_state;
+            app_data.timestamp_proofs = timestamp_proofs;
+            restore_drafts(&mut app_data, drafts);
+            if let Ok(cached_data) = cached_data {
+                app_data.followed_pubkeys = cached_data.followed_pubkeys;
+                // 古い版のキャッシュに重なったステータスが残っていることがあるので、最新のものだけにする
+                app_data.timeline_posts.clear();
+                replaceable::merge_timeline_posts(&mut app_data.timeline_posts, cached_data.timeline_posts);
+                app_data.profiles = cached_data.contact_profiles;
+                app_data.editable_profile = cached_data.profile_metadata;
+                app_data.nip65_relays = editable_relays(&cached_data.nip65_relays);
+            }
+            app_data.is_logged_in = true;
+            app_data.is_loading = true;
+        }
+        current
+    };
+    if !started {
+        info!("The session was locked or closed while logging in; discarding it.");
+        client.shutdown().await;
+        return Ok(());
+    }
+    let fresh_data_result = load_fresh_data_from_network(&client, &signer, &settings, &cache_db, &app_data_arc, &runtime_handle, session).await;
+    let mut app_data = app_data_arc.lock().unwrap();
+    if !app_data.is_current_session(session) {
+        return Ok(());
+    }
+    app_data.login_stage = None;
+    if let Err(e) = fresh_data_result {
+        app_data.profile_fetch_status = tr!("データを更新できませんでした: {}", e);
+        app_data.report_error(tr!("リレーから最新のデータを取得できませんでした: {}", e));
+    }
+    Ok(())
+}
+
+// リモート署名者から届いた auth_url を画面に表示し、ブラウザで承認してもらう
+struct AuthUrlNotifier {
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+}
+
+impl fmt::Debug for AuthUrlNotifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuthUrlNotifier").finish()
+    }
+}
+
+impl AuthUrlHandler for AuthUrlNotifier {
+    fn on_auth_url(&self, auth_url: Url) -> BoxedFuture<'_, nostr::Result<()>> {
+        Box::pin(async move {
+            let mut app_data = self.app_data_arc.lock().unwrap();
+            app_data.bunker_auth_url = Some(auth_url.to_string());
+            app_data.remote_signer_status = Some(tr!("リモート署名者の承認ページで許可してください").to_string());
+            app_data.should_repaint = true;
+            Ok(())
+        })
+    }
+}
+
+// bunker URI でリモート署名者に接続し、次回起動時のために接続情報を保存する
+async fn connect_remote_signer(
+    uri: NostrConnectURI,
+    app_keys: Keys,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+) -> Result<AppSigner, Box<dyn std::error::Error + Send + Sync>> {
+    if !uri.is_bunker() {
+        return Err(tr!("bunker:// から始まるURIを入力してください").into());
+    }
+    if let Some(relay) = uri.relays().iter().find(|relay| !is_relay_allowed(relay)) {
+        return Err(tr!("Torのみの設定のため、このリレーは使えません: {}", relay).into());
+    }
+    let mut remote = NostrConnect::new(uri, app_keys, Duration::from_secs(REMOTE_SIGNER_TIMEOUT_SECS), Some(relay_options()))?;
+    remote.auth_url_handler(AuthUrlNotifier { app_data_arc });
+    let signer = AppSigner::from_remote(remote.clone()).await?;
+
+    // 保存するURIには接続用のシークレットを含めない
+    let session = BunkerSession {
+        uri: remote.bunker_uri().await?.to_string(),
+        app_secret_key: remote.local_keys().secret_key().to_secret_hex(),
+    };
+    fs::write(bunker_file_path(), serde_json::to_string_pretty(&session)?)?;
+    Ok(signer)
+}
+
+// 入力された bunker URI、または保存済みの接続情報でリモート署名者にログインする
+fn spawn_remote_signer_login(
+    bunker_uri: Option<String>,
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: tokio::runtime::Handle,
+) {
+    let cache_db = app_data.cache_db.clone();
+    app_data.is_loading = true;
+    app_data.bunker_auth_url = None;
+    app_data.remote_signer_status = Some(tr!("リモート署名者に接続中...").to_string());
+    app_data.should_repaint = true;
+    runtime_handle.clone().spawn(async move {
+        let login_result: Result<(), Box<dyn std::error::Error + Send + Sync>> = async {
+            let (uri, app_keys) = match bunker_uri {
+                Some(uri) => (NostrConnectURI::parse(uri.trim())?, Keys::generate()),
+                None => {
+                    let session: BunkerSession = serde_json::from_str(&fs::read_to_string(bunker_file_path())?)?;
+                    (NostrConnectURI::parse(&session.uri)?, Keys::parse(&session.app_secret_key)?)
+                }
+            };
+            let signer = connect_remote_signer(uri, app_keys, app_data_arc.clone()).await?;
+            {
+                let mut app_data = app_data_arc.lock().unwrap();
+                app_data.bunker_uri_input.clear();
+                app_data.bunker_auth_url = None;
+                app_data.remote_signer_status = Some(tr!("リモート署名者に接続しました").to_string());
+            }
+            start_session(signer, cache_db, app_data_arc.clone(), runtime_handle).await
+        }.await;
+        let mut app_data = app_data_arc.lock().unwrap();
+        if let Err(e) = login_result {
+            error!("Failed to log in with remote signer: {e}");
+            app_data.remote_signer_status = Some(tr!("リモート署名者への接続に失敗しました: {}", e));
+            app_data.report_error(tr!("リモート署名者への接続に失敗しました: {}", e));
+        }
+        app_data.is_loading = false;
+        app_data.should_repaint = true;
+    });
+}
+
+// 確認の単語の入力をやめて、シードフレーズの表示に戻す
+fn clear_mnemonic_verification(app_data: &mut NostrStatusAppInternal) {
+    for input in &mut app_data.mnemonic_verify_inputs {
+        input.zeroize();
+    }
+    app_data.mnemonic_verify_inputs.clear();
+    app_data.mnemonic_verify_positions.clear();
+    app_data.generated_key_backed_up = false;
+}
+
+fn discard_generated_key(app_data: &mut NostrStatusAppInternal) {
+    clear_mnemonic_verification(app_data);
+    app_data.generated_mnemonic = None;
+    app_data.generated_keys = None;
+}
 
 pub fn draw_login_view(
     ui: &mut egui::Ui,
@@ -132,21 +616,64 @@ pub fn draw_login_view(
     app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
     runtime_handle: tokio::runtime::Handle,
 ) {
-    let login_heading_text = "ログインまたは登録";
-    let secret_key_label_text = "秘密鍵 (nsec):";
-    let secret_key_hint_text = "nsec1...";
-    let passphrase_label_text = "パスフレーズ:";
-    let passphrase_hint_text = "パスワード";
-    let confirm_passphrase_label_text = "パスフレーズの確認:";
-    let confirm_passphrase_hint_text = "パスワードを再入力";
-    let login_button_text = "ログイン";
-    let register_button_text = "登録";
+    let login_heading_text = tr!("ログインまたは登録");
+    let secret_key_label_text = tr!("秘密鍵 (nsec / ncryptsec / シードフレーズ):");
+    let secret_key_hint_text = tr!("nsec1... / ncryptsec1... / 12〜24 個の英単語");
+    let passphrase_label_text = tr!("パスフレーズ:");
+    let passphrase_hint_text = tr!("パスワード");
+    let confirm_passphrase_label_text = tr!("パスフレーズの確認:");
+    let confirm_passphrase_hint_text = tr!("パスワードを再入力");
+    let login_button_text = tr!("ログイン");
+    let register_button_text = tr!("登録");
+    let generate_key_button_text = tr!("新しい鍵を生成");
+    let backup_warning_text = tr!("このシードフレーズは今しか表示されません。失うとアカウントを復元できないので、順番どおり紙に書き留めて安全な場所に保管してください。");
+    let backup_done_button_text = tr!("書き留めました");
+    let verify_description_text = tr!("書き留めたシードフレーズから、次の番号の単語を入力してください");
+    let verify_ok_text = tr!("✔ シードフレーズを確認しました");
+    let show_words_again_text = tr!("単語をもう一度表示");
+    let mnemonic_passphrase_label_text = tr!("BIP-39 パスフレーズ (任意):");
+    let mnemonic_passphrase_hint_text = tr!("設定していなければ空のまま");
+    let mnemonic_account_label_text = tr!("アカウント番号:");
+    let profile_name_label_text = tr!("名前:");
+    let profile_name_hint_text = tr!("プロフィールに表示する名前");
+    let discard_generated_key_text = tr!("生成した鍵を破棄");
+    let account_label_text = tr!("アカウント:");
+    let add_account_button_text = tr!("別のアカウントを追加");
+    let cancel_add_account_text = tr!("追加をやめる");
+    let locked_text = tr!("🔒 ロックしました。続けるにはパスフレーズを入力してください");
+
+    // アカウントを追加している途中でも、バックグラウンドに残したアカウントへすぐ戻れるようにする
+    if let Some(secondary) = &app_data.secondary_account {
+        let back_text = tr!("← {} に戻る", read_state::with_badge(&secondary.label, secondary.unread));
+        if ui.add_enabled(!app_data.is_loading, egui::Button::new(back_text)).clicked() {
+            app_data.adding_account = false;
+            crate::accounts::spawn_switch_to_secondary(app_data, app_data_arc.clone(), &runtime_handle, ui.ctx());
+        }
+        ui.add_space(10.0);
+    }
 
     ui.group(|ui| {
         ui.heading(login_heading_text);
         ui.add_space(10.0);
-        if Path::new(CONFIG_FILE).exists() {
+        if app_data.is_locked && !app_data.adding_account {
+            ui.label(egui::RichText::new(locked_text).strong());
+            ui.add_space(5.0);
+        }
+        if !app_data.accounts.is_empty() && !app_data.adding_account {
             // --- ログイン ---
+            if app_data.accounts.len() > 1 {
+                ui.horizontal(|ui| {
+                    ui.label(account_label_text);
+                    let selected_text = app_data.accounts.get(app_data.selected_login_account).map(|a| a.label.clone()).unwrap_or_default();
+                    egui::ComboBox::from_id_salt("login_account")
+                        .selected_text(selected_text)
+                        .show_ui(ui, |ui| {
+                            for (index, account) in app_data.accounts.iter().enumerate() {
+                                ui.selectable_value(&mut app_data.selected_login_account, index, &account.label);
+                            }
+                        });
+                });
+            }
             ui.horizontal(|ui| {
                 ui.label(passphrase_label_text);
                 ui.add(egui::TextEdit::singleline(&mut app_data.passphrase_input)
@@ -154,144 +681,25 @@ pub fn draw_login_view(
                     .hint_text(passphrase_hint_text));
             });
 
+            if ui.button(add_account_button_text).clicked() {
+                app_data.adding_account = true;
+                app_data.passphrase_input.zeroize();
+            }
+
             if ui.button(egui::RichText::new(login_button_text).strong()).clicked() && !app_data.is_loading {
-                let passphrase = app_data.passphrase_input.clone();
+                let account_index = app_data.selected_login_account;
+                // 入力欄には残さず、使い終わったらメモリから消す
+                let passphrase = Zeroizing::new(std::mem::take(&mut app_data.passphrase_input));
                 let cache_db_clone = app_data.cache_db.clone();
                 app_data.is_loading = true;
                 app_data.should_repaint = true;
                 let cloned_app_data_arc = app_data_arc.clone();
+                let runtime_handle = runtime_handle.clone();
                 runtime_handle.clone().spawn(async move {
                     let app_data_for_login_logic = cloned_app_data_arc.clone();
                     let login_result: Result<(), Box<dyn std::error::Error + Send + Sync>> = async move {
-                        let (keys, nwc_uri) = (|| -> Result<_, Box<dyn std::error::Error + Send + Sync>> {
-                            let config_str = fs::read_to_string(CONFIG_FILE)?;
-                            let config: Config = serde_json::from_str(&config_str)?;
-                            let decrypted_bytes = crate::nip49::decrypt(
-                                &config.encrypted_secret_key,
-                                &passphrase,
-                                &config.salt,
-                            )?;
-                            let keys = Keys::parse(&hex::encode(&decrypted_bytes))?;
-
-                            let nwc_uri = if let Some(encrypted_nwc) = config.encrypted_nwc_uri {
-                                let decrypted_nwc_bytes = crate::nip49::decrypt(
-                                    &encrypted_nwc,
-                                    &passphrase,
-                                    &config.salt,
-                                )?;
-                                let nwc_uri_str = String::from_utf8(decrypted_nwc_bytes)?;
-                                Some(NostrWalletConnectURI::from_str(&nwc_uri_str)?)
-                            } else {
-                                None
-                            };
-                            Ok((keys, nwc_uri))
-                        })()?;
-
-                        if let Some(uri) = nwc_uri {
-                            let app_data_for_nwc_task = app_data_for_login_logic.clone();
-                            runtime_handle.clone().spawn(async move {
-                                if let Err(e) =
-                                    super::wallet_view::connect_nwc(uri, app_data_for_nwc_task.clone())
-                                        .await
-                                {
-                                    eprintln!("Failed to connect to NWC: {}", e);
-                                    let mut app_data =
-                                        app_data_for_nwc_task.lock().unwrap();
-                                    app_data.nwc_error = Some(format!("NWC auto-connect failed: {}", e));
-                                }
-                            });
-                        }
-
-                        let client = Client::new(keys.clone());
-                        let pubkey_hex = keys.public_key().to_string();
-                        let (discover_relays, default_relays) = {
-                            let app_data = app_data_for_login_logic.lock().unwrap();
-                            (app_data.discover_relays_editor.clone(), app_data.default_relays_editor.clone())
-                        };
-                        if let Ok(cached_data) = load_data_from_cache(&cache_db_clone, &pubkey_hex) {
-                            let mut app_data = app_data_for_login_logic.lock().unwrap();
-                            app_data.my_keys = Some(keys.clone());
-                            app_data.nostr_client = Some(client.clone());
-                            app_data.followed_pubkeys = cached_data.followed_pubkeys;
-                            app_data.timeline_posts = cached_data.timeline_posts;
-                            app_data.editable_profile = cached_data.profile_metadata;
-                            app_data.nip65_relays = cached_data.nip65_relays.into_iter().map(|(url, policy)| {
-                                let (read, write) = match policy.as_deref() {
-                                    Some("read") => (true, false),
-                                    Some("write") => (false, true),
-                                    _ => (true, true),
-                                };
-                                EditableRelay { url, read, write }
-                            }).collect();
-                            app_data.is_logged_in = true;
-                            app_data.is_loading = true;
-                        } else {
-                            let mut app_data = app_data_for_login_logic.lock().unwrap();
-                            app_data.my_keys = Some(keys.clone());
-                            app_data.nostr_client = Some(client.clone());
-                            app_data.is_logged_in = true;
-                            app_data.is_loading = true;
-                        }
-                        let fresh_data_result = fetch_fresh_data_from_network(&client, &keys, &discover_relays, &default_relays, &cache_db_clone).await;
-                        if let Ok(fresh_data) = fresh_data_result {
-                            let mut app_data = app_data_for_login_logic.lock().unwrap();
-                            app_data.followed_pubkeys = fresh_data.followed_pubkeys;
-                            app_data.timeline_posts = fresh_data.timeline_posts;
-                            if let Some(pos) = fresh_data.log_message.find("--- 現在接続中のリレー ---") {
-                                app_data.connected_relays_display = fresh_data.log_message[pos..].to_string();
-                            }
-                            app_data.nip65_relays = fresh_data.fetched_nip65_relays.clone().into_iter().map(|(url, policy)| {
-                                let (read, write) = match policy.as_deref() {
-                                    Some("read") => (true, false),
-                                    Some("write") => (false, true),
-                                    _ => (true, true),
-                                };
-                                EditableRelay { url, read, write }
-                            }).collect();
-                            let my_emojis: std::collections::HashMap<String, String> = fresh_data.profile_metadata.emojis
-                                .iter()
-                                .map(|emoji_pair| (emoji_pair[0].clone(), emoji_pair[1].clone()))
-                                .collect();
-                            app_data.my_emojis = my_emojis;
-                            app_data.editable_profile = fresh_data.profile_metadata;
-                            app_data.nip01_profile_display = fresh_data.profile_json_string;
-                            app_data.profile_fetch_status = "Profile loaded.".to_string();
-
-                            // --- Fetch NIP-30/51 Emojis with fallback ---
-                            let pubkey = keys.public_key();
-                            let nip65_relays = fresh_data.fetched_nip65_relays.clone();
-                            let app_data_clone_for_emojis = app_data_for_login_logic.clone();
-                            runtime_handle.clone().spawn(async move {
-                                println!("Spawning emoji fetch task for kind:30030...");
-                                let nip65_urls: Vec<String> = nip65_relays.iter().map(|(url, _)| url.clone()).collect();
-
-                                let mut custom_emojis = crate::emoji_loader::fetch_emoji_sets(&nip65_urls, pubkey).await;
-
-                                if custom_emojis.is_empty() {
-                                    println!("No emojis found in NIP-65 relays, trying default relays...");
-                                    let default_relays_str = {
-                                        let app_data = app_data_clone_for_emojis.lock().unwrap();
-                                        app_data.default_relays_editor.clone()
-                                    };
-                                    let default_relay_urls: Vec<String> = default_relays_str.lines().map(String::from).collect();
-                                    custom_emojis = crate::emoji_loader::fetch_emoji_sets(&default_relay_urls, pubkey).await;
-                                }
-
-                                if !custom_emojis.is_empty() {
-                                    println!("Fetched {} custom emojis from kind:30030.", custom_emojis.len());
-                                    let mut app_data = app_data_clone_for_emojis.lock().unwrap();
-                                    app_data.my_emojis.extend(custom_emojis);
-                                    app_data.should_repaint = true;
-                                } else {
-                                    println!("No custom emojis found from NIP-65 or default relays.");
-                                }
-                            });
-                            // --- End Fetch Emojis ---
-                        } else if let Err(e) = fresh_data_result {
-                            let mut app_data = app_data_for_login_logic.lock().unwrap();
-                            app_data.profile_fetch_status = format!("Failed to refresh data: {e}");
-                        }
-                        Ok(())
+                        let unlocked = unlock_account(account_index, &passphrase)?;
+                        start_unlocked_session(unlocked, cache_db_clone, app_data_for_login_logic, runtime_handle).await
                     }.await;
                     if let Err(e) = login_result {
                         let client_to_shutdown = {
@@ -300,7 +708,9 @@ pub fn draw_login_view(
                         };
                         if let Some(client) = client_to_shutdown { client.shutdown().await; }
                         let mut app_data_in_task = cloned_app_data_arc.lock().unwrap();
-                        app_data_in_task.profile_fetch_status = format!("Login failed: {e}");
+                        app_data_in_task.profile_fetch_status = tr!("ログインに失敗しました: {}", e);
+                        error!("Failed to log in: {e}");
+                        app_data_in_task.report_error(tr!("ログインに失敗しました。パスフレーズを確認してください: {}", e));
                     }
                     let mut app_data_in_task = cloned_app_data_arc.lock().unwrap();
                     app_data_in_task.is_loading = false;
@@ -309,12 +719,97 @@ pub fn draw_login_view(
             }
         } else {
             // --- 新規登録 ---
-            ui.horizontal(|ui| {
-                ui.label(secret_key_label_text);
-                ui.add(egui::TextEdit::singleline(&mut app_data.secret_key_input)
-                    .password(true)
-                    .hint_text(secret_key_hint_text));
-            });
+            if app_data.adding_account && !app_data.accounts.is_empty() && ui.button(cancel_add_account_text).clicked() {
+                app_data.adding_account = false;
+            }
+            if let Some(mnemonic) = app_data.generated_mnemonic.clone() {
+                // シードフレーズはこの画面でしか表示しないので、書き留めた単語を入力してもらってから登録させる
+                let words: Vec<&str> = mnemonic.split_whitespace().collect();
+                egui::Frame::group(ui.style())
+                    .stroke(egui::Stroke::new(1.0, app_data.current_theme.danger_zone_stroke_color()))
+                    .show(ui, |ui| {
+                        if app_data.mnemonic_verify_positions.is_empty() {
+                            ui.label(egui::RichText::new(backup_warning_text).strong());
+                            egui::Grid::new("generated_mnemonic_grid").num_columns(4).spacing([16.0, 6.0]).show(ui, |ui| {
+                                for (i, word) in words.iter().enumerate() {
+                                    ui.monospace(format!("{:>2}. {word}", i + 1));
+                                    if i % 4 == 3 {
+                                        ui.end_row();
+                                    }
+                                }
+                            });
+                            if ui.button(backup_done_button_text).clicked() {
+                                app_data.mnemonic_verify_positions = crate::nip06::pick_verify_positions(words.len());
+                                app_data.mnemonic_verify_inputs = vec![String::new(); app_data.mnemonic_verify_positions.len()];
+                            }
+                        } else {
+                            ui.label(verify_description_text);
+                            let positions = app_data.mnemonic_verify_positions.clone();
+                            for (input, position) in app_data.mnemonic_verify_inputs.iter_mut().zip(&positions) {
+                                ui.horizontal(|ui| {
+                                    ui.label(format!("{}.", position + 1));
+                                    ui.add(egui::TextEdit::singleline(input).desired_width(160.0));
+                                });
+                            }
+                            app_data.generated_key_backed_up = app_data
+                                .mnemonic_verify_inputs
+                                .iter()
+                                .zip(&positions)
+                                .all(|(input, position)| input.trim().eq_ignore_ascii_case(words[*position]));
+                            if app_data.generated_key_backed_up {
+                                ui.label(egui::RichText::new(verify_ok_text).color(egui::Color32::from_rgb(0x06, 0xC7, 0x55)));
+                            }
+                            if ui.button(show_words_again_text).clicked() {
+                                clear_mnemonic_verification(app_data);
+                            }
+                        }
+                    });
+                ui.horizontal(|ui| {
+                    ui.label(profile_name_label_text);
+                    ui.add(egui::TextEdit::singleline(&mut app_data.new_profile_name_input)
+                        .hint_text(profile_name_hint_text));
+                });
+                if ui.button(discard_generated_key_text).clicked() {
+                    discard_generated_key(app_data);
+                }
+            } else {
+                ui.horizontal(|ui| {
+                    ui.label(secret_key_label_text);
+                    ui.add(egui::TextEdit::singleline(&mut app_data.secret_key_input)
+                        .password(true)
+                        .hint_text(secret_key_hint_text));
+                    if ui.button(generate_key_button_text).clicked() {
+                        match crate::nip06::generate_mnemonic().and_then(|mnemonic| {
+                            let keys = crate::nip06::keys_from_mnemonic(&mnemonic, "", 0)?;
+                            Ok((mnemonic, keys))
+                        }) {
+                            Ok((mnemonic, keys)) => {
+                                app_data.generated_mnemonic = Some(mnemonic);
+                                app_data.generated_keys = Some(keys);
+                                clear_mnemonic_verification(app_data);
+                                app_data.secret_key_input.zeroize();
+                            }
+                            Err(e) => {
+                                error!("Failed to generate a mnemonic: {e}");
+                                app_data.report_error(tr!("シードフレーズを生成できませんでした: {}", e));
+                            }
+                        }
+                    }
+                });
+                // シードフレーズを取り込むときは、ほかのクライアントで使っていたパスフレーズとアカウント番号も指定できる
+                if crate::nip06::looks_like_mnemonic(&app_data.secret_key_input) {
+                    ui.horizontal(|ui| {
+                        ui.label(mnemonic_passphrase_label_text);
+                        ui.add(egui::TextEdit::singleline(&mut app_data.mnemonic_passphrase_input)
+                            .password(true)
+                            .hint_text(mnemonic_passphrase_hint_text));
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label(mnemonic_account_label_text);
+                        ui.add(egui::DragValue::new(&mut app_data.mnemonic_account_input).range(0..=u32::MAX >> 1));
+                    });
+                }
+            }
 
             ui.horizontal(|ui| {
                 ui.label(passphrase_label_text);
@@ -329,113 +824,103 @@ pub fn draw_login_view(
                     .hint_text(confirm_passphrase_hint_text));
             });
 
-            if ui.button(egui::RichText::new(register_button_text).strong()).clicked() && !app_data.is_loading {
-                let secret_key_input = app_data.secret_key_input.clone();
-                let passphrase = app_data.passphrase_input.clone();
-                let confirm_passphrase = app_data.confirm_passphrase_input.clone();
+            let can_register = app_data.generated_keys.is_none() || app_data.generated_key_backed_up;
+            if ui.add_enabled(can_register, egui::Button::new(egui::RichText::new(register_button_text).strong())).clicked() && !app_data.is_loading {
+                let secret_key_input = Zeroizing::new(std::mem::take(&mut app_data.secret_key_input));
+                let generated_keys = app_data.generated_keys.clone();
+                let mnemonic_passphrase = Zeroizing::new(std::mem::take(&mut app_data.mnemonic_passphrase_input));
+                let mnemonic_account = app_data.mnemonic_account_input;
+                let new_profile_name = app_data.new_profile_name_input.trim().to_string();
+                let passphrase = Zeroizing::new(std::mem::take(&mut app_data.passphrase_input));
+                let confirm_passphrase = Zeroizing::new(std::mem::take(&mut app_data.confirm_passphrase_input));
                 let cache_db_clone = app_data.cache_db.clone();
                 app_data.is_loading = true;
                 app_data.should_repaint = true;
                 let cloned_app_data_arc = app_data_arc.clone();
+                let runtime_handle = runtime_handle.clone();
                 runtime_handle.clone().spawn(async move {
                     if passphrase != confirm_passphrase {
                         let mut current_app_data = cloned_app_data_arc.lock().unwrap();
-                        current_app_data.profile_fetch_status = "Passphrases do not match.".to_string();
+                        current_app_data.profile_fetch_status = tr!("パスフレーズが一致しません").to_string();
                         current_app_data.is_loading = false;
                         current_app_data.should_repaint = true;
                         return;
                     }
                     let registration_result: Result<(), Box<dyn std::error::Error + Send + Sync>> = async {
-                        let keys = (|| -> Result<Keys, Box<dyn std::error::Error + Send + Sync>> {
-                            let user_provided_keys = Keys::parse(&secret_key_input)?;
-                            let plaintext_bytes = user_provided_keys.secret_key().to_secret_bytes();
-                            let (nip49_encoded, salt_base64) =
-                                crate::nip49::encrypt(&plaintext_bytes, &passphrase)?;
-                            let config = Config {
-                                encrypted_secret_key: nip49_encoded,
-                                salt: salt_base64,
-                                encrypted_nwc_uri: None,
+                        let is_new_key = generated_keys.is_some();
+                        let user_provided_keys = match generated_keys {
+                            Some(keys) => keys,
+                            // 他のクライアントから書き出した ncryptsec はパスフレーズで復号して取り込む
+                            None if secret_key_input.trim().starts_with("ncryptsec1") => {
+                                Keys::new(crate::nip49::decrypt_secret_key(&secret_key_input, &passphrase, "")?)
+                            }
+                            None if crate::nip06::looks_like_mnemonic(&secret_key_input) => {
+                                crate::nip06::keys_from_mnemonic(&secret_key_input, &mnemonic_passphrase, mnemonic_account)?
+                            }
+                            None => Keys::parse(&secret_key_input)?,
+                        };
+                        let settings = cloned_app_data_arc.lock().unwrap().settings.clone();
+                        let label = if new_profile_name.is_empty() { short_npub(&user_provided_keys.public_key()) } else { new_profile_name.clone() };
+                        // 新しい鍵は他のクライアントからも見つけられるよう、最初のプロフィールを公開しておく
+                        if is_new_key {
+                            let initial_profile = ProfileMetadata {
+                                name: new_profile_name,
+                                ..Default::default()
                             };
-                            let config_json = serde_json::to_string_pretty(&config)?;
-                            fs::write(CONFIG_FILE, config_json)?;
-                            Ok(user_provided_keys)
+                            publish_initial_profile(&AppSigner::from_keys(user_provided_keys.clone()), &settings.default_relays, &initial_profile).await?;
+                        }
+                        let signer = (|| -> Result<AppSigner, Box<dyn std::error::Error + Send + Sync>> {
+                            // すでにほかのアカウントがあれば、その後ろに足す
+                            let mut config = if config_file_path().exists() { read_config()? } else { Config::default() };
+                            let kdf = config.kdf;
+                            add_account(&mut config, StoredAccount {
+                                label,
+                                pubkey: user_provided_keys.public_key().to_hex(),
+                                encrypted_secret_key: crate::nip49::encrypt_secret_key(user_provided_keys.secret_key(), &passphrase)?,
+                                salt: crate::nip49::generate_salt(),
+                                encrypted_nwc_uri: None,
+                                key_storage: KeyStorage::File,
+                                kdf,
+                            });
+                            write_config(&config)?;
+                            Ok(AppSigner::from_keys(user_provided_keys))
                         })()?;
-                        let client = Client::new(keys.clone());
-                        let (discover_relays, default_relays) = {
-                            let app_data = cloned_app_data_arc.lock().unwrap();
-                            (app_data.discover_relays_editor.clone(), app_data.default_relays_editor.clone())
-                        };
-                        let fresh_data_result = fetch_fresh_data_from_network(&client, &keys, &discover_relays, &default_relays, &cache_db_clone).await;
-                        if let Ok(fresh_data) = fresh_data_result {
+                        let client = new_client(signer.clone());
+                        let session = {
                             let mut app_data = cloned_app_data_arc.lock().unwrap();
-                            app_data.my_keys = Some(keys.clone());
-                            app_data.nostr_client = Some(client);
+                            app_data.adding_account = false;
+                            crate::accounts::refresh_accounts(&mut app_data);
+                            discard_generated_key(&mut app_data);
+                            app_data.mnemonic_account_input = 0;
+                            app_data.new_profile_name_input.clear();
+                            // 取得を待たずにホームを開き、届いたものから表示する
+                            app_data.my_signer = Some(signer.clone());
+                            app_data.nostr_client = Some(client.clone());
                             app_data.is_logged_in = true;
                             app_data.current_tab = AppTab::Home;
-                            app_data.followed_pubkeys = fresh_data.followed_pubkeys;
-                            app_data.timeline_posts = fresh_data.timeline_posts;
-                            if let Some(pos) = fresh_data.log_message.find("--- 現在接続中のリレー ---") {
-                                app_data.connected_relays_display = fresh_data.log_message[pos..].to_string();
-                            }
-                            app_data.nip65_relays = fresh_data.fetched_nip65_relays.clone().into_iter().map(|(url, policy)| {
-                                let (read, write) = match policy.as_deref() {
-                                    Some("read") => (true, false),
-                                    Some("write") => (false, true),
-                                    _ => (true, true),
-                                };
-                                EditableRelay { url, read, write }
-                            }).collect();
-                            let my_emojis: std::collections::HashMap<String, String> = fresh_data.profile_metadata.emojis
-                                .iter()
-                                .map(|emoji_pair| (emoji_pair[0].clone(), emoji_pair[1].clone()))
-                                .collect();
-                            app_data.my_emojis = my_emojis;
-                            app_data.editable_profile = fresh_data.profile_metadata;
-                            app_data.nip01_profile_display = fresh_data.profile_json_string;
-                            app_data.profile_fetch_status = "Profile loaded.".to_string();
-
-                            // --- Fetch NIP-30/51 Emojis with fallback ---
-                            let pubkey = keys.public_key();
-                            let nip65_relays = fresh_data.fetched_nip65_relays.clone();
-                            let app_data_clone_for_emojis = cloned_app_data_arc.clone();
-                            runtime_handle.clone().spawn(async move {
-                                println!("Spawning emoji fetch task for kind:30030...");
-                                let nip65_urls: Vec<String> = nip65_relays.iter().map(|(url, _)| url.clone()).collect();
-
-                                let mut custom_emojis = crate::emoji_loader::fetch_emoji_sets(&nip65_urls, pubkey).await;
-
-                                if custom_emojis.is_empty() {
-                                    println!("No emojis found in NIP-65 relays, trying default relays...");
-                                    let default_relays_str = {
-                                        let app_data = app_data_clone_for_emojis.lock().unwrap();
-                                        app_data.default_relays_editor.clone()
-                                    };
-                                    let default_relay_urls: Vec<String> = default_relays_str.lines().map(String::from).collect();
-                                    custom_emojis = crate::emoji_loader::fetch_emoji_sets(&default_relay_urls, pubkey).await;
-                                }
-
-                                if !custom_emojis.is_empty() {
-                                    println!("Fetched {} custom emojis from kind:30030.", custom_emojis.len());
-                                    let mut app_data = app_data_clone_for_emojis.lock().unwrap();
-                                    app_data.my_emojis.extend(custom_emojis);
-                                    app_data.should_repaint = true;
-                                } else {
-                                    println!("No custom emojis found from NIP-65 or default relays.");
-                                }
-                            });
-                            // --- End Fetch Emojis ---
-                        } else if let Err(e) = fresh_data_result {
-                            eprintln!("Failed to fetch initial data for registration: {e}");
+                            app_data.key_storage = KeyStorage::File;
+                            app_data.session_generation
+                        };
+                        let fresh_data_result = load_fresh_data_from_network(&client, &signer, &settings, &cache_db_clone, &cloned_app_data_arc, &runtime_handle, session).await;
+                        let mut app_data = cloned_app_data_arc.lock().unwrap();
+                        if !app_data.is_current_session(session) {
+                            return Ok(());
+                        }
+                        app_data.login_stage = None;
+                        if let Err(e) = fresh_data_result {
+                            error!("Failed to fetch initial data for registration: {e}");
+                            app_data.report_error(tr!("リレーからデータを取得できませんでした: {}", e));
                         }
                         Ok(())
                     }.await;
                     if let Err(e) = registration_result {
-                        eprintln!("Failed to register new key: {e}");
+                        error!("Failed to register new key: {e}");
                         let client_to_shutdown = {
                             let mut app_data_in_task = cloned_app_data_arc.lock().unwrap();
                             app_data_in_task.nostr_client.take()
                         };
                         if let Some(client) = client_to_shutdown { client.shutdown().await; }
+                        cloned_app_data_arc.lock().unwrap().report_error(tr!("鍵の登録に失敗しました: {}", e));
                     }
                     let mut app_data_async = cloned_app_data_arc.lock().unwrap();
                     app_data_async.is_loading = false;
@@ -444,4 +929,59 @@ pub fn draw_login_view(
             }
         }
     });
+
+    ui.add_space(15.0);
+
+    // --- リモート署名者 (NIP-46) ---
+    let remote_signer_heading_text = tr!("リモート署名者でログイン (NIP-46)");
+    let remote_signer_description_text = tr!("bunker URI を使うと、秘密鍵をこのアプリに入力せずにログインできます。");
+    let bunker_uri_label_text = "bunker URI:";
+    let bunker_uri_hint_text = "bunker://...";
+    let connect_button_text = tr!("接続");
+    let reconnect_button_text = tr!("前回の署名者に再接続");
+    let forget_button_text = tr!("保存した接続を削除");
+    let open_auth_url_text = tr!("承認ページを開く");
+
+    ui.group(|ui| {
+        ui.heading(remote_signer_heading_text);
+        ui.label(remote_signer_description_text);
+        ui.add_space(10.0);
+        ui.horizontal(|ui| {
+            ui.label(bunker_uri_label_text);
+            ui.add(egui::TextEdit::singleline(&mut app_data.bunker_uri_input)
+                .password(true)
+                .hint_text(bunker_uri_hint_text));
+            let can_connect = !app_data.is_loading && !app_data.bunker_uri_input.trim().is_empty();
+            if ui.add_enabled(can_connect, egui::Button::new(connect_button_text)).clicked() {
+                let bunker_uri = app_data.bunker_uri_input.clone();
+                spawn_remote_signer_login(Some(bunker_uri), app_data, app_data_arc.clone(), runtime_handle.clone());
+            }
+        });
+        if bunker_file_path().exists() {
+            ui.horizontal(|ui| {
+                if ui.add_enabled(!app_data.is_loading, egui::Button::new(reconnect_button_text)).clicked() {
+                    spawn_remote_signer_login(None, app_data, app_data_arc.clone(), runtime_handle.clone());
+                }
+                if ui.add_enabled(!app_data.is_loading, egui::Button::new(forget_button_text)).clicked() {
+                    if let Err(e) = fs::remove_file(bunker_file_path()) {
+                        error!("Failed to remove bunker session: {e}");
+                        app_data.report_error(tr!("保存済みの接続情報を削除できませんでした: {}", e));
+                    }
+                    app_data.remote_signer_status = None;
+                }
+            });
+        }
+        if let Some(status) = &app_data.remote_signer_status {
+            ui.add_space(5.0);
+            ui.horizontal(|ui| {
+                if app_data.is_loading {
+                    ui.spinner();
+                }
+                ui.label(status);
+            });
+        }
+        if let Some(auth_url) = &app_data.bunker_auth_url {
+            ui.hyperlink_to(open_auth_url_text, auth_url);
+        }
+    });
 }
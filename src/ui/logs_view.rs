@@ -0,0 +1,74 @@
+use eframe::egui;
+use tracing::Level;
+
+use crate::{i18n::tr, logs, types::*};
+
+pub fn draw_logs_view(
+    ui: &mut egui::Ui,
+    ctx: &egui::Context,
+    app_data: &mut NostrStatusAppInternal,
+) {
+    let logs_heading_text = tr!("ログ");
+    let level_label_text = tr!("表示するレベル:");
+    let copy_button_text = tr!("クリップボードにコピー");
+    let clear_button_text = tr!("消去");
+    let no_logs_text = tr!("まだログはありません");
+    let logs_hint_text = tr!("不具合を報告するときは、コピーしたログを添えてください");
+
+    let card_frame = egui::Frame {
+        inner_margin: egui::Margin::same(12),
+        corner_radius: 8.0.into(),
+        shadow: eframe::epaint::Shadow::NONE,
+        fill: app_data.current_theme.card_background_color(),
+        ..Default::default()
+    };
+
+    let entries = logs::entries(app_data.log_level);
+
+    ui.heading(logs_heading_text);
+    ui.add_space(10.0);
+    ui.horizontal(|ui| {
+        ui.label(level_label_text);
+        egui::ComboBox::from_id_salt("log_level_combo")
+            .selected_text(app_data.log_level.as_str())
+            .show_ui(ui, |ui| {
+                for level in [Level::ERROR, Level::WARN, Level::INFO, Level::DEBUG] {
+                    ui.selectable_value(&mut app_data.log_level, level, level.as_str());
+                }
+            });
+        if ui.add_enabled(!entries.is_empty(), egui::Button::new(copy_button_text)).clicked() {
+            let text: Vec<String> = entries.iter().map(|entry| entry.to_line()).collect();
+            ctx.copy_text(text.join("\n"));
+            app_data.notify(tr!("ログをコピーしました"));
+        }
+        if ui.button(clear_button_text).clicked() {
+            logs::clear();
+        }
+    });
+    ui.small(logs_hint_text);
+    ui.add_space(10.0);
+
+    card_frame.show(ui, |ui| {
+        if entries.is_empty() {
+            ui.label(no_logs_text);
+            return;
+        }
+        egui::ScrollArea::both()
+            .id_salt("logs_scroll_area")
+            .stick_to_bottom(true)
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                for entry in &entries {
+                    let color = match entry.level {
+                        Level::ERROR => ui.visuals().error_fg_color,
+                        Level::WARN => ui.visuals().warn_fg_color,
+                        _ => ui.visuals().text_color(),
+                    };
+                    ui.label(egui::RichText::new(entry.to_line()).monospace().color(color));
+                }
+            });
+    });
+
+    // 新しいログが届いたら表示されるよう、タブを開いている間は定期的に描き直す
+    ctx.request_repaint_after(std::time::Duration::from_secs(1));
+}
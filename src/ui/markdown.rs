@@ -0,0 +1,236 @@
+use eframe::egui;
+use regex::Regex;
+use std::ops::Range;
+use std::sync::LazyLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::fonts;
+use crate::types::Settings;
+
+// 本文の簡単な書式 (太字・斜体・コード・箇条書き) を表示する
+// 書式の記号は隠して、その部分だけ見た目を変える。設定で切ると書いたままの本文を表示する
+
+static RENDER_MARKDOWN: AtomicBool = AtomicBool::new(true);
+static LINK_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(!?)\[([^\]\n]+)\]\(((?:https?://|nostr:)[^)\s]+)\)").unwrap());
+
+// 行の先頭にあれば箇条書きとして「•」で表示する
+const BULLET_MARKERS: [&str; 3] = ["- ", "* ", "+ "];
+const BULLET_TEXT: &str = "• ";
+// 前に \ を付けると記号のまま表示する
+const ESCAPABLE_CHARS: &str = "\\`*_-+";
+
+// [文字](URL) の形で書かれたリンク。![説明](URL) の画像もリンクとして扱う
+#[derive(Clone, Debug)]
+pub struct MarkdownLink {
+    pub range: Range<usize>,
+    pub label: String,
+    pub url: String,
+    pub is_image: bool,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct SpanStyle {
+    pub bold: bool,
+    pub italic: bool,
+    pub code: bool,
+}
+
+// 本文の text[range] を style で表示する。bullet なら範囲の文字 (「- 」) の代わりに「•」を表示する
+#[derive(Clone, Debug)]
+pub struct Span {
+    pub range: Range<usize>,
+    pub style: SpanStyle,
+    pub bullet: bool,
+}
+
+pub fn apply_markdown_settings(settings: &Settings) {
+    RENDER_MARKDOWN.store(settings.render_markdown, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    RENDER_MARKDOWN.load(Ordering::Relaxed)
+}
+
+// 書式を表示しない設定のときは探さない
+pub fn find_links(text: &str) -> Vec<MarkdownLink> {
+    if !is_enabled() {
+        return Vec::new();
+    }
+    LINK_REGEX
+        .captures_iter(text)
+        .map(|caps| MarkdownLink {
+            range: caps.get(0).unwrap().range(),
+            label: caps[2].trim().to_string(),
+            url: caps[3].to_string(),
+            is_image: !caps[1].is_empty(),
+        })
+        .collect()
+}
+
+fn prev_char(text: &str, i: usize) -> Option<char> {
+    text[..i].chars().next_back()
+}
+
+fn next_char(text: &str, i: usize) -> Option<char> {
+    text[i..].chars().next()
+}
+
+// _ は単語の途中では書式にしない (snake_case などを崩さない)
+fn is_word_marker(marker: &str) -> bool {
+    marker.starts_with('_')
+}
+
+// 1文字の記号が ** や __ の片方になっていないか
+fn is_doubled(text: &str, i: usize, marker: &str) -> bool {
+    let c = marker.chars().next().unwrap();
+    marker.len() == 1 && (prev_char(text, i) == Some(c) || next_char(text, i + 1) == Some(c))
+}
+
+fn can_open(text: &str, i: usize, marker: &str) -> bool {
+    if is_doubled(text, i, marker) {
+        return false;
+    }
+    if next_char(text, i + marker.len()).is_none_or(char::is_whitespace) {
+        return false;
+    }
+    !(is_word_marker(marker) && prev_char(text, i).is_some_and(char::is_alphanumeric))
+}
+
+fn can_close(text: &str, i: usize, marker: &str) -> bool {
+    if is_doubled(text, i, marker) {
+        return false;
+    }
+    if prev_char(text, i).is_none_or(char::is_whitespace) {
+        return false;
+    }
+    !(is_word_marker(marker) && next_char(text, i + marker.len()).is_some_and(char::is_alphanumeric))
+}
+
+fn is_protected(protected: &[Range<usize>], i: usize) -> bool {
+    protected.iter().any(|range| range.contains(&i))
+}
+
+// from より後ろで marker を閉じられる位置を探す
+fn find_closer(text: &str, from: usize, marker: &str, protected: &[Range<usize>]) -> Option<usize> {
+    text[from..].match_indices(marker).map(|(j, _)| from + j).find(|&j| {
+        !is_protected(protected, j) && (marker == "`" || can_close(text, j, marker))
+    })
+}
+
+fn push_span(spans: &mut Vec<Span>, range: Range<usize>, style: SpanStyle) {
+    if !range.is_empty() {
+        spans.push(Span { range, style, bullet: false });
+    }
+}
+
+// 本文を書式ごとの範囲に分ける。protected の範囲 (リンクや絵文字) の中の記号は書式として扱わない
+// 書式を表示しない設定のときは、本文全体を1つの範囲で返す
+pub fn parse(text: &str, protected: &[Range<usize>]) -> Vec<Span> {
+    if !is_enabled() {
+        return vec![Span { range: 0..text.len(), style: SpanStyle::default(), bullet: false }];
+    }
+    let mut spans = Vec::new();
+    let mut style = SpanStyle::default();
+    let mut bold_marker: Option<&str> = None;
+    let mut italic_marker: Option<&str> = None;
+    // 今の書式で、まだ spans に入れていない部分の始まり
+    let mut run_start = 0;
+    let mut line_start = true;
+    let mut i = 0;
+    while i < text.len() {
+        if let Some(range) = protected.iter().find(|range| range.start == i && !range.is_empty()) {
+            i = range.end;
+            line_start = false;
+            continue;
+        }
+        let rest = &text[i..];
+        if line_start {
+            line_start = false;
+            let indent = rest.len() - rest.trim_start_matches([' ', '\t']).len();
+            if BULLET_MARKERS.iter().any(|marker| rest[indent..].starts_with(marker)) {
+                push_span(&mut spans, run_start..i + indent, style);
+                spans.push(Span { range: i + indent..i + indent + 2, style, bullet: true });
+                i += indent + 2;
+                run_start = i;
+                continue;
+            }
+        }
+        let c = rest.chars().next().unwrap();
+        match c {
+            '\n' => line_start = true,
+            '\\' if next_char(text, i + 1).is_some_and(|next| ESCAPABLE_CHARS.contains(next)) => {
+                push_span(&mut spans, run_start..i, style);
+                run_start = i + 1;
+                i += 2;
+                continue;
+            }
+            '`' => {
+                if let Some(end) = find_closer(text, i + 1, "`", protected).filter(|&end| end > i + 1) {
+                    push_span(&mut spans, run_start..i, style);
+                    push_span(&mut spans, i + 1..end, SpanStyle { code: true, ..style });
+                    i = end + 1;
+                    run_start = i;
+                    continue;
+                }
+            }
+            '*' | '_' => {
+                let double = if c == '*' { "**" } else { "__" };
+                let single = if c == '*' { "*" } else { "_" };
+                let marker = if rest.starts_with(double) { double } else { single };
+                let (current, is_bold) = if marker.len() == 2 { (bold_marker, true) } else { (italic_marker, false) };
+                let toggled = match current {
+                    Some(open) if open == marker && can_close(text, i, marker) => Some(None),
+                    None if can_open(text, i, marker) && find_closer(text, i + marker.len(), marker, protected).is_some() => {
+                        Some(Some(marker))
+                    }
+                    _ => None,
+                };
+                if let Some(new_marker) = toggled {
+                    push_span(&mut spans, run_start..i, style);
+                    if is_bold {
+                        bold_marker = new_marker;
+                        style.bold = new_marker.is_some();
+                    } else {
+                        italic_marker = new_marker;
+                        style.italic = new_marker.is_some();
+                    }
+                    i += marker.len();
+                    run_start = i;
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        i += c.len_utf8();
+    }
+    push_span(&mut spans, run_start..text.len(), style);
+    spans
+}
+
+fn styled_text(text: &str, style: SpanStyle, color: egui::Color32) -> egui::RichText {
+    let rich_text = egui::RichText::new(text).color(color);
+    if style.code {
+        return rich_text.code();
+    }
+    let rich_text = if style.bold { rich_text.family(fonts::bold_font_family()) } else { rich_text };
+    if style.italic { rich_text.italics() } else { rich_text }
+}
+
+// spans のうち text[range] に入る部分を描く。horizontal_wrapped の中で呼ぶ
+pub fn draw_spans(ui: &mut egui::Ui, text: &str, spans: &[Span], range: Range<usize>, color: egui::Color32) {
+    for span in spans {
+        let start = span.range.start.max(range.start);
+        let end = span.range.end.min(range.end);
+        if start >= end {
+            continue;
+        }
+        if span.bullet {
+            if start == span.range.start {
+                ui.label(egui::RichText::new(BULLET_TEXT).color(color));
+            }
+            continue;
+        }
+        ui.label(styled_text(&text[start..end], span.style, color));
+    }
+}
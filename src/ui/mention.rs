@@ -0,0 +1,32 @@
+use eframe::egui;
+use nostr::PublicKey;
+use std::collections::{HashMap, HashSet};
+
+use crate::{note, types::ProfileMetadata};
+
+// 入力欄のすぐ下に、`@` に続けて入力した名前に合うフォロー中のユーザーを出す。選ぶと参照に置き換える
+pub fn draw_mention_popup(
+    ui: &egui::Ui,
+    input_response: &egui::Response,
+    input: &mut String,
+    followed: &HashSet<PublicKey>,
+    profiles: &HashMap<PublicKey, ProfileMetadata>,
+) {
+    let suggestions = note::mention_suggestions(input, followed, profiles);
+    if suggestions.is_empty() {
+        return;
+    }
+    egui::Area::new(input_response.id.with("mention_popup"))
+        .fixed_pos(input_response.rect.left_bottom())
+        .order(egui::Order::Foreground)
+        .show(ui.ctx(), |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                for (public_key, name) in suggestions {
+                    if ui.selectable_label(false, format!("@{name}")).clicked() {
+                        note::insert_mention(input, &public_key);
+                        input_response.request_focus();
+                    }
+                }
+            });
+        });
+}
@@ -0,0 +1,117 @@
+use eframe::egui;
+use nostr::PublicKey;
+use nostr::nips::nip05::{self, Nip05Address};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::error;
+
+use crate::cache_db::{DB_NIP05, LmdbCache};
+use crate::i18n::tr;
+use crate::network;
+use crate::types::{Nip05State, NostrStatusAppInternal};
+
+// .well-known/nostr.json を待つ時間
+const NIP05_TIMEOUT_SECS: u64 = 10;
+
+// 公開鍵と NIP-05 の組をキーにする。どちらかが変われば確認し直す
+fn cache_key(public_key: &PublicKey, nip05: &str) -> String {
+    format!("{public_key}:{nip05}")
+}
+
+// 名前の横に NIP-05 の確認結果を表示する。まだ確認していなければ to_verify に積む
+pub fn draw_nip05_badge(
+    ui: &mut egui::Ui,
+    nip05_cache: &HashMap<String, Nip05State>,
+    public_key: &PublicKey,
+    nip05: &str,
+    to_verify: &mut Vec<(PublicKey, String)>,
+) {
+    let verified_hover_text = tr!("NIP-05 で確認済み");
+    let mismatch_hover_text = tr!("NIP-05 の公開鍵がこのユーザーと一致しません");
+    let failed_hover_text = tr!("NIP-05 を確認できませんでした");
+    let checking_hover_text = tr!("NIP-05 を確認中");
+
+    if nip05.is_empty() {
+        return;
+    }
+    let (text, color, hover_text) = match nip05_cache.get(&cache_key(public_key, nip05)) {
+        Some(Nip05State::Verified) => (format!("✓ {nip05}"), egui::Color32::from_rgb(0x06, 0xC7, 0x55), verified_hover_text),
+        Some(Nip05State::Mismatch) => (format!("⚠ {nip05}"), ui.visuals().warn_fg_color, mismatch_hover_text),
+        Some(Nip05State::Failed) => (nip05.to_string(), egui::Color32::GRAY, failed_hover_text),
+        Some(Nip05State::Checking) => (nip05.to_string(), egui::Color32::GRAY, checking_hover_text),
+        None => {
+            to_verify.push((*public_key, nip05.to_string()));
+            (nip05.to_string(), egui::Color32::GRAY, checking_hover_text)
+        }
+    };
+    ui.label(egui::RichText::new(text).small().color(color)).on_hover_text(hover_text);
+}
+
+fn load_from_lmdb(cache_db: &LmdbCache, key: &str) -> Option<Nip05State> {
+    cache_db.read_cache::<Nip05State>(DB_NIP05, key).ok().map(|cache| cache.data)
+}
+
+fn fetch_nip05_state(public_key: &PublicKey, nip05: &str) -> Nip05State {
+    // "example.com" だけの場合は "_@example.com" として扱う (NIP-05)
+    let identifier = if nip05.contains('@') { nip05.to_string() } else { format!("_@{nip05}") };
+    let Ok(address) = Nip05Address::parse(&identifier) else {
+        return Nip05State::Failed;
+    };
+    let agent = network::http_agent(Duration::from_secs(NIP05_TIMEOUT_SECS));
+    let body = match agent.get(address.url().as_str()).call().map(|res| res.into_string()) {
+        Ok(Ok(body)) => body,
+        Ok(Err(e)) => {
+            error!("Failed to read the NIP-05 response for {nip05}: {e}");
+            return Nip05State::Failed;
+        }
+        Err(e) => {
+            error!("Failed to fetch the NIP-05 document for {nip05}: {e}");
+            return Nip05State::Failed;
+        }
+    };
+    match nip05::verify_from_raw_json(public_key, &address, &body) {
+        Ok(true) => Nip05State::Verified,
+        Ok(false) => Nip05State::Mismatch,
+        Err(e) => {
+            error!("Invalid NIP-05 document for {nip05}: {e}");
+            Nip05State::Failed
+        }
+    }
+}
+
+// まだ確認していない NIP-05 を確認する。結果は 24 時間キャッシュする
+pub fn verify_nip05s(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    to_verify: Vec<(PublicKey, String)>,
+) {
+    for (public_key, nip05) in to_verify {
+        let key = cache_key(&public_key, &nip05);
+        if app_data.nip05_cache.contains_key(&key) {
+            continue;
+        }
+        app_data.nip05_cache.insert(key.clone(), Nip05State::Checking);
+
+        let app_data_clone = app_data_arc.clone();
+        let cache_db = app_data.cache_db.clone();
+        runtime_handle.spawn_blocking(move || {
+            let state = match load_from_lmdb(&cache_db, &key) {
+                Some(state) => state,
+                None => {
+                    let state = fetch_nip05_state(&public_key, &nip05);
+                    // 通信の失敗は一時的なことが多いので、次に起動したときに確認し直す
+                    if state != Nip05State::Failed
+                        && let Err(e) = cache_db.write_cache(DB_NIP05, &key, &state) {
+                        error!("Failed to cache the NIP-05 result: {e}");
+                    }
+                    state
+                }
+            };
+            let mut app_data = app_data_clone.lock().unwrap();
+            app_data.nip05_cache.insert(key, state);
+            app_data.should_repaint = true;
+        });
+    }
+}
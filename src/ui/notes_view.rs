@@ -0,0 +1,392 @@
+use eframe::egui;
+use nostr::{Event, EventId};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use tracing::error;
+
+use crate::{
+    i18n::tr,
+    note::{content_warning, fetch_notes, publish_quote, publish_reply, repost, reposted_event, thread_refs},
+    types::*,
+    ui::{bookmarks_view, content_warning, image_cache, link_preview, link_view, mention, signature_badge, timestamp},
+    wot,
+};
+
+// 返信をこれより深くは字下げしない
+const MAX_THREAD_INDENT: usize = 4;
+
+fn spawn_fetch_notes(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+) {
+    let (Some(client), Some(signer)) = (app_data.nostr_client.clone(), app_data.my_signer.clone()) else {
+        return;
+    };
+    let mut authors = app_data.followed_pubkeys.iter().copied().collect::<Vec<_>>();
+    authors.push(signer.public_key());
+    let known_profiles: HashSet<_> = app_data.profiles.keys().copied().collect();
+    app_data.is_loading_notes = true;
+    app_data.should_repaint = true;
+    runtime_handle.spawn(async move {
+        let result = fetch_notes(&client, authors, known_profiles).await;
+        let mut app_data = app_data_arc.lock().unwrap();
+        match result {
+            Ok((notes, profiles)) => {
+                app_data.notes = notes;
+                app_data.profiles.extend(profiles);
+            }
+            Err(e) => {
+                error!("Failed to fetch notes: {e}");
+                app_data.report_error(tr!("ノートを取得できませんでした: {}", e));
+            }
+        }
+        app_data.is_loading_notes = false;
+        app_data.should_repaint = true;
+    });
+}
+
+pub fn spawn_repost(
+    app_data: &NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    event_id: EventId,
+) {
+    if let (Some(client), Some(signer)) = (app_data.nostr_client.clone(), app_data.my_signer.clone()) {
+        runtime_handle.spawn(repost(client, signer, event_id, app_data_arc));
+    }
+}
+
+// 引用のコメントを書くダイアログ。ホームとノートのどちらからでも開けるよう、ここで描く
+pub fn draw_quote_dialog(
+    ctx: &egui::Context,
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: tokio::runtime::Handle,
+) {
+    let quote_window_title_text = tr!("引用");
+    let quote_hint_text = tr!("コメントを書く");
+    let send_quote_button_text = tr!("投稿");
+    let cancel_button_text = tr!("キャンセル");
+
+    let Some(target) = app_data.quote_target.clone() else {
+        return;
+    };
+    egui::Window::new(quote_window_title_text)
+        .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.label(egui::RichText::new(tr!("「{}」", target.preview)).small().color(egui::Color32::GRAY));
+            let quote_response = ui.add(
+                egui::TextEdit::multiline(&mut app_data.quote_input)
+                    .desired_rows(3)
+                    .hint_text(quote_hint_text),
+            );
+            mention::draw_mention_popup(ui, &quote_response, &mut app_data.quote_input, &app_data.followed_pubkeys, &app_data.profiles);
+            ui.horizontal(|ui| {
+                let can_send = !app_data.quote_input.trim().is_empty() && !app_data.is_posting_note;
+                if ui.add_enabled(can_send, egui::Button::new(send_quote_button_text)).clicked()
+                    && let (Some(client), Some(signer)) = (app_data.nostr_client.clone(), app_data.my_signer.clone()) {
+                    let content = app_data.quote_input.clone();
+                    app_data.is_posting_note = true;
+                    let cloned_app_data_arc = app_data_arc.clone();
+                    runtime_handle.spawn(async move {
+                        let published = publish_quote(client, signer, content, target, cloned_app_data_arc.clone()).await;
+                        let mut data = cloned_app_data_arc.lock().unwrap();
+                        if published {
+                            data.quote_input.clear();
+                            data.quote_target = None;
+                        }
+                        data.is_posting_note = false;
+                        data.should_repaint = true;
+                    });
+                }
+                if ui.button(cancel_button_text).clicked() {
+                    app_data.quote_target = None;
+                    app_data.quote_input.clear();
+                }
+                if app_data.is_posting_note {
+                    ui.spinner();
+                }
+            });
+        });
+}
+
+// ノートのボタンから行う操作
+enum NoteAction {
+    Reply(Event),
+    Repost(EventId),
+    Quote(QuoteTarget),
+    ToggleBookmark(EventId),
+    OpenLink(NostrLink),
+    ToggleContentWarning(EventId),
+}
+
+// 描画中に集めて、描き終わってから読み込むもの
+#[derive(Default)]
+struct PendingLoads {
+    images: Vec<(String, ImageKind)>,
+    link_previews: Vec<String>,
+}
+
+// ノート1件と、その下に返信を折りたたんで描く
+fn draw_note(
+    ui: &mut egui::Ui,
+    app_data: &NostrStatusAppInternal,
+    event: &Event,
+    children: &HashMap<EventId, Vec<Event>>,
+    depth: usize,
+    action: &mut Option<NoteAction>,
+    pending: &mut PendingLoads,
+) {
+    let reply_button_text = tr!("返信");
+    let repost_button_text = "🔁";
+    let quote_button_text = tr!("引用");
+
+    // リポストは誰がリポストしたかを添えて元のノートを描く
+    if event.kind != nostr::Kind::TextNote {
+        let Some(inner) = reposted_event(event) else {
+            return;
+        };
+        let reposter = app_data
+            .profiles
+            .get(&event.pubkey)
+            .map(|metadata| metadata.display_name_or_npub(&event.pubkey))
+            .unwrap_or_else(|| short_npub(&event.pubkey));
+        ui.label(egui::RichText::new(tr!("🔁 {} がリポスト", reposter)).small().color(egui::Color32::GRAY));
+        draw_note(ui, app_data, &inner, children, depth, action, pending);
+        return;
+    }
+
+    let author_metadata = app_data.profiles.get(&event.pubkey).cloned().unwrap_or_default();
+    ui.horizontal(|ui| {
+        image_cache::draw_user_avatar(
+            ui,
+            &app_data.image_cache,
+            &event.pubkey,
+            &author_metadata.picture,
+            egui::vec2(24.0, 24.0),
+            4.0,
+            &mut pending.images,
+        );
+        if ui.link(egui::RichText::new(author_metadata.display_name_or_npub(&event.pubkey)).strong()).clicked() {
+            *action = Some(NoteAction::OpenLink(NostrLink::Profile(event.pubkey)));
+        }
+        timestamp::draw_timestamp(ui, event.created_at);
+        signature_badge::draw_signature_badge(ui, &event.id);
+        if ui.small_button(reply_button_text).clicked() {
+            *action = Some(NoteAction::Reply(event.clone()));
+        }
+        if ui.small_button(repost_button_text).on_hover_text(tr!("リポスト")).clicked() {
+            *action = Some(NoteAction::Repost(event.id));
+        }
+        if ui.small_button(quote_button_text).clicked() {
+            *action = Some(NoteAction::Quote(QuoteTarget {
+                event_id: event.id,
+                author: event.pubkey,
+                kind: event.kind,
+                preview: event.content.chars().take(50).collect(),
+            }));
+        }
+        if bookmarks_view::draw_bookmark_button(ui, app_data, event.id) {
+            *action = Some(NoteAction::ToggleBookmark(event.id));
+        }
+    });
+    let mut link_to_open: Option<NostrLink> = None;
+    let mut content_warning_toggle: Option<EventId> = None;
+    let content_warning = content_warning(event.tags.iter());
+    if content_warning::draw_content_warning(ui, &app_data.revealed_content_warnings, event.id, content_warning.as_deref(), &mut content_warning_toggle) {
+        ui.horizontal_wrapped(|ui| {
+            link_view::draw_text_with_links(ui, &event.content, ui.visuals().text_color(), &app_data.profiles, &mut link_to_open);
+        });
+        link_preview::draw_previews(ui, app_data, &event.content, event.tags.iter(), &mut pending.images, &mut pending.link_previews);
+    }
+    if let Some(link) = link_to_open {
+        *action = Some(NoteAction::OpenLink(link));
+    }
+    if let Some(event_id) = content_warning_toggle {
+        *action = Some(NoteAction::ToggleContentWarning(event_id));
+    }
+
+    let Some(replies) = children.get(&event.id) else {
+        return;
+    };
+    // フォロー中の人に近い人の返信を先に並べ、つながりのない人の返信は畳んでおく
+    let mut sorted_replies: Vec<&Event> = replies.iter().collect();
+    sorted_replies.sort_by_key(|reply| wot::trust_level(app_data, &reply.pubkey));
+    let (stranger_replies, known_replies): (Vec<&Event>, Vec<&Event>) =
+        sorted_replies.into_iter().partition(|reply| wot::is_stranger(app_data, &reply.pubkey));
+    egui::CollapsingHeader::new(tr!("返信 {}件", replies.len()))
+        .id_salt(("note_thread", event.id))
+        .show(ui, |ui| {
+            for reply in known_replies {
+                draw_reply(ui, app_data, reply, children, depth, action, pending);
+            }
+            if !stranger_replies.is_empty() {
+                egui::CollapsingHeader::new(tr!("知らない人の返信 {}件", stranger_replies.len()))
+                    .id_salt(("note_stranger_replies", event.id))
+                    .default_open(false)
+                    .show(ui, |ui| {
+                        for reply in stranger_replies {
+                            draw_reply(ui, app_data, reply, children, depth, action, pending);
+                        }
+                    });
+            }
+        });
+}
+
+fn draw_reply(
+    ui: &mut egui::Ui,
+    app_data: &NostrStatusAppInternal,
+    reply: &Event,
+    children: &HashMap<EventId, Vec<Event>>,
+    depth: usize,
+    action: &mut Option<NoteAction>,
+    pending: &mut PendingLoads,
+) {
+    if depth < MAX_THREAD_INDENT {
+        ui.indent(("note_reply", reply.id), |ui| {
+            draw_note(ui, app_data, reply, children, depth + 1, action, pending);
+        });
+    } else {
+        draw_note(ui, app_data, reply, children, depth + 1, action, pending);
+    }
+    ui.add_space(5.0);
+}
+
+pub fn draw_notes_view(
+    ui: &mut egui::Ui,
+    ctx: &egui::Context,
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: tokio::runtime::Handle,
+) {
+    let notes_heading_text = tr!("ノート");
+    let refresh_button_text = tr!("更新");
+    let no_notes_text = tr!("フォロー中のユーザーのノートはまだありません。");
+    let reply_hint_text = tr!("返信を書く");
+    let send_reply_button_text = tr!("返信する");
+    let cancel_button_text = tr!("キャンセル");
+    let mut pending = PendingLoads::default();
+
+    let card_frame = egui::Frame {
+        inner_margin: egui::Margin::same(12),
+        corner_radius: 8.0.into(),
+        shadow: eframe::epaint::Shadow::NONE,
+        fill: app_data.current_theme.card_background_color(),
+        ..Default::default()
+    };
+
+    // 初回表示時に取得する
+    if !app_data.notes_loaded && app_data.nostr_client.is_some() {
+        app_data.notes_loaded = true;
+        spawn_fetch_notes(app_data, app_data_arc.clone(), &runtime_handle);
+    }
+
+    ui.horizontal(|ui| {
+        ui.heading(notes_heading_text);
+        if ui.add_enabled(!app_data.is_loading_notes, egui::Button::new(refresh_button_text)).clicked() {
+            spawn_fetch_notes(app_data, app_data_arc.clone(), &runtime_handle);
+        }
+        if app_data.is_loading_notes {
+            ui.spinner();
+        }
+    });
+    ui.add_space(10.0);
+
+    // --- 返信の入力 ---
+    if let Some(target) = app_data.reply_target.clone() {
+        card_frame.show(ui, |ui| {
+            let target_name = app_data
+                .profiles
+                .get(&target.pubkey)
+                .map(|metadata| metadata.display_name_or_npub(&target.pubkey))
+                .unwrap_or_else(|| short_npub(&target.pubkey));
+            let preview: String = target.content.chars().take(50).collect();
+            ui.label(egui::RichText::new(tr!("{} への返信: {}", target_name, preview)).small());
+            let reply_response = ui.add(
+                egui::TextEdit::multiline(&mut app_data.reply_input)
+                    .desired_rows(3)
+                    .desired_width(f32::INFINITY)
+                    .hint_text(reply_hint_text),
+            );
+            mention::draw_mention_popup(ui, &reply_response, &mut app_data.reply_input, &app_data.followed_pubkeys, &app_data.profiles);
+            ui.horizontal(|ui| {
+                let can_send = !app_data.reply_input.trim().is_empty() && !app_data.is_posting_note;
+                if ui.add_enabled(can_send, egui::Button::new(send_reply_button_text)).clicked()
+                    && let (Some(client), Some(signer)) = (app_data.nostr_client.clone(), app_data.my_signer.clone()) {
+                    let content = app_data.reply_input.clone();
+                    app_data.is_posting_note = true;
+                    let cloned_app_data_arc = app_data_arc.clone();
+                    runtime_handle.spawn(async move {
+                        let published = publish_reply(client, signer, content, target, cloned_app_data_arc.clone()).await;
+                        let mut data = cloned_app_data_arc.lock().unwrap();
+                        if published {
+                            data.reply_input.clear();
+                            data.reply_target = None;
+                        }
+                        data.is_posting_note = false;
+                        data.should_repaint = true;
+                    });
+                }
+                if ui.button(cancel_button_text).clicked() {
+                    app_data.reply_target = None;
+                    app_data.reply_input.clear();
+                }
+                if app_data.is_posting_note {
+                    ui.spinner();
+                }
+            });
+        });
+        ui.add_space(10.0);
+    }
+
+    // 返信先が取得済みのノートは、その下にまとめる
+    let ids: HashSet<EventId> = app_data.notes.iter().map(|event| event.id).collect();
+    let mut roots: Vec<Event> = Vec::new();
+    let mut children: HashMap<EventId, Vec<Event>> = HashMap::new();
+    // ミュートする語を含むノートは返信ごと表示しない
+    for event in app_data.notes.iter().filter(|event| !app_data.settings.is_muted(&event.content)) {
+        // リポストの e タグは返信先ではない
+        let parent = if event.kind == nostr::Kind::TextNote { thread_refs(event).1 } else { None };
+        match parent {
+            Some(parent) if ids.contains(&parent) && parent != event.id => {
+                children.entry(parent).or_default().push(event.clone());
+            }
+            _ => roots.push(event.clone()),
+        }
+    }
+    for replies in children.values_mut() {
+        replies.sort_by_key(|event| event.created_at);
+    }
+
+    let mut action: Option<NoteAction> = None;
+    if roots.is_empty() {
+        ui.label(no_notes_text);
+    } else {
+        egui::ScrollArea::vertical().id_salt("notes_scroll_area").show(ui, |ui| {
+            for event in &roots {
+                card_frame.show(ui, |ui| {
+                    ui.set_width(ui.available_width());
+                    draw_note(ui, app_data, event, &children, 0, &mut action, &mut pending);
+                });
+                ui.add_space(8.0);
+            }
+        });
+    }
+    match action {
+        Some(NoteAction::Reply(target)) => app_data.reply_target = Some(target),
+        Some(NoteAction::Repost(event_id)) => spawn_repost(app_data, app_data_arc.clone(), &runtime_handle, event_id),
+        Some(NoteAction::Quote(target)) => app_data.quote_target = Some(target),
+        Some(NoteAction::ToggleBookmark(event_id)) => bookmarks_view::spawn_toggle_bookmark(app_data, app_data_arc.clone(), &runtime_handle, event_id),
+        Some(NoteAction::OpenLink(link)) => link_view::open_link(app_data, app_data_arc.clone(), &runtime_handle, link),
+        Some(NoteAction::ToggleContentWarning(event_id)) => {
+            content_warning::toggle_revealed(&mut app_data.revealed_content_warnings, Some(event_id));
+        }
+        None => {}
+    }
+
+    link_preview::fetch_link_previews(app_data, app_data_arc.clone(), &runtime_handle, pending.link_previews);
+    image_cache::load_images(ctx, app_data, app_data_arc, &runtime_handle, pending.images);
+}
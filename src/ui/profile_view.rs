@@ -1,14 +1,25 @@
-use eframe::egui::{self, Sense};
+use eframe::egui;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use nostr::{EventBuilder, Kind, nips::nip19::ToBech32};
+use nostr::{EventBuilder, Kind, PublicKey, nips::nip19::ToBech32};
+use tracing::{error, info};
+use zeroize::{Zeroize, Zeroizing};
 
 use crate::{
+    accounts::{self, read_config, write_config},
     cache_db::DB_PROFILES,
+    i18n::tr,
+    key_store,
+    profile_card,
     types::*,
-    ui::image_cache,
+    nip39,
+    ui::{identicon, identity_badge, image_cache, qr},
 };
 
+// 書き出した秘密鍵を表示しておく秒数
+const EXPORTED_KEY_DISPLAY_SECS: u64 = 60;
+
 pub fn draw_profile_view(
     ui: &mut egui::Ui,
     ctx: &egui::Context,
@@ -18,8 +29,20 @@ pub fn draw_profile_view(
 ) {
     let mut urls_to_load: Vec<(String, ImageKind)> = Vec::new();
 
-    let save_profile_button_text = "プロフィールを保存";
-    let logout_button_text = "ログアウト";
+    let save_profile_button_text = tr!("プロフィールを保存");
+    let logout_button_text = tr!("ログアウト");
+    let signer_label_text = tr!("署名方式");
+    let remote_signer_text = tr!("リモート署名者 (NIP-46)");
+    let local_key_signer_text = tr!("このアプリに保存した秘密鍵");
+    let check_connection_button_text = tr!("接続状態を確認");
+    let copy_ncryptsec_button_text = tr!("暗号化した秘密鍵 (ncryptsec) をコピー");
+    let key_storage_label_text = tr!("鍵の保存先:");
+    let key_storage_file_text = tr!("設定ファイル");
+    let key_storage_keyring_text = tr!("OSのキーチェーン");
+    let export_secret_key_button_text = tr!("秘密鍵 (nsec) を書き出す");
+    let qr_hint_text = tr!("相手のアプリでこの QR コードを読み取ると、友だちに追加できます");
+    let save_profile_card_button_text = tr!("プロフィールカードを PNG で保存");
+    let identities_label_text = tr!("外部アカウント (NIP-39)");
 
     let card_frame = |ui: &egui::Ui| egui::Frame {
         inner_margin: egui::Margin::same(12),
@@ -35,43 +58,23 @@ pub fn draw_profile_view(
             ui.add_space(20.0);
             // --- New Profile Header ---
             ui.horizontal(|ui| {
-                let avatar_size_val = 80.0;
-                let (avatar_rect, _) =
-                    ui.allocate_exact_size(egui::vec2(avatar_size_val, avatar_size_val), Sense::hover());
-
-                let picture_url = &app_data.editable_profile.picture;
-                if !picture_url.is_empty() {
-                    let image_state = app_data.image_cache.get(picture_url).cloned();
-                    match image_state {
-                        Some(ImageState::Loaded(texture_handle)) => {
-                            let image_widget = egui::Image::new(&texture_handle)
-                                .sense(Sense::hover())
-                                .fit_to_exact_size(avatar_rect.size())
-                                .corner_radius(12.0);
-                            ui.put(avatar_rect, image_widget);
-                        }
-                        _ => {
-                            if !urls_to_load.iter().any(|(u, _)| u == picture_url) {
-                                urls_to_load
-                                    .push((picture_url.clone(), ImageKind::ProfilePicture));
-                            }
-                            ui.painter().rect_filled(
-                                avatar_rect,
-                                12.0,
-                                ui.style().visuals.extreme_bg_color,
-                            );
-                            ui.put(
-                                avatar_rect.shrink(avatar_size_val * 0.4),
-                                egui::Spinner::new(),
-                            );
-                        }
+                let profile_picture_size = egui::vec2(80.0, 80.0);
+                match app_data.my_signer.as_ref().map(|signer| signer.public_key()) {
+                    Some(public_key) if app_data.editable_profile.picture.is_empty() => {
+                        let (rect, _) = ui.allocate_exact_size(profile_picture_size, egui::Sense::hover());
+                        identicon::paint_identicon(ui.painter(), rect, 12.0, &public_key);
+                    }
+                    _ => {
+                        image_cache::draw_avatar(
+                            ui,
+                            &app_data.image_cache,
+                            &app_data.editable_profile.picture,
+                            ImageKind::ProfilePicture,
+                            profile_picture_size,
+                            12.0,
+                            &mut urls_to_load,
+                        );
                     }
-                } else {
-                    ui.painter().rect_filled(
-                        avatar_rect,
-                        12.0,
-                        ui.style().visuals.extreme_bg_color,
-                    );
                 }
 
                 ui.add_space(15.0);
@@ -95,7 +98,7 @@ pub fn draw_profile_view(
 
             // --- Profile Information Card ---
             card_frame(ui).show(ui, |ui| {
-                ui.heading("プロフィール情報");
+                ui.heading(tr!("プロフィール情報"));
                 ui.add_space(10.0);
 
                 egui::Grid::new("profile_grid")
@@ -103,17 +106,17 @@ pub fn draw_profile_view(
                     .spacing([20.0, 10.0])
                     .striped(true)
                     .show(ui, |ui| {
-                        ui.label("名前:");
+                        ui.label(tr!("名前:"));
                         ui.text_edit_singleline(&mut app_data.editable_profile.name);
                         ui.end_row();
 
-                        ui.label("自己紹介:");
+                        ui.label(tr!("自己紹介:"));
                         ui.add(egui::TextEdit::multiline(&mut app_data.editable_profile.about)
                             .desired_rows(3)
                             .desired_width(f32::INFINITY));
                         ui.end_row();
 
-                        ui.label("画像URL:");
+                        ui.label(tr!("画像URL:"));
                         ui.text_edit_singleline(&mut app_data.editable_profile.picture);
                         ui.end_row();
 
@@ -126,15 +129,22 @@ pub fn draw_profile_view(
                         ui.end_row();
                     });
 
+                ui.add_space(10.0);
+                ui.label(egui::RichText::new(identities_label_text).strong());
+                let public_key = app_data.my_signer.as_ref().map(|signer| signer.public_key());
+                identity_badge::draw_identity_editor(ui, public_key, &mut app_data.my_identities);
+
                 ui.add_space(15.0);
                 ui.horizontal(|ui| {
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                          let save_button = egui::Button::new(egui::RichText::new(save_profile_button_text).strong());
                          if ui.add_enabled(!app_data.is_loading, save_button).clicked() {
                             let client_clone = app_data.nostr_client.as_ref().unwrap().clone();
-                            let keys_clone = app_data.my_keys.clone().unwrap();
+                            let keys_clone = app_data.my_signer.clone().unwrap();
                             let editable_profile_clone = app_data.editable_profile.clone();
+                            let identity_tags = nip39::identity_tags(&app_data.my_identities);
                             let cache_db_clone = app_data.cache_db.clone();
+                            let session = app_data.session_generation;
 
                             app_data.is_loading = true;
                             app_data.should_repaint = true;
@@ -145,24 +155,27 @@ pub fn draw_profile_view(
                                     let profile_content = serde_json::to_string(&editable_profile_clone)?;
 
                                     let event = EventBuilder::new(Kind::Metadata, profile_content.clone())
+                                        .tags(identity_tags)
                                         .sign(&keys_clone)
                                         .await?;
 
                                     match client_clone.send_event(&event).await {
                                         Ok(event_id) => {
-                                            println!("NIP-01 profile published with event id: {event_id:?}");
+                                            info!("NIP-01 profile published with event id: {event_id:?}");
                                             let pubkey_hex = keys_clone.public_key().to_string();
                                             if let Err(e) = cache_db_clone.write_cache(DB_PROFILES, &pubkey_hex, &editable_profile_clone) {
-                                                eprintln!("Failed to write profile cache: {e}");
+                                                error!("Failed to write profile cache: {e}");
                                             }
 
                                             let mut app_data_async = cloned_app_data_arc.lock().unwrap();
-                                            app_data_async.profile_fetch_status = "プロフィールを保存しました！".to_string();
+                                            app_data_async.profile_fetch_status = tr!("プロフィールを保存しました！").to_string();
+                                            app_data_async.notify(tr!("プロフィールを保存しました"));
                                             app_data_async.nip01_profile_display = serde_json::to_string_pretty(&serde_json::from_str::<serde_json::Value>(&profile_content)?)?;
                                         }
                                         Err(e) => {
                                             let mut app_data_async = cloned_app_data_arc.lock().unwrap();
-                                            app_data_async.profile_fetch_status = format!("プロフィールの保存に失敗しました: {e}");
+                                            app_data_async.profile_fetch_status = tr!("プロフィールの保存に失敗しました: {}", e);
+                                            app_data_async.report_error(tr!("プロフィールの保存に失敗しました: {}", e));
                                         }
                                     }
                                     Ok(())
@@ -170,10 +183,14 @@ pub fn draw_profile_view(
 
                                 if let Err(e) = result {
                                     let mut app_data_async = cloned_app_data_arc.lock().unwrap();
-                                    app_data_async.profile_fetch_status = format!("プロフィールの保存中にエラー: {e}");
+                                    app_data_async.profile_fetch_status = tr!("プロフィールの保存中にエラー: {}", e);
+                                    app_data_async.report_error(tr!("プロフィールの保存中にエラー: {}", e));
                                 }
 
                                 let mut app_data_async = cloned_app_data_arc.lock().unwrap();
+                                if !app_data_async.is_current_session(session) {
+                                    return;
+                                }
                                 app_data_async.is_loading = false;
                                 app_data_async.should_repaint = true;
                             });
@@ -201,129 +218,260 @@ pub fn draw_profile_view(
                 ..Default::default()
             };
             danger_frame.show(ui, |ui| {
-                ui.heading("公開鍵とログアウト");
+                ui.heading(tr!("公開鍵とログアウト"));
                 ui.add_space(10.0);
 
-                ui.label("あなたの公開鍵 (npub)");
-                let public_key_bech32 = app_data.my_keys.as_ref().map_or("N/A".to_string(), |k| k.public_key().to_bech32().unwrap_or_default());
+                ui.label(tr!("あなたの公開鍵 (npub)"));
+                let public_key_bech32 = app_data.my_signer.as_ref().map_or("N/A".to_string(), |k| k.public_key().to_bech32().unwrap_or_default());
                 ui.horizontal(|ui| {
-                    ui.text_edit_singleline(&mut public_key_bech32.clone()).on_hover_text("クリックしてコピー");
-                    if ui.button("コピー").clicked() {
-                        ctx.copy_text(public_key_bech32);
+                    ui.text_edit_singleline(&mut public_key_bech32.clone()).on_hover_text(tr!("クリックしてコピー"));
+                    if ui.button(tr!("コピー")).clicked() {
+                        ctx.copy_text(public_key_bech32.clone());
                     }
                 });
+                if let Some(public_key) = app_data.my_signer.as_ref().map(|signer| signer.public_key()) {
+                    ui.add_space(5.0);
+                    // NIP-21 の URI にしておくと、読み取ったアプリがそのままプロフィールを開ける
+                    if let Err(e) = qr::draw_qr_code(ui, &format!("nostr:{public_key_bech32}"), 180.0) {
+                        error!("Failed to draw the npub QR code: {e}");
+                    }
+                    ui.small(qr_hint_text);
+                    if ui.add_enabled(!app_data.is_saving_profile_card, egui::Button::new(save_profile_card_button_text)).clicked() {
+                        spawn_save_profile_card(app_data, app_data_arc.clone(), &runtime_handle, public_key);
+                    }
+                }
+
+                ui.add_space(10.0);
+                ui.label(signer_label_text);
+                match app_data.my_signer.as_ref().and_then(|signer| signer.remote()).cloned() {
+                    Some(remote) => {
+                        ui.horizontal(|ui| {
+                            ui.label(remote_signer_text);
+                            if ui.button(check_connection_button_text).clicked() {
+                                let app_data_arc_clone = app_data_arc.clone();
+                                runtime_handle.spawn(async move {
+                                    let status = remote.status().await;
+                                    let connected = status.values().filter(|s| matches!(s, nostr_sdk::RelayStatus::Connected)).count();
+                                    let mut app_data = app_data_arc_clone.lock().unwrap();
+                                    app_data.remote_signer_status = Some(tr!("署名者のリレーに接続中: {}/{}", connected, status.len()));
+                                    app_data.should_repaint = true;
+                                });
+                            }
+                        });
+                        if let Some(remote) = app_data.my_signer.as_ref().and_then(|signer| signer.remote()) {
+                            for relay in remote.relays() {
+                                ui.small(relay.to_string());
+                            }
+                        }
+                        if let Some(status) = &app_data.remote_signer_status {
+                            ui.label(status);
+                        }
+                    }
+                    None => {
+                        ui.horizontal(|ui| {
+                            ui.label(local_key_signer_text);
+                            // 保存済みの暗号化鍵は NIP-49 形式なので、そのまま他のクライアントに取り込める
+                            if ui.button(copy_ncryptsec_button_text).clicked() {
+                                let encrypted_secret_key = read_config().and_then(|config| {
+                                    key_store::load_encrypted_secret_key(config.active().ok_or(tr!("アカウントが見つかりません"))?)
+                                });
+                                match encrypted_secret_key {
+                                    Ok(encrypted_secret_key) => ctx.copy_text(encrypted_secret_key),
+                                    Err(e) => {
+                                        error!("Failed to read the encrypted key for export: {e}");
+                                        app_data.report_error(tr!("暗号化された鍵を読み出せませんでした: {}", e));
+                                    }
+                                }
+                            }
+                            if ui.button(export_secret_key_button_text).clicked() {
+                                app_data.show_export_key_dialog = true;
+                                app_data.export_key_error = None;
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label(key_storage_label_text);
+                            let mut selected_storage = app_data.key_storage;
+                            ui.radio_value(&mut selected_storage, KeyStorage::File, key_storage_file_text);
+                            ui.radio_value(&mut selected_storage, KeyStorage::Keyring, key_storage_keyring_text);
+                            if selected_storage != app_data.key_storage {
+                                let result = read_config().and_then(|mut config| {
+                                    key_store::change_key_storage(config.active_mut().ok_or(tr!("アカウントが見つかりません"))?, selected_storage)?;
+                                    write_config(&config)
+                                });
+                                match result {
+                                    Ok(()) => {
+                                        app_data.key_storage = selected_storage;
+                                        app_data.key_storage_error = None;
+                                    }
+                                    Err(e) => app_data.key_storage_error = Some(tr!("保存先を変更できませんでした: {}", e)),
+                                }
+                            }
+                        });
+                        if let Some(error) = &app_data.key_storage_error {
+                            ui.colored_label(egui::Color32::RED, error);
+                        }
+                    }
+                }
 
                 ui.add_space(20.0);
                 ui.separator();
                 ui.add_space(20.0);
 
                 if ui.button(egui::RichText::new(logout_button_text).color(egui::Color32::RED).strong()).clicked() {
-                    let client_to_shutdown = app_data.nostr_client.take();
-
-                    app_data.is_logged_in = false;
-                    app_data.my_keys = None;
-                    app_data.followed_pubkeys.clear();
-                    app_data.followed_pubkeys_display.clear();
-                    app_data.timeline_posts.clear();
-                    app_data.status_message_input.clear();
-                    app_data.passphrase_input.clear();
-                    app_data.confirm_passphrase_input.clear();
-                    app_data.secret_key_input.clear();
-                    app_data.current_tab = AppTab::Home;
-                    app_data.nip01_profile_display.clear();
-                    app_data.editable_profile = ProfileMetadata::default();
-                    app_data.profile_fetch_status = "ログインしてください".to_string();
-                    app_data.should_repaint = true;
-                    println!("Logged out.");
-
-                    if let Some(client) = client_to_shutdown {
-                        runtime_handle.spawn(async move {
-                            client.shutdown().await;
-                        });
-                    }
+                    accounts::logout(app_data, &runtime_handle);
                 }
             });
         });
 
 
     // --- Image Loading Logic ---
-    let cache_db = app_data.cache_db.clone();
-    let mut still_to_load = Vec::new();
-    for (url_key, kind) in urls_to_load {
-        if let Some(image_bytes) = image_cache::load_from_lmdb(&cache_db, &url_key) {
-            if let Ok(mut dynamic_image) = image::load_from_memory(&image_bytes) {
-                let (width, height) = match kind {
-                    ImageKind::Avatar => (32, 32), // Not used here, but for consistency
-                    ImageKind::Emoji => (20, 20),
-                    ImageKind::ProfilePicture => (100, 100),
-                };
-                dynamic_image = dynamic_image.thumbnail(width, height);
-                let color_image = egui::ColorImage::from_rgba_unmultiplied(
-                    [dynamic_image.width() as usize, dynamic_image.height() as usize],
-                    dynamic_image.to_rgba8().as_flat_samples().as_slice(),
-                );
-                let texture_handle = ctx.load_texture(
-                    &url_key,
-                    color_image,
-                    Default::default()
-                );
-                app_data.image_cache.insert(url_key, ImageState::Loaded(texture_handle));
-            } else {
-                app_data.image_cache.insert(url_key, ImageState::Failed);
+    image_cache::load_images(ctx, app_data, app_data_arc, &runtime_handle, urls_to_load);
+}
+
+// 名前・アイコン・QR コードを1枚の画像にしてダウンロードフォルダに保存する
+fn spawn_save_profile_card(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    public_key: PublicKey,
+) {
+    let Ok(npub) = public_key.to_bech32();
+    let name = app_data.editable_profile.display_name_or_npub(&public_key);
+    let picture = app_data.editable_profile.picture.clone();
+    let cache_db = app_data.image_cache_db.clone();
+    app_data.is_saving_profile_card = true;
+    runtime_handle.spawn_blocking(move || {
+        // アイコンはプロフィールを表示したときにキャッシュしたものを使う
+        let avatar_bytes = if picture.is_empty() { None } else { image_cache::load_from_lmdb(&cache_db, &picture) };
+        let result = profile_card::save_profile_card(&name, &npub, avatar_bytes.as_deref());
+        let mut app_data = app_data_arc.lock().unwrap();
+        app_data.is_saving_profile_card = false;
+        match result {
+            Ok(path) => app_data.notify(tr!("{} に保存しました", path.display())),
+            Err(e) => {
+                error!("Failed to save the profile card: {e}");
+                app_data.report_error(tr!("プロフィールカードを保存できませんでした: {}", e));
             }
-        } else {
-            still_to_load.push((url_key, kind));
         }
+    });
+}
+
+fn close_export_key_dialog(app_data: &mut NostrStatusAppInternal) {
+    app_data.show_export_key_dialog = false;
+    app_data.export_key_passphrase_input.zeroize();
+    app_data.export_key_error = None;
+    app_data.exported_secret_key = None;
+}
+
+// パスフレーズを入れ直してもらってから、秘密鍵を nsec の文字列と QR コードで表示する
+// 表示したままにならないよう、EXPORTED_KEY_DISPLAY_SECS 秒たったら消す
+// ほかのタブに移っても時間どおりに消えるよう、ここではなく ui.rs から毎フレーム呼ぶ
+pub fn draw_export_key_dialog(
+    ctx: &egui::Context,
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+) {
+    let window_title_text = tr!("秘密鍵を書き出す");
+    let passphrase_prompt_text = tr!("秘密鍵を表示するには、パスフレーズを入力してください");
+    let passphrase_label_text = tr!("パスフレーズ:");
+    let show_button_text = tr!("表示する");
+    let cancel_button_text = tr!("キャンセル");
+    let exporting_text = tr!("確認中...");
+    let warning_text = tr!("この秘密鍵を知っている人は、あなたとして投稿やトークができます。ほかの人に見せないでください。");
+    let qr_hint_text = tr!("モバイルの署名アプリで読み取れます");
+    let hide_button_text = tr!("隠す");
+    let hide_countdown_text = tr!("秒後に隠します");
+
+    if !app_data.show_export_key_dialog {
+        return;
+    }
+    if app_data.exported_secret_key.as_ref().is_some_and(|exported| Instant::now() >= exported.hide_at) {
+        info!("Hiding the exported secret key");
+        close_export_key_dialog(app_data);
+        return;
     }
+    let Some(public_key) = app_data.my_signer.as_ref().map(|signer| signer.public_key()) else {
+        return;
+    };
+
+    let mut open = true;
+    let mut close = false;
+    egui::Window::new(window_title_text)
+        .open(&mut open)
+        .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            if let Some(exported) = &app_data.exported_secret_key {
+                ui.label(egui::RichText::new(warning_text).strong().color(app_data.current_theme.danger_zone_stroke_color()));
+                ui.add_space(5.0);
+                ui.add(egui::TextEdit::singleline(&mut exported.nsec.as_str()).desired_width(420.0));
+                ui.add_space(5.0);
+                if let Err(e) = qr::draw_qr_code(ui, &exported.nsec, 240.0) {
+                    error!("Failed to draw the secret key QR code: {e}");
+                }
+                ui.small(qr_hint_text);
+                ui.add_space(5.0);
+                let remaining = exported.hide_at.saturating_duration_since(Instant::now());
+                ui.horizontal(|ui| {
+                    ui.label(format!("{} {hide_countdown_text}", remaining.as_secs() + 1));
+                    if ui.button(hide_button_text).clicked() {
+                        close = true;
+                    }
+                });
+                // 残り時間の表示を進めるため、1秒ごとに描き直す
+                ctx.request_repaint_after(Duration::from_secs(1));
+                return;
+            }
 
-    let data_clone = app_data_arc.clone();
-    for (url_key, kind) in still_to_load {
-        app_data.image_cache.insert(url_key.clone(), ImageState::Loading);
-        app_data.should_repaint = true;
-
-        let app_data_clone = data_clone.clone();
-        let ctx_clone = ctx.clone();
-        let cache_db_for_fetch = app_data.cache_db.clone();
-        let request = ehttp::Request::get(&url_key);
-
-        ehttp::fetch(request, move |result| {
-            let new_state = match result {
-                Ok(response) => {
-                    if response.ok {
-                        image_cache::save_to_lmdb(&cache_db_for_fetch, &response.url, &response.bytes);
-
-                        match image::load_from_memory(&response.bytes) {
-                            Ok(mut dynamic_image) => {
-                                let (width, height) = match kind {
-                                    ImageKind::Avatar => (32, 32),
-                                    ImageKind::Emoji => (20, 20),
-                                    ImageKind::ProfilePicture => (100, 100),
-                                };
-                                dynamic_image = dynamic_image.thumbnail(width, height);
-
-                                let color_image = egui::ColorImage::from_rgba_unmultiplied(
-                                    [dynamic_image.width() as usize, dynamic_image.height() as usize],
-                                    dynamic_image.to_rgba8().as_flat_samples().as_slice(),
-                                );
-                                let texture_handle = ctx_clone.load_texture(
-                                    &response.url,
-                                    color_image,
-                                    Default::default()
-                                );
-                                ImageState::Loaded(texture_handle)
+            ui.label(passphrase_prompt_text);
+            ui.horizontal(|ui| {
+                ui.label(passphrase_label_text);
+                ui.add(egui::TextEdit::singleline(&mut app_data.export_key_passphrase_input).password(true));
+            });
+            if let Some(error) = &app_data.export_key_error {
+                ui.colored_label(egui::Color32::RED, error);
+            }
+            ui.add_space(5.0);
+            ui.horizontal(|ui| {
+                if app_data.is_exporting_key {
+                    ui.spinner();
+                    ui.label(exporting_text);
+                    return;
+                }
+                if ui.button(egui::RichText::new(show_button_text).strong()).clicked() {
+                    let passphrase = Zeroizing::new(std::mem::take(&mut app_data.export_key_passphrase_input));
+                    app_data.is_exporting_key = true;
+                    app_data.export_key_error = None;
+                    let app_data_arc = app_data_arc.clone();
+                    // scrypt での復号に時間がかかるので、画面を止めないよう別のスレッドで行う
+                    runtime_handle.spawn_blocking(move || {
+                        let result = accounts::export_secret_key(public_key, &passphrase);
+                        let mut app_data = app_data_arc.lock().unwrap();
+                        app_data.is_exporting_key = false;
+                        match result {
+                            // 待っている間にダイアログを閉じていたら表示しない
+                            Ok(nsec) if app_data.show_export_key_dialog => {
+                                info!("Showing the secret key for export");
+                                let hide_at = Instant::now() + Duration::from_secs(EXPORTED_KEY_DISPLAY_SECS);
+                                app_data.exported_secret_key = Some(ExportedSecretKey { nsec, hide_at });
+                            }
+                            Ok(_) => {}
+                            Err(e) => {
+                                error!("Failed to export the secret key: {e}");
+                                app_data.export_key_error = Some(tr!("表示できませんでした: {}", e));
                             }
-                            Err(_) => ImageState::Failed,
                         }
-                    } else {
-                        ImageState::Failed
-                    }
+                        app_data.should_repaint = true;
+                    });
                 }
-                Err(_) => ImageState::Failed,
-            };
-
-            let mut app_data = app_data_clone.lock().unwrap();
-            app_data.image_cache.insert(url_key, new_state);
-            ctx_clone.request_repaint();
+                if ui.button(cancel_button_text).clicked() {
+                    close = true;
+                }
+            });
         });
+    if !open || close {
+        close_export_key_dialog(app_data);
     }
 }
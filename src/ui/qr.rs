@@ -0,0 +1,90 @@
+use eframe::egui;
+use qrcode::{Color, QrCode};
+use std::path::Path;
+
+use crate::i18n::tr;
+
+// 周囲に確保する余白(モジュール数)
+const QUIET_ZONE: usize = 4;
+
+// 文字列をQRコードとして描画する。白地に黒で描くのでダークテーマでも読み取れる
+pub fn draw_qr_code(ui: &mut egui::Ui, data: &str, size: f32) -> Result<egui::Response, qrcode::types::QrError> {
+    let code = QrCode::new(data.as_bytes())?;
+    let width = code.width();
+    let colors = code.to_colors();
+
+    let (rect, response) = ui.allocate_exact_size(egui::vec2(size, size), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 0.0, egui::Color32::WHITE);
+
+    let module_size = size / (width + QUIET_ZONE * 2) as f32;
+    for (i, color) in colors.iter().enumerate() {
+        if *color != Color::Dark {
+            continue;
+        }
+        let x = (i % width + QUIET_ZONE) as f32 * module_size;
+        let y = (i / width + QUIET_ZONE) as f32 * module_size;
+        let module_rect = egui::Rect::from_min_size(
+            rect.min + egui::vec2(x, y),
+            egui::vec2(module_size.ceil(), module_size.ceil()),
+        );
+        painter.rect_filled(module_rect, 0.0, egui::Color32::BLACK);
+    }
+    Ok(response)
+}
+
+// 画像ファイルから QR コードを探して、最初に読み取れたものの内容を返す
+pub fn decode_qr_image(path: &Path) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    decode_qr_luma(image::open(path)?.to_luma8())
+}
+
+// グレースケールの画像から QR コードを読み取る。カメラの映像もこれで読む
+pub fn decode_qr_luma(image: image::GrayImage) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let mut prepared = rqrr::PreparedImage::prepare(image);
+    let grids = prepared.detect_grids();
+    if grids.is_empty() {
+        return Err(tr!("画像に QR コードが見つかりません").into());
+    }
+    grids
+        .iter()
+        .find_map(|grid| grid.decode().ok())
+        .map(|(_, content)| content)
+        .ok_or_else(|| tr!("QR コードを読み取れませんでした").into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 描画と同じ余白を付けて画像に書き出し、読み取れるか確かめる
+    #[test]
+    fn decodes_an_encoded_qr_code() {
+        const SCALE: usize = 4;
+        let data = "nostr:npub1sn0wdenkukak0d9dfczzeacvhkrgz92ak56egt7vdgzn8pv2wfqqhrjdv9";
+        let code = QrCode::new(data.as_bytes()).unwrap();
+        let width = code.width();
+        let colors = code.to_colors();
+        let size = ((width + QUIET_ZONE * 2) * SCALE) as u32;
+        let image = image::GrayImage::from_fn(size, size, |x, y| {
+            let (x, y) = (x as usize / SCALE, y as usize / SCALE);
+            let dark = (QUIET_ZONE..width + QUIET_ZONE).contains(&x)
+                && (QUIET_ZONE..width + QUIET_ZONE).contains(&y)
+                && colors[(y - QUIET_ZONE) * width + x - QUIET_ZONE] == Color::Dark;
+            image::Luma([if dark { 0 } else { 255 }])
+        });
+        let path = std::env::temp_dir().join(format!("qr-test-{}.png", std::process::id()));
+        image.save(&path).unwrap();
+        let decoded = decode_qr_image(&path);
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(decoded.unwrap(), data);
+    }
+
+    #[test]
+    fn reports_images_without_a_qr_code() {
+        let path = std::env::temp_dir().join(format!("qr-test-blank-{}.png", std::process::id()));
+        image::GrayImage::from_pixel(64, 64, image::Luma([255])).save(&path).unwrap();
+        let decoded = decode_qr_image(&path);
+        let _ = std::fs::remove_file(&path);
+        assert!(decoded.is_err());
+    }
+}
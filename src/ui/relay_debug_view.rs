@@ -0,0 +1,115 @@
+use eframe::egui;
+use std::collections::BTreeSet;
+
+use crate::{
+    i18n::tr,
+    relay_debug::{self, Direction},
+    types::*,
+};
+
+pub fn draw_relay_debug_view(
+    ui: &mut egui::Ui,
+    ctx: &egui::Context,
+    app_data: &mut NostrStatusAppInternal,
+) {
+    let heading_text = tr!("リレー通信");
+    let recording_checkbox_text = tr!("送受信を記録する");
+    let relay_label_text = tr!("リレー:");
+    let type_label_text = tr!("種類:");
+    let all_text = tr!("すべて");
+    let search_hint_text = tr!("含む文字列");
+    let copy_button_text = tr!("クリップボードにコピー");
+    let clear_button_text = tr!("消去");
+    let no_messages_text = tr!("まだ記録はありません");
+    let not_recording_text = tr!("記録を有効にすると、リレーとの REQ・EVENT・EOSE・OK・NOTICE などをそのまま表示します");
+    let hint_text = tr!("記録は最新の数千件だけを残し、長いメッセージは途中で切ります");
+    let truncated_text = tr!("(長いため省略)");
+
+    let card_frame = egui::Frame {
+        inner_margin: egui::Margin::same(12),
+        corner_radius: 8.0.into(),
+        shadow: eframe::epaint::Shadow::NONE,
+        fill: app_data.current_theme.card_background_color(),
+        ..Default::default()
+    };
+
+    let messages = relay_debug::messages();
+    let relays: BTreeSet<&str> = messages.iter().map(|message| message.relay.as_str()).collect();
+    let message_types: BTreeSet<&str> = messages.iter().map(|message| message.message_type.as_str()).collect();
+    let needle = app_data.relay_debug_search.trim().to_lowercase();
+    let shown: Vec<&relay_debug::ProtocolMessage> = messages
+        .iter()
+        .filter(|message| app_data.relay_debug_relay_filter.as_ref().is_none_or(|relay| &message.relay == relay))
+        .filter(|message| app_data.relay_debug_type_filter.as_ref().is_none_or(|message_type| &message.message_type == message_type))
+        .filter(|message| needle.is_empty() || message.raw.to_lowercase().contains(&needle))
+        .collect();
+
+    ui.heading(heading_text);
+    ui.add_space(10.0);
+    let mut recording = relay_debug::is_recording();
+    if ui.checkbox(&mut recording, recording_checkbox_text).changed() {
+        relay_debug::set_recording(recording);
+    }
+    ui.horizontal_wrapped(|ui| {
+        ui.label(relay_label_text);
+        egui::ComboBox::from_id_salt("relay_debug_relay_combo")
+            .selected_text(app_data.relay_debug_relay_filter.as_deref().unwrap_or(all_text))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut app_data.relay_debug_relay_filter, None, all_text);
+                for relay in &relays {
+                    ui.selectable_value(&mut app_data.relay_debug_relay_filter, Some(relay.to_string()), *relay);
+                }
+            });
+        ui.label(type_label_text);
+        egui::ComboBox::from_id_salt("relay_debug_type_combo")
+            .selected_text(app_data.relay_debug_type_filter.as_deref().unwrap_or(all_text))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut app_data.relay_debug_type_filter, None, all_text);
+                for message_type in &message_types {
+                    ui.selectable_value(&mut app_data.relay_debug_type_filter, Some(message_type.to_string()), *message_type);
+                }
+            });
+        ui.add(egui::TextEdit::singleline(&mut app_data.relay_debug_search).hint_text(search_hint_text).desired_width(160.0));
+        if ui.add_enabled(!shown.is_empty(), egui::Button::new(copy_button_text)).clicked() {
+            let text: Vec<String> = shown.iter().map(|message| message.to_line()).collect();
+            ctx.copy_text(text.join("\n"));
+            app_data.notify(tr!("記録をコピーしました"));
+        }
+        if ui.button(clear_button_text).clicked() {
+            relay_debug::clear();
+        }
+    });
+    ui.small(hint_text);
+    ui.add_space(10.0);
+
+    card_frame.show(ui, |ui| {
+        if messages.is_empty() {
+            ui.label(if recording { no_messages_text } else { not_recording_text });
+            return;
+        }
+        egui::ScrollArea::both()
+            .id_salt("relay_debug_scroll_area")
+            .stick_to_bottom(true)
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                for message in &shown {
+                    let color = match (message.direction, message.message_type.as_str()) {
+                        (_, "NOTICE" | "CLOSED") => ui.visuals().warn_fg_color,
+                        (Direction::Sent, _) => egui::Color32::from_rgb(0x06, 0xC7, 0x55),
+                        (Direction::Received, _) => ui.visuals().text_color(),
+                    };
+                    let mut line = message.to_line();
+                    if message.truncated {
+                        line.push(' ');
+                        line.push_str(truncated_text);
+                    }
+                    ui.label(egui::RichText::new(line).monospace().color(color));
+                }
+            });
+    });
+
+    // 記録している間は、新しいメッセージが表示されるよう定期的に描き直す
+    if recording {
+        ctx.request_repaint_after(std::time::Duration::from_secs(1));
+    }
+}
@@ -1,14 +1,16 @@
 use eframe::egui;
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
 
-use nostr::{EventBuilder, Kind, Tag};
-use nostr_sdk::{RelayUrl, nips::nip65::RelayMetadata, Client, ClientOptions as Options};
+use nostr_sdk::{RelayStatus, RelayUrl};
+use tracing::{error, info};
 
 use crate::{
+    i18n::tr,
     types::*,
-    nostr_client::{connect_to_relays_with_nip65},
+    nostr_client::{connect_to_relays_with_nip65, publish_relay_list},
     cache_db::{DB_RELAYS},
+    settings::save_settings,
+    ui::timestamp,
 };
 
 pub fn draw_relays_view(
@@ -17,16 +19,27 @@ pub fn draw_relays_view(
     app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
     runtime_handle: tokio::runtime::Handle,
 ) {
-    let current_connection_heading_text = "現在の接続";
-    let reconnect_button_text = "再接続";
-    let edit_relay_lists_heading_text = "リレーリストを編集";
-    let nip65_relay_list_label_text = "あなたのリレーリスト (NIP-65)";
-    let add_relay_button_text = "リレーを追加";
-    let read_checkbox_text = "読み取り";
-    let write_checkbox_text = "書き込み";
-    let discover_relays_label_text = "発見リレー (他ユーザーを見つけるため)";
-    let default_relays_label_text = "デフォルトリレー (フォールバック用)";
-    let save_nip65_button_text = "保存して発見リレーに公開";
+    let current_connection_heading_text = tr!("現在の接続");
+    let reconnect_button_text = tr!("再接続");
+    let edit_relay_lists_heading_text = tr!("リレーリストを編集");
+    let nip65_relay_list_label_text = tr!("あなたのリレーリスト (NIP-65)");
+    let add_relay_button_text = tr!("リレーを追加");
+    let read_checkbox_text = tr!("読み取り");
+    let write_checkbox_text = tr!("書き込み");
+    let manual_relays_heading_text = tr!("手動で追加したリレー");
+    let manual_relays_hint_text = tr!("NIP-65のリレーリストとあわせて接続します。変更は再接続で反映されます");
+    let disabled_relay_text = tr!("(無効)");
+    let relay_settings_hint_text = tr!("発見リレーとデフォルトリレーは「設定」タブで変更できます");
+    let publish_relay_list_button_text = tr!("リレーリストを公開");
+    let no_relay_status_text = tr!("リレーの状態を取得しています...");
+    let relay_column_text = tr!("リレー");
+    let status_column_text = tr!("状態");
+    let last_message_column_text = tr!("最終受信");
+    let latency_column_text = tr!("遅延");
+    let events_column_text = tr!("受信イベント数");
+    let connected_text = tr!("接続中");
+    let connecting_text = tr!("接続処理中");
+    let disconnected_text = tr!("切断");
 
     let card_frame = egui::Frame {
         inner_margin: egui::Margin::same(12),
@@ -44,28 +57,32 @@ pub fn draw_relays_view(
             let reconnect_button = egui::Button::new(egui::RichText::new(reconnect_button_text).strong());
             if ui.add_enabled(!app_data.is_loading, reconnect_button).clicked() {
                 let client_clone = app_data.nostr_client.as_ref().unwrap().clone();
-                let keys_clone = app_data.my_keys.clone().unwrap();
-                let discover_relays = app_data.discover_relays_editor.clone();
-                let default_relays = app_data.default_relays_editor.clone();
+                let keys_clone = app_data.my_signer.clone().unwrap();
+                let discover_relays = app_data.settings.discover_relays.clone();
+                let default_relays = app_data.settings.default_relays.clone();
+                let manual_relays = app_data.settings.manual_relays.clone();
                 let cache_db_clone = app_data.cache_db.clone();
+                let session = app_data.session_generation;
 
                 app_data.is_loading = true;
                 app_data.should_repaint = true;
 
                 let cloned_app_data_arc = app_data_arc.clone();
                 runtime_handle.spawn(async move {
-                    match connect_to_relays_with_nip65(&client_clone, &keys_clone, &discover_relays, &default_relays).await {
+                    let result = connect_to_relays_with_nip65(&client_clone, &keys_clone, &discover_relays, &default_relays, &manual_relays).await;
+                    // つなぎ直している間にロックやログアウトをしていたら結果は捨てる
+                    if !cloned_app_data_arc.lock().unwrap().is_current_session(session) {
+                        return;
+                    }
+                    match result {
                         Ok((log_message, fetched_nip65_relays)) => {
-                            println!("Relay connection successful!\n{log_message}");
+                            info!("Relay connection successful!\n{log_message}");
                             let pubkey_hex = keys_clone.public_key().to_string();
                             if let Err(e) = cache_db_clone.write_cache(DB_RELAYS, &pubkey_hex, &fetched_nip65_relays) {
-                                eprintln!("Failed to write NIP-65 cache: {e}");
+                                error!("Failed to write NIP-65 cache: {e}");
                             }
 
                             let mut app_data_async = cloned_app_data_arc.lock().unwrap();
-                            if let Some(pos) = log_message.find("--- 現在接続中のリレー ---") {
-                                app_data_async.connected_relays_display = log_message[pos..].to_string();
-                            }
                             app_data_async.nip65_relays = fetched_nip65_relays.into_iter().map(|(url, policy)| {
                                 let (read, write) = match policy.as_deref() {
                                     Some("read") => (true, false),
@@ -76,7 +93,8 @@ pub fn draw_relays_view(
                             }).collect();
                         }
                         Err(e) => {
-                            eprintln!("Failed to connect to relays: {e}");
+                            error!("Failed to connect to relays: {e}");
+                            cloned_app_data_arc.lock().unwrap().report_error(tr!("リレーに接続できませんでした: {}", e));
                         }
                     }
                     let mut app_data_async = cloned_app_data_arc.lock().unwrap();
@@ -85,11 +103,103 @@ pub fn draw_relays_view(
                 });
             }
             ui.add_space(10.0);
-            egui::ScrollArea::vertical().id_salt("relay_connection_scroll_area").max_height(150.0).show(ui, |ui| {
-                ui.add(egui::TextEdit::multiline(&mut app_data.connected_relays_display)
-                    .desired_width(ui.available_width())
-                    .interactive(false));
+            if app_data.relay_status_rows.is_empty() {
+                ui.label(no_relay_status_text);
+            } else {
+                egui::ScrollArea::vertical().id_salt("relay_connection_scroll_area").max_height(200.0).show(ui, |ui| {
+                    egui::Grid::new("relay_status_grid")
+                        .num_columns(5)
+                        .spacing([16.0, 6.0])
+                        .striped(true)
+                        .show(ui, |ui| {
+                            ui.strong(relay_column_text);
+                            ui.strong(status_column_text);
+                            ui.strong(last_message_column_text);
+                            ui.strong(latency_column_text);
+                            ui.strong(events_column_text);
+                            ui.end_row();
+
+                            for row in &app_data.relay_status_rows {
+                                ui.label(&row.url);
+                                let (status_text, status_color) = match row.status {
+                                    RelayStatus::Connected => (connected_text, egui::Color32::from_rgb(60, 170, 90)),
+                                    RelayStatus::Connecting | RelayStatus::Pending | RelayStatus::Initialized => (connecting_text, egui::Color32::from_rgb(220, 160, 40)),
+                                    _ => (disconnected_text, egui::Color32::GRAY),
+                                };
+                                ui.colored_label(status_color, status_text);
+                                match row.last_message_at {
+                                    Some(last_message_at) => timestamp::draw_timestamp(ui, last_message_at),
+                                    None => ui.label("-"),
+                                };
+                                match row.latency {
+                                    Some(latency) => ui.label(format!("{} ms", latency.as_millis())),
+                                    None => ui.label("-"),
+                                };
+                                ui.label(row.events_received.to_string());
+                                ui.end_row();
+                            }
+                        });
+                });
+            }
+        });
+
+        ui.add_space(15.0);
+
+        // --- 手動リレー ---
+        card_frame.show(ui, |ui| {
+            ui.heading(manual_relays_heading_text);
+            ui.add_space(5.0);
+            ui.small(manual_relays_hint_text);
+            ui.add_space(10.0);
+
+            let mut changed = false;
+            let mut relay_to_remove = None;
+            for (i, relay) in app_data.settings.manual_relays.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.label(&relay.url);
+                    changed |= ui.checkbox(&mut relay.read, read_checkbox_text).changed();
+                    changed |= ui.checkbox(&mut relay.write, write_checkbox_text).changed();
+                    if !relay.read && !relay.write {
+                        ui.weak(disabled_relay_text);
+                    }
+                    if ui.button("❌").clicked() {
+                        relay_to_remove = Some(i);
+                    }
+                });
+            }
+            if let Some(i) = relay_to_remove {
+                app_data.settings.manual_relays.remove(i);
+                changed = true;
+            }
+
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut app_data.manual_relay_input)
+                    .hint_text("wss://...")
+                    .desired_width(300.0));
+                if ui.button(add_relay_button_text).clicked() {
+                    let input = app_data.manual_relay_input.trim().to_string();
+                    match RelayUrl::parse(&input) {
+                        Ok(url) if app_data.settings.manual_relays.iter().any(|r| r.url == url.as_str()) => {
+                            app_data.manual_relay_error = Some(tr!("{} は登録済みです", url));
+                        }
+                        Ok(url) => {
+                            app_data.settings.manual_relays.push(EditableRelay { url: url.to_string(), read: true, write: true });
+                            app_data.manual_relay_input.clear();
+                            app_data.manual_relay_error = None;
+                            changed = true;
+                        }
+                        Err(e) => app_data.manual_relay_error = Some(tr!("リレーURLが正しくありません: {}", e)),
+                    }
+                }
             });
+            if let Some(error) = &app_data.manual_relay_error {
+                ui.colored_label(egui::Color32::RED, error);
+            }
+
+            if changed
+                && let Err(e) = save_settings(&app_data.settings) {
+                app_data.manual_relay_error = Some(tr!("リレーの保存に失敗しました: {}", e));
+            }
         });
 
         ui.add_space(15.0);
@@ -125,92 +235,53 @@ pub fn draw_relays_view(
                 app_data.nip65_relays.push(EditableRelay::default());
             }
 
-            ui.add_space(15.0);
-            ui.label(discover_relays_label_text);
             ui.add_space(5.0);
-             egui::ScrollArea::vertical().id_salt("discover_editor_scroll").max_height(80.0).show(ui, |ui| {
-                ui.add(egui::TextEdit::multiline(&mut app_data.discover_relays_editor)
-                    .desired_width(ui.available_width()));
-            });
-
-            ui.add_space(15.0);
-            ui.label(default_relays_label_text);
-            ui.add_space(5.0);
-            egui::ScrollArea::vertical().id_salt("default_editor_scroll").max_height(80.0).show(ui, |ui| {
-                ui.add(egui::TextEdit::multiline(&mut app_data.default_relays_editor)
-                    .desired_width(ui.available_width()));
-            });
+            ui.small(relay_settings_hint_text);
 
             ui.add_space(15.0);
-            let save_nip65_button = egui::Button::new(egui::RichText::new(save_nip65_button_text).strong());
-            if ui.add_enabled(!app_data.is_loading, save_nip65_button).clicked() {
-                let keys = app_data.my_keys.clone().unwrap();
+            let publish_button = egui::Button::new(egui::RichText::new(publish_relay_list_button_text).strong());
+            if ui.add_enabled(!app_data.is_loading, publish_button).clicked() {
+                let client = app_data.nostr_client.clone().unwrap();
+                let keys = app_data.my_signer.clone().unwrap();
                 let nip65_relays = app_data.nip65_relays.clone();
-                let discover_relays = app_data.discover_relays_editor.clone();
+                let discover_relays = app_data.settings.discover_relays.clone();
+                let timeout = app_data.settings.relay_timeout();
+                let cache_db = app_data.cache_db.clone();
+                let session = app_data.session_generation;
 
                 app_data.is_loading = true;
+                app_data.relay_list_status = None;
                 app_data.should_repaint = true;
 
                 let cloned_app_data_arc = app_data_arc.clone();
                 runtime_handle.spawn(async move {
-                    let result: Result<(), Box<dyn std::error::Error + Send + Sync>> = async {
-                        let tags: Vec<Tag> = nip65_relays
-                            .iter()
-                            .filter_map(|relay| {
-                                if relay.url.trim().is_empty() {
-                                    return None;
-                                }
-                                let policy = if relay.read && !relay.write {
-                                    Some(RelayMetadata::Read)
-                                } else if !relay.read && relay.write {
-                                    Some(RelayMetadata::Write)
-                                } else {
-                                    None
-                                };
-                                match RelayUrl::parse(&relay.url) {
-                                    Ok(url) => Some(Tag::relay_metadata(url, policy)),
-                                    Err(_) => None,
-                                }
-                            })
-                            .collect();
-
-                        if tags.is_empty() {
-                                    println!("Warning: Publishing an empty NIP-65 list.");
-                        }
-
-                        let event = EventBuilder::new(Kind::RelayList, "").tags(tags).sign(&keys).await?;
+                    let result = publish_relay_list(&client, &keys, &nip65_relays, &discover_relays, timeout).await;
 
-                         let opts = Options::new();
-                         let discover_client = Client::builder()
-                             .signer(keys.clone())
-                             .opts(opts)
-                             .build();
-                        discover_client.connect().await;
-                        discover_client.wait_for_connection(Duration::from_secs(20)).await;
-
-                        for relay_url in discover_relays.lines() {
-                            if !relay_url.trim().is_empty() {
-                                discover_client.add_relay(relay_url.trim()).await?;
+                    let mut app_data_async = cloned_app_data_arc.lock().unwrap();
+                    if !app_data_async.is_current_session(session) {
+                        return;
+                    }
+                    match result {
+                        Ok(published_relays) => {
+                            info!("NIP-65 list published with {} relays", published_relays.len());
+                            if let Err(e) = cache_db.write_cache(DB_RELAYS, &keys.public_key().to_string(), &published_relays) {
+                                error!("Failed to write NIP-65 cache: {e}");
                             }
+                            app_data_async.relay_list_status = Some(tr!("リレーリストを公開しました ({}件)", published_relays.len()));
+                        }
+                        Err(e) => {
+                            error!("Failed to publish NIP-65 list: {e}");
+                            app_data_async.relay_list_status = Some(tr!("公開に失敗しました: {}", e));
+                            app_data_async.report_error(tr!("リレーリストを公開できませんでした: {}", e));
                         }
-                        discover_client.connect().await;
-
-                                let event_id = discover_client.send_event(&event).await?;
-                                println!("NIP-65 list published with event id: {event_id:?}");
-
-                        discover_client.shutdown().await;
-                        Ok(())
-                    }.await;
-
-                    if let Err(e) = result {
-                        eprintln!("Failed to publish NIP-65 list: {e}");
                     }
-
-                    let mut app_data_async = cloned_app_data_arc.lock().unwrap();
                     app_data_async.is_loading = false;
                     app_data_async.should_repaint = true;
                 });
             }
+            if let Some(status) = &app_data.relay_list_status {
+                ui.label(status);
+            }
         });
     });
 }
@@ -0,0 +1,111 @@
+use eframe::egui;
+use nostr::nips::nip56::Report;
+use std::sync::{Arc, Mutex};
+
+use crate::{i18n::tr, report::publish_report, types::*};
+
+fn report_reason_text(reason: &Report) -> &'static str {
+    match reason {
+        Report::Spam => tr!("スパム"),
+        Report::Impersonation => tr!("なりすまし"),
+        Report::Illegal => tr!("違法な内容"),
+        Report::Profanity => tr!("暴言・ヘイト"),
+        Report::Nudity => tr!("性的な内容"),
+        Report::Malware => tr!("マルウェア"),
+        Report::Other => tr!("その他"),
+    }
+}
+
+// 通報 (NIP-56) の理由を選ぶダイアログ。どのタブからでも開けるよう、ここで描く
+pub fn draw_report_dialog(
+    ctx: &egui::Context,
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: tokio::runtime::Handle,
+) {
+    let report_window_title_text = tr!("通報");
+    let report_user_text = tr!("このユーザーを通報します");
+    let report_event_text = tr!("この投稿を通報します");
+    let reason_label_text = tr!("理由:");
+    let comment_hint_text = tr!("補足 (任意)");
+    let send_report_button_text = tr!("通報する");
+    let cancel_button_text = tr!("キャンセル");
+    let report_hint_text = tr!("通報は公開され、モデレーションに対応したリレーやクライアントが参考にします");
+
+    let Some(target) = app_data.report_target.clone() else {
+        return;
+    };
+    egui::Window::new(report_window_title_text)
+        .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            let name = app_data
+                .profiles
+                .get(&target.public_key)
+                .map(|metadata| metadata.display_name_or_npub(&target.public_key))
+                .unwrap_or_else(|| short_npub(&target.public_key));
+            let target_text = if target.event_id.is_some() { report_event_text } else { report_user_text };
+            ui.label(format!("{target_text} ({name})"));
+            ui.add_space(5.0);
+            ui.horizontal(|ui| {
+                ui.label(reason_label_text);
+                egui::ComboBox::from_id_salt("report_reason_combo")
+                    .selected_text(report_reason_text(&app_data.report_reason))
+                    .show_ui(ui, |ui| {
+                        for reason in [
+                            Report::Spam,
+                            Report::Impersonation,
+                            Report::Illegal,
+                            Report::Profanity,
+                            Report::Nudity,
+                            Report::Malware,
+                            Report::Other,
+                        ] {
+                            let text = report_reason_text(&reason);
+                            ui.selectable_value(&mut app_data.report_reason, reason, text);
+                        }
+                    });
+            });
+            ui.add(
+                egui::TextEdit::multiline(&mut app_data.report_comment)
+                    .desired_rows(2)
+                    .hint_text(comment_hint_text),
+            );
+            ui.small(report_hint_text);
+            ui.add_space(5.0);
+            ui.horizontal(|ui| {
+                if ui.add_enabled(!app_data.is_sending_report, egui::Button::new(send_report_button_text)).clicked()
+                    && let (Some(client), Some(signer)) = (app_data.nostr_client.clone(), app_data.my_signer.clone()) {
+                    let reason = app_data.report_reason.clone();
+                    let comment = app_data.report_comment.clone();
+                    app_data.is_sending_report = true;
+                    let cloned_app_data_arc = app_data_arc.clone();
+                    runtime_handle.spawn(async move {
+                        let published = publish_report(client, signer, target, reason, comment, cloned_app_data_arc.clone()).await;
+                        let mut data = cloned_app_data_arc.lock().unwrap();
+                        if published {
+                            data.report_target = None;
+                            data.report_comment.clear();
+                        }
+                        data.is_sending_report = false;
+                        data.should_repaint = true;
+                    });
+                }
+                if ui.button(cancel_button_text).clicked() {
+                    app_data.report_target = None;
+                    app_data.report_comment.clear();
+                }
+                if app_data.is_sending_report {
+                    ui.spinner();
+                }
+            });
+        });
+}
+
+// 通報のダイアログを開く
+pub fn open_report_dialog(app_data: &mut NostrStatusAppInternal, target: ReportTarget) {
+    app_data.report_target = Some(target);
+    app_data.report_reason = Report::Spam;
+    app_data.report_comment.clear();
+}
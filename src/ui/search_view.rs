@@ -0,0 +1,190 @@
+use eframe::egui;
+use nostr::{EventId, Kind, PublicKey};
+use std::sync::{Arc, Mutex};
+use tracing::error;
+
+use crate::{
+    i18n::tr,
+    note,
+    search::{search_local, search_relays},
+    types::*,
+    ui::{content_warning, image_cache, link_view, nip05_badge, signature_badge, timestamp},
+};
+
+fn start_search(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+) {
+    let query = app_data.search_query.trim().to_string();
+    if query.is_empty() {
+        return;
+    }
+    // 検索用リレーがなければ手元のキャッシュから探す
+    let Some(signer) = app_data.my_signer.clone().filter(|_| !app_data.settings.search_relays.trim().is_empty()) else {
+        app_data.search_results = Some(search_local(app_data, &query));
+        return;
+    };
+    let settings = app_data.settings.clone();
+    app_data.is_searching = true;
+    app_data.should_repaint = true;
+    runtime_handle.spawn(async move {
+        let result = search_relays(signer, settings, query.clone()).await;
+        let mut app_data = app_data_arc.lock().unwrap();
+        match result {
+            Ok((results, profiles)) => {
+                app_data.profiles.extend(profiles);
+                app_data.search_results = Some(results);
+            }
+            Err(e) => {
+                error!("Failed to search relays: {e}");
+                app_data.report_error(tr!("検索リレーで検索できませんでした。手元のキャッシュから探します: {}", e));
+                let results = search_local(&app_data, &query);
+                app_data.search_results = Some(results);
+            }
+        }
+        app_data.is_searching = false;
+        app_data.should_repaint = true;
+    });
+}
+
+pub fn draw_search_view(
+    ui: &mut egui::Ui,
+    ctx: &egui::Context,
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: tokio::runtime::Handle,
+) {
+    let search_heading_text = tr!("検索");
+    let search_hint_text = tr!("キーワード");
+    let search_button_text = tr!("🔍 検索");
+    let from_relays_text = tr!("検索リレー (NIP-50) の結果");
+    let from_cache_text = tr!("手元のキャッシュから探した結果");
+    let profiles_heading_text = tr!("ユーザー");
+    let posts_heading_text = tr!("ノート・ステータス");
+    let no_results_text = tr!("見つかりませんでした");
+    let searching_text = tr!("検索中...");
+
+    let card_frame = egui::Frame {
+        inner_margin: egui::Margin::same(12),
+        corner_radius: 8.0.into(),
+        shadow: eframe::epaint::Shadow::NONE,
+        fill: app_data.current_theme.card_background_color(),
+        ..Default::default()
+    };
+
+    ui.heading(search_heading_text);
+    ui.add_space(10.0);
+    let mut search_requested = false;
+    ui.horizontal(|ui| {
+        let response = ui.add(
+            egui::TextEdit::singleline(&mut app_data.search_query)
+                .desired_width(300.0)
+                .hint_text(search_hint_text),
+        );
+        if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+            search_requested = true;
+        }
+        let can_search = !app_data.is_searching && !app_data.search_query.trim().is_empty();
+        if ui.add_enabled(can_search, egui::Button::new(search_button_text)).clicked() {
+            search_requested = true;
+        }
+        if app_data.is_searching {
+            ui.spinner();
+            ui.label(searching_text);
+        }
+    });
+    if search_requested && !app_data.is_searching {
+        start_search(app_data, app_data_arc.clone(), &runtime_handle);
+    }
+    ui.add_space(10.0);
+
+    let Some(results) = app_data.search_results.clone() else {
+        return;
+    };
+    let source_text = if results.from_relays { from_relays_text } else { from_cache_text };
+    ui.label(egui::RichText::new(tr!("「{}」: {}", results.query, source_text)).small().color(egui::Color32::GRAY));
+    ui.add_space(5.0);
+    if results.profiles.is_empty() && results.posts.is_empty() {
+        ui.label(no_results_text);
+        return;
+    }
+
+    let mut urls_to_load: Vec<(String, ImageKind)> = Vec::new();
+    let mut nip05_to_verify: Vec<(PublicKey, String)> = Vec::new();
+    let mut link_to_open: Option<NostrLink> = None;
+    let mut content_warning_toggle: Option<EventId> = None;
+
+    egui::ScrollArea::vertical()
+        .id_salt("search_results_scroll_area")
+        .show(ui, |ui| {
+            if !results.profiles.is_empty() {
+                card_frame.show(ui, |ui| {
+                    ui.set_width(ui.available_width());
+                    ui.label(egui::RichText::new(profiles_heading_text).strong());
+                    ui.add_space(5.0);
+                    for public_key in &results.profiles {
+                        let metadata = app_data.profiles.get(public_key).cloned().unwrap_or_default();
+                        ui.horizontal(|ui| {
+                            image_cache::draw_user_avatar(
+                                ui,
+                                &app_data.image_cache,
+                                public_key,
+                                &metadata.picture,
+                                egui::vec2(32.0, 32.0),
+                                16.0,
+                                &mut urls_to_load,
+                            );
+                            if ui.link(egui::RichText::new(metadata.display_name_or_npub(public_key)).strong()).clicked() {
+                                link_to_open = Some(NostrLink::Profile(*public_key));
+                            }
+                            nip05_badge::draw_nip05_badge(ui, &app_data.nip05_cache, public_key, &metadata.nip05, &mut nip05_to_verify);
+                        });
+                    }
+                });
+                ui.add_space(10.0);
+            }
+
+            if !results.posts.is_empty() {
+                card_frame.show(ui, |ui| {
+                    ui.set_width(ui.available_width());
+                    ui.label(egui::RichText::new(posts_heading_text).strong());
+                    ui.add_space(5.0);
+                    for hit in &results.posts {
+                        let metadata = app_data.profiles.get(&hit.author).cloned().unwrap_or_default();
+                        ui.horizontal(|ui| {
+                            let icon = if hit.kind == Kind::TextNote {
+                                "📝"
+                            } else {
+                                let d_tag = hit.tags.iter().find_map(|tag| match tag.as_standardized() {
+                                    Some(nostr::TagStandard::Identifier(d_tag)) => Some(d_tag.clone()),
+                                    _ => None,
+                                });
+                                StatusGroup::from_d_tag(d_tag.as_deref().unwrap_or_default()).icon()
+                            };
+                            ui.label(icon);
+                            if ui.link(egui::RichText::new(metadata.display_name_or_npub(&hit.author)).strong()).clicked() {
+                                link_to_open = Some(NostrLink::Profile(hit.author));
+                            }
+                            timestamp::draw_timestamp(ui, hit.created_at);
+                            signature_badge::draw_signature_badge(ui, &hit.id);
+                        });
+                        let content_warning = note::content_warning(&hit.tags);
+                        if content_warning::draw_content_warning(ui, &app_data.revealed_content_warnings, hit.id, content_warning.as_deref(), &mut content_warning_toggle) {
+                            ui.horizontal_wrapped(|ui| {
+                                link_view::draw_text_with_links(ui, &hit.content, ui.visuals().text_color(), &app_data.profiles, &mut link_to_open);
+                            });
+                        }
+                        ui.separator();
+                    }
+                });
+            }
+        });
+
+    content_warning::toggle_revealed(&mut app_data.revealed_content_warnings, content_warning_toggle);
+    nip05_badge::verify_nip05s(app_data, app_data_arc.clone(), &runtime_handle, nip05_to_verify);
+    if let Some(link) = link_to_open {
+        link_view::open_link(app_data, app_data_arc.clone(), &runtime_handle, link);
+    }
+    image_cache::load_images(ctx, app_data, app_data_arc, &runtime_handle, urls_to_load);
+}
@@ -0,0 +1,675 @@
+use eframe::egui;
+use nostr::Kind;
+use std::sync::{Arc, Mutex};
+
+use tracing::error;
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::{
+    accounts,
+    desktop_notify,
+    fonts,
+    i18n::{self, tr},
+    network::apply_network_settings,
+    now_playing,
+    settings::save_settings,
+    settings_sync,
+    sign_gate,
+    theme::{apply_font_size, apply_theme_preference, apply_theme_preset, apply_ui_scale, FONT_SIZE_RANGE, UI_SCALE_RANGE},
+    types::*,
+    ui::markdown::apply_markdown_settings,
+};
+
+pub fn draw_settings_view(
+    ui: &mut egui::Ui,
+    ctx: &egui::Context,
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: tokio::runtime::Handle,
+) {
+    let settings_heading_text = tr!("設定");
+    let appearance_heading_text = tr!("表示");
+    let theme_label_text = tr!("テーマ:");
+    let light_theme_text = tr!("ライト");
+    let dark_theme_text = tr!("ダーク");
+    let system_theme_text = tr!("システムに合わせる");
+    let theme_preset_label_text = tr!("配色:");
+    let classic_preset_text = tr!("クラシック");
+    let line_preset_text = tr!("LINE 風 (白と緑)");
+    let language_label_text = tr!("言語:");
+    let japanese_text = tr!("日本語");
+    let english_text = "English";
+    let font_label_text = tr!("フォント:");
+    let bundled_font_text = tr!("LINE Seed JP (同梱)");
+    let system_font_text = tr!("OS の CJK フォント");
+    let custom_font_text = tr!("ファイルを指定");
+    let custom_font_hint_text = "/path/to/font.ttf";
+    let font_fallback_hint_text = tr!("選んだフォントにない文字 (中国語・韓国語など) は、ほかのフォントで表示します。保存すると切り替わります");
+    let ui_scale_label_text = tr!("画面の拡大率:");
+    let font_size_label_text = tr!("文字の大きさ:");
+    let ui_scale_hint_text = tr!("画面の拡大率は、つまみを離すと反映されます");
+    let previews_label_text = tr!("プレビュー:");
+    let previews_checkbox_text = tr!("本文中の画像とリンクのプレビューを表示する");
+    let markdown_label_text = tr!("書式:");
+    let markdown_checkbox_text = tr!("本文の太字・斜体・コード・箇条書きを表示する");
+    let markdown_hint_text = tr!("切ると、記号を含めて書いたままの本文を表示します");
+    let previews_hint_text = tr!("オフにすると、画像やリンク先のページを読み込みません");
+    let relays_heading_text = tr!("リレー");
+    let discover_relays_label_text = tr!("発見リレー (他ユーザーを見つけるため)");
+    let search_relays_label_text = tr!("検索リレー (NIP-50。空欄なら手元のキャッシュから探します)");
+    let default_relays_label_text = tr!("デフォルトリレー (フォールバック用)");
+    let network_heading_text = tr!("通信");
+    let relay_timeout_label_text = tr!("リレーの応答待ち時間 (秒):");
+    let discovery_timeout_label_text = tr!("リレーリストを探す時間 (秒):");
+    let connect_wait_label_text = tr!("接続を待つ時間 (秒):");
+    let fetch_retry_label_text = tr!("取得に失敗したときにやり直す回数:");
+    let timeline_limit_label_text = tr!("タイムラインの取得件数:");
+    let global_feed_label_text = tr!("グローバルフィード:");
+    let global_feed_checkbox_text = tr!("タブを表示する");
+    let global_feed_hint_text = tr!("フォローしていない人も含めた最近のノートとステータスを表示します");
+    let pow_difficulty_label_text = tr!("投稿のPoW難易度 (NIP-13):");
+    let pow_difficulty_hint_text = tr!("0で無効。値を大きくすると投稿に時間がかかります。計算は通知から中止できます");
+    let undo_send_label_text = tr!("送信を取り消せる時間:");
+    let undo_send_off_text = tr!("すぐ送る");
+    let undo_send_hint_text = tr!("ステータス・ノート・トークを送る前に待ち、その間は通知から取り消せます");
+    let opentimestamps_label_text = tr!("タイムスタンプの証明 (NIP-03):");
+    let opentimestamps_checkbox_text = tr!("公開したステータスを OpenTimestamps で記録する");
+    let opentimestamps_hint_text = tr!("ステータスの ID をカレンダーサーバーに送り、ビットコインのブロックに記録されたら (数時間後) 証明を公開します");
+    let relay_changes_hint_text = tr!("リレーと通信の設定は次回の接続から反映されます");
+    let proxy_heading_text = tr!("プロキシ");
+    let proxy_label_text = tr!("SOCKS5プロキシ (host:port):");
+    let proxy_hint_text = tr!("例: Torなら 127.0.0.1:9050。空欄なら直接接続します");
+    let tor_only_checkbox_text = tr!("Torのみ (.onion 以外のリレーには接続しない)");
+    let proxy_changes_hint_text = tr!("プロキシの設定は保存後に作られる接続から反映されます。すべてに反映するには再ログインしてください");
+    let presets_heading_text = tr!("定型文");
+    let presets_hint_text = tr!("新規投稿の入力欄の上に並び、押すだけで入力できます");
+    let new_preset_hint_text = tr!("例: 移動中");
+    let add_preset_button_text = tr!("追加");
+    let remove_preset_button_text = tr!("削除");
+    let muted_words_heading_text = tr!("ミュートする語");
+    let muted_words_hint_text = tr!("1行に1つ。この語を含む投稿はタイムラインやフィードに表示しません");
+    let labels_heading_text = tr!("ラベル (NIP-32)");
+    let trusted_labelers_label_text = tr!("参考にする人 (npub か hex で1行に1つ)");
+    let hidden_labels_label_text = tr!("表示しないラベル");
+    let collapsed_labels_label_text = tr!("本文を隠すラベル");
+    let labels_hint_text = tr!("参考にする人と自分が投稿や投稿者に付けたラベル (例: nsfw, spam) に合わせて、タイムラインの投稿を隠したり畳んだりします。ラベルは1行に1つ");
+    let wot_heading_text = tr!("知らない人 (Web of Trust)");
+    let wot_checkbox_text = tr!("フォロー中の人とつながりのない人の返信・投稿を畳み、トークはリクエストに分ける");
+    let wot_min_follows_label_text = tr!("知り合いとして扱う人数:");
+    let wot_hint_text = tr!("フォロー中の人のうち、この人数以上がフォローしている人は知り合いとして扱います。フォローリストは受信済みのものを使い、1時間ごとに数え直します");
+    let wot_loading_text = tr!("フォローリストを数えています...");
+    let sync_hint_text = tr!("テーマ、言語、定型文、ミュートする語はログイン中のアカウントに暗号化して保存し、ほかの端末と共有します");
+    let integrations_heading_text = tr!("連携");
+    let now_playing_checkbox_text = tr!("再生中の曲を音楽ステータスとして公開する");
+    let now_playing_hint_text = tr!("再生中のプレイヤーの曲名とアーティストを、曲の残り時間を期限にして公開します");
+    let now_playing_unsupported_text = tr!("この OS ではまだ対応していません (Linux、Windows、macOS のみ)");
+    let media_heading_text = tr!("画像の添付");
+    let media_server_label_text = tr!("メディアサーバー:");
+    let media_server_kind_label_text = tr!("方式:");
+    let media_server_hint_text = tr!("添付した画像のアップロード先です。NIP-96 では NIP-98 で、Blossom では認可イベントで署名します");
+    let notifications_heading_text = tr!("通知");
+    let notify_direct_messages_checkbox_text = tr!("新しいメッセージ (DM)");
+    let notify_mentions_checkbox_text = tr!("自分へのメンション");
+    let notify_starred_statuses_checkbox_text = tr!("星を付けた友だちの新しいステータス");
+    let notifications_hint_text = tr!("ウィンドウを見ていないときにデスクトップ通知を出します。友だちタブの ☆ で星を付けられます");
+    let notifications_unsupported_text = tr!("この OS ではまだ対応していません (Linux のみ)");
+    let send_read_receipts_checkbox_text = tr!("トークを開いたら既読を相手に知らせる (NIP-17 のトークのみ)");
+    let send_typing_checkbox_text = tr!("メッセージの入力中であることを相手に知らせる");
+    let send_typing_hint_text = tr!("入力中の知らせは暗号化されないため、誰とトークしているかがリレーから分かります");
+    let save_button_text = tr!("保存");
+    let reset_button_text = tr!("初期値に戻す");
+    let security_heading_text = tr!("セキュリティ");
+    let auto_lock_label_text = tr!("自動ロックまでの時間 (分):");
+    let auto_lock_hint_text = tr!("操作がないままこの時間が過ぎたら、鍵をメモリから消してパスフレーズの入力に戻ります。0 なら自動ではロックしません");
+    let confirm_signing_checkbox_text = tr!("署名する前に内容を確認する");
+    let confirm_signing_hint_text = tr!("投稿やリアクションなど、署名するイベントの種類・タグ・本文をダイアログで確認してから署名します");
+    let auto_approve_label_text = tr!("確認せずに署名する種類:");
+    let no_auto_approve_text = tr!("(なし。すべて確認します)");
+    let auto_approve_kind_hint_text = tr!("kind の番号");
+    let add_kind_button_text = tr!("追加");
+    let remove_kind_hover_text = tr!("確認するようにする");
+    let invalid_kind_text = tr!("kind は 0〜65535 の番号で入力してください");
+    let change_passphrase_button_text = tr!("パスフレーズを変更");
+    let passphrase_hint_text = tr!("このアプリに保存した秘密鍵を、新しいパスフレーズで暗号化し直します");
+
+    let card_frame = egui::Frame {
+        inner_margin: egui::Margin::same(12),
+        corner_radius: 8.0.into(),
+        shadow: eframe::epaint::Shadow::NONE,
+        fill: app_data.current_theme.card_background_color(),
+        ..Default::default()
+    };
+
+    egui::ScrollArea::vertical().id_salt("settings_tab_scroll_area").show(ui, |ui| {
+        ui.heading(settings_heading_text);
+        ui.add_space(10.0);
+
+        card_frame.show(ui, |ui| {
+            ui.heading(appearance_heading_text);
+            ui.add_space(10.0);
+            egui::Grid::new("appearance_settings_grid")
+                .num_columns(2)
+                .spacing([20.0, 10.0])
+                .show(ui, |ui| {
+                    ui.label(theme_label_text);
+                    ui.horizontal(|ui| {
+                        ui.radio_value(&mut app_data.settings.theme, ThemePreference::Light, light_theme_text);
+                        ui.radio_value(&mut app_data.settings.theme, ThemePreference::Dark, dark_theme_text);
+                        ui.radio_value(&mut app_data.settings.theme, ThemePreference::System, system_theme_text);
+                    });
+                    ui.end_row();
+
+                    ui.label(theme_preset_label_text);
+                    ui.horizontal(|ui| {
+                        let classic = ui.radio_value(&mut app_data.settings.theme_preset, ThemePreset::Classic, classic_preset_text);
+                        let line = ui.radio_value(&mut app_data.settings.theme_preset, ThemePreset::Line, line_preset_text);
+                        if classic.changed() || line.changed() {
+                            apply_theme_preset(ctx, app_data.settings.theme_preset);
+                        }
+                    });
+                    ui.end_row();
+
+                    ui.label(language_label_text);
+                    ui.horizontal(|ui| {
+                        let japanese = ui.radio_value(&mut app_data.settings.language, Language::Japanese, japanese_text);
+                        let english = ui.radio_value(&mut app_data.settings.language, Language::English, english_text);
+                        // 言語はその場で切り替える
+                        if japanese.changed() || english.changed() {
+                            i18n::set_language(app_data.settings.language);
+                        }
+                    });
+                    ui.end_row();
+
+                    ui.label(font_label_text);
+                    ui.vertical(|ui| {
+                        ui.horizontal(|ui| {
+                            ui.radio_value(&mut app_data.settings.ui_font, UiFont::Bundled, bundled_font_text);
+                            ui.radio_value(&mut app_data.settings.ui_font, UiFont::System, system_font_text);
+                            ui.radio_value(&mut app_data.settings.ui_font, UiFont::Custom, custom_font_text);
+                        });
+                        if app_data.settings.ui_font == UiFont::Custom {
+                            ui.add(egui::TextEdit::singleline(&mut app_data.settings.custom_font_path).hint_text(custom_font_hint_text));
+                        }
+                        ui.small(font_fallback_hint_text);
+                    });
+                    ui.end_row();
+
+                    ui.label(ui_scale_label_text);
+                    ui.vertical(|ui| {
+                        let ui_scale = ui.add(egui::Slider::new(&mut app_data.settings.ui_scale, UI_SCALE_RANGE).step_by(0.05).fixed_decimals(2));
+                        // 動かしている間に画面ごと大きさが変わると、つまみがカーソルからずれるので離してから反映する
+                        if ui_scale.drag_stopped() || (ui_scale.changed() && !ui_scale.dragged()) {
+                            apply_ui_scale(ctx, app_data.settings.ui_scale);
+                        }
+                        ui.small(ui_scale_hint_text);
+                    });
+                    ui.end_row();
+
+                    ui.label(font_size_label_text);
+                    let font_size = ui.add(egui::Slider::new(&mut app_data.settings.font_size, FONT_SIZE_RANGE).step_by(0.5).suffix(" pt"));
+                    if font_size.changed() {
+                        apply_font_size(ctx, app_data.settings.font_size);
+                    }
+                    ui.end_row();
+
+                    ui.label(previews_label_text);
+                    ui.checkbox(&mut app_data.settings.show_media_previews, previews_checkbox_text)
+                        .on_hover_text(previews_hint_text);
+                    ui.end_row();
+
+                    ui.label(markdown_label_text);
+                    if ui.checkbox(&mut app_data.settings.render_markdown, markdown_checkbox_text)
+                        .on_hover_text(markdown_hint_text)
+                        .changed()
+                    {
+                        apply_markdown_settings(&app_data.settings);
+                    }
+                    ui.end_row();
+                });
+        });
+
+        // テーマはその場で切り替える
+        apply_theme_preference(ctx, app_data.settings.theme);
+
+        ui.add_space(15.0);
+        card_frame.show(ui, |ui| {
+            ui.heading(relays_heading_text);
+            ui.add_space(10.0);
+            ui.label(discover_relays_label_text);
+            ui.add_space(5.0);
+            egui::ScrollArea::vertical().id_salt("discover_editor_scroll").max_height(80.0).show(ui, |ui| {
+                ui.add(egui::TextEdit::multiline(&mut app_data.settings.discover_relays)
+                    .desired_width(ui.available_width()));
+            });
+
+            ui.add_space(15.0);
+            ui.label(default_relays_label_text);
+            ui.add_space(5.0);
+            egui::ScrollArea::vertical().id_salt("default_editor_scroll").max_height(80.0).show(ui, |ui| {
+                ui.add(egui::TextEdit::multiline(&mut app_data.settings.default_relays)
+                    .desired_width(ui.available_width()));
+            });
+
+            ui.add_space(15.0);
+            ui.label(search_relays_label_text);
+            ui.add_space(5.0);
+            egui::ScrollArea::vertical().id_salt("search_editor_scroll").max_height(80.0).show(ui, |ui| {
+                ui.add(egui::TextEdit::multiline(&mut app_data.settings.search_relays)
+                    .desired_width(ui.available_width()));
+            });
+        });
+
+        ui.add_space(15.0);
+        card_frame.show(ui, |ui| {
+            ui.heading(network_heading_text);
+            ui.add_space(10.0);
+            egui::Grid::new("network_settings_grid")
+                .num_columns(2)
+                .spacing([20.0, 10.0])
+                .show(ui, |ui| {
+                    ui.label(relay_timeout_label_text);
+                    ui.add(egui::DragValue::new(&mut app_data.settings.relay_timeout_secs).range(1..=120));
+                    ui.end_row();
+
+                    ui.label(discovery_timeout_label_text);
+                    ui.add(egui::DragValue::new(&mut app_data.settings.discovery_timeout_secs).range(1..=120));
+                    ui.end_row();
+
+                    ui.label(connect_wait_label_text);
+                    ui.add(egui::DragValue::new(&mut app_data.settings.connect_wait_secs).range(0..=60));
+                    ui.end_row();
+
+                    ui.label(fetch_retry_label_text);
+                    ui.add(egui::DragValue::new(&mut app_data.settings.fetch_retry_count).range(0..=5));
+                    ui.end_row();
+
+                    ui.label(timeline_limit_label_text);
+                    ui.add(egui::DragValue::new(&mut app_data.settings.timeline_limit).range(1..=500));
+                    ui.end_row();
+
+                    ui.label(global_feed_label_text);
+                    ui.checkbox(&mut app_data.settings.global_feed_enabled, global_feed_checkbox_text)
+                        .on_hover_text(global_feed_hint_text);
+                    ui.end_row();
+
+                    ui.label(pow_difficulty_label_text);
+                    ui.add(egui::DragValue::new(&mut app_data.settings.pow_difficulty).range(0..=32))
+                        .on_hover_text(pow_difficulty_hint_text);
+                    ui.end_row();
+
+                    ui.label(undo_send_label_text);
+                    ui.horizontal(|ui| {
+                        ui.radio_value(&mut app_data.settings.undo_send_secs, 0, undo_send_off_text);
+                        for secs in [5, 10, 15] {
+                            ui.radio_value(&mut app_data.settings.undo_send_secs, secs, tr!("{}秒", secs));
+                        }
+                    })
+                    .response
+                    .on_hover_text(undo_send_hint_text);
+                    ui.end_row();
+
+                    ui.label(opentimestamps_label_text);
+                    ui.checkbox(&mut app_data.settings.opentimestamps_enabled, opentimestamps_checkbox_text)
+                        .on_hover_text(opentimestamps_hint_text);
+                    ui.end_row();
+                });
+            ui.add_space(5.0);
+            ui.small(relay_changes_hint_text);
+        });
+
+        ui.add_space(15.0);
+        card_frame.show(ui, |ui| {
+            ui.heading(proxy_heading_text);
+            ui.add_space(10.0);
+            ui.horizontal(|ui| {
+                ui.label(proxy_label_text);
+                ui.add(egui::TextEdit::singleline(&mut app_data.settings.proxy)
+                    .hint_text(proxy_hint_text)
+                    .desired_width(200.0));
+            });
+            ui.checkbox(&mut app_data.settings.tor_only, tor_only_checkbox_text);
+            ui.add_space(5.0);
+            ui.small(proxy_changes_hint_text);
+        });
+
+        ui.add_space(15.0);
+        card_frame.show(ui, |ui| {
+            ui.heading(presets_heading_text);
+            ui.add_space(10.0);
+            let mut preset_to_remove: Option<usize> = None;
+            for (index, preset) in app_data.settings.status_presets.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.add(egui::TextEdit::singleline(preset).desired_width(200.0));
+                    if ui.small_button(remove_preset_button_text).clicked() {
+                        preset_to_remove = Some(index);
+                    }
+                });
+            }
+            if let Some(index) = preset_to_remove {
+                app_data.settings.status_presets.remove(index);
+            }
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut app_data.new_preset_input)
+                    .hint_text(new_preset_hint_text)
+                    .desired_width(200.0));
+                let new_preset = app_data.new_preset_input.trim().to_string();
+                if ui.add_enabled(!new_preset.is_empty(), egui::Button::new(add_preset_button_text)).clicked() {
+                    app_data.settings.status_presets.push(new_preset);
+                    app_data.new_preset_input.clear();
+                }
+            });
+            ui.add_space(5.0);
+            ui.small(presets_hint_text);
+        });
+
+        ui.add_space(15.0);
+        card_frame.show(ui, |ui| {
+            ui.heading(muted_words_heading_text);
+            ui.add_space(10.0);
+            egui::ScrollArea::vertical().id_salt("muted_words_editor_scroll").max_height(80.0).show(ui, |ui| {
+                ui.add(egui::TextEdit::multiline(&mut app_data.settings.muted_words)
+                    .desired_width(ui.available_width()));
+            });
+            ui.add_space(5.0);
+            ui.small(muted_words_hint_text);
+        });
+
+        ui.add_space(15.0);
+        card_frame.show(ui, |ui| {
+            ui.heading(labels_heading_text);
+            ui.add_space(10.0);
+            ui.label(trusted_labelers_label_text);
+            egui::ScrollArea::vertical().id_salt("trusted_labelers_editor_scroll").max_height(80.0).show(ui, |ui| {
+                ui.add(egui::TextEdit::multiline(&mut app_data.settings.trusted_labelers)
+                    .desired_rows(2)
+                    .desired_width(ui.available_width()));
+            });
+            ui.columns(2, |columns| {
+                columns[0].label(hidden_labels_label_text);
+                columns[0].add(egui::TextEdit::multiline(&mut app_data.settings.hidden_labels)
+                    .desired_rows(3)
+                    .desired_width(f32::INFINITY));
+                columns[1].label(collapsed_labels_label_text);
+                columns[1].add(egui::TextEdit::multiline(&mut app_data.settings.collapsed_labels)
+                    .desired_rows(3)
+                    .desired_width(f32::INFINITY));
+            });
+            ui.add_space(5.0);
+            ui.small(labels_hint_text);
+        });
+
+        ui.add_space(15.0);
+        card_frame.show(ui, |ui| {
+            ui.heading(wot_heading_text);
+            ui.add_space(10.0);
+            ui.checkbox(&mut app_data.settings.wot_enabled, wot_checkbox_text);
+            ui.add_enabled_ui(app_data.settings.wot_enabled, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(wot_min_follows_label_text);
+                    ui.add(egui::DragValue::new(&mut app_data.settings.wot_min_follows).range(1..=50));
+                    if app_data.is_logged_in && !app_data.wot_loaded {
+                        ui.spinner();
+                        ui.label(wot_loading_text);
+                    }
+                });
+            });
+            ui.add_space(5.0);
+            ui.small(wot_hint_text);
+        });
+
+        ui.add_space(15.0);
+        card_frame.show(ui, |ui| {
+            ui.heading(integrations_heading_text);
+            ui.add_space(10.0);
+            ui.add_enabled(
+                now_playing::is_supported(),
+                egui::Checkbox::new(&mut app_data.settings.now_playing_enabled, now_playing_checkbox_text),
+            );
+            ui.add_space(5.0);
+            if now_playing::is_supported() {
+                ui.small(now_playing_hint_text);
+            } else {
+                ui.small(now_playing_unsupported_text);
+            }
+        });
+
+        ui.add_space(15.0);
+        card_frame.show(ui, |ui| {
+            ui.heading(media_heading_text);
+            ui.add_space(10.0);
+            ui.horizontal(|ui| {
+                ui.label(media_server_label_text);
+                ui.add(egui::TextEdit::singleline(&mut app_data.settings.media_server_url)
+                    .hint_text("https://nostr.build")
+                    .desired_width(250.0));
+            });
+            ui.horizontal(|ui| {
+                ui.label(media_server_kind_label_text);
+                ui.radio_value(&mut app_data.settings.media_server_kind, MediaServerKind::Nip96, "NIP-96");
+                ui.radio_value(&mut app_data.settings.media_server_kind, MediaServerKind::Blossom, "Blossom");
+            });
+            ui.add_space(5.0);
+            ui.small(media_server_hint_text);
+        });
+
+        ui.add_space(15.0);
+        card_frame.show(ui, |ui| {
+            ui.heading(notifications_heading_text);
+            ui.add_space(10.0);
+            ui.add_enabled_ui(desktop_notify::is_supported(), |ui| {
+                ui.checkbox(&mut app_data.settings.notify_direct_messages, notify_direct_messages_checkbox_text);
+                ui.checkbox(&mut app_data.settings.notify_mentions, notify_mentions_checkbox_text);
+                ui.checkbox(&mut app_data.settings.notify_starred_statuses, notify_starred_statuses_checkbox_text);
+            });
+            ui.add_space(5.0);
+            if desktop_notify::is_supported() {
+                ui.small(notifications_hint_text);
+            } else {
+                ui.small(notifications_unsupported_text);
+            }
+            ui.add_space(10.0);
+            ui.checkbox(&mut app_data.settings.send_read_receipts, send_read_receipts_checkbox_text);
+            ui.checkbox(&mut app_data.settings.send_typing_indicators, send_typing_checkbox_text)
+                .on_hover_text(send_typing_hint_text);
+        });
+
+        ui.add_space(15.0);
+        card_frame.show(ui, |ui| {
+            ui.heading(security_heading_text);
+            ui.add_space(10.0);
+            ui.horizontal(|ui| {
+                ui.label(auto_lock_label_text);
+                ui.add(egui::DragValue::new(&mut app_data.settings.auto_lock_minutes).range(0..=1440))
+                    .on_hover_text(auto_lock_hint_text);
+            });
+            ui.add_space(10.0);
+            ui.checkbox(&mut app_data.settings.confirm_before_signing, confirm_signing_checkbox_text)
+                .on_hover_text(confirm_signing_hint_text);
+            if app_data.settings.confirm_before_signing {
+                ui.label(auto_approve_label_text);
+                if app_data.settings.auto_approve_kinds.is_empty() {
+                    ui.small(no_auto_approve_text);
+                }
+                let mut kind_to_remove: Option<u16> = None;
+                for &kind in &app_data.settings.auto_approve_kinds {
+                    ui.horizontal(|ui| {
+                        ui.label(sign_gate::kind_label(Kind::from(kind)));
+                        if ui.small_button("✖").on_hover_text(remove_kind_hover_text).clicked() {
+                            kind_to_remove = Some(kind);
+                        }
+                    });
+                }
+                if let Some(kind) = kind_to_remove {
+                    app_data.settings.auto_approve_kinds.remove(&kind);
+                }
+                ui.horizontal(|ui| {
+                    ui.add(egui::TextEdit::singleline(&mut app_data.auto_approve_kind_input)
+                        .hint_text(auto_approve_kind_hint_text)
+                        .desired_width(80.0));
+                    if ui.button(add_kind_button_text).clicked() {
+                        match app_data.auto_approve_kind_input.trim().parse::<u16>() {
+                            Ok(kind) => {
+                                app_data.settings.auto_approve_kinds.insert(kind);
+                                app_data.auto_approve_kind_input.clear();
+                            }
+                            Err(_) => app_data.settings_status = Some(invalid_kind_text.to_string()),
+                        }
+                    }
+                });
+            }
+            // リモート署名者でログインしているときは、このアプリに鍵がないので出さない
+            let has_local_key = app_data.my_signer.as_ref().is_some_and(|signer| signer.remote().is_none());
+            if app_data.is_logged_in && has_local_key {
+                ui.add_space(10.0);
+                if ui.button(change_passphrase_button_text).clicked() {
+                    app_data.show_passphrase_dialog = true;
+                    app_data.passphrase_change_error = None;
+                }
+                ui.small(passphrase_hint_text);
+            }
+        });
+
+        ui.add_space(15.0);
+        ui.horizontal(|ui| {
+            if ui.button(egui::RichText::new(save_button_text).strong()).clicked() {
+                app_data.settings.status_presets.retain(|preset| !preset.trim().is_empty());
+                sign_gate::apply_sign_settings(&app_data.settings);
+                // プロキシの指定が不正なときや、指定したフォントを読み込めないときは保存しない
+                app_data.settings_status = Some(match apply_network_settings(&app_data.settings).and_then(|()| fonts::apply_fonts(ctx, &app_data.settings)) {
+                    Err(e) => e,
+                    Ok(()) => match save_settings(&app_data.settings) {
+                        Ok(()) => tr!("設定を保存しました").to_string(),
+                        Err(e) => tr!("設定の保存に失敗しました: {}", e),
+                    },
+                });
+                if app_data.is_logged_in {
+                    settings_sync::sync_if_changed(app_data, app_data_arc.clone(), &runtime_handle);
+                }
+            }
+            if ui.button(reset_button_text).clicked() {
+                // 手動リレーはリレータブで、星は友だちタブで管理しているので初期化の対象にしない
+                let manual_relays = std::mem::take(&mut app_data.settings.manual_relays);
+                let starred_friends = std::mem::take(&mut app_data.settings.starred_friends);
+                // 共有する設定をいつリレーと合わせたかは残す
+                let synced_settings_synced_at = app_data.settings.synced_settings_synced_at;
+                app_data.settings = Settings { manual_relays, starred_friends, synced_settings_synced_at, ..Default::default() };
+                app_data.settings_status = None;
+                apply_ui_scale(ctx, app_data.settings.ui_scale);
+                apply_font_size(ctx, app_data.settings.font_size);
+                apply_markdown_settings(&app_data.settings);
+            }
+            if let Some(status) = &app_data.settings_status {
+                ui.label(status);
+            }
+        });
+        ui.add_space(5.0);
+        ui.small(sync_hint_text);
+    });
+
+    draw_change_passphrase_dialog(ctx, app_data, app_data_arc, runtime_handle);
+}
+
+fn close_passphrase_dialog(app_data: &mut NostrStatusAppInternal) {
+    app_data.show_passphrase_dialog = false;
+    app_data.old_passphrase_input.zeroize();
+    app_data.new_passphrase_input.zeroize();
+    app_data.confirm_new_passphrase_input.zeroize();
+    app_data.passphrase_change_error = None;
+}
+
+// 今のパスフレーズで鍵を復号し、新しいパスフレーズで暗号化し直して設定ファイルを置き換える
+fn draw_change_passphrase_dialog(
+    ctx: &egui::Context,
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: tokio::runtime::Handle,
+) {
+    let window_title_text = tr!("パスフレーズを変更");
+    let old_passphrase_label_text = tr!("今のパスフレーズ:");
+    let new_passphrase_label_text = tr!("新しいパスフレーズ:");
+    let confirm_passphrase_label_text = tr!("新しいパスフレーズの確認:");
+    let change_button_text = tr!("変更");
+    let cancel_button_text = tr!("キャンセル");
+    let changing_text = tr!("暗号化し直しています...");
+    let empty_error_text = tr!("新しいパスフレーズを入力してください");
+    let mismatch_error_text = tr!("新しいパスフレーズが一致しません");
+    let unchanged_error_text = tr!("今と同じパスフレーズです");
+    let hint_text = tr!("ほかの端末にこのアカウントを保存している場合、そちらのパスフレーズは変わりません");
+
+    if !app_data.show_passphrase_dialog {
+        return;
+    }
+    let Some(public_key) = app_data.my_signer.as_ref().map(|signer| signer.public_key()) else {
+        close_passphrase_dialog(app_data);
+        return;
+    };
+    egui::Window::new(window_title_text)
+        .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            egui::Grid::new("change_passphrase_grid").num_columns(2).show(ui, |ui| {
+                ui.label(old_passphrase_label_text);
+                ui.add(egui::TextEdit::singleline(&mut app_data.old_passphrase_input).password(true));
+                ui.end_row();
+                ui.label(new_passphrase_label_text);
+                ui.add(egui::TextEdit::singleline(&mut app_data.new_passphrase_input).password(true));
+                ui.end_row();
+                ui.label(confirm_passphrase_label_text);
+                ui.add(egui::TextEdit::singleline(&mut app_data.confirm_new_passphrase_input).password(true));
+                ui.end_row();
+            });
+            ui.small(hint_text);
+            if let Some(error) = &app_data.passphrase_change_error {
+                ui.colored_label(egui::Color32::RED, error);
+            }
+            ui.add_space(5.0);
+            ui.horizontal(|ui| {
+                if ui.add_enabled(!app_data.is_changing_passphrase, egui::Button::new(change_button_text)).clicked() {
+                    let error = if app_data.new_passphrase_input.is_empty() {
+                        Some(empty_error_text)
+                    } else if app_data.new_passphrase_input != app_data.confirm_new_passphrase_input {
+                        Some(mismatch_error_text)
+                    } else if app_data.new_passphrase_input == app_data.old_passphrase_input {
+                        Some(unchanged_error_text)
+                    } else {
+                        None
+                    };
+                    app_data.passphrase_change_error = error.map(str::to_string);
+                    if error.is_none() {
+                        // 入力欄には残さず、使い終わったらメモリから消す
+                        let old_passphrase = Zeroizing::new(std::mem::take(&mut app_data.old_passphrase_input));
+                        let new_passphrase = Zeroizing::new(std::mem::take(&mut app_data.new_passphrase_input));
+                        app_data.confirm_new_passphrase_input.zeroize();
+                        app_data.is_changing_passphrase = true;
+                        let app_data_arc = app_data_arc.clone();
+                        // scrypt での暗号化に時間がかかるので、画面を止めないよう別のスレッドで行う
+                        runtime_handle.spawn_blocking(move || {
+                            let result = accounts::change_passphrase(public_key, &old_passphrase, &new_passphrase);
+                            let mut app_data = app_data_arc.lock().unwrap();
+                            app_data.is_changing_passphrase = false;
+                            match result {
+                                Ok(()) => {
+                                    close_passphrase_dialog(&mut app_data);
+                                    // 保存していた NWC の接続先も暗号化し直したので、そのまま使える
+                                    app_data.notify(tr!("パスフレーズを変更しました"));
+                                }
+                                Err(e) => {
+                                    error!("Failed to change the passphrase: {e}");
+                                    app_data.passphrase_change_error = Some(tr!("変更できませんでした。設定ファイルは元のままです: {}", e));
+                                }
+                            }
+                            app_data.should_repaint = true;
+                        });
+                    }
+                }
+                if ui.add_enabled(!app_data.is_changing_passphrase, egui::Button::new(cancel_button_text)).clicked() {
+                    close_passphrase_dialog(app_data);
+                }
+                if app_data.is_changing_passphrase {
+                    ui.spinner();
+                    ui.label(changing_text);
+                }
+            });
+        });
+}
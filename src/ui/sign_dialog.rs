@@ -0,0 +1,91 @@
+use eframe::egui;
+use tracing::{error, info};
+
+use crate::{i18n::tr, settings::save_settings, sign_gate, types::*};
+
+// 署名する前の確認のダイアログ。どのタブからでも答えられるよう、ここで描く
+pub fn draw_sign_dialog(ctx: &egui::Context, app_data: &mut NostrStatusAppInternal) {
+    let window_title_text = tr!("署名の確認");
+    let description_text = tr!("次のイベントに署名しようとしています。内容を確認してください");
+    let kind_label_text = tr!("種類:");
+    let tags_label_text = tr!("タグ:");
+    let content_label_text = tr!("本文:");
+    let no_tags_text = tr!("(なし)");
+    let empty_content_text = tr!("(空)");
+    let always_allow_text = tr!("この種類は今後確認せずに署名する");
+    let approve_button_text = tr!("署名する");
+    let reject_button_text = tr!("拒否");
+    let more_pending_text = tr!("件が確認を待っています");
+
+    sign_gate::set_repaint_context(ctx);
+    let Some((unsigned, pending_count)) = sign_gate::front() else {
+        return;
+    };
+
+    let mut response: Option<bool> = None;
+    egui::Window::new(window_title_text)
+        .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.label(description_text);
+            ui.add_space(5.0);
+            ui.horizontal(|ui| {
+                ui.label(kind_label_text);
+                ui.label(egui::RichText::new(sign_gate::kind_label(unsigned.kind)).strong());
+            });
+            ui.label(tags_label_text);
+            egui::ScrollArea::vertical().id_salt("sign_dialog_tags").max_height(120.0).show(ui, |ui| {
+                if unsigned.tags.is_empty() {
+                    ui.small(no_tags_text);
+                }
+                for tag in unsigned.tags.iter() {
+                    ui.monospace(tag.as_slice().join(", "));
+                }
+            });
+            ui.label(content_label_text);
+            egui::ScrollArea::vertical().id_salt("sign_dialog_content").max_height(200.0).show(ui, |ui| {
+                if unsigned.content.is_empty() {
+                    ui.small(empty_content_text);
+                } else {
+                    ui.add(egui::Label::new(&unsigned.content).wrap());
+                }
+            });
+            ui.add_space(5.0);
+            ui.checkbox(&mut app_data.sign_always_allow_kind, always_allow_text);
+            ui.add_space(5.0);
+            ui.horizontal(|ui| {
+                if ui.button(egui::RichText::new(approve_button_text).strong()).clicked() {
+                    response = Some(true);
+                }
+                if ui.button(reject_button_text).clicked() {
+                    response = Some(false);
+                }
+            });
+            if pending_count > 1 {
+                ui.small(tr!("ほかに {} {}", pending_count - 1, more_pending_text));
+            }
+        });
+
+    let Some(approved) = response else {
+        return;
+    };
+    if approved && app_data.sign_always_allow_kind {
+        info!("Signing {} without confirmation from now on", unsigned.kind);
+        app_data.settings.auto_approve_kinds.insert(unsigned.kind.as_u16());
+        sign_gate::apply_sign_settings(&app_data.settings);
+        if let Err(e) = save_settings(&app_data.settings) {
+            error!("Failed to save settings: {e}");
+        }
+    }
+    app_data.sign_always_allow_kind = false;
+    sign_gate::respond(approved);
+    // 許可した種類のものが続けて待っていれば、そのまま通す
+    while let Some((next, _)) = sign_gate::front() {
+        if !app_data.settings.auto_approve_kinds.contains(&next.kind.as_u16()) {
+            break;
+        }
+        sign_gate::respond(true);
+    }
+    app_data.should_repaint = true;
+}
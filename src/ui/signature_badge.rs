@@ -0,0 +1,23 @@
+use eframe::egui;
+use nostr::EventId;
+
+use crate::event_check;
+use crate::i18n::tr;
+
+// 投稿の横に署名の確認結果を小さく表示する。確認済みならマウスを置くと届いたリレーを出す
+pub fn draw_signature_badge(ui: &mut egui::Ui, event_id: &EventId) {
+    let stored_hover_text = tr!("署名を確認済み (保存済みのイベント)");
+    let invalid_hover_text = tr!("署名が不正なイベントです");
+    let unchecked_hover_text = tr!("署名を未確認 (キャッシュから表示しています)");
+
+    let (text, color, hover_text) = match event_check::event_check(event_id) {
+        Some(check) if !check.valid => ("⚠", ui.visuals().warn_fg_color, invalid_hover_text.to_string()),
+        Some(check) if check.relays.is_empty() => ("✔", egui::Color32::from_rgb(0x06, 0xC7, 0x55), stored_hover_text.to_string()),
+        Some(check) => {
+            let relays = check.relays.into_iter().collect::<Vec<_>>().join("\n");
+            ("✔", egui::Color32::from_rgb(0x06, 0xC7, 0x55), tr!("署名を確認済み。受信したリレー:\n{}", relays))
+        }
+        None => ("?", egui::Color32::GRAY, unchecked_hover_text.to_string()),
+    };
+    ui.label(egui::RichText::new(text).small().color(color)).on_hover_text(hover_text);
+}
@@ -0,0 +1,67 @@
+use chrono::{DateTime, Datelike, Local};
+use eframe::egui;
+use nostr::Timestamp;
+use std::time::Duration;
+
+use crate::{
+    i18n::{self, tr},
+    types::Language,
+    ui::chats_view::to_local,
+};
+
+const WEEKDAYS_JA: [&str; 7] = ["日", "月", "火", "水", "木", "金", "土"];
+// 「○分前」のように分単位で変わる表示を描き直す間隔
+const MINUTE_REPAINT_INTERVAL: Duration = Duration::from_secs(30);
+
+// 曜日付きの日付。トークの日付の区切りと、時刻の詳しい表示で使う
+pub fn format_date(date: &DateTime<Local>) -> String {
+    match i18n::language() {
+        Language::Japanese => {
+            let weekday = WEEKDAYS_JA[date.weekday().num_days_from_sunday() as usize];
+            format!("{}({})", date.format("%Y/%m/%d"), weekday)
+        }
+        Language::English => date.format("%a, %b %-d, %Y").to_string(),
+    }
+}
+
+// 秒まで入れた時刻。相対表示の上にマウスを置いたときに出す
+pub fn format_absolute(timestamp: Timestamp) -> String {
+    let local = to_local(timestamp);
+    format!("{} {}", format_date(&local), local.format("%H:%M:%S"))
+}
+
+// 「3分前」「昨日 14:02」のように今からの近さに合わせた時刻
+pub fn format_relative(timestamp: Timestamp) -> String {
+    let local = to_local(timestamp);
+    let now = Local::now();
+    let seconds = now.signed_duration_since(local).num_seconds();
+    // 端末の時計のずれで少し先の時刻になっていても「たった今」にする
+    if seconds.abs() < 60 {
+        return tr!("たった今").to_string();
+    }
+    if (60..60 * 60).contains(&seconds) {
+        return tr!("{}分前", seconds / 60);
+    }
+    if seconds > 0 && local.date_naive() == now.date_naive() {
+        return tr!("{}時間前", seconds / (60 * 60));
+    }
+    let time = local.format("%H:%M").to_string();
+    if now.date_naive().pred_opt() == Some(local.date_naive()) {
+        return tr!("昨日 {}", time);
+    }
+    match (i18n::language(), local.year() == now.year()) {
+        (Language::Japanese, true) => format!("{} {time}", local.format("%-m月%-d日")),
+        (Language::Japanese, false) => local.format("%Y年%-m月%-d日").to_string(),
+        (Language::English, true) => format!("{}, {time}", local.format("%b %-d")),
+        (Language::English, false) => local.format("%b %-d, %Y").to_string(),
+    }
+}
+
+// 投稿などの時刻を小さく灰色で描く。マウスを置くと秒までの時刻を出す
+pub fn draw_timestamp(ui: &mut egui::Ui, timestamp: Timestamp) -> egui::Response {
+    if Timestamp::now().as_u64().saturating_sub(timestamp.as_u64()) < 60 * 60 {
+        ui.ctx().request_repaint_after(MINUTE_REPAINT_INTERVAL);
+    }
+    ui.label(egui::RichText::new(format_relative(timestamp)).small().color(egui::Color32::GRAY))
+        .on_hover_text(format_absolute(timestamp))
+}
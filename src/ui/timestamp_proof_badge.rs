@@ -0,0 +1,20 @@
+use eframe::egui;
+
+use crate::i18n::tr;
+use crate::types::{TimestampProof, TimestampProofState};
+
+// 自分のステータスの横に OpenTimestamps (NIP-03) の証明の状態を小さく表示する。証明を作っていなければ何も出さない
+pub fn draw_timestamp_proof_badge(ui: &mut egui::Ui, proof: Option<&TimestampProof>) {
+    let pending_hover_text = tr!("タイムスタンプの証明: ビットコインのブロックに記録されるのを待っています");
+    let attested_hover_text = tr!("タイムスタンプの証明: ビットコインのブロックに記録され、証明 (kind 1040) を公開しました。記録はカレンダーの応答のみで確認しています");
+
+    let Some(proof) = proof else {
+        return;
+    };
+    let (color, hover_text) = match &proof.state {
+        TimestampProofState::Pending => (egui::Color32::GRAY, pending_hover_text.to_string()),
+        TimestampProofState::Attested => (egui::Color32::from_rgb(247, 147, 26), attested_hover_text.to_string()),
+        TimestampProofState::Failed(e) => (ui.visuals().warn_fg_color, tr!("タイムスタンプの証明を作れませんでした: {}", e)),
+    };
+    ui.label(egui::RichText::new("OTS").small().color(color)).on_hover_text(hover_text);
+}
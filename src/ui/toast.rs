@@ -0,0 +1,118 @@
+use eframe::egui;
+use std::time::Duration;
+
+use crate::i18n::tr;
+use crate::outbox;
+use crate::pow;
+use crate::types::{NostrStatusAppInternal, ToastKind};
+
+// 通知を表示しておく時間。エラーは読めるように長めにする
+const INFO_TOAST_SECS: u64 = 4;
+const ERROR_TOAST_SECS: u64 = 8;
+
+// 直近のエラーを画面上部に表示する。閉じるまで残す
+pub fn draw_error_banner(ui: &mut egui::Ui, app_data: &mut NostrStatusAppInternal) {
+    let dismiss_button_text = tr!("閉じる");
+
+    let Some(error) = app_data.last_error.clone() else {
+        return;
+    };
+    let error_color = ui.visuals().error_fg_color;
+    egui::Frame::new()
+        .inner_margin(egui::Margin::same(8))
+        .corner_radius(6.0)
+        .stroke(egui::Stroke::new(1.0, error_color))
+        .show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.colored_label(error_color, format!("⚠ {error}"));
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui.small_button(dismiss_button_text).clicked() {
+                        app_data.last_error = None;
+                    }
+                });
+            });
+        });
+    ui.add_space(10.0);
+}
+
+// 通知を右下に積み重ねて表示し、時間が来たら消す。クリックでも消せる
+// 送信を取り消せる間のものと PoW を計算している投稿は、「取り消す」「中止」を付けて先頭に出す
+pub fn draw_toasts(ctx: &egui::Context, app_data: &mut NostrStatusAppInternal) {
+    let undo_button_text = tr!("取り消す");
+    let cancel_pow_button_text = tr!("中止");
+
+    app_data.toasts.retain(|toast| {
+        let lifetime = match toast.kind {
+            ToastKind::Info => INFO_TOAST_SECS,
+            ToastKind::Error => ERROR_TOAST_SECS,
+        };
+        toast.created_at.elapsed() < Duration::from_secs(lifetime)
+    });
+    if app_data.toasts.is_empty() && app_data.held_sends.is_empty() && app_data.pow_jobs.is_empty() {
+        return;
+    }
+
+    let mut dismissed: Option<usize> = None;
+    let mut undone: Option<u64> = None;
+    let mut cancelled_pow: Option<u64> = None;
+    egui::Area::new(egui::Id::new("toasts"))
+        .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-15.0, -15.0))
+        .order(egui::Order::Foreground)
+        .show(ctx, |ui| {
+            for held in &app_data.held_sends {
+                let remaining = held.send_at.saturating_duration_since(std::time::Instant::now()).as_secs() + 1;
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.set_max_width(320.0);
+                    ui.horizontal(|ui| {
+                        ui.label(tr!("{} (あと {}秒)", held.description, remaining));
+                        if ui.button(undo_button_text).clicked() {
+                            undone = Some(held.id);
+                        }
+                    });
+                });
+                ui.add_space(6.0);
+            }
+            for job in &app_data.pow_jobs {
+                let attempts = job.attempts.load(std::sync::atomic::Ordering::Relaxed);
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.set_max_width(320.0);
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label(tr!("PoW を計算中 (難易度 {}、{}回試行)", job.difficulty, attempts));
+                        if ui.button(cancel_pow_button_text).clicked() {
+                            cancelled_pow = Some(job.id);
+                        }
+                    });
+                });
+                ui.add_space(6.0);
+            }
+            for (index, toast) in app_data.toasts.iter().enumerate() {
+                let text_color = match toast.kind {
+                    ToastKind::Info => ui.visuals().text_color(),
+                    ToastKind::Error => ui.visuals().error_fg_color,
+                };
+                let response = egui::Frame::popup(ui.style())
+                    .show(ui, |ui| {
+                        ui.set_max_width(320.0);
+                        ui.colored_label(text_color, &toast.message);
+                    })
+                    .response
+                    .interact(egui::Sense::click());
+                if response.clicked() {
+                    dismissed = Some(index);
+                }
+                ui.add_space(6.0);
+            }
+        });
+    if let Some(index) = dismissed {
+        app_data.toasts.remove(index);
+    }
+    if let Some(id) = undone {
+        outbox::undo(app_data, id);
+    }
+    if let Some(id) = cancelled_pow {
+        pow::cancel(app_data, id);
+    }
+    // 表示時間が過ぎたら消えるように再描画を予約する
+    ctx.request_repaint_after(Duration::from_millis(500));
+}
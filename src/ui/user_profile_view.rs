@@ -0,0 +1,366 @@
+use eframe::egui;
+use nostr::{EventId, PublicKey};
+use nostr::nips::nip19::ToBech32;
+use nostr::nips::nip39::Identity;
+use std::sync::{Arc, Mutex};
+use tracing::error;
+
+use crate::{
+    badges,
+    i18n::tr,
+    nostr_client::{fetch_profile_counts, fetch_user_profile},
+    note,
+    types::*,
+    ui::{badge_dialog, content_warning, friends_view, identity_badge, image_cache, link_preview, link_view, nip05_badge, report_dialog, signature_badge, timestamp, zap},
+};
+
+// 他のユーザーのプロフィールページを開き、内容を取得する。自分の場合はプロフィールタブを開く
+pub fn open_user_profile(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    public_key: PublicKey,
+) {
+    if app_data.my_signer.as_ref().map(|signer| signer.public_key()) == Some(public_key) {
+        app_data.current_tab = AppTab::Profile;
+        return;
+    }
+    app_data.current_tab = AppTab::UserProfile(public_key);
+    app_data.should_repaint = true;
+    if app_data.user_profile.as_ref().map(|profile| profile.public_key) == Some(public_key) {
+        return;
+    }
+    spawn_fetch_user_profile(app_data, app_data_arc, runtime_handle, public_key);
+}
+
+fn spawn_fetch_user_profile(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    public_key: PublicKey,
+) {
+    let Some(client) = app_data.nostr_client.clone() else {
+        return;
+    };
+    app_data.user_profile = None;
+    app_data.is_loading_user_profile = true;
+    runtime_handle.spawn(async move {
+        let result = fetch_user_profile(&client, public_key).await;
+        {
+            let mut app_data = app_data_arc.lock().unwrap();
+            // 取得中に別のプロフィールが開かれていたら結果は使わない
+            if app_data.current_tab != AppTab::UserProfile(public_key) {
+                return;
+            }
+            app_data.is_loading_user_profile = false;
+            app_data.should_repaint = true;
+            match result {
+                Ok(profile) => {
+                    app_data.profiles.insert(public_key, profile.metadata.clone());
+                    app_data.user_profile = Some(profile);
+                }
+                Err(e) => {
+                    error!("Failed to fetch the user profile: {e}");
+                    app_data.report_error(tr!("プロフィールを取得できませんでした: {}", e));
+                    return;
+                }
+            }
+        }
+
+        // 件数は返事の遅いリレーもあるので、プロフィールを表示してから数える。バッジも定義と授与をたどるので後から出す
+        let (counts, badges) = tokio::join!(
+            fetch_profile_counts(&client, public_key),
+            badges::fetch_profile_badges(&client, public_key),
+        );
+        let badges = badges.unwrap_or_else(|e| {
+            error!("Failed to fetch the profile badges: {e}");
+            Vec::new()
+        });
+        let mut app_data = app_data_arc.lock().unwrap();
+        if let Some(profile) = app_data.user_profile.as_mut().filter(|profile| profile.public_key == public_key) {
+            profile.counts = Some(counts);
+            profile.badges = Some(badges);
+            app_data.should_repaint = true;
+        }
+    });
+}
+
+// NIP-65 の用途を表示用の文字列にする
+fn relay_policy_label(policy: Option<&str>) -> &'static str {
+    match policy {
+        Some("read") => tr!("読み取り"),
+        Some("write") => tr!("書き込み"),
+        _ => tr!("読み書き"),
+    }
+}
+
+pub fn draw_user_profile_view(
+    ui: &mut egui::Ui,
+    ctx: &egui::Context,
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: tokio::runtime::Handle,
+    public_key: PublicKey,
+) {
+    let loading_text = tr!("読み込み中...");
+    let reload_button_text = tr!("🔄 再読み込み");
+    let copy_npub_button_text = tr!("npub をコピー");
+    let follow_button_text = tr!("フォロー");
+    let unfollow_button_text = tr!("アンフォロー");
+    let message_button_text = tr!("メッセージ");
+    let zap_button_text = "⚡ ZAP";
+    let report_button_text = tr!("通報");
+    let award_badge_button_text = tr!("🏅 バッジを贈る");
+    let badges_heading_text = tr!("バッジ");
+    let issuer_label_text = tr!("発行:");
+    let status_heading_text = tr!("今のステータス");
+    let no_status_text = tr!("ステータスはありません");
+    let relays_heading_text = tr!("リレー");
+    let no_relays_text = tr!("リレーリスト (NIP-65) は公開されていません");
+    let notes_heading_text = tr!("最近のノート");
+    let no_notes_text = tr!("まだノートはありません");
+    let following_count_text = tr!("フォロー中");
+    let follower_count_text = tr!("フォロワー");
+    let zap_count_text = "ZAP";
+    let counting_text = "…";
+    let unknown_count_text = "—";
+    let no_contact_list_hover_text = tr!("フォローリストは公開されていません");
+    let count_unsupported_hover_text = tr!("接続中のリレーが件数の問い合わせ (NIP-45) に対応していません");
+
+    let card_frame = egui::Frame {
+        inner_margin: egui::Margin::same(12),
+        corner_radius: 8.0.into(),
+        shadow: eframe::epaint::Shadow::NONE,
+        fill: app_data.current_theme.card_background_color(),
+        ..Default::default()
+    };
+
+    if app_data.is_loading_user_profile {
+        ui.horizontal(|ui| {
+            ui.spinner();
+            ui.label(loading_text);
+        });
+        return;
+    }
+    let Some(profile) = app_data.user_profile.clone().filter(|profile| profile.public_key == public_key) else {
+        if ui.button(reload_button_text).clicked() {
+            spawn_fetch_user_profile(app_data, app_data_arc, &runtime_handle, public_key);
+        }
+        return;
+    };
+
+    let mut urls_to_load: Vec<(String, ImageKind)> = Vec::new();
+    let mut link_to_open: Option<NostrLink> = None;
+    let mut links_to_preview: Vec<String> = Vec::new();
+    let mut follow_change: Option<bool> = None;
+    let mut open_chat = false;
+    let mut open_report = false;
+    let mut open_badge_award = false;
+    let mut zap_target: Option<ZapTarget> = None;
+    let mut reload = false;
+    let mut nip05_to_verify: Vec<(PublicKey, String)> = Vec::new();
+    let mut identities_to_verify: Vec<(PublicKey, Identity)> = Vec::new();
+    let mut content_warning_toggle: Option<EventId> = None;
+    let metadata = &profile.metadata;
+
+    egui::ScrollArea::vertical()
+        .id_salt("user_profile_scroll_area")
+        .show(ui, |ui| {
+            card_frame.show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    image_cache::draw_user_avatar(
+                        ui,
+                        &app_data.image_cache,
+                        &public_key,
+                        &metadata.picture,
+                        egui::vec2(64.0, 64.0),
+                        32.0,
+                        &mut urls_to_load,
+                    );
+                    ui.add_space(8.0);
+                    ui.vertical(|ui| {
+                        ui.heading(metadata.display_name_or_npub(&public_key));
+                        nip05_badge::draw_nip05_badge(ui, &app_data.nip05_cache, &public_key, &metadata.nip05, &mut nip05_to_verify);
+                        identity_badge::draw_identities(ui, &app_data.identity_cache, &public_key, &profile.identities, &mut identities_to_verify);
+                        let Ok(npub) = public_key.to_bech32();
+                        ui.label(egui::RichText::new(npub).small().monospace().color(egui::Color32::GRAY));
+                    });
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
+                        if ui.button(reload_button_text).clicked() {
+                            reload = true;
+                        }
+                    });
+                });
+                if !metadata.about.is_empty() {
+                    ui.add_space(8.0);
+                    ui.horizontal_wrapped(|ui| {
+                        link_view::draw_text_with_links(ui, &metadata.about, ui.visuals().text_color(), &app_data.profiles, &mut link_to_open);
+                    });
+                }
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    let count_label = |ui: &mut egui::Ui, label: &str, count: Option<usize>, counted: bool, unknown_hover_text: &str| {
+                        let value = match count {
+                            Some(count) => count.to_string(),
+                            None if !counted => counting_text.to_string(),
+                            None => unknown_count_text.to_string(),
+                        };
+                        let response = ui.label(egui::RichText::new(format!("{label} {value}")).small());
+                        if counted && count.is_none() {
+                            response.on_hover_text(unknown_hover_text);
+                        }
+                    };
+                    count_label(ui, following_count_text, profile.following_count, true, no_contact_list_hover_text);
+                    let counts = profile.counts.unwrap_or_default();
+                    let counted = profile.counts.is_some();
+                    count_label(ui, follower_count_text, counts.followers, counted, count_unsupported_hover_text);
+                    count_label(ui, zap_count_text, counts.zaps, counted, count_unsupported_hover_text);
+                });
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    let is_followed = app_data.followed_pubkeys.contains(&public_key);
+                    let button_text = if is_followed { unfollow_button_text } else { follow_button_text };
+                    if ui.add_enabled(!app_data.is_loading, egui::Button::new(button_text)).clicked() {
+                        follow_change = Some(!is_followed);
+                    }
+                    if ui.button(message_button_text).clicked() {
+                        open_chat = true;
+                    }
+                    if (!metadata.lud16.is_empty() || !metadata.lud06.is_empty()) && ui.button(zap_button_text).clicked() {
+                        zap_target = Some(ZapTarget {
+                            pubkey: public_key,
+                            metadata: metadata.clone(),
+                            event: None,
+                        });
+                    }
+                    if ui.button(copy_npub_button_text).clicked() {
+                        let Ok(npub) = public_key.to_bech32();
+                        ui.ctx().copy_text(npub);
+                    }
+                    if ui.button(report_button_text).clicked() {
+                        open_report = true;
+                    }
+                    if ui.button(award_badge_button_text).clicked() {
+                        open_badge_award = true;
+                    }
+                });
+            });
+            ui.add_space(10.0);
+
+            // 受け取って表示しているバッジがなければカードごと出さない
+            if let Some(badges) = profile.badges.as_ref().filter(|badges| !badges.is_empty()) {
+                card_frame.show(ui, |ui| {
+                    ui.set_width(ui.available_width());
+                    ui.label(egui::RichText::new(badges_heading_text).strong());
+                    ui.add_space(5.0);
+                    ui.horizontal_wrapped(|ui| {
+                        for badge in badges {
+                            let issuer = app_data
+                                .profiles
+                                .get(&badge.issuer)
+                                .map(|metadata| metadata.display_name_or_npub(&badge.issuer))
+                                .unwrap_or_else(|| short_npub(&badge.issuer));
+                            badge_dialog::draw_badge_image(ui, &app_data.image_cache, badge, egui::vec2(40.0, 40.0), &mut urls_to_load)
+                                .on_hover_ui(|ui| {
+                                    let name = if badge.name.is_empty() { &badge.d_tag } else { &badge.name };
+                                    ui.label(egui::RichText::new(name).strong());
+                                    if !badge.description.is_empty() {
+                                        ui.label(&badge.description);
+                                    }
+                                    ui.label(egui::RichText::new(format!("{issuer_label_text} {issuer}")).small().color(egui::Color32::GRAY));
+                                });
+                        }
+                    });
+                });
+                ui.add_space(10.0);
+            }
+
+            card_frame.show(ui, |ui| {
+                ui.set_width(ui.available_width());
+                ui.label(egui::RichText::new(status_heading_text).strong());
+                ui.add_space(5.0);
+                if profile.statuses.is_empty() {
+                    ui.label(egui::RichText::new(no_status_text).color(egui::Color32::GRAY));
+                }
+                for status in &profile.statuses {
+                    let d_tag = status.tags.identifier().unwrap_or_default();
+                    ui.horizontal_wrapped(|ui| {
+                        ui.label(StatusGroup::from_d_tag(d_tag).icon());
+                        link_view::draw_text_with_links(ui, &status.content, ui.visuals().text_color(), &app_data.profiles, &mut link_to_open);
+                        timestamp::draw_timestamp(ui, status.created_at);
+                        signature_badge::draw_signature_badge(ui, &status.id);
+                    });
+                }
+            });
+            ui.add_space(10.0);
+
+            card_frame.show(ui, |ui| {
+                ui.set_width(ui.available_width());
+                ui.label(egui::RichText::new(relays_heading_text).strong());
+                ui.add_space(5.0);
+                if profile.relays.is_empty() {
+                    ui.label(egui::RichText::new(no_relays_text).color(egui::Color32::GRAY));
+                }
+                for (url, policy) in &profile.relays {
+                    ui.horizontal(|ui| {
+                        ui.label(egui::RichText::new(url).monospace());
+                        ui.label(egui::RichText::new(relay_policy_label(policy.as_deref())).small().color(egui::Color32::GRAY));
+                    });
+                }
+            });
+            ui.add_space(10.0);
+
+            card_frame.show(ui, |ui| {
+                ui.set_width(ui.available_width());
+                ui.label(egui::RichText::new(notes_heading_text).strong());
+                ui.add_space(5.0);
+                if profile.notes.is_empty() {
+                    ui.label(egui::RichText::new(no_notes_text).color(egui::Color32::GRAY));
+                }
+                for note in &profile.notes {
+                    ui.horizontal(|ui| {
+                        timestamp::draw_timestamp(ui, note.created_at);
+                        signature_badge::draw_signature_badge(ui, &note.id);
+                    });
+                    let content_warning = note::content_warning(note.tags.iter());
+                    if content_warning::draw_content_warning(ui, &app_data.revealed_content_warnings, note.id, content_warning.as_deref(), &mut content_warning_toggle) {
+                        ui.horizontal_wrapped(|ui| {
+                            link_view::draw_text_with_links(ui, &note.content, ui.visuals().text_color(), &app_data.profiles, &mut link_to_open);
+                        });
+                        link_preview::draw_previews(ui, app_data, &note.content, note.tags.iter(), &mut urls_to_load, &mut links_to_preview);
+                    }
+                    ui.separator();
+                }
+            });
+        });
+
+    if reload {
+        spawn_fetch_user_profile(app_data, app_data_arc.clone(), &runtime_handle, public_key);
+    }
+    if let Some(follow) = follow_change {
+        friends_view::spawn_contact_list_update(app_data, app_data_arc.clone(), &runtime_handle, public_key, None, follow);
+    }
+    if open_chat {
+        app_data.conversations.entry(public_key).or_default();
+        app_data.selected_conversation = Some(public_key);
+        app_data.current_tab = AppTab::Chats;
+        app_data.should_repaint = true;
+    }
+    if open_badge_award {
+        badge_dialog::open_badge_award_dialog(app_data, app_data_arc.clone(), &runtime_handle, public_key);
+    }
+    if open_report {
+        report_dialog::open_report_dialog(app_data, ReportTarget { public_key, event_id: None });
+    }
+    if let Some(target) = zap_target {
+        zap::open_zap_dialog(app_data, target);
+    }
+    if let Some(link) = link_to_open {
+        link_view::open_link(app_data, app_data_arc.clone(), &runtime_handle, link);
+    }
+    nip05_badge::verify_nip05s(app_data, app_data_arc.clone(), &runtime_handle, nip05_to_verify);
+    identity_badge::verify_identities(app_data, app_data_arc.clone(), &runtime_handle, identities_to_verify);
+    content_warning::toggle_revealed(&mut app_data.revealed_content_warnings, content_warning_toggle);
+    link_preview::fetch_link_previews(app_data, app_data_arc.clone(), &runtime_handle, links_to_preview);
+    image_cache::load_images(ctx, app_data, app_data_arc, &runtime_handle, urls_to_load);
+}
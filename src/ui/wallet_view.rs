@@ -1,17 +1,18 @@
 use eframe::egui;
-use nostr::nips::nip47::{NostrWalletConnectURI, Response};
+use nostr::nips::nip47::NostrWalletConnectURI;
 use nostr::{Event, Filter, JsonUtil, Kind, Keys, SingleLetterTag, TagKind};
 use nostr_sdk::Client;
-use serde_json;
-use std::fs;
 use std::str::FromStr;
 use std::sync::{Arc, Mutex};
 use tokio::runtime::Handle;
+use zeroize::Zeroizing;
 
+use crate::i18n::tr;
+use crate::network::{self, new_client};
 use crate::nostr_client::get_profile_metadata;
-use crate::types::{Config, NostrStatusAppInternal, ProfileMetadata, ZapReceipt};
-use crate::{nip49, CONFIG_FILE};
-use chrono::{DateTime, Utc};
+use crate::types::{NostrStatusAppInternal, ProfileMetadata, ZapReceipt};
+use crate::ui::timestamp;
+use crate::{accounts::{read_config, write_config}, nip49};
 use lightning_invoice::Bolt11Invoice;
 
 pub fn draw_wallet_view(
@@ -20,11 +21,11 @@ pub fn draw_wallet_view(
     app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
     runtime_handle: Handle,
 ) {
-    ui.heading("ウォレット");
+    ui.heading(tr!("ウォレット"));
     ui.add_space(10.0);
 
     if !app_data.is_logged_in {
-        ui.label("ウォレット機能を使うにはログインしてください。");
+        ui.label(tr!("ウォレット機能を使うにはログインしてください。"));
         return;
     }
 
@@ -46,17 +47,42 @@ fn draw_wallet_details(
     app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
     runtime_handle: Handle,
 ) {
-    ui.label("ウォレット接続済み");
+    ui.horizontal(|ui| {
+        ui.label(tr!("ウォレット接続済み"));
+        if ui.button(tr!("接続を解除")).clicked() {
+            match remove_nwc_from_config() {
+                Ok(()) => {
+                    let client_to_shutdown = app_data.nwc_client.take();
+                    app_data.nwc = None;
+                    app_data.nwc_uri_input.clear();
+                    app_data.zap_history.clear();
+                    app_data.nwc_error = None;
+                    if let Some(client) = client_to_shutdown {
+                        runtime_handle.spawn(async move {
+                            client.shutdown().await;
+                        });
+                    }
+                }
+                Err(e) => app_data.nwc_error = Some(tr!("接続の解除に失敗しました: {}", e)),
+            }
+        }
+    });
+    ui.add_space(5.0);
+    // ⚡ボタンを押したときにこの金額で即座にZAPする
+    ui.horizontal(|ui| {
+        ui.label(tr!("ワンクリックZAPの金額 (sats):"));
+        ui.add(egui::TextEdit::singleline(&mut app_data.default_zap_amount_input).desired_width(80.0));
+    });
     ui.add_space(10.0);
 
     ui.horizontal(|ui| {
-        let refresh_button = ui.add_enabled(!app_data.is_fetching_zap_history, egui::Button::new("履歴を更新"));
+        let refresh_button = ui.add_enabled(!app_data.is_fetching_zap_history, egui::Button::new(tr!("履歴を更新")));
         if refresh_button.clicked() {
             let app_data_clone = app_data_arc.clone();
             runtime_handle.spawn(async move {
                 if let Err(e) = get_zap_history(app_data_clone.clone()).await {
                     let mut app_data = app_data_clone.lock().unwrap();
-                    app_data.nwc_error = Some(format!("Zap履歴の取得エラー: {}", e));
+                    app_data.nwc_error = Some(tr!("Zap履歴の取得エラー: {}", e));
                 }
             });
         }
@@ -72,23 +98,19 @@ fn draw_wallet_details(
 
     egui::ScrollArea::vertical().show(ui, |ui| {
         if app_data.zap_history.is_empty() {
-            ui.label("Zap履歴はありません。");
+            ui.label(tr!("Zap履歴はありません。"));
         } else {
             for zap in &app_data.zap_history {
                 ui.horizontal(|ui| {
                     let name = if zap.recipient_metadata.name.is_empty() {
-                        "不明なユーザー"
+                        tr!("不明なユーザー")
                     } else {
                         &zap.recipient_metadata.name
                     };
                     ui.label(name);
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                         ui.label(format!("{} sats", zap.amount_msats / 1000));
-                        let date = DateTime::<Utc>::from_timestamp(zap.created_at.as_u64() as i64, 0)
-                            .unwrap()
-                            .format("%Y-%m-%d %H:%M")
-                            .to_string();
-                        ui.label(date);
+                        timestamp::draw_timestamp(ui, zap.created_at);
                     });
                 });
                 ui.separator();
@@ -103,24 +125,23 @@ fn draw_setup_view(
     app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
     runtime_handle: Handle,
 ) {
-    ui.label("Nostrウォレットに接続");
+    ui.label(tr!("Nostrウォレットに接続"));
     ui.add_space(5.0);
-    ui.label("Nostr Wallet ConnectのURIと、暗号化のためのメインパスフレーズを入力してください。");
+    ui.label(tr!("Nostr Wallet ConnectのURIと、暗号化のためのメインパスフレーズを入力してください。"));
 
     ui.horizontal(|ui| {
         ui.label("NWC URI:");
-        ui.text_edit_singleline(&mut app_data.nwc_uri_input);
+        ui.add(egui::TextEdit::singleline(&mut app_data.nwc_uri_input).password(true));
     });
 
     ui.horizontal(|ui| {
-        ui.label("アプリのパスフレーズ:");
+        ui.label(tr!("アプリのパスフレーズ:"));
         ui.add(egui::TextEdit::singleline(&mut app_data.nwc_passphrase_input).password(true));
     });
 
-    if ui.button("保存して接続").clicked() {
+    if ui.button(tr!("保存して接続")).clicked() {
         let nwc_uri = app_data.nwc_uri_input.clone();
-        let passphrase = app_data.nwc_passphrase_input.clone();
-        app_data.nwc_passphrase_input.clear(); // Clear passphrase after use
+        let passphrase = Zeroizing::new(std::mem::take(&mut app_data.nwc_passphrase_input)); // Clear passphrase after use
         let app_data_clone = app_data_arc.clone();
 
         runtime_handle.spawn(async move {
@@ -131,7 +152,8 @@ fn draw_setup_view(
                 }
                 Err(e) => {
                     let mut app_data = app_data_clone.lock().unwrap();
-                    app_data.nwc_error = Some(format!("保存と接続に失敗しました: {}", e));
+                    app_data.nwc_error = Some(tr!("保存と接続に失敗しました: {}", e));
+                    app_data.report_error(tr!("ウォレットの保存と接続に失敗しました: {}", e));
                 }
             }
         });
@@ -140,57 +162,58 @@ fn draw_setup_view(
 
 async fn save_and_connect(
     nwc_uri_str: String,
-    passphrase: String,
+    passphrase: Zeroizing<String>,
     app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     if passphrase.is_empty() {
-        return Err("パスフレーズは空にできません".into());
+        return Err(tr!("パスフレーズは空にできません").into());
     }
     let nwc_uri = NostrWalletConnectURI::from_str(&nwc_uri_str)?;
 
-    // Read existing config to get the salt
-    let config_str = fs::read_to_string(CONFIG_FILE)?;
-    let mut config: Config = serde_json::from_str(&config_str)?;
+    // Read existing config to get the salt of the active account
+    let mut config = read_config()?;
+    let account = config.active_mut().ok_or(tr!("アカウントが見つかりません"))?;
 
     // Verify passphrase by trying to decrypt the main secret key
-    let _ = nip49::decrypt(&config.encrypted_secret_key, &passphrase, &config.salt)?;
+    let encrypted_secret_key = crate::key_store::load_encrypted_secret_key(account)?;
+    let _ = nip49::decrypt_secret_key(&encrypted_secret_key, &passphrase, &account.salt)?;
 
     // Encrypt NWC URI with the same salt and passphrase
     let encrypted_nwc_uri =
-        nip49::encrypt_with_salt(nwc_uri_str.as_bytes(), &passphrase, &config.salt)?;
-    config.encrypted_nwc_uri = Some(encrypted_nwc_uri);
+        nip49::encrypt_with_salt(nwc_uri_str.as_bytes(), &passphrase, &account.salt, &account.kdf)?;
+    account.encrypted_nwc_uri = Some(encrypted_nwc_uri);
 
     // Save updated config
-    let config_json = serde_json::to_string_pretty(&config)?;
-    fs::write(CONFIG_FILE, config_json)?;
+    write_config(&config)?;
 
     connect_nwc(nwc_uri, app_data_arc).await?;
 
     Ok(())
 }
 
+// 保存済みのNWC URIを設定ファイルから削除する
+fn remove_nwc_from_config() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut config = read_config()?;
+    if let Some(account) = config.active_mut() {
+        account.encrypted_nwc_uri = None;
+    }
+    write_config(&config)
+}
+
 pub async fn connect_nwc(
     nwc_uri: NostrWalletConnectURI,
     app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     let keys = Keys::new(nwc_uri.secret.clone());
-    let client = Client::new(keys);
+    let client = new_client(keys);
 
     if let Some(relay_url) = nwc_uri.relays.first() {
         client.add_relay(relay_url.to_string()).await?;
     } else {
-        return Err("NWC URIにリレーURLが含まれていません".into());
+        return Err(tr!("NWC URIにリレーURLが含まれていません").into());
     }
     client.connect().await;
 
-    // Spawn listener task
-    let client_clone = client.clone();
-    let nwc_uri_clone = nwc_uri.clone();
-    let app_data_clone = app_data_arc.clone();
-    tokio::spawn(async move {
-        listen_for_nwc_responses(client_clone, nwc_uri_clone, app_data_clone).await;
-    });
-
     {
         let mut app_data = app_data_arc.lock().unwrap();
         app_data.nwc_client = Some(client);
@@ -202,69 +225,30 @@ pub async fn connect_nwc(
     tokio::spawn(async move {
         if let Err(e) = get_zap_history(app_data_clone.clone()).await {
             let mut app_data = app_data_clone.lock().unwrap();
-            app_data.nwc_error = Some(format!("Zap履歴の取得エラー: {}", e));
+            app_data.nwc_error = Some(tr!("Zap履歴の取得エラー: {}", e));
         }
     });
 
     Ok(())
 }
 
-async fn listen_for_nwc_responses(
-    _client: Client,
-    _nwc: NostrWalletConnectURI,
-    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
-) {
-    // We keep the listener active for potential future uses,
-    // like real-time updates, but for now it only handles PayInvoice responses.
-    let mut notifications = _client.notifications();
-    loop {
-        tokio::select! {
-            _ = tokio::time::sleep(std::time::Duration::from_secs(1)) => {
-                // Keep the loop from being too tight
-            }
-            Ok(notification) = notifications.recv() => {
-                if let nostr_sdk::RelayPoolNotification::Event { event, .. } = notification {
-                    if event.kind == Kind::WalletConnectResponse {
-                         if let Ok(decrypted_response) = Response::from_event(&_nwc, &event) {
-                            let mut app_data = app_data_arc.lock().unwrap();
-                            if let Some(res) = decrypted_response.result {
-                                match res {
-                                    nostr::nips::nip47::ResponseResult::PayInvoice(_pay_invoice_res) => {
-                                        println!("ZAP成功！");
-                                        // Here you might want to trigger a refresh of the zap history
-                                    },
-                                    _ => {
-                                        // Other responses are ignored for now
-                                    }
-                                }
-                            } else if let Some(error) = decrypted_response.error {
-                                app_data.nwc_error = Some(format!("NWCエラー: {}", error.message));
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    }
-}
-
 async fn get_zap_history(
     app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     let (client, my_pubkey) = {
         let mut app_data = app_data_arc.lock().unwrap();
         app_data.is_fetching_zap_history = true;
-        app_data.zap_history_fetch_status = "取得中...".to_string();
+        app_data.zap_history_fetch_status = tr!("取得中...").to_string();
         let client = app_data
             .nostr_client
             .as_ref()
             .cloned()
-            .ok_or("Nostrクライアントが接続されていません")?;
+            .ok_or(tr!("Nostrクライアントが接続されていません"))?;
         let my_pubkey = app_data
-            .my_keys
+            .my_signer
             .as_ref()
             .map(|k| k.public_key())
-            .ok_or("ログインしていません")?;
+            .ok_or(tr!("ログインしていません"))?;
         (client, my_pubkey)
     };
 
@@ -275,7 +259,7 @@ async fn get_zap_history(
 
     let relays = client.relays().await;
     let relay_urls: Vec<String> = relays.keys().map(|url| url.to_string()).collect();
-    let events = client.fetch_events_from(relay_urls, filter, std::time::Duration::from_secs(10)).await;
+    let events = network::fetch_events_from(&client, relay_urls, filter).await;
 
     // The rest of the function will run regardless of whether events were found or not,
     // so we can set is_fetching to false after this point.
@@ -298,7 +282,7 @@ async fn get_zap_history(
             }
 
             // Sort by creation date, newest first
-            zap_receipts.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+            zap_receipts.sort_by_key(|r| std::cmp::Reverse(r.created_at));
 
             {
                 let mut app_data = app_data_arc.lock().unwrap();
@@ -308,7 +292,7 @@ async fn get_zap_history(
         },
         Err(e) => {
              let mut app_data = app_data_arc.lock().unwrap();
-             app_data.nwc_error = Some(format!("Zap履歴の取得エラー: {}", e));
+             app_data.nwc_error = Some(tr!("Zap履歴の取得エラー: {}", e));
         }
     }
 
@@ -324,17 +308,16 @@ async fn parse_zap_receipt(
     let mut zapper_pubkey = None; // This is us, but we get it from the 'P' tag
     let mut zapped_event_id = None;
     let mut amount_msats = 0;
-    let note: String;
 
     let description_tag = event
         .tags
         .iter()
         .find(|t| t.kind() == TagKind::Description)
         .and_then(|t| t.as_slice().get(1))
-        .ok_or("Descriptionタグが見つかりません")?;
+        .ok_or(tr!("Descriptionタグが見つかりません"))?;
 
     let zap_request_event = Event::from_json(description_tag)?;
-    note = zap_request_event.content.clone();
+    let note = zap_request_event.content.clone();
 
     // Check for the specific k tag ("30315")
     let required_k_tag_found =
@@ -343,10 +326,8 @@ async fn parse_zap_receipt(
             .iter()
             .any(|t| {
                 if t.kind() == TagKind::SingleLetter(SingleLetterTag::from_char('k').unwrap())
-                {
-                    if let Some(val) = t.as_slice().get(1) {
-                        return val == "30315";
-                    }
+                    && let Some(val) = t.as_slice().get(1) {
+                    return val == "30315";
                 }
                 false
             });
@@ -363,22 +344,18 @@ async fn parse_zap_receipt(
         }
     }
 
-    let recipient_pubkey = recipient_pubkey.ok_or("受信者の公開鍵が見つかりません")?;
+    let recipient_pubkey = recipient_pubkey.ok_or(tr!("受信者の公開鍵が見つかりません"))?;
 
-    if let Some(bolt11_tag) = event.tags.iter().find(|t| t.kind() == TagKind::Bolt11) {
-        if let Some(invoice_str) = bolt11_tag.as_slice().get(1) {
-             if let Ok(invoice) = Bolt11Invoice::from_str(invoice_str) {
-                if let Some(amount) = invoice.amount_milli_satoshis() {
-                    amount_msats = amount;
-                }
-            }
-        }
+    if let Some(bolt11_tag) = event.tags.iter().find(|t| t.kind() == TagKind::Bolt11)
+        && let Some(invoice_str) = bolt11_tag.as_slice().get(1)
+        && let Ok(invoice) = Bolt11Invoice::from_str(invoice_str)
+        && let Some(amount) = invoice.amount_milli_satoshis() {
+        amount_msats = amount;
     }
 
-    if let Some(p_tag) = event.tags.iter().find(|t| t.kind() == TagKind::SingleLetter(SingleLetterTag::from_char('P').unwrap())) {
-        if let Some(pk_str) = p_tag.as_slice().get(1) {
-            zapper_pubkey = Some(nostr::PublicKey::from_str(pk_str)?);
-        }
+    if let Some(p_tag) = event.tags.iter().find(|t| t.kind() == TagKind::SingleLetter(SingleLetterTag::from_char('P').unwrap()))
+        && let Some(pk_str) = p_tag.as_slice().get(1) {
+        zapper_pubkey = Some(nostr::PublicKey::from_str(pk_str)?);
     }
 
 
@@ -1,15 +1,33 @@
 use anyhow::{anyhow, Result};
+use bech32::{FromBase32, ToBase32};
+use eframe::egui;
+use lightning_invoice::{Bolt11Invoice, Bolt11InvoiceDescriptionRef};
 use nostr::{
     nips::{
         nip04,
-        nip47::{NostrWalletConnectURI, PayInvoiceRequest, Request, RequestParams},
+        nip47::{NostrWalletConnectURI, PayInvoiceRequest, Request, RequestParams, Response, ResponseResult},
     },
-    EventBuilder, Keys, Kind, PublicKey, RelayUrl, Tag,
+    Event, EventBuilder, EventId, Filter, JsonUtil, Keys, Kind, PublicKey, RelayUrl, Tag, TagKind, Timestamp,
 };
-use nostr_sdk::Client;
-use serde::{Deserialize, Serialize};
+use nostr_sdk::{Client, RelayPoolNotification};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{hash_map::Entry, HashMap, HashSet};
 use std::str::FromStr;
-use ureq;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+use tracing::{error, info};
+
+use crate::i18n::tr;
+use crate::network;
+use crate::signer::AppSigner;
+use crate::types::{NostrStatusAppInternal, ProfileMetadata, ZapTarget};
+use crate::ui::qr;
+
+// ウォレットから支払いの結果 (kind 23195) が届くまで待つ時間
+const NWC_RESPONSE_TIMEOUT_SECS: u64 = 60;
+const LNURL_TIMEOUT_SECS: u64 = 15;
 
 #[derive(Debug, Serialize, Deserialize)]
 struct LnurlPayResponse {
@@ -43,50 +61,76 @@ fn lud16_to_lnurl(lud16: &str) -> Result<String> {
     Ok(format!("https://{}/.well-known/lnurlp/{}", domain, name))
 }
 
-pub async fn send_zap_request(
-    nwc: &NostrWalletConnectURI,
-    nwc_client: &Client,
-    from_keys: &Keys,
-    to_pubkey: PublicKey,
-    lud16: &str,
-    amount_sats: u64,
-    note_id: Option<nostr::EventId>,
-    note_kind: Option<Kind>,
-) -> Result<()> {
-    let amount_msats = amount_sats * 1000;
-    let lnurl = lud16_to_lnurl(lud16)?;
+// lud06 (bech32エンコードされたLNURL) をURLに戻す
+fn lud06_to_lnurl(lud06: &str) -> Result<String> {
+    let lud06 = lud06.trim().trim_start_matches("lightning:");
+    let (hrp, data, _) = bech32::decode(lud06).map_err(|e| anyhow!(e))?;
+    if hrp != "lnurl" {
+        return Err(anyhow!("Invalid lud06 prefix: {}", hrp));
+    }
+    let bytes = Vec::<u8>::from_base32(&data).map_err(|e| anyhow!(e))?;
+    String::from_utf8(bytes).map_err(|e| anyhow!(e))
+}
 
-    // 1. Fetch LNURL pay parameters
-    let lnurl_clone = lnurl.clone();
-    let pay_params: LnurlPayResponse = tokio::task::spawn_blocking(move || -> anyhow::Result<LnurlPayResponse> {
-        let agent = ureq::agent();
-        let res = agent.get(&lnurl_clone).call().map_err(|e| anyhow!(e))?;
+// lud16を優先し、なければlud06からLNURL-payのURLを求める
+fn resolve_lnurl(lud16: &str, lud06: &str) -> Result<String> {
+    if !lud16.is_empty() {
+        lud16_to_lnurl(lud16)
+    } else if !lud06.is_empty() {
+        lud06_to_lnurl(lud06)
+    } else {
+        Err(anyhow!(tr!("ライトニングアドレスが設定されていません")))
+    }
+}
+
+async fn http_get_json<T: DeserializeOwned + Send + 'static>(url: String) -> Result<T> {
+    tokio::task::spawn_blocking(move || -> anyhow::Result<T> {
+        let agent = network::http_agent(Duration::from_secs(LNURL_TIMEOUT_SECS));
+        let res = agent.get(&url).call().map_err(|e| anyhow!(e))?;
         let text = res.into_string().map_err(|e| anyhow!(e))?;
         serde_json::from_str(&text).map_err(|e| anyhow!(e))
     })
-    .await??;
+    .await?
+}
+
+// ZAPリクエストを作成し、LNURLのコールバックからBolt11請求書を取得する
+pub async fn fetch_zap_invoice(
+    client: &Client,
+    from_keys: &AppSigner,
+    target: &ZapTarget,
+    amount_sats: u64,
+) -> Result<String> {
+    let amount_msats = amount_sats.checked_mul(1000).ok_or_else(|| anyhow!(tr!("金額が大きすぎます")))?;
+    let lnurl = resolve_lnurl(&target.metadata.lud16, &target.metadata.lud06)?;
+
+    // 1. Fetch LNURL pay parameters
+    let pay_params: LnurlPayResponse = http_get_json(lnurl.clone()).await?;
 
     if amount_msats < pay_params.min_sendable || amount_msats > pay_params.max_sendable {
-        return Err(anyhow!(
-            "Amount must be between {} and {} sats",
+        return Err(anyhow!(tr!(
+            "金額は {} から {} sats の間で指定してください",
             pay_params.min_sendable / 1000,
             pay_params.max_sendable / 1000
-        ));
+        )));
     }
 
     // 2. Create ZAP request event
-    let relays = vec![RelayUrl::from_str("wss://relay.damus.io")?];
+    // 受領証は接続中のリレーに発行してもらう
+    let mut relays: Vec<RelayUrl> = client.relays().await.into_keys().collect();
+    if relays.is_empty() {
+        relays.push(RelayUrl::from_str("wss://relay.damus.io")?);
+    }
     let amount_str = amount_msats.to_string();
+    let encoded_lnurl = bech32::encode("lnurl", lnurl.as_bytes().to_base32(), bech32::Variant::Bech32)
+        .map_err(|e| anyhow!(e))?;
     let mut tags = vec![
-        Tag::public_key(to_pubkey),
+        Tag::public_key(target.pubkey),
         Tag::parse(["amount", &amount_str])?,
         Tag::relays(relays),
-        Tag::parse(["lnurl", &lnurl])?,
+        Tag::parse(["lnurl", &encoded_lnurl])?,
     ];
-    if let Some(event_id) = note_id {
+    if let Some((event_id, kind)) = target.event {
         tags.push(Tag::event(event_id));
-    }
-    if let Some(kind) = note_kind {
         tags.push(Tag::parse(["k", &kind.as_u16().to_string()])?);
     }
     let zap_request = EventBuilder::new(Kind::ZapRequest, "")
@@ -96,27 +140,45 @@ pub async fn send_zap_request(
     let zap_request_str = serde_json::to_string(&zap_request)?;
 
     // 3. Fetch Bolt11 invoice from LNURL callback
+    let separator = if pay_params.callback.contains('?') { '&' } else { '?' };
     let callback_url = format!(
-        "{}?amount={}&nostr={}",
+        "{}{}amount={}&nostr={}&lnurl={}",
         pay_params.callback,
+        separator,
         amount_msats,
-        urlencoding::encode(&zap_request_str)
+        urlencoding::encode(&zap_request_str),
+        encoded_lnurl
     );
+    let invoice_response: LnurlInvoiceResponse = http_get_json(callback_url).await?;
 
-    let invoice_response: LnurlInvoiceResponse = tokio::task::spawn_blocking(move || -> anyhow::Result<LnurlInvoiceResponse> {
-        let agent = ureq::agent();
-        let res = agent.get(&callback_url).call().map_err(|e| anyhow!(e))?;
-        let text = res.into_string().map_err(|e| anyhow!(e))?;
-        serde_json::from_str(&text).map_err(|e| anyhow!(e))
-    })
-    .await??;
-    let invoice = invoice_response.pr;
+    // 4. 頼んだ金額の、署名したZAPリクエストに対する請求書か確かめる
+    verify_zap_invoice(&invoice_response.pr, amount_msats, &zap_request_str)?;
+    Ok(invoice_response.pr)
+}
+
+// LNURLサーバーが返した請求書の金額と description hash を確かめる (NIP-57)
+fn verify_zap_invoice(invoice: &str, amount_msats: u64, zap_request_json: &str) -> Result<()> {
+    let invoice = Bolt11Invoice::from_str(invoice).map_err(|e| anyhow!(tr!("請求書を読み取れません: {}", e)))?;
+    if invoice.amount_milli_satoshis() != Some(amount_msats) {
+        return Err(anyhow!(tr!("請求書の金額が指定した金額と違います")));
+    }
+    let expected_hash = Sha256::digest(zap_request_json.as_bytes());
+    match invoice.description() {
+        Bolt11InvoiceDescriptionRef::Hash(hash) if hash.0[..] == expected_hash[..] => Ok(()),
+        _ => Err(anyhow!(tr!("請求書がZAPリクエストに対応していません"))),
+    }
+}
 
-    // 4. Send pay_invoice request to NWC
+// NWCのウォレットに請求書の支払いを依頼し、結果が届くのを待ってプリイメージを返す
+pub async fn pay_invoice(
+    nwc: &NostrWalletConnectURI,
+    nwc_client: &Client,
+    invoice: &str,
+) -> Result<String> {
     let req = Request {
         method: nostr::nips::nip47::Method::PayInvoice,
         params: RequestParams::PayInvoice(PayInvoiceRequest {
-            invoice,
+            invoice: invoice.to_string(),
             amount: None,
             id: None,
         }),
@@ -130,12 +192,425 @@ pub async fn send_zap_request(
         .sign(&Keys::new(nwc.secret.clone()))
         .await?;
 
-    nwc_client.send_event(&event).await?;
+    // 返事を取りこぼさないよう、送る前に購読しておく
+    let mut notifications = nwc_client.notifications();
+    let filter = Filter::new()
+        .kind(Kind::WalletConnectResponse)
+        .author(nwc.public_key)
+        .event(event.id);
+    let subscription = nwc_client.subscribe(filter, None).await?;
 
-    println!(
-        "ZAP request sent for {} sats to {}. Waiting for confirmation.",
-        amount_sats, lud16
-    );
+    let result = async {
+        nwc_client.send_event(&event).await?;
+        info!("pay_invoice request sent to NWC. Waiting for the response.");
+        tokio::time::timeout(
+            Duration::from_secs(NWC_RESPONSE_TIMEOUT_SECS),
+            wait_for_pay_response(nwc, &mut notifications, event.id),
+        )
+        .await
+        .map_err(|_| anyhow!(tr!("ウォレットから {} 秒以内に返事がありませんでした", NWC_RESPONSE_TIMEOUT_SECS)))?
+    }
+    .await;
+    nwc_client.unsubscribe(&subscription.val).await;
+    result
+}
+
+// 支払いの依頼 (request_id) に対するウォレットの返事を待つ
+async fn wait_for_pay_response(
+    nwc: &NostrWalletConnectURI,
+    notifications: &mut Receiver<RelayPoolNotification>,
+    request_id: EventId,
+) -> Result<String> {
+    loop {
+        let event = match notifications.recv().await {
+            Ok(RelayPoolNotification::Event { event, .. }) => event,
+            Ok(_) | Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => return Err(anyhow!(tr!("ウォレットとの接続が切れました"))),
+        };
+        if event.kind != Kind::WalletConnectResponse
+            || event.pubkey != nwc.public_key
+            || !event.tags.event_ids().any(|id| *id == request_id)
+        {
+            continue;
+        }
+        let response = Response::from_event(nwc, &event)?;
+        if let Some(error) = response.error {
+            return Err(anyhow!(tr!("ウォレットが支払いを断りました: {}", error.message)));
+        }
+        return match response.result {
+            Some(ResponseResult::PayInvoice(result)) => {
+                info!("Zap paid. Preimage: {}", result.preimage);
+                Ok(result.preimage)
+            }
+            _ => Err(anyhow!(tr!("ウォレットから予期しない返事が届きました"))),
+        };
+    }
+}
+
+// 支払い済みの通知に添えるプリイメージ。長いので先頭だけ出す
+fn paid_message(preimage: &str) -> String {
+    let head: String = preimage.chars().take(16).collect();
+    tr!("ZAPしました (プリイメージ: {}...)", head)
+}
+
+// 受け取る人のLNURLサーバーが受領証に署名するときの公開鍵。Nostrに対応していなければ None
+async fn fetch_zapper_pubkey(metadata: &ProfileMetadata) -> Option<PublicKey> {
+    let lnurl = resolve_lnurl(&metadata.lud16, &metadata.lud06).ok()?;
+    let pay_params: LnurlPayResponse = match http_get_json(lnurl).await {
+        Ok(pay_params) => pay_params,
+        Err(e) => {
+            error!("Failed to fetch LNURL pay parameters for zap receipts: {e}");
+            return None;
+        }
+    };
+    if !pay_params.allows_nostr {
+        return None;
+    }
+    PublicKey::parse(pay_params.nostr_pubkey.as_deref()?).ok()
+}
+
+fn tag_value<'a>(event: &'a Event, kind: TagKind<'_>) -> Option<&'a str> {
+    event.tags.iter().find(|t| t.kind() == kind).and_then(|t| t.content())
+}
+
+// ZAP受領証(kind 9735)を NIP-57 Appendix F に沿って確かめ、ZAPされたイベントと金額(msats)を取り出す
+// 署名者が受け取る人のLNURLサーバーであること、中のZAPリクエストが正しく署名されていること、
+// 請求書がそのZAPリクエストに対するもので、金額が amount タグと同じであることを確かめる
+fn parse_zap_receipt_amount(event: &Event, zapper: &PublicKey) -> Option<(EventId, u64)> {
+    if event.pubkey != *zapper {
+        return None;
+    }
+    let zapped_event_id = event.tags.event_ids().next().copied()?;
+    let description = tag_value(event, TagKind::Description)?;
+    let zap_request = Event::from_json(description).ok()?;
+    if zap_request.kind != Kind::ZapRequest || zap_request.verify().is_err() {
+        return None;
+    }
+    let invoice = Bolt11Invoice::from_str(tag_value(event, TagKind::Bolt11)?).ok()?;
+    let description_hash = Sha256::digest(description.as_bytes());
+    if !matches!(invoice.description(), Bolt11InvoiceDescriptionRef::Hash(hash) if hash.0[..] == description_hash[..]) {
+        return None;
+    }
+    let amount_msats = invoice.amount_milli_satoshis()?;
+    if let Some(requested) = tag_value(&zap_request, TagKind::Amount)
+        && requested.parse::<u64>().ok() != Some(amount_msats)
+    {
+        return None;
+    }
+    Some((zapped_event_id, amount_msats))
+}
+
+// 受領証を集計に加える。同じ受領証は二重に数えない
+fn record_zap_receipt(app_data: &mut NostrStatusAppInternal, event: &Event, zapper: &PublicKey) -> bool {
+    if !app_data.counted_zap_receipts.insert(event.id) {
+        return false;
+    }
+    let Some((zapped_event_id, amount_msats)) = parse_zap_receipt_amount(event, zapper) else {
+        return false;
+    };
+    *app_data.zap_totals.entry(zapped_event_id).or_default() += amount_msats;
+    true
+}
+
+// 受領証の受け取る人 (p タグ) のLNURLサーバーの公開鍵を調べ、確かめられたものだけ集計に加える
+// 調べた公開鍵は zappers に覚えておく
+async fn record_zap_receipts(
+    app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>,
+    zappers: &mut HashMap<PublicKey, Option<PublicKey>>,
+    receipts: &[Event],
+) -> bool {
+    let mut changed = false;
+    for receipt in receipts {
+        let Some(recipient) = receipt.tags.public_keys().next().copied() else {
+            continue;
+        };
+        if let Entry::Vacant(entry) = zappers.entry(recipient) {
+            let metadata = app_data_arc.lock().unwrap().profiles.get(&recipient).cloned();
+            let Some(metadata) = metadata else {
+                // プロフィールが届いていない人の分は、確かめられないので数えない
+                continue;
+            };
+            entry.insert(fetch_zapper_pubkey(&metadata).await);
+        }
+        if let Some(Some(zapper)) = zappers.get(&recipient) {
+            changed |= record_zap_receipt(&mut app_data_arc.lock().unwrap(), receipt, zapper);
+        }
+    }
+    changed
+}
+
+async fn fetch_zap_receipts_for(client: &Client, event_ids: Vec<EventId>) -> Vec<Event> {
+    let filter = Filter::new().kind(Kind::ZapReceipt).events(event_ids);
+    match network::fetch_events(client, filter).await {
+        Ok(events) => events.into_iter().collect(),
+        Err(e) => {
+            error!("Failed to fetch zap receipts: {e}");
+            Vec::new()
+        }
+    }
+}
+
+// タイムラインのステータスに対するZAP受領証を集計し、新着の受領証を購読し続けるタスク
+pub async fn run_zap_receipt_listener(
+    client: Client,
+    keys: AppSigner,
+    session: u64,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    ctx: egui::Context,
+) {
+    let mut notifications = client.notifications();
+    let mut queried_event_ids: HashSet<EventId> = HashSet::new();
+    let mut zappers: HashMap<PublicKey, Option<PublicKey>> = HashMap::new();
+
+    let recipients = {
+        let app_data = app_data_arc.lock().unwrap();
+        let mut recipients = app_data.followed_pubkeys.clone();
+        recipients.insert(keys.public_key());
+        recipients
+    };
+    let filter = Filter::new()
+        .kind(Kind::ZapReceipt)
+        .pubkeys(recipients)
+        .since(Timestamp::now());
+    if let Err(e) = client.subscribe(filter, None).await {
+        error!("Failed to subscribe to zap receipts: {e}");
+    }
+
+    loop {
+        // タイムラインに新しく増えたステータスの受領証を取得する
+        let new_event_ids: Vec<EventId> = {
+            let app_data = app_data_arc.lock().unwrap();
+            if !app_data.is_current_session(session) {
+                break;
+            }
+            app_data
+                .timeline_posts
+                .iter()
+                .map(|p| p.id)
+                .filter(|id| !queried_event_ids.contains(id))
+                .collect()
+        };
+        if !new_event_ids.is_empty() {
+            queried_event_ids.extend(new_event_ids.iter().copied());
+            let receipts = fetch_zap_receipts_for(&client, new_event_ids).await;
+            if record_zap_receipts(&app_data_arc, &mut zappers, &receipts).await {
+                ctx.request_repaint();
+            }
+        }
+
+        let notification = match tokio::time::timeout(Duration::from_secs(5), notifications.recv()).await {
+            Err(_) => continue,
+            Ok(Ok(notification)) => notification,
+            Ok(Err(RecvError::Lagged(_))) => continue,
+            Ok(Err(RecvError::Closed)) => break,
+        };
+        if let RelayPoolNotification::Event { event, .. } = notification {
+            if event.kind != Kind::ZapReceipt {
+                continue;
+            }
+            if record_zap_receipts(&app_data_arc, &mut zappers, &[*event]).await {
+                ctx.request_repaint();
+            }
+        }
+    }
+}
+
+// ZAPダイアログ。金額を決めて請求書を作成し、QRコード表示またはNWCで支払う
+pub fn draw_zap_dialog(
+    ctx: &egui::Context,
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: tokio::runtime::Handle,
+) {
+    if !app_data.show_zap_dialog {
+        return;
+    }
+    let Some(target) = app_data.zap_target.clone() else {
+        return;
+    };
+    let create_invoice_button_text = tr!("請求書を作成");
+    let pay_with_nwc_button_text = tr!("NWCで支払う");
+    let copy_button_text = tr!("コピー");
+    let cancel_button_text = tr!("キャンセル");
+    let close_button_text = tr!("閉じる");
+
+    let mut close_dialog = false;
+    egui::Window::new(tr!("ZAPを送る"))
+        .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.vertical_centered_justified(|ui| {
+                ui.add_space(10.0);
+                let display_name = app_data
+                    .profiles
+                    .get(&target.pubkey)
+                    .unwrap_or(&target.metadata)
+                    .display_name_or_npub(&target.pubkey);
+                ui.label(tr!("{} にZAPします", display_name));
+                ui.add_space(10.0);
+
+                if let Some(invoice) = app_data.zap_invoice.clone() {
+                    // --- 請求書の表示 ---
+                    if let Err(e) = qr::draw_qr_code(ui, &invoice.to_uppercase(), 240.0) {
+                        ui.colored_label(egui::Color32::RED, tr!("QRコードを作成できません: {}", e));
+                    }
+                    ui.add_space(5.0);
+                    ui.horizontal(|ui| {
+                        ui.label(egui::RichText::new(format!("{}...", &invoice[..invoice.len().min(32)])).monospace().small());
+                        if ui.button(copy_button_text).clicked() {
+                            ctx.copy_text(invoice.clone());
+                        }
+                    });
+                    ui.add_space(5.0);
+                    let can_pay = app_data.nwc.is_some() && app_data.nwc_client.is_some() && !app_data.is_paying_zap_invoice;
+                    if ui.add_enabled(can_pay, egui::Button::new(pay_with_nwc_button_text)).clicked()
+                        && let (Some(nwc), Some(nwc_client)) = (app_data.nwc.clone(), app_data.nwc_client.clone()) {
+                        app_data.is_paying_zap_invoice = true;
+                        let cloned_app_data_arc = app_data_arc.clone();
+                        runtime_handle.spawn(async move {
+                            let result = pay_invoice(&nwc, &nwc_client, &invoice).await;
+                            let mut data = cloned_app_data_arc.lock().unwrap();
+                            data.is_paying_zap_invoice = false;
+                            match result {
+                                Ok(preimage) => {
+                                    data.show_zap_dialog = false;
+                                    data.zap_target = None;
+                                    data.zap_invoice = None;
+                                    let message = paid_message(&preimage);
+                                    data.zap_status_message = Some(message.clone());
+                                    data.notify(message);
+                                }
+                                Err(e) => {
+                                    error!("Zap failed: {}", e);
+                                    data.zap_error = Some(tr!("支払いに失敗しました: {}", e));
+                                }
+                            }
+                            data.should_repaint = true;
+                        });
+                    }
+                    if app_data.nwc.is_none() {
+                        ui.label(egui::RichText::new(tr!("NWC未接続のため、QRコードをウォレットで読み取って支払ってください。")).small());
+                    }
+                } else {
+                    // --- 金額の入力 ---
+                    ui.horizontal(|ui| {
+                        ui.label(tr!("金額 (sats):"));
+                        ui.add(egui::TextEdit::singleline(&mut app_data.zap_amount_input)
+                            .desired_width(120.0));
+                    });
+                    ui.add_space(5.0);
+                    let can_create = !app_data.is_fetching_zap_invoice;
+                    if ui.add_enabled(can_create, egui::Button::new(create_invoice_button_text)).clicked() {
+                        match (app_data.zap_amount_input.parse::<u64>(), app_data.nostr_client.clone(), app_data.my_signer.clone()) {
+                            (Ok(amount_sats), Some(client), Some(my_signer)) => {
+                                app_data.is_fetching_zap_invoice = true;
+                                app_data.zap_error = None;
+                                let target = target.clone();
+                                let cloned_app_data_arc = app_data_arc.clone();
+                                runtime_handle.spawn(async move {
+                                    let result = fetch_zap_invoice(&client, &my_signer, &target, amount_sats).await;
+                                    let mut data = cloned_app_data_arc.lock().unwrap();
+                                    data.is_fetching_zap_invoice = false;
+                                    match result {
+                                        Ok(invoice) => data.zap_invoice = Some(invoice),
+                                        Err(e) => {
+                                            error!("Zap failed: {}", e);
+                                            data.zap_error = Some(tr!("請求書の取得に失敗しました: {}", e));
+                                        }
+                                    }
+                                    data.should_repaint = true;
+                                });
+                            }
+                            (Err(_), _, _) => app_data.zap_error = Some(tr!("無効な金額です").to_string()),
+                            _ => app_data.zap_error = Some(tr!("ログインしていません").to_string()),
+                        }
+                    }
+                    if app_data.is_fetching_zap_invoice {
+                        ui.spinner();
+                    }
+                }
+
+                if let Some(error) = &app_data.zap_error {
+                    ui.colored_label(egui::Color32::RED, error);
+                }
+                ui.add_space(10.0);
+            });
+
+            ui.separator();
+            ui.add_space(5.0);
+
+            let button_text = if app_data.zap_invoice.is_some() { close_button_text } else { cancel_button_text };
+            if ui.button(button_text).clicked() {
+                close_dialog = true;
+            }
+        });
+    if close_dialog {
+        app_data.show_zap_dialog = false;
+        app_data.zap_target = None;
+        app_data.zap_invoice = None;
+        app_data.zap_error = None;
+    }
+}
+
+// NWC接続済みなら、既定の金額で請求書の取得から支払いまでを一度に行う
+pub fn spawn_one_click_zap(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    target: ZapTarget,
+) {
+    let (Some(nwc), Some(nwc_client), Some(client), Some(my_signer)) = (
+        app_data.nwc.clone(),
+        app_data.nwc_client.clone(),
+        app_data.nostr_client.clone(),
+        app_data.my_signer.clone(),
+    ) else {
+        open_zap_dialog(app_data, target);
+        return;
+    };
+    let Ok(amount_sats) = app_data.default_zap_amount_input.parse::<u64>() else {
+        app_data.zap_status_message = Some(tr!("ワンクリックZAPの金額が不正です").to_string());
+        return;
+    };
+    let pending_id = target.event.map(|(id, _)| id);
+    if let Some(id) = pending_id
+        && !app_data.pending_zaps.insert(id) {
+        return;
+    }
+    app_data.zap_status_message = None;
+    app_data.should_repaint = true;
+
+    runtime_handle.spawn(async move {
+        let result = match fetch_zap_invoice(&client, &my_signer, &target, amount_sats).await {
+            Ok(invoice) => pay_invoice(&nwc, &nwc_client, &invoice).await,
+            Err(e) => Err(e),
+        };
+        let mut data = app_data_arc.lock().unwrap();
+        if let Some(id) = pending_id {
+            data.pending_zaps.remove(&id);
+        }
+        match result {
+            Ok(preimage) => {
+                let message = paid_message(&preimage);
+                data.zap_status_message = Some(message.clone());
+                data.notify(message);
+            }
+            Err(e) => {
+                error!("Zap failed: {}", e);
+                data.zap_status_message = Some(tr!("ZAPに失敗しました: {}", e));
+                data.report_error(tr!("ZAPに失敗しました: {}", e));
+            }
+        }
+        data.should_repaint = true;
+    });
+}
 
-    Ok(())
+// ZAPダイアログを開く
+pub fn open_zap_dialog(app_data: &mut NostrStatusAppInternal, target: ZapTarget) {
+    app_data.zap_target = Some(target);
+    app_data.zap_invoice = None;
+    app_data.zap_error = None;
+    app_data.show_zap_dialog = true;
+    app_data.zap_amount_input = "21".to_string(); // Default amount
 }
@@ -0,0 +1,257 @@
+use eframe::egui;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+// ボイスメッセージの録音と再生。cargo の voice 機能を有効にしたときだけ使える
+// 録音はマイクの音をモノラルの 16 ビット WAV にまとめて一時ファイルに書き、添付としてアップロードする
+// 再生はメッセージの URL から取得してアプリの中で鳴らす。再生できない形式なら既定のプレイヤーで開く
+
+// 再生中のボイスメッセージの URL。吹き出しのボタンの表示に使う
+static PLAYING: Mutex<Option<String>> = Mutex::new(None);
+
+// 録音できるビルドか
+pub fn is_supported() -> bool {
+    cfg!(feature = "voice")
+}
+
+pub fn is_playing(url: &str) -> bool {
+    PLAYING.lock().unwrap().as_deref() == Some(url)
+}
+
+// 録音した音を WAV (PCM, 16 ビット, モノラル) にする
+#[cfg_attr(not(feature = "voice"), allow(dead_code))]
+fn encode_wav(samples: &[i16], sample_rate: u32) -> Vec<u8> {
+    let data_len = (samples.len() * 2) as u32;
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVE");
+    wav.extend_from_slice(b"fmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    wav.extend_from_slice(&1u16.to_le_bytes()); // チャンネル数
+    wav.extend_from_slice(&sample_rate.to_le_bytes());
+    wav.extend_from_slice(&(sample_rate * 2).to_le_bytes()); // バイトレート
+    wav.extend_from_slice(&2u16.to_le_bytes()); // ブロックサイズ
+    wav.extend_from_slice(&16u16.to_le_bytes()); // ビット数
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        wav.extend_from_slice(&sample.to_le_bytes());
+    }
+    wav
+}
+
+#[cfg(feature = "voice")]
+mod backend {
+    use super::*;
+    use rodio::cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+    use rodio::cpal::{self, FromSample, SampleFormat, SizedSample};
+    use rodio::{Decoder, OutputStream, Sink};
+    use std::io::{Cursor, Read};
+    use std::sync::mpsc;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Instant;
+    use tracing::error;
+
+    use crate::i18n::tr;
+    use crate::network;
+
+    // 録音できる長さの上限。過ぎたら自動で止める
+    const MAX_RECORDING_SECS: u64 = 5 * 60;
+    // 再生するときに取得する大きさの上限
+    const MAX_CLIP_BYTES: u64 = 20 * 1024 * 1024;
+    const FETCH_TIMEOUT_SECS: u64 = 30;
+    // 再生を止められたか確かめる間隔
+    const PLAYBACK_POLL_MILLIS: u64 = 100;
+
+    pub struct Recording {
+        stop: mpsc::Sender<()>,
+        thread: thread::JoinHandle<Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>>>,
+        started_at: Instant,
+    }
+
+    impl Recording {
+        pub fn elapsed(&self) -> Duration {
+            self.started_at.elapsed()
+        }
+
+        // 録音を止めて WAV を一時ファイルに書き、そのパスを返す。捨てるときは drop するだけでよい
+        pub fn finish(self) -> Result<PathBuf, Box<dyn std::error::Error + Send + Sync>> {
+            let _ = self.stop.send(());
+            let wav = self.thread.join().map_err(|_| tr!("録音に失敗しました"))??;
+            let path = std::env::temp_dir().join(format!("voice-{}.wav", nostr::Timestamp::now().as_u64()));
+            std::fs::write(&path, wav)?;
+            Ok(path)
+        }
+    }
+
+    fn build_input_stream<T>(
+        device: &cpal::Device,
+        config: &cpal::StreamConfig,
+        samples: Arc<Mutex<Vec<i16>>>,
+    ) -> Result<cpal::Stream, cpal::BuildStreamError>
+    where
+        T: SizedSample,
+        i16: FromSample<T>,
+    {
+        // チャンネルを平均してモノラルにする
+        let channels = config.channels.max(1) as usize;
+        device.build_input_stream(
+            config,
+            move |data: &[T], _| {
+                let mut samples = samples.lock().unwrap();
+                samples.extend(data.chunks(channels).map(|frame| {
+                    let sum: i32 = frame.iter().map(|sample| sample.to_sample::<i16>() as i32).sum();
+                    (sum / frame.len() as i32) as i16
+                }));
+            },
+            |e| error!("Microphone stream error: {e}"),
+            None,
+        )
+    }
+
+    fn open_microphone(samples: Arc<Mutex<Vec<i16>>>) -> Result<(cpal::Stream, u32), Box<dyn std::error::Error + Send + Sync>> {
+        let device = cpal::default_host().default_input_device().ok_or(tr!("マイクが見つかりません"))?;
+        let supported = device.default_input_config()?;
+        let sample_rate = supported.sample_rate().0;
+        let config = supported.config();
+        let stream = match supported.sample_format() {
+            SampleFormat::F32 => build_input_stream::<f32>(&device, &config, samples)?,
+            SampleFormat::I16 => build_input_stream::<i16>(&device, &config, samples)?,
+            SampleFormat::U16 => build_input_stream::<u16>(&device, &config, samples)?,
+            SampleFormat::I32 => build_input_stream::<i32>(&device, &config, samples)?,
+            _ => return Err(tr!("このマイクの形式には対応していません").into()),
+        };
+        stream.play()?;
+        Ok((stream, sample_rate))
+    }
+
+    // マイクの音を止められるまで録る。cpal のストリームはスレッドをまたげないので、専用のスレッドで持つ
+    pub fn start_recording() -> Result<Recording, Box<dyn std::error::Error + Send + Sync>> {
+        let (stop, stop_rx) = mpsc::channel::<()>();
+        let (ready_tx, ready_rx) = mpsc::channel();
+        let thread = thread::spawn(move || -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+            let samples = Arc::new(Mutex::new(Vec::new()));
+            let (stream, sample_rate) = match open_microphone(samples.clone()) {
+                Ok(opened) => {
+                    let _ = ready_tx.send(Ok(()));
+                    opened
+                }
+                Err(e) => {
+                    let message = e.to_string();
+                    let _ = ready_tx.send(Err(e));
+                    return Err(message.into());
+                }
+            };
+            // 止めるか、取り消されて Recording が捨てられるか、上限の長さになるまで待つ
+            let _ = stop_rx.recv_timeout(Duration::from_secs(MAX_RECORDING_SECS));
+            drop(stream);
+            let samples = std::mem::take(&mut *samples.lock().unwrap());
+            if samples.is_empty() {
+                return Err(tr!("音を録れませんでした").into());
+            }
+            Ok(encode_wav(&samples, sample_rate))
+        });
+        match ready_rx.recv() {
+            Ok(Ok(())) => Ok(Recording { stop, thread, started_at: Instant::now() }),
+            Ok(Err(e)) => Err(e),
+            Err(_) => Err(tr!("録音に失敗しました").into()),
+        }
+    }
+
+    fn play(url: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let response = network::http_agent(Duration::from_secs(FETCH_TIMEOUT_SECS)).get(url).call()?;
+        let mut data = Vec::new();
+        response.into_reader().take(MAX_CLIP_BYTES).read_to_end(&mut data)?;
+        let (_stream, handle) = OutputStream::try_default()?;
+        let sink = Sink::try_new(&handle)?;
+        sink.append(Decoder::new(Cursor::new(data))?);
+        while !sink.empty() {
+            if !is_playing(url) {
+                sink.stop();
+                break;
+            }
+            thread::sleep(Duration::from_millis(PLAYBACK_POLL_MILLIS));
+        }
+        Ok(())
+    }
+
+    // 再生中なら止め、そうでなければ取得して再生する。再生できなければ既定のプレイヤーで開く
+    pub fn toggle_playback(ctx: &egui::Context, url: &str) {
+        {
+            let mut playing = PLAYING.lock().unwrap();
+            if playing.as_deref() == Some(url) {
+                *playing = None;
+                return;
+            }
+            *playing = Some(url.to_string());
+        }
+        let ctx = ctx.clone();
+        let url = url.to_string();
+        thread::spawn(move || {
+            if let Err(e) = play(&url) {
+                error!("Failed to play the voice message: {e}");
+                ctx.open_url(egui::OpenUrl::new_tab(&url));
+            }
+            let mut playing = PLAYING.lock().unwrap();
+            if playing.as_deref() == Some(url.as_str()) {
+                *playing = None;
+            }
+            ctx.request_repaint();
+        });
+    }
+}
+
+#[cfg(not(feature = "voice"))]
+mod backend {
+    use super::*;
+
+    use crate::i18n::tr;
+
+    // このビルドでは録音しないので、値を持たない
+    pub enum Recording {}
+
+    impl Recording {
+        pub fn elapsed(&self) -> Duration {
+            match *self {}
+        }
+
+        pub fn finish(self) -> Result<PathBuf, Box<dyn std::error::Error + Send + Sync>> {
+            match self {}
+        }
+    }
+
+    pub fn start_recording() -> Result<Recording, Box<dyn std::error::Error + Send + Sync>> {
+        Err(tr!("このビルドでは録音できません").into())
+    }
+
+    // アプリの中では再生できないので、既定のプレイヤーで開く
+    pub fn toggle_playback(ctx: &egui::Context, url: &str) {
+        ctx.open_url(egui::OpenUrl::new_tab(url));
+    }
+}
+
+pub use backend::{start_recording, toggle_playback, Recording};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_wav_writes_a_mono_pcm_header() {
+        let wav = encode_wav(&[0, 1, -1, i16::MAX], 16000);
+        assert_eq!(wav.len(), 44 + 8);
+        assert_eq!(&wav[0..4], b"RIFF");
+        assert_eq!(u32::from_le_bytes(wav[4..8].try_into().unwrap()), 36 + 8);
+        assert_eq!(&wav[8..16], b"WAVEfmt ");
+        assert_eq!(u16::from_le_bytes(wav[22..24].try_into().unwrap()), 1);
+        assert_eq!(u32::from_le_bytes(wav[24..28].try_into().unwrap()), 16000);
+        assert_eq!(u32::from_le_bytes(wav[28..32].try_into().unwrap()), 32000);
+        assert_eq!(&wav[36..40], b"data");
+        assert_eq!(u32::from_le_bytes(wav[40..44].try_into().unwrap()), 8);
+        assert_eq!(&wav[44..], &[0, 0, 1, 0, 0xff, 0xff, 0xff, 0x7f]);
+    }
+}
@@ -0,0 +1,171 @@
+use nostr::{Event, Filter, Kind, PublicKey};
+use nostr_sdk::Client;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+use crate::network;
+use crate::replaceable;
+use crate::types::{NostrStatusAppInternal, TrustLevel};
+
+// 友だちの輪 (Web of Trust)
+// フォロー中の人のフォローリスト (kind 3) を数えて、知らない人からの返信やトークを見分ける
+// まず受信済みのイベントのデータベースにあるもので数え、取ってから時間のたったものをリレーに取り直して数え直す
+
+// 数え直す間隔。フォローした人のリストを取り込むため
+const REBUILD_INTERVAL_SECS: u64 = 60 * 60;
+// 一度にリレーへ問い合わせる作者の数
+const FETCH_BATCH_SIZE: usize = 300;
+// フォローリストをリレーから取り直す間隔。データベースのものは古くなっているかもしれないので、起動後の最初の回は全員分を取り直す
+const CONTACT_LIST_TTL_SECS: u64 = 6 * 60 * 60;
+
+pub fn trust_level(app_data: &NostrStatusAppInternal, pubkey: &PublicKey) -> TrustLevel {
+    let is_me = app_data.my_signer.as_ref().is_some_and(|signer| signer.public_key() == *pubkey);
+    if is_me || app_data.followed_pubkeys.contains(pubkey) {
+        return TrustLevel::Followed;
+    }
+    let count = app_data.wot_follow_counts.get(pubkey).copied().unwrap_or_default();
+    level_for_count(count, app_data.settings.wot_min_follows)
+}
+
+// フォロー中の人のうち min_follows 人以上がフォローしていれば友だちの友だち
+fn level_for_count(count: u32, min_follows: u32) -> TrustLevel {
+    if count >= min_follows.max(1) {
+        TrustLevel::FollowedByFollows
+    } else {
+        TrustLevel::Unknown
+    }
+}
+
+// 畳む・リクエストに分ける対象か。数え終わるまでは誰も知らない人として扱わない
+pub fn is_stranger(app_data: &NostrStatusAppInternal, pubkey: &PublicKey) -> bool {
+    app_data.settings.wot_enabled && app_data.wot_loaded && trust_level(app_data, pubkey) == TrustLevel::Unknown
+}
+
+async fn cached_contact_lists(client: &Client, follows: &HashSet<PublicKey>) -> Result<Vec<Event>, Box<dyn std::error::Error + Send + Sync>> {
+    let events = client
+        .database()
+        .query(Filter::new().authors(follows.iter().copied()).kind(Kind::ContactList))
+        .await?;
+    Ok(replaceable::dedupe_events(events))
+}
+
+// リレーから取り直す。取れた分の作者は refreshed_at に時刻を記録し、取れなかった分は次に数え直すときに取り直す
+async fn refresh_contact_lists(client: &Client, authors: &[PublicKey], refreshed_at: &mut HashMap<PublicKey, Instant>) -> Vec<Event> {
+    let mut events = Vec::new();
+    for chunk in authors.chunks(FETCH_BATCH_SIZE) {
+        let filter = Filter::new().authors(chunk.iter().copied()).kind(Kind::ContactList);
+        match network::fetch_events(client, filter).await {
+            Ok(fetched) => {
+                let now = Instant::now();
+                refreshed_at.extend(chunk.iter().map(|pubkey| (*pubkey, now)));
+                events.extend(fetched);
+            }
+            Err(e) => warn!("Failed to fetch contact lists for the web of trust: {e}"),
+        }
+    }
+    events
+}
+
+fn count_follows(contact_lists: &[Event]) -> HashMap<PublicKey, u32> {
+    let mut counts: HashMap<PublicKey, u32> = HashMap::new();
+    for contact_list in contact_lists {
+        let followed: HashSet<&PublicKey> = contact_list.tags.public_keys().collect();
+        for pubkey in followed {
+            *counts.entry(*pubkey).or_default() += 1;
+        }
+    }
+    counts
+}
+
+// 数えた結果を反映する。ログアウトやアカウントの切り替えのあとなら false
+fn store_counts(app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>, session: u64, counts: HashMap<PublicKey, u32>) -> bool {
+    let mut app_data = app_data_arc.lock().unwrap();
+    if !app_data.is_current_session(session) {
+        return false;
+    }
+    app_data.wot_follow_counts = counts;
+    app_data.wot_loaded = true;
+    app_data.should_repaint = true;
+    true
+}
+
+// ログインしている間、フォロー中の人のフォローリストを定期的に数え直すタスク
+pub async fn run_wot_worker(client: Client, session: u64, app_data_arc: Arc<Mutex<NostrStatusAppInternal>>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(REBUILD_INTERVAL_SECS));
+    let ttl = Duration::from_secs(CONTACT_LIST_TTL_SECS);
+    let mut refreshed_at: HashMap<PublicKey, Instant> = HashMap::new();
+    loop {
+        interval.tick().await;
+        let follows = {
+            let app_data = app_data_arc.lock().unwrap();
+            // ロックやログアウトの後は終了する
+            if !app_data.is_current_session(session) {
+                break;
+            }
+            app_data.followed_pubkeys.clone()
+        };
+
+        let cached = match cached_contact_lists(&client, &follows).await {
+            Ok(cached) => cached,
+            Err(e) => {
+                warn!("Failed to load contact lists for the web of trust: {e}");
+                Vec::new()
+            }
+        };
+        // 取り直しを待たずに、手元にあるもので数えておく
+        if !cached.is_empty() && !store_counts(&app_data_arc, session, count_follows(&cached)) {
+            break;
+        }
+
+        let stale: Vec<PublicKey> = follows
+            .iter()
+            .filter(|pubkey| refreshed_at.get(pubkey).is_none_or(|at| at.elapsed() >= ttl))
+            .copied()
+            .collect();
+        let contact_lists = if stale.is_empty() {
+            cached
+        } else {
+            let fetched = refresh_contact_lists(&client, &stale, &mut refreshed_at).await;
+            replaceable::dedupe_events(cached.into_iter().chain(fetched))
+        };
+        let counts = count_follows(&contact_lists);
+        info!("Web of trust built from {} contact lists ({} people)", contact_lists.len(), counts.len());
+        if !store_counts(&app_data_arc, session, counts) {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nostr::{EventBuilder, Keys, Tag};
+
+    fn contact_list(keys: &Keys, follows: &[PublicKey]) -> Event {
+        EventBuilder::new(Kind::ContactList, "").tags(follows.iter().map(|pubkey| Tag::public_key(*pubkey))).sign_with_keys(keys).unwrap()
+    }
+
+    #[test]
+    fn count_follows_counts_each_list_once() {
+        let (alice, bob) = (Keys::generate(), Keys::generate());
+        let (carol, dave) = (Keys::generate().public_key(), Keys::generate().public_key());
+        // 同じ人を何度も入れたリストでも 1 人と数える
+        let lists = [contact_list(&alice, &[carol, carol, dave]), contact_list(&bob, &[carol])];
+        let counts = count_follows(&lists);
+        assert_eq!(counts.get(&carol), Some(&2));
+        assert_eq!(counts.get(&dave), Some(&1));
+        assert_eq!(counts.get(&alice.public_key()), None);
+    }
+
+    #[test]
+    fn level_for_count_uses_the_threshold() {
+        assert_eq!(level_for_count(0, 2), TrustLevel::Unknown);
+        assert_eq!(level_for_count(1, 2), TrustLevel::Unknown);
+        assert_eq!(level_for_count(2, 2), TrustLevel::FollowedByFollows);
+        // 0 人に設定されていても、誰にもフォローされていない人は知らない人のまま
+        assert_eq!(level_for_count(0, 0), TrustLevel::Unknown);
+        assert_eq!(level_for_count(1, 0), TrustLevel::FollowedByFollows);
+    }
+}